@@ -22,6 +22,7 @@ use swc_common::errors::DiagnosticBuilder;
 use swc_common::errors::Emitter;
 use swc_common::errors::Handler;
 use swc_common::errors::HandlerFlags;
+use swc_common::BytePos;
 use swc_common::FileName;
 use swc_common::Globals;
 use swc_common::Loc;
@@ -49,6 +50,8 @@ use swc_ecmascript::transforms::proposals;
 use swc_ecmascript::transforms::react;
 use swc_ecmascript::transforms::typescript;
 use swc_ecmascript::visit::FoldWith;
+use swc_ecmascript::visit::Visit;
+use swc_ecmascript::visit::VisitWith;
 
 static TARGET: JscTarget = JscTarget::Es2020;
 
@@ -253,12 +256,62 @@ impl fmt::Debug for ParsedModule {
   }
 }
 
+struct DynamicImportCollector {
+  spans: Vec<Span>,
+}
+
+impl Visit for DynamicImportCollector {
+  fn visit_call_expr(
+    &mut self,
+    node: &swc_ecmascript::ast::CallExpr,
+    parent: &dyn swc_ecmascript::visit::Node,
+  ) {
+    use swc_ecmascript::ast::Expr;
+    use swc_ecmascript::ast::ExprOrSuper;
+    use swc_ecmascript::ast::Lit;
+
+    swc_ecmascript::visit::visit_call_expr(self, node, parent);
+    let is_import = match &node.callee {
+      ExprOrSuper::Expr(callee) => match &**callee {
+        Expr::Ident(ident) => &*ident.sym == "import",
+        _ => false,
+      },
+      ExprOrSuper::Super(_) => false,
+    };
+    let has_literal_specifier = match node.args.get(0) {
+      Some(arg) => matches!(&*arg.expr, Expr::Lit(Lit::Str(_))),
+      None => false,
+    };
+    if is_import && !has_literal_specifier {
+      self.spans.push(node.span);
+    }
+  }
+}
+
 impl ParsedModule {
   /// Return a vector of dependencies for the module.
   pub fn analyze_dependencies(&self) -> Vec<DependencyDescriptor> {
     analyze_dependencies(&self.module, &self.source_map, &self.comments)
   }
 
+  /// Get the locations of the dynamic imports whose specifier is not a string
+  /// literal, like `import(url)`, so that the module they load can't be known
+  /// without running the code.
+  pub fn get_non_analyzable_dynamic_imports(&self) -> Vec<Location> {
+    let mut collector = DynamicImportCollector { spans: vec![] };
+    self.module.visit_with(
+      &swc_ecmascript::ast::Invalid {
+        span: swc_common::DUMMY_SP,
+      },
+      &mut collector,
+    );
+    collector
+      .spans
+      .iter()
+      .map(|span| self.get_location(span))
+      .collect()
+  }
+
   /// Get the module's leading comments, where triple slash directives might
   /// be located.
   pub fn get_leading_comments(&self) -> Vec<Comment> {
@@ -270,6 +323,21 @@ impl ParsedModule {
     self.source_map.lookup_char_pos(span.lo).into()
   }
 
+  /// Get the offset in the module's source of a position within the module.
+  pub fn get_byte_offset(&self, pos: BytePos) -> usize {
+    (pos.0 - self.source_file.start_pos.0) as usize
+  }
+
+  /// Walk the module's AST with the given visitor.
+  pub fn visit_with<V: Visit>(&self, visitor: &mut V) {
+    self.module.visit_with(
+      &swc_ecmascript::ast::Invalid {
+        span: swc_common::DUMMY_SP,
+      },
+      visitor,
+    );
+  }
+
   /// Transform a TypeScript file into a JavaScript file, based on the supplied
   /// options.
   ///
@@ -586,6 +654,26 @@ mod tests {
   use std::collections::HashMap;
   use swc_ecmascript::dep_graph::DependencyKind;
 
+  #[test]
+  fn test_parsed_module_get_non_analyzable_dynamic_imports() {
+    let specifier = resolve_url_or_path("https://deno.land/x/mod.js").unwrap();
+    let source = r#"const a = await import("./a.ts");
+    const b = await import(`./${name}.ts`);
+    const c = await import(url);
+    "#;
+    let parsed_module =
+      parse(specifier.as_str(), source, &MediaType::JavaScript)
+        .expect("could not parse module");
+    let actual = parsed_module.get_non_analyzable_dynamic_imports();
+    assert_eq!(
+      actual
+        .iter()
+        .map(|location| (location.line, location.col))
+        .collect::<Vec<_>>(),
+      vec![(2, 20), (3, 20)]
+    );
+  }
+
   #[test]
   fn test_parsed_module_analyze_dependencies() {
     let specifier = resolve_url_or_path("https://deno.land/x/mod.js").unwrap();
@@ -1,39 +1,71 @@
 // Copyright 2018-2021 the Deno authors. All rights reserved. MIT license.
 
+use crate::deno_dir::dirs;
 use deno_core::ModuleSpecifier;
+use std::env;
 use std::fmt;
+use std::path::PathBuf;
+
+/// The environment variable with the path of the `.netrc` file, overriding
+/// `$HOME/.netrc`.
+static NETRC: &str = "NETRC";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AuthTokenData {
+  Bearer(String),
+  Basic { username: String, password: String },
+}
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct AuthToken {
   host: String,
-  token: String,
+  token: AuthTokenData,
 }
 
 impl fmt::Display for AuthToken {
   fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-    write!(f, "Bearer {}", self.token)
+    match &self.token {
+      AuthTokenData::Bearer(token) => write!(f, "Bearer {}", token),
+      AuthTokenData::Basic { username, password } => {
+        let credentials = format!("{}:{}", username, password);
+        write!(f, "Basic {}", base64::encode(credentials))
+      }
+    }
   }
 }
 
-/// A structure which contains bearer tokens that can be used when sending
-/// requests to websites, intended to authorize access to private resources
-/// such as remote modules.
+/// A structure which contains bearer tokens and basic auth credentials that
+/// can be used when sending requests to websites, intended to authorize
+/// access to private resources such as remote modules.
 #[derive(Debug, Clone)]
-pub struct AuthTokens(Vec<AuthToken>);
+pub struct AuthTokens {
+  tokens: Vec<AuthToken>,
+  /// The credentials from `.netrc`, which are only used for hosts that none
+  /// of `tokens` match.
+  netrc: Vec<AuthToken>,
+}
 
 impl AuthTokens {
   /// Create a new set of tokens based on the provided string. It is intended
   /// that the string be the value of an environment variable and the string is
   /// parsed for token values.  The string is expected to be a semi-colon
-  /// separated string, where each value is `{token}@{hostname}`.
+  /// separated string, where each value is `{token}@{hostname}` for a bearer
+  /// token or `{username}:{password}@{hostname}` for basic auth.
   pub fn new(maybe_tokens_str: Option<String>) -> Self {
     let mut tokens = Vec::new();
     if let Some(tokens_str) = maybe_tokens_str {
       for token_str in tokens_str.split(';') {
         if token_str.contains('@') {
           let pair: Vec<&str> = token_str.rsplitn(2, '@').collect();
-          let token = pair[1].to_string();
+          let token = pair[1];
           let host = pair[0].to_lowercase();
+          let token = match token.find(':') {
+            Some(i) => AuthTokenData::Basic {
+              username: token[..i].to_string(),
+              password: token[i + 1..].to_string(),
+            },
+            None => AuthTokenData::Bearer(token.to_string()),
+          };
           tokens.push(AuthToken { host, token });
         } else {
           error!("Badly formed auth token discarded.");
@@ -42,28 +74,117 @@ impl AuthTokens {
       debug!("Parsed {} auth token(s).", tokens.len());
     }
 
-    Self(tokens)
+    Self {
+      tokens,
+      netrc: Vec::new(),
+    }
+  }
+
+  /// Adds the credentials of the `machine` entries of a `.netrc` file, as
+  /// returned by `read_netrc()`. They are sent as basic auth, to the exact
+  /// host of the entry on any port. The `default` entry is ignored, so
+  /// credentials are never sent to hosts that aren't listed.
+  pub fn with_netrc(mut self, maybe_netrc_str: Option<String>) -> Self {
+    if let Some(netrc_str) = maybe_netrc_str {
+      self.netrc = parse_netrc(&netrc_str);
+      debug!("Parsed {} .netrc entries.", self.netrc.len());
+    }
+    self
   }
 
   /// Attempt to match the provided specifier to the tokens in the set.  The
   /// matching occurs from the right of the hostname plus port, irrespective of
   /// scheme.  For example `https://www.deno.land:8080/` would match a token
   /// with a host value of `deno.land:8080` but not match `www.deno.land`.  The
-  /// matching is case insensitive.
+  /// matching is case insensitive.  If no token matches, the `.netrc` entry
+  /// for the host is used.
   pub fn get(&self, specifier: &ModuleSpecifier) -> Option<AuthToken> {
-    self.0.iter().find_map(|t| {
-      let hostname = if let Some(port) = specifier.port() {
-        format!("{}:{}", specifier.host_str()?, port)
-      } else {
-        specifier.host_str()?.to_string()
-      };
-      if hostname.to_lowercase().ends_with(&t.host) {
-        Some(t.clone())
-      } else {
-        None
+    let host = specifier.host_str()?.to_lowercase();
+    let hostname = if let Some(port) = specifier.port() {
+      format!("{}:{}", host, port)
+    } else {
+      host.clone()
+    };
+    self
+      .tokens
+      .iter()
+      .find(|t| {
+        hostname == t.host || hostname.ends_with(&format!(".{}", t.host))
+      })
+      .or_else(|| self.netrc.iter().find(|t| host == t.host))
+      .cloned()
+  }
+}
+
+/// Reads the file `$NETRC` points to, or `.netrc` (`_netrc` on Windows) in
+/// the home directory. Returns `None` if there is no such file.
+pub fn read_netrc() -> Option<String> {
+  let path = match env::var_os(NETRC) {
+    Some(path) => PathBuf::from(path),
+    None if cfg!(windows) => dirs::home_dir()?.join("_netrc"),
+    None => dirs::home_dir()?.join(".netrc"),
+  };
+  std::fs::read_to_string(path).ok()
+}
+
+fn parse_netrc(netrc_str: &str) -> Vec<AuthToken> {
+  #[derive(Default)]
+  struct Entry {
+    machine: Option<String>,
+    login: Option<String>,
+    password: Option<String>,
+  }
+
+  let mut entries: Vec<Entry> = Vec::new();
+  let mut in_macdef = false;
+  for line in netrc_str.lines() {
+    // A macro definition runs until the next empty line.
+    if in_macdef {
+      in_macdef = !line.trim().is_empty();
+      continue;
+    }
+    if line.trim_start().starts_with('#') {
+      continue;
+    }
+    let mut words = line.split_whitespace();
+    while let Some(word) = words.next() {
+      match word {
+        "machine" => entries.push(Entry {
+          machine: words.next().map(|m| m.to_lowercase()),
+          ..Default::default()
+        }),
+        "default" => entries.push(Entry::default()),
+        "login" | "password" | "account" => {
+          let value = words.next().map(String::from);
+          if let Some(entry) = entries.last_mut() {
+            match word {
+              "login" => entry.login = value,
+              "password" => entry.password = value,
+              _ => {}
+            }
+          }
+        }
+        "macdef" => {
+          in_macdef = true;
+          break;
+        }
+        _ => {}
       }
-    })
+    }
   }
+
+  entries
+    .into_iter()
+    .filter_map(|entry| {
+      Some(AuthToken {
+        host: entry.machine?,
+        token: AuthTokenData::Basic {
+          username: entry.login?,
+          password: entry.password.unwrap_or_default(),
+        },
+      })
+    })
+    .collect()
 }
 
 #[cfg(test)]
@@ -131,4 +252,65 @@ mod tests {
       "Bearer abc@123".to_string()
     );
   }
+
+  #[test]
+  fn test_auth_tokens_basic() {
+    let auth_tokens = AuthTokens::new(Some(
+      "user:p@ss:word@deno.land;abc123@example.com".to_string(),
+    ));
+    let fixture = resolve_url("https://deno.land/x/mod.ts").unwrap();
+    assert_eq!(
+      auth_tokens.get(&fixture).unwrap().to_string(),
+      format!("Basic {}", base64::encode("user:p@ss:word"))
+    );
+    let fixture = resolve_url("https://example.com/x/mod.ts").unwrap();
+    assert_eq!(
+      auth_tokens.get(&fixture).unwrap().to_string(),
+      "Bearer abc123"
+    );
+  }
+
+  #[test]
+  fn test_auth_tokens_host_boundary() {
+    let auth_tokens = AuthTokens::new(Some("abc123@deno.land".to_string()));
+    let fixture = resolve_url("https://evildeno.land/x/mod.ts").unwrap();
+    assert_eq!(auth_tokens.get(&fixture), None);
+    let fixture = resolve_url("https://DENO.land/x/mod.ts").unwrap();
+    assert!(auth_tokens.get(&fixture).is_some());
+  }
+
+  #[test]
+  fn test_auth_tokens_netrc() {
+    let netrc = r#"
+# A comment.
+machine deno.land login user password secret
+machine example.com
+  login other
+  password hunter2
+macdef init
+  machine nope.com login nope password nope
+
+default login anyone password anything
+"#;
+    let auth_tokens = AuthTokens::new(Some("abc123@example.com".to_string()))
+      .with_netrc(Some(netrc.to_string()));
+    let fixture = resolve_url("https://deno.land:8080/x/mod.ts").unwrap();
+    assert_eq!(
+      auth_tokens.get(&fixture).unwrap().to_string(),
+      format!("Basic {}", base64::encode("user:secret"))
+    );
+    // DENO_AUTH_TOKENS takes precedence.
+    let fixture = resolve_url("https://example.com/x/mod.ts").unwrap();
+    assert_eq!(
+      auth_tokens.get(&fixture).unwrap().to_string(),
+      "Bearer abc123"
+    );
+    // Entries only match their exact host, and `default` is ignored.
+    let fixture = resolve_url("https://www.deno.land/x/mod.ts").unwrap();
+    assert_eq!(auth_tokens.get(&fixture), None);
+    let fixture = resolve_url("https://nope.com/x/mod.ts").unwrap();
+    assert_eq!(auth_tokens.get(&fixture), None);
+    let fixture = resolve_url("https://deno.com/x/mod.ts").unwrap();
+    assert_eq!(auth_tokens.get(&fixture), None);
+  }
 }
@@ -77,6 +77,11 @@ const EXEC_TIME_BENCHMARKS: &[(&str, &[&str], Option<i32>)] = &[
     &["run", "cli/tests/text_encoder_perf.js"],
     None,
   ),
+  (
+    "async_ops",
+    &["run", "--allow-read", "cli/tests/async_ops_perf.js"],
+    None,
+  ),
   (
     "check",
     &[
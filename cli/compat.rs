@@ -0,0 +1,279 @@
+// Copyright 2018-2021 the Deno authors. All rights reserved. MIT license.
+
+//! Resolution of the specifiers of Node code: `node:` specifiers and, with
+//! `--compat`, bare names of Node built-in modules resolve to the polyfills of
+//! `std/node`, and `npm:` specifiers are reported as unsupported unless an
+//! import map maps them.
+//!
+//! Built-in modules resolve to `node:` URLs, which the file fetcher fetches
+//! from the polyfills like redirects, so that the module graph and the
+//! dependencies it caches don't depend on where the polyfills are.
+
+use deno_core::error::generic_error;
+use deno_core::error::AnyError;
+use deno_core::url::Url;
+use deno_core::ModuleSpecifier;
+use std::env;
+use std::fmt;
+
+/// The location of the `std/node` polyfills, unless it is overridden with the
+/// `DENO_NODE_COMPAT_URL` environment variable.
+const DEFAULT_NODE_COMPAT_URL: &str = "https://deno.land/std@0.88.0/node/";
+
+/// The Node built-in modules that have a polyfill, and the path of the
+/// polyfill relative to the polyfills' location.
+static NODE_BUILTINS: &[(&str, &str)] = &[
+  ("assert", "assert.ts"),
+  ("buffer", "buffer.ts"),
+  ("child_process", "child_process.ts"),
+  ("constants", "constants.ts"),
+  ("crypto", "crypto.ts"),
+  ("events", "events.ts"),
+  ("fs", "fs.ts"),
+  ("fs/promises", "fs/promises.ts"),
+  ("module", "module.ts"),
+  ("os", "os.ts"),
+  ("path", "path.ts"),
+  ("perf_hooks", "perf_hooks.ts"),
+  ("process", "process.ts"),
+  ("querystring", "querystring.ts"),
+  ("stream", "stream.ts"),
+  ("string_decoder", "string_decoder.ts"),
+  ("timers", "timers.ts"),
+  ("tty", "tty.ts"),
+  ("url", "url.ts"),
+  ("util", "util.ts"),
+];
+
+fn is_node_builtin(name: &str) -> bool {
+  NODE_BUILTINS.iter().any(|(builtin, _)| *builtin == name)
+}
+
+fn node_compat_url() -> Result<Url, AnyError> {
+  let url = match env::var("DENO_NODE_COMPAT_URL") {
+    Ok(url) if !url.is_empty() => url,
+    _ => return Ok(Url::parse(DEFAULT_NODE_COMPAT_URL).unwrap()),
+  };
+  // Without a trailing slash, the last segment would be replaced when
+  // joining the paths of the polyfills.
+  let url = if url.ends_with('/') {
+    url
+  } else {
+    format!("{}/", url)
+  };
+  Url::parse(&url).map_err(|err| {
+    generic_error(format!("Invalid DENO_NODE_COMPAT_URL \"{}\": {}", url, err))
+  })
+}
+
+/// Returns the URL of the polyfill of a `node:` specifier.
+pub fn node_builtin_url(specifier: &ModuleSpecifier) -> Result<Url, AnyError> {
+  let name = specifier.path();
+  let (_, path) = NODE_BUILTINS
+    .iter()
+    .find(|(builtin, _)| *builtin == name)
+    .ok_or_else(|| unknown_node_builtin(specifier.as_str()))?;
+  Ok(node_compat_url()?.join(path)?)
+}
+
+fn unknown_node_builtin(specifier: &str) -> AnyError {
+  let builtins: Vec<&str> =
+    NODE_BUILTINS.iter().map(|(builtin, _)| *builtin).collect();
+  generic_error(format!(
+    "Unknown Node built-in module \"{}\". The known modules are: {}",
+    specifier,
+    builtins.join(", ")
+  ))
+}
+
+/// Resolves `specifier` if it refers to Node code: `node:` specifiers of
+/// known built-in modules, and bare names of built-in modules when `compat`
+/// is set, resolve to `node:` URLs. `npm:` specifiers are errors. Other
+/// specifiers return `None`, and are resolved as usual.
+///
+/// Import maps take precedence, so this is used for the specifiers they
+/// don't map.
+pub fn resolve(
+  specifier: &str,
+  compat: bool,
+) -> Result<Option<ModuleSpecifier>, AnyError> {
+  if let Some(name) = specifier.strip_prefix("node:") {
+    if !is_node_builtin(name) {
+      return Err(unknown_node_builtin(specifier));
+    }
+    Ok(Some(Url::parse(specifier)?))
+  } else if specifier.starts_with("npm:") {
+    let reference = NpmPackageReference::parse(specifier)?;
+    Err(generic_error(format!(
+      "npm: specifiers are not supported yet: \"{}\" ({}). Map it to a module with an import map, e.g. {{ \"imports\": {{ \"{}\": \"./node_modules/{}/{}\" }} }}",
+      specifier,
+      reference,
+      specifier,
+      reference.name,
+      reference.subpath.as_deref().unwrap_or("index.js")
+    )))
+  } else if compat && is_node_builtin(specifier) {
+    Ok(Some(Url::parse(&format!("node:{}", specifier))?))
+  } else {
+    Ok(None)
+  }
+}
+
+/// A parsed `npm:` specifier, `npm:<name>[@<version>][/<subpath>]`, where
+/// the name can be scoped.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NpmPackageReference {
+  pub name: String,
+  pub version_req: Option<String>,
+  pub subpath: Option<String>,
+}
+
+impl NpmPackageReference {
+  pub fn parse(specifier: &str) -> Result<Self, AnyError> {
+    let invalid = |reason: &str| {
+      generic_error(format!(
+        "Invalid npm specifier \"{}\": {}",
+        specifier, reason
+      ))
+    };
+    let rest = specifier
+      .strip_prefix("npm:")
+      .ok_or_else(|| invalid("it must start with \"npm:\""))?;
+    // The name of a scoped package spans two segments.
+    let name_segments = if rest.starts_with('@') { 2 } else { 1 };
+    let mut segments = rest.splitn(name_segments + 1, '/');
+    let mut name_and_version = vec![];
+    for _ in 0..name_segments {
+      match segments.next() {
+        Some(segment) if !segment.is_empty() => name_and_version.push(segment),
+        _ => return Err(invalid("the package name is missing")),
+      }
+    }
+    let name_and_version = name_and_version.join("/");
+    let subpath = match segments.next() {
+      Some("") => None,
+      Some(subpath) => Some(subpath.to_string()),
+      None => None,
+    };
+    // A scoped name starts with "@", which doesn't separate the version.
+    let (name, version_req) = match name_and_version[1..].find('@') {
+      Some(index) => {
+        let (name, version_req) = name_and_version.split_at(index + 1);
+        let version_req = &version_req[1..];
+        if version_req.is_empty() {
+          return Err(invalid("the version after \"@\" is missing"));
+        }
+        (name.to_string(), Some(version_req.to_string()))
+      }
+      None => (name_and_version, None),
+    };
+    Ok(Self {
+      name,
+      version_req,
+      subpath,
+    })
+  }
+}
+
+impl fmt::Display for NpmPackageReference {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "package \"{}\"", self.name)?;
+    if let Some(version_req) = &self.version_req {
+      write!(f, ", version \"{}\"", version_req)?;
+    }
+    if let Some(subpath) = &self.subpath {
+      write!(f, ", subpath \"{}\"", subpath)?;
+    }
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_resolve_node_specifiers() {
+    assert_eq!(
+      resolve("node:path", false).unwrap(),
+      Some(Url::parse("node:path").unwrap())
+    );
+    assert_eq!(
+      resolve("node:fs/promises", false).unwrap(),
+      Some(Url::parse("node:fs/promises").unwrap())
+    );
+    let err = resolve("node:nope", false).unwrap_err();
+    assert!(err
+      .to_string()
+      .starts_with("Unknown Node built-in module \"node:nope\""));
+    assert!(err.to_string().contains("fs/promises, module, os, path"));
+
+    // Bare names of built-in modules only resolve with `--compat`.
+    assert_eq!(resolve("path", false).unwrap(), None);
+    assert_eq!(
+      resolve("path", true).unwrap(),
+      Some(Url::parse("node:path").unwrap())
+    );
+    assert_eq!(resolve("lodash", true).unwrap(), None);
+    assert_eq!(resolve("./path.ts", true).unwrap(), None);
+  }
+
+  #[test]
+  fn test_node_builtin_url() {
+    let url =
+      node_builtin_url(&Url::parse("node:fs/promises").unwrap()).unwrap();
+    assert_eq!(
+      url.as_str(),
+      "https://deno.land/std@0.88.0/node/fs/promises.ts"
+    );
+    assert!(node_builtin_url(&Url::parse("node:nope").unwrap()).is_err());
+  }
+
+  #[test]
+  fn test_npm_package_reference() {
+    let cases = vec![
+      ("npm:chalk", "chalk", None, None),
+      ("npm:chalk@5", "chalk", Some("5"), None),
+      (
+        "npm:chalk@^5.0.1/source/index.js",
+        "chalk",
+        Some("^5.0.1"),
+        Some("source/index.js"),
+      ),
+      ("npm:@types/node", "@types/node", None, None),
+      ("npm:@types/node@16", "@types/node", Some("16"), None),
+      (
+        "npm:@scope/pkg@1.2.3/sub/path",
+        "@scope/pkg",
+        Some("1.2.3"),
+        Some("sub/path"),
+      ),
+      ("npm:@scope/pkg/sub", "@scope/pkg", None, Some("sub")),
+    ];
+    for (specifier, name, version_req, subpath) in cases {
+      assert_eq!(
+        NpmPackageReference::parse(specifier).unwrap(),
+        NpmPackageReference {
+          name: name.to_string(),
+          version_req: version_req.map(String::from),
+          subpath: subpath.map(String::from),
+        },
+        "{}",
+        specifier
+      );
+    }
+
+    for specifier in &["npm:", "npm:@scope", "npm:@scope/", "npm:chalk@"] {
+      assert!(
+        NpmPackageReference::parse(specifier).is_err(),
+        "{}",
+        specifier
+      );
+    }
+
+    let err = resolve("npm:@scope/pkg@1/sub", false).unwrap_err();
+    assert!(err.to_string().starts_with(
+      "npm: specifiers are not supported yet: \"npm:@scope/pkg@1/sub\" (package \"@scope/pkg\", version \"1\", subpath \"sub\")"
+    ));
+  }
+}
@@ -369,6 +369,18 @@ impl Diagnostics {
   pub fn is_empty(&self) -> bool {
     self.0.is_empty()
   }
+
+  /// Removes the diagnostics of remote modules, returning whether there were
+  /// any.
+  pub fn remove_remote(&mut self) -> bool {
+    let len = self.0.len();
+    self.0.retain(|diagnostic| {
+      !diagnostic.file_name.as_ref().map_or(false, |file_name| {
+        file_name.starts_with("http://") || file_name.starts_with("https://")
+      })
+    });
+    self.0.len() != len
+  }
 }
 
 impl<'de> Deserialize<'de> for Diagnostics {
@@ -95,7 +95,7 @@ declare namespace Deno {
   export const noColor: boolean;
 
   export interface TestDefinition {
-    fn: () => void | Promise<void>;
+    fn: (t: TestContext) => void | Promise<void>;
     name: string;
     ignore?: boolean;
     /** If at lease one test has `only` set to true, only run tests that have
@@ -110,6 +110,42 @@ declare namespace Deno {
     sanitizeResources?: boolean;
   }
 
+  export interface TestContext {
+    /** Run a sub step of the parent test or step. Steps run one after another,
+     * so the returned promise must be awaited before starting the next step or
+     * finishing the parent. Resolves to whether the step passed; a failed step
+     * fails its parent.
+     *
+     * ```ts
+     * Deno.test("database", async (t) => {
+     *   await t.step("insert", async () => {
+     *     // ...
+     *   });
+     *   await t.step("query", async (t) => {
+     *     await t.step("by id", () => {});
+     *   });
+     * });
+     * ```
+     */
+    step(t: TestStepDefinition): Promise<boolean>;
+    step(
+      name: string,
+      fn: (t: TestContext) => void | Promise<void>,
+    ): Promise<boolean>;
+  }
+
+  export interface TestStepDefinition {
+    fn: (t: TestContext) => void | Promise<void>;
+    name: string;
+    ignore?: boolean;
+    /** Check that the number of async completed ops after the step is the same
+     * as number of dispatched ops. Defaults to the value of the parent. */
+    sanitizeOps?: boolean;
+    /** Ensure the step does not "leak" resources. Defaults to the value of the
+     * parent. */
+    sanitizeResources?: boolean;
+  }
+
   /** Register a test which will be run when `deno test` is used on the command
    * line and the containing module looks like a test module.
    * `fn` can be async if required.
@@ -161,7 +197,10 @@ declare namespace Deno {
    * });
    * ```
    * */
-  export function test(name: string, fn: () => void | Promise<void>): void;
+  export function test(
+    name: string,
+    fn: (t: TestContext) => void | Promise<void>,
+  ): void;
 
   /** Exit the Deno process with optional exit code. If no exit code is supplied
    * then Deno will exit with return code of 0.
@@ -1713,6 +1752,13 @@ declare namespace Deno {
     /** Shuts down (`shutdown(2)`) the write side of the connection. Most
      * callers should just use `close()`. */
     closeWrite(): Promise<void>;
+    /** Enables or disables Nagle's algorithm (`TCP_NODELAY`) on a TCP
+     * connection. Not supported on TLS or Unix connections. */
+    setNoDelay(nodelay?: boolean): Promise<void>;
+    /** Enables or disables TCP keepalive probes on a TCP connection.
+     * `intervalSecs` is the idle time before the first probe is sent, and
+     * defaults to two hours. Not supported on TLS or Unix connections. */
+    setKeepAlive(keepalive?: boolean, intervalSecs?: number): Promise<void>;
   }
 
   export interface ListenOptions {
@@ -1721,6 +1767,9 @@ declare namespace Deno {
     /** A literal IP address or host name that can be resolved to an IP address.
      * If not specified, defaults to `0.0.0.0`. */
     hostname?: string;
+    /** The maximum number of pending connections waiting to be accepted.
+     * Defaults to 128. */
+    backlog?: number;
   }
 
   /** Listen announces on the local transport address.
@@ -2036,6 +2085,9 @@ declare namespace Deno {
     getters?: boolean;
     /** Show an object's non-enumerable properties. Defaults to false. */
     showHidden?: boolean;
+    /** The maximum length of strings nested in collections before they are
+     * truncated. Defaults to 100. */
+    strAbbreviateSize?: number;
   }
 
   /** Converts the input into a string that has the same format as printed by
@@ -2065,6 +2117,10 @@ declare namespace Deno {
    *      const inStringFormat = Deno.inspect(new A()); // "x=10, y=hello"
    *      console.log(inStringFormat);  // prints "x=10, y=hello"
    *
+   * The custom inspect function is called with `Deno.inspect` and the options
+   * in effect, with `depth` reduced by the nesting level of the object, so
+   * that it can format nested values consistently.
+   *
    * Finally, you can also specify the depth to which it will format.
    *
    *      Deno.inspect({a: {b: {c: {d: 'hello'}}}}, {depth: 2}); // { a: { b: [Object] } }
@@ -537,6 +537,10 @@ declare class Performance {
   getEntriesByName(name: string, type?: string): PerformanceEntryList;
   getEntriesByType(type: string): PerformanceEntryList;
 
+  /** Limits the number of buffered marks and measures to `maxSize`. Once the
+   * buffer is full, the oldest entries are dropped to make room. */
+  setResourceTimingBufferSize(maxSize: number): void;
+
   /** Stores a timestamp with the associated name (a "mark"). */
   mark(markName: string, options?: PerformanceMarkOptions): PerformanceMark;
 
@@ -4,6 +4,54 @@
 /// <reference lib="deno.ns" />
 
 declare namespace Deno {
+  export interface BenchDefinition {
+    fn: () => void | Promise<void>;
+    name: string;
+    ignore?: boolean;
+    /** If at least one bench has `only` set to true, only run benches that
+     * have `only` set to true and fail the bench suite. */
+    only?: boolean;
+    /** Benches in the same group are reported together. When one of them is
+     * the baseline, the mean times of the others are reported relative to
+     * it. */
+    group?: string;
+    /** Compare the other benches of the group against this one. */
+    baseline?: boolean;
+  }
+
+  /** **UNSTABLE**: New API, yet to be vetted.
+   *
+   * Register a benchmark which will be run when `deno bench` is used on the
+   * command line and the containing module looks like a bench module.
+   * `fn` can be async if required. It is called repeatedly, first to warm
+   * up and then until enough time has been spent measuring it.
+   *
+   * ```ts
+   * Deno.bench({
+   *   name: "JSON.parse",
+   *   group: "parse",
+   *   baseline: true,
+   *   fn() {
+   *     JSON.parse('{"hello":"world"}');
+   *   },
+   * });
+   *
+   * Deno.bench("read file", async () => {
+   *   await Deno.readFile("hello_world.txt");
+   * });
+   * ```
+   */
+  export function bench(b: BenchDefinition): void;
+  export function bench(
+    name: string,
+    fn: () => void | Promise<void>,
+  ): void;
+  export function bench(
+    name: string,
+    options: Omit<BenchDefinition, "fn" | "name">,
+    fn: () => void | Promise<void>,
+  ): void;
+
   /**
    * **UNSTABLE**: New API, yet to be vetted.  This API is under consideration to
    * determine if permissions are required to call it.
@@ -859,6 +907,9 @@ declare namespace Deno {
       * If not specified, defaults to 53. */
       port?: number;
     };
+    /** An abort signal to cancel the lookup. An aborted lookup rejects with
+     * an `AbortError` `DOMException`. */
+    signal?: AbortSignal;
   }
 
   /** If `resolveDns` is called with "MX" record type specified, it will return an array of this interface. */
@@ -935,6 +986,31 @@ declare namespace Deno {
      *
      * Sends a message to the target. */
     send(p: Uint8Array, addr: Addr): Promise<number>;
+    /** **UNSTABLE**: new API, yet to be vetted.
+     *
+     * Joins an IPv4 multicast group on the interface with the given local
+     * address (`"0.0.0.0"` lets the system choose). Packets sent to the group
+     * are then delivered to `receive()` with the sender as remote address.
+     *
+     * Requires `allow-net` permission for the group address. */
+    joinMulticastV4(
+      address: string,
+      multiInterface: string,
+    ): Promise<MulticastV4Membership>;
+    /** **UNSTABLE**: new API, yet to be vetted.
+     *
+     * Joins an IPv6 multicast group on the interface with the given index
+     * (`0` lets the system choose).
+     *
+     * Requires `allow-net` permission for the group address. */
+    joinMulticastV6(
+      address: string,
+      multiInterface: number,
+    ): Promise<MulticastV6Membership>;
+    /** **UNSTABLE**: new API, yet to be vetted.
+     *
+     * Enables or disables sending to broadcast addresses from this socket. */
+    setBroadcast(broadcast: boolean): Promise<void>;
     /** UNSTABLE: new API, yet to be vetted.
      *
      * Close closes the socket. Any pending message promises will be rejected
@@ -945,6 +1021,28 @@ declare namespace Deno {
     [Symbol.asyncIterator](): AsyncIterableIterator<[Uint8Array, Addr]>;
   }
 
+  /** **UNSTABLE**: new API, yet to be vetted.
+   *
+   * The membership of a `DatagramConn` in an IPv4 multicast group. */
+  export interface MulticastV4Membership {
+    /** Leaves the multicast group. */
+    leave(): Promise<void>;
+    /** Sets whether packets sent to the group are looped back to this host. */
+    setLoopback(loopback: boolean): Promise<void>;
+    /** Sets the time-to-live of outgoing multicast packets. */
+    setTTL(ttl: number): Promise<void>;
+  }
+
+  /** **UNSTABLE**: new API, yet to be vetted.
+   *
+   * The membership of a `DatagramConn` in an IPv6 multicast group. */
+  export interface MulticastV6Membership {
+    /** Leaves the multicast group. */
+    leave(): Promise<void>;
+    /** Sets whether packets sent to the group are looped back to this host. */
+    setLoopback(loopback: boolean): Promise<void>;
+  }
+
   export interface UnixListenOptions {
     /** A Path to the Unix Socket. */
     path: string;
@@ -1030,6 +1128,11 @@ declare namespace Deno {
     hostname?: string;
     /** Server certificate file. */
     certFile?: string;
+    /** PEM encoded CA certificates to trust, in addition to Mozilla's root
+     * certificates. */
+    caCerts?: string[];
+    /** Application protocols to offer during ALPN negotiation. */
+    alpnProtocols?: string[];
   }
 
   /** **UNSTABLE**: new API, yet to be vetted.
@@ -1039,7 +1142,8 @@ declare namespace Deno {
    * cert file is optional and if not included Mozilla's root certificates will
    * be used (see also https://github.com/ctz/webpki-roots for specifics)
    * Using this function requires that the other end of the connection is
-   * prepared for TLS handshake.
+   * prepared for TLS handshake. The connection can't have reads or writes in
+   * flight. The upgraded connection keeps the `rid` of `conn`.
    *
    * ```ts
    * const conn = await Deno.connect({ port: 80, hostname: "127.0.0.1" });
@@ -1333,6 +1437,84 @@ declare namespace Deno {
     mtime: number | Date,
   ): Promise<void>;
 
+  /** **UNSTABLE**: new API, yet to be vetted.
+   *
+   * Synchronously acquires an advisory lock on the file referenced by `rid`,
+   * blocking until it is available. The lock is shared unless `exclusive` is
+   * `true`. It is released by `Deno.funlockSync()` or when the file is
+   * closed.
+   *
+   * ```ts
+   * const file = Deno.openSync("file.txt", { write: true, create: true });
+   * Deno.flockSync(file.rid, true);
+   * // ... write to the file ...
+   * Deno.funlockSync(file.rid);
+   * ```
+   */
+  export function flockSync(rid: number, exclusive?: boolean): void;
+
+  /** **UNSTABLE**: new API, yet to be vetted.
+   *
+   * Acquires an advisory lock on the file referenced by `rid`, resolving once
+   * it is available. The lock is shared unless `exclusive` is `true`. Waiting
+   * for the lock does not block the event loop.
+   *
+   * ```ts
+   * const file = await Deno.open("file.txt", { write: true, create: true });
+   * await Deno.flock(file.rid, true);
+   * // ... write to the file ...
+   * await Deno.funlock(file.rid);
+   * ```
+   */
+  export function flock(rid: number, exclusive?: boolean): Promise<void>;
+
+  /** **UNSTABLE**: new API, yet to be vetted.
+   *
+   * Like `Deno.flockSync()`, but returns `false` instead of blocking when the
+   * lock is held elsewhere.
+   *
+   * ```ts
+   * const file = Deno.openSync("file.txt", { write: true, create: true });
+   * if (!Deno.tryFlockSync(file.rid, true)) {
+   *   console.log("file.txt is in use");
+   * }
+   * ```
+   */
+  export function tryFlockSync(rid: number, exclusive?: boolean): boolean;
+
+  /** **UNSTABLE**: new API, yet to be vetted.
+   *
+   * Like `Deno.flock()`, but resolves to `false` instead of waiting when the
+   * lock is held elsewhere.
+   */
+  export function tryFlock(rid: number, exclusive?: boolean): Promise<boolean>;
+
+  /** **UNSTABLE**: new API, yet to be vetted.
+   *
+   * Synchronously releases the lock taken on the file referenced by `rid`.
+   * Throws `Deno.errors.BadResource` if the file is not locked.
+   *
+   * ```ts
+   * const file = Deno.openSync("file.txt", { write: true, create: true });
+   * Deno.flockSync(file.rid);
+   * Deno.funlockSync(file.rid);
+   * ```
+   */
+  export function funlockSync(rid: number): void;
+
+  /** **UNSTABLE**: new API, yet to be vetted.
+   *
+   * Releases the lock taken on the file referenced by `rid`. Rejects with
+   * `Deno.errors.BadResource` if the file is not locked.
+   *
+   * ```ts
+   * const file = await Deno.open("file.txt", { write: true, create: true });
+   * await Deno.flock(file.rid);
+   * await Deno.funlock(file.rid);
+   * ```
+   */
+  export function funlock(rid: number): Promise<void>;
+
   /** *UNSTABLE**: new API, yet to be vetted.
    *
    * SleepSync puts the main thread to sleep synchronously for a given amount of
@@ -1427,3 +1609,110 @@ declare interface WorkerOptions {
     };
   };
 }
+
+declare interface WebSocketStreamOptions {
+  protocols?: string[];
+  /** Aborts connecting to the server. */
+  signal?: AbortSignal;
+}
+
+declare interface WebSocketConnection {
+  /** Messages from the server. A message is only read from the socket when
+   * the stream is read from, so a slow reader slows down the server. */
+  readable: ReadableStream<string | Uint8Array>;
+  /** Messages to the server. A write resolves once the message has been
+   * flushed to the socket. */
+  writable: WritableStream<string | BufferSource | Blob>;
+  /** The extensions selected by the server. */
+  extensions: string;
+  /** The subprotocol selected by the server. */
+  protocol: string;
+}
+
+declare interface WebSocketCloseInfo {
+  code?: number;
+  reason?: string;
+}
+
+/** UNSTABLE: New API, yet to be vetted.
+ *
+ * A WebSocket client with a streams based API, which applies backpressure
+ * in both directions.
+ *
+ * ```ts
+ * const wss = new WebSocketStream("wss://echo.websocket.org");
+ * const { readable, writable } = await wss.connection;
+ * const writer = writable.getWriter();
+ * await writer.write("hello");
+ * for await (const message of readable) {
+ *   console.log(message);
+ * }
+ * ```
+ */
+declare class WebSocketStream {
+  constructor(url: string, options?: WebSocketStreamOptions);
+  readonly url: string;
+  /** Resolves once the handshake with the server completed. */
+  readonly connection: Promise<WebSocketConnection>;
+  /** Resolves with the server's close code and reason once the closing
+   * handshake completed, or rejects if the connection failed. */
+  readonly closed: Promise<WebSocketCloseInfo>;
+  /** Starts the closing handshake. */
+  close(closeInfo?: WebSocketCloseInfo): void;
+}
+
+declare interface URLPatternInit {
+  protocol?: string;
+  username?: string;
+  password?: string;
+  hostname?: string;
+  port?: string;
+  pathname?: string;
+  search?: string;
+  hash?: string;
+  baseURL?: string;
+}
+
+declare type URLPatternInput = string | URLPatternInit;
+
+declare interface URLPatternComponentResult {
+  input: string;
+  groups: Record<string, string | undefined>;
+}
+
+declare interface URLPatternResult {
+  inputs: [URLPatternInput] | [URLPatternInput, string];
+  protocol: URLPatternComponentResult;
+  username: URLPatternComponentResult;
+  password: URLPatternComponentResult;
+  hostname: URLPatternComponentResult;
+  port: URLPatternComponentResult;
+  pathname: URLPatternComponentResult;
+  search: URLPatternComponentResult;
+  hash: URLPatternComponentResult;
+}
+
+/** UNSTABLE: New API, yet to be vetted.
+ *
+ * Matches URLs against a pattern, with named groups for the parts that
+ * vary. Components left out of the pattern match anything.
+ *
+ * ```ts
+ * const pattern = new URLPattern({ pathname: "/books/:id" });
+ * const match = pattern.exec("https://example.com/books/123");
+ * console.log(match?.pathname.groups.id); // "123"
+ * ```
+ */
+declare class URLPattern {
+  constructor(input?: URLPatternInput, baseURL?: string);
+  readonly protocol: string;
+  readonly username: string;
+  readonly password: string;
+  readonly hostname: string;
+  readonly port: string;
+  readonly pathname: string;
+  readonly search: string;
+  readonly hash: string;
+  test(input?: URLPatternInput, baseURL?: string): boolean;
+  exec(input?: URLPatternInput, baseURL?: string): URLPatternResult | null;
+}
@@ -0,0 +1,123 @@
+// Copyright 2018-2021 the Deno authors. All rights reserved. MIT license.
+
+//! Loading of the environment variables of `--env-file`.
+
+use deno_core::error::generic_error;
+use deno_core::error::AnyError;
+use std::env;
+use std::fs;
+use std::path::Path;
+
+/// Sets the environment variables defined in the file at `path`. Variables
+/// that are already set keep their value.
+pub fn load(path: &Path) -> Result<(), AnyError> {
+  let text = fs::read_to_string(path).map_err(|err| {
+    generic_error(format!("Unable to read env file {:?}: {}", path, err))
+  })?;
+  let vars = parse(&text).map_err(|err| {
+    generic_error(format!("Unable to parse env file {:?}: {}", path, err))
+  })?;
+  for (key, value) in vars {
+    if env::var_os(&key).is_none() {
+      env::set_var(key, value);
+    }
+  }
+  Ok(())
+}
+
+/// Parses the lines of an env file, `KEY=VALUE` with an optional `export `
+/// prefix. Empty lines and lines starting with `#` are skipped. Values can be
+/// quoted: single quoted values are taken literally, and double quoted values
+/// support `\n`, `\"` and `\\` escapes. Unquoted values are trimmed and end
+/// at a ` #` comment.
+fn parse(text: &str) -> Result<Vec<(String, String)>, AnyError> {
+  let mut vars = Vec::new();
+  for (index, line) in text.lines().enumerate() {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with('#') {
+      continue;
+    }
+    let line = line.strip_prefix("export ").unwrap_or(line);
+    let invalid_line = || {
+      generic_error(format!("Invalid line {}: expected KEY=VALUE", index + 1))
+    };
+    let eq = line.find('=').ok_or_else(invalid_line)?;
+    let key = line[..eq].trim();
+    let is_valid_key =
+      key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+        && !key.is_empty()
+        && !key.starts_with(|c: char| c.is_ascii_digit());
+    if !is_valid_key {
+      return Err(invalid_line());
+    }
+    let value = line[eq + 1..].trim();
+    let value = if let Some(value) = value.strip_prefix('\'') {
+      value
+        .strip_suffix('\'')
+        .ok_or_else(invalid_line)?
+        .to_string()
+    } else if let Some(value) = value.strip_prefix('"') {
+      let value = value.strip_suffix('"').ok_or_else(invalid_line)?;
+      let mut unescaped = String::new();
+      let mut chars = value.chars();
+      while let Some(c) = chars.next() {
+        if c != '\\' {
+          unescaped.push(c);
+          continue;
+        }
+        match chars.next() {
+          Some('n') => unescaped.push('\n'),
+          Some(c) => unescaped.push(c),
+          None => unescaped.push('\\'),
+        }
+      }
+      unescaped
+    } else {
+      match value.find(" #") {
+        Some(comment) => value[..comment].trim_end().to_string(),
+        None => value.to_string(),
+      }
+    };
+    vars.push((key.to_string(), value));
+  }
+  Ok(vars)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_parse() {
+    let text = r#"
+# A comment
+PLAIN=value
+export EXPORTED = padded  # trailing comment
+SINGLE='literal \n # kept'
+DOUBLE="line\nbreak \"quoted\""
+EMPTY=
+URL=https://example.com/#anchor
+"#;
+    let vars = parse(text).unwrap();
+    let expected: Vec<(String, String)> = vec![
+      ("PLAIN", "value"),
+      ("EXPORTED", "padded"),
+      ("SINGLE", "literal \\n # kept"),
+      ("DOUBLE", "line\nbreak \"quoted\""),
+      ("EMPTY", ""),
+      ("URL", "https://example.com/#anchor"),
+    ]
+    .into_iter()
+    .map(|(key, value)| (key.to_string(), value.to_string()))
+    .collect();
+    assert_eq!(vars, expected);
+  }
+
+  #[test]
+  fn test_parse_invalid() {
+    for text in &["NO_EQUALS", "1KEY=value", "BAD-KEY=value", "OPEN='value"] {
+      let err = parse(text).unwrap_err();
+      assert_eq!(err.to_string(), "Invalid line 1: expected KEY=VALUE");
+    }
+  }
+}
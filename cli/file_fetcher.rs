@@ -1,7 +1,9 @@
 // Copyright 2018-2021 the Deno authors. All rights reserved. MIT license.
 
+use crate::auth_tokens::read_netrc;
 use crate::auth_tokens::AuthTokens;
 use crate::colors;
+use crate::compat;
 use crate::http_cache::HttpCache;
 use crate::http_util::create_http_client;
 use crate::http_util::fetch_once;
@@ -75,6 +77,10 @@ pub enum CacheSetting {
   /// Only the cached files should be used.  Any files not in the cache will
   /// error.  This is the equivalent of `--cached-only` in the CLI.
   Only,
+  /// Only the cached files should be used, except for some resources which
+  /// are reloaded.  This is the equivalent of
+  /// `--cached-only --reload=https://deno.land/std` in the CLI.
+  OnlyReloadSome(Vec<String>),
   /// No cached source files should be used, and all files should be reloaded.
   /// This is the equivalent of `--reload` in the CLI.
   ReloadAll,
@@ -93,26 +99,43 @@ impl CacheSetting {
     match self {
       CacheSetting::ReloadAll => false,
       CacheSetting::Use | CacheSetting::Only => true,
-      CacheSetting::ReloadSome(list) => {
-        let mut url = specifier.clone();
-        url.set_fragment(None);
-        if list.contains(&url.as_str().to_string()) {
-          return false;
-        }
-        url.set_query(None);
-        let mut path = PathBuf::from(url.as_str());
-        loop {
-          if list.contains(&path.to_str().unwrap().to_string()) {
-            return false;
-          }
-          if !path.pop() {
-            break;
-          }
-        }
-        true
+      CacheSetting::ReloadSome(list) | CacheSetting::OnlyReloadSome(list) => {
+        !matches_reload_list(list, specifier)
       }
     }
   }
+
+  /// Returns if a given specifier may be downloaded when it isn't cached.
+  pub fn should_download(&self, specifier: &ModuleSpecifier) -> bool {
+    match self {
+      CacheSetting::Only => false,
+      CacheSetting::OnlyReloadSome(list) => {
+        matches_reload_list(list, specifier)
+      }
+      CacheSetting::ReloadAll
+      | CacheSetting::ReloadSome(_)
+      | CacheSetting::Use => true,
+    }
+  }
+}
+
+/// Whether a specifier is one of the URLs of a `--reload` list, ignoring its
+/// fragment, or is under one of them path segment wise:
+/// `https://deno.land/std` matches `https://deno.land/std/fs/mod.ts?a=b`,
+/// but not `https://deno.land/std2/mod.ts`.
+fn matches_reload_list(list: &[String], specifier: &ModuleSpecifier) -> bool {
+  let mut url = specifier.clone();
+  url.set_fragment(None);
+  if list.iter().any(|entry| entry == url.as_str()) {
+    return true;
+  }
+  url.set_query(None);
+  let url = url.as_str();
+  list.iter().any(|entry| {
+    let prefix = entry.trim_end_matches('/');
+    url == prefix
+      || (url.starts_with(prefix) && url[prefix.len()..].starts_with('/'))
+  })
 }
 
 /// Fetch a source file from the local file system.
@@ -326,7 +349,8 @@ impl FileFetcher {
     ca_data: Option<Vec<u8>>,
   ) -> Result<Self, AnyError> {
     Ok(Self {
-      auth_tokens: AuthTokens::new(env::var(DENO_AUTH_TOKENS).ok()),
+      auth_tokens: AuthTokens::new(env::var(DENO_AUTH_TOKENS).ok())
+        .with_netrc(read_netrc()),
       allow_remote,
       cache: Default::default(),
       cache_setting,
@@ -413,7 +437,7 @@ impl FileFetcher {
       Err(err) => return Err(err),
     }
 
-    if self.cache_setting == CacheSetting::Only {
+    if !self.cache_setting.should_download(specifier) {
       return Err(custom_error(
         "NotFound",
         format!(
@@ -478,7 +502,7 @@ impl FileFetcher {
       }
     }
 
-    if self.cache_setting == CacheSetting::Only {
+    if !self.cache_setting.should_download(specifier) {
       return futures::future::err(custom_error(
         "NotFound",
         format!(
@@ -540,6 +564,20 @@ impl FileFetcher {
     permissions: &Permissions,
   ) -> Result<File, AnyError> {
     debug!("FileFetcher::fetch() - specifier: {}", specifier);
+    // `node:` specifiers are fetched from their polyfills, whose specifier is
+    // returned, so they are handled like redirects.
+    if specifier.scheme() == "node" {
+      let polyfill = compat::node_builtin_url(specifier)?;
+      return self.fetch_resolved(&polyfill, permissions).await;
+    }
+    self.fetch_resolved(specifier, permissions).await
+  }
+
+  async fn fetch_resolved(
+    &self,
+    specifier: &ModuleSpecifier,
+    permissions: &Permissions,
+  ) -> Result<File, AnyError> {
     let scheme = get_validated_scheme(specifier)?;
     permissions.check_specifier(specifier)?;
     if let Some(file) = self.cache.get(specifier) {
@@ -1473,6 +1511,46 @@ mod tests {
     let _ = fs::remove_dir_all(temp_dir);
   }
 
+  #[test]
+  fn test_cache_setting_reload_prefixes() {
+    let list = vec![
+      "https://deno.land/std".to_string(),
+      "http://localhost:4545".to_string(),
+      "https://example.com/mod.ts?v=1".to_string(),
+    ];
+    let cache_setting = CacheSetting::OnlyReloadSome(list.clone());
+    let fixtures = [
+      ("https://deno.land/std", true),
+      ("https://deno.land/std/fs/mod.ts", true),
+      ("https://deno.land/std/fs/mod.ts?a=b#c", true),
+      ("https://deno.land/std2/mod.ts", false),
+      ("https://deno.land/x/std/mod.ts", false),
+      ("http://localhost:4545/cli/tests/002_hello.ts", true),
+      ("http://localhost:45450/cli/tests/002_hello.ts", false),
+      ("http://127.0.0.1:4545/cli/tests/002_hello.ts", false),
+      ("https://example.com/mod.ts?v=1#a", true),
+      ("https://example.com/mod.ts?v=2", false),
+    ];
+    for (specifier, is_reloaded) in fixtures.iter() {
+      let specifier = resolve_url(specifier).unwrap();
+      assert_eq!(
+        cache_setting.should_use(&specifier),
+        !is_reloaded,
+        "{}",
+        specifier
+      );
+      assert_eq!(
+        cache_setting.should_download(&specifier),
+        *is_reloaded,
+        "{}",
+        specifier
+      );
+      let cache_setting = CacheSetting::ReloadSome(list.clone());
+      assert_eq!(cache_setting.should_use(&specifier), !is_reloaded);
+      assert!(cache_setting.should_download(&specifier));
+    }
+  }
+
   #[tokio::test]
   async fn test_fetch_local_bypasses_file_cache() {
     let (file_fetcher, temp_dir) = setup(CacheSetting::Use, None);
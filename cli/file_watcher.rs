@@ -1,6 +1,9 @@
 // Copyright 2018-2021 the Deno authors. All rights reserved. MIT license.
 
 use crate::colors;
+use crate::flags::Flags;
+use crate::fs_util;
+use crate::tools::vendor::VENDOR_DIR;
 use deno_core::error::AnyError;
 use deno_core::futures::ready;
 use deno_core::futures::stream::{Stream, StreamExt};
@@ -13,6 +16,8 @@ use notify::RecommendedWatcher;
 use notify::RecursiveMode;
 use notify::Watcher;
 use pin_project::pin_project;
+use regex::Regex;
+use std::path::Path;
 use std::path::PathBuf;
 use std::pin::Pin;
 use std::sync::atomic::{AtomicBool, Ordering};
@@ -28,13 +33,101 @@ use tokio::time::Sleep;
 
 const DEBOUNCE_INTERVAL: Duration = Duration::from_millis(200);
 
+/// Globs of the paths that are never watched, relative to the current
+/// directory, in addition to the ones passed to `--watch-exclude`.
+const DEFAULT_EXCLUDE: &[&str] = &["**/.git", VENDOR_DIR];
+
 type FileWatcherFuture<T> = Pin<Box<dyn Future<Output = T>>>;
 
+/// The options of the `--watch`, `--watch-exclude` and `--no-clear-screen`
+/// flags, shared by all the subcommands that can watch files.
+#[derive(Clone)]
+pub struct WatcherOptions {
+  /// Files and directories that are watched in addition to the ones the job
+  /// reads. Directories are watched recursively.
+  paths: Vec<PathBuf>,
+  exclude: ExcludeFilter,
+  clear_screen: bool,
+}
+
+impl WatcherOptions {
+  pub fn from_flags(flags: &Flags) -> Result<Self, AnyError> {
+    let paths = flags
+      .watch_paths
+      .iter()
+      .map(|path| fs_util::resolve_from_cwd(path))
+      .collect::<Result<Vec<_>, AnyError>>()?;
+    Ok(Self {
+      paths,
+      exclude: ExcludeFilter::new(&flags.watch_exclude)?,
+      clear_screen: !flags.no_clear_screen,
+    })
+  }
+
+  /// Adds `self.paths` to `paths`, leaving out the excluded ones.
+  fn merge_paths(&self, paths: &[PathBuf]) -> Vec<(PathBuf, RecursiveMode)> {
+    let mut merged: Vec<(PathBuf, RecursiveMode)> = vec![];
+    let paths = paths
+      .iter()
+      .map(|path| (path, RecursiveMode::NonRecursive))
+      .chain(self.paths.iter().map(|path| {
+        let mode = if path.is_dir() {
+          RecursiveMode::Recursive
+        } else {
+          RecursiveMode::NonRecursive
+        };
+        (path, mode)
+      }));
+    for (path, mode) in paths {
+      if !self.exclude.is_excluded(path)
+        && !merged.iter().any(|(merged_path, _)| merged_path == path)
+      {
+        merged.push((path.clone(), mode));
+      }
+    }
+    merged
+  }
+}
+
+/// Matches the paths whose changes are ignored.
+#[derive(Clone)]
+struct ExcludeFilter {
+  cwd: PathBuf,
+  globs: Vec<Regex>,
+}
+
+impl ExcludeFilter {
+  fn new(exclude: &[String]) -> Result<Self, AnyError> {
+    let globs = DEFAULT_EXCLUDE
+      .iter()
+      .copied()
+      .chain(exclude.iter().map(String::as_str))
+      .map(fs_util::glob_to_regex)
+      .collect::<Result<Vec<_>, AnyError>>()?;
+    Ok(Self {
+      cwd: std::env::current_dir()?,
+      globs,
+    })
+  }
+
+  /// Paths outside of the current directory are matched as they are, so that
+  /// only globs starting with `**` apply to them.
+  fn is_excluded(&self, path: &Path) -> bool {
+    let path = path.strip_prefix(&self.cwd).unwrap_or(path);
+    self
+      .globs
+      .iter()
+      .any(|glob| fs_util::matches_glob(glob, path))
+  }
+}
+
 #[pin_project(project = DebounceProjection)]
 struct Debounce {
   #[pin]
   timer: Sleep,
   event_detected: Arc<AtomicBool>,
+  /// Whether events were detected that haven't been reported yet.
+  pending: bool,
 }
 
 impl Debounce {
@@ -42,6 +135,7 @@ impl Debounce {
     Self {
       timer: sleep(DEBOUNCE_INTERVAL),
       event_detected: Arc::new(AtomicBool::new(false)),
+      pending: false,
     }
   }
 }
@@ -49,23 +143,47 @@ impl Debounce {
 impl Stream for Debounce {
   type Item = ();
 
+  /// Yields once no more events were detected for `DEBOUNCE_INTERVAL`, so
+  /// that a burst of events, like an editor saving several files, or events
+  /// detected while the job was running, is reported only once.
+  ///
   /// Note that this never returns `Poll::Ready(None)`, which means that the
   /// file watcher will be alive until the Deno process is terminated.
   fn poll_next(
     self: Pin<&mut Self>,
     cx: &mut Context,
   ) -> Poll<Option<Self::Item>> {
-    if self.event_detected.swap(false, Ordering::Relaxed) {
-      Poll::Ready(Some(()))
-    } else {
-      let mut timer = self.project().timer;
-      ready!(timer.as_mut().poll(cx));
-      timer.reset(Instant::now() + DEBOUNCE_INTERVAL);
-      Poll::Pending
+    let mut this = self.project();
+    loop {
+      if this.event_detected.swap(false, Ordering::Relaxed) {
+        *this.pending = true;
+        this
+          .timer
+          .as_mut()
+          .reset(Instant::now() + DEBOUNCE_INTERVAL);
+      }
+      ready!(this.timer.as_mut().poll(cx));
+      this
+        .timer
+        .as_mut()
+        .reset(Instant::now() + DEBOUNCE_INTERVAL);
+      if std::mem::take(this.pending) {
+        return Poll::Ready(Some(()));
+      }
     }
   }
 }
 
+fn print_file_change_detected(options: &WatcherOptions) {
+  if options.clear_screen && atty::is(atty::Stream::Stderr) {
+    eprint!("\x1B[2J\x1B[1;1H");
+  }
+  info!(
+    "{} File change detected! Restarting!",
+    colors::intense_blue("Watcher"),
+  );
+}
+
 async fn error_handler(watch_future: FileWatcherFuture<Result<(), AnyError>>) {
   let result = watch_future.await;
   if let Err(err) = result {
@@ -87,6 +205,8 @@ async fn error_handler(watch_future: FileWatcherFuture<Result<(), AnyError>>) {
 ///
 /// - `job_name` is just used for printing watcher status to terminal.
 ///
+/// - `options` adds paths to watch and excludes others.
+///
 /// Note that the watcher will stop working if `target_resolver` fails at some point.
 ///
 /// [`ModuleGraph`]: crate::module_graph::Graph
@@ -94,6 +214,7 @@ pub async fn watch_func<F, G>(
   target_resolver: F,
   operation: G,
   job_name: &str,
+  options: WatcherOptions,
 ) -> Result<(), AnyError>
 where
   F: Fn() -> Result<Vec<PathBuf>, AnyError>,
@@ -104,16 +225,14 @@ where
 
   loop {
     let paths = target_resolver()?;
-    let _watcher = new_watcher(&paths, &debounce)?;
+    let _watcher =
+      new_watcher(&options.merge_paths(&paths), &options, &debounce)?;
     let func = error_handler(operation(paths));
     let mut is_file_changed = false;
     select! {
       _ = debounce.next() => {
         is_file_changed = true;
-        info!(
-          "{} File change detected! Restarting!",
-          colors::intense_blue("Watcher"),
-        );
+        print_file_change_detected(&options);
       },
       _ = func => {},
     };
@@ -125,10 +244,7 @@ where
         job_name,
       );
       debounce.next().await;
-      info!(
-        "{} File change detected! Restarting!",
-        colors::intense_blue("Watcher"),
-      );
+      print_file_change_detected(&options);
     }
   }
 }
@@ -157,6 +273,9 @@ pub enum ModuleResolutionResult<T> {
 ///
 /// - `job_name` is just used for printing watcher status to terminal.
 ///
+/// - `options` adds paths to watch and excludes others. The paths are merged
+/// with the ones returned by `module_resolver` every time it succeeds.
+///
 /// Note that the watcher will try to continue watching files using the previously resolved
 /// data if `module_resolver` fails at some point, which means the watcher won't work at all
 /// if `module_resolver` fails at the first attempt.
@@ -167,6 +286,7 @@ pub async fn watch_func_with_module_resolution<F, G, T>(
   module_resolver: F,
   operation: G,
   job_name: &str,
+  options: WatcherOptions,
 ) -> Result<(), AnyError>
 where
   F: Fn() -> FileWatcherFuture<ModuleResolutionResult<T>>,
@@ -200,7 +320,8 @@ where
         }
       }
     }
-    let _watcher = new_watcher(&paths, &debounce)?;
+    let _watcher =
+      new_watcher(&options.merge_paths(&paths), &options, &debounce)?;
 
     if let Some(module) = &module {
       let func = error_handler(operation(module.clone()));
@@ -208,10 +329,7 @@ where
       select! {
         _ = debounce.next() => {
           is_file_changed = true;
-          info!(
-            "{} File change detected! Restarting!",
-            colors::intense_blue("Watcher"),
-          );
+          print_file_change_detected(&options);
         },
         _ = func => {},
       };
@@ -223,10 +341,7 @@ where
           job_name,
         );
         debounce.next().await;
-        info!(
-          "{} File change detected! Restarting!",
-          colors::intense_blue("Watcher"),
-        );
+        print_file_change_detected(&options);
       }
     } else {
       info!(
@@ -235,19 +350,18 @@ where
         job_name,
       );
       debounce.next().await;
-      info!(
-        "{} File change detected! Restarting!",
-        colors::intense_blue("Watcher"),
-      );
+      print_file_change_detected(&options);
     }
   }
 }
 
 fn new_watcher(
-  paths: &[PathBuf],
+  paths: &[(PathBuf, RecursiveMode)],
+  options: &WatcherOptions,
   debounce: &Debounce,
 ) -> Result<RecommendedWatcher, AnyError> {
   let event_detected = Arc::clone(&debounce.event_detected);
+  let exclude = options.exclude.clone();
 
   let mut watcher: RecommendedWatcher =
     Watcher::new_immediate(move |res: Result<NotifyEvent, NotifyError>| {
@@ -255,7 +369,9 @@ fn new_watcher(
         if matches!(
           event.kind,
           EventKind::Create(_) | EventKind::Modify(_) | EventKind::Remove(_)
-        ) {
+        ) && (event.paths.is_empty()
+          || event.paths.iter().any(|path| !exclude.is_excluded(path)))
+        {
           event_detected.store(true, Ordering::Relaxed);
         }
       }
@@ -263,9 +379,9 @@ fn new_watcher(
 
   watcher.configure(Config::PreciseEvents(true)).unwrap();
 
-  for path in paths {
+  for (path, mode) in paths {
     // Ignore any error e.g. `PathNotFound`
-    let _ = watcher.watch(path, RecursiveMode::NonRecursive);
+    let _ = watcher.watch(path, *mode);
   }
 
   Ok(watcher)
@@ -1,5 +1,6 @@
 // Copyright 2018-2021 the Deno authors. All rights reserved. MIT license.
 
+use crate::tools::standalone::SUPPORTED_TARGETS;
 use clap::App;
 use clap::AppSettings;
 use clap::Arg;
@@ -14,16 +15,28 @@ use log::Level;
 use std::net::SocketAddr;
 use std::path::PathBuf;
 use std::str::FromStr;
+use std::time::Duration;
 use tempfile::TempDir;
 
 #[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
 pub enum DenoSubcommand {
+  Bench {
+    include: Option<Vec<String>>,
+    filter: Option<String>,
+    json: bool,
+  },
   Bundle {
     source_file: String,
     out_file: Option<PathBuf>,
   },
   Cache {
     files: Vec<String>,
+    /// Set by `--prune`: the cached remote modules that weren't used for this
+    /// long are removed.
+    prune: Option<Duration>,
+  },
+  Check {
+    files: Vec<String>,
   },
   Compile {
     source_file: String,
@@ -31,6 +44,7 @@ pub enum DenoSubcommand {
     args: Vec<String>,
     target: Option<String>,
     lite: bool,
+    include: Vec<PathBuf>,
   },
   Completions {
     buf: Box<[u8]>,
@@ -44,17 +58,19 @@ pub enum DenoSubcommand {
   Eval {
     print: bool,
     code: String,
-    as_typescript: bool,
   },
   Fmt {
     check: bool,
     files: Vec<PathBuf>,
     ignore: Vec<PathBuf>,
     ext: String,
+    options: FmtOptions,
   },
   Info {
     json: bool,
     file: Option<String>,
+    reverse: Option<String>,
+    cache: bool,
   },
   Install {
     module_url: String,
@@ -62,6 +78,7 @@ pub enum DenoSubcommand {
     name: Option<String>,
     root: Option<PathBuf>,
     force: bool,
+    list: bool,
   },
   LanguageServer,
   Lint {
@@ -69,11 +86,19 @@ pub enum DenoSubcommand {
     ignore: Vec<PathBuf>,
     rules: bool,
     json: bool,
+    fix: bool,
+  },
+  Repl {
+    eval: Option<String>,
+    eval_files: Option<Vec<String>>,
   },
-  Repl,
   Run {
     script: String,
   },
+  Task {
+    task: Option<String>,
+    args: Vec<String>,
+  },
   Test {
     no_run: bool,
     fail_fast: bool,
@@ -81,8 +106,19 @@ pub enum DenoSubcommand {
     allow_none: bool,
     include: Option<Vec<String>>,
     filter: Option<String>,
+    skip: Vec<String>,
+    shuffle: Option<u64>,
+    concurrent_jobs: usize,
+    lcov: Option<PathBuf>,
+    html: Option<PathBuf>,
+    reporter: TestReporterKind,
+    output: Option<PathBuf>,
   },
   Types,
+  Uninstall {
+    name: String,
+    root: Option<PathBuf>,
+  },
   Upgrade {
     dry_run: bool,
     force: bool,
@@ -91,11 +127,59 @@ pub enum DenoSubcommand {
     output: Option<PathBuf>,
     ca_file: Option<String>,
   },
+  Vendor {
+    entrypoints: Vec<String>,
+    force: bool,
+  },
+}
+
+/// The formatting options of `deno fmt` passed on the command line, which
+/// take precedence over the `"fmt"` section of the configuration file.
+#[derive(Clone, Debug, Default, PartialEq, Deserialize, Serialize)]
+pub struct FmtOptions {
+  pub line_width: Option<u32>,
+  pub indent_width: Option<u8>,
+  pub use_tabs: Option<bool>,
+  pub single_quote: Option<bool>,
+  pub prose_wrap: Option<String>,
+}
+
+/// The format of the report of `deno test`, set with `--reporter`.
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize, Serialize)]
+pub enum TestReporterKind {
+  Pretty,
+  Dot,
+  Junit,
+  Tap,
+}
+
+impl Default for TestReporterKind {
+  fn default() -> Self {
+    TestReporterKind::Pretty
+  }
+}
+
+/// Which modules type errors are reported for when type checking. Remote
+/// modules are only checked with `--check=all` or `deno check --remote`,
+/// except by the subcommands that always type check, like `deno cache`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TypeCheckMode {
+  All,
+  Local,
+}
+
+impl Default for TypeCheckMode {
+  fn default() -> Self {
+    TypeCheckMode::All
+  }
 }
 
 impl Default for DenoSubcommand {
   fn default() -> DenoSubcommand {
-    DenoSubcommand::Repl
+    DenoSubcommand::Repl {
+      eval: None,
+      eval_files: None,
+    }
   }
 }
 
@@ -117,14 +201,24 @@ pub struct Flags {
   pub cache_blocklist: Vec<String>,
   pub ca_file: Option<String>,
   pub cached_only: bool,
+  /// Set by `--compat`: bare names of Node built-in modules resolve to their
+  /// polyfills, like `node:` specifiers.
+  pub compat: bool,
   pub config_path: Option<String>,
   pub coverage_dir: Option<String>,
+  /// The file of `--env-file`, whose variables are set before the subcommand
+  /// runs.
+  pub env_file: Option<String>,
+  /// The extension of `--ext`, which sets the media type of the code of
+  /// `deno eval` and of `deno run -`.
+  pub ext: Option<String>,
   pub ignore: Vec<PathBuf>,
   pub import_map_path: Option<String>,
   pub inspect: Option<SocketAddr>,
   pub inspect_brk: Option<SocketAddr>,
   pub lock: Option<PathBuf>,
   pub lock_write: bool,
+  pub frozen_lockfile: bool,
   pub log_level: Option<Level>,
   pub no_check: bool,
   pub no_prompts: bool,
@@ -132,10 +226,16 @@ pub struct Flags {
   pub reload: bool,
   pub repl: bool,
   pub seed: Option<u64>,
+  pub type_check_mode: TypeCheckMode,
   pub unstable: bool,
   pub v8_flags: Vec<String>,
   pub version: bool,
   pub watch: bool,
+  /// Files and directories passed to `--watch`, which are watched in addition
+  /// to the ones the subcommand uses.
+  pub watch_paths: Vec<PathBuf>,
+  pub watch_exclude: Vec<String>,
+  pub no_clear_screen: bool,
 }
 
 fn join_paths(allowlist: &[PathBuf], d: &str) -> String {
@@ -220,14 +320,21 @@ impl From<Flags> for PermissionsOptions {
 }
 
 static ENV_VARIABLES_HELP: &str = r#"ENVIRONMENT VARIABLES:
-    DENO_AUTH_TOKENS     A semi-colon separated list of bearer tokens and
-                         hostnames to use when fetching remote modules from
-                         private repositories
-                         (e.g. "abcde12345@deno.land;54321edcba@github.com")
+    DENO_AUTH_TOKENS     A semi-colon separated list of bearer tokens or
+                         basic auth credentials and hostnames to use when
+                         fetching remote modules from private repositories
+                         (e.g. "abcde12345@deno.land;user:pass@github.com")
+    NETRC                Path of the .netrc file with credentials for hosts
+                         that DENO_AUTH_TOKENS doesn't match
+                         (defaults to $HOME/.netrc)
     DENO_CERT            Load certificate authority from PEM encoded file
     DENO_DIR             Set the cache directory
     DENO_INSTALL_ROOT    Set deno install's output directory
                          (defaults to $HOME/.deno/bin)
+    DENO_NODE_COMPAT_URL Set the location of the std/node polyfills that
+                         node: specifiers resolve to
+    DENO_REPL_HISTORY    Set the REPL history file, or disable the history
+                         with an empty value (defaults to $DENO_DIR/history)
     HTTP_PROXY           Proxy address for HTTP requests
                          (module downloads, fetch)
     HTTPS_PROXY          Proxy address for HTTPS requests
@@ -294,12 +401,16 @@ pub fn flags_from_vec(args: Vec<String>) -> clap::Result<Flags> {
 
   if let Some(m) = matches.subcommand_matches("run") {
     run_parse(&mut flags, m);
+  } else if let Some(m) = matches.subcommand_matches("bench") {
+    bench_parse(&mut flags, m);
   } else if let Some(m) = matches.subcommand_matches("fmt") {
     fmt_parse(&mut flags, m);
   } else if let Some(m) = matches.subcommand_matches("types") {
     types_parse(&mut flags, m);
   } else if let Some(m) = matches.subcommand_matches("cache") {
     cache_parse(&mut flags, m);
+  } else if let Some(m) = matches.subcommand_matches("check") {
+    check_parse(&mut flags, m);
   } else if let Some(m) = matches.subcommand_matches("info") {
     info_parse(&mut flags, m);
   } else if let Some(m) = matches.subcommand_matches("eval") {
@@ -312,10 +423,16 @@ pub fn flags_from_vec(args: Vec<String>) -> clap::Result<Flags> {
     install_parse(&mut flags, m);
   } else if let Some(m) = matches.subcommand_matches("completions") {
     completions_parse(&mut flags, m);
+  } else if let Some(m) = matches.subcommand_matches("task") {
+    task_parse(&mut flags, m);
   } else if let Some(m) = matches.subcommand_matches("test") {
     test_parse(&mut flags, m);
+  } else if let Some(m) = matches.subcommand_matches("uninstall") {
+    uninstall_parse(&mut flags, m);
   } else if let Some(m) = matches.subcommand_matches("upgrade") {
     upgrade_parse(&mut flags, m);
+  } else if let Some(m) = matches.subcommand_matches("vendor") {
+    vendor_parse(&mut flags, m);
   } else if let Some(m) = matches.subcommand_matches("doc") {
     doc_parse(&mut flags, m);
   } else if let Some(m) = matches.subcommand_matches("lint") {
@@ -371,8 +488,10 @@ If the flag is set, restrict these messages to errors.",
         )
         .global(true),
     )
+    .subcommand(bench_subcommand())
     .subcommand(bundle_subcommand())
     .subcommand(cache_subcommand())
+    .subcommand(check_subcommand())
     .subcommand(compile_subcommand())
     .subcommand(completions_subcommand())
     .subcommand(doc_subcommand())
@@ -384,9 +503,12 @@ If the flag is set, restrict these messages to errors.",
     .subcommand(lint_subcommand())
     .subcommand(repl_subcommand())
     .subcommand(run_subcommand())
+    .subcommand(task_subcommand())
     .subcommand(test_subcommand())
     .subcommand(types_subcommand())
+    .subcommand(uninstall_subcommand())
     .subcommand(upgrade_subcommand())
+    .subcommand(vendor_subcommand())
     .long_about(DENO_HELP)
     .after_help(ENV_VARIABLES_HELP)
 }
@@ -396,7 +518,8 @@ fn types_parse(flags: &mut Flags, _matches: &clap::ArgMatches) {
 }
 
 fn fmt_parse(flags: &mut Flags, matches: &clap::ArgMatches) {
-  flags.watch = matches.is_present("watch");
+  config_arg_parse(flags, matches);
+  watch_arg_parse(flags, matches);
   let files = match matches.values_of("files") {
     Some(f) => f.map(PathBuf::from).collect(),
     None => vec![],
@@ -406,17 +529,38 @@ fn fmt_parse(flags: &mut Flags, matches: &clap::ArgMatches) {
     None => vec![],
   };
   let ext = matches.value_of("ext").unwrap().to_string();
+  // A boolean option without a value, e.g. `--use-tabs`, is true.
+  let bool_option = |name| {
+    if matches.is_present(name) {
+      Some(matches.value_of(name).map_or(true, |value| value == "true"))
+    } else {
+      None
+    }
+  };
+  let options = FmtOptions {
+    line_width: matches
+      .value_of("line-width")
+      .map(|value| value.parse().unwrap()),
+    indent_width: matches
+      .value_of("indent-width")
+      .map(|value| value.parse().unwrap()),
+    use_tabs: bool_option("use-tabs"),
+    single_quote: bool_option("single-quote"),
+    prose_wrap: matches.value_of("prose-wrap").map(ToOwned::to_owned),
+  };
 
   flags.subcommand = DenoSubcommand::Fmt {
     check: matches.is_present("check"),
     ext,
     files,
     ignore,
+    options,
   }
 }
 
 fn install_parse(flags: &mut Flags, matches: &clap::ArgMatches) {
   runtime_args_parse(flags, matches, true, true);
+  env_file_arg_parse(flags, matches);
 
   let root = if matches.is_present("root") {
     let install_root = matches.value_of("root").unwrap();
@@ -426,15 +570,20 @@ fn install_parse(flags: &mut Flags, matches: &clap::ArgMatches) {
   };
 
   let force = matches.is_present("force");
+  let list = matches.is_present("list");
   let name = matches.value_of("name").map(|s| s.to_string());
-  let cmd_values = matches.values_of("cmd").unwrap();
   let mut cmd = vec![];
-  for value in cmd_values {
-    cmd.push(value.to_string());
+  if let Some(cmd_values) = matches.values_of("cmd") {
+    for value in cmd_values {
+      cmd.push(value.to_string());
+    }
   }
 
-  let module_url = cmd[0].to_string();
-  let args = cmd[1..].to_vec();
+  // `--list` takes no module.
+  let (module_url, args) = match cmd.split_first() {
+    Some((module_url, args)) => (module_url.to_string(), args.to_vec()),
+    None => ("".to_string(), vec![]),
+  };
 
   flags.subcommand = DenoSubcommand::Install {
     name,
@@ -442,9 +591,16 @@ fn install_parse(flags: &mut Flags, matches: &clap::ArgMatches) {
     args,
     root,
     force,
+    list,
   };
 }
 
+fn uninstall_parse(flags: &mut Flags, matches: &clap::ArgMatches) {
+  let name = matches.value_of("name").unwrap().to_string();
+  let root = matches.value_of("root").map(PathBuf::from);
+  flags.subcommand = DenoSubcommand::Uninstall { name, root };
+}
+
 fn compile_parse(flags: &mut Flags, matches: &clap::ArgMatches) {
   runtime_args_parse(flags, matches, true, false);
 
@@ -459,6 +615,10 @@ fn compile_parse(flags: &mut Flags, matches: &clap::ArgMatches) {
   let output = matches.value_of("output").map(PathBuf::from);
   let lite = matches.is_present("lite");
   let target = matches.value_of("target").map(String::from);
+  let include = match matches.values_of("include") {
+    Some(f) => f.map(PathBuf::from).collect(),
+    None => vec![],
+  };
 
   flags.subcommand = DenoSubcommand::Compile {
     source_file,
@@ -466,6 +626,43 @@ fn compile_parse(flags: &mut Flags, matches: &clap::ArgMatches) {
     args,
     lite,
     target,
+    include,
+  };
+}
+
+fn bench_parse(flags: &mut Flags, matches: &clap::ArgMatches) {
+  runtime_args_parse(flags, matches, true, false);
+
+  let filter = matches.value_of("filter").map(String::from);
+  let json = matches.is_present("json");
+
+  if matches.is_present("script_arg") {
+    let script_arg: Vec<String> = matches
+      .values_of("script_arg")
+      .unwrap()
+      .map(String::from)
+      .collect();
+
+    for v in script_arg {
+      flags.argv.push(v);
+    }
+  }
+
+  let include = if matches.is_present("files") {
+    let files: Vec<String> = matches
+      .values_of("files")
+      .unwrap()
+      .map(String::from)
+      .collect();
+    Some(files)
+  } else {
+    None
+  };
+
+  flags.subcommand = DenoSubcommand::Bench {
+    include,
+    filter,
+    json,
   };
 }
 
@@ -481,7 +678,7 @@ fn bundle_parse(flags: &mut Flags, matches: &clap::ArgMatches) {
     None
   };
 
-  flags.watch = matches.is_present("watch");
+  watch_arg_parse(flags, matches);
 
   flags.subcommand = DenoSubcommand::Bundle {
     source_file,
@@ -506,7 +703,12 @@ fn completions_parse(flags: &mut Flags, matches: &clap::ArgMatches) {
 fn repl_parse(flags: &mut Flags, matches: &clap::ArgMatches) {
   runtime_args_parse(flags, matches, false, true);
   flags.repl = true;
-  flags.subcommand = DenoSubcommand::Repl;
+  flags.subcommand = DenoSubcommand::Repl {
+    eval: matches.value_of("eval").map(ToOwned::to_owned),
+    eval_files: matches
+      .values_of("eval-file")
+      .map(|values| values.map(String::from).collect()),
+  };
   flags.allow_net = Some(vec![]);
   flags.allow_env = true;
   flags.allow_run = true;
@@ -525,7 +727,10 @@ fn eval_parse(flags: &mut Flags, matches: &clap::ArgMatches) {
   flags.allow_write = Some(vec![]);
   flags.allow_plugin = true;
   flags.allow_hrtime = true;
-  let as_typescript = matches.is_present("ts");
+  ext_arg_parse(flags, matches);
+  if matches.is_present("ts") {
+    flags.ext = Some("ts".to_string());
+  }
   let print = matches.is_present("print");
   let mut code: Vec<String> = matches
     .values_of("code_arg")
@@ -538,11 +743,7 @@ fn eval_parse(flags: &mut Flags, matches: &clap::ArgMatches) {
   for v in code_args {
     flags.argv.push(v);
   }
-  flags.subcommand = DenoSubcommand::Eval {
-    print,
-    code,
-    as_typescript,
-  };
+  flags.subcommand = DenoSubcommand::Eval { print, code };
 }
 
 fn info_parse(flags: &mut Flags, matches: &clap::ArgMatches) {
@@ -553,17 +754,65 @@ fn info_parse(flags: &mut Flags, matches: &clap::ArgMatches) {
   flags.subcommand = DenoSubcommand::Info {
     file: matches.value_of("file").map(|f| f.to_string()),
     json,
+    reverse: matches.value_of("reverse").map(|f| f.to_string()),
+    cache: matches.is_present("cache"),
   };
 }
 
 fn cache_parse(flags: &mut Flags, matches: &clap::ArgMatches) {
   compile_args_parse(flags, matches);
+  cached_only_arg_parse(flags, matches);
+  let files = matches
+    .values_of("file")
+    .map(|files| files.map(String::from).collect())
+    .unwrap_or_default();
+  let prune = if matches.is_present("prune") {
+    Some(match matches.value_of("prune") {
+      Some(age) => parse_age(age).unwrap(),
+      None => DEFAULT_PRUNE_AGE,
+    })
+  } else {
+    None
+  };
+  flags.subcommand = DenoSubcommand::Cache { files, prune };
+}
+
+/// How long cached remote modules can go unused before `deno cache --prune`
+/// removes them, unless an age is given.
+const DEFAULT_PRUNE_AGE: Duration = Duration::from_secs(30 * 24 * 60 * 60);
+
+/// Parses an age like "30d", "12h", "90m" or "45s". A number without a unit
+/// is a number of days.
+fn parse_age(age: &str) -> Result<Duration, String> {
+  let (value, unit_secs) = match age.chars().last() {
+    Some('d') => (&age[..age.len() - 1], 24 * 60 * 60),
+    Some('h') => (&age[..age.len() - 1], 60 * 60),
+    Some('m') => (&age[..age.len() - 1], 60),
+    Some('s') => (&age[..age.len() - 1], 1),
+    _ => (age, 24 * 60 * 60),
+  };
+  match value.parse::<u64>() {
+    Ok(value) => Ok(Duration::from_secs(value.saturating_mul(unit_secs))),
+    Err(_) => Err(format!(
+      "Invalid age \"{}\", expected e.g. \"30d\", \"12h\", \"90m\" or \"45s\"",
+      age
+    )),
+  }
+}
+
+fn check_parse(flags: &mut Flags, matches: &clap::ArgMatches) {
+  compile_args_without_no_check_parse(flags, matches);
   let files = matches
     .values_of("file")
     .unwrap()
     .map(String::from)
     .collect();
-  flags.subcommand = DenoSubcommand::Cache { files };
+  flags.type_check_mode = if matches.is_present("remote") {
+    TypeCheckMode::All
+  } else {
+    TypeCheckMode::Local
+  };
+  flags.subcommand = DenoSubcommand::Check { files };
 }
 
 fn lock_args_parse(flags: &mut Flags, matches: &clap::ArgMatches) {
@@ -574,25 +823,41 @@ fn lock_args_parse(flags: &mut Flags, matches: &clap::ArgMatches) {
   if matches.is_present("lock-write") {
     flags.lock_write = true;
   }
+  if matches.is_present("frozen-lockfile") {
+    flags.frozen_lockfile = true;
+  }
 }
 
 fn compile_args<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+  compile_args_without_no_check(app).arg(no_check_arg())
+}
+
+fn compile_args_without_no_check<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
   app
     .arg(import_map_arg())
     .arg(no_remote_arg())
+    .arg(compat_arg())
     .arg(config_arg())
-    .arg(no_check_arg())
     .arg(reload_arg())
     .arg(lock_arg())
     .arg(lock_write_arg())
+    .arg(frozen_lockfile_arg())
     .arg(ca_file_arg())
 }
 
 fn compile_args_parse(flags: &mut Flags, matches: &clap::ArgMatches) {
+  compile_args_without_no_check_parse(flags, matches);
+  no_check_arg_parse(flags, matches);
+}
+
+fn compile_args_without_no_check_parse(
+  flags: &mut Flags,
+  matches: &clap::ArgMatches,
+) {
   import_map_arg_parse(flags, matches);
   no_remote_arg_parse(flags, matches);
+  compat_arg_parse(flags, matches);
   config_arg_parse(flags, matches);
-  no_check_arg_parse(flags, matches);
   reload_arg_parse(flags, matches);
   lock_args_parse(flags, matches);
   ca_file_arg_parse(flags, matches);
@@ -643,6 +908,9 @@ fn runtime_args_parse(
 
 fn run_parse(flags: &mut Flags, matches: &clap::ArgMatches) {
   runtime_args_parse(flags, matches, true, true);
+  check_arg_parse(flags, matches);
+  env_file_arg_parse(flags, matches);
+  ext_arg_parse(flags, matches);
 
   let mut script: Vec<String> = matches
     .values_of("script_arg")
@@ -656,18 +924,71 @@ fn run_parse(flags: &mut Flags, matches: &clap::ArgMatches) {
     flags.argv.push(v);
   }
 
-  flags.watch = matches.is_present("watch");
+  watch_arg_parse(flags, matches);
   flags.subcommand = DenoSubcommand::Run { script };
 }
 
+fn task_parse(flags: &mut Flags, matches: &clap::ArgMatches) {
+  config_arg_parse(flags, matches);
+
+  let mut task_args: Vec<String> = match matches.values_of("task") {
+    Some(values) => values.map(String::from).collect(),
+    None => vec![],
+  };
+  let task = if task_args.is_empty() {
+    None
+  } else {
+    Some(task_args.remove(0))
+  };
+
+  flags.subcommand = DenoSubcommand::Task {
+    task,
+    args: task_args,
+  };
+}
+
 fn test_parse(flags: &mut Flags, matches: &clap::ArgMatches) {
   runtime_args_parse(flags, matches, true, true);
+  check_arg_parse(flags, matches);
+  env_file_arg_parse(flags, matches);
 
   let no_run = matches.is_present("no-run");
   let fail_fast = matches.is_present("fail-fast");
   let allow_none = matches.is_present("allow-none");
   let quiet = matches.is_present("quiet");
   let filter = matches.value_of("filter").map(String::from);
+  let skip = matches
+    .values_of("skip")
+    .map_or(vec![], |values| values.map(String::from).collect());
+  let shuffle = if matches.is_present("shuffle") {
+    if let Some(value) = matches.value_of("shuffle") {
+      Some(value.parse().unwrap())
+    } else {
+      Some(rand::random())
+    }
+  } else {
+    None
+  };
+  let concurrent_jobs = if matches.is_present("jobs") {
+    if let Some(value) = matches.value_of("jobs") {
+      value.parse().unwrap()
+    } else {
+      num_cpus::get()
+    }
+  } else {
+    1
+  };
+
+  let lcov = matches.value_of("lcov").map(PathBuf::from);
+  let html = matches.value_of("html").map(PathBuf::from);
+  let reporter = match matches.value_of("reporter") {
+    Some("dot") => TestReporterKind::Dot,
+    Some("junit") => TestReporterKind::Junit,
+    Some("tap") => TestReporterKind::Tap,
+    _ => TestReporterKind::Pretty,
+  };
+  let output = matches.value_of("output").map(PathBuf::from);
+  watch_arg_parse(flags, matches);
 
   flags.coverage_dir = if matches.is_present("coverage") {
     if let Some(coverage_dir) = matches.value_of("coverage") {
@@ -716,6 +1037,13 @@ fn test_parse(flags: &mut Flags, matches: &clap::ArgMatches) {
     include,
     filter,
     allow_none,
+    skip,
+    shuffle,
+    concurrent_jobs,
+    lcov,
+    html,
+    reporter,
+    output,
   };
 }
 
@@ -743,6 +1071,21 @@ fn upgrade_parse(flags: &mut Flags, matches: &clap::ArgMatches) {
   };
 }
 
+fn vendor_parse(flags: &mut Flags, matches: &clap::ArgMatches) {
+  import_map_arg_parse(flags, matches);
+  reload_arg_parse(flags, matches);
+  lock_args_parse(flags, matches);
+  ca_file_arg_parse(flags, matches);
+
+  let entrypoints = matches
+    .values_of("entrypoints")
+    .unwrap()
+    .map(String::from)
+    .collect();
+  let force = matches.is_present("force");
+  flags.subcommand = DenoSubcommand::Vendor { entrypoints, force };
+}
+
 fn doc_parse(flags: &mut Flags, matches: &clap::ArgMatches) {
   import_map_arg_parse(flags, matches);
   reload_arg_parse(flags, matches);
@@ -774,11 +1117,13 @@ fn lint_parse(flags: &mut Flags, matches: &clap::ArgMatches) {
   };
   let rules = matches.is_present("rules");
   let json = matches.is_present("json");
+  let fix = matches.is_present("fix");
   flags.subcommand = DenoSubcommand::Lint {
     files,
     rules,
     ignore,
     json,
+    fix,
   };
 }
 
@@ -809,8 +1154,30 @@ Ignore formatting code by preceding it with an ignore comment:
   // deno-fmt-ignore
 
 Ignore formatting a file by adding an ignore comment at the top of the file:
-  // deno-fmt-ignore-file",
+  // deno-fmt-ignore-file
+
+The formatting options and the files to format can be set in the \"fmt\"
+object of the configuration file, which is the one passed with --config, or
+else the first deno.json or deno.jsonc found in the current directory or its
+parents. Options passed on the command line take precedence.
+Example:
+  {
+    \"fmt\": {
+      \"lineWidth\": 100,
+      \"indentWidth\": 4,
+      \"useTabs\": false,
+      \"singleQuote\": true,
+      \"proseWrap\": \"preserve\",
+      \"include\": [\"src/\"],
+      \"exclude\": [\"src/**/*.generated.ts\"]
+    }
+  }
+
+The globs in \"include\" and \"exclude\" are relative to the configuration
+file. Files passed on the command line are formatted instead of the included
+ones, and --ignore replaces \"exclude\".",
     )
+    .arg(config_arg())
     .arg(
       Arg::with_name("check")
         .long("check")
@@ -825,6 +1192,60 @@ Ignore formatting a file by adding an ignore comment at the top of the file:
         .default_value("ts")
         .possible_values(&["ts", "tsx", "js", "jsx", "md", "json", "jsonc"]),
     )
+    .arg(
+      Arg::with_name("line-width")
+        .long("line-width")
+        .takes_value(true)
+        .value_name("n")
+        .validator(|value| {
+          value
+            .parse::<u32>()
+            .map(|_| ())
+            .map_err(|_| "line width must be a positive integer".to_string())
+        })
+        .help("Define maximum line width. Defaults to 80"),
+    )
+    .arg(
+      Arg::with_name("indent-width")
+        .long("indent-width")
+        .takes_value(true)
+        .value_name("n")
+        .validator(|value| {
+          value.parse::<u8>().map(|_| ()).map_err(|_| {
+            "indent width must be an integer up to 255".to_string()
+          })
+        })
+        .help("Define indentation width. Defaults to 2"),
+    )
+    .arg(
+      Arg::with_name("use-tabs")
+        .long("use-tabs")
+        .min_values(0)
+        .max_values(1)
+        .takes_value(true)
+        .require_equals(true)
+        .possible_values(&["true", "false"])
+        .help("Use tabs instead of spaces for indentation"),
+    )
+    .arg(
+      Arg::with_name("single-quote")
+        .long("single-quote")
+        .min_values(0)
+        .max_values(1)
+        .takes_value(true)
+        .require_equals(true)
+        .possible_values(&["true", "false"])
+        .help("Use single quotes instead of double quotes"),
+    )
+    .arg(
+      Arg::with_name("prose-wrap")
+        .long("prose-wrap")
+        .takes_value(true)
+        .possible_values(&["always", "never", "preserve"])
+        .help(
+          "Define how prose in markdown files is wrapped. Defaults to always",
+        ),
+    )
     .arg(
       Arg::with_name("ignore")
         .long("ignore")
@@ -840,11 +1261,44 @@ Ignore formatting a file by adding an ignore comment at the top of the file:
         .required(false),
     )
     .arg(watch_arg())
+    .arg(watch_exclude_arg())
+    .arg(no_clear_screen_arg())
 }
 
 fn repl_subcommand<'a, 'b>() -> App<'a, 'b> {
   runtime_args(SubCommand::with_name("repl"), false, true)
+    .arg(
+      Arg::with_name("eval-file")
+        .long("eval-file")
+        .min_values(1)
+        .takes_value(true)
+        .use_delimiter(true)
+        .require_equals(true)
+        .value_name("PATHS")
+        .help(
+          "Evaluate the given files or URLs as scripts when the REPL starts",
+        ),
+    )
+    .arg(
+      Arg::with_name("eval")
+        .long("eval")
+        .takes_value(true)
+        .value_name("code")
+        .help("Evaluate the given code when the REPL starts"),
+    )
     .about("Read Eval Print Loop")
+    .long_about(
+      "Starts a read-eval-print-loop, which lets you interactively build up
+program state in the global context.
+
+Code evaluated with --eval-file and --eval runs before the first prompt, in
+that order; errors are reported but don't stop the REPL:
+  deno repl --eval-file=setup.js,https://example.com/utils.js --eval \"init()\"
+
+The history of the REPL is saved to $DENO_DIR/history, or to the file set with
+the DENO_REPL_HISTORY environment variable. Set it to an empty value to disable
+the history.",
+    )
 }
 
 fn install_subcommand<'a, 'b>() -> App<'a, 'b> {
@@ -852,7 +1306,7 @@ fn install_subcommand<'a, 'b>() -> App<'a, 'b> {
         .setting(AppSettings::TrailingVarArg)
         .arg(
           Arg::with_name("cmd")
-            .required(true)
+            .required_unless("list")
             .multiple(true)
             .allow_hyphen_values(true))
         .arg(
@@ -874,6 +1328,13 @@ fn install_subcommand<'a, 'b>() -> App<'a, 'b> {
             .short("f")
             .help("Forcefully overwrite existing installation")
             .takes_value(false))
+        .arg(
+          Arg::with_name("list")
+            .long("list")
+            .help("List the installed executables")
+            .conflicts_with_all(&["cmd", "name", "force"])
+            .takes_value(false))
+        .arg(env_file_arg())
         .about("Install script as an executable")
         .long_about(
 "Installs a script as an executable in the installation root's bin directory.
@@ -899,7 +1360,40 @@ The installation root is determined, in order of precedence:
   - DENO_INSTALL_ROOT environment variable
   - $HOME/.deno
 
-These must be added to the path manually if required.")
+These must be added to the path manually if required.
+
+The files passed to --import-map, --config, --lock and --env-file are copied
+next to the executable, so that it runs the same way from any directory.
+Relative specifiers of the import map are resolved against its original
+location.
+
+To list the installed executables, use --list:
+  deno install --list
+
+To remove an executable, use 'deno uninstall'.")
+}
+
+fn uninstall_subcommand<'a, 'b>() -> App<'a, 'b> {
+  SubCommand::with_name("uninstall")
+    .arg(
+      Arg::with_name("name")
+        .required(true)
+        .help("Executable file name"),
+    )
+    .arg(
+      Arg::with_name("root")
+        .long("root")
+        .help("Installation root")
+        .takes_value(true),
+    )
+    .about("Uninstall a script previously installed with deno install")
+    .long_about(
+      "Removes an executable installed with 'deno install' from the installation
+root's bin directory, along with the files copied next to it.
+  deno uninstall file_server
+
+The installation root is determined as for 'deno install'.",
+    )
 }
 
 fn compile_subcommand<'a, 'b>() -> App<'a, 'b> {
@@ -920,13 +1414,21 @@ fn compile_subcommand<'a, 'b>() -> App<'a, 'b> {
         .long("target")
         .help("Target OS architecture")
         .takes_value(true)
-        .possible_values(&["x86_64-unknown-linux-gnu", "x86_64-pc-windows-msvc", "x86_64-apple-darwin", "aarch64-apple-darwin"])
+        .possible_values(SUPPORTED_TARGETS)
     )
     .arg(
       Arg::with_name("lite")
         .long("lite")
         .help("Use lite runtime")
     )
+    .arg(
+      Arg::with_name("include")
+        .long("include")
+        .help("Embed a file or directory into the executable")
+        .takes_value(true)
+        .multiple(true)
+        .number_of_values(1)
+    )
     .about("Compile the script into a self contained executable")
     .long_about(
       "Compiles the given script into a self contained executable.
@@ -952,10 +1454,59 @@ aarch64-apple-darwin target is not supported in canary.
 
 It is possible to use \"lite\" binaries when compiling by passing `--lite` flag; these are stripped down versions
 of the deno binary that do not contain built-in tooling (eg. formatter, linter). This feature is experimental.
+
+Files and directories passed to `--include` are embedded into the executable under their paths relative to the
+current directory. The executable reads them with `Deno.open()`, `Deno.readFile()`, etc. using those relative
+paths, without needing read permissions:
+  deno compile --unstable --include templates --include config.json server.ts
 ",
     )
 }
 
+fn bench_subcommand<'a, 'b>() -> App<'a, 'b> {
+  runtime_args(SubCommand::with_name("bench"), true, false)
+    .setting(AppSettings::TrailingVarArg)
+    .arg(
+      Arg::with_name("filter")
+        .set(ArgSettings::AllowLeadingHyphen)
+        .long("filter")
+        .takes_value(true)
+        .help("Run benchmarks with this string or pattern in the name")
+        .long_help(
+          "Run benchmarks with this string in the name. Patterns have the same
+syntax as for 'deno test --filter'.",
+        ),
+    )
+    .arg(
+      Arg::with_name("json")
+        .long("json")
+        .help("Output the results in JSON format")
+        .takes_value(false),
+    )
+    .arg(
+      Arg::with_name("files")
+        .help("List of file names to run")
+        .takes_value(true)
+        .multiple(true),
+    )
+    .arg(script_arg().last(true))
+    .about("Run benchmarks")
+    .long_about(
+      "Run benchmarks using Deno's built-in bench runner.
+
+Evaluate the given modules, run all benchmarks declared with 'Deno.bench()'
+and report their results to standard output:
+  deno bench --unstable src/fetch_bench.ts src/signal_bench.ts
+
+Every benchmark is warmed up first and then run until enough time has been
+spent measuring it. Pass --allow-hrtime for precise timings.
+
+Directory arguments are expanded to all contained files matching the glob
+{*_,*.,}bench.{js,mjs,ts,jsx,tsx}:
+  deno bench --unstable src/",
+    )
+}
+
 fn bundle_subcommand<'a, 'b>() -> App<'a, 'b> {
   compile_args(SubCommand::with_name("bundle"))
     .arg(
@@ -965,6 +1516,8 @@ fn bundle_subcommand<'a, 'b>() -> App<'a, 'b> {
     )
     .arg(Arg::with_name("out_file").takes_value(true).required(false))
     .arg(watch_arg())
+    .arg(watch_exclude_arg())
+    .arg(no_clear_screen_arg())
     .about("Bundle module and dependencies into single file")
     .long_about(
       "Output a single JavaScript file with all dependencies.
@@ -999,7 +1552,7 @@ fn eval_subcommand<'a, 'b>() -> App<'a, 'b> {
   deno eval \"console.log('hello world')\"
 
 To evaluate as TypeScript:
-  deno eval -T \"const v: string = 'hello'; console.log(v)\"
+  deno eval --ext=ts \"const v: string = 'hello'; console.log(v)\"
 
 This command has implicit access to all permissions (--allow-all).",
     )
@@ -1007,10 +1560,12 @@ This command has implicit access to all permissions (--allow-all).",
       Arg::with_name("ts")
         .long("ts")
         .short("T")
-        .help("Treat eval input as TypeScript")
+        .help("Treat eval input as TypeScript, like --ext=ts")
         .takes_value(false)
-        .multiple(false),
+        .multiple(false)
+        .conflicts_with("ext"),
     )
+    .arg(ext_arg().help("Set the content type of the eval input [default: js]"))
     .arg(
       Arg::with_name("print")
         .long("print")
@@ -1044,12 +1599,20 @@ type: JavaScript, TypeScript, or JSON.
 compiled: Local path of compiled source code. (TypeScript only.)
 map: Local path of source map. (TypeScript only.)
 deps: Dependency tree of the source file.
+total: Size of the source and emitted code of the unique modules.
+
+Find the modules that import a dependency of a module:
+  deno info --reverse https://deno.land/std/path/mod.ts \\
+    https://deno.land/std/http/file_server.ts
 
 Without any additional arguments, 'deno info' shows:
 
 DENO_DIR: Directory containing Deno-managed files.
 Remote modules cache: Subdirectory containing downloaded remote modules.
-TypeScript compiler cache: Subdirectory containing TS compiler output.",
+TypeScript compiler cache: Subdirectory containing TS compiler output.
+
+Show how much space the remote modules of each origin use in the cache:
+  deno info --cache",
     )
     .arg(Arg::with_name("file").takes_value(true).required(false))
     .arg(reload_arg().requires("file"))
@@ -1063,16 +1626,72 @@ TypeScript compiler cache: Subdirectory containing TS compiler output.",
         .help("Outputs the information in JSON format")
         .takes_value(false),
     )
+    .arg(
+      Arg::with_name("reverse")
+        .long("reverse")
+        .value_name("SPECIFIER")
+        .help("Show the modules that directly import the given module")
+        .takes_value(true)
+        .requires("file"),
+    )
+    .arg(
+      Arg::with_name("cache")
+        .long("cache")
+        .help("Show the size of the cached remote modules of each origin")
+        .takes_value(false)
+        .conflicts_with("file"),
+    )
+}
+
+fn check_subcommand<'a, 'b>() -> App<'a, 'b> {
+  compile_args_without_no_check(SubCommand::with_name("check"))
+    .arg(
+      Arg::with_name("remote")
+        .long("remote")
+        .help("Type check remote modules too"),
+    )
+    .arg(
+      Arg::with_name("file")
+        .takes_value(true)
+        .required(true)
+        .min_values(1),
+    )
+    .about("Type check the program")
+    .long_about(
+      "Download and type check a module and all of its static dependencies,
+without running any code:
+  deno check main.ts
+
+Only the type errors of local modules are reported, unless --remote is given.
+The program is exited with a non-zero code if there are any.",
+    )
 }
 
 fn cache_subcommand<'a, 'b>() -> App<'a, 'b> {
   compile_args(SubCommand::with_name("cache"))
+    .arg(cached_only_arg())
     .arg(
       Arg::with_name("file")
         .takes_value(true)
-        .required(true)
+        .required_unless("prune")
         .min_values(1),
     )
+    .arg(
+      Arg::with_name("prune")
+        .long("prune")
+        .min_values(0)
+        .max_values(1)
+        .takes_value(true)
+        .require_equals(true)
+        .value_name("AGE")
+        .validator(|age| parse_age(&age).map(|_| ()))
+        .help("Remove the remote modules that weren't used recently")
+        .long_help(
+          "Remove the cached remote modules that weren't downloaded or used for
+longer than AGE (default: 30d), e.g. 12h or 90m. Modules in the lock file
+are kept.",
+        ),
+    )
     .about("Cache the dependencies")
     .long_about(
       "Cache and compile remote dependencies recursively.
@@ -1082,7 +1701,15 @@ in the local cache, without running any code:
   deno cache https://deno.land/std/http/file_server.ts
 
 Future runs of this module will trigger no downloads or compilation unless
---reload is specified.",
+--reload is specified.
+
+Remove the remote modules that weren't used in the last 30 days from the cache,
+except the ones in the lock file:
+  deno cache --prune --lock=lock.json
+
+Only download the modules of an origin again, requiring everything else to come
+from the cache:
+  deno cache --cached-only --reload=https://deno.land/std main.ts",
     )
 }
 
@@ -1132,6 +1759,42 @@ update to a different location, use the --output flag
     .arg(ca_file_arg())
 }
 
+fn vendor_subcommand<'a, 'b>() -> App<'a, 'b> {
+  SubCommand::with_name("vendor")
+    .arg(import_map_arg())
+    .arg(reload_arg())
+    .arg(lock_arg())
+    .arg(lock_write_arg())
+    .arg(frozen_lockfile_arg())
+    .arg(ca_file_arg())
+    .arg(
+      Arg::with_name("force")
+        .long("force")
+        .short("f")
+        .help("Overwrite the vendor directory if it already exists"),
+    )
+    .arg(
+      Arg::with_name("entrypoints")
+        .takes_value(true)
+        .multiple(true)
+        .required(true),
+    )
+    .about("Vendor remote modules into a local directory")
+    .long_about(
+      "Vendor the remote modules of a module graph into a local directory.
+
+Copies every remote module that the entrypoints import, statically or with
+dynamic imports of a string literal, into the 'vendor' directory of the
+current directory, along with an import map that resolves the original
+specifiers to the copies:
+  deno vendor --unstable main.ts test.ts
+  deno run --unstable --no-remote --import-map vendor/import_map.json main.ts
+
+The mappings of an import map passed with --import-map are carried over. An
+existing vendor directory is only replaced with --force.",
+    )
+}
+
 fn doc_subcommand<'a, 'b>() -> App<'a, 'b> {
   SubCommand::with_name("doc")
     .about("Show documentation for a module")
@@ -1150,6 +1813,9 @@ Output documentation in JSON format:
 Target a specific symbol:
     deno doc ./path/to/module.ts MyClass.someField
 
+Output a specific symbol, and the symbols it references, in JSON format:
+    deno doc --json ./path/to/module.ts myFunction
+
 Show documentation for runtime built-ins:
     deno doc
     deno doc --builtin Deno.Listener",
@@ -1179,7 +1845,6 @@ Show documentation for runtime built-ins:
         .help("Dot separated path to symbol")
         .takes_value(true)
         .required(false)
-        .conflicts_with("json")
         .conflicts_with("pretty"),
     )
 }
@@ -1206,6 +1871,9 @@ fn lint_subcommand<'a, 'b>() -> App<'a, 'b> {
 Print result as JSON:
   deno lint --unstable --json
 
+Fix the problems that have an automatic fix, in place:
+  deno lint --unstable --fix
+
 Read from stdin:
   cat file.ts | deno lint --unstable -
   cat file.ts | deno lint --unstable --json -
@@ -1245,6 +1913,13 @@ Ignore linting a file by adding an ignore comment at the top of the file:
         .help("Output lint result in JSON format")
         .takes_value(false),
     )
+    .arg(
+      Arg::with_name("fix")
+        .long("fix")
+        .help("Fix the problems that have an automatic fix")
+        .conflicts_with("rules")
+        .takes_value(false),
+    )
     .arg(
       Arg::with_name("files")
         .takes_value(true)
@@ -1318,6 +1993,11 @@ fn run_subcommand<'a, 'b>() -> App<'a, 'b> {
         .conflicts_with("inspect")
         .conflicts_with("inspect-brk")
     )
+    .arg(watch_exclude_arg())
+    .arg(no_clear_screen_arg())
+    .arg(check_arg())
+    .arg(env_file_arg())
+    .arg(ext_arg().help("Set the content type of stdin, with '-' as the filename [default: ts]"))
     .setting(AppSettings::TrailingVarArg)
     .arg(
         script_arg()
@@ -1340,14 +2020,58 @@ Grant permission to read from disk and listen to network:
 Grant permission to read allow-listed files from disk:
   deno run --allow-read=/etc https://deno.land/std/http/file_server.ts
 
+Modules are not type checked, unless --check is given:
+  deno run --check main.ts
+
 Deno allows specifying the filename '-' to read the file from stdin.
-  curl https://deno.land/std/examples/welcome.ts | target/debug/deno run -",
+  curl https://deno.land/std/examples/welcome.ts | target/debug/deno run -
+
+Code from stdin is TypeScript, unless --ext is given:
+  echo \"console.log('hello')\" | deno run --ext=js -",
+    )
+}
+
+fn task_subcommand<'a, 'b>() -> App<'a, 'b> {
+  SubCommand::with_name("task")
+    .setting(AppSettings::TrailingVarArg)
+    .arg(config_arg())
+    .arg(
+      Arg::with_name("task")
+        .help("Task to run, followed by extra arguments for it")
+        .multiple(true)
+        .allow_hyphen_values(true),
+    )
+    .about("Run a task defined in the configuration file")
+    .long_about(
+      "Run a task defined in the \"tasks\" object of the configuration file.
+  deno task --unstable build
+  deno task --unstable test --filter parser
+
+Extra arguments are appended to the task's command. Without a task name, the
+available tasks are listed.
+
+The configuration file is the one passed with --config, or else the first
+deno.json or deno.jsonc found in the current directory or its parents.
+Example:
+  {
+    \"tasks\": {
+      \"build\": \"deno bundle mod.ts dist/bundle.js\",
+      \"start\": \"PORT=8080 deno run --allow-net --allow-env server.ts\"
+    }
+  }
+
+Tasks run in the directory of the configuration file, with a built-in shell
+that works the same on every platform. It supports sequences of commands
+with '&&', '||' and ';', quoting, environment variables like $NAME, the
+redirects '>', '>>' and '<', and the commands 'cd', 'echo' and 'exit'.
+Commands named 'deno' run the current deno executable.",
     )
 }
 
 fn test_subcommand<'a, 'b>() -> App<'a, 'b> {
   runtime_args(SubCommand::with_name("test"), true, true)
     .setting(AppSettings::TrailingVarArg)
+    .arg(env_file_arg())
     .arg(
       Arg::with_name("no-run")
         .long("no-run")
@@ -1373,7 +2097,62 @@ fn test_subcommand<'a, 'b>() -> App<'a, 'b> {
         .set(ArgSettings::AllowLeadingHyphen)
         .long("filter")
         .takes_value(true)
-        .help("Run tests with this string or pattern in the test name"),
+        .help("Run tests with this string or pattern in the test name")
+        .long_help(
+          "Run tests with this string in the test name. Use /pattern/ to match
+test names against a regular expression and \"name\" to only match the test
+called exactly name. A leading ! runs all tests except the matching ones.",
+        ),
+    )
+    .arg(
+      Arg::with_name("skip")
+        .set(ArgSettings::AllowLeadingHyphen)
+        .long("skip")
+        .takes_value(true)
+        .multiple(true)
+        .number_of_values(1)
+        .help("Skip tests matching this pattern, same syntax as --filter"),
+    )
+    .arg(
+      Arg::with_name("shuffle")
+        .long("shuffle")
+        .value_name("NUMBER")
+        .min_values(0)
+        .max_values(1)
+        .require_equals(true)
+        .takes_value(true)
+        .requires("unstable")
+        .validator(|val: String| match val.parse::<u64>() {
+          Ok(_) => Ok(()),
+          Err(_) => Err("shuffle should be a number".to_string()),
+        })
+        .help("Shuffle the order in which the tests are run")
+        .long_help(
+          "Shuffle the order in which test modules and the tests in them are run,
+using the given seed. A random seed is used if no value is given; it is printed
+when the tests start so the order can be reproduced.",
+        ),
+    )
+    .arg(
+      Arg::with_name("jobs")
+        .long("jobs")
+        .min_values(0)
+        .max_values(1)
+        .require_equals(true)
+        .takes_value(true)
+        .requires("unstable")
+        .conflicts_with("coverage")
+        .conflicts_with("inspect")
+        .conflicts_with("inspect-brk")
+        .validator(|val: String| match val.parse::<usize>() {
+          Ok(jobs) if jobs > 0 => Ok(()),
+          _ => Err("jobs should be a positive number".to_string()),
+        })
+        .help("Number of test modules to run in parallel")
+        .long_help(
+          "Number of test modules to run in parallel, each in its own isolate.
+Defaults to the number of available CPUs if no value is given.",
+        ),
     )
     .arg(
       Arg::with_name("coverage")
@@ -1387,6 +2166,63 @@ fn test_subcommand<'a, 'b>() -> App<'a, 'b> {
         .conflicts_with("inspect-brk")
         .help("Collect coverage information"),
     )
+    .arg(
+      Arg::with_name("lcov")
+        .long("lcov")
+        .value_name("FILE")
+        .require_equals(true)
+        .takes_value(true)
+        .requires("coverage")
+        .help("Also write the coverage report to FILE in lcov format"),
+    )
+    .arg(
+      Arg::with_name("html")
+        .long("html")
+        .value_name("DIR")
+        .require_equals(true)
+        .takes_value(true)
+        .requires("coverage")
+        .help("Also write the coverage report to DIR as HTML pages"),
+    )
+    .arg(
+      Arg::with_name("reporter")
+        .long("reporter")
+        .value_name("REPORTER")
+        .require_equals(true)
+        .takes_value(true)
+        .possible_values(&["pretty", "dot", "junit", "tap"])
+        .requires("unstable")
+        .conflicts_with("coverage")
+        .conflicts_with("inspect")
+        .conflicts_with("inspect-brk")
+        .help("Format of the test report")
+        .long_help(
+          "Format of the test report: \"pretty\" (the default), \"dot\" for a
+character per test, \"junit\" for JUnit XML or \"tap\" for TAP version 13.",
+        ),
+    )
+    .arg(
+      Arg::with_name("output")
+        .long("output")
+        .value_name("FILE")
+        .require_equals(true)
+        .takes_value(true)
+        .requires("unstable")
+        .conflicts_with("coverage")
+        .conflicts_with("inspect")
+        .conflicts_with("inspect-brk")
+        .help("Write the test report to FILE instead of stdout"),
+    )
+    .arg(
+      watch_arg()
+        .conflicts_with("no-run")
+        .conflicts_with("coverage")
+        .conflicts_with("inspect")
+        .conflicts_with("inspect-brk"),
+    )
+    .arg(watch_exclude_arg())
+    .arg(no_clear_screen_arg())
+    .arg(check_arg())
     .arg(
       Arg::with_name("files")
         .help("List of file names to run")
@@ -1404,7 +2240,10 @@ report results to standard output:
 
 Directory arguments are expanded to all contained files matching the glob
 {*_,*.,}test.{js,mjs,ts,jsx,tsx}:
-  deno test src/",
+  deno test src/
+
+Modules are not type checked, unless --check is given:
+  deno test --check src/",
     )
 }
 
@@ -1437,6 +2276,18 @@ fn lock_write_arg<'a, 'b>() -> Arg<'a, 'b> {
     .help("Write lock file (use with --lock)")
 }
 
+fn frozen_lockfile_arg<'a, 'b>() -> Arg<'a, 'b> {
+  Arg::with_name("frozen-lockfile")
+    .long("frozen-lockfile")
+    .conflicts_with("lock-write")
+    .help("Error out if the lock file is missing a module")
+    .long_help(
+      "Error out if a module is missing from the lock file or does not match it,
+instead of adding it to the lock file. Without --lock, the deno.lock next to
+the deno.json passed with --config is used.",
+    )
+}
+
 fn config_arg<'a, 'b>() -> Arg<'a, 'b> {
   Arg::with_name("config")
     .short("c")
@@ -1450,6 +2301,34 @@ fn config_arg_parse(flags: &mut Flags, matches: &ArgMatches) {
   flags.config_path = matches.value_of("config").map(ToOwned::to_owned);
 }
 
+fn env_file_arg<'a, 'b>() -> Arg<'a, 'b> {
+  Arg::with_name("env-file")
+    .long("env-file")
+    .value_name("FILE")
+    .help("Load environment variables from a KEY=VALUE file")
+    .long_help(
+      "Load environment variables from a file of KEY=VALUE lines. Variables that
+are already set aren't overridden.",
+    )
+    .takes_value(true)
+}
+
+fn env_file_arg_parse(flags: &mut Flags, matches: &ArgMatches) {
+  flags.env_file = matches.value_of("env-file").map(ToOwned::to_owned);
+}
+
+fn ext_arg<'a, 'b>() -> Arg<'a, 'b> {
+  Arg::with_name("ext")
+    .long("ext")
+    .value_name("EXT")
+    .takes_value(true)
+    .possible_values(&["ts", "tsx", "js", "jsx"])
+}
+
+fn ext_arg_parse(flags: &mut Flags, matches: &ArgMatches) {
+  flags.ext = matches.value_of("ext").map(ToOwned::to_owned);
+}
+
 fn ca_file_arg<'a, 'b>() -> Arg<'a, 'b> {
   Arg::with_name("cert")
     .long("cert")
@@ -1556,14 +2435,20 @@ fn reload_arg<'a, 'b>() -> Arg<'a, 'b> {
     .long("reload")
     .help("Reload source code cache (recompile TypeScript)")
     .value_name("CACHE_BLOCKLIST")
+    .validator(|url| match Url::parse(&url) {
+      Ok(_) => Ok(()),
+      Err(err) => Err(format!("Invalid URL \"{}\": {}", url, err)),
+    })
     .long_help(
       "Reload source code cache (recompile TypeScript)
 --reload
   Reload everything
 --reload=https://deno.land/std
-  Reload only standard modules
+  Reload only standard modules, i.e. the URLs under this prefix
 --reload=https://deno.land/std/fs/utils.ts,https://deno.land/std/fmt/colors.ts
-  Reloads specific modules",
+  Reloads specific modules
+--reload=https://deno.land --cached-only
+  Reload the modules of an origin and require all others to be cached",
     )
 }
 
@@ -1621,13 +2506,55 @@ fn watch_arg<'a, 'b>() -> Arg<'a, 'b> {
   Arg::with_name("watch")
     .requires("unstable")
     .long("watch")
+    .min_values(0)
+    .takes_value(true)
+    .use_delimiter(true)
+    .require_equals(true)
+    .value_name("FILES")
     .help("Watch for file changes and restart process automatically")
     .long_help(
       "Watch for file changes and restart process automatically.
-Only local files from entry point module graph are watched.",
+Only local files from entry point module graph are watched, as well as the
+files and directories passed to this flag, e.g. --watch=data.json,static/",
+    )
+}
+
+fn watch_exclude_arg<'a, 'b>() -> Arg<'a, 'b> {
+  Arg::with_name("watch-exclude")
+    .requires("watch")
+    .long("watch-exclude")
+    .takes_value(true)
+    .use_delimiter(true)
+    .require_equals(true)
+    .value_name("GLOBS")
+    .help("Ignore changes to files matching these globs, when watching")
+    .long_help(
+      "Ignore changes to files matching these globs, relative to the current
+directory, when watching. Changes in .git and vendor directories are always
+ignored.",
     )
 }
 
+fn no_clear_screen_arg<'a, 'b>() -> Arg<'a, 'b> {
+  Arg::with_name("no-clear-screen")
+    .requires("watch")
+    .long("no-clear-screen")
+    .help("Don't clear the terminal screen when restarting, when watching")
+}
+
+fn watch_arg_parse(flags: &mut Flags, matches: &clap::ArgMatches) {
+  if matches.is_present("watch") {
+    flags.watch = true;
+    if let Some(paths) = matches.values_of("watch") {
+      flags.watch_paths = paths.map(PathBuf::from).collect();
+    }
+    if let Some(globs) = matches.values_of("watch-exclude") {
+      flags.watch_exclude = globs.map(String::from).collect();
+    }
+    flags.no_clear_screen = matches.is_present("no-clear-screen");
+  }
+}
+
 fn seed_arg<'a, 'b>() -> Arg<'a, 'b> {
   Arg::with_name("seed")
     .long("seed")
@@ -1662,6 +2589,36 @@ fn cached_only_arg_parse(flags: &mut Flags, matches: &ArgMatches) {
   }
 }
 
+fn check_arg<'a, 'b>() -> Arg<'a, 'b> {
+  Arg::with_name("check")
+    .long("check")
+    .min_values(0)
+    .max_values(1)
+    .require_equals(true)
+    .takes_value(true)
+    .possible_values(&["all"])
+    .conflicts_with("no-check")
+    .help("Type check modules before running them")
+    .long_help(
+      "Type check modules before running them. Only the type errors of local
+modules are reported, unless --check=all is given.",
+    )
+}
+
+/// `deno run` and `deno test` only type check with `--check`.
+fn check_arg_parse(flags: &mut Flags, matches: &clap::ArgMatches) {
+  if matches.is_present("check") {
+    flags.no_check = false;
+    flags.type_check_mode = if matches.value_of("check") == Some("all") {
+      TypeCheckMode::All
+    } else {
+      TypeCheckMode::Local
+    };
+  } else {
+    flags.no_check = true;
+  }
+}
+
 fn no_check_arg<'a, 'b>() -> Arg<'a, 'b> {
   Arg::with_name("no-check")
     .long("no-check")
@@ -1686,6 +2643,24 @@ fn no_remote_arg_parse(flags: &mut Flags, matches: &clap::ArgMatches) {
   }
 }
 
+fn compat_arg<'a, 'b>() -> Arg<'a, 'b> {
+  Arg::with_name("compat")
+    .long("compat")
+    .requires("unstable")
+    .help("Resolve bare names of Node built-in modules to their polyfills")
+    .long_help(
+      "Resolve bare names of Node built-in modules, e.g. \"fs\", to the polyfills of
+std/node, like \"node:\" specifiers. The polyfills are fetched from
+DENO_NODE_COMPAT_URL if it is set.",
+    )
+}
+
+fn compat_arg_parse(flags: &mut Flags, matches: &clap::ArgMatches) {
+  if matches.is_present("compat") {
+    flags.compat = true;
+  }
+}
+
 fn permission_args_parse(flags: &mut Flags, matches: &clap::ArgMatches) {
   if let Some(read_wl) = matches.values_of("allow-read") {
     let read_allowlist: Vec<PathBuf> = read_wl.map(PathBuf::from).collect();
@@ -1769,6 +2744,7 @@ mod tests {
         },
         unstable: true,
         log_level: Some(Level::Error),
+        no_check: true,
         ..Flags::default()
       }
     );
@@ -1817,6 +2793,7 @@ mod tests {
           script: "script.ts".to_string(),
         },
         reload: true,
+        no_check: true,
         ..Flags::default()
       }
     );
@@ -1840,9 +2817,48 @@ mod tests {
         },
         watch: true,
         unstable: true,
+        no_check: true,
+        ..Flags::default()
+      }
+    );
+  }
+
+  #[test]
+  fn run_watch_with_options() {
+    let r = flags_from_vec(svec![
+      "deno",
+      "run",
+      "--unstable",
+      "--watch=data.json,static",
+      "--watch-exclude=static/gen/**,*.log",
+      "--no-clear-screen",
+      "script.ts"
+    ]);
+    let flags = r.unwrap();
+    assert_eq!(
+      flags,
+      Flags {
+        subcommand: DenoSubcommand::Run {
+          script: "script.ts".to_string(),
+        },
+        watch: true,
+        watch_paths: vec![PathBuf::from("data.json"), PathBuf::from("static")],
+        watch_exclude: svec!["static/gen/**", "*.log"],
+        no_clear_screen: true,
+        unstable: true,
+        no_check: true,
         ..Flags::default()
       }
     );
+
+    let r = flags_from_vec(svec![
+      "deno",
+      "run",
+      "--unstable",
+      "--no-clear-screen",
+      "script.ts"
+    ]);
+    assert!(r.is_err());
   }
 
   #[test]
@@ -1857,6 +2873,7 @@ mod tests {
           script: "script.ts".to_string(),
         },
         allow_write: Some(vec![]),
+        no_check: true,
         ..Flags::default()
       }
     );
@@ -1872,6 +2889,7 @@ mod tests {
           script: "_".to_string(),
         },
         v8_flags: svec!["--help"],
+        no_check: true,
         ..Flags::default()
       }
     );
@@ -1889,6 +2907,7 @@ mod tests {
           script: "script.ts".to_string(),
         },
         v8_flags: svec!["--expose-gc", "--gc-stats=1"],
+        no_check: true,
         ..Flags::default()
       }
     );
@@ -1912,6 +2931,7 @@ mod tests {
         },
         argv: svec!["--title", "X"],
         allow_net: Some(vec![]),
+        no_check: true,
         ..Flags::default()
       }
     );
@@ -1933,6 +2953,7 @@ mod tests {
         allow_write: Some(vec![]),
         allow_plugin: true,
         allow_hrtime: true,
+        no_check: true,
         ..Flags::default()
       }
     );
@@ -1948,6 +2969,7 @@ mod tests {
           script: "gist.ts".to_string(),
         },
         allow_read: Some(vec![]),
+        no_check: true,
         ..Flags::default()
       }
     );
@@ -1963,6 +2985,7 @@ mod tests {
           script: "gist.ts".to_string(),
         },
         allow_hrtime: true,
+        no_check: true,
         ..Flags::default()
       }
     );
@@ -1990,9 +3013,52 @@ mod tests {
         },
         argv: svec!["--", "-D", "--allow-net"],
         allow_write: Some(vec![]),
+        no_check: true,
+        ..Flags::default()
+      }
+    );
+  }
+
+  #[test]
+  fn fmt_with_options() {
+    let r = flags_from_vec(svec![
+      "deno",
+      "fmt",
+      "--config",
+      "deno.json",
+      "--line-width=100",
+      "--indent-width",
+      "4",
+      "--use-tabs",
+      "--single-quote=false",
+      "--prose-wrap=never",
+      "foo.md"
+    ]);
+    assert_eq!(
+      r.unwrap(),
+      Flags {
+        subcommand: DenoSubcommand::Fmt {
+          ignore: vec![],
+          check: false,
+          files: vec![PathBuf::from("foo.md")],
+          ext: "ts".to_string(),
+          options: FmtOptions {
+            line_width: Some(100),
+            indent_width: Some(4),
+            use_tabs: Some(true),
+            single_quote: Some(false),
+            prose_wrap: Some("never".to_string()),
+          },
+        },
+        config_path: Some("deno.json".to_string()),
         ..Flags::default()
       }
     );
+
+    let r = flags_from_vec(svec!["deno", "fmt", "--line-width=wide"]);
+    assert!(r.is_err());
+    let r = flags_from_vec(svec!["deno", "fmt", "--prose-wrap=sometimes"]);
+    assert!(r.is_err());
   }
 
   #[test]
@@ -2008,7 +3074,8 @@ mod tests {
             PathBuf::from("script_1.ts"),
             PathBuf::from("script_2.ts")
           ],
-          ext: "ts".to_string()
+          ext: "ts".to_string(),
+          options: Default::default(),
         },
         ..Flags::default()
       }
@@ -2023,6 +3090,7 @@ mod tests {
           check: true,
           files: vec![],
           ext: "ts".to_string(),
+          options: Default::default(),
         },
         ..Flags::default()
       }
@@ -2037,6 +3105,7 @@ mod tests {
           check: false,
           files: vec![],
           ext: "ts".to_string(),
+          options: Default::default(),
         },
         ..Flags::default()
       }
@@ -2051,6 +3120,7 @@ mod tests {
           check: false,
           files: vec![],
           ext: "ts".to_string(),
+          options: Default::default(),
         },
         watch: true,
         unstable: true,
@@ -2075,6 +3145,7 @@ mod tests {
           check: true,
           files: vec![PathBuf::from("foo.ts")],
           ext: "ts".to_string(),
+          options: Default::default(),
         },
         watch: true,
         unstable: true,
@@ -2115,6 +3186,7 @@ mod tests {
           rules: false,
           json: false,
           ignore: vec![],
+          fix: false,
         },
         unstable: true,
         ..Flags::default()
@@ -2138,6 +3210,7 @@ mod tests {
             PathBuf::from("script_1.ts"),
             PathBuf::from("script_2.ts")
           ],
+          fix: false,
         },
         unstable: true,
         ..Flags::default()
@@ -2153,6 +3226,7 @@ mod tests {
           rules: true,
           json: false,
           ignore: vec![],
+          fix: false,
         },
         unstable: true,
         ..Flags::default()
@@ -2174,6 +3248,23 @@ mod tests {
           rules: false,
           json: true,
           ignore: vec![],
+          fix: false,
+        },
+        unstable: true,
+        ..Flags::default()
+      }
+    );
+
+    let r = flags_from_vec(svec!["deno", "lint", "--unstable", "--fix"]);
+    assert_eq!(
+      r.unwrap(),
+      Flags {
+        subcommand: DenoSubcommand::Lint {
+          files: vec![],
+          rules: false,
+          json: false,
+          ignore: vec![],
+          fix: true,
         },
         unstable: true,
         ..Flags::default()
@@ -2201,6 +3292,7 @@ mod tests {
       Flags {
         subcommand: DenoSubcommand::Cache {
           files: svec!["script.ts"],
+          prune: None,
         },
         ..Flags::default()
       }
@@ -2208,51 +3300,160 @@ mod tests {
   }
 
   #[test]
-  fn info() {
-    let r = flags_from_vec(svec!["deno", "info", "script.ts"]);
+  fn cache_prune() {
+    let r = flags_from_vec(svec!["deno", "cache", "--prune"]);
     assert_eq!(
       r.unwrap(),
       Flags {
-        subcommand: DenoSubcommand::Info {
-          json: false,
-          file: Some("script.ts".to_string()),
+        subcommand: DenoSubcommand::Cache {
+          files: vec![],
+          prune: Some(Duration::from_secs(30 * 24 * 60 * 60)),
         },
         ..Flags::default()
       }
     );
 
-    let r = flags_from_vec(svec!["deno", "info", "--reload", "script.ts"]);
+    let r = flags_from_vec(svec![
+      "deno",
+      "cache",
+      "--prune=12h",
+      "--lock=lock.json",
+      "script.ts"
+    ]);
     assert_eq!(
       r.unwrap(),
       Flags {
-        subcommand: DenoSubcommand::Info {
-          json: false,
-          file: Some("script.ts".to_string()),
+        subcommand: DenoSubcommand::Cache {
+          files: svec!["script.ts"],
+          prune: Some(Duration::from_secs(12 * 60 * 60)),
         },
-        reload: true,
+        lock: Some(PathBuf::from("lock.json")),
         ..Flags::default()
       }
     );
 
-    let r = flags_from_vec(svec!["deno", "info", "--json", "script.ts"]);
+    let r = flags_from_vec(svec!["deno", "cache", "--prune=7"]);
     assert_eq!(
-      r.unwrap(),
+      r.unwrap().subcommand,
+      DenoSubcommand::Cache {
+        files: vec![],
+        prune: Some(Duration::from_secs(7 * 24 * 60 * 60)),
+      }
+    );
+
+    let r = flags_from_vec(svec!["deno", "cache", "--prune=soon"]);
+    assert!(r.is_err());
+    let r = flags_from_vec(svec!["deno", "cache"]);
+    assert!(r.is_err());
+  }
+
+  #[test]
+  fn check() {
+    let r = flags_from_vec(svec!["deno", "check", "main.ts", "other.ts"]);
+    assert_eq!(
+      r.unwrap(),
+      Flags {
+        subcommand: DenoSubcommand::Check {
+          files: svec!["main.ts", "other.ts"],
+        },
+        type_check_mode: TypeCheckMode::Local,
+        ..Flags::default()
+      }
+    );
+
+    let r = flags_from_vec(svec!["deno", "check", "--remote", "main.ts"]);
+    assert_eq!(
+      r.unwrap(),
+      Flags {
+        subcommand: DenoSubcommand::Check {
+          files: svec!["main.ts"],
+        },
+        type_check_mode: TypeCheckMode::All,
+        ..Flags::default()
+      }
+    );
+
+    let r = flags_from_vec(svec!["deno", "check", "--no-check", "main.ts"]);
+    assert!(r.is_err());
+  }
+
+  #[test]
+  fn info() {
+    let r = flags_from_vec(svec!["deno", "info", "script.ts"]);
+    assert_eq!(
+      r.unwrap(),
+      Flags {
+        subcommand: DenoSubcommand::Info {
+          json: false,
+          file: Some("script.ts".to_string()),
+          reverse: None,
+          cache: false,
+        },
+        ..Flags::default()
+      }
+    );
+
+    let r = flags_from_vec(svec!["deno", "info", "--reload", "script.ts"]);
+    assert_eq!(
+      r.unwrap(),
+      Flags {
+        subcommand: DenoSubcommand::Info {
+          json: false,
+          file: Some("script.ts".to_string()),
+          reverse: None,
+          cache: false,
+        },
+        reload: true,
+        ..Flags::default()
+      }
+    );
+
+    let r = flags_from_vec(svec!["deno", "info", "--json", "script.ts"]);
+    assert_eq!(
+      r.unwrap(),
       Flags {
         subcommand: DenoSubcommand::Info {
           json: true,
           file: Some("script.ts".to_string()),
+          reverse: None,
+          cache: false,
+        },
+        ..Flags::default()
+      }
+    );
+
+    let r = flags_from_vec(svec![
+      "deno",
+      "info",
+      "--reverse",
+      "./dep.ts",
+      "script.ts"
+    ]);
+    assert_eq!(
+      r.unwrap(),
+      Flags {
+        subcommand: DenoSubcommand::Info {
+          json: false,
+          file: Some("script.ts".to_string()),
+          reverse: Some("./dep.ts".to_string()),
+          cache: false,
         },
         ..Flags::default()
       }
     );
 
+    let r = flags_from_vec(svec!["deno", "info", "--reverse", "./dep.ts"]);
+    assert!(r.is_err());
+
     let r = flags_from_vec(svec!["deno", "info"]);
     assert_eq!(
       r.unwrap(),
       Flags {
         subcommand: DenoSubcommand::Info {
           json: false,
-          file: None
+          file: None,
+          reverse: None,
+          cache: false,
         },
         ..Flags::default()
       }
@@ -2264,11 +3465,30 @@ mod tests {
       Flags {
         subcommand: DenoSubcommand::Info {
           json: true,
-          file: None
+          file: None,
+          reverse: None,
+          cache: false,
+        },
+        ..Flags::default()
+      }
+    );
+
+    let r = flags_from_vec(svec!["deno", "info", "--cache", "--json"]);
+    assert_eq!(
+      r.unwrap(),
+      Flags {
+        subcommand: DenoSubcommand::Info {
+          json: true,
+          file: None,
+          reverse: None,
+          cache: true,
         },
         ..Flags::default()
       }
     );
+
+    let r = flags_from_vec(svec!["deno", "info", "--cache", "script.ts"]);
+    assert!(r.is_err());
   }
 
   #[test]
@@ -2282,6 +3502,7 @@ mod tests {
           script: "script.ts".to_string(),
         },
         config_path: Some("tsconfig.json".to_owned()),
+        no_check: true,
         ..Flags::default()
       }
     );
@@ -2296,7 +3517,6 @@ mod tests {
         subcommand: DenoSubcommand::Eval {
           print: false,
           code: "'console.log(\"hello\")'".to_string(),
-          as_typescript: false,
         },
         allow_net: Some(vec![]),
         allow_env: true,
@@ -2319,7 +3539,6 @@ mod tests {
         subcommand: DenoSubcommand::Eval {
           print: true,
           code: "1+2".to_string(),
-          as_typescript: false,
         },
         allow_net: Some(vec![]),
         allow_env: true,
@@ -2343,8 +3562,36 @@ mod tests {
         subcommand: DenoSubcommand::Eval {
           print: false,
           code: "'console.log(\"hello\")'".to_string(),
-          as_typescript: true,
         },
+        ext: Some("ts".to_string()),
+        allow_net: Some(vec![]),
+        allow_env: true,
+        allow_run: true,
+        allow_read: Some(vec![]),
+        allow_write: Some(vec![]),
+        allow_plugin: true,
+        allow_hrtime: true,
+        ..Flags::default()
+      }
+    );
+  }
+
+  #[test]
+  fn eval_ext() {
+    let r = flags_from_vec(svec![
+      "deno",
+      "eval",
+      "--ext=tsx",
+      "console.log(<div />)"
+    ]);
+    assert_eq!(
+      r.unwrap(),
+      Flags {
+        subcommand: DenoSubcommand::Eval {
+          print: false,
+          code: "console.log(<div />)".to_string(),
+        },
+        ext: Some("tsx".to_string()),
         allow_net: Some(vec![]),
         allow_env: true,
         allow_run: true,
@@ -2355,6 +3602,11 @@ mod tests {
         ..Flags::default()
       }
     );
+
+    let r = flags_from_vec(svec!["deno", "eval", "--ext=md", "1"]);
+    assert!(r.is_err());
+    let r = flags_from_vec(svec!["deno", "eval", "-T", "--ext=js", "1"]);
+    assert!(r.is_err());
   }
 
   #[test]
@@ -2367,7 +3619,6 @@ mod tests {
         subcommand: DenoSubcommand::Eval {
           print: false,
           code: "42".to_string(),
-          as_typescript: false,
         },
         unstable: true,
         import_map_path: Some("import_map.json".to_string()),
@@ -2410,7 +3661,6 @@ mod tests {
         subcommand: DenoSubcommand::Eval {
           print: false,
           code: "console.log(Deno.args)".to_string(),
-          as_typescript: false,
         },
         argv: svec!["arg1", "arg2"],
         allow_net: Some(vec![]),
@@ -2432,7 +3682,39 @@ mod tests {
       r.unwrap(),
       Flags {
         repl: true,
-        subcommand: DenoSubcommand::Repl,
+        subcommand: DenoSubcommand::Repl {
+          eval: None,
+          eval_files: None,
+        },
+        allow_net: Some(vec![]),
+        allow_env: true,
+        allow_run: true,
+        allow_read: Some(vec![]),
+        allow_write: Some(vec![]),
+        allow_plugin: true,
+        allow_hrtime: true,
+        ..Flags::default()
+      }
+    );
+  }
+
+  #[test]
+  fn repl_with_eval_flags() {
+    let r = flags_from_vec(svec![
+      "deno",
+      "repl",
+      "--eval-file=./a.js,https://example.com/b.js",
+      "--eval",
+      "console.log('hello');"
+    ]);
+    assert_eq!(
+      r.unwrap(),
+      Flags {
+        repl: true,
+        subcommand: DenoSubcommand::Repl {
+          eval: Some("console.log('hello');".to_string()),
+          eval_files: Some(svec!["./a.js", "https://example.com/b.js"]),
+        },
         allow_net: Some(vec![]),
         allow_env: true,
         allow_run: true,
@@ -2453,7 +3735,10 @@ mod tests {
       r.unwrap(),
       Flags {
         repl: true,
-        subcommand: DenoSubcommand::Repl,
+        subcommand: DenoSubcommand::Repl {
+          eval: None,
+          eval_files: None,
+        },
         unstable: true,
         import_map_path: Some("import_map.json".to_string()),
         no_remote: true,
@@ -2498,6 +3783,7 @@ mod tests {
         subcommand: DenoSubcommand::Run {
           script: "script.ts".to_string(),
         },
+        no_check: true,
         ..Flags::default()
       }
     );
@@ -2521,6 +3807,7 @@ mod tests {
         subcommand: DenoSubcommand::Run {
           script: "script.ts".to_string(),
         },
+        no_check: true,
         ..Flags::default()
       }
     );
@@ -2541,6 +3828,7 @@ mod tests {
           script: "script.ts".to_string(),
         },
         allow_net: Some(svec!["127.0.0.1"]),
+        no_check: true,
         ..Flags::default()
       }
     );
@@ -2699,6 +3987,7 @@ mod tests {
         },
         unstable: true,
         import_map_path: Some("import_map.json".to_owned()),
+        no_check: true,
         ..Flags::default()
       }
     );
@@ -2719,6 +4008,8 @@ mod tests {
         subcommand: DenoSubcommand::Info {
           file: Some("script.ts".to_string()),
           json: false,
+          reverse: None,
+          cache: false,
         },
         unstable: true,
         import_map_path: Some("import_map.json".to_owned()),
@@ -2741,6 +4032,7 @@ mod tests {
       Flags {
         subcommand: DenoSubcommand::Cache {
           files: svec!["script.ts"],
+          prune: None,
         },
         unstable: true,
         import_map_path: Some("import_map.json".to_owned()),
@@ -2783,6 +4075,7 @@ mod tests {
       Flags {
         subcommand: DenoSubcommand::Cache {
           files: svec!["script.ts", "script_two.ts"],
+          prune: None,
         },
         ..Flags::default()
       }
@@ -2800,6 +4093,7 @@ mod tests {
         },
         seed: Some(250_u64),
         v8_flags: svec!["--random-seed=250"],
+        no_check: true,
         ..Flags::default()
       }
     );
@@ -2823,6 +4117,7 @@ mod tests {
         },
         seed: Some(250_u64),
         v8_flags: svec!["--expose-gc", "--random-seed=250"],
+        no_check: true,
         ..Flags::default()
       }
     );
@@ -2844,6 +4139,7 @@ mod tests {
           args: vec![],
           root: None,
           force: false,
+          list: false,
         },
         ..Flags::default()
       }
@@ -2863,6 +4159,7 @@ mod tests {
           args: svec!["foo", "bar"],
           root: Some(PathBuf::from("/foo")),
           force: true,
+          list: false,
         },
         unstable: true,
         import_map_path: Some("import_map.json".to_string()),
@@ -2885,137 +4182,167 @@ mod tests {
   }
 
   #[test]
-  fn log_level() {
-    let r =
-      flags_from_vec(svec!["deno", "run", "--log-level=debug", "script.ts"]);
+  fn install_env_file() {
+    let r = flags_from_vec(svec![
+      "deno",
+      "install",
+      "--env-file",
+      ".env",
+      "https://deno.land/std/examples/colors.ts"
+    ]);
     assert_eq!(
       r.unwrap(),
       Flags {
-        subcommand: DenoSubcommand::Run {
-          script: "script.ts".to_string(),
+        subcommand: DenoSubcommand::Install {
+          name: None,
+          module_url: "https://deno.land/std/examples/colors.ts".to_string(),
+          args: vec![],
+          root: None,
+          force: false,
+          list: false,
         },
-        log_level: Some(Level::Debug),
+        env_file: Some(".env".to_string()),
         ..Flags::default()
       }
     );
   }
 
   #[test]
-  fn quiet() {
-    let r = flags_from_vec(svec!["deno", "run", "-q", "script.ts"]);
+  fn install_list() {
+    let r =
+      flags_from_vec(svec!["deno", "install", "--list", "--root", "/foo"]);
     assert_eq!(
       r.unwrap(),
       Flags {
-        subcommand: DenoSubcommand::Run {
-          script: "script.ts".to_string(),
+        subcommand: DenoSubcommand::Install {
+          name: None,
+          module_url: "".to_string(),
+          args: vec![],
+          root: Some(PathBuf::from("/foo")),
+          force: false,
+          list: true,
         },
-        log_level: Some(Level::Error),
         ..Flags::default()
       }
     );
-  }
-
-  #[test]
-  fn completions() {
-    let r = flags_from_vec(svec!["deno", "completions", "zsh"]).unwrap();
 
-    match r.subcommand {
-      DenoSubcommand::Completions { buf } => assert!(!buf.is_empty()),
-      _ => unreachable!(),
-    }
-  }
-
-  #[test]
-  fn run_with_args() {
     let r = flags_from_vec(svec![
       "deno",
-      "run",
-      "script.ts",
-      "--allow-read",
-      "--allow-net"
+      "install",
+      "--list",
+      "https://deno.land/std/examples/colors.ts"
     ]);
+    assert!(r.is_err());
+    let r = flags_from_vec(svec!["deno", "install"]);
+    assert!(r.is_err());
+  }
+
+  #[test]
+  fn uninstall() {
+    let r = flags_from_vec(svec!["deno", "uninstall", "file_server"]);
     assert_eq!(
       r.unwrap(),
       Flags {
-        subcommand: DenoSubcommand::Run {
-          script: "script.ts".to_string(),
+        subcommand: DenoSubcommand::Uninstall {
+          name: "file_server".to_string(),
+          root: None,
         },
-        argv: svec!["--allow-read", "--allow-net"],
         ..Flags::default()
       }
     );
+
     let r = flags_from_vec(svec![
       "deno",
-      "run",
-      "--location",
-      "https:foo",
-      "--allow-read",
-      "script.ts",
-      "--allow-net",
-      "-r",
-      "--help",
-      "--foo",
-      "bar"
+      "uninstall",
+      "--root",
+      "/foo",
+      "file_server"
     ]);
     assert_eq!(
       r.unwrap(),
       Flags {
-        subcommand: DenoSubcommand::Run {
-          script: "script.ts".to_string(),
+        subcommand: DenoSubcommand::Uninstall {
+          name: "file_server".to_string(),
+          root: Some(PathBuf::from("/foo")),
         },
-        location: Some(Url::parse("https://foo/").unwrap()),
-        allow_read: Some(vec![]),
-        argv: svec!["--allow-net", "-r", "--help", "--foo", "bar"],
         ..Flags::default()
       }
     );
 
-    let r = flags_from_vec(svec!["deno", "run", "script.ts", "foo", "bar"]);
+    let r = flags_from_vec(svec!["deno", "uninstall"]);
+    assert!(r.is_err());
+  }
+
+  #[test]
+  fn run_env_file() {
+    let r =
+      flags_from_vec(svec!["deno", "run", "--env-file=.env", "script.ts"]);
     assert_eq!(
       r.unwrap(),
       Flags {
         subcommand: DenoSubcommand::Run {
           script: "script.ts".to_string(),
         },
-        argv: svec!["foo", "bar"],
+        env_file: Some(".env".to_string()),
+        no_check: true,
         ..Flags::default()
       }
     );
-    let r = flags_from_vec(svec!["deno", "run", "script.ts", "-"]);
+  }
+
+  #[test]
+  fn run_stdin_ext() {
+    let r = flags_from_vec(svec!["deno", "run", "--ext=js", "-"]);
     assert_eq!(
       r.unwrap(),
       Flags {
         subcommand: DenoSubcommand::Run {
-          script: "script.ts".to_string(),
+          script: "-".to_string(),
         },
-        argv: svec!["-"],
+        ext: Some("js".to_string()),
+        no_check: true,
         ..Flags::default()
       }
     );
+  }
 
-    let r =
-      flags_from_vec(svec!["deno", "run", "script.ts", "-", "foo", "bar"]);
+  #[test]
+  fn run_compat() {
+    let r = flags_from_vec(svec![
+      "deno",
+      "run",
+      "--unstable",
+      "--compat",
+      "script.ts"
+    ]);
     assert_eq!(
       r.unwrap(),
       Flags {
         subcommand: DenoSubcommand::Run {
           script: "script.ts".to_string(),
         },
-        argv: svec!["-", "foo", "bar"],
+        compat: true,
+        unstable: true,
+        no_check: true,
         ..Flags::default()
       }
     );
+
+    let r = flags_from_vec(svec!["deno", "run", "--compat", "script.ts"]);
+    assert!(r.is_err());
   }
 
   #[test]
-  fn no_check() {
-    let r = flags_from_vec(svec!["deno", "run", "--no-check", "script.ts"]);
+  fn log_level() {
+    let r =
+      flags_from_vec(svec!["deno", "run", "--log-level=debug", "script.ts"]);
     assert_eq!(
       r.unwrap(),
       Flags {
         subcommand: DenoSubcommand::Run {
           script: "script.ts".to_string(),
         },
+        log_level: Some(Level::Debug),
         no_check: true,
         ..Flags::default()
       }
@@ -3023,8 +4350,167 @@ mod tests {
   }
 
   #[test]
-  fn no_remote() {
-    let r = flags_from_vec(svec!["deno", "run", "--no-remote", "script.ts"]);
+  fn quiet() {
+    let r = flags_from_vec(svec!["deno", "run", "-q", "script.ts"]);
+    assert_eq!(
+      r.unwrap(),
+      Flags {
+        subcommand: DenoSubcommand::Run {
+          script: "script.ts".to_string(),
+        },
+        log_level: Some(Level::Error),
+        no_check: true,
+        ..Flags::default()
+      }
+    );
+  }
+
+  #[test]
+  fn completions() {
+    let r = flags_from_vec(svec!["deno", "completions", "zsh"]).unwrap();
+
+    match r.subcommand {
+      DenoSubcommand::Completions { buf } => assert!(!buf.is_empty()),
+      _ => unreachable!(),
+    }
+  }
+
+  #[test]
+  fn run_with_args() {
+    let r = flags_from_vec(svec![
+      "deno",
+      "run",
+      "script.ts",
+      "--allow-read",
+      "--allow-net"
+    ]);
+    assert_eq!(
+      r.unwrap(),
+      Flags {
+        subcommand: DenoSubcommand::Run {
+          script: "script.ts".to_string(),
+        },
+        argv: svec!["--allow-read", "--allow-net"],
+        no_check: true,
+        ..Flags::default()
+      }
+    );
+    let r = flags_from_vec(svec![
+      "deno",
+      "run",
+      "--location",
+      "https:foo",
+      "--allow-read",
+      "script.ts",
+      "--allow-net",
+      "-r",
+      "--help",
+      "--foo",
+      "bar"
+    ]);
+    assert_eq!(
+      r.unwrap(),
+      Flags {
+        subcommand: DenoSubcommand::Run {
+          script: "script.ts".to_string(),
+        },
+        location: Some(Url::parse("https://foo/").unwrap()),
+        allow_read: Some(vec![]),
+        argv: svec!["--allow-net", "-r", "--help", "--foo", "bar"],
+        no_check: true,
+        ..Flags::default()
+      }
+    );
+
+    let r = flags_from_vec(svec!["deno", "run", "script.ts", "foo", "bar"]);
+    assert_eq!(
+      r.unwrap(),
+      Flags {
+        subcommand: DenoSubcommand::Run {
+          script: "script.ts".to_string(),
+        },
+        argv: svec!["foo", "bar"],
+        no_check: true,
+        ..Flags::default()
+      }
+    );
+    let r = flags_from_vec(svec!["deno", "run", "script.ts", "-"]);
+    assert_eq!(
+      r.unwrap(),
+      Flags {
+        subcommand: DenoSubcommand::Run {
+          script: "script.ts".to_string(),
+        },
+        argv: svec!["-"],
+        no_check: true,
+        ..Flags::default()
+      }
+    );
+
+    let r =
+      flags_from_vec(svec!["deno", "run", "script.ts", "-", "foo", "bar"]);
+    assert_eq!(
+      r.unwrap(),
+      Flags {
+        subcommand: DenoSubcommand::Run {
+          script: "script.ts".to_string(),
+        },
+        argv: svec!["-", "foo", "bar"],
+        no_check: true,
+        ..Flags::default()
+      }
+    );
+  }
+
+  #[test]
+  fn no_check() {
+    let r = flags_from_vec(svec!["deno", "run", "--no-check", "script.ts"]);
+    assert_eq!(
+      r.unwrap(),
+      Flags {
+        subcommand: DenoSubcommand::Run {
+          script: "script.ts".to_string(),
+        },
+        no_check: true,
+        ..Flags::default()
+      }
+    );
+  }
+
+  #[test]
+  fn run_check() {
+    let r = flags_from_vec(svec!["deno", "run", "--check", "script.ts"]);
+    assert_eq!(
+      r.unwrap(),
+      Flags {
+        subcommand: DenoSubcommand::Run {
+          script: "script.ts".to_string(),
+        },
+        type_check_mode: TypeCheckMode::Local,
+        ..Flags::default()
+      }
+    );
+
+    let r = flags_from_vec(svec!["deno", "test", "--check=all"]);
+    let flags = r.unwrap();
+    assert!(!flags.no_check);
+    assert_eq!(flags.type_check_mode, TypeCheckMode::All);
+
+    let r = flags_from_vec(svec!["deno", "run", "--check=remote", "script.ts"]);
+    assert!(r.is_err());
+    let r = flags_from_vec(svec![
+      "deno",
+      "run",
+      "--check",
+      "--no-check",
+      "script.ts"
+    ]);
+    assert!(r.is_err());
+  }
+
+  #[test]
+  fn no_remote() {
+    let r = flags_from_vec(svec!["deno", "run", "--no-remote", "script.ts"]);
     assert_eq!(
       r.unwrap(),
       Flags {
@@ -3032,11 +4518,41 @@ mod tests {
           script: "script.ts".to_string(),
         },
         no_remote: true,
+        no_check: true,
         ..Flags::default()
       }
     );
   }
 
+  #[test]
+  fn cached_only_with_reload() {
+    let r = flags_from_vec(svec![
+      "deno",
+      "cache",
+      "--cached-only",
+      "--reload=http://127.0.0.1:4545/,https://deno.land/std#x",
+      "script.ts"
+    ]);
+    assert_eq!(
+      r.unwrap(),
+      Flags {
+        subcommand: DenoSubcommand::Cache {
+          files: svec!["script.ts"],
+          prune: None,
+        },
+        cached_only: true,
+        cache_blocklist: svec![
+          "http://127.0.0.1:4545",
+          "https://deno.land/std"
+        ],
+        ..Flags::default()
+      }
+    );
+
+    let r = flags_from_vec(svec!["deno", "cache", "--reload=std", "script.ts"]);
+    assert!(r.is_err());
+  }
+
   #[test]
   fn cached_only() {
     let r = flags_from_vec(svec!["deno", "run", "--cached-only", "script.ts"]);
@@ -3047,6 +4563,7 @@ mod tests {
           script: "script.ts".to_string(),
         },
         cached_only: true,
+        no_check: true,
         ..Flags::default()
       }
     );
@@ -3075,6 +4592,7 @@ mod tests {
           "127.0.0.1:4545",
           "localhost:4545"
         ]),
+        no_check: true,
         ..Flags::default()
       }
     );
@@ -3106,6 +4624,7 @@ mod tests {
           "localhost:5678",
           "[::1]:8080"
         ]),
+        no_check: true,
         ..Flags::default()
       }
     );
@@ -3128,11 +4647,46 @@ mod tests {
         },
         lock_write: true,
         lock: Some(PathBuf::from("lock.json")),
+        no_check: true,
         ..Flags::default()
       }
     );
   }
 
+  #[test]
+  fn frozen_lockfile() {
+    let r = flags_from_vec(svec![
+      "deno",
+      "cache",
+      "--config",
+      "deno.json",
+      "--frozen-lockfile",
+      "script.ts"
+    ]);
+    assert_eq!(
+      r.unwrap(),
+      Flags {
+        subcommand: DenoSubcommand::Cache {
+          files: svec!["script.ts"],
+          prune: None,
+        },
+        config_path: Some("deno.json".to_string()),
+        frozen_lockfile: true,
+        ..Flags::default()
+      }
+    );
+
+    let r = flags_from_vec(svec![
+      "deno",
+      "cache",
+      "--lock=lock.json",
+      "--lock-write",
+      "--frozen-lockfile",
+      "script.ts"
+    ]);
+    assert!(r.is_err());
+  }
+
   #[test]
   fn test_with_flags() {
     #[rustfmt::skip]
@@ -3147,15 +4701,301 @@ mod tests {
           allow_none: true,
           quiet: false,
           include: Some(svec!["dir1/", "dir2/"]),
+          skip: vec![],
+          shuffle: None,
+          concurrent_jobs: 1,
+          lcov: None,
+          html: None,
+          reporter: TestReporterKind::Pretty,
+          output: None,
         },
         unstable: true,
         coverage_dir: Some("cov".to_string()),
         location: Some(Url::parse("https://foo/").unwrap()),
         allow_net: Some(vec![]),
         argv: svec!["arg1", "arg2"],
+        no_check: true,
+        ..Flags::default()
+      }
+    );
+  }
+
+  #[test]
+  fn test_with_jobs() {
+    let r = flags_from_vec(svec!["deno", "test", "--unstable", "--jobs=4"]);
+    assert_eq!(
+      r.unwrap(),
+      Flags {
+        subcommand: DenoSubcommand::Test {
+          no_run: false,
+          fail_fast: false,
+          filter: None,
+          allow_none: false,
+          quiet: false,
+          include: None,
+          skip: vec![],
+          shuffle: None,
+          concurrent_jobs: 4,
+          lcov: None,
+          html: None,
+          reporter: TestReporterKind::Pretty,
+          output: None,
+        },
+        unstable: true,
+        no_check: true,
         ..Flags::default()
       }
     );
+
+    let r = flags_from_vec(svec!["deno", "test", "--unstable", "--jobs=0"]);
+    assert!(r.is_err());
+  }
+
+  #[test]
+  fn test_with_skip() {
+    let r = flags_from_vec(svec![
+      "deno", "test", "--filter", "!flaky", "--skip", "/db$/", "--skip", "net"
+    ]);
+    assert_eq!(
+      r.unwrap(),
+      Flags {
+        subcommand: DenoSubcommand::Test {
+          no_run: false,
+          fail_fast: false,
+          filter: Some("!flaky".to_string()),
+          allow_none: false,
+          quiet: false,
+          include: None,
+          skip: svec!["/db$/", "net"],
+          shuffle: None,
+          concurrent_jobs: 1,
+          lcov: None,
+          html: None,
+          reporter: TestReporterKind::Pretty,
+          output: None,
+        },
+        no_check: true,
+        ..Flags::default()
+      }
+    );
+  }
+
+  #[test]
+  fn test_with_coverage_reports() {
+    let r = flags_from_vec(svec![
+      "deno",
+      "test",
+      "--unstable",
+      "--coverage=cov",
+      "--lcov=cov.lcov",
+      "--html=cov_html"
+    ]);
+    let flags = r.unwrap();
+    assert_eq!(flags.coverage_dir, Some("cov".to_string()));
+    match flags.subcommand {
+      DenoSubcommand::Test { lcov, html, .. } => {
+        assert_eq!(lcov, Some(PathBuf::from("cov.lcov")));
+        assert_eq!(html, Some(PathBuf::from("cov_html")));
+      }
+      _ => unreachable!(),
+    }
+
+    let r = flags_from_vec(svec!["deno", "test", "--lcov=cov.lcov"]);
+    assert!(r.is_err());
+  }
+
+  #[test]
+  fn test_with_reporter() {
+    let r = flags_from_vec(svec![
+      "deno",
+      "test",
+      "--unstable",
+      "--reporter=junit",
+      "--output=report.xml"
+    ]);
+    match r.unwrap().subcommand {
+      DenoSubcommand::Test {
+        reporter, output, ..
+      } => {
+        assert_eq!(reporter, TestReporterKind::Junit);
+        assert_eq!(output, Some(PathBuf::from("report.xml")));
+      }
+      _ => unreachable!(),
+    }
+
+    let r =
+      flags_from_vec(svec!["deno", "test", "--unstable", "--reporter=xml"]);
+    assert!(r.is_err());
+    let r = flags_from_vec(svec![
+      "deno",
+      "test",
+      "--unstable",
+      "--reporter=tap",
+      "--coverage"
+    ]);
+    assert!(r.is_err());
+  }
+
+  #[test]
+  fn test_watch() {
+    let r = flags_from_vec(svec![
+      "deno",
+      "test",
+      "--unstable",
+      "--watch=fixtures",
+      "--no-clear-screen",
+      "a_test.ts"
+    ]);
+    let flags = r.unwrap();
+    assert!(flags.watch);
+    assert_eq!(flags.watch_paths, vec![PathBuf::from("fixtures")]);
+    assert!(flags.no_clear_screen);
+    match flags.subcommand {
+      DenoSubcommand::Test { include, .. } => {
+        assert_eq!(include, Some(svec!["a_test.ts"]));
+      }
+      _ => unreachable!(),
+    }
+
+    let r = flags_from_vec(svec![
+      "deno",
+      "test",
+      "--unstable",
+      "--watch",
+      "--no-run"
+    ]);
+    assert!(r.is_err());
+  }
+
+  #[test]
+  fn test_with_shuffle() {
+    let r = flags_from_vec(svec!["deno", "test", "--unstable", "--shuffle=1"]);
+    assert_eq!(
+      r.unwrap(),
+      Flags {
+        subcommand: DenoSubcommand::Test {
+          no_run: false,
+          fail_fast: false,
+          filter: None,
+          allow_none: false,
+          quiet: false,
+          include: None,
+          skip: vec![],
+          shuffle: Some(1),
+          concurrent_jobs: 1,
+          lcov: None,
+          html: None,
+          reporter: TestReporterKind::Pretty,
+          output: None,
+        },
+        unstable: true,
+        no_check: true,
+        ..Flags::default()
+      }
+    );
+
+    let r = flags_from_vec(svec!["deno", "test", "--unstable", "--shuffle"]);
+    match r.unwrap().subcommand {
+      DenoSubcommand::Test { shuffle, .. } => assert!(shuffle.is_some()),
+      _ => unreachable!(),
+    }
+  }
+
+  #[test]
+  fn bench_with_flags() {
+    let r = flags_from_vec(svec![
+      "deno",
+      "bench",
+      "--unstable",
+      "--filter",
+      "/^parse/",
+      "--json",
+      "--allow-read",
+      "dir1/",
+      "--",
+      "arg1"
+    ]);
+    assert_eq!(
+      r.unwrap(),
+      Flags {
+        subcommand: DenoSubcommand::Bench {
+          include: Some(svec!["dir1/"]),
+          filter: Some("/^parse/".to_string()),
+          json: true,
+        },
+        unstable: true,
+        allow_read: Some(vec![]),
+        argv: svec!["arg1"],
+        ..Flags::default()
+      }
+    );
+  }
+
+  #[test]
+  fn task_with_args() {
+    let r = flags_from_vec(svec![
+      "deno",
+      "task",
+      "--unstable",
+      "--config",
+      "deno.json",
+      "build",
+      "--flag",
+      "arg"
+    ]);
+    assert_eq!(
+      r.unwrap(),
+      Flags {
+        subcommand: DenoSubcommand::Task {
+          task: Some("build".to_string()),
+          args: svec!["--flag", "arg"],
+        },
+        unstable: true,
+        config_path: Some("deno.json".to_string()),
+        ..Flags::default()
+      }
+    );
+
+    let r = flags_from_vec(svec!["deno", "task"]);
+    assert_eq!(
+      r.unwrap(),
+      Flags {
+        subcommand: DenoSubcommand::Task {
+          task: None,
+          args: vec![],
+        },
+        ..Flags::default()
+      }
+    );
+  }
+
+  #[test]
+  fn vendor() {
+    let r = flags_from_vec(svec![
+      "deno",
+      "vendor",
+      "--unstable",
+      "--force",
+      "--import-map",
+      "import_map.json",
+      "main.ts",
+      "test.ts"
+    ]);
+    assert_eq!(
+      r.unwrap(),
+      Flags {
+        subcommand: DenoSubcommand::Vendor {
+          entrypoints: svec!["main.ts", "test.ts"],
+          force: true,
+        },
+        unstable: true,
+        import_map_path: Some("import_map.json".to_string()),
+        ..Flags::default()
+      }
+    );
+
+    let r = flags_from_vec(svec!["deno", "vendor"]);
+    assert!(r.is_err());
   }
 
   #[test]
@@ -3174,6 +5014,7 @@ mod tests {
           script: "script.ts".to_string(),
         },
         ca_file: Some("example.crt".to_owned()),
+        no_check: true,
         ..Flags::default()
       }
     );
@@ -3236,6 +5077,7 @@ mod tests {
       Flags {
         subcommand: DenoSubcommand::Cache {
           files: svec!["script.ts", "script_two.ts"],
+          prune: None,
         },
         ca_file: Some("example.crt".to_owned()),
         ..Flags::default()
@@ -3258,6 +5100,8 @@ mod tests {
         subcommand: DenoSubcommand::Info {
           json: false,
           file: Some("https://example.com".to_string()),
+          reverse: None,
+          cache: false,
         },
         ca_file: Some("example.crt".to_owned()),
         ..Flags::default()
@@ -3300,6 +5144,26 @@ mod tests {
       }
     );
 
+    let r = flags_from_vec(svec![
+      "deno",
+      "doc",
+      "--json",
+      "path/to/module.ts",
+      "someFunction"
+    ]);
+    assert_eq!(
+      r.unwrap(),
+      Flags {
+        subcommand: DenoSubcommand::Doc {
+          private: false,
+          json: true,
+          source_file: Some("path/to/module.ts".to_string()),
+          filter: Some("someFunction".to_string()),
+        },
+        ..Flags::default()
+      }
+    );
+
     let r = flags_from_vec(svec!["deno", "doc"]);
     assert_eq!(
       r.unwrap(),
@@ -3354,6 +5218,7 @@ mod tests {
           script: "foo.js".to_string(),
         },
         inspect: Some("127.0.0.1:9229".parse().unwrap()),
+        no_check: true,
         ..Flags::default()
       }
     );
@@ -3376,16 +5241,32 @@ mod tests {
           args: vec![],
           target: None,
           lite: true,
+          include: vec![],
         },
         ..Flags::default()
       }
     );
   }
 
+  #[test]
+  fn compile_with_unknown_target() {
+    let r = flags_from_vec(svec![
+      "deno",
+      "compile",
+      "--target",
+      "riscv64gc-unknown-linux-gnu",
+      "main.ts"
+    ]);
+    let err = r.unwrap_err();
+    for target in SUPPORTED_TARGETS {
+      assert!(err.message.contains(target));
+    }
+  }
+
   #[test]
   fn compile_with_flags() {
     #[rustfmt::skip]
-    let r = flags_from_vec(svec!["deno", "compile", "--unstable", "--import-map", "import_map.json", "--no-remote", "--config", "tsconfig.json", "--no-check", "--reload", "--lock", "lock.json", "--lock-write", "--cert", "example.crt", "--cached-only", "--location", "https:foo", "--allow-read", "--allow-net", "--v8-flags=--help", "--seed", "1", "--output", "colors", "https://deno.land/std/examples/colors.ts", "foo", "bar"]);
+    let r = flags_from_vec(svec!["deno", "compile", "--unstable", "--import-map", "import_map.json", "--no-remote", "--config", "tsconfig.json", "--no-check", "--reload", "--lock", "lock.json", "--lock-write", "--cert", "example.crt", "--cached-only", "--location", "https:foo", "--allow-read", "--allow-net", "--v8-flags=--help", "--seed", "1", "--output", "colors", "--include", "assets", "--include", "data.json", "https://deno.land/std/examples/colors.ts", "foo", "bar"]);
     assert_eq!(
       r.unwrap(),
       Flags {
@@ -3395,6 +5276,7 @@ mod tests {
           args: svec!["foo", "bar"],
           target: None,
           lite: false,
+          include: vec![PathBuf::from("assets"), PathBuf::from("data.json")],
         },
         unstable: true,
         import_map_path: Some("import_map.json".to_string()),
@@ -3,6 +3,7 @@
 use deno_core::error::AnyError;
 pub use deno_core::normalize_path;
 use deno_runtime::deno_crypto::rand;
+use regex::Regex;
 use std::env::current_dir;
 use std::fs::OpenOptions;
 use std::io::{Error, Write};
@@ -159,6 +160,45 @@ where
   Ok(target_files)
 }
 
+/// Converts `glob`, a path relative to some directory that may contain
+/// wildcards, into a regex matching paths relative to that directory, using
+/// `/` as separator. `**` matches any number of directories, while `*` and `?`
+/// match any characters, or one character, within a path component.
+pub fn glob_to_regex(glob: &str) -> Result<Regex, AnyError> {
+  let glob = glob.replace('\\', "/");
+  let glob = glob.trim_start_matches("./").trim_end_matches('/');
+  let mut regex = String::from("^");
+  let mut chars = glob.chars().peekable();
+  while let Some(c) = chars.next() {
+    match c {
+      '*' if chars.peek() == Some(&'*') => {
+        chars.next();
+        if chars.peek() == Some(&'/') {
+          chars.next();
+          regex.push_str("(?:.*/)?");
+        } else {
+          regex.push_str(".*");
+        }
+      }
+      '*' => regex.push_str("[^/]*"),
+      '?' => regex.push_str("[^/]"),
+      c => regex.push_str(&regex::escape(&c.to_string())),
+    }
+  }
+  regex.push('$');
+  Ok(Regex::new(&regex)?)
+}
+
+/// Whether the relative `path` or one of the directories containing it
+/// matches `glob`, as returned by `glob_to_regex()`, so that globs naming a
+/// directory match all the files in it.
+pub fn matches_glob(glob: &Regex, path: &Path) -> bool {
+  path
+    .ancestors()
+    .filter(|path| !path.as_os_str().is_empty())
+    .any(|path| glob.is_match(&path.to_string_lossy().replace('\\', "/")))
+}
+
 #[cfg(test)]
 mod tests {
   use super::*;
@@ -306,4 +346,24 @@ mod tests {
     }
     assert_eq!(result.len(), expected.len());
   }
+
+  #[test]
+  fn test_glob_to_regex() {
+    let matches = |glob: &str, path: &str| {
+      matches_glob(&glob_to_regex(glob).unwrap(), Path::new(path))
+    };
+    assert!(matches("src", "src/a/b.ts"));
+    assert!(matches("./src/", "src/b.ts"));
+    assert!(!matches("src", "srcs/b.ts"));
+    assert!(matches("src/*.ts", "src/b.ts"));
+    assert!(!matches("src/*.ts", "src/a/b.ts"));
+    assert!(matches("src/**/*.ts", "src/b.ts"));
+    assert!(matches("src/**/*.ts", "src/a/b/c.ts"));
+    assert!(matches("**/*.generated.ts", "a/b.generated.ts"));
+    assert!(!matches("**/*.generated.ts", "a/b.ts"));
+    assert!(matches("a?.md", "ab.md"));
+    assert!(!matches("a?.md", "a/.md"));
+    assert!(matches("src/a+b.js", "src/a+b.js"));
+    assert!(!matches("src/a+b.js", "src/aab.js"));
+  }
 }
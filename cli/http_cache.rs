@@ -12,14 +12,23 @@ use deno_core::serde_json;
 use deno_core::url::Url;
 use serde::Deserialize;
 use serde::Serialize;
+use std::collections::BTreeMap;
+use std::collections::HashSet;
 use std::fs;
 use std::fs::File;
 use std::io;
 use std::path::Path;
 use std::path::PathBuf;
+use std::time::Duration;
+use std::time::SystemTime;
+use std::time::UNIX_EPOCH;
 
 pub const CACHE_PERM: u32 = 0o644;
 
+/// How often the access time of an entry is updated when it is read, so that
+/// reading the cache doesn't mean rewriting its metadata every time.
+const TOUCH_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
 /// Turn base of url (scheme, hostname, port) into a valid filename.
 /// This method replaces port part with a special string token (because
 /// ":" cannot be used in filename on some platforms).
@@ -77,10 +86,33 @@ pub struct HttpCache {
   pub location: PathBuf,
 }
 
+/// The inverse of `base_url_to_filename()`, for the directories of the cache:
+/// ("https", "deno.land_PORT8080") is "https://deno.land:8080".
+fn dirnames_to_origin(scheme: &str, host_port: Option<&str>) -> String {
+  match host_port {
+    Some(host_port) => {
+      format!("{}://{}", scheme, host_port.replacen("_PORT", ":", 1))
+    }
+    None => format!("{}:", scheme),
+  }
+}
+
+fn to_unix_secs(time: SystemTime) -> u64 {
+  time
+    .duration_since(UNIX_EPOCH)
+    .map(|duration| duration.as_secs())
+    .unwrap_or(0)
+}
+
 #[derive(Serialize, Deserialize)]
 pub struct Metadata {
   pub headers: HeadersMap,
   pub url: String,
+  /// When the entry was last written or read, in seconds since the UNIX
+  /// epoch. Entries written by older versions don't have it, and the
+  /// modification time of their metadata is used instead.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub accessed: Option<u64>,
 }
 
 impl Metadata {
@@ -103,6 +135,42 @@ impl Metadata {
   pub fn filename(cache_filename: &Path) -> PathBuf {
     cache_filename.with_extension("metadata.json")
   }
+
+  /// Records that the entry was read, unless that was already done in the
+  /// last `TOUCH_INTERVAL`.
+  fn touch(&mut self, cache_filename: &Path) -> Result<(), AnyError> {
+    let now = to_unix_secs(SystemTime::now());
+    if let Some(accessed) = self.accessed {
+      if now.saturating_sub(accessed) < TOUCH_INTERVAL.as_secs() {
+        return Ok(());
+      }
+    }
+    self.accessed = Some(now);
+    self.write(cache_filename)
+  }
+}
+
+/// A module in the cache; see `HttpCache::entries()`.
+#[derive(Debug, Clone)]
+pub struct CacheEntry {
+  /// Ex: "https://deno.land:8080" or "data:".
+  pub origin: String,
+  /// The URL of the module, or `None` if its metadata is missing.
+  pub url: Option<String>,
+  pub filename: PathBuf,
+  /// The size of the cached source, in bytes.
+  pub size: u64,
+  pub accessed: SystemTime,
+}
+
+/// The modules of an origin in the cache; see `HttpCache::origins()`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OriginUsage {
+  pub origin: String,
+  pub modules: usize,
+  /// The total size of the cached sources, in bytes.
+  pub size: u64,
 }
 
 impl HttpCache {
@@ -145,9 +213,14 @@ impl HttpCache {
         .ok_or_else(|| generic_error("Can't convert url to filename."))?,
     );
     let metadata_filename = Metadata::filename(&cache_filename);
-    let file = File::open(cache_filename)?;
+    let file = File::open(&cache_filename)?;
     let metadata = fs::read_to_string(metadata_filename)?;
-    let metadata: Metadata = serde_json::from_str(&metadata)?;
+    let mut metadata: Metadata = serde_json::from_str(&metadata)?;
+    // The access time is only used to prune the cache, so failing to record
+    // it, e.g. in a read-only cache, isn't an error.
+    if let Err(err) = metadata.touch(&cache_filename) {
+      debug!("Failed to update the access time of {}: {}", url, err);
+    }
     Ok((file, metadata.headers))
   }
 
@@ -172,9 +245,164 @@ impl HttpCache {
     let metadata = Metadata {
       url: url.to_string(),
       headers: headers_map,
+      accessed: Some(to_unix_secs(SystemTime::now())),
     };
     metadata.write(&cache_filename)
   }
+
+  /// Lists the modules in the cache. Other processes may add or remove
+  /// entries at the same time, so the list is only a snapshot.
+  pub fn entries(&self) -> Result<Vec<CacheEntry>, AnyError> {
+    let mut entries = Vec::new();
+    for scheme_dir in read_dir_or_empty(&self.location)? {
+      let scheme = match scheme_dir.file_name().to_str() {
+        Some(scheme) => scheme.to_string(),
+        None => continue,
+      };
+      if scheme == "data" {
+        let origin = dirnames_to_origin(&scheme, None);
+        read_entries(&scheme_dir.path(), &origin, &mut entries)?;
+        continue;
+      }
+      for host_dir in read_dir_or_empty(&scheme_dir.path())? {
+        if let Some(host_port) = host_dir.file_name().to_str() {
+          let origin = dirnames_to_origin(&scheme, Some(host_port));
+          read_entries(&host_dir.path(), &origin, &mut entries)?;
+        }
+      }
+    }
+    Ok(entries)
+  }
+
+  /// Sums up the sizes of the modules in the cache by origin, sorted by
+  /// origin.
+  pub fn origins(&self) -> Result<Vec<OriginUsage>, AnyError> {
+    let mut origins = BTreeMap::<String, OriginUsage>::new();
+    for entry in self.entries()? {
+      let usage =
+        origins
+          .entry(entry.origin.clone())
+          .or_insert_with(|| OriginUsage {
+            origin: entry.origin.clone(),
+            ..Default::default()
+          });
+      usage.modules += 1;
+      usage.size += entry.size;
+    }
+    Ok(origins.into_iter().map(|(_, usage)| usage).collect())
+  }
+
+  /// Removes the modules that weren't written or read in the last `max_age`,
+  /// except the ones whose URL is in `keep`, and returns them.
+  ///
+  /// Another process may be reading the cache meanwhile. The source of an
+  /// entry is removed before its metadata, and readers treat either one
+  /// being missing as a cache miss, so the worst case is that a module that
+  /// was just read is downloaded again later. Entries that can't be removed,
+  /// e.g. because they are open on Windows, are skipped.
+  pub fn prune(
+    &self,
+    max_age: Duration,
+    keep: &HashSet<String>,
+  ) -> Result<Vec<CacheEntry>, AnyError> {
+    let cutoff = SystemTime::now().checked_sub(max_age).unwrap_or(UNIX_EPOCH);
+    let mut removed = Vec::new();
+    for entry in self.entries()? {
+      if entry.accessed >= cutoff {
+        continue;
+      }
+      if let Some(url) = &entry.url {
+        if keep.contains(url) {
+          continue;
+        }
+      }
+      // Check again, in case another process read the entry since it was
+      // listed.
+      let maybe_metadata = read_metadata(&entry.filename);
+      if accessed_time(&entry.filename, maybe_metadata.as_ref()) >= cutoff {
+        continue;
+      }
+      if let Err(err) = remove_file_if_exists(&entry.filename).and_then(|_| {
+        remove_file_if_exists(&Metadata::filename(&entry.filename))
+      }) {
+        debug!("Failed to remove {}: {}", entry.filename.display(), err);
+        continue;
+      }
+      removed.push(entry);
+    }
+    Ok(removed)
+  }
+}
+
+fn read_dir_or_empty(path: &Path) -> io::Result<Vec<fs::DirEntry>> {
+  match fs::read_dir(path) {
+    Ok(read_dir) => read_dir.collect(),
+    Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(Vec::new()),
+    Err(err) => Err(err),
+  }
+}
+
+fn remove_file_if_exists(path: &Path) -> io::Result<()> {
+  match fs::remove_file(path) {
+    Err(err) if err.kind() != io::ErrorKind::NotFound => Err(err),
+    _ => Ok(()),
+  }
+}
+
+fn read_metadata(cache_filename: &Path) -> Option<Metadata> {
+  let json = fs::read_to_string(Metadata::filename(cache_filename)).ok()?;
+  serde_json::from_str(&json).ok()
+}
+
+/// The access time recorded in the metadata of an entry, falling back to the
+/// modification time of the metadata or of the source.
+fn accessed_time(
+  cache_filename: &Path,
+  maybe_metadata: Option<&Metadata>,
+) -> SystemTime {
+  maybe_metadata
+    .and_then(|metadata| metadata.accessed)
+    .map(|secs| UNIX_EPOCH + Duration::from_secs(secs))
+    .or_else(|| {
+      fs::metadata(Metadata::filename(cache_filename))
+        .ok()?
+        .modified()
+        .ok()
+    })
+    .or_else(|| fs::metadata(cache_filename).ok()?.modified().ok())
+    .unwrap_or(UNIX_EPOCH)
+}
+
+/// Reads the entries in the directory of an origin. The sources are the
+/// files without an extension; the others are their metadata or temporary
+/// files of writes in progress.
+fn read_entries(
+  dir: &Path,
+  origin: &str,
+  entries: &mut Vec<CacheEntry>,
+) -> Result<(), AnyError> {
+  for dir_entry in read_dir_or_empty(dir)? {
+    let filename = dir_entry.path();
+    if filename.extension().is_some() {
+      continue;
+    }
+    // The entry may have been removed since the directory was read.
+    let size = match dir_entry.metadata() {
+      Ok(metadata) if metadata.is_file() => metadata.len(),
+      _ => continue,
+    };
+    let maybe_metadata = read_metadata(&filename);
+    let accessed = accessed_time(&filename, maybe_metadata.as_ref());
+    let url = maybe_metadata.map(|metadata| metadata.url);
+    entries.push(CacheEntry {
+      origin: origin.to_string(),
+      url,
+      filename,
+      size,
+      accessed,
+    });
+  }
+  Ok(())
 }
 
 #[cfg(test)]
@@ -240,6 +468,105 @@ mod tests {
     assert_eq!(headers.get("foobar"), None);
   }
 
+  /// Makes the entry of `url` look like it was last accessed `age` ago.
+  fn set_age(cache: &HttpCache, url: &Url, age: Duration) {
+    let cache_filename = cache.get_cache_filename(url).unwrap();
+    let mut metadata = Metadata::read(&cache_filename).unwrap();
+    metadata.accessed = Some(to_unix_secs(SystemTime::now() - age));
+    metadata.write(&cache_filename).unwrap();
+  }
+
+  #[test]
+  fn test_get_touches_old_entries() {
+    let dir = TempDir::new().unwrap();
+    let cache = HttpCache::new(dir.path());
+    let url = Url::parse("https://deno.land/x/welcome.ts").unwrap();
+    cache.set(&url, HashMap::new(), b"Hello world").unwrap();
+    let cache_filename = cache.get_cache_filename(&url).unwrap();
+    let accessed = Metadata::read(&cache_filename).unwrap().accessed.unwrap();
+
+    // Reading a recently accessed entry doesn't rewrite its metadata.
+    let recent = accessed - 60;
+    let mut metadata = Metadata::read(&cache_filename).unwrap();
+    metadata.accessed = Some(recent);
+    metadata.write(&cache_filename).unwrap();
+    cache.get(&url).unwrap();
+    let metadata = Metadata::read(&cache_filename).unwrap();
+    assert_eq!(metadata.accessed, Some(recent));
+
+    set_age(&cache, &url, Duration::from_secs(60 * 60 * 24));
+    cache.get(&url).unwrap();
+    let metadata = Metadata::read(&cache_filename).unwrap();
+    assert!(metadata.accessed.unwrap() >= accessed);
+  }
+
+  #[test]
+  fn test_origins() {
+    let dir = TempDir::new().unwrap();
+    let cache = HttpCache::new(dir.path());
+    assert_eq!(cache.origins().unwrap(), vec![]);
+    for (url, content) in &[
+      ("https://deno.land/x/a.ts", "a".repeat(10)),
+      ("https://deno.land/x/b.ts", "b".repeat(20)),
+      ("http://localhost:4545/c.ts", "c".repeat(30)),
+      ("data:text/plain,Hello", "Hello".to_string()),
+    ] {
+      let url = Url::parse(url).unwrap();
+      cache.set(&url, HashMap::new(), content.as_bytes()).unwrap();
+    }
+    assert_eq!(
+      cache.origins().unwrap(),
+      vec![
+        OriginUsage {
+          origin: "data:".to_string(),
+          modules: 1,
+          size: 5,
+        },
+        OriginUsage {
+          origin: "http://localhost:4545".to_string(),
+          modules: 1,
+          size: 30,
+        },
+        OriginUsage {
+          origin: "https://deno.land".to_string(),
+          modules: 2,
+          size: 30,
+        },
+      ]
+    );
+  }
+
+  #[test]
+  fn test_prune() {
+    let dir = TempDir::new().unwrap();
+    let cache = HttpCache::new(dir.path());
+    let day = Duration::from_secs(60 * 60 * 24);
+    let recent = Url::parse("https://deno.land/x/recent.ts").unwrap();
+    let old = Url::parse("https://deno.land/x/old.ts").unwrap();
+    let old_locked = Url::parse("https://deno.land/x/old_locked.ts").unwrap();
+    for url in &[&recent, &old, &old_locked] {
+      cache.set(url, HashMap::new(), b"export {};").unwrap();
+    }
+    set_age(&cache, &recent, day);
+    set_age(&cache, &old, day * 40);
+    set_age(&cache, &old_locked, day * 40);
+    let mut keep = HashSet::new();
+    keep.insert(old_locked.to_string());
+
+    let removed = cache.prune(day * 30, &keep).unwrap();
+    let removed: Vec<_> = removed.into_iter().map(|entry| entry.url).collect();
+    assert_eq!(removed, vec![Some(old.to_string())]);
+    assert!(cache.get(&recent).is_ok());
+    assert!(cache.get(&old_locked).is_ok());
+    let err = cache.get(&old).unwrap_err();
+    let err = err.downcast_ref::<io::Error>().unwrap();
+    assert_eq!(err.kind(), io::ErrorKind::NotFound);
+    let old_filename = cache.get_cache_filename(&old).unwrap();
+    assert!(!Metadata::filename(&old_filename).exists());
+
+    assert!(cache.prune(day * 30, &keep).unwrap().is_empty());
+  }
+
   #[test]
   fn test_url_to_filename() {
     let test_cases = [
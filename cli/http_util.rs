@@ -101,6 +101,7 @@ pub async fn fetch_once(
     let if_none_match_val = HeaderValue::from_str(&etag).unwrap();
     request = request.header(IF_NONE_MATCH, if_none_match_val);
   }
+  let has_auth_token = args.maybe_auth_token.is_some();
   if let Some(auth_token) = args.maybe_auth_token {
     let authorization_val =
       HeaderValue::from_str(&auth_token.to_string()).unwrap();
@@ -148,6 +149,31 @@ pub async fn fetch_once(
     }
   }
 
+  if response.status() == StatusCode::UNAUTHORIZED {
+    let host = args.url.host_str().unwrap_or_default();
+    let host = match args.url.port() {
+      Some(port) => format!("{}:{}", host, port),
+      None => host.to_string(),
+    };
+    let hint = if has_auth_token {
+      format!("The credentials for \"{}\" were rejected.", host)
+    } else {
+      format!(
+        "\"{}\" requires authentication. Set DENO_AUTH_TOKENS to \
+        \"{{token}}@{}\" for a bearer token or to \
+        \"{{username}}:{{password}}@{}\" for basic auth, or add the host to \
+        .netrc.",
+        host, host, host
+      )
+    };
+    return Err(generic_error(format!(
+      "Import '{}' failed: {}\n  {}",
+      args.url,
+      response.status(),
+      hint
+    )));
+  }
+
   if response.status().is_client_error() || response.status().is_server_error()
   {
     let err = generic_error(format!(
@@ -15,7 +15,8 @@ use std::path::PathBuf;
 
 /// The core structure representing information about a specific "root" file in
 /// a module graph.  This is used to represent information as part of the `info`
-/// subcommand.
+/// subcommand, and its serialization is the document printed by
+/// `deno info --json`.
 #[derive(Debug, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ModuleGraphInfo {
@@ -29,9 +30,23 @@ pub struct ModuleGraphInfo {
   pub local: PathBuf,
   pub map: Option<PathBuf>,
   pub module: ModuleSpecifier,
+  /// The redirects that were followed while building the graph, from the
+  /// requested specifier to the specifier it was redirected to.
+  pub redirects: BTreeMap<String, ModuleSpecifier>,
+  /// The emitted size of the unique modules of the graph that have an emit.
+  pub total_emit_size: usize,
+  /// The source size of the unique modules of the graph.
   pub total_size: usize,
 }
 
+impl ModuleGraphInfo {
+  /// The first error in the dependency tree of the root module, if any of its
+  /// dependencies could not be loaded.
+  pub fn first_error(&self) -> Option<&str> {
+    self.info.first_error()
+  }
+}
+
 impl fmt::Display for ModuleGraphInfo {
   fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
     writeln!(
@@ -75,6 +90,15 @@ impl fmt::Display for ModuleGraphInfo {
       dep.write_info(f, "", idx == dep_count - 1)?;
     }
 
+    writeln!(f)?;
+    writeln!(
+      f,
+      "{} {} source, {} emitted",
+      colors::bold("total:"),
+      human_size(self.total_size as f64),
+      human_size(self.total_emit_size as f64)
+    )?;
+
     Ok(())
   }
 }
@@ -85,6 +109,8 @@ impl fmt::Display for ModuleGraphInfo {
 #[serde(rename_all = "camelCase")]
 pub struct ModuleInfo {
   pub deps: Vec<ModuleInfo>,
+  /// Set when the module could not be loaded.
+  pub error: Option<String>,
   pub name: ModuleSpecifier,
   pub size: usize,
   pub total_size: Option<usize>,
@@ -103,6 +129,13 @@ impl Ord for ModuleInfo {
 }
 
 impl ModuleInfo {
+  fn first_error(&self) -> Option<&str> {
+    self
+      .error
+      .as_deref()
+      .or_else(|| self.deps.iter().find_map(|dep| dep.first_error()))
+  }
+
   pub fn write_info(
     &self,
     f: &mut fmt::Formatter<'_>,
@@ -169,14 +202,82 @@ impl Serialize for ModuleInfoMap {
 
 /// An entry in the `ModuleInfoMap` the provides the size of the module and
 /// a vector of its dependencies, which should also be available as entries
-/// in the map.
+/// in the map.  Modules that could not be loaded only have an `error`.
 #[derive(Debug, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ModuleInfoMapItem {
+  pub dependencies: Vec<DependencyInfo>,
   pub deps: Vec<ModuleSpecifier>,
+  /// The size of the emitted code, for modules that have been transpiled.
+  pub emit_size: Option<usize>,
+  pub error: Option<String>,
+  pub local: Option<PathBuf>,
+  #[serde(serialize_with = "serialize_media_type")]
+  pub media_type: MediaType,
   pub size: usize,
 }
 
+/// A dependency of a module in the graph, in the order it appears in the
+/// source of the module.
+#[derive(Debug, Clone, Serialize, Eq, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct DependencyInfo {
+  /// The specifier the runtime code resolves to.
+  pub code: Option<ModuleSpecifier>,
+  pub col: usize,
+  pub is_dynamic: bool,
+  pub line: usize,
+  /// The specifier as it is written in the source.
+  pub specifier: String,
+  /// The specifier the types resolve to, when it differs from the code.
+  #[serde(rename = "type")]
+  pub type_dependency: Option<ModuleSpecifier>,
+}
+
+/// The modules that directly import a given module, as returned by
+/// `deno info --reverse`.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ModuleImportersInfo {
+  pub importers: Vec<ImporterInfo>,
+  pub module: ModuleSpecifier,
+}
+
+#[derive(Debug, Serialize, Eq, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ImporterInfo {
+  pub dependency: DependencyInfo,
+  pub module: ModuleSpecifier,
+}
+
+impl fmt::Display for ModuleImportersInfo {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    if self.importers.is_empty() {
+      return writeln!(f, "{} is not imported by any module", self.module);
+    }
+    writeln!(f, "{} is imported by:", self.module)?;
+    let count = self.importers.len();
+    for (idx, importer) in self.importers.iter().enumerate() {
+      let connector = if idx == count - 1 { '└' } else { '├' };
+      let dependency = &importer.dependency;
+      let dynamic = if dependency.is_dynamic {
+        " (dynamic)"
+      } else {
+        ""
+      };
+      writeln!(
+        f,
+        "{} {}{} {}",
+        colors::gray(&format!("{}──", connector)),
+        importer.module,
+        colors::gray(&format!(":{}:{}", dependency.line, dependency.col)),
+        colors::gray(&format!("\"{}\"{}", dependency.specifier, dynamic))
+      )?;
+    }
+    Ok(())
+  }
+}
+
 /// A function that converts a float to a string the represents a human
 /// readable version of that number.
 pub fn human_size(size: f64) -> String {
@@ -223,12 +324,14 @@ mod test {
     let spec_d = resolve_url_or_path("https://deno.land/x/a/b/c.ts").unwrap();
     let deps = vec![ModuleInfo {
       deps: Vec::new(),
+      error: None,
       name: spec_d.clone(),
       size: 12345,
       total_size: None,
     }];
     let info = ModuleInfo {
       deps,
+      error: None,
       name: spec_c.clone(),
       size: 12345,
       total_size: Some(12345),
@@ -237,14 +340,31 @@ mod test {
     items.insert(
       spec_c,
       ModuleInfoMapItem {
+        dependencies: vec![DependencyInfo {
+          code: Some(spec_d.clone()),
+          col: 0,
+          is_dynamic: false,
+          line: 1,
+          specifier: "./c.ts".to_string(),
+          type_dependency: None,
+        }],
         deps: vec![spec_d.clone()],
+        emit_size: Some(6789),
+        error: None,
+        local: Some(PathBuf::from("/a/b/c.ts")),
+        media_type: MediaType::TypeScript,
         size: 12345,
       },
     );
     items.insert(
       spec_d,
       ModuleInfoMapItem {
+        dependencies: Vec::new(),
         deps: Vec::new(),
+        emit_size: Some(6789),
+        error: None,
+        local: Some(PathBuf::from("/a/b/c.ts")),
+        media_type: MediaType::TypeScript,
         size: 12345,
       },
     );
@@ -259,6 +379,8 @@ mod test {
       local: PathBuf::from("/a/b/c.ts"),
       map: None,
       module: resolve_url_or_path("https://deno.land/x/a/b/c.ts").unwrap(),
+      redirects: BTreeMap::new(),
+      total_emit_size: 6789,
       total_size: 999999,
     }
   }
@@ -271,6 +393,7 @@ mod test {
     assert!(actual.contains(" 99 unique"));
     assert!(actual.contains("(12.06KB)"));
     assert!(actual.contains("\n\nhttps://deno.land/x/a/b/c.ts"));
+    assert!(actual.ends_with("976.56KB source, 6.63KB emitted\n"));
   }
 
   #[test]
@@ -285,15 +408,55 @@ mod test {
         "fileType": "TypeScript",
         "files": {
           "https://deno.land/x/a/b/c.ts":{
+            "dependencies": [],
             "deps": [],
+            "emitSize": 6789,
+            "error": null,
+            "local": "/a/b/c.ts",
+            "mediaType": "TypeScript",
             "size": 12345
           }
         },
         "local": "/a/b/c.ts",
         "map": null,
         "module": "https://deno.land/x/a/b/c.ts",
+        "redirects": {},
+        "totalEmitSize": 6789,
         "totalSize": 999999
       })
     );
   }
+
+  #[test]
+  fn test_module_importers_info_display() {
+    let module = resolve_url_or_path("https://deno.land/x/a/b/c.ts").unwrap();
+    let dependency = DependencyInfo {
+      code: Some(module.clone()),
+      col: 7,
+      is_dynamic: true,
+      line: 3,
+      specifier: "./b/c.ts".to_string(),
+      type_dependency: None,
+    };
+    let info = ModuleImportersInfo {
+      importers: vec![ImporterInfo {
+        dependency,
+        module: resolve_url_or_path("https://deno.land/x/a/mod.ts").unwrap(),
+      }],
+      module: module.clone(),
+    };
+    let actual = colors::strip_ansi_codes(&info.to_string()).to_string();
+    assert_eq!(
+      actual,
+      "https://deno.land/x/a/b/c.ts is imported by:\n└── https://deno.land/x/a/mod.ts:3:7 \"./b/c.ts\" (dynamic)\n"
+    );
+    let info = ModuleImportersInfo {
+      importers: Vec::new(),
+      module,
+    };
+    assert_eq!(
+      info.to_string(),
+      "https://deno.land/x/a/b/c.ts is not imported by any module\n"
+    );
+  }
 }
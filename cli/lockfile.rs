@@ -2,39 +2,114 @@
 
 use deno_core::serde_json;
 use deno_core::serde_json::json;
+use deno_core::serde_json::Value;
 use std::collections::BTreeMap;
+use std::io::Error;
+use std::io::ErrorKind;
 use std::io::Result;
+use std::path::Path;
 use std::path::PathBuf;
 
+/// The version of the lock file format that is written.
+const VERSION: &str = "2";
+
+/// The name of the lock file that is used next to a `deno.json` or
+/// `deno.jsonc` configuration file when no lock file is passed.
+pub const DEFAULT_LOCKFILE_NAME: &str = "deno.lock";
+
+/// Why a module failed the lock file check.
+#[derive(Debug, Clone, PartialEq)]
+pub enum LockfileError {
+  /// The module is not in a frozen lock file.
+  Missing,
+  /// The hash of the module's source doesn't match the lock file.
+  Mismatch { expected: String, actual: String },
+}
+
+/// The lock file, which records the hash of the source of every remote
+/// module. The current format is versioned and keeps the remote modules
+/// under their own key, so other kinds of entries can be added later:
+///
+/// ```json
+/// {
+///   "version": "2",
+///   "remote": {
+///     "https://deno.land/std@0.88.0/path/mod.ts": "c40258e5..."
+///   }
+/// }
+/// ```
+///
+/// Lock files in the original format, a flat object of specifiers to hashes,
+/// are still read and are upgraded when they are written.
 #[derive(Debug, Clone)]
 pub struct Lockfile {
-  write: bool,
+  /// Set by `--lock-write`: the lock file is recreated from the modules that
+  /// are loaded, instead of checking them.
+  overwrite: bool,
+  /// Modules that are not in the lock file are errors instead of being
+  /// added.
+  frozen: bool,
+  has_changes: bool,
   map: BTreeMap<String, String>,
   pub filename: PathBuf,
 }
 
 impl Lockfile {
-  pub fn new(filename: PathBuf, write: bool) -> Result<Lockfile> {
-    let map = if write {
+  pub fn new(
+    filename: PathBuf,
+    overwrite: bool,
+    frozen: bool,
+  ) -> Result<Lockfile> {
+    let mut has_changes = false;
+    // A lock file that isn't frozen is created if it doesn't exist yet.
+    let map = if overwrite || (!frozen && !filename.exists()) {
       BTreeMap::new()
     } else {
       let s = std::fs::read_to_string(&filename)?;
-      serde_json::from_str(&s)?
+      let (map, is_current_version) = parse(&s)?;
+      // Upgrade lock files in the original format when they are written.
+      has_changes = !is_current_version;
+      map
     };
 
     Ok(Lockfile {
-      write,
+      overwrite,
+      frozen,
+      has_changes,
       map,
       filename,
     })
   }
 
-  // Synchronize lock file to disk - noop if --lock-write file is not specified.
+  /// The lock file next to the configuration file at `config_path`, if it is
+  /// a `deno.json` or `deno.jsonc`.
+  pub fn discover(config_path: &Path, frozen: bool) -> Result<Option<Self>> {
+    let is_deno_config = matches!(
+      config_path.file_name().and_then(|name| name.to_str()),
+      Some("deno.json") | Some("deno.jsonc")
+    );
+    if !is_deno_config {
+      return Ok(None);
+    }
+    let filename = config_path.with_file_name(DEFAULT_LOCKFILE_NAME);
+    Ok(Some(Self::new(filename, false, frozen)?))
+  }
+
+  /// The specifiers of the modules in the lock file.
+  pub fn specifiers(&self) -> impl Iterator<Item = &str> {
+    self.map.keys().map(|specifier| specifier.as_str())
+  }
+
+  // Synchronize lock file to disk - noop if nothing changed or the lock file
+  // is frozen, unless --lock-write is specified.
   pub fn write(&self) -> Result<()> {
-    if !self.write {
+    if !self.overwrite && (self.frozen || !self.has_changes) {
       return Ok(());
     }
-    let j = json!(&self.map);
+    let j = json!({
+      "version": VERSION,
+      "remote": &self.map,
+    });
     let s = serde_json::to_string_pretty(&j).unwrap();
     let mut f = std::fs::OpenOptions::new()
       .write(true)
@@ -43,31 +118,38 @@ impl Lockfile {
       .open(&self.filename)?;
     use std::io::Write;
     f.write_all(s.as_bytes())?;
+    f.write_all(b"\n")?;
     debug!("lockfile write {}", self.filename.display());
     Ok(())
   }
 
-  pub fn check_or_insert(&mut self, specifier: &str, code: &str) -> bool {
-    if self.write {
+  /// Checks the source of a module against the lock file. Modules that are
+  /// not in the lock file are added to it, unless it is frozen.
+  pub fn check_or_insert(
+    &mut self,
+    specifier: &str,
+    code: &str,
+  ) -> std::result::Result<(), LockfileError> {
+    if specifier.starts_with("file:") {
+      return Ok(());
+    }
+    if self.overwrite {
       // In case --lock-write is specified check always passes
       self.insert(specifier, code);
-      true
-    } else {
-      self.check(specifier, code)
+      return Ok(());
     }
-  }
-
-  /// Checks the given module is included.
-  /// Returns Ok(true) if check passed.
-  fn check(&mut self, specifier: &str, code: &str) -> bool {
-    if specifier.starts_with("file:") {
-      return true;
-    }
-    if let Some(lockfile_checksum) = self.map.get(specifier) {
-      let compiled_checksum = crate::checksum::gen(&[code.as_bytes()]);
-      lockfile_checksum == &compiled_checksum
-    } else {
-      false
+    let actual = crate::checksum::gen(&[code.as_bytes()]);
+    match self.map.get(specifier) {
+      Some(expected) if *expected == actual => Ok(()),
+      Some(expected) => Err(LockfileError::Mismatch {
+        expected: expected.clone(),
+        actual,
+      }),
+      None if self.frozen => Err(LockfileError::Missing),
+      None => {
+        self.insert(specifier, code);
+        Ok(())
+      }
     }
   }
 
@@ -77,6 +159,31 @@ impl Lockfile {
     }
     let checksum = crate::checksum::gen(&[code.as_bytes()]);
     self.map.insert(specifier.to_string(), checksum);
+    self.has_changes = true;
+  }
+}
+
+/// Parses a lock file of either format, returning its entries and whether it
+/// is in the current format.
+fn parse(s: &str) -> Result<(BTreeMap<String, String>, bool)> {
+  let invalid = |msg: &str| Error::new(ErrorKind::InvalidData, msg.to_string());
+  let value: Value = serde_json::from_str(s)?;
+  let object = value
+    .as_object()
+    .ok_or_else(|| invalid("The lock file must be a JSON object"))?;
+  match object.get("version") {
+    Some(Value::String(version)) if version == VERSION => {
+      let remote = match object.get("remote") {
+        Some(remote) => serde_json::from_value(remote.clone())?,
+        None => BTreeMap::new(),
+      };
+      Ok((remote, true))
+    }
+    Some(version) => Err(invalid(&format!(
+      "Unsupported lock file version {}, expected \"{}\"",
+      version, VERSION
+    ))),
+    None => Ok((serde_json::from_value(value)?, false)),
   }
 }
 
@@ -115,14 +222,16 @@ mod tests {
   #[test]
   fn new_nonexistent_lockfile() {
     let file_path = PathBuf::from("nonexistent_lock_file.json");
-    assert!(Lockfile::new(file_path, false).is_err());
+    assert!(Lockfile::new(file_path.clone(), false, true).is_err());
+    // Unless it's frozen, a missing lock file is created when written.
+    assert!(Lockfile::new(file_path, false, false).is_ok());
   }
 
   #[test]
   fn new_valid_lockfile() {
     let (temp_dir, file_path) = setup();
 
-    let result = Lockfile::new(file_path, false).unwrap();
+    let result = Lockfile::new(file_path, false, true).unwrap();
 
     let keys: Vec<String> = result.map.keys().cloned().collect();
     let expected_keys = vec![
@@ -140,7 +249,7 @@ mod tests {
   fn new_lockfile_from_file_and_insert() {
     let (temp_dir, file_path) = setup();
 
-    let mut lockfile = Lockfile::new(file_path, false).unwrap();
+    let mut lockfile = Lockfile::new(file_path, false, true).unwrap();
 
     lockfile.insert(
       "https://deno.land/std@0.71.0/io/util.ts",
@@ -163,7 +272,7 @@ mod tests {
   fn new_lockfile_and_write() {
     let (temp_dir, file_path) = setup();
 
-    let mut lockfile = Lockfile::new(file_path, true).unwrap();
+    let mut lockfile = Lockfile::new(file_path, true, false).unwrap();
 
     lockfile.insert(
       "https://deno.land/std@0.71.0/textproto/mod.ts",
@@ -192,7 +301,8 @@ mod tests {
 
     let contents_json =
       serde_json::from_str::<serde_json::Value>(&contents).unwrap();
-    let object = contents_json.as_object().unwrap();
+    assert_eq!(contents_json["version"], "2");
+    let object = contents_json["remote"].as_object().unwrap();
 
     assert_eq!(
       object
@@ -222,7 +332,7 @@ mod tests {
   fn check_or_insert_lockfile_false() {
     let (temp_dir, file_path) = setup();
 
-    let mut lockfile = Lockfile::new(file_path, false).unwrap();
+    let mut lockfile = Lockfile::new(file_path, false, true).unwrap();
 
     lockfile.insert(
       "https://deno.land/std@0.71.0/textproto/mod.ts",
@@ -233,14 +343,96 @@ mod tests {
       "https://deno.land/std@0.71.0/textproto/mod.ts",
       "Here is some source code",
     );
-    assert!(check_true);
+    assert!(check_true.is_ok());
 
     let check_false = lockfile.check_or_insert(
       "https://deno.land/std@0.71.0/textproto/mod.ts",
       "This is new Source code",
     );
-    assert!(!check_false);
+    assert_eq!(
+      check_false,
+      Err(LockfileError::Mismatch {
+        expected: crate::checksum::gen(&[b"Here is some source code"]),
+        actual: crate::checksum::gen(&[b"This is new Source code"]),
+      })
+    );
+
+    let check_missing = lockfile
+      .check_or_insert("https://deno.land/std@0.71.0/io/util.ts", "source");
+    assert_eq!(check_missing, Err(LockfileError::Missing));
+
+    teardown(temp_dir);
+  }
+
+  #[test]
+  fn check_or_insert_adds_new_modules() {
+    let (temp_dir, file_path) = setup();
+
+    let mut lockfile = Lockfile::new(file_path.clone(), false, false).unwrap();
+    assert!(lockfile
+      .check_or_insert("https://deno.land/std@0.71.0/io/util.ts", "source")
+      .is_ok());
+    // Known modules are still checked.
+    assert!(lockfile
+      .check_or_insert("https://deno.land/std@0.71.0/async/delay.ts", "x")
+      .is_err());
+    lockfile.write().unwrap();
+
+    let lockfile = Lockfile::new(file_path, false, true).unwrap();
+    let keys: Vec<&str> = lockfile.map.keys().map(|k| k.as_str()).collect();
+    assert_eq!(
+      keys,
+      vec![
+        "https://deno.land/std@0.71.0/async/delay.ts",
+        "https://deno.land/std@0.71.0/io/util.ts",
+        "https://deno.land/std@0.71.0/textproto/mod.ts",
+      ]
+    );
+
+    teardown(temp_dir);
+  }
+
+  #[test]
+  fn upgrade_original_format() {
+    // `setup()` writes a lock file in the original, flat format.
+    let (temp_dir, file_path) = setup();
+
+    let lockfile = Lockfile::new(file_path.clone(), false, false).unwrap();
+    lockfile.write().unwrap();
+    let contents: serde_json::Value =
+      serde_json::from_str(&std::fs::read_to_string(&file_path).unwrap())
+        .unwrap();
+    assert_eq!(
+      contents,
+      json!({
+        "version": "2",
+        "remote": {
+          "https://deno.land/std@0.71.0/async/delay.ts": "35957d585a6e3dd87706858fb1d6b551cb278271b03f52c5a2cb70e65e00c26a",
+          "https://deno.land/std@0.71.0/textproto/mod.ts": "3118d7a42c03c242c5a49c2ad91c8396110e14acca1324e7aaefd31a999b71a4"
+        }
+      })
+    );
+
+    // A frozen lock file is never written.
+    std::fs::write(&file_path, "{}").unwrap();
+    let lockfile = Lockfile::new(file_path.clone(), false, true).unwrap();
+    lockfile.write().unwrap();
+    assert_eq!(std::fs::read_to_string(&file_path).unwrap(), "{}");
+
+    std::fs::write(&file_path, r#"{ "version": "3", "remote": {} }"#).unwrap();
+    let err = Lockfile::new(file_path, false, false).unwrap_err();
+    assert!(err.to_string().contains("Unsupported lock file version"));
 
     teardown(temp_dir);
   }
+
+  #[test]
+  fn discover_next_to_config_file() {
+    let temp_dir = TempDir::new().expect("could not create temp dir");
+    let config_path = temp_dir.path().join("deno.json");
+    let lockfile = Lockfile::discover(&config_path, false).unwrap().unwrap();
+    assert_eq!(lockfile.filename, temp_dir.path().join("deno.lock"));
+    let tsconfig_path = temp_dir.path().join("tsconfig.json");
+    assert!(Lockfile::discover(&tsconfig_path, false).unwrap().is_none());
+  }
 }
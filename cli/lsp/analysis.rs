@@ -4,6 +4,7 @@ use super::language_server;
 use super::tsc;
 
 use crate::ast;
+use crate::compat;
 use crate::import_map::ImportMap;
 use crate::media_type::MediaType;
 use crate::module_graph::parse_deno_types;
@@ -151,6 +152,8 @@ pub enum ResolvedDependencyErr {
   InvalidLocalImport,
   InvalidSpecifier(ModuleResolutionError),
   Missing,
+  /// A `node:` or `npm:` specifier that can't be resolved.
+  Unsupported(String),
 }
 
 impl ResolvedDependencyErr {
@@ -166,6 +169,9 @@ impl ResolvedDependencyErr {
         lsp::NumberOrString::String("invalid-specifier".to_string())
       }
       Self::Missing => lsp::NumberOrString::String("missing".to_string()),
+      Self::Unsupported(_) => {
+        lsp::NumberOrString::String("unsupported-specifier".to_string())
+      }
     }
   }
 }
@@ -181,6 +187,7 @@ impl fmt::Display for ResolvedDependencyErr {
       }
       Self::InvalidSpecifier(err) => write!(f, "{}", err),
       Self::Missing => write!(f, "The module is unexpectedly missing."),
+      Self::Unsupported(message) => write!(f, "{}", message),
     }
   }
 }
@@ -195,6 +202,7 @@ pub fn resolve_import(
   specifier: &str,
   referrer: &ModuleSpecifier,
   maybe_import_map: &Option<ImportMap>,
+  compat: bool,
 ) -> ResolvedDependency {
   let maybe_mapped = if let Some(import_map) = maybe_import_map {
     if let Ok(maybe_specifier) =
@@ -207,6 +215,19 @@ pub fn resolve_import(
   } else {
     None
   };
+  // Node specifiers are resolved like in the module graph, see
+  // `compat::resolve()`.
+  let maybe_mapped = match maybe_mapped {
+    Some(mapped) => Some(mapped),
+    None => match compat::resolve(specifier, compat) {
+      Ok(maybe_resolved) => maybe_resolved,
+      Err(err) => {
+        return ResolvedDependency::Err(ResolvedDependencyErr::Unsupported(
+          err.to_string(),
+        ))
+      }
+    },
+  };
   let remapped = maybe_mapped.is_some();
   let specifier = if let Some(remapped) = maybe_mapped {
     remapped
@@ -242,6 +263,7 @@ pub fn analyze_dependencies(
   source: &str,
   media_type: &MediaType,
   maybe_import_map: &Option<ImportMap>,
+  compat: bool,
 ) -> Option<(HashMap<String, Dependency>, Option<ResolvedDependency>)> {
   let specifier_str = specifier.to_string();
   let source_map = Rc::new(swc_common::SourceMap::default());
@@ -258,12 +280,12 @@ pub fn analyze_dependencies(
           TypeScriptReference::Path(import) => {
             let dep = dependencies.entry(import.clone()).or_default();
             let resolved_import =
-              resolve_import(&import, specifier, maybe_import_map);
+              resolve_import(&import, specifier, maybe_import_map, compat);
             dep.maybe_code = Some(resolved_import);
           }
           TypeScriptReference::Types(import) => {
             let resolved_import =
-              resolve_import(&import, specifier, maybe_import_map);
+              resolve_import(&import, specifier, maybe_import_map, compat);
             if media_type == &MediaType::JavaScript
               || media_type == &MediaType::JSX
             {
@@ -283,13 +305,13 @@ pub fn analyze_dependencies(
       desc.kind != swc_ecmascript::dep_graph::DependencyKind::Require
     }) {
       let resolved_import =
-        resolve_import(&desc.specifier, specifier, maybe_import_map);
+        resolve_import(&desc.specifier, specifier, maybe_import_map, compat);
 
       let maybe_resolved_type_dependency =
         // Check for `@deno-types` pragmas that affect the import
         if let Some(comment) = desc.leading_comments.last() {
           if let Some(deno_types) = parse_deno_types(&comment.text).as_ref() {
-            Some(resolve_import(deno_types, specifier, maybe_import_map))
+            Some(resolve_import(deno_types, specifier, maybe_import_map, compat))
           } else {
             None
           }
@@ -331,6 +353,139 @@ pub fn analyze_dependencies(
   }
 }
 
+/// Returns the new specifier of `specifier` after `renames`, pairs of the old
+/// and new specifiers of files or directories, if it is affected by them.
+pub fn get_renamed_specifier(
+  specifier: &ModuleSpecifier,
+  renames: &[(ModuleSpecifier, ModuleSpecifier)],
+) -> Option<ModuleSpecifier> {
+  for (old, new) in renames {
+    if specifier == old {
+      return Some(new.clone());
+    }
+    // Only modules in a renamed directory are affected, not siblings that
+    // share a prefix, e.g. `a.tsx` when `a.ts` is renamed.
+    let old_dir = format!("{}/", old.as_str().trim_end_matches('/'));
+    if let Some(rest) = specifier.as_str().strip_prefix(&old_dir) {
+      let new_dir = format!("{}/", new.as_str().trim_end_matches('/'));
+      return new_dir.parse::<ModuleSpecifier>().ok()?.join(rest).ok();
+    }
+  }
+  None
+}
+
+/// Returns the relative import specifier of `specifier` from `referrer`,
+/// e.g. `./b.ts` or `../c/d.ts`, if they have the same origin.
+pub fn get_relative_specifier(
+  referrer: &ModuleSpecifier,
+  specifier: &ModuleSpecifier,
+) -> Option<String> {
+  if referrer.scheme() != specifier.scheme()
+    || referrer.host() != specifier.host()
+    || referrer.port() != specifier.port()
+  {
+    return None;
+  }
+  let mut referrer_dir = referrer.path_segments()?.collect::<Vec<_>>();
+  referrer_dir.pop();
+  let segments = specifier.path_segments()?.collect::<Vec<_>>();
+  let common = referrer_dir
+    .iter()
+    .zip(segments.iter())
+    .take_while(|(a, b)| a == b)
+    .count()
+    .min(segments.len() - 1);
+  let mut parts = vec![".."; referrer_dir.len() - common];
+  parts.extend(&segments[common..]);
+  let mut relative = parts.join("/");
+  if !relative.starts_with("../") {
+    relative = format!("./{}", relative);
+  }
+  if let Some(query) = specifier.query() {
+    relative = format!("{}?{}", relative, query);
+  }
+  Some(relative)
+}
+
+/// Returns the edits of the import specifiers of the module `specifier` that
+/// are affected by `renames` (see `get_renamed_specifier()`), either because
+/// the modules they refer to are renamed or because the module itself is
+/// moved. Relative specifiers stay relative and `file:` ones absolute, while
+/// bare specifiers are left to the import map. Deno doesn't resolve
+/// extensions or directory indexes, so a specifier always refers to a file by
+/// its full name, the new one after the rename.
+pub fn get_rename_file_edits(
+  specifier: &ModuleSpecifier,
+  source: &str,
+  media_type: &MediaType,
+  maybe_import_map: &Option<ImportMap>,
+  compat: bool,
+  renames: &[(ModuleSpecifier, ModuleSpecifier)],
+) -> Vec<lsp::TextEdit> {
+  let dependencies = if let Some((dependencies, _)) = analyze_dependencies(
+    specifier,
+    source,
+    media_type,
+    maybe_import_map,
+    compat,
+  ) {
+    dependencies
+  } else {
+    return Vec::new();
+  };
+  let maybe_new_referrer = get_renamed_specifier(specifier, renames);
+  let mut edits = Vec::new();
+  for (import, dependency) in dependencies.iter() {
+    let (resolved, range) = match (
+      &dependency.maybe_code,
+      &dependency.maybe_code_specifier_range,
+    ) {
+      (Some(ResolvedDependency::Resolved(resolved)), Some(range)) => {
+        (resolved, range)
+      }
+      _ => continue,
+    };
+    let maybe_new_resolved = get_renamed_specifier(resolved, renames);
+    let maybe_new_import =
+      if import.starts_with("./") || import.starts_with("../") {
+        if maybe_new_referrer.is_none() && maybe_new_resolved.is_none() {
+          continue;
+        }
+        get_relative_specifier(
+          maybe_new_referrer.as_ref().unwrap_or(specifier),
+          maybe_new_resolved.as_ref().unwrap_or(resolved),
+        )
+      } else if import.starts_with("file:") {
+        maybe_new_resolved.map(|s| s.to_string())
+      } else {
+        None
+      };
+    if let Some(new_import) = maybe_new_import {
+      if &new_import != import {
+        // The range of the specifier includes its quotes.
+        edits.push(lsp::TextEdit {
+          range: Range {
+            start: Position {
+              line: range.start.line,
+              character: range.start.character + 1,
+            },
+            end: Position {
+              line: range.end.line,
+              character: range.end.character - 1,
+            },
+          },
+          new_text: new_import,
+        });
+      }
+    }
+  }
+  edits.sort_by(|a, b| {
+    (a.range.start.line, a.range.start.character)
+      .cmp(&(b.range.start.line, b.range.start.character))
+  });
+  edits
+}
+
 #[derive(Debug, Deserialize, Serialize)]
 pub enum CodeLensSource {
   #[serde(rename = "implementations")]
@@ -695,8 +850,13 @@ mod tests {
     // @deno-types="https://deno.land/x/types/react/index.d.ts";
     import * as React from "https://cdn.skypack.dev/react";
     "#;
-    let actual =
-      analyze_dependencies(&specifier, source, &MediaType::TypeScript, &None);
+    let actual = analyze_dependencies(
+      &specifier,
+      source,
+      &MediaType::TypeScript,
+      &None,
+      false,
+    );
     assert!(actual.is_some());
     let (actual, maybe_type) = actual.unwrap();
     assert!(maybe_type.is_none());
@@ -744,4 +904,100 @@ mod tests {
       })
     );
   }
+
+  #[test]
+  fn test_get_relative_specifier() {
+    let referrer = resolve_url("file:///a/b/c.ts").unwrap();
+    let fixtures = vec![
+      ("file:///a/b/d.ts", Some("./d.ts")),
+      ("file:///a/b/e/f.ts", Some("./e/f.ts")),
+      ("file:///a/g.ts", Some("../g.ts")),
+      ("file:///h/i.ts?v=1", Some("../../h/i.ts?v=1")),
+      ("https://deno.land/x/mod.ts", None),
+    ];
+    for (specifier, expected) in fixtures {
+      let specifier = resolve_url(specifier).unwrap();
+      assert_eq!(
+        get_relative_specifier(&referrer, &specifier).as_deref(),
+        expected
+      );
+    }
+  }
+
+  #[test]
+  fn test_get_rename_file_edits() {
+    let source = r#"import { a } from "./a.ts";
+import { ab } from "./a.tsx";
+import { c } from "./dir/c.ts";
+import { d } from "file:///project/dir/d.ts";
+import { e } from "https://deno.land/x/e.ts";
+"#;
+    let renames = vec![
+      (
+        resolve_url("file:///project/a.ts").unwrap(),
+        resolve_url("file:///project/renamed.ts").unwrap(),
+      ),
+      (
+        resolve_url("file:///project/dir").unwrap(),
+        resolve_url("file:///project/other").unwrap(),
+      ),
+    ];
+    let specifier = resolve_url("file:///project/main.ts").unwrap();
+    let edits = get_rename_file_edits(
+      &specifier,
+      source,
+      &MediaType::TypeScript,
+      &None,
+      false,
+      &renames,
+    );
+    let range = |line, start, end| Range {
+      start: Position {
+        line,
+        character: start,
+      },
+      end: Position {
+        line,
+        character: end,
+      },
+    };
+    assert_eq!(
+      edits,
+      vec![
+        lsp::TextEdit {
+          range: range(0, 19, 25),
+          new_text: "./renamed.ts".to_string(),
+        },
+        lsp::TextEdit {
+          range: range(2, 19, 29),
+          new_text: "./other/c.ts".to_string(),
+        },
+        lsp::TextEdit {
+          range: range(3, 19, 43),
+          new_text: "file:///project/other/d.ts".to_string(),
+        },
+      ]
+    );
+
+    // A moved module keeps referring to the modules it imports.
+    let renames = vec![(
+      resolve_url("file:///project/main.ts").unwrap(),
+      resolve_url("file:///project/sub/main.ts").unwrap(),
+    )];
+    let edits = get_rename_file_edits(
+      &specifier,
+      source,
+      &MediaType::TypeScript,
+      &None,
+      false,
+      &renames,
+    );
+    assert_eq!(
+      edits
+        .iter()
+        .map(|e| e.new_text.as_str())
+        .collect::<Vec<_>>(),
+      vec!["../a.ts", "../a.tsx", "../dir/c.ts"]
+    );
+  }
 }
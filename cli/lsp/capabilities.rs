@@ -11,6 +11,9 @@ use lspower::lsp::CodeActionOptions;
 use lspower::lsp::CodeActionProviderCapability;
 use lspower::lsp::CodeLensOptions;
 use lspower::lsp::CompletionOptions;
+use lspower::lsp::FileOperationFilter;
+use lspower::lsp::FileOperationPattern;
+use lspower::lsp::FileOperationRegistrationOptions;
 use lspower::lsp::HoverProviderCapability;
 use lspower::lsp::ImplementationProviderCapability;
 use lspower::lsp::OneOf;
@@ -21,6 +24,8 @@ use lspower::lsp::TextDocumentSyncCapability;
 use lspower::lsp::TextDocumentSyncKind;
 use lspower::lsp::TextDocumentSyncOptions;
 use lspower::lsp::WorkDoneProgressOptions;
+use lspower::lsp::WorkspaceFileOperationsServerCapabilities;
+use lspower::lsp::WorkspaceServerCapabilities;
 
 fn code_action_capabilities(
   client_capabilities: &ClientCapabilities,
@@ -106,7 +111,22 @@ pub fn server_capabilities(
     execute_command_provider: None,
     call_hierarchy_provider: None,
     semantic_tokens_provider: None,
-    workspace: None,
+    workspace: Some(WorkspaceServerCapabilities {
+      workspace_folders: None,
+      file_operations: Some(WorkspaceFileOperationsServerCapabilities {
+        will_rename: Some(FileOperationRegistrationOptions {
+          filters: vec![FileOperationFilter {
+            scheme: Some("file".to_string()),
+            pattern: FileOperationPattern {
+              glob: "**/*".to_string(),
+              matches: None,
+              options: None,
+            },
+          }],
+        }),
+        ..Default::default()
+      }),
+    }),
     experimental: None,
     linked_editing_range_provider: None,
     moniker_provider: None,
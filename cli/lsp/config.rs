@@ -28,6 +28,9 @@ pub struct CodeLensSettings {
   /// an impact, the `references` flag needs to be `true`.
   #[serde(default)]
   pub references_all_functions: bool,
+  /// Flag for providing code lenses that run and debug tests.
+  #[serde(default)]
+  pub test: bool,
 }
 
 #[derive(Debug, Default, Deserialize)]
@@ -42,6 +45,10 @@ pub struct WorkspaceSettings {
   pub lint: bool,
   #[serde(default)]
   pub unstable: bool,
+
+  /// Resolve bare names of Node built-in modules, like `--compat`.
+  #[serde(default)]
+  pub compat: bool,
 }
 
 impl WorkspaceSettings {
@@ -50,7 +57,7 @@ impl WorkspaceSettings {
   pub fn enabled_code_lens(&self) -> bool {
     if let Some(code_lens) = &self.code_lens {
       // This should contain all the "top level" code lens references
-      code_lens.implementations || code_lens.references
+      code_lens.implementations || code_lens.references || code_lens.test
     } else {
       false
     }
@@ -79,6 +86,14 @@ impl WorkspaceSettings {
       false
     }
   }
+
+  pub fn enabled_code_lens_test(&self) -> bool {
+    if let Some(code_lens) = &self.code_lens {
+      code_lens.test
+    } else {
+      false
+    }
+  }
 }
 
 #[derive(Debug, Default)]
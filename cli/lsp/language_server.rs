@@ -18,16 +18,20 @@ use lspower::Client;
 use regex::Regex;
 use serde_json::from_value;
 use std::cell::RefCell;
+use std::collections::BTreeMap;
 use std::collections::HashMap;
 use std::env;
 use std::path::PathBuf;
 use std::rc::Rc;
 use std::sync::Arc;
 use tokio::fs;
+use tokio::sync::mpsc;
 
 use crate::deno_dir;
+use crate::fs_util;
 use crate::import_map::ImportMap;
 use crate::media_type::MediaType;
+use crate::tools::task::parse_jsonc;
 use crate::tsc_config::parse_config;
 use crate::tsc_config::TsConfig;
 
@@ -47,6 +51,8 @@ use super::documents::DocumentCache;
 use super::performance::Performance;
 use super::sources;
 use super::sources::Sources;
+use super::testing;
+use super::testing::TestDefinition;
 use super::text;
 use super::text::LineIndex;
 use super::tsc;
@@ -95,6 +101,8 @@ pub(crate) struct Inner {
   navigation_trees: HashMap<ModuleSpecifier, tsc::NavigationTree>,
   /// A collection of measurements which instrument that performance of the LSP.
   performance: Performance,
+  /// The tests registered in each of the open documents.
+  tests: HashMap<ModuleSpecifier, Vec<TestDefinition>>,
   /// Cached sources that are read-only.
   sources: Sources,
   /// A memoized version of fixable diagnostic codes retrieved from TypeScript.
@@ -131,6 +139,7 @@ impl Inner {
       navigation_trees: Default::default(),
       performance: Default::default(),
       sources,
+      tests: Default::default(),
       ts_fixable_diagnostics: Default::default(),
       ts_server: TsServer::new(),
       url_map: Default::default(),
@@ -149,6 +158,7 @@ impl Inner {
       source,
       &MediaType::from(specifier),
       &self.maybe_import_map,
+      self.config.settings.compat,
     ) {
       for (_, dep) in deps.iter_mut() {
         if dep.maybe_type.is_none() {
@@ -164,6 +174,14 @@ impl Inner {
     }
   }
 
+  /// Finds the tests registered in a document that has been opened in the
+  /// editor, which get code lenses to run them.
+  fn analyze_tests(&mut self, specifier: &ModuleSpecifier, source: &str) {
+    let tests =
+      testing::collect_tests(specifier, source, &MediaType::from(specifier));
+    self.tests.insert(specifier.clone(), tests);
+  }
+
   fn enabled(&self) -> bool {
     self.config.settings.enable
   }
@@ -400,13 +418,86 @@ impl Inner {
     }
   }
 
+  /// Resolves the `config` setting against the root of the workspace.
+  fn resolve_config_url(&self) -> Result<Option<Url>, AnyError> {
+    let config_str = match &self.config.settings.config {
+      Some(config_str) => config_str,
+      None => return Ok(None),
+    };
+    let config_url = if let Ok(url) = Url::from_file_path(config_str) {
+      Ok(url)
+    } else if let Some(root_uri) = &self.config.root_uri {
+      let root_path = root_uri
+        .to_file_path()
+        .map_err(|_| anyhow!("Bad root_uri: {}", root_uri))?;
+      let config_path = root_path.join(config_str);
+      Url::from_file_path(config_path).map_err(|_| {
+        anyhow!("Bad file path for configuration file: \"{}\"", config_str)
+      })
+    } else {
+      Err(anyhow!(
+        "The path to the configuration file (\"{}\") is not resolvable.",
+        config_str
+      ))
+    }?;
+    Ok(Some(config_url))
+  }
+
+  async fn read_config_file(
+    config_url: &Url,
+  ) -> Result<(PathBuf, String), AnyError> {
+    let config_path = config_url
+      .to_file_path()
+      .map_err(|_| anyhow!("Bad file path."))?;
+    let config_text =
+      fs::read_to_string(config_path.clone())
+        .await
+        .map_err(|err| {
+          anyhow!(
+            "Failed to load the configuration file at: {}. [{}]",
+            config_url,
+            err
+          )
+        })?;
+    Ok((config_path, config_text))
+  }
+
+  /// Reads the import map embedded in the configuration file as its
+  /// `"imports"` and `"scopes"`, if any.
+  async fn read_embedded_import_map(
+    &self,
+  ) -> Result<Option<ImportMap>, AnyError> {
+    let config_url = match self.resolve_config_url()? {
+      Some(config_url) => config_url,
+      None => return Ok(None),
+    };
+    let (_, config_text) = Self::read_config_file(&config_url).await?;
+    let config = parse_jsonc(&config_text)?;
+    Ok(ImportMap::from_config(&config_url, &config)?)
+  }
+
   pub async fn update_import_map(&mut self) -> Result<(), AnyError> {
     let mark = self.performance.mark("update_import_map");
     let (maybe_import_map, maybe_root_uri) = {
       let config = &self.config;
       (config.settings.import_map.clone(), config.root_uri.clone())
     };
-    if let Some(import_map_str) = &maybe_import_map {
+    let maybe_embedded_import_map = self.read_embedded_import_map().await?;
+    if let (Some(import_map_str), Some(_)) =
+      (&maybe_import_map, &maybe_embedded_import_map)
+    {
+      return Err(anyhow!(
+        "Both the import map setting (\"{}\") and the \"imports\" or \"scopes\" of the configuration file supply an import map. Remove one of them.",
+        import_map_str
+      ));
+    }
+    if let Some(import_map) = maybe_embedded_import_map {
+      info!("Updating import map from the configuration file");
+      // The map is reloaded along with the configuration file, and isn't
+      // passed to `deno test` with `--import-map`.
+      self.maybe_import_map_uri = None;
+      self.maybe_import_map = Some(import_map);
+    } else if let Some(import_map_str) = &maybe_import_map {
       info!("Updating import map from: \"{}\"", import_map_str);
       let import_map_url = if let Ok(url) = Url::from_file_path(import_map_str)
       {
@@ -461,7 +552,7 @@ impl Inner {
       "strict": true,
       "target": "esnext",
     }));
-    let (maybe_config, maybe_root_uri) = {
+    {
       let config = &self.config;
       if config.settings.unstable {
         let unstable_libs = json!({
@@ -469,39 +560,11 @@ impl Inner {
         });
         tsconfig.merge(&unstable_libs);
       }
-      (config.settings.config.clone(), config.root_uri.clone())
-    };
-    if let Some(config_str) = &maybe_config {
-      info!("Updating TypeScript configuration from: \"{}\"", config_str);
-      let config_url = if let Ok(url) = Url::from_file_path(config_str) {
-        Ok(url)
-      } else if let Some(root_uri) = &maybe_root_uri {
-        let root_path = root_uri
-          .to_file_path()
-          .map_err(|_| anyhow!("Bad root_uri: {}", root_uri))?;
-        let config_path = root_path.join(config_str);
-        Url::from_file_path(config_path).map_err(|_| {
-          anyhow!("Bad file path for configuration file: \"{}\"", config_str)
-        })
-      } else {
-        Err(anyhow!(
-          "The path to the configuration file (\"{}\") is not resolvable.",
-          config_str
-        ))
-      }?;
-      let config_path = config_url
-        .to_file_path()
-        .map_err(|_| anyhow!("Bad file path."))?;
-      let config_text =
-        fs::read_to_string(config_path.clone())
-          .await
-          .map_err(|err| {
-            anyhow!(
-              "Failed to load the configuration file at: {}. [{}]",
-              config_url,
-              err
-            )
-          })?;
+    }
+    if let Some(config_url) = self.resolve_config_url()? {
+      info!("Updating TypeScript configuration from: \"{}\"", config_url);
+      let (config_path, config_text) =
+        Self::read_config_file(&config_url).await?;
       let (value, maybe_ignored_options) =
         parse_config(&config_text, &config_path)?;
       tsconfig.merge(&value);
@@ -671,6 +734,7 @@ impl Inner {
       &params.text_document.text,
     );
     self.analyze_dependencies(&specifier, &params.text_document.text);
+    self.analyze_tests(&specifier, &params.text_document.text);
     self.performance.measure(mark);
 
     // TODO(@kitsonk): how to better lazily do this?
@@ -687,7 +751,10 @@ impl Inner {
       params.text_document.version,
       params.content_changes,
     ) {
-      Ok(Some(source)) => self.analyze_dependencies(&specifier, &source),
+      Ok(Some(source)) => {
+        self.analyze_dependencies(&specifier, &source);
+        self.analyze_tests(&specifier, &source);
+      }
       Ok(_) => error!("No content returned from change."),
       Err(err) => error!("{}", err),
     }
@@ -710,6 +777,7 @@ impl Inner {
     let specifier = self.url_map.normalize_url(&params.text_document.uri);
     self.documents.close(&specifier);
     self.navigation_trees.remove(&specifier);
+    self.tests.remove(&specifier);
 
     self.performance.measure(mark);
     // TODO(@kitsonk): how to better lazily do this?
@@ -786,9 +854,16 @@ impl Inner {
         }
       }
     }
-    // if the current tsconfig has changed, we need to reload it
+    // if the current tsconfig has changed, we need to reload it, along with
+    // the import map it may embed
     if let Some(config_uri) = &self.maybe_config_uri {
       if params.changes.iter().any(|fe| *config_uri == fe.uri) {
+        if let Err(err) = self.update_import_map().await {
+          self
+            .client
+            .show_message(MessageType::Warning, err.to_string())
+            .await;
+        }
         if let Err(err) = self.update_tsconfig().await {
           self
             .client
@@ -1171,8 +1246,18 @@ impl Inner {
       }
     });
 
+    let mut code_lenses = Rc::try_unwrap(cl).unwrap().into_inner();
+    if self.config.settings.enabled_code_lens_test() {
+      if let Some(tests) = self.tests.get(&specifier) {
+        for test in tests {
+          code_lenses
+            .extend(testing::to_code_lenses(&params.text_document.uri, test));
+        }
+      }
+    }
+
     self.performance.measure(mark);
-    Ok(Some(Rc::try_unwrap(cl).unwrap().into_inner()))
+    Ok(Some(code_lenses))
   }
 
   async fn code_lens_resolve(
@@ -1661,6 +1746,17 @@ impl Inner {
 
     if let Some(locations) = maybe_locations {
       let rename_locations = tsc::RenameLocations { locations };
+      if let Some(specifier) = rename_locations.find_read_only_specifier() {
+        self.performance.measure(mark);
+        return Err(LspError {
+          code: lspower::jsonrpc::ErrorCode::InvalidRequest,
+          message: format!(
+            "Cannot rename a symbol declared in a read-only module ({}).",
+            specifier
+          ),
+          data: None,
+        });
+      }
       let workspace_edits = rename_locations
         .into_workspace_edit(&params.new_name, self)
         .await
@@ -1676,6 +1772,118 @@ impl Inner {
     }
   }
 
+  async fn will_rename_files(
+    &mut self,
+    params: RenameFilesParams,
+  ) -> LspResult<Option<WorkspaceEdit>> {
+    if !self.enabled() {
+      return Ok(None);
+    }
+    let mark = self.performance.mark("will_rename_files");
+    // Only local files can be renamed, remote modules are read-only.
+    let renames = params
+      .files
+      .iter()
+      .filter_map(|rename| {
+        let old = resolve_url(&rename.old_uri).ok()?;
+        let new = resolve_url(&rename.new_uri).ok()?;
+        if old.scheme() == "file" && new.scheme() == "file" {
+          Some((old, new))
+        } else {
+          None
+        }
+      })
+      .collect::<Vec<_>>();
+    if renames.is_empty() {
+      self.performance.measure(mark);
+      return Ok(None);
+    }
+
+    // Any of the open documents and of the modules in the workspace can
+    // import the renamed files.
+    let mut specifiers = self
+      .documents
+      .open_specifiers()
+      .into_iter()
+      .filter(|s| s.scheme() == "file")
+      .cloned()
+      .collect::<Vec<_>>();
+    if let Some(root_path) = self
+      .config
+      .root_uri
+      .as_ref()
+      .and_then(|root_uri| root_uri.to_file_path().ok())
+    {
+      let paths = fs_util::collect_files(&[root_path], &[], |path| {
+        fs_util::is_supported_ext(path)
+      })
+      .unwrap_or_default();
+      specifiers.extend(paths.into_iter().filter_map(|path| {
+        let specifier = Url::from_file_path(path).ok()?;
+        if self.documents.contains_key(&specifier) {
+          None
+        } else {
+          Some(specifier)
+        }
+      }));
+    }
+
+    let mut document_edits = BTreeMap::new();
+    for specifier in specifiers {
+      let source = if self.documents.contains_key(&specifier) {
+        self.documents.content(&specifier).ok().flatten()
+      } else if let Ok(path) = specifier.to_file_path() {
+        fs::read_to_string(path).await.ok()
+      } else {
+        None
+      };
+      let source = if let Some(source) = source {
+        source
+      } else {
+        continue;
+      };
+      let edits = analysis::get_rename_file_edits(
+        &specifier,
+        &source,
+        &MediaType::from(&specifier),
+        &self.maybe_import_map,
+        self.config.settings.compat,
+        &renames,
+      );
+      if !edits.is_empty() {
+        document_edits.insert(specifier, edits);
+      }
+    }
+    self.performance.measure(mark);
+    if document_edits.is_empty() {
+      return Ok(None);
+    }
+
+    let mut text_document_edits = Vec::new();
+    for (specifier, edits) in document_edits {
+      let uri =
+        self
+          .url_map
+          .normalize_specifier(&specifier)
+          .map_err(|err| {
+            error!("Failed to normalize specifier: {}", err);
+            LspError::internal_error()
+          })?;
+      text_document_edits.push(TextDocumentEdit {
+        text_document: OptionalVersionedTextDocumentIdentifier {
+          uri,
+          version: self.documents.version(&specifier),
+        },
+        edits: edits.into_iter().map(OneOf::Left).collect(),
+      });
+    }
+    Ok(Some(WorkspaceEdit {
+      changes: None,
+      document_changes: Some(DocumentChanges::Edits(text_document_edits)),
+      change_annotations: None,
+    }))
+  }
+
   async fn request_else(
     &mut self,
     method: &str,
@@ -1693,6 +1901,18 @@ impl Inner {
         None => Err(LspError::invalid_params("Missing parameters")),
       },
       "deno/performance" => Ok(Some(self.get_performance())),
+      "workspace/willRenameFiles" => match params.map(serde_json::from_value) {
+        Some(Ok(params)) => Ok(Some(
+          serde_json::to_value(self.will_rename_files(params).await?).map_err(
+            |err| {
+              error!("Failed to serialize will_rename_files response: {}", err);
+              LspError::internal_error()
+            },
+          )?,
+        )),
+        Some(Err(err)) => Err(LspError::invalid_params(err.to_string())),
+        None => Err(LspError::invalid_params("Missing parameters")),
+      },
       "deno/virtualTextDocument" => match params.map(serde_json::from_value) {
         Some(Ok(params)) => Ok(Some(
           serde_json::to_value(self.virtual_text_document(params).await?)
@@ -1908,6 +2128,14 @@ impl lspower::LanguageServer for LanguageServer {
     method: &str,
     params: Option<Value>,
   ) -> LspResult<Option<Value>> {
+    // A test run can take a while, so the server isn't locked during it.
+    if method == testing::TEST_RUN_REQUEST {
+      let (client, run) = {
+        let inner = self.0.lock().await;
+        (inner.client.clone(), inner.test_run(params)?)
+      };
+      return run_test(client, run).await;
+    }
     self.0.lock().await.request_else(method, params).await
   }
 
@@ -2034,6 +2262,81 @@ impl Inner {
     self.performance.measure(mark);
     Ok(contents)
   }
+
+  /// Prepares a `deno/testRun` request, which runs a test with the
+  /// configuration of the workspace.
+  fn test_run(&self, params: Option<Value>) -> LspResult<testing::TestRun> {
+    let params: testing::TestRunParams = match params.map(from_value) {
+      Some(Ok(params)) => params,
+      Some(Err(err)) => return Err(LspError::invalid_params(err.to_string())),
+      None => return Err(LspError::invalid_params("Missing parameters")),
+    };
+    let specifier = self.url_map.normalize_url(&params.text_document.uri);
+    let maybe_range = self.tests.get(&specifier).and_then(|tests| {
+      tests
+        .iter()
+        .find(|test| test.name == params.name)
+        .map(|test| test.range)
+    });
+    let mut args = vec![];
+    if let Some(config_uri) = &self.maybe_config_uri {
+      if let Ok(config_path) = config_uri.to_file_path() {
+        args.push("--config".to_string());
+        args.push(config_path.to_string_lossy().to_string());
+      }
+    }
+    if let Some(import_map_uri) = &self.maybe_import_map_uri {
+      args.push("--import-map".to_string());
+      args.push(import_map_uri.to_string());
+    }
+    if self.config.settings.unstable {
+      args.push("--unstable".to_string());
+    }
+    let maybe_cwd = self
+      .config
+      .root_uri
+      .as_ref()
+      .and_then(|uri| uri.to_file_path().ok());
+    Ok(testing::TestRun {
+      id: params.id,
+      url: params.text_document.uri,
+      specifier,
+      name: params.name,
+      maybe_range,
+      inspect: params.inspect,
+      args,
+      maybe_cwd,
+    })
+  }
+}
+
+/// Runs the test of a `deno/testRun` request, forwarding its progress to the
+/// client as it happens.
+async fn run_test(
+  client: Client,
+  run: testing::TestRun,
+) -> LspResult<Option<Value>> {
+  let deno_exe = env::current_exe().map_err(|err| {
+    error!("Unable to get the current executable: {}", err);
+    LspError::internal_error()
+  })?;
+  let (sender, mut receiver) = mpsc::unbounded_channel();
+  let forward_progress = async {
+    while let Some(params) = receiver.recv().await {
+      client
+        .send_custom_notification::<testing::TestRunProgressNotification>(
+          params,
+        )
+        .await;
+    }
+  };
+  let (result, _) =
+    tokio::join!(testing::run_test(run, &deno_exe, sender), forward_progress);
+  result.map_err(|err| {
+    error!("Failed to run test: {}", err);
+    LspError::internal_error()
+  })?;
+  Ok(None)
 }
 
 #[cfg(test)]
@@ -2512,6 +2815,197 @@ mod tests {
     harness.run().await;
   }
 
+  #[tokio::test]
+  async fn test_rename_across_files() {
+    let mut harness = LspTestHarness::new(vec![
+      ("initialize_request.json", LspResponse::RequestAny),
+      ("initialized_notification.json", LspResponse::None),
+      ("rename_did_open_notification_a.json", LspResponse::None),
+      ("rename_did_open_notification_b.json", LspResponse::None),
+      ("rename_did_open_notification_c.json", LspResponse::None),
+      (
+        "rename_request_across_files.json",
+        LspResponse::Request(
+          2,
+          json!({
+            "documentChanges": [{
+              "textDocument": {
+                "uri": "file:///a/a.ts",
+                "version": 1,
+              },
+              "edits": [{
+                "range": {
+                  "start": {
+                    "line": 0,
+                    "character": 13
+                  },
+                  "end": {
+                    "line": 0,
+                    "character": 16
+                  }
+                },
+                "newText": "renamed"
+              }]
+            }, {
+              "textDocument": {
+                "uri": "file:///a/b.ts",
+                "version": 1,
+              },
+              "edits": [{
+                "range": {
+                  "start": {
+                    "line": 0,
+                    "character": 9
+                  },
+                  "end": {
+                    "line": 0,
+                    "character": 12
+                  }
+                },
+                "newText": "renamed"
+              }, {
+                "range": {
+                  "start": {
+                    "line": 2,
+                    "character": 12
+                  },
+                  "end": {
+                    "line": 2,
+                    "character": 15
+                  }
+                },
+                "newText": "renamed"
+              }]
+            }, {
+              "textDocument": {
+                "uri": "file:///a/c.ts",
+                "version": 1,
+              },
+              "edits": [{
+                "range": {
+                  "start": {
+                    "line": 0,
+                    "character": 9
+                  },
+                  "end": {
+                    "line": 0,
+                    "character": 12
+                  }
+                },
+                "newText": "renamed"
+              }, {
+                "range": {
+                  "start": {
+                    "line": 2,
+                    "character": 19
+                  },
+                  "end": {
+                    "line": 2,
+                    "character": 22
+                  }
+                },
+                "newText": "renamed"
+              }]
+            }]
+          }),
+        ),
+      ),
+      (
+        "shutdown_request.json",
+        LspResponse::Request(3, json!(null)),
+      ),
+      ("exit_notification.json", LspResponse::None),
+    ]);
+    harness.run().await;
+  }
+
+  #[tokio::test]
+  async fn test_rename_read_only() {
+    let mut harness = LspTestHarness::new(vec![
+      ("initialize_request.json", LspResponse::RequestAny),
+      ("initialized_notification.json", LspResponse::None),
+      ("rename_did_open_notification_lib.json", LspResponse::None),
+      (
+        "rename_request_lib.json",
+        LspResponse::RequestAssert(|value| {
+          assert_eq!(value["id"], 2);
+          assert_eq!(value["error"]["code"], -32600);
+          assert!(value["error"]["message"].as_str().unwrap().starts_with(
+            "Cannot rename a symbol declared in a read-only module"
+          ));
+        }),
+      ),
+      (
+        "shutdown_request.json",
+        LspResponse::Request(3, json!(null)),
+      ),
+      ("exit_notification.json", LspResponse::None),
+    ]);
+    harness.run().await;
+  }
+
+  #[tokio::test]
+  async fn test_will_rename_files() {
+    let mut harness = LspTestHarness::new(vec![
+      ("initialize_request.json", LspResponse::RequestAny),
+      ("initialized_notification.json", LspResponse::None),
+      ("rename_did_open_notification_a.json", LspResponse::None),
+      ("rename_did_open_notification_b.json", LspResponse::None),
+      ("rename_did_open_notification_c.json", LspResponse::None),
+      (
+        "will_rename_files_request.json",
+        LspResponse::Request(
+          2,
+          json!({
+            "documentChanges": [{
+              "textDocument": {
+                "uri": "file:///a/b.ts",
+                "version": 1,
+              },
+              "edits": [{
+                "range": {
+                  "start": {
+                    "line": 0,
+                    "character": 21
+                  },
+                  "end": {
+                    "line": 0,
+                    "character": 27
+                  }
+                },
+                "newText": "./lib/renamed.ts"
+              }]
+            }, {
+              "textDocument": {
+                "uri": "file:///a/c.ts",
+                "version": 1,
+              },
+              "edits": [{
+                "range": {
+                  "start": {
+                    "line": 0,
+                    "character": 21
+                  },
+                  "end": {
+                    "line": 0,
+                    "character": 27
+                  }
+                },
+                "newText": "./lib/renamed.ts"
+              }]
+            }]
+          }),
+        ),
+      ),
+      (
+        "shutdown_request.json",
+        LspResponse::Request(3, json!(null)),
+      ),
+      ("exit_notification.json", LspResponse::None),
+    ]);
+    harness.run().await;
+  }
+
   #[tokio::test]
   async fn test_code_lens_request() {
     let mut harness = LspTestHarness::new(vec![
@@ -2614,6 +3108,103 @@ mod tests {
     harness.run().await;
   }
 
+  #[tokio::test]
+  async fn test_code_lens_test_request() {
+    let mut harness = LspTestHarness::new(vec![
+      (
+        "initialize_request_test_code_lens.json",
+        LspResponse::RequestAny,
+      ),
+      ("initialized_notification.json", LspResponse::None),
+      (
+        "did_open_notification_test_code_lens.json",
+        LspResponse::None,
+      ),
+      (
+        "code_lens_request.json",
+        LspResponse::Request(
+          2,
+          json!([
+            {
+              "range": {
+                "start": {
+                  "line": 0,
+                  "character": 0,
+                },
+                "end": {
+                  "line": 0,
+                  "character": 29,
+                }
+              },
+              "command": {
+                "title": "▶\u{fe0e} Run Test",
+                "command": "deno.test",
+                "arguments": ["file:///a/file.ts", "passes", { "inspect": false }],
+              },
+            },
+            {
+              "range": {
+                "start": {
+                  "line": 0,
+                  "character": 0,
+                },
+                "end": {
+                  "line": 0,
+                  "character": 29,
+                }
+              },
+              "command": {
+                "title": "Debug",
+                "command": "deno.test",
+                "arguments": ["file:///a/file.ts", "passes", { "inspect": true }],
+              },
+            },
+            {
+              "range": {
+                "start": {
+                  "line": 2,
+                  "character": 0,
+                },
+                "end": {
+                  "line": 5,
+                  "character": 2,
+                }
+              },
+              "command": {
+                "title": "▶\u{fe0e} Run Test",
+                "command": "deno.test",
+                "arguments": ["file:///a/file.ts", "object", { "inspect": false }],
+              },
+            },
+            {
+              "range": {
+                "start": {
+                  "line": 2,
+                  "character": 0,
+                },
+                "end": {
+                  "line": 5,
+                  "character": 2,
+                }
+              },
+              "command": {
+                "title": "Debug",
+                "command": "deno.test",
+                "arguments": ["file:///a/file.ts", "object", { "inspect": true }],
+              },
+            },
+          ]),
+        ),
+      ),
+      (
+        "shutdown_request.json",
+        LspResponse::Request(3, json!(null)),
+      ),
+      ("exit_notification.json", LspResponse::None),
+    ]);
+    harness.run().await;
+  }
+
   #[tokio::test]
   async fn test_signature_help() {
     let mut harness = LspTestHarness::new(vec![
@@ -11,6 +11,7 @@ mod documents;
 mod language_server;
 mod performance;
 mod sources;
+mod testing;
 mod text;
 mod tsc;
 mod urls;
@@ -3,6 +3,7 @@
 use super::analysis;
 use super::text::LineIndex;
 
+use crate::compat;
 use crate::file_fetcher::get_source_from_bytes;
 use crate::file_fetcher::map_content_type;
 use crate::file_fetcher::SUPPORTED_SCHEMES;
@@ -76,6 +77,11 @@ fn resolve_specifier(
   http_cache: &HttpCache,
 ) -> Option<ModuleSpecifier> {
   let scheme = specifier.scheme();
+  // The polyfills of Node built-in modules are fetched like redirects.
+  if scheme == "node" {
+    let polyfill = compat::node_builtin_url(specifier).ok()?;
+    return resolve_specifier(&polyfill, redirects, http_cache);
+  }
   if !SUPPORTED_SCHEMES.contains(&scheme) {
     return None;
   }
@@ -123,6 +129,7 @@ impl Metadata {
         source,
         media_type,
         maybe_import_map,
+        false,
       ) {
       (Some(dependencies), maybe_types)
     } else {
@@ -309,7 +316,7 @@ impl Inner {
         map_content_type(specifier, maybe_content_type);
       let source = get_source_from_bytes(bytes, maybe_charset).ok()?;
       let maybe_types = headers.get("x-typescript-types").map(|s| {
-        analysis::resolve_import(s, &specifier, &self.maybe_import_map)
+        analysis::resolve_import(s, &specifier, &self.maybe_import_map, false)
       });
       (source, media_type, maybe_types)
     };
@@ -0,0 +1,671 @@
+// Copyright 2018-2021 the Deno authors. All rights reserved. MIT license.
+
+//! Running tests from the editor. `collect_tests()` finds the `Deno.test()`
+//! registrations of a document, which get "Run Test" and "Debug" code lenses,
+//! and `run_test()` runs one of them in a `deno test` subprocess for the
+//! `deno/testRun` request, reporting its progress with `deno/testRunProgress`
+//! notifications, whose payloads follow the `TestRun` of the VS Code testing
+//! API.
+
+use crate::ast;
+use crate::media_type::MediaType;
+
+use deno_core::error::AnyError;
+use deno_core::serde::Deserialize;
+use deno_core::serde::Serialize;
+use deno_core::serde_json;
+use deno_core::ModuleSpecifier;
+use lspower::lsp;
+use regex::Regex;
+use std::path::Path;
+use std::path::PathBuf;
+use std::process::Stdio;
+use std::time::Instant;
+use swc_common::Span;
+use swc_ecmascript::ast::Expr;
+use swc_ecmascript::ast::ExprOrSuper;
+use swc_ecmascript::ast::Lit;
+use swc_ecmascript::ast::Prop;
+use swc_ecmascript::ast::PropName;
+use swc_ecmascript::ast::PropOrSpread;
+use swc_ecmascript::visit::Node;
+use swc_ecmascript::visit::Visit;
+use tokio::sync::mpsc;
+
+pub const TEST_RUN_REQUEST: &str = "deno/testRun";
+
+/// A test registered with `Deno.test()` whose name is known statically.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TestDefinition {
+  pub name: String,
+  /// The range of the `Deno.test()` call.
+  pub range: lsp::Range,
+}
+
+struct TestCollector {
+  tests: Vec<(String, Span)>,
+}
+
+/// The name of a test registered with `Deno.test(arg, ...)` given `arg`,
+/// which is either the name, a test definition object or a named function.
+fn get_test_name(arg: &Expr) -> Option<String> {
+  match arg {
+    Expr::Lit(Lit::Str(str)) => Some(str.value.to_string()),
+    Expr::Tpl(tpl) if tpl.exprs.is_empty() => tpl.quasis.get(0).map(|quasi| {
+      quasi
+        .cooked
+        .as_ref()
+        .unwrap_or(&quasi.raw)
+        .value
+        .to_string()
+    }),
+    Expr::Object(object) => object.props.iter().find_map(|prop| match prop {
+      PropOrSpread::Prop(prop) => match &**prop {
+        Prop::KeyValue(key_value) => {
+          let key = match &key_value.key {
+            PropName::Ident(ident) => ident.sym.to_string(),
+            PropName::Str(str) => str.value.to_string(),
+            _ => return None,
+          };
+          if key == "name" {
+            get_test_name(&key_value.value)
+          } else {
+            None
+          }
+        }
+        _ => None,
+      },
+      PropOrSpread::Spread(_) => None,
+    }),
+    Expr::Fn(fn_expr) => {
+      fn_expr.ident.as_ref().map(|ident| ident.sym.to_string())
+    }
+    Expr::Paren(paren) => get_test_name(&paren.expr),
+    _ => None,
+  }
+}
+
+impl Visit for TestCollector {
+  fn visit_call_expr(
+    &mut self,
+    node: &swc_ecmascript::ast::CallExpr,
+    parent: &dyn Node,
+  ) {
+    swc_ecmascript::visit::visit_call_expr(self, node, parent);
+    let is_deno_test = match &node.callee {
+      ExprOrSuper::Expr(callee) => match &**callee {
+        Expr::Member(member) if !member.computed => {
+          let is_deno = match &member.obj {
+            ExprOrSuper::Expr(obj) => {
+              matches!(&**obj, Expr::Ident(ident) if &*ident.sym == "Deno")
+            }
+            ExprOrSuper::Super(_) => false,
+          };
+          is_deno
+            && matches!(&*member.prop, Expr::Ident(ident) if &*ident.sym == "test")
+        }
+        _ => false,
+      },
+      ExprOrSuper::Super(_) => false,
+    };
+    if !is_deno_test {
+      return;
+    }
+    if let Some(name) =
+      node.args.get(0).and_then(|arg| get_test_name(&arg.expr))
+    {
+      self.tests.push((name, node.span));
+    }
+  }
+}
+
+/// Find the tests registered with `Deno.test()` in a document, in the order
+/// they appear in. Returns an empty list if the document can't be parsed.
+pub fn collect_tests(
+  specifier: &ModuleSpecifier,
+  source: &str,
+  media_type: &MediaType,
+) -> Vec<TestDefinition> {
+  let parsed_module =
+    match ast::parse(&specifier.to_string(), source, media_type) {
+      Ok(parsed_module) => parsed_module,
+      Err(_) => return vec![],
+    };
+  let mut collector = TestCollector { tests: vec![] };
+  parsed_module.visit_with(&mut collector);
+  let to_position = |location: ast::Location| lsp::Position {
+    line: (location.line - 1) as u32,
+    character: location.col as u32,
+  };
+  collector
+    .tests
+    .into_iter()
+    .map(|(name, span)| TestDefinition {
+      name,
+      range: lsp::Range {
+        start: to_position(parsed_module.get_location(&span)),
+        end: to_position(parsed_module.get_location(&span.shrink_to_hi())),
+      },
+    })
+    .collect()
+}
+
+/// The code lenses of a test, which run it with the `deno.test` command of
+/// the client, passing the document, the name and whether to debug it.
+pub fn to_code_lenses(
+  url: &lsp::Url,
+  test: &TestDefinition,
+) -> Vec<lsp::CodeLens> {
+  [("▶\u{fe0e} Run Test", false), ("Debug", true)]
+    .iter()
+    .map(|(title, inspect)| lsp::CodeLens {
+      range: test.range,
+      command: Some(lsp::Command {
+        title: title.to_string(),
+        command: "deno.test".to_string(),
+        arguments: Some(vec![
+          serde_json::to_value(url).unwrap(),
+          serde_json::to_value(&test.name).unwrap(),
+          serde_json::json!({ "inspect": inspect }),
+        ]),
+      }),
+      data: None,
+    })
+    .collect()
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TestRunParams {
+  /// An id chosen by the client, which the progress notifications refer to.
+  pub id: u32,
+  pub text_document: lsp::TextDocumentIdentifier,
+  /// The name of the test to run.
+  pub name: String,
+  /// Whether to run the test with `--inspect-brk`, for debugging it.
+  #[serde(default)]
+  pub inspect: bool,
+}
+
+/// A test as the client knows it, a `TestItem` in VS Code.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TestItem {
+  pub id: String,
+  pub label: String,
+  pub uri: lsp::Url,
+  pub range: Option<lsp::Range>,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TestMessage {
+  pub message: String,
+  pub location: Option<lsp::Location>,
+}
+
+/// The state changes of a test during a run, which are the methods of a
+/// `TestRun` in VS Code.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum TestRunProgressMessage {
+  Enqueued {
+    test: TestItem,
+  },
+  Started {
+    test: TestItem,
+  },
+  Passed {
+    test: TestItem,
+    duration: u64,
+  },
+  Failed {
+    test: TestItem,
+    messages: Vec<TestMessage>,
+    duration: u64,
+  },
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TestRunProgressParams {
+  pub id: u32,
+  pub message: TestRunProgressMessage,
+}
+
+pub enum TestRunProgressNotification {}
+
+impl lsp::notification::Notification for TestRunProgressNotification {
+  type Params = TestRunProgressParams;
+
+  const METHOD: &'static str = "deno/testRunProgress";
+}
+
+/// A test to run with `run_test()`.
+#[derive(Debug, Clone)]
+pub struct TestRun {
+  pub id: u32,
+  /// The document as the client knows it, which the notifications refer to.
+  pub url: lsp::Url,
+  pub specifier: ModuleSpecifier,
+  pub name: String,
+  pub maybe_range: Option<lsp::Range>,
+  pub inspect: bool,
+  /// Additional arguments of `deno test`, like `--config`.
+  pub args: Vec<String>,
+  pub maybe_cwd: Option<PathBuf>,
+}
+
+/// The result of a top level test in the TAP output of `deno test`.
+#[derive(Debug, PartialEq)]
+struct TapResult {
+  passed: bool,
+  maybe_message: Option<String>,
+  stack: Vec<String>,
+}
+
+/// Find the result of the top level test `name` in the output of
+/// `deno test --reporter=tap`.
+fn parse_tap_result(output: &str, name: &str) -> Option<TapResult> {
+  let name = name.replace('#', "\\#");
+  let mut lines = output.lines();
+  while let Some(line) = lines.next() {
+    let passed = if let Some(rest) = line.strip_prefix("ok ") {
+      rest.splitn(2, " - ").nth(1) == Some(name.as_str())
+    } else {
+      false
+    };
+    if passed {
+      return Some(TapResult {
+        passed: true,
+        maybe_message: None,
+        stack: vec![],
+      });
+    }
+    let failed = match line.strip_prefix("not ok ") {
+      Some(rest) => rest.splitn(2, " - ").nth(1) == Some(name.as_str()),
+      None => false,
+    };
+    if !failed {
+      continue;
+    }
+    let mut result = TapResult {
+      passed: false,
+      maybe_message: None,
+      stack: vec![],
+    };
+    let mut in_stack = false;
+    for line in lines.take_while(|line| *line != "  ...") {
+      if let Some(message) = line.strip_prefix("  message: ") {
+        result.maybe_message = serde_json::from_str(message).ok();
+        in_stack = false;
+      } else if line == "  stack: |-" {
+        in_stack = true;
+      } else if in_stack {
+        if let Some(line) = line.strip_prefix("    ") {
+          result.stack.push(line.to_string());
+        }
+      }
+    }
+    return Some(result);
+  }
+  None
+}
+
+/// The location of the first frame of `stack` in `specifier`, as reported
+/// to the client for `url`.
+fn get_failure_location(
+  stack: &[String],
+  specifier: &ModuleSpecifier,
+  url: &lsp::Url,
+) -> Option<lsp::Location> {
+  let re = Regex::new(&format!(
+    r"{}:(\d+):(\d+)",
+    regex::escape(&specifier.to_string())
+  ))
+  .unwrap();
+  stack.iter().find_map(|line| {
+    let captures = re.captures(line)?;
+    let line: u32 = captures[1].parse().ok()?;
+    let character: u32 = captures[2].parse().ok()?;
+    let position = lsp::Position {
+      line: line.saturating_sub(1),
+      character: character.saturating_sub(1),
+    };
+    Some(lsp::Location {
+      uri: url.clone(),
+      range: lsp::Range {
+        start: position,
+        end: position,
+      },
+    })
+  })
+}
+
+/// Run the test of `run` with `deno_exe`, sending its progress to `sender`.
+/// The subprocess is killed if the returned future is dropped, which is how
+/// a cancelled request is handled.
+pub async fn run_test(
+  run: TestRun,
+  deno_exe: &Path,
+  sender: mpsc::UnboundedSender<TestRunProgressParams>,
+) -> Result<(), AnyError> {
+  let test = TestItem {
+    id: format!("{}#{}", run.url, run.name),
+    label: run.name.clone(),
+    uri: run.url.clone(),
+    range: run.maybe_range,
+  };
+  let send = |message| {
+    // The receiver is only gone when the request was cancelled.
+    sender
+      .send(TestRunProgressParams {
+        id: run.id,
+        message,
+      })
+      .ok();
+  };
+  send(TestRunProgressMessage::Enqueued { test: test.clone() });
+
+  let mut command = tokio::process::Command::new(deno_exe);
+  command
+    .arg("test")
+    .arg("--allow-all")
+    .arg("--reporter=tap")
+    .arg("--filter")
+    .arg(format!("\"{}\"", run.name));
+  if run.inspect {
+    command.arg("--inspect-brk");
+  }
+  command.args(&run.args);
+  match run.specifier.to_file_path() {
+    Ok(path) if run.specifier.scheme() == "file" => command.arg(path),
+    _ => command.arg(run.specifier.as_str()),
+  };
+  if let Some(cwd) = &run.maybe_cwd {
+    command.current_dir(cwd);
+  }
+  command
+    .env("NO_COLOR", "1")
+    .stdin(Stdio::null())
+    .stdout(Stdio::piped())
+    .stderr(Stdio::piped())
+    .kill_on_drop(true);
+  let child = command.spawn()?;
+  let start = Instant::now();
+  send(TestRunProgressMessage::Started { test: test.clone() });
+
+  let output = child.wait_with_output().await?;
+  let duration = start.elapsed().as_millis() as u64;
+  let stdout = String::from_utf8_lossy(&output.stdout);
+  let message = match parse_tap_result(&stdout, &run.name) {
+    Some(result) if result.passed => {
+      TestRunProgressMessage::Passed { test, duration }
+    }
+    Some(result) => {
+      let location =
+        get_failure_location(&result.stack, &run.specifier, &run.url);
+      let message = if result.stack.is_empty() {
+        result.maybe_message.unwrap_or_default()
+      } else {
+        result.stack.join("\n")
+      };
+      TestRunProgressMessage::Failed {
+        test,
+        messages: vec![TestMessage { message, location }],
+        duration,
+      }
+    }
+    None => {
+      // The module failed to load, or the test wasn't registered.
+      let stderr = String::from_utf8_lossy(&output.stderr);
+      let message = if stderr.trim().is_empty() {
+        format!("The test \"{}\" was not found.", run.name)
+      } else {
+        stderr.trim().to_string()
+      };
+      TestRunProgressMessage::Failed {
+        test,
+        messages: vec![TestMessage {
+          message,
+          location: None,
+        }],
+        duration,
+      }
+    }
+  };
+  send(message);
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use deno_core::resolve_url;
+  use std::fs;
+  use std::time::Duration;
+  use tempfile::TempDir;
+
+  fn range(
+    (start_line, start_character): (u32, u32),
+    (end_line, end_character): (u32, u32),
+  ) -> lsp::Range {
+    lsp::Range {
+      start: lsp::Position {
+        line: start_line,
+        character: start_character,
+      },
+      end: lsp::Position {
+        line: end_line,
+        character: end_character,
+      },
+    }
+  }
+
+  #[test]
+  fn test_collect_tests() {
+    let specifier = resolve_url("file:///a/test.ts").unwrap();
+    let source = r#"Deno.test("a", () => {});
+Deno.test(`b`, () => {});
+Deno.test({
+  name: "c",
+  fn() {},
+});
+Deno.test(function d() {});
+Deno.test(name, () => {});
+test("e", () => {});
+"#;
+    let tests = collect_tests(&specifier, source, &MediaType::TypeScript);
+    assert_eq!(
+      tests,
+      vec![
+        TestDefinition {
+          name: "a".to_string(),
+          range: range((0, 0), (0, 24)),
+        },
+        TestDefinition {
+          name: "b".to_string(),
+          range: range((1, 0), (1, 24)),
+        },
+        TestDefinition {
+          name: "c".to_string(),
+          range: range((2, 0), (5, 2)),
+        },
+        TestDefinition {
+          name: "d".to_string(),
+          range: range((6, 0), (6, 26)),
+        },
+      ]
+    );
+
+    let tests =
+      collect_tests(&specifier, "Deno.test(\"a\", (", &MediaType::TypeScript);
+    assert!(tests.is_empty());
+  }
+
+  #[test]
+  fn test_parse_tap_result() {
+    let output = r#"TAP version 13
+# file:///a/test.ts
+ok 1 - passes
+not ok 2 - fails
+  ---
+  duration_ms: 2
+  message: "Error: oops"
+  stack: |-
+    Error: oops
+        at file:///a/test.ts:7:9
+  ...
+1..2
+"#;
+    assert_eq!(
+      parse_tap_result(output, "passes"),
+      Some(TapResult {
+        passed: true,
+        maybe_message: None,
+        stack: vec![],
+      })
+    );
+    assert_eq!(
+      parse_tap_result(output, "fails"),
+      Some(TapResult {
+        passed: false,
+        maybe_message: Some("Error: oops".to_string()),
+        stack: vec![
+          "Error: oops".to_string(),
+          "    at file:///a/test.ts:7:9".to_string()
+        ],
+      })
+    );
+    assert_eq!(parse_tap_result(output, "missing"), None);
+
+    let specifier = resolve_url("file:///a/test.ts").unwrap();
+    let url = lsp::Url::parse("file:///a/test.ts").unwrap();
+    let stack = parse_tap_result(output, "fails").unwrap().stack;
+    assert_eq!(
+      get_failure_location(&stack, &specifier, &url),
+      Some(lsp::Location {
+        uri: url,
+        range: range((6, 8), (6, 8)),
+      })
+    );
+  }
+
+  fn setup_run(temp_dir: &TempDir, name: &str) -> TestRun {
+    let path = temp_dir.path().join("test.ts");
+    fs::write(
+      &path,
+      r#"Deno.test("passes", () => {});
+
+Deno.test("fails", () => {
+  throw new Error("oops");
+});
+
+Deno.test("hangs", async () => {
+  await new Promise((resolve) => setTimeout(resolve, 2000));
+  Deno.writeTextFileSync("after.txt", "");
+});
+"#,
+    )
+    .unwrap();
+    let url = lsp::Url::from_file_path(&path).unwrap();
+    TestRun {
+      id: 1,
+      specifier: url.clone(),
+      url,
+      name: name.to_string(),
+      maybe_range: None,
+      inspect: false,
+      args: vec![],
+      maybe_cwd: Some(temp_dir.path().to_path_buf()),
+    }
+  }
+
+  async fn run(run: TestRun) -> Vec<TestRunProgressMessage> {
+    let (sender, mut receiver) = mpsc::unbounded_channel();
+    run_test(run, &test_util::deno_exe_path(), sender)
+      .await
+      .unwrap();
+    let mut messages = vec![];
+    while let Some(params) = receiver.recv().await {
+      assert_eq!(params.id, 1);
+      messages.push(params.message);
+    }
+    messages
+  }
+
+  #[tokio::test]
+  async fn test_run_test() {
+    let temp_dir = TempDir::new().expect("could not create temp dir");
+
+    let messages = run(setup_run(&temp_dir, "passes")).await;
+    assert_eq!(messages.len(), 3);
+    assert!(matches!(
+      messages[0],
+      TestRunProgressMessage::Enqueued { .. }
+    ));
+    assert!(matches!(
+      messages[1],
+      TestRunProgressMessage::Started { .. }
+    ));
+    match &messages[2] {
+      TestRunProgressMessage::Passed { test, .. } => {
+        assert_eq!(test.label, "passes")
+      }
+      message => panic!("unexpected message: {:?}", message),
+    }
+
+    let test_run = setup_run(&temp_dir, "fails");
+    let url = test_run.url.clone();
+    let messages = run(test_run).await;
+    assert_eq!(messages.len(), 3);
+    assert!(matches!(
+      messages[0],
+      TestRunProgressMessage::Enqueued { .. }
+    ));
+    assert!(matches!(
+      messages[1],
+      TestRunProgressMessage::Started { .. }
+    ));
+    match &messages[2] {
+      TestRunProgressMessage::Failed { test, messages, .. } => {
+        assert_eq!(test.label, "fails");
+        assert_eq!(messages.len(), 1);
+        assert!(messages[0].message.starts_with("Error: oops"));
+        assert_eq!(
+          messages[0].location,
+          Some(lsp::Location {
+            uri: url,
+            range: range((3, 8), (3, 8)),
+          })
+        );
+      }
+      message => panic!("unexpected message: {:?}", message),
+    }
+  }
+
+  #[tokio::test]
+  async fn test_run_test_cancel() {
+    let temp_dir = TempDir::new().expect("could not create temp dir");
+    let (sender, mut receiver) = mpsc::unbounded_channel();
+    let deno_exe = test_util::deno_exe_path();
+    let run_future = run_test(setup_run(&temp_dir, "hangs"), &deno_exe, sender);
+    let cancel = async {
+      while let Some(params) = receiver.recv().await {
+        if let TestRunProgressMessage::Started { .. } = params.message {
+          tokio::time::sleep(Duration::from_millis(500)).await;
+          return;
+        }
+      }
+    };
+    tokio::select! {
+      _ = run_future => panic!("the run should have been cancelled"),
+      _ = cancel => {}
+    }
+
+    // The test would have written the file if the subprocess hadn't been
+    // killed.
+    tokio::time::sleep(Duration::from_millis(3000)).await;
+    assert!(!temp_dir.path().join("after.txt").exists());
+  }
+}
@@ -33,6 +33,7 @@ use lspower::lsp;
 use regex::Captures;
 use regex::Regex;
 use std::borrow::Cow;
+use std::collections::BTreeMap;
 use std::collections::HashMap;
 use std::thread;
 use text_size::TextSize;
@@ -632,13 +633,25 @@ pub struct RenameLocations {
 }
 
 impl RenameLocations {
+  /// Returns the first module with a location that can't be edited, because
+  /// it is a remote module or one built into Deno, if there is one.
+  pub fn find_read_only_specifier(&self) -> Option<ModuleSpecifier> {
+    self.locations.iter().find_map(|location| {
+      let specifier = resolve_url(&location.document_span.file_name).ok()?;
+      match specifier.scheme() {
+        "asset" | "data" | "http" | "https" => Some(specifier),
+        _ => None,
+      }
+    })
+  }
+
   pub(crate) async fn into_workspace_edit(
     self,
     new_name: &str,
     language_server: &mut language_server::Inner,
   ) -> Result<lsp::WorkspaceEdit, AnyError> {
-    let mut text_document_edit_map: HashMap<Url, lsp::TextDocumentEdit> =
-      HashMap::new();
+    let mut text_document_edit_map: BTreeMap<Url, lsp::TextDocumentEdit> =
+      BTreeMap::new();
     for location in self.locations.iter() {
       let specifier = resolve_url(&location.document_span.file_name)?;
       let uri = language_server.url_map.normalize_specifier(&specifier)?;
@@ -11,10 +11,12 @@ mod ast;
 mod auth_tokens;
 mod checksum;
 mod colors;
+mod compat;
 mod deno_dir;
 mod diagnostics;
 mod diff;
 mod disk_cache;
+mod env_file;
 mod errors;
 mod file_fetcher;
 mod file_watcher;
@@ -46,9 +48,14 @@ mod version;
 use crate::file_fetcher::File;
 use crate::file_fetcher::FileFetcher;
 use crate::file_watcher::ModuleResolutionResult;
+use crate::file_watcher::WatcherOptions;
 use crate::flags::DenoSubcommand;
 use crate::flags::Flags;
+use crate::flags::FmtOptions;
+use crate::flags::TestReporterKind;
+use crate::flags::TypeCheckMode;
 use crate::fmt_errors::PrettyJsError;
+use crate::http_cache::HttpCache;
 use crate::import_map::ImportMap;
 use crate::media_type::MediaType;
 use crate::module_loader::CliModuleLoader;
@@ -76,15 +83,18 @@ use deno_runtime::worker::MainWorker;
 use deno_runtime::worker::WorkerOptions;
 use log::Level;
 use log::LevelFilter;
+use std::collections::HashSet;
 use std::env;
 use std::io::Read;
 use std::io::Write;
 use std::iter::once;
+use std::path::Path;
 use std::path::PathBuf;
 use std::pin::Pin;
 use std::rc::Rc;
 use std::sync::Arc;
 use std::sync::Mutex;
+use std::time::Duration;
 
 fn create_web_worker_callback(
   program_state: Arc<ProgramState>,
@@ -222,6 +232,7 @@ pub fn create_main_worker(
     // above
     ops::errors::init(js_runtime);
     ops::runtime_compiler::init(js_runtime);
+    ops::testing::init(js_runtime);
   }
   worker.bootstrap(&options);
 
@@ -251,16 +262,25 @@ where
 fn print_cache_info(
   state: &Arc<ProgramState>,
   json: bool,
+  by_origin: bool,
 ) -> Result<(), AnyError> {
   let deno_dir = &state.dir.root;
   let modules_cache = &state.file_fetcher.get_http_cache_location();
   let typescript_cache = &state.dir.gen_cache.location;
+  let origins = if by_origin {
+    HttpCache::new(modules_cache).origins()?
+  } else {
+    vec![]
+  };
   if json {
-    let output = json!({
+    let mut output = json!({
         "denoDir": deno_dir,
         "modulesCache": modules_cache,
         "typescriptCache": typescript_cache,
     });
+    if by_origin {
+      output["origins"] = json!(origins);
+    }
     write_json_to_stdout(&output)
   } else {
     println!("{} {:?}", colors::bold("DENO_DIR location:"), deno_dir);
@@ -274,6 +294,22 @@ fn print_cache_info(
       colors::bold("TypeScript compiler cache:"),
       typescript_cache
     );
+    if by_origin {
+      println!("{}", colors::bold("Remote modules by origin:"));
+      for usage in origins {
+        println!(
+          "  {} {} ({} {})",
+          usage.origin,
+          colors::gray(&info::human_size(usage.size as f64)),
+          usage.modules,
+          if usage.modules == 1 {
+            "module"
+          } else {
+            "modules"
+          }
+        );
+      }
+    }
     Ok(())
   }
 }
@@ -303,6 +339,7 @@ async fn compile_command(
   args: Vec<String>,
   target: Option<String>,
   lite: bool,
+  include: Vec<PathBuf>,
 ) -> Result<(), AnyError> {
   if !flags.unstable {
     exit_unstable("compile");
@@ -322,6 +359,10 @@ async fn compile_command(
   }).ok_or_else(|| generic_error(
     "An executable name was not provided. One could not be inferred from the URL. Aborting.",
   ))?;
+  let windows = target
+    .as_ref()
+    .map_or(cfg!(windows), |target| target.contains("windows"));
+  let output = tools::standalone::output_path(output, windows);
 
   let module_graph = create_module_graph_and_maybe_check(
     module_specifier.clone(),
@@ -344,12 +385,20 @@ async fn compile_command(
   );
 
   // Select base binary based on `target` and `lite` arguments
-  let original_binary =
-    tools::standalone::get_base_binary(deno_dir, target, lite).await?;
+  let original_binary = tools::standalone::get_base_binary(
+    deno_dir,
+    target,
+    lite,
+    program_state.flags.cached_only,
+  )
+  .await?;
+
+  let assets = tools::standalone::collect_assets(&include)?;
 
   let final_bin = tools::standalone::create_standalone_binary(
     original_binary,
     bundle_str,
+    assets,
     run_flags,
   )?;
 
@@ -364,6 +413,8 @@ async fn info_command(
   flags: Flags,
   maybe_specifier: Option<String>,
   json: bool,
+  maybe_reverse: Option<String>,
+  by_origin: bool,
 ) -> Result<(), AnyError> {
   if json && !flags.unstable {
     exit_unstable("--json");
@@ -382,19 +433,32 @@ async fn info_command(
       program_state.maybe_import_map.clone(),
       program_state.lockfile.clone(),
     );
+    builder.set_compat(program_state.flags.compat);
     builder.add(&specifier, false).await?;
     let graph = builder.get_graph();
-    let info = graph.info()?;
 
+    if let Some(reverse) = maybe_reverse {
+      let importers = graph.importers(&resolve_url_or_path(&reverse)?)?;
+      if json {
+        write_json_to_stdout(&json!(importers))?;
+      } else {
+        write_to_stdout_ignore_sigpipe(importers.to_string().as_bytes())?;
+      }
+      return Ok(());
+    }
+
+    let info = graph.info()?;
     if json {
       write_json_to_stdout(&json!(info))?;
+    } else if let Some(err) = info.first_error() {
+      return Err(generic_error(err.to_string()));
     } else {
       write_to_stdout_ignore_sigpipe(info.to_string().as_bytes())?;
     }
     Ok(())
   } else {
     // If it was just "deno info" print location of caches and exit
-    print_cache_info(&program_state, json)
+    print_cache_info(&program_state, json, by_origin)
   }
 }
 
@@ -405,7 +469,11 @@ async fn install_command(
   name: Option<String>,
   root: Option<PathBuf>,
   force: bool,
+  list: bool,
 ) -> Result<(), AnyError> {
+  if list {
+    return tools::installer::list(root);
+  }
   let mut preload_flags = flags.clone();
   preload_flags.inspect = None;
   preload_flags.inspect_brk = None;
@@ -419,6 +487,13 @@ async fn install_command(
   tools::installer::install(flags, &module_url, args, name, root, force)
 }
 
+async fn uninstall_command(
+  name: String,
+  root: Option<PathBuf>,
+) -> Result<(), AnyError> {
+  tools::installer::uninstall(&name, root)
+}
+
 async fn language_server_command() -> Result<(), AnyError> {
   lsp::start().await
 }
@@ -429,6 +504,7 @@ async fn lint_command(
   list_rules: bool,
   ignore: Vec<PathBuf>,
   json: bool,
+  fix: bool,
 ) -> Result<(), AnyError> {
   if !flags.unstable {
     exit_unstable("lint");
@@ -439,12 +515,13 @@ async fn lint_command(
     return Ok(());
   }
 
-  tools::lint::lint_files(files, ignore, json).await
+  tools::lint::lint_files(files, ignore, json, fix).await
 }
 
 async fn cache_command(
   flags: Flags,
   files: Vec<String>,
+  maybe_prune: Option<Duration>,
 ) -> Result<(), AnyError> {
   let lib = if flags.unstable {
     module_graph::TypeLib::UnstableDenoWindow
@@ -466,17 +543,45 @@ async fn cache_command(
       .await?;
   }
 
+  if let Some(max_age) = maybe_prune {
+    let keep: HashSet<String> = match &program_state.lockfile {
+      Some(lockfile) => lockfile
+        .lock()
+        .unwrap()
+        .specifiers()
+        .map(String::from)
+        .collect(),
+      None => HashSet::new(),
+    };
+    let http_cache =
+      HttpCache::new(&program_state.file_fetcher.get_http_cache_location());
+    let removed = http_cache.prune(max_age, &keep)?;
+    let size: u64 = removed.iter().map(|entry| entry.size).sum();
+    println!(
+      "{} {} cached {} ({})",
+      colors::green("Removed"),
+      removed.len(),
+      if removed.len() == 1 {
+        "module"
+      } else {
+        "modules"
+      },
+      info::human_size(size as f64)
+    );
+  }
+
   Ok(())
 }
 
 async fn eval_command(
   flags: Flags,
   code: String,
-  as_typescript: bool,
   print: bool,
 ) -> Result<(), AnyError> {
-  // Force TypeScript compile.
-  let main_module = resolve_url_or_path("./$deno$eval.ts").unwrap();
+  // The extension of the specifier sets the media type of the code.
+  let ext = flags.ext.as_deref().unwrap_or("js");
+  let main_module =
+    resolve_url_or_path(&format!("./$deno$eval.{}", ext)).unwrap();
   let permissions = Permissions::from_options(&flags.clone().into());
   let program_state = ProgramState::build(flags).await?;
   let mut worker =
@@ -492,11 +597,7 @@ async fn eval_command(
   let file = File {
     local: main_module.clone().to_file_path().unwrap(),
     maybe_types: None,
-    media_type: if as_typescript {
-      MediaType::TypeScript
-    } else {
-      MediaType::JavaScript
-    },
+    media_type: MediaType::from(&main_module),
     source: String::from_utf8(source_code)?,
     specifier: main_module.clone(),
   };
@@ -528,6 +629,7 @@ async fn create_module_graph_and_maybe_check(
     program_state.maybe_import_map.clone(),
     program_state.lockfile.clone(),
   );
+  builder.set_compat(program_state.flags.compat);
   builder.add(&module_specifier, false).await?;
   let module_graph = builder.get_graph();
 
@@ -545,6 +647,7 @@ async fn create_module_graph_and_maybe_check(
         lib,
         maybe_config_path: program_state.flags.config_path.clone(),
         reload: program_state.flags.reload,
+        check_remote: program_state.flags.type_check_mode == TypeCheckMode::All,
       })?;
 
     debug!("{}", result_info.stats);
@@ -667,6 +770,7 @@ async fn bundle_command(
       module_resolver,
       operation,
       "Bundle",
+      WatcherOptions::from_flags(&flags)?,
     )
     .await?;
   } else {
@@ -775,7 +879,14 @@ async fn doc_command(
   };
 
   if json {
-    write_json_to_stdout(&doc_nodes)
+    if let Some(filter) = maybe_filter {
+      doc_nodes = tools::doc::find_nodes_with_references(&doc_nodes, &filter);
+      if doc_nodes.is_empty() {
+        eprintln!("Node {} was not found!", filter);
+        std::process::exit(1);
+      }
+    }
+    write_json_to_stdout(&tools::doc::DocJson::new(&doc_nodes))
   } else {
     doc_nodes.retain(|doc_node| doc_node.kind != doc::DocNodeKind::Import);
     let details = if let Some(filter) = maybe_filter {
@@ -806,16 +917,34 @@ async fn format_command(
   ignore: Vec<PathBuf>,
   check: bool,
   ext: String,
+  options: FmtOptions,
 ) -> Result<(), AnyError> {
   if args.len() == 1 && args[0].to_string_lossy() == "-" {
-    return tools::fmt::format_stdin(check, ext);
+    return tools::fmt::format_stdin(check, ext, options, flags.config_path);
   }
 
-  tools::fmt::format(args, ignore, check, flags.watch).await?;
+  let watcher_options = if flags.watch {
+    Some(WatcherOptions::from_flags(&flags)?)
+  } else {
+    None
+  };
+  tools::fmt::format(
+    args,
+    ignore,
+    check,
+    watcher_options,
+    options,
+    flags.config_path,
+  )
+  .await?;
   Ok(())
 }
 
-async fn run_repl(flags: Flags) -> Result<(), AnyError> {
+async fn run_repl(
+  flags: Flags,
+  maybe_eval: Option<String>,
+  maybe_eval_files: Option<Vec<String>>,
+) -> Result<(), AnyError> {
   let main_module = resolve_url_or_path("./$deno$repl.ts").unwrap();
   let permissions = Permissions::from_options(&flags.clone().into());
   let program_state = ProgramState::build(flags).await?;
@@ -823,13 +952,17 @@ async fn run_repl(flags: Flags) -> Result<(), AnyError> {
     create_main_worker(&program_state, main_module.clone(), permissions);
   worker.run_event_loop().await?;
 
-  tools::repl::run(&program_state, worker).await
+  tools::repl::run(&program_state, worker, maybe_eval, maybe_eval_files).await
 }
 
 async fn run_from_stdin(flags: Flags) -> Result<(), AnyError> {
   let program_state = ProgramState::build(flags.clone()).await?;
   let permissions = Permissions::from_options(&flags.clone().into());
-  let main_module = resolve_url_or_path("./$deno$stdin.ts").unwrap();
+  // Relative imports resolve against the current directory, and the
+  // extension of the specifier sets the media type of the code.
+  let ext = flags.ext.as_deref().unwrap_or("ts");
+  let main_module =
+    resolve_url_or_path(&format!("./$deno$stdin.{}", ext)).unwrap();
   let mut worker = create_main_worker(
     &program_state.clone(),
     main_module.clone(),
@@ -842,7 +975,7 @@ async fn run_from_stdin(flags: Flags) -> Result<(), AnyError> {
   let source_file = File {
     local: main_module.clone().to_file_path().unwrap(),
     maybe_types: None,
-    media_type: MediaType::TypeScript,
+    media_type: MediaType::from(&main_module),
     source: String::from_utf8(source)?,
     specifier: main_module.clone(),
   };
@@ -875,6 +1008,7 @@ async fn run_with_watch(flags: Flags, script: String) -> Result<(), AnyError> {
         program_state.maybe_import_map.clone(),
         program_state.lockfile.clone(),
       );
+      builder.set_compat(program_state.flags.compat);
       builder.add(&main_module, false).await?;
       let module_graph = builder.get_graph();
 
@@ -927,6 +1061,7 @@ async fn run_with_watch(flags: Flags, script: String) -> Result<(), AnyError> {
     module_resolver,
     operation,
     "Process",
+    WatcherOptions::from_flags(&flags)?,
   )
   .await
 }
@@ -974,6 +1109,22 @@ async fn run_command(flags: Flags, script: String) -> Result<(), AnyError> {
   Ok(())
 }
 
+async fn task_command(
+  flags: Flags,
+  task: Option<String>,
+  args: Vec<String>,
+) -> Result<(), AnyError> {
+  if !flags.unstable {
+    exit_unstable("task");
+  }
+  let exit_code = tools::task::run_task(flags.config_path, task, args)?;
+  if exit_code != 0 {
+    std::process::exit(exit_code);
+  }
+  Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
 async fn test_command(
   flags: Flags,
   include: Option<Vec<String>>,
@@ -982,13 +1133,43 @@ async fn test_command(
   quiet: bool,
   allow_none: bool,
   filter: Option<String>,
+  skip: Vec<String>,
+  shuffle: Option<u64>,
+  concurrent_jobs: usize,
+  lcov: Option<PathBuf>,
+  html: Option<PathBuf>,
+  reporter: TestReporterKind,
+  output: Option<PathBuf>,
 ) -> Result<(), AnyError> {
+  let filter = tools::test_runner::TestFilter::parse(filter.as_deref(), &skip)?;
+  if flags.watch {
+    return test_with_watch(
+      flags,
+      include,
+      fail_fast,
+      quiet,
+      filter,
+      shuffle,
+      concurrent_jobs,
+      reporter,
+      output,
+    )
+    .await;
+  }
+  // Reporters other than the JS one and reports written to a file need the
+  // events of each module, so every module runs in its own worker.
+  let worker_per_module = concurrent_jobs > 1
+    || reporter != TestReporterKind::Pretty
+    || output.is_some();
   let program_state = ProgramState::build(flags.clone()).await?;
   let permissions = Permissions::from_options(&flags.clone().into());
   let cwd = std::env::current_dir().expect("No current directory");
   let include = include.unwrap_or_else(|| vec![".".to_string()]);
-  let test_modules =
+  let mut test_modules =
     tools::test_runner::prepare_test_modules_urls(include, &cwd)?;
+  if let Some(seed) = shuffle {
+    tools::test_runner::shuffle_test_modules(&mut test_modules, seed);
+  }
 
   if test_modules.is_empty() {
     println!("No matching test modules found");
@@ -997,39 +1178,37 @@ async fn test_command(
     }
     return Ok(());
   }
-  let main_module = deno_core::resolve_path("$deno$test.ts")?;
-  // Create a dummy source file.
-  let source_file = File {
-    local: main_module.to_file_path().unwrap(),
-    maybe_types: None,
-    media_type: MediaType::TypeScript,
-    source: tools::test_runner::render_test_file(
-      test_modules.clone(),
+  let main_module = prepare_test_main_module(
+    &program_state,
+    &test_modules,
+    fail_fast,
+    quiet,
+    &filter,
+    shuffle,
+    no_run || worker_per_module,
+  )
+  .await?;
+  if no_run {
+    return Ok(());
+  }
+
+  if worker_per_module {
+    let failed = run_test_workers(
+      program_state,
+      permissions,
+      test_modules,
       fail_fast,
       quiet,
-      filter,
-    ),
-    specifier: main_module.clone(),
-  };
-  // Save our fake file into file fetcher cache
-  // to allow module access by TS compiler
-  program_state.file_fetcher.insert_cached(source_file);
-
-  if no_run {
-    let lib = if flags.unstable {
-      module_graph::TypeLib::UnstableDenoWindow
-    } else {
-      module_graph::TypeLib::DenoWindow
-    };
-    program_state
-      .prepare_module_load(
-        main_module.clone(),
-        lib,
-        Permissions::allow_all(),
-        false,
-        program_state.maybe_import_map.clone(),
-      )
-      .await?;
+      &filter,
+      shuffle,
+      concurrent_jobs,
+      reporter,
+      output,
+    )
+    .await?;
+    if failed {
+      std::process::exit(1);
+    }
     return Ok(());
   }
 
@@ -1074,6 +1253,8 @@ async fn test_command(
         &coverage_collector.dir,
         quiet,
         exclude,
+        lcov,
+        html,
       )
       .await?;
     }
@@ -1082,6 +1263,292 @@ async fn test_command(
   Ok(())
 }
 
+/// Creates the module that imports all of `test_modules` and runs their
+/// tests, and type checks it if `check` is set. Returns its specifier.
+#[allow(clippy::too_many_arguments)]
+async fn prepare_test_main_module(
+  program_state: &Arc<ProgramState>,
+  test_modules: &[ModuleSpecifier],
+  fail_fast: bool,
+  quiet: bool,
+  filter: &tools::test_runner::TestFilter,
+  shuffle: Option<u64>,
+  check: bool,
+) -> Result<ModuleSpecifier, AnyError> {
+  let main_module = deno_core::resolve_path("$deno$test.ts")?;
+  // Create a dummy source file.
+  let source_file = File {
+    local: main_module.to_file_path().unwrap(),
+    maybe_types: None,
+    media_type: MediaType::TypeScript,
+    source: tools::test_runner::render_test_file(
+      test_modules.to_vec(),
+      fail_fast,
+      quiet,
+      filter,
+      shuffle,
+    ),
+    specifier: main_module.clone(),
+  };
+  // Save our fake file into file fetcher cache
+  // to allow module access by TS compiler
+  program_state.file_fetcher.insert_cached(source_file);
+
+  if check {
+    let lib = if program_state.flags.unstable {
+      module_graph::TypeLib::UnstableDenoWindow
+    } else {
+      module_graph::TypeLib::DenoWindow
+    };
+    program_state
+      .prepare_module_load(
+        main_module.clone(),
+        lib,
+        Permissions::allow_all(),
+        false,
+        program_state.maybe_import_map.clone(),
+      )
+      .await?;
+  }
+
+  Ok(main_module)
+}
+
+/// Runs every test module in its own worker, writing the report to `output`
+/// or stdout. The modules must have been type checked with
+/// `prepare_test_main_module()`. Returns whether a test failed.
+#[allow(clippy::too_many_arguments)]
+async fn run_test_workers(
+  program_state: Arc<ProgramState>,
+  permissions: Permissions,
+  test_modules: Vec<ModuleSpecifier>,
+  fail_fast: bool,
+  quiet: bool,
+  filter: &tools::test_runner::TestFilter,
+  shuffle: Option<u64>,
+  concurrent_jobs: usize,
+  reporter: TestReporterKind,
+  output: Option<PathBuf>,
+) -> Result<bool, AnyError> {
+  // Every test module gets its own entry point, loaded by its own worker;
+  // type checking already happened for all of them at once.
+  let mut main_modules = vec![];
+  for (index, test_module) in test_modules.into_iter().enumerate() {
+    let main_module =
+      deno_core::resolve_path(&format!("$deno$test{}.js", index))?;
+    program_state.file_fetcher.insert_cached(File {
+      local: main_module.to_file_path().unwrap(),
+      maybe_types: None,
+      media_type: MediaType::JavaScript,
+      source: tools::test_runner::render_concurrent_test_file(
+        test_module.clone(),
+        fail_fast,
+        quiet,
+        filter,
+        shuffle,
+        reporter != TestReporterKind::Pretty,
+      ),
+      specifier: main_module.clone(),
+    });
+    main_modules.push((main_module, test_module));
+  }
+
+  let writer: Box<dyn Write> = match output {
+    Some(path) => Box::new(std::fs::File::create(path)?),
+    None => Box::new(std::io::stdout()),
+  };
+  tools::test_runner::run_tests_concurrently(
+    program_state,
+    permissions,
+    main_modules,
+    concurrent_jobs,
+    fail_fast,
+    tools::test_reporter::create_reporter(reporter, writer),
+  )
+  .await
+}
+
+/// Runs the tests again whenever a file in the module graph of a test module
+/// changes. Every module runs in its own worker, so that failing tests don't
+/// exit the process.
+#[allow(clippy::too_many_arguments)]
+async fn test_with_watch(
+  flags: Flags,
+  include: Option<Vec<String>>,
+  fail_fast: bool,
+  quiet: bool,
+  filter: tools::test_runner::TestFilter,
+  shuffle: Option<u64>,
+  concurrent_jobs: usize,
+  reporter: TestReporterKind,
+  output: Option<PathBuf>,
+) -> Result<(), AnyError> {
+  let cwd = std::env::current_dir().expect("No current directory");
+  let include = include.unwrap_or_else(|| vec![".".to_string()]);
+
+  let module_resolver = || {
+    let flags = flags.clone();
+    let include = include.clone();
+    let cwd = cwd.clone();
+    let source_path = cwd.clone();
+    async move {
+      let mut test_modules =
+        tools::test_runner::prepare_test_modules_urls(include, &cwd)?;
+      if test_modules.is_empty() {
+        return Err(generic_error("No matching test modules found"));
+      }
+      if let Some(seed) = shuffle {
+        tools::test_runner::shuffle_test_modules(&mut test_modules, seed);
+      }
+
+      let program_state = ProgramState::build(flags).await?;
+      let handler = Arc::new(Mutex::new(FetchHandler::new(
+        &program_state,
+        Permissions::allow_all(),
+      )?));
+      let mut builder = module_graph::GraphBuilder::new(
+        handler,
+        program_state.maybe_import_map.clone(),
+        program_state.lockfile.clone(),
+      );
+      builder.set_compat(program_state.flags.compat);
+      for test_module in &test_modules {
+        builder.add(test_module, false).await?;
+      }
+      let module_graph = builder.get_graph();
+
+      // Find all local files in graph
+      let mut paths_to_watch: Vec<PathBuf> = module_graph
+        .get_modules()
+        .iter()
+        .filter_map(|specifier| specifier.to_file_path().ok())
+        .collect();
+
+      if let Some(import_map) = program_state.flags.import_map_path.as_ref() {
+        paths_to_watch
+          .push(fs_util::resolve_from_cwd(std::path::Path::new(import_map))?);
+      }
+
+      Ok((paths_to_watch, test_modules))
+    }
+    .map(move |result| match result {
+      Ok((paths_to_watch, module_info)) => ModuleResolutionResult::Success {
+        paths_to_watch,
+        module_info,
+      },
+      Err(e) => ModuleResolutionResult::Fail {
+        source_path,
+        error: e,
+      },
+    })
+    .boxed_local()
+  };
+
+  let operation = |test_modules: Vec<ModuleSpecifier>| {
+    let flags = flags.clone();
+    let permissions = Permissions::from_options(&flags.clone().into());
+    let filter = filter.clone();
+    let output = output.clone();
+    async move {
+      let program_state = ProgramState::build(flags).await?;
+      prepare_test_main_module(
+        &program_state,
+        &test_modules,
+        fail_fast,
+        quiet,
+        &filter,
+        shuffle,
+        true,
+      )
+      .await?;
+      run_test_workers(
+        program_state,
+        permissions,
+        test_modules,
+        fail_fast,
+        quiet,
+        &filter,
+        shuffle,
+        concurrent_jobs,
+        reporter,
+        output,
+      )
+      .await?;
+      Ok(())
+    }
+    .boxed_local()
+  };
+
+  file_watcher::watch_func_with_module_resolution(
+    module_resolver,
+    operation,
+    "Test",
+    WatcherOptions::from_flags(&flags)?,
+  )
+  .await
+}
+
+async fn bench_command(
+  flags: Flags,
+  include: Option<Vec<String>>,
+  filter: Option<String>,
+  json: bool,
+) -> Result<(), AnyError> {
+  if !flags.unstable {
+    exit_unstable("bench");
+  }
+  let filter = tools::test_runner::TestFilter::parse(filter.as_deref(), &[])?;
+  let program_state = ProgramState::build(flags.clone()).await?;
+  let permissions = Permissions::from_options(&flags.clone().into());
+  let cwd = std::env::current_dir().expect("No current directory");
+  let include = include.unwrap_or_else(|| vec![".".to_string()]);
+  let bench_modules =
+    tools::bench_runner::prepare_bench_modules_urls(include, &cwd)?;
+
+  if bench_modules.is_empty() {
+    println!("No matching bench modules found");
+    std::process::exit(1);
+  }
+  let main_module = deno_core::resolve_path("$deno$bench.ts")?;
+  // Create a dummy source file.
+  let source_file = File {
+    local: main_module.to_file_path().unwrap(),
+    maybe_types: None,
+    media_type: MediaType::TypeScript,
+    source: tools::bench_runner::render_bench_file(
+      bench_modules,
+      &filter,
+      json,
+    ),
+    specifier: main_module.clone(),
+  };
+  // Save our fake file into file fetcher cache
+  // to allow module access by TS compiler
+  program_state.file_fetcher.insert_cached(source_file);
+
+  let mut worker =
+    create_main_worker(&program_state, main_module.clone(), permissions);
+  worker.execute_module(&main_module).await?;
+  worker.execute("window.dispatchEvent(new Event('load'))")?;
+  worker.run_event_loop().await?;
+  worker.execute("window.dispatchEvent(new Event('unload'))")?;
+  worker.run_event_loop().await?;
+
+  Ok(())
+}
+
+async fn vendor_command(
+  flags: Flags,
+  entrypoints: Vec<String>,
+  force: bool,
+) -> Result<(), AnyError> {
+  if !flags.unstable {
+    exit_unstable("vendor");
+  }
+  let program_state = ProgramState::build(flags).await?;
+  tools::vendor::vendor(program_state, entrypoints, force).await
+}
+
 fn init_v8_flags(v8_flags: &[String]) {
   let v8_flags_includes_help = v8_flags
     .iter()
@@ -1144,6 +1611,11 @@ fn get_subcommand(
   flags: Flags,
 ) -> Pin<Box<dyn Future<Output = Result<(), AnyError>>>> {
   match flags.clone().subcommand {
+    DenoSubcommand::Bench {
+      include,
+      filter,
+      json,
+    } => bench_command(flags, include, filter, json).boxed_local(),
     DenoSubcommand::Bundle {
       source_file,
       out_file,
@@ -1154,13 +1626,16 @@ fn get_subcommand(
       filter,
       private,
     } => doc_command(flags, source_file, json, filter, private).boxed_local(),
-    DenoSubcommand::Eval {
-      print,
-      code,
-      as_typescript,
-    } => eval_command(flags, code, as_typescript, print).boxed_local(),
-    DenoSubcommand::Cache { files } => {
-      cache_command(flags, files).boxed_local()
+    DenoSubcommand::Eval { print, code } => {
+      eval_command(flags, code, print).boxed_local()
+    }
+    DenoSubcommand::Cache { files, prune } => {
+      cache_command(flags, files, prune).boxed_local()
+    }
+    // Caching type checks the modules, here with the modules whose type
+    // errors are reported picked by the flags of `deno check`.
+    DenoSubcommand::Check { files } => {
+      cache_command(flags, files, None).boxed_local()
     }
     DenoSubcommand::Compile {
       source_file,
@@ -1168,35 +1643,50 @@ fn get_subcommand(
       args,
       lite,
       target,
-    } => compile_command(flags, source_file, output, args, target, lite)
-      .boxed_local(),
+      include,
+    } => {
+      compile_command(flags, source_file, output, args, target, lite, include)
+        .boxed_local()
+    }
     DenoSubcommand::Fmt {
       check,
       files,
       ignore,
       ext,
-    } => format_command(flags, files, ignore, check, ext).boxed_local(),
-    DenoSubcommand::Info { file, json } => {
-      info_command(flags, file, json).boxed_local()
+      options,
+    } => {
+      format_command(flags, files, ignore, check, ext, options).boxed_local()
     }
+    DenoSubcommand::Info {
+      file,
+      json,
+      reverse,
+      cache,
+    } => info_command(flags, file, json, reverse, cache).boxed_local(),
     DenoSubcommand::Install {
       module_url,
       args,
       name,
       root,
       force,
-    } => {
-      install_command(flags, module_url, args, name, root, force).boxed_local()
-    }
+      list,
+    } => install_command(flags, module_url, args, name, root, force, list)
+      .boxed_local(),
     DenoSubcommand::LanguageServer => language_server_command().boxed_local(),
     DenoSubcommand::Lint {
       files,
       rules,
       ignore,
       json,
-    } => lint_command(flags, files, rules, ignore, json).boxed_local(),
-    DenoSubcommand::Repl => run_repl(flags).boxed_local(),
+      fix,
+    } => lint_command(flags, files, rules, ignore, json, fix).boxed_local(),
+    DenoSubcommand::Repl { eval, eval_files } => {
+      run_repl(flags, eval, eval_files).boxed_local()
+    }
     DenoSubcommand::Run { script } => run_command(flags, script).boxed_local(),
+    DenoSubcommand::Task { task, args } => {
+      task_command(flags, task, args).boxed_local()
+    }
     DenoSubcommand::Test {
       no_run,
       fail_fast,
@@ -1204,10 +1694,30 @@ fn get_subcommand(
       include,
       allow_none,
       filter,
-    } => {
-      test_command(flags, include, no_run, fail_fast, quiet, allow_none, filter)
-        .boxed_local()
-    }
+      skip,
+      shuffle,
+      concurrent_jobs,
+      lcov,
+      html,
+      reporter,
+      output,
+    } => test_command(
+      flags,
+      include,
+      no_run,
+      fail_fast,
+      quiet,
+      allow_none,
+      filter,
+      skip,
+      shuffle,
+      concurrent_jobs,
+      lcov,
+      html,
+      reporter,
+      output,
+    )
+    .boxed_local(),
     DenoSubcommand::Completions { buf } => {
       if let Err(e) = write_to_stdout_ignore_sigpipe(&buf) {
         eprintln!("{}", e);
@@ -1223,6 +1733,9 @@ fn get_subcommand(
       }
       std::process::exit(0);
     }
+    DenoSubcommand::Uninstall { name, root } => {
+      uninstall_command(name, root).boxed_local()
+    }
     DenoSubcommand::Upgrade {
       force,
       dry_run,
@@ -1234,6 +1747,9 @@ fn get_subcommand(
       dry_run, force, canary, version, output, ca_file,
     )
     .boxed_local(),
+    DenoSubcommand::Vendor { entrypoints, force } => {
+      vendor_command(flags, entrypoints, force).boxed_local()
+    }
   }
 }
 
@@ -1286,6 +1802,9 @@ pub fn main() {
     init_v8_flags(&*flags.v8_flags);
   }
   init_logger(flags.log_level);
+  if let Some(env_file) = &flags.env_file {
+    unwrap_or_exit(env_file::load(Path::new(env_file)));
+  }
 
   unwrap_or_exit(tokio_util::run_basic(get_subcommand(flags)));
 }
@@ -7,15 +7,21 @@ use crate::ast::BundleHook;
 use crate::ast::Location;
 use crate::ast::ParsedModule;
 use crate::colors;
+use crate::compat;
 use crate::diagnostics::Diagnostics;
 use crate::import_map::ImportMap;
+use crate::info::DependencyInfo;
+use crate::info::ImporterInfo;
 use crate::info::ModuleGraphInfo;
+use crate::info::ModuleImportersInfo;
 use crate::info::ModuleInfo;
 use crate::info::ModuleInfoMap;
 use crate::info::ModuleInfoMapItem;
 use crate::lockfile::Lockfile;
+use crate::lockfile::LockfileError;
 use crate::media_type::MediaType;
 use crate::specifier_handler::CachedModule;
+use crate::specifier_handler::CheckCacheEntry;
 use crate::specifier_handler::Dependency;
 use crate::specifier_handler::DependencyMap;
 use crate::specifier_handler::Emit;
@@ -84,7 +90,9 @@ pub enum GraphError {
   InvalidLocalImport(ModuleSpecifier, Location),
   /// The source code is invalid, as it does not match the expected hash in the
   /// lockfile.
-  InvalidSource(ModuleSpecifier, PathBuf),
+  InvalidSource(ModuleSpecifier, PathBuf, String, String),
+  /// A module is not in a frozen lockfile.
+  MissingFromLockfile(ModuleSpecifier, PathBuf),
   /// An unexpected dependency was requested for a module.
   MissingDependency(ModuleSpecifier, String),
   /// An unexpected specifier was requested.
@@ -100,7 +108,8 @@ impl fmt::Display for GraphError {
     match self {
       GraphError::InvalidDowngrade(ref specifier, ref location) => write!(f, "Modules imported via https are not allowed to import http modules.\n  Importing: {}\n    at {}", specifier, location),
       GraphError::InvalidLocalImport(ref specifier, ref location) => write!(f, "Remote modules are not allowed to import local modules.  Consider using a dynamic import instead.\n  Importing: {}\n    at {}", specifier, location),
-      GraphError::InvalidSource(ref specifier, ref lockfile) => write!(f, "The source code is invalid, as it does not match the expected hash in the lock file.\n  Specifier: {}\n  Lock file: {}", specifier, lockfile.to_str().unwrap()),
+      GraphError::InvalidSource(ref specifier, ref lockfile, ref expected, ref actual) => write!(f, "The source code is invalid, as it does not match the expected hash in the lock file.\n  Specifier: {}\n  Lock file: {}\n  Expected: {}\n  Actual: {}", specifier, lockfile.to_str().unwrap(), expected, actual),
+      GraphError::MissingFromLockfile(ref specifier, ref lockfile) => write!(f, "The module is missing from the frozen lock file.\n  Specifier: {}\n  Lock file: {}", specifier, lockfile.to_str().unwrap()),
       GraphError::MissingDependency(ref referrer, specifier) => write!(
         f,
         "The graph is missing a dependency.\n  Specifier: {} from {}",
@@ -235,6 +244,9 @@ fn get_version(source: &str, version: &str, config: &[u8]) -> String {
 #[derive(Debug, Clone)]
 pub struct Module {
   pub dependencies: DependencyMap,
+  /// Whether bare names of Node built-in modules are resolved, see
+  /// `compat::resolve()`.
+  compat: bool,
   is_dirty: bool,
   is_parsed: bool,
   maybe_emit: Option<Emit>,
@@ -252,6 +264,7 @@ impl Default for Module {
   fn default() -> Self {
     Module {
       dependencies: HashMap::new(),
+      compat: false,
       is_dirty: false,
       is_parsed: false,
       maybe_emit: None,
@@ -272,6 +285,7 @@ impl Module {
     cached_module: CachedModule,
     is_root: bool,
     maybe_import_map: Option<Arc<Mutex<ImportMap>>>,
+    compat: bool,
   ) -> Self {
     // If this is a local root file, and its media type is unknown, set the
     // media type to JavaScript.  This allows easier ability to create "shell"
@@ -286,6 +300,7 @@ impl Module {
     };
     let mut module = Module {
       specifier: cached_module.specifier,
+      compat,
       maybe_import_map,
       media_type,
       source: cached_module.source,
@@ -296,7 +311,9 @@ impl Module {
       is_dirty: false,
       ..Self::default()
     };
-    if module.maybe_import_map.is_none() {
+    // The cached dependencies were resolved without an import map or
+    // `--compat`.
+    if module.maybe_import_map.is_none() && !compat {
       if let Some(dependencies) = cached_module.maybe_dependencies {
         module.dependencies = dependencies;
         module.is_parsed = true;
@@ -445,6 +462,10 @@ impl Module {
     } else {
       None
     };
+    let maybe_resolve = match maybe_resolve {
+      Some(module_specifier) => Some(module_specifier),
+      None => compat::resolve(specifier, self.compat)?,
+    };
     let mut remapped_import = false;
     let specifier = if let Some(module_specifier) = maybe_resolve {
       remapped_import = true;
@@ -469,7 +490,7 @@ impl Module {
     }
 
     // Disallow a remote URL from trying to import a local URL, unless it is a
-    // remapped import via the import map
+    // remapped import via the import map or a Node built-in module
     if (referrer_scheme == "https" || referrer_scheme == "http")
       && !(specifier_scheme == "https" || specifier_scheme == "http")
       && !remapped_import
@@ -495,6 +516,34 @@ impl Module {
   pub fn size(&self) -> usize {
     self.source.as_bytes().len()
   }
+
+  /// The size of the emitted code, if the module has been emitted.
+  pub fn emit_size(&self) -> Option<usize> {
+    match &self.maybe_emit {
+      Some(Emit::Cli((code, _))) => Some(code.as_bytes().len()),
+      None => None,
+    }
+  }
+
+  /// The dependencies of the module, in the order they appear in its source.
+  fn dependency_infos(&self) -> Vec<DependencyInfo> {
+    let mut dependencies: Vec<DependencyInfo> = self
+      .dependencies
+      .iter()
+      .map(|(specifier, dep)| DependencyInfo {
+        code: dep.maybe_code.clone(),
+        col: dep.location.col,
+        is_dynamic: dep.is_dynamic,
+        line: dep.location.line,
+        specifier: specifier.clone(),
+        type_dependency: dep.maybe_type.clone(),
+      })
+      .collect();
+    dependencies.sort_by(|a, b| {
+      (a.line, a.col, &a.specifier).cmp(&(b.line, b.col, &b.specifier))
+    });
+    dependencies
+  }
 }
 
 #[derive(Clone, Debug, Default, Eq, PartialEq)]
@@ -608,6 +657,8 @@ pub struct CheckOptions {
   /// Ignore any previously emits and ensure that all files are emitted from
   /// source.
   pub reload: bool,
+  /// Also report the diagnostics of remote modules.
+  pub check_remote: bool,
 }
 
 #[derive(Debug, Eq, PartialEq)]
@@ -679,9 +730,9 @@ pub struct Graph {
   /// A reference to the specifier handler that will retrieve and cache modules
   /// for the graph.
   handler: Arc<Mutex<dyn SpecifierHandler>>,
-  /// Optional TypeScript build info that will be passed to `tsc` if `tsc` is
-  /// invoked.
-  maybe_tsbuildinfo: Option<String>,
+  /// The check cache entry of the last type check of the graph, along with
+  /// its key, which is written out by `flush()`.
+  maybe_check_cache: Option<(String, CheckCacheEntry)>,
   /// The modules that are part of the graph.
   modules: HashMap<ModuleSpecifier, ModuleSlot>,
   /// A map of redirects, where a module specifier is redirected to another
@@ -751,7 +802,7 @@ impl Graph {
   ) -> Self {
     Graph {
       handler,
-      maybe_tsbuildinfo: None,
+      maybe_check_cache: None,
       modules: HashMap::new(),
       redirects: HashMap::new(),
       roots: Vec::new(),
@@ -828,13 +879,36 @@ impl Graph {
     let maybe_ignored_options =
       config.merge_tsconfig(options.maybe_config_path)?;
 
+    // The state of a previous check of the same roots with the same options,
+    // which is ignored if it can't be read, so that a corrupted cache only
+    // results in a full check.
+    let check_cache_key = self.get_check_cache_key(&config);
+    let maybe_check_cache = self
+      .handler
+      .lock()
+      .unwrap()
+      .get_check_cache(&check_cache_key)
+      .unwrap_or_else(|err| {
+        debug!("could not read check cache: {}", err);
+        None
+      })
+      .filter(|entry| entry.deno_version == version::deno());
+    let versions = self.get_check_versions(&config);
+
     // Short circuit if none of the modules require an emit, or all of the
     // modules that require an emit have a valid emit.  There is also an edge
     // case where there are multiple imports of a dynamic module during a
     // single invocation, if that is the case, even if there is a reload, we
     // will simply look at if the emit is invalid, to avoid two checks for the
-    // same programme.
-    if !self.needs_emit(&config)
+    // same programme.  When nothing is emitted, the check is skipped if none
+    // of the modules changed since the last successful check.
+    let is_check_valid = !options.emit
+      && !options.reload
+      && maybe_check_cache
+        .as_ref()
+        .map_or(false, |entry| entry.versions == versions);
+    if is_check_valid
+      || !self.needs_emit(&config)
       || (self.is_emit_valid(&config)
         && (!options.reload || self.roots_dynamic))
     {
@@ -855,7 +929,8 @@ impl Graph {
     }
 
     let root_names = self.get_root_names(!config.get_check_js())?;
-    let maybe_tsbuildinfo = self.maybe_tsbuildinfo.clone();
+    let maybe_tsbuildinfo =
+      maybe_check_cache.and_then(|entry| entry.maybe_tsbuildinfo);
     let hash_data =
       vec![config.as_bytes(), version::deno().as_bytes().to_owned()];
     let graph = Arc::new(Mutex::new(self));
@@ -870,10 +945,15 @@ impl Graph {
     })?;
 
     let mut graph = graph.lock().unwrap();
-    graph.maybe_tsbuildinfo = response.maybe_tsbuildinfo;
+    let mut diagnostics = response.diagnostics;
+    // The emit of a program whose remote modules have type errors is still
+    // used, but isn't recorded as valid, so that a later check that includes
+    // remote modules reports them.
+    let ignored_remote_diagnostics =
+      !options.check_remote && diagnostics.remove_remote();
     // Only process changes to the graph if there are no diagnostics and there
     // were files emitted.
-    if response.diagnostics.is_empty() {
+    if diagnostics.is_empty() {
       if !response.emitted_files.is_empty() {
         let mut codes = HashMap::new();
         let mut maps = HashMap::new();
@@ -910,18 +990,30 @@ impl Graph {
             graph.get_module_mut(specifier).unwrap()
           {
             module.set_emit(code.clone(), maps.get(specifier).cloned());
-            module.set_version(&config);
+            if !ignored_remote_diagnostics {
+              module.set_version(&config);
+            }
             module.is_dirty = true;
           } else {
             return Err(GraphError::MissingSpecifier(specifier.clone()).into());
           }
         }
       }
+      let entry = CheckCacheEntry {
+        deno_version: version::deno(),
+        versions: if ignored_remote_diagnostics {
+          HashMap::new()
+        } else {
+          versions
+        },
+        maybe_tsbuildinfo: response.maybe_tsbuildinfo,
+      };
+      graph.maybe_check_cache = Some((check_cache_key, entry));
       graph.flush()?;
     }
 
     Ok(ResultInfo {
-      diagnostics: response.diagnostics,
+      diagnostics,
       loadable_modules: graph.get_loadable_modules(),
       maybe_ignored_options,
       stats: response.stats,
@@ -1184,10 +1276,8 @@ impl Graph {
         }
       }
     }
-    for root_specifier in self.roots.iter() {
-      if let Some(tsbuildinfo) = &self.maybe_tsbuildinfo {
-        handler.set_tsbuildinfo(root_specifier, tsbuildinfo.to_owned())?;
-      }
+    if let Some((key, entry)) = self.maybe_check_cache.take() {
+      handler.set_check_cache(&key, &entry)?;
     }
 
     Ok(())
@@ -1199,12 +1289,20 @@ impl Graph {
     seen: &mut HashSet<ModuleSpecifier>,
     totals: &mut HashMap<ModuleSpecifier, usize>,
   ) -> ModuleInfo {
-    let not_seen = seen.insert(specifier.clone());
+    // Modules are tracked by their redirected specifier, so a module that is
+    // imported via more than one specifier is only counted once.
+    let resolved_specifier = self.resolve_specifier(specifier);
+    let not_seen = seen.insert(resolved_specifier.clone());
     let module = match self.get_module(specifier) {
       ModuleSlot::Module(module) => module,
       ModuleSlot::Err(err) => {
-        error!("{}: {}", colors::red_bold("error"), err.to_string());
-        std::process::exit(1);
+        return ModuleInfo {
+          deps: Vec::new(),
+          error: Some(err.to_string()),
+          name: specifier.clone(),
+          size: 0,
+          total_size: None,
+        };
       }
       _ => unreachable!(),
     };
@@ -1223,7 +1321,7 @@ impl Graph {
         }
       }
       deps.sort();
-      total_size = if let Some(total) = totals.get(specifier) {
+      total_size = if let Some(total) = totals.get(resolved_specifier) {
         Some(total.to_owned())
       } else {
         let mut total = deps
@@ -1237,13 +1335,14 @@ impl Graph {
           })
           .sum();
         total += module.size();
-        totals.insert(specifier.clone(), total);
+        totals.insert(resolved_specifier.clone(), total);
         Some(total)
       };
     }
 
     ModuleInfo {
       deps,
+      error: None,
       name: specifier.clone(),
       size: module.size(),
       total_size,
@@ -1255,27 +1354,42 @@ impl Graph {
       .modules
       .iter()
       .filter_map(|(specifier, module_slot)| {
-        if let ModuleSlot::Module(module) = module_slot {
-          let mut deps = BTreeSet::new();
-          for (_, dep) in module.dependencies.iter() {
-            if let Some(code_dep) = &dep.maybe_code {
-              deps.insert(code_dep.clone());
+        let item = match module_slot {
+          ModuleSlot::Module(module) => {
+            let mut deps = BTreeSet::new();
+            for (_, dep) in module.dependencies.iter() {
+              if let Some(code_dep) = &dep.maybe_code {
+                deps.insert(code_dep.clone());
+              }
+              if let Some(type_dep) = &dep.maybe_type {
+                deps.insert(type_dep.clone());
+              }
             }
-            if let Some(type_dep) = &dep.maybe_type {
-              deps.insert(type_dep.clone());
+            if let Some((_, types_dep)) = &module.maybe_types {
+              deps.insert(types_dep.clone());
+            }
+            ModuleInfoMapItem {
+              dependencies: module.dependency_infos(),
+              deps: deps.into_iter().collect(),
+              emit_size: module.emit_size(),
+              error: None,
+              local: Some(module.source_path.clone()),
+              media_type: module.media_type,
+              size: module.size(),
             }
           }
-          if let Some((_, types_dep)) = &module.maybe_types {
-            deps.insert(types_dep.clone());
-          }
-          let item = ModuleInfoMapItem {
-            deps: deps.into_iter().collect(),
-            size: module.size(),
-          };
-          Some((specifier.clone(), item))
-        } else {
-          None
-        }
+          ModuleSlot::Err(err) => ModuleInfoMapItem {
+            dependencies: Vec::new(),
+            deps: Vec::new(),
+            emit_size: None,
+            error: Some(err.to_string()),
+            local: None,
+            media_type: MediaType::Unknown,
+            size: 0,
+          },
+          _ => return None,
+        };
+        Some((specifier.clone(), item))
       })
       .collect();
 
@@ -1412,6 +1526,18 @@ impl Graph {
     Ok(root_types)
   }
 
+  /// Return the redirects that occurred when building the graph, mapping each
+  /// redirected specifier to the final specifier of the module it loaded.
+  pub fn get_redirects(&self) -> HashMap<ModuleSpecifier, ModuleSpecifier> {
+    self
+      .redirects
+      .keys()
+      .map(|specifier| {
+        (specifier.clone(), self.resolve_specifier(specifier).clone())
+      })
+      .collect()
+  }
+
   /// Get the source for a given module specifier.  If the module is not part
   /// of the graph, the result will be `None`.
   pub fn get_source(&self, specifier: &ModuleSpecifier) -> Option<String> {
@@ -1442,7 +1568,22 @@ impl Graph {
     let info = self.get_info(&module, &mut seen, &mut totals);
 
     let files = self.get_info_map();
-    let total_size = totals.get(&module).unwrap_or(&m.size()).to_owned();
+    let total_size = totals
+      .get(self.resolve_specifier(&module))
+      .unwrap_or(&m.size())
+      .to_owned();
+    let total_emit_size = seen
+      .iter()
+      .filter_map(|specifier| match self.get_module(specifier) {
+        ModuleSlot::Module(module) => module.emit_size(),
+        _ => None,
+      })
+      .sum();
+    let redirects = self
+      .redirects
+      .iter()
+      .map(|(from, to)| (from.to_string(), to.clone()))
+      .collect();
     let (compiled, map) =
       if let Some((emit_path, maybe_map_path)) = &m.maybe_emit_path {
         (Some(emit_path.clone()), maybe_map_path.clone())
@@ -1469,10 +1610,58 @@ impl Graph {
       local: m.source_path.clone(),
       map,
       module,
+      redirects,
+      total_emit_size,
       total_size,
     })
   }
 
+  /// Return the modules of the graph that directly import `specifier`, after
+  /// following redirects on both the imported specifiers and `specifier`.
+  pub fn importers(
+    &self,
+    specifier: &ModuleSpecifier,
+  ) -> Result<ModuleImportersInfo, AnyError> {
+    let resolved_specifier = self.resolve_specifier(specifier);
+    if !self.modules.contains_key(resolved_specifier) {
+      return Err(GraphError::MissingSpecifier(specifier.clone()).into());
+    }
+    let is_target = |dep: &Option<ModuleSpecifier>| {
+      dep
+        .as_ref()
+        .map(|dep| self.resolve_specifier(dep) == resolved_specifier)
+        .unwrap_or(false)
+    };
+
+    let mut importers = Vec::new();
+    for (module_specifier, module_slot) in self.modules.iter() {
+      if let ModuleSlot::Module(module) = module_slot {
+        for dependency in module.dependency_infos() {
+          if is_target(&dependency.code)
+            || is_target(&dependency.type_dependency)
+          {
+            importers.push(ImporterInfo {
+              dependency,
+              module: module_specifier.clone(),
+            });
+          }
+        }
+      }
+    }
+    importers.sort_by(|a, b| {
+      (a.module.as_str(), a.dependency.line, a.dependency.col).cmp(&(
+        b.module.as_str(),
+        b.dependency.line,
+        b.dependency.col,
+      ))
+    });
+
+    Ok(ModuleImportersInfo {
+      importers,
+      module: resolved_specifier.clone(),
+    })
+  }
+
   /// Determines if all of the modules in the graph that require an emit have
   /// a valid emit.  Returns `true` if all the modules have a valid emit,
   /// otherwise false.
@@ -1497,23 +1686,55 @@ impl Graph {
     })
   }
 
+  /// The key of the check cache entry for the roots of the graph checked with
+  /// `config`, which doesn't depend on the order the roots were added in.
+  fn get_check_cache_key(&self, config: &TsConfig) -> String {
+    let mut roots: Vec<String> =
+      self.roots.iter().map(|s| s.to_string()).collect();
+    roots.sort();
+    let roots = roots.join("\n");
+    crate::checksum::gen(&[roots.as_bytes(), &config.as_bytes()])
+  }
+
+  /// The versions of all of the modules of the graph, which are recorded in
+  /// the check cache to tell if any of them changed since the last check.
+  fn get_check_versions(&self, config: &TsConfig) -> HashMap<String, String> {
+    let config = config.as_bytes();
+    self
+      .modules
+      .values()
+      .filter_map(|module_slot| match module_slot {
+        ModuleSlot::Module(m) => Some((
+          m.specifier.to_string(),
+          get_version(&m.source, &version::deno(), &config),
+        )),
+        _ => None,
+      })
+      .collect()
+  }
+
   /// Verify the subresource integrity of the graph based upon the optional
   /// lockfile, updating the lockfile with any missing resources.  This will
-  /// error if any of the resources do not match their lock status.
+  /// error if any of the resources do not match their lock status, or are
+  /// missing from a frozen lockfile.
   pub fn lock(&self) {
     if let Some(lf) = self.maybe_lockfile.as_ref() {
       let mut lockfile = lf.lock().unwrap();
       for (ms, module_slot) in self.modules.iter() {
         if let ModuleSlot::Module(module) = module_slot {
           let specifier = module.specifier.to_string();
-          let valid = lockfile.check_or_insert(&specifier, &module.source);
-          if !valid {
-            eprintln!(
-              "{}",
-              GraphError::InvalidSource(ms.clone(), lockfile.filename.clone())
-            );
-            std::process::exit(10);
-          }
+          let filename = lockfile.filename.clone();
+          let err = match lockfile.check_or_insert(&specifier, &module.source) {
+            Ok(()) => continue,
+            Err(LockfileError::Mismatch { expected, actual }) => {
+              GraphError::InvalidSource(ms.clone(), filename, expected, actual)
+            }
+            Err(LockfileError::Missing) => {
+              GraphError::MissingFromLockfile(ms.clone(), filename)
+            }
+          };
+          eprintln!("{}", err);
+          std::process::exit(10);
         }
       }
     }
@@ -1788,6 +2009,7 @@ impl swc_bundler::Resolve for Graph {
 pub struct GraphBuilder {
   graph: Graph,
   maybe_import_map: Option<Arc<Mutex<ImportMap>>>,
+  compat: bool,
   pending: FuturesUnordered<FetchFuture>,
 }
 
@@ -1805,10 +2027,17 @@ impl GraphBuilder {
     GraphBuilder {
       graph: Graph::new(handler, maybe_lockfile),
       maybe_import_map: internal_import_map,
+      compat: false,
       pending: FuturesUnordered::new(),
     }
   }
 
+  /// Sets whether bare names of Node built-in modules are resolved, as with
+  /// `--compat`.
+  pub fn set_compat(&mut self, compat: bool) {
+    self.compat = compat;
+  }
+
   /// Add a module into the graph based on a module specifier.  The module
   /// and any dependencies will be fetched from the handler.  The module will
   /// also be treated as a _root_ module in the graph.
@@ -1822,6 +2051,25 @@ impl GraphBuilder {
     loop {
       match self.pending.next().await {
         Some(Err((specifier, err))) => {
+          // Point at the entry of the import map that produced the specifier,
+          // as it is most likely where the mistake is.
+          let maybe_entry = self
+            .maybe_import_map
+            .as_ref()
+            .and_then(|im| im.lock().unwrap().describe_entry(&specifier));
+          let err = match maybe_entry {
+            Some(entry) => {
+              let message = format!(
+                "{}\n    Mapped by the import map entry {}",
+                err, entry
+              );
+              match get_custom_error_class(&err) {
+                Some(class) => custom_error(class, message),
+                None => anyhow!(message),
+              }
+            }
+            None => err,
+          };
           self
             .graph
             .modules
@@ -1841,10 +2089,6 @@ impl GraphBuilder {
     if !self.graph.roots.contains(specifier) {
       self.graph.roots.push(specifier.clone());
       self.graph.roots_dynamic = self.graph.roots_dynamic && is_dynamic;
-      if self.graph.maybe_tsbuildinfo.is_none() {
-        let handler = self.graph.handler.lock().unwrap();
-        self.graph.maybe_tsbuildinfo = handler.get_tsbuildinfo(specifier)?;
-      }
     }
 
     Ok(())
@@ -1880,8 +2124,12 @@ impl GraphBuilder {
   ) -> Result<(), AnyError> {
     let specifier = cached_module.specifier.clone();
     let requested_specifier = cached_module.requested_specifier.clone();
-    let mut module =
-      Module::new(cached_module, is_root, self.maybe_import_map.clone());
+    let mut module = Module::new(
+      cached_module,
+      is_root,
+      self.maybe_import_map.clone(),
+      self.compat,
+    );
     match module.media_type {
       MediaType::Json
       | MediaType::SourceMap
@@ -1900,7 +2148,7 @@ impl GraphBuilder {
     if !module.is_parsed {
       let has_types = module.maybe_types.is_some();
       module.parse()?;
-      if self.maybe_import_map.is_none() {
+      if self.maybe_import_map.is_none() && !self.compat {
         let mut handler = self.graph.handler.lock().unwrap();
         handler.set_deps(&specifier, module.dependencies.clone())?;
         if !has_types {
@@ -1955,6 +2203,7 @@ pub mod tests {
   use std::fs;
   use std::path::PathBuf;
   use std::sync::Mutex;
+  use tempfile::TempDir;
 
   macro_rules! map (
     { $($key:expr => $value:expr),+ } => {
@@ -1974,8 +2223,9 @@ pub mod tests {
   #[derive(Debug, Default)]
   pub struct MockSpecifierHandler {
     pub fixtures: PathBuf,
-    pub maybe_tsbuildinfo: Option<String>,
-    pub tsbuildinfo_calls: Vec<(ModuleSpecifier, String)>,
+    pub check_cache: HashMap<String, CheckCacheEntry>,
+    pub redirects: HashMap<ModuleSpecifier, ModuleSpecifier>,
+    pub check_cache_calls: Vec<(String, CheckCacheEntry)>,
     pub cache_calls: Vec<(ModuleSpecifier, Emit)>,
     pub deps_calls: Vec<(ModuleSpecifier, DependencyMap)>,
     pub types_calls: Vec<(ModuleSpecifier, String)>,
@@ -1985,8 +2235,13 @@ pub mod tests {
   impl MockSpecifierHandler {
     fn get_cache(
       &self,
-      specifier: ModuleSpecifier,
+      requested_specifier: ModuleSpecifier,
     ) -> Result<CachedModule, (ModuleSpecifier, AnyError)> {
+      let specifier = self
+        .redirects
+        .get(&requested_specifier)
+        .cloned()
+        .unwrap_or_else(|| requested_specifier.clone());
       let specifier_text = specifier
         .to_string()
         .replace(":///", "_")
@@ -1995,12 +2250,12 @@ pub mod tests {
       let source_path = self.fixtures.join(specifier_text);
       let media_type = MediaType::from(&source_path);
       let source = fs::read_to_string(&source_path)
-        .map_err(|err| (specifier.clone(), err.into()))?;
+        .map_err(|err| (requested_specifier.clone(), err.into()))?;
       let is_remote = specifier.scheme() != "file";
 
       Ok(CachedModule {
         source,
-        requested_specifier: specifier.clone(),
+        requested_specifier,
         source_path,
         specifier,
         media_type,
@@ -2019,11 +2274,11 @@ pub mod tests {
     ) -> FetchFuture {
       Box::pin(future::ready(self.get_cache(specifier)))
     }
-    fn get_tsbuildinfo(
+    fn get_check_cache(
       &self,
-      _specifier: &ModuleSpecifier,
-    ) -> Result<Option<String>, AnyError> {
-      Ok(self.maybe_tsbuildinfo.clone())
+      key: &str,
+    ) -> Result<Option<CheckCacheEntry>, AnyError> {
+      Ok(self.check_cache.get(key).cloned())
     }
     fn set_cache(
       &mut self,
@@ -2041,15 +2296,15 @@ pub mod tests {
       self.types_calls.push((specifier.clone(), types));
       Ok(())
     }
-    fn set_tsbuildinfo(
+    fn set_check_cache(
       &mut self,
-      specifier: &ModuleSpecifier,
-      tsbuildinfo: String,
+      key: &str,
+      entry: &CheckCacheEntry,
     ) -> Result<(), AnyError> {
-      self.maybe_tsbuildinfo = Some(tsbuildinfo.clone());
+      self.check_cache.insert(key.to_string(), entry.clone());
       self
-        .tsbuildinfo_calls
-        .push((specifier.clone(), tsbuildinfo));
+        .check_cache_calls
+        .push((key.to_string(), entry.clone()));
       Ok(())
     }
     fn set_deps(
@@ -2235,14 +2490,15 @@ pub mod tests {
         lib: TypeLib::DenoWindow,
         maybe_config_path: None,
         reload: false,
+        check_remote: true,
       })
       .expect("should have checked");
     assert!(result_info.maybe_ignored_options.is_none());
-    assert_eq!(result_info.stats.0.len(), 12);
+    assert_eq!(result_info.stats.0.len(), 13);
     assert!(result_info.diagnostics.is_empty());
     let h = handler.lock().unwrap();
     assert_eq!(h.cache_calls.len(), 2);
-    assert_eq!(h.tsbuildinfo_calls.len(), 1);
+    assert_eq!(h.check_cache_calls.len(), 1);
   }
 
   #[tokio::test]
@@ -2257,6 +2513,7 @@ pub mod tests {
         lib: TypeLib::DenoWindow,
         maybe_config_path: None,
         reload: false,
+        check_remote: true,
       })
       .expect("should have checked");
     assert!(result_info.diagnostics.is_empty());
@@ -2274,16 +2531,56 @@ pub mod tests {
         lib: TypeLib::DenoWindow,
         maybe_config_path: None,
         reload: false,
+        check_remote: true,
       })
       .expect("should have checked");
     assert!(result_info.maybe_ignored_options.is_none());
-    assert_eq!(result_info.stats.0.len(), 12);
+    assert_eq!(result_info.stats.0.len(), 13);
     assert!(!result_info.diagnostics.is_empty());
     let h = handler.lock().unwrap();
-    // we shouldn't cache any files or write out tsbuildinfo if there are
+    // we shouldn't cache any files or write out the check cache if there are
     // diagnostic errors
     assert_eq!(h.cache_calls.len(), 0);
-    assert_eq!(h.tsbuildinfo_calls.len(), 0);
+    assert_eq!(h.check_cache_calls.len(), 0);
+  }
+
+  #[tokio::test]
+  async fn test_graph_check_ignores_remote_diagnostics() {
+    let specifier = resolve_url_or_path("file:///tests/remotediag.ts")
+      .expect("could not resolve module");
+    let (graph, handler) = setup(specifier.clone()).await;
+    let result_info = graph
+      .check(CheckOptions {
+        debug: false,
+        emit: true,
+        lib: TypeLib::DenoWindow,
+        maybe_config_path: None,
+        reload: false,
+        check_remote: false,
+      })
+      .expect("should have checked");
+    assert!(result_info.diagnostics.is_empty());
+    let h = handler.lock().unwrap();
+    // the emit is used, but not recorded as valid
+    assert_eq!(h.cache_calls.len(), 2);
+    assert_eq!(h.version_calls.len(), 0);
+    drop(h);
+
+    let (graph, _) = setup(specifier).await;
+    let result_info = graph
+      .check(CheckOptions {
+        debug: false,
+        emit: true,
+        lib: TypeLib::DenoWindow,
+        maybe_config_path: None,
+        reload: false,
+        check_remote: true,
+      })
+      .expect("should have checked");
+    assert!(result_info
+      .diagnostics
+      .to_string()
+      .contains("https://deno.land/x/diag.ts"));
   }
 
   #[tokio::test]
@@ -2298,14 +2595,124 @@ pub mod tests {
         lib: TypeLib::DenoWindow,
         maybe_config_path: None,
         reload: false,
+        check_remote: true,
       })
       .expect("should have checked");
     assert!(result_info.maybe_ignored_options.is_none());
-    assert_eq!(result_info.stats.0.len(), 12);
+    assert_eq!(result_info.stats.0.len(), 13);
     assert!(result_info.diagnostics.is_empty());
     let h = handler.lock().unwrap();
     assert_eq!(h.cache_calls.len(), 0);
-    assert_eq!(h.tsbuildinfo_calls.len(), 1);
+    assert_eq!(h.check_cache_calls.len(), 1);
+  }
+
+  fn get_stat(stats: &Stats, name: &str) -> Option<u32> {
+    stats.0.iter().find(|(n, _)| n == name).map(|(_, v)| *v)
+  }
+
+  #[tokio::test]
+  async fn test_graph_check_cache_unchanged() {
+    let specifier = resolve_url_or_path("file:///tests/main.ts")
+      .expect("could not resolve module");
+    let options = || CheckOptions {
+      debug: false,
+      emit: false,
+      lib: TypeLib::DenoWindow,
+      maybe_config_path: None,
+      reload: false,
+      check_remote: true,
+    };
+    let (graph, handler) = setup(specifier.clone()).await;
+    let result_info = graph.check(options()).expect("should have checked");
+    assert!(result_info.diagnostics.is_empty());
+    assert!(get_stat(&result_info.stats, "Checked files").unwrap() > 0);
+
+    // Checking the same graph again doesn't invoke the compiler at all.
+    let mut builder = GraphBuilder::new(handler.clone(), None, None);
+    builder
+      .add(&specifier, false)
+      .await
+      .expect("module not inserted");
+    let result_info = builder
+      .get_graph()
+      .check(options())
+      .expect("should have checked");
+    assert!(result_info.diagnostics.is_empty());
+    assert!(result_info.stats.0.is_empty());
+
+    // An entry of another version of Deno is ignored.
+    let mut h = handler.lock().unwrap();
+    for entry in h.check_cache.values_mut() {
+      entry.deno_version = "0.0.0".to_string();
+    }
+    drop(h);
+    let mut builder = GraphBuilder::new(handler.clone(), None, None);
+    builder
+      .add(&specifier, false)
+      .await
+      .expect("module not inserted");
+    let result_info = builder
+      .get_graph()
+      .check(options())
+      .expect("should have checked");
+    assert!(!result_info.stats.0.is_empty());
+  }
+
+  #[tokio::test]
+  async fn test_graph_check_cache_rechecks_dependents() {
+    let temp_dir = TempDir::new().expect("could not create temp dir");
+    let fixtures = temp_dir.path().to_path_buf();
+    fs::write(
+      fixtures.join("file_tests-a.ts"),
+      "import { b } from \"./b.ts\";\nexport const a: string = b;\n",
+    )
+    .unwrap();
+    fs::write(
+      fixtures.join("file_tests-b.ts"),
+      "export const b = \"b\";\n",
+    )
+    .unwrap();
+    fs::write(fixtures.join("file_tests-c.ts"), "export const c = 1;\n")
+      .unwrap();
+    let handler = Arc::new(Mutex::new(MockSpecifierHandler {
+      fixtures: fixtures.clone(),
+      ..MockSpecifierHandler::default()
+    }));
+    async fn check(handler: Arc<Mutex<MockSpecifierHandler>>) -> ResultInfo {
+      let mut builder = GraphBuilder::new(handler, None, None);
+      for root in &["file:///tests/a.ts", "file:///tests/c.ts"] {
+        let specifier = resolve_url_or_path(root).unwrap();
+        builder
+          .add(&specifier, false)
+          .await
+          .expect("module not inserted");
+      }
+      builder
+        .get_graph()
+        .check(CheckOptions {
+          debug: false,
+          emit: false,
+          lib: TypeLib::DenoWindow,
+          maybe_config_path: None,
+          reload: false,
+          check_remote: true,
+        })
+        .expect("should have checked")
+    }
+
+    let result_info = check(handler.clone()).await;
+    assert!(result_info.diagnostics.is_empty());
+    assert_eq!(handler.lock().unwrap().check_cache_calls.len(), 1);
+
+    // Only the edited module and the module that imports it are checked.
+    fs::write(
+      fixtures.join("file_tests-b.ts"),
+      "export const b = \"b\";\nexport const bb = 1;\n",
+    )
+    .unwrap();
+    let result_info = check(handler.clone()).await;
+    assert!(result_info.diagnostics.is_empty());
+    assert_eq!(get_stat(&result_info.stats, "Checked files"), Some(2));
   }
 
   #[tokio::test]
@@ -2320,13 +2727,14 @@ pub mod tests {
         lib: TypeLib::DenoWindow,
         maybe_config_path: None,
         reload: false,
+        check_remote: true,
       })
       .expect("should have checked");
     assert!(result_info.maybe_ignored_options.is_none());
     assert!(result_info.diagnostics.is_empty());
     let h = handler.lock().unwrap();
     assert_eq!(h.cache_calls.len(), 1);
-    assert_eq!(h.tsbuildinfo_calls.len(), 1);
+    assert_eq!(h.check_cache_calls.len(), 1);
   }
 
   #[tokio::test]
@@ -2341,6 +2749,7 @@ pub mod tests {
         lib: TypeLib::DenoWindow,
         maybe_config_path: None,
         reload: false,
+        check_remote: true,
       })
       .expect("should have checked");
     assert!(result_info.diagnostics.is_empty());
@@ -2360,6 +2769,7 @@ pub mod tests {
           "tests/module_graph/tsconfig_01.json".to_string(),
         ),
         reload: true,
+        check_remote: true,
       })
       .expect("should have checked");
     assert!(result_info.maybe_ignored_options.is_none());
@@ -2381,6 +2791,7 @@ pub mod tests {
           "tests/module_graph/tsconfig_01.json".to_string(),
         ),
         reload: true,
+        check_remote: true,
       })
       .expect("should have checked");
     assert!(result_info.maybe_ignored_options.is_none());
@@ -2514,7 +2925,7 @@ pub mod tests {
   async fn test_graph_info() {
     let specifier = resolve_url_or_path("file:///tests/main.ts")
       .expect("could not resolve module");
-    let (graph, _) = setup(specifier).await;
+    let (graph, _) = setup(specifier.clone()).await;
     let info = graph.info().expect("could not get info");
     assert!(info.compiled.is_none());
     assert_eq!(info.dep_count, 6);
@@ -2527,6 +2938,119 @@ pub mod tests {
       resolve_url_or_path("file:///tests/main.ts").unwrap()
     );
     assert_eq!(info.total_size, 344);
+    assert_eq!(info.total_emit_size, 0);
+    assert!(info.first_error().is_none());
+    assert_eq!(
+      json!(info.files.0[&specifier]),
+      json!({
+        "dependencies": [{
+          "code": "https://deno.land/x/lib/mod.js",
+          "col": 0,
+          "isDynamic": false,
+          "line": 2,
+          "specifier": "https://deno.land/x/lib/mod.js",
+          "type": "https://deno.land/x/lib/mod.d.ts",
+        }],
+        "deps": [
+          "https://deno.land/x/lib/mod.d.ts",
+          "https://deno.land/x/lib/mod.js",
+        ],
+        "emitSize": null,
+        "error": null,
+        "local": info.local,
+        "mediaType": "TypeScript",
+        "size": 124,
+      })
+    );
+  }
+
+  #[tokio::test]
+  async fn test_graph_info_redirects() {
+    let specifier = resolve_url_or_path("file:///tests/inforedirect.ts")
+      .expect("could not resolve module");
+    let redirect = resolve_url_or_path("https://deno.land/x/redirect.ts")
+      .expect("could not resolve module");
+    let a = resolve_url_or_path("https://deno.land/x/a.ts")
+      .expect("could not resolve module");
+    let c = PathBuf::from(env::var_os("CARGO_MANIFEST_DIR").unwrap());
+    let fixtures = c.join("tests/module_graph");
+    let mut redirects = HashMap::new();
+    redirects.insert(redirect.clone(), a.clone());
+    let handler = Arc::new(Mutex::new(MockSpecifierHandler {
+      fixtures,
+      redirects,
+      ..MockSpecifierHandler::default()
+    }));
+    let mut builder = GraphBuilder::new(handler.clone(), None, None);
+    builder
+      .add(&specifier, false)
+      .await
+      .expect("module not inserted");
+    let graph = builder.get_graph();
+    let info = graph.info().expect("could not get info");
+    assert_eq!(info.dep_count, 2);
+    assert_eq!(info.files.0.len(), 3);
+    // The redirected module is only counted once.
+    assert_eq!(info.total_size, 180 + 26 + 46);
+    assert_eq!(
+      json!(info.redirects),
+      json!({ "https://deno.land/x/redirect.ts": "https://deno.land/x/a.ts" })
+    );
+
+    let importers =
+      graph.importers(&redirect).expect("could not get importers");
+    assert_eq!(importers.module, a);
+    let actual: Vec<(String, String)> = importers
+      .importers
+      .iter()
+      .map(|i| (i.module.to_string(), i.dependency.specifier.clone()))
+      .collect();
+    assert_eq!(
+      actual,
+      vec![
+        (
+          "file:///tests/inforedirect.ts".to_string(),
+          "https://deno.land/x/a.ts".to_string()
+        ),
+        (
+          "file:///tests/inforedirect.ts".to_string(),
+          "https://deno.land/x/redirect.ts".to_string()
+        ),
+        (
+          "https://deno.land/x/mod.ts".to_string(),
+          "./a.ts".to_string()
+        ),
+      ]
+    );
+    let importers = graph
+      .importers(&specifier)
+      .expect("could not get importers");
+    assert!(importers.importers.is_empty());
+    let missing = resolve_url_or_path("https://deno.land/x/missing.ts")
+      .expect("could not resolve module");
+    assert!(graph.importers(&missing).is_err());
+  }
+
+  #[tokio::test]
+  async fn test_graph_info_error() {
+    let specifier = resolve_url_or_path("file:///tests/main.ts")
+      .expect("could not resolve module");
+    let graph = setup_memory(
+      specifier.clone(),
+      map!(
+        "/tests/main.ts" => r#"import * as a from "./a.ts";
+
+        console.log(a);
+        "#
+      ),
+    )
+    .await;
+    let info = graph.info().expect("could not get info");
+    let a = resolve_url_or_path("file:///tests/a.ts").unwrap();
+    let error = info.first_error().expect("missing error");
+    assert!(error.contains("Unable to find specifier in sources"));
+    assert_eq!(info.files.0[&a].error.as_deref(), Some(error));
+    assert_eq!(info.total_size, info.files.0[&specifier].size);
   }
 
   #[tokio::test]
@@ -2665,8 +3189,8 @@ pub mod tests {
     let c = PathBuf::from(env::var_os("CARGO_MANIFEST_DIR").unwrap());
     let fixtures = c.join("tests/module_graph");
     let lockfile_path = fixtures.join("lockfile.json");
-    let lockfile =
-      Lockfile::new(lockfile_path, false).expect("could not load lockfile");
+    let lockfile = Lockfile::new(lockfile_path, false, true)
+      .expect("could not load lockfile");
     let maybe_lockfile = Some(Arc::new(Mutex::new(lockfile)));
     let handler = Arc::new(Mutex::new(MockSpecifierHandler {
       fixtures,
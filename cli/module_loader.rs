@@ -1,5 +1,6 @@
 // Copyright 2018-2021 the Deno authors. All rights reserved. MIT license.
 
+use crate::compat;
 use crate::import_map::ImportMap;
 use crate::module_graph::TypeLib;
 use crate::program_state::ProgramState;
@@ -90,6 +91,13 @@ impl ModuleLoader for CliModuleLoader {
       }
     }
 
+    // Resolved like in the module graph, which has loaded the modules.
+    if let Some(module_specifier) =
+      compat::resolve(specifier, self.program_state.flags.compat)?
+    {
+      return Ok(module_specifier);
+    }
+
     let module_specifier = deno_core::resolve_import(specifier, referrer)?;
 
     Ok(module_specifier)
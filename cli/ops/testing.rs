@@ -0,0 +1,58 @@
+// Copyright 2018-2021 the Deno authors. All rights reserved. MIT license.
+
+use crate::tools::test_runner::TestEvent;
+use crate::tools::test_runner::TestEventSender;
+use deno_core::error::generic_error;
+use deno_core::error::AnyError;
+use deno_core::serde_json;
+use deno_core::serde_json::json;
+use deno_core::serde_json::Value;
+use deno_core::OpState;
+use deno_core::ZeroCopyBuf;
+use serde::Deserialize;
+
+pub fn init(rt: &mut deno_core::JsRuntime) {
+  super::reg_json_sync(rt, "op_test_output", op_test_output);
+  super::reg_json_sync(rt, "op_test_message", op_test_message);
+  super::reg_json_sync(rt, "op_test_summary", op_test_summary);
+}
+
+fn send(state: &mut OpState, event: TestEvent) -> Result<Value, AnyError> {
+  let sender = state.try_borrow::<TestEventSender>().ok_or_else(|| {
+    generic_error("Test events can only be sent by the workers of `deno test`")
+  })?;
+  sender.send(event);
+  Ok(json!({}))
+}
+
+#[derive(Deserialize)]
+struct TestOutputArgs {
+  text: String,
+}
+
+fn op_test_output(
+  state: &mut OpState,
+  args: Value,
+  _zero_copy: &mut [ZeroCopyBuf],
+) -> Result<Value, AnyError> {
+  let args: TestOutputArgs = serde_json::from_value(args)?;
+  send(state, TestEvent::Output(args.text))
+}
+
+fn op_test_message(
+  state: &mut OpState,
+  args: Value,
+  _zero_copy: &mut [ZeroCopyBuf],
+) -> Result<Value, AnyError> {
+  let message = serde_json::from_value(args)?;
+  send(state, TestEvent::Message(message))
+}
+
+fn op_test_summary(
+  state: &mut OpState,
+  args: Value,
+  _zero_copy: &mut [ZeroCopyBuf],
+) -> Result<Value, AnyError> {
+  let summary = serde_json::from_value(args)?;
+  send(state, TestEvent::Summary(summary))
+}
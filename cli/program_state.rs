@@ -4,6 +4,7 @@ use crate::deno_dir;
 use crate::file_fetcher::CacheSetting;
 use crate::file_fetcher::FileFetcher;
 use crate::flags;
+use crate::fs_util::canonicalize_path;
 use crate::http_cache;
 use crate::import_map::ImportMap;
 use crate::lockfile::Lockfile;
@@ -13,21 +14,27 @@ use crate::module_graph::TranspileOptions;
 use crate::module_graph::TypeLib;
 use crate::source_maps::SourceMapGetter;
 use crate::specifier_handler::FetchHandler;
+use crate::tools::task::find_config_file;
+use crate::tools::task::parse_jsonc;
 use crate::version;
 use deno_runtime::inspector::InspectorServer;
 use deno_runtime::permissions::Permissions;
 
 use deno_core::error::anyhow;
+use deno_core::error::generic_error;
 use deno_core::error::get_custom_error_class;
 use deno_core::error::AnyError;
 use deno_core::error::Context;
 use deno_core::resolve_url;
+use deno_core::serde_json::Value;
 use deno_core::url::Url;
 use deno_core::ModuleSource;
 use deno_core::ModuleSpecifier;
 use std::collections::HashMap;
 use std::env;
 use std::fs::read;
+use std::path::Path;
+use std::path::PathBuf;
 use std::sync::Arc;
 use std::sync::Mutex;
 
@@ -39,6 +46,39 @@ pub fn exit_unstable(api_name: &str) {
   std::process::exit(70);
 }
 
+/// Reads the import map embedded in the configuration file passed with
+/// `--config`, or else the first `deno.json` or `deno.jsonc` in the current
+/// directory or its parents, returning the URL of the file along with the
+/// import map JSON value.
+pub fn read_embedded_import_map(
+  flags: &flags::Flags,
+) -> Result<Option<(ModuleSpecifier, Value)>, AnyError> {
+  let config_path = match &flags.config_path {
+    Some(path) => PathBuf::from(path),
+    None => match find_config_file(&env::current_dir()?) {
+      Some(path) => path,
+      None => return Ok(None),
+    },
+  };
+  let config_path = canonicalize_path(&config_path)
+    .with_context(|| format!("Unable to find config file {:?}", config_path))?;
+  let text = std::fs::read_to_string(&config_path)
+    .with_context(|| format!("Unable to read config file {:?}", config_path))?;
+  let config = parse_jsonc(&text).with_context(|| {
+    format!("Unable to parse config file {:?}", config_path)
+  })?;
+  match ImportMap::embedded_value(&config) {
+    Some(import_map) => {
+      let config_url =
+        ModuleSpecifier::from_file_path(&config_path).map_err(|_| {
+          anyhow!("Bad file path for config file {:?}", config_path)
+        })?;
+      Ok(Some((config_url, import_map)))
+    }
+    None => Ok(None),
+  }
+}
+
 /// This structure represents state of single "deno" program.
 ///
 /// It is shared by all created workers (thus V8 isolates).
@@ -68,7 +108,10 @@ impl ProgramState {
       None => None,
     };
 
-    let cache_usage = if flags.cached_only {
+    let cache_usage = if flags.cached_only && !flags.cache_blocklist.is_empty()
+    {
+      CacheSetting::OnlyReloadSome(flags.cache_blocklist.clone())
+    } else if flags.cached_only {
       CacheSetting::Only
     } else if !flags.cache_blocklist.is_empty() {
       CacheSetting::ReloadSome(flags.cache_blocklist.clone())
@@ -85,32 +128,56 @@ impl ProgramState {
       ca_data.clone(),
     )?;
 
+    // A lock file passed with --lock is only checked, unless --lock-write is
+    // passed, while the deno.lock next to a deno.json is kept up to date.
     let lockfile = if let Some(filename) = &flags.lock {
-      let lockfile = Lockfile::new(filename.clone(), flags.lock_write)?;
+      let lockfile =
+        Lockfile::new(filename.clone(), flags.lock_write, !flags.lock_write)?;
       Some(Arc::new(Mutex::new(lockfile)))
+    } else if let Some(config_path) = &flags.config_path {
+      Lockfile::discover(Path::new(config_path), flags.frozen_lockfile)?
+        .map(|lockfile| Arc::new(Mutex::new(lockfile)))
     } else {
       None
     };
 
-    let maybe_import_map: Option<ImportMap> =
-      match flags.import_map_path.as_ref() {
+    let maybe_embedded_import_map = read_embedded_import_map(&flags)?;
+    let maybe_import_map: Option<ImportMap> = match flags
+      .import_map_path
+      .as_ref()
+    {
+      None => match maybe_embedded_import_map {
         None => None,
-        Some(import_map_url) => {
+        Some((config_url, import_map)) => {
           if !flags.unstable {
-            exit_unstable("--import-map")
+            exit_unstable("import map in the config file")
           }
-          let import_map_specifier =
-            deno_core::resolve_url_or_path(&import_map_url).context(
-              format!("Bad URL (\"{}\") for import map.", import_map_url),
-            )?;
-          let file = file_fetcher
-            .fetch(&import_map_specifier, &Permissions::allow_all())
-            .await?;
-          let import_map =
-            ImportMap::from_json(import_map_specifier.as_str(), &file.source)?;
-          Some(import_map)
+          Some(ImportMap::from_value(config_url.as_str(), &import_map)?)
         }
-      };
+      },
+      Some(import_map_url) if maybe_embedded_import_map.is_some() => {
+        let (config_url, _) = maybe_embedded_import_map.unwrap();
+        return Err(generic_error(format!(
+            "Both --import-map (\"{}\") and the \"imports\" or \"scopes\" of the config file \"{}\" supply an import map. Remove one of them.",
+            import_map_url, config_url
+          )));
+      }
+      Some(import_map_url) => {
+        if !flags.unstable {
+          exit_unstable("--import-map")
+        }
+        let import_map_specifier = deno_core::resolve_url_or_path(
+          &import_map_url,
+        )
+        .context(format!("Bad URL (\"{}\") for import map.", import_map_url))?;
+        let file = file_fetcher
+          .fetch(&import_map_specifier, &Permissions::allow_all())
+          .await?;
+        let import_map =
+          ImportMap::from_json(import_map_specifier.as_str(), &file.source)?;
+        Some(import_map)
+      }
+    };
 
     let maybe_inspect_host = flags.inspect.or(flags.inspect_brk);
     let maybe_inspector_server = match maybe_inspect_host {
@@ -163,6 +230,7 @@ impl ProgramState {
       Arc::new(Mutex::new(FetchHandler::new(self, runtime_permissions)?));
     let mut builder =
       GraphBuilder::new(handler, maybe_import_map, self.lockfile.clone());
+    builder.set_compat(self.flags.compat);
     builder.add(&specifier, is_dynamic).await?;
     let mut graph = builder.get_graph();
     let debug = self.flags.log_level == Some(log::Level::Debug);
@@ -186,6 +254,7 @@ impl ProgramState {
         lib,
         maybe_config_path,
         reload: self.flags.reload,
+        check_remote: self.flags.type_check_mode == flags::TypeCheckMode::All,
       })?;
 
       debug!("{}", result_info.stats);
@@ -140,15 +140,13 @@ pub trait SpecifierHandler: Sync + Send {
     is_dynamic: bool,
   ) -> FetchFuture;
 
-  /// Get the optional build info from the cache for a given module specifier.
-  /// Because build infos are only associated with the "root" modules, they are
-  /// not expected to be cached for each module, but are "lazily" checked when
-  /// a root module is identified.  The `emit_type` also indicates what form
-  /// of the module the build info is valid for.
-  fn get_tsbuildinfo(
+  /// Get the check cache entry stored under `key`, which identifies a set of
+  /// root modules and compiler options.  An entry that can't be read is
+  /// treated as missing.
+  fn get_check_cache(
     &self,
-    specifier: &ModuleSpecifier,
-  ) -> Result<Option<String>, AnyError>;
+    key: &str,
+  ) -> Result<Option<CheckCacheEntry>, AnyError>;
 
   /// Set the emit for the module specifier.
   fn set_cache(
@@ -165,11 +163,11 @@ pub trait SpecifierHandler: Sync + Send {
     types: String,
   ) -> Result<(), AnyError>;
 
-  /// Set the build info for a module specifier, also providing the cache type.
-  fn set_tsbuildinfo(
+  /// Set the check cache entry for `key`, replacing any previous entry.
+  fn set_check_cache(
     &mut self,
-    specifier: &ModuleSpecifier,
-    tsbuildinfo: String,
+    key: &str,
+    entry: &CheckCacheEntry,
   ) -> Result<(), AnyError>;
 
   /// Set the graph dependencies for a given module specifier.
@@ -215,6 +213,22 @@ impl CompiledFileMetadata {
   }
 }
 
+/// The state of the last type check of a set of root modules with a set of
+/// compiler options, which allows a later check of the same roots to skip
+/// the modules that haven't changed.
+#[derive(Debug, Clone, Default, Deserialize, Serialize, Eq, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct CheckCacheEntry {
+  /// The version of Deno that performed the check.  Entries of other versions
+  /// are ignored.
+  pub deno_version: String,
+  /// The versions of the checked modules, keyed by specifier.  These are only
+  /// recorded when the check succeeded.
+  pub versions: HashMap<String, String>,
+  /// The incremental build info emitted by the TypeScript compiler.
+  pub maybe_tsbuildinfo: Option<String>,
+}
+
 /// An implementation of the `SpecifierHandler` trait that integrates with the
 /// existing `file_fetcher` interface, which will eventually be refactored to
 /// align it more to the `SpecifierHandler` trait.
@@ -246,6 +260,11 @@ impl FetchHandler {
   }
 }
 
+/// The filename of the check cache entry for `key`, relative to the gen cache.
+fn get_check_cache_filename(key: &str) -> PathBuf {
+  PathBuf::from("check").join(format!("{}.json", key))
+}
+
 impl SpecifierHandler for FetchHandler {
   fn fetch(
     &mut self,
@@ -365,38 +384,33 @@ impl SpecifierHandler for FetchHandler {
     .boxed()
   }
 
-  fn get_tsbuildinfo(
+  fn get_check_cache(
     &self,
-    specifier: &ModuleSpecifier,
-  ) -> Result<Option<String>, AnyError> {
-    let filename = self
-      .disk_cache
-      .get_cache_filename_with_extension(specifier, "buildinfo");
-    if let Some(filename) = filename {
-      if let Ok(tsbuildinfo) = self.disk_cache.get(&filename) {
-        Ok(Some(String::from_utf8(tsbuildinfo)?))
-      } else {
+    key: &str,
+  ) -> Result<Option<CheckCacheEntry>, AnyError> {
+    let filename = get_check_cache_filename(key);
+    let bytes = match self.disk_cache.get(&filename) {
+      Ok(bytes) => bytes,
+      Err(_) => return Ok(None),
+    };
+    match serde_json::from_slice(&bytes) {
+      Ok(entry) => Ok(Some(entry)),
+      Err(err) => {
+        debug!("get_check_cache - invalid entry {:?}: {}", filename, err);
         Ok(None)
       }
-    } else {
-      Ok(None)
     }
   }
 
-  fn set_tsbuildinfo(
+  fn set_check_cache(
     &mut self,
-    specifier: &ModuleSpecifier,
-    tsbuildinfo: String,
+    key: &str,
+    entry: &CheckCacheEntry,
   ) -> Result<(), AnyError> {
-    let filename = self
-      .disk_cache
-      .get_cache_filename_with_extension(specifier, "buildinfo")
-      .unwrap();
-    debug!("set_tsbuildinfo - filename {:?}", filename);
-    self
-      .disk_cache
-      .set(&filename, tsbuildinfo.as_bytes())
-      .map_err(|e| e.into())
+    let filename = get_check_cache_filename(key);
+    debug!("set_check_cache - filename {:?}", filename);
+    let data = serde_json::to_vec(entry)?;
+    self.disk_cache.set(&filename, &data).map_err(|e| e.into())
   }
 
   fn set_cache(
@@ -514,10 +528,10 @@ impl SpecifierHandler for MemoryHandler {
     Box::pin(future::ready(result))
   }
 
-  fn get_tsbuildinfo(
+  fn get_check_cache(
     &self,
-    _specifier: &ModuleSpecifier,
-  ) -> Result<Option<String>, AnyError> {
+    _key: &str,
+  ) -> Result<Option<CheckCacheEntry>, AnyError> {
     Ok(None)
   }
 
@@ -537,10 +551,10 @@ impl SpecifierHandler for MemoryHandler {
     Ok(())
   }
 
-  fn set_tsbuildinfo(
+  fn set_check_cache(
     &mut self,
-    _specifier: &ModuleSpecifier,
-    _tsbuildinfo: String,
+    _key: &str,
+    _entry: &CheckCacheEntry,
   ) -> Result<(), AnyError> {
     Ok(())
   }
@@ -652,6 +666,30 @@ pub mod tests {
     );
   }
 
+  #[test]
+  fn test_fetch_handler_check_cache() {
+    let (_temp_dir, mut fetch_handler) = setup();
+    assert_eq!(fetch_handler.get_check_cache("abc").unwrap(), None);
+
+    let entry = CheckCacheEntry {
+      deno_version: "1.2.3".to_string(),
+      versions: map!("file:///a.ts".to_string() => "1".to_string()),
+      maybe_tsbuildinfo: Some("{}".to_string()),
+    };
+    fetch_handler
+      .set_check_cache("abc", &entry)
+      .expect("could not set check cache");
+    assert_eq!(fetch_handler.get_check_cache("abc").unwrap(), Some(entry));
+
+    // A corrupted entry degrades to a missing one.
+    let filename = get_check_cache_filename("abc");
+    fetch_handler
+      .disk_cache
+      .set(&filename, b"{ not json")
+      .unwrap();
+    assert_eq!(fetch_handler.get_check_cache("abc").unwrap(), None);
+  }
+
   #[tokio::test]
   async fn test_fetch_handler_is_remote() {
     let _http_server_guard = test_util::http_server();
@@ -15,6 +15,7 @@ use deno_core::v8_set_flags;
 use deno_core::ModuleLoader;
 use deno_core::ModuleSpecifier;
 use deno_core::OpState;
+use deno_runtime::embedded_files::EmbeddedFiles;
 use deno_runtime::permissions::Permissions;
 use deno_runtime::permissions::PermissionsOptions;
 use deno_runtime::worker::MainWorker;
@@ -42,6 +43,16 @@ pub struct Metadata {
   pub v8_flags: Vec<String>,
   pub log_level: Option<Level>,
   pub ca_data: Option<Vec<u8>>,
+  pub assets: Vec<EmbeddedAsset>,
+}
+
+/// A file embedded with `deno compile --include`, stored at `offset` in the
+/// executable.
+#[derive(Deserialize, Serialize)]
+pub struct EmbeddedAsset {
+  pub path: String,
+  pub offset: u64,
+  pub len: u64,
 }
 
 pub const MAGIC_TRAILER: &[u8; 8] = b"d3n0l4nd";
@@ -53,8 +64,9 @@ pub const MAGIC_TRAILER: &[u8; 8] = b"d3n0l4nd";
 /// - a u64 pointer to the JS bundle embedded in the binary
 /// - a u64 pointer to JSON metadata (serialized flags) embedded in the binary
 /// These are dereferenced, and the bundle is executed under the configuration
-/// specified by the metadata. If no magic trailer is present, this function
-/// exits with `Ok(None)`.
+/// specified by the metadata. The files embedded with `--include` precede the
+/// bundle; the metadata lists where. If no magic trailer is present, this
+/// function exits with `Ok(None)`.
 pub fn extract_standalone(
   args: Vec<String>,
 ) -> Result<Option<(Metadata, String)>, AnyError> {
@@ -64,15 +76,10 @@ pub fn extract_standalone(
   let trailer_pos = current_exe.seek(SeekFrom::End(-24))?;
   let mut trailer = [0; 24];
   current_exe.read_exact(&mut trailer)?;
-  let (magic_trailer, rest) = trailer.split_at(8);
-  if magic_trailer != MAGIC_TRAILER {
-    return Ok(None);
-  }
-
-  let (bundle_pos, rest) = rest.split_at(8);
-  let metadata_pos = rest;
-  let bundle_pos = u64_from_bytes(bundle_pos)?;
-  let metadata_pos = u64_from_bytes(metadata_pos)?;
+  let (bundle_pos, metadata_pos) = match parse_trailer(&trailer) {
+    Some(positions) => positions,
+    None => return Ok(None),
+  };
   let bundle_len = metadata_pos - bundle_pos;
   let metadata_len = trailer_pos - metadata_pos;
   current_exe.seek(SeekFrom::Start(bundle_pos))?;
@@ -88,11 +95,21 @@ pub fn extract_standalone(
   Ok(Some((metadata, bundle)))
 }
 
-fn u64_from_bytes(arr: &[u8]) -> Result<u64, AnyError> {
-  let fixed_arr: &[u8; 8] = arr
-    .try_into()
-    .context("Failed to convert the buffer into a fixed-size array")?;
-  Ok(u64::from_be_bytes(*fixed_arr))
+/// Parses the last 24 bytes of a `deno compile` output into the positions of
+/// the bundle and of the metadata. Returns `None` if they don't start with
+/// the magic trailer.
+pub fn parse_trailer(trailer: &[u8; 24]) -> Option<(u64, u64)> {
+  let (magic_trailer, rest) = trailer.split_at(8);
+  if magic_trailer != MAGIC_TRAILER {
+    return None;
+  }
+  let (bundle_pos, metadata_pos) = rest.split_at(8);
+  Some((u64_from_bytes(bundle_pos), u64_from_bytes(metadata_pos)))
+}
+
+fn u64_from_bytes(arr: &[u8]) -> u64 {
+  let fixed_arr: [u8; 8] = arr.try_into().unwrap();
+  u64::from_be_bytes(fixed_arr)
 }
 
 fn read_string_slice(
@@ -190,8 +207,17 @@ pub async fn run(
     get_error_class_fn: Some(&get_error_class_name),
     location: metadata.location,
   };
+  let mut embedded_files = EmbeddedFiles::new(current_exe()?);
+  for asset in metadata.assets {
+    embedded_files.insert(asset.path, asset.offset, asset.len);
+  }
   let mut worker =
     MainWorker::from_options(main_module.clone(), permissions, &options);
+  worker
+    .js_runtime
+    .op_state()
+    .borrow_mut()
+    .put(embedded_files);
   worker.bootstrap(&options);
   worker.execute_module(&main_module).await?;
   worker.execute("window.dispatchEvent(new Event('load'))")?;
@@ -0,0 +1,13 @@
+Deno.bench("throws", () => {
+  throw new Error("bench failed");
+});
+
+let calls = 0;
+Deno.bench("rejects", async () => {
+  if (++calls > 10) {
+    throw new Error("rejected after 10 calls");
+  }
+  await Promise.resolve();
+});
+
+Deno.bench("passes", () => {});
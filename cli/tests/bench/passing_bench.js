@@ -0,0 +1,36 @@
+const large = JSON.stringify(Array.from({ length: 100 }, (_, i) => ({ i })));
+
+Deno.bench("sum", { baseline: true }, () => {
+  let sum = 0;
+  for (let i = 0; i < 100; i++) {
+    sum += i;
+  }
+  return sum;
+});
+
+Deno.bench("async", async () => {
+  await Promise.resolve();
+});
+
+Deno.bench({
+  name: "parse small",
+  group: "parse",
+  baseline: true,
+  fn() {
+    JSON.parse('{"i":0}');
+  },
+});
+
+Deno.bench({
+  name: "parse large",
+  group: "parse",
+  fn() {
+    JSON.parse(large);
+  },
+});
+
+Deno.bench({
+  name: "ignored",
+  ignore: true,
+  fn() {},
+});
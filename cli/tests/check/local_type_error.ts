@@ -0,0 +1,2 @@
+const a: string = 1;
+console.log(a);
@@ -0,0 +1,3 @@
+import { value } from "http://localhost:4545/cli/tests/check/remote_dep_type_error.ts";
+
+console.log(value);
@@ -0,0 +1,4 @@
+import "moment";
+import "./scope/scoped.ts";
+
+Deno.test("embedded import map", () => {});
@@ -226,7 +226,10 @@ mod integration {
     assert!(stdout_str.is_empty());
     let stderr_str = std::str::from_utf8(&output.stderr).unwrap().trim();
     eprintln!("{}", stderr_str);
-    assert!(stderr_str.contains("Import 'http://127.0.0.1:4551/cli/tests/001_hello.js' failed: 404 Not Found"));
+    assert!(stderr_str.contains("Import 'http://127.0.0.1:4551/cli/tests/001_hello.js' failed: 401 Unauthorized"));
+    assert!(stderr_str.contains(
+      "\"127.0.0.1:4551\" requires authentication. Set DENO_AUTH_TOKENS"
+    ));
 
     let output = util::deno_cmd()
       .current_dir(util::root_path())
@@ -244,6 +247,75 @@ mod integration {
     assert_eq!(util::strip_ansi_codes(stdout_str), "Hello World");
   }
 
+  #[test]
+  fn auth_tokens_basic_netrc_and_redirects() {
+    let _g = util::http_server();
+    let netrc_dir = TempDir::new().expect("tempdir fail");
+    let netrc_path = netrc_dir.path().join(".netrc");
+    std::fs::write(
+      &netrc_path,
+      "machine 127.0.0.1 login testuser password testpassword\n",
+    )
+    .unwrap();
+    let run = |url: &str, envs: &[(&str, &std::ffi::OsStr)]| {
+      let output = util::deno_cmd()
+        .current_dir(util::root_path())
+        .arg("run")
+        .arg("--reload")
+        .arg(url)
+        .env_remove("DENO_AUTH_TOKENS")
+        .env("NETRC", netrc_dir.path().join("missing"))
+        .envs(envs.iter().cloned())
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped())
+        .spawn()
+        .unwrap()
+        .wait_with_output()
+        .unwrap();
+      let stdout = util::strip_ansi_codes(
+        std::str::from_utf8(&output.stdout).unwrap().trim(),
+      )
+      .to_string();
+      let stderr = std::str::from_utf8(&output.stderr).unwrap().to_string();
+      (output.status.success(), stdout, stderr)
+    };
+    let hello = "http://127.0.0.1:4551/cli/tests/001_hello.js";
+    let no_authorization = "http://127.0.0.1:4551/no_authorization.js";
+
+    // Basic auth, from DENO_AUTH_TOKENS and from .netrc.
+    let tokens = std::ffi::OsStr::new("testuser:testpassword@127.0.0.1:4551");
+    let (success, stdout, _) = run(hello, &[("DENO_AUTH_TOKENS", tokens)]);
+    assert!(success);
+    assert_eq!(stdout, "Hello World");
+    let (success, stdout, _) = run(hello, &[("NETRC", netrc_path.as_os_str())]);
+    assert!(success);
+    assert_eq!(stdout, "Hello World");
+
+    // Rejected credentials are reported without echoing them.
+    let tokens = std::ffi::OsStr::new("wronguser:secret@127.0.0.1:4551");
+    let (success, _, stderr) = run(hello, &[("DENO_AUTH_TOKENS", tokens)]);
+    assert!(!success);
+    assert!(
+      stderr.contains("The credentials for \"127.0.0.1:4551\" were rejected.")
+    );
+    assert!(!stderr.contains("secret"));
+
+    // The redirect to localhost:4545 doesn't send the header along.
+    let tokens = std::ffi::OsStr::new("abcdef123456789@127.0.0.1:4551");
+    let (success, stdout, stderr) =
+      run(no_authorization, &[("DENO_AUTH_TOKENS", tokens)]);
+    assert!(success, "{}", stderr);
+    assert_eq!(stdout, "no authorization");
+
+    // Tokens for a port aren't sent to other ports of the host.
+    let (success, stdout, stderr) = run(
+      "http://127.0.0.1:4545/no_authorization.js",
+      &[("DENO_AUTH_TOKENS", tokens)],
+    );
+    assert!(success, "{}", stderr);
+    assert_eq!(stdout, "no authorization");
+  }
+
   #[cfg(unix)]
   #[test]
   pub fn test_raw_tty() {
@@ -574,6 +646,74 @@ mod integration {
     assert_eq!(expected_json, actual_json);
   }
 
+  #[test]
+  fn fmt_with_config() {
+    let t = TempDir::new().expect("tempdir fail");
+    std::fs::write(
+      t.path().join("deno.jsonc"),
+      r#"{
+  // Formatting options.
+  "fmt": {
+    "singleQuote": true,
+    "indentWidth": 4,
+    "exclude": ["ignored/"]
+  }
+}
+"#,
+    )
+    .unwrap();
+    std::fs::create_dir(t.path().join("ignored")).unwrap();
+    std::fs::write(t.path().join("ignored/a.ts"), "const a = \"a\"\n").unwrap();
+    std::fs::write(t.path().join("b.ts"), "if (b) {\nb = \"b\"\n}\n").unwrap();
+
+    let output = util::deno_cmd()
+      .current_dir(t.path())
+      .arg("fmt")
+      .arg("--check")
+      .env("NO_COLOR", "1")
+      .output()
+      .expect("Failed to spawn script");
+    assert!(!output.status.success());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("Not formatted files:"));
+    assert!(stderr.contains("b.ts"));
+    assert!(!stderr.contains("a.ts"));
+
+    let status = util::deno_cmd()
+      .current_dir(t.path())
+      .arg("fmt")
+      .spawn()
+      .expect("Failed to spawn script")
+      .wait()
+      .expect("Failed to wait for child process");
+    assert!(status.success());
+    assert_eq!(
+      std::fs::read_to_string(t.path().join("b.ts")).unwrap(),
+      "if (b) {\n    b = 'b';\n}\n"
+    );
+    assert_eq!(
+      std::fs::read_to_string(t.path().join("ignored/a.ts")).unwrap(),
+      "const a = \"a\"\n"
+    );
+
+    // Options passed on the command line take precedence.
+    let status = util::deno_cmd()
+      .current_dir(t.path())
+      .arg("fmt")
+      .arg("--single-quote=false")
+      .arg("--indent-width=2")
+      .arg("b.ts")
+      .spawn()
+      .expect("Failed to spawn script")
+      .wait()
+      .expect("Failed to wait for child process");
+    assert!(status.success());
+    assert_eq!(
+      std::fs::read_to_string(t.path().join("b.ts")).unwrap(),
+      "if (b) {\n  b = \"b\";\n}\n"
+    );
+  }
+
   mod file_watcher {
     use super::*;
 
@@ -987,6 +1127,170 @@ mod integration {
       drop(import_map_path);
       temp_directory.close().unwrap();
     }
+
+    #[test]
+    fn run_watch_extra_paths_and_exclude() {
+      let t = TempDir::new().expect("tempdir fail");
+      let file_to_watch = t.path().join("file_to_watch.js");
+      std::fs::write(&file_to_watch, "console.log('run 1');")
+        .expect("error writing file");
+      let data_dir = t.path().join("data");
+      std::fs::create_dir_all(data_dir.join("ignored")).unwrap();
+      let data_file = data_dir.join("data.json");
+      std::fs::write(&data_file, "{}").expect("error writing file");
+      let ignored_file = data_dir.join("ignored").join("data.json");
+      std::fs::write(&ignored_file, "{}").expect("error writing file");
+
+      let mut child = util::deno_cmd()
+        .current_dir(t.path())
+        .arg("run")
+        .arg("--unstable")
+        .arg("--watch=data")
+        .arg("--watch-exclude=data/ignored")
+        .arg(&file_to_watch)
+        .env("NO_COLOR", "1")
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped())
+        .spawn()
+        .expect("failed to spawn script");
+
+      let stdout = child.stdout.as_mut().unwrap();
+      let mut stdout_lines =
+        std::io::BufReader::new(stdout).lines().map(|r| r.unwrap());
+      let stderr = child.stderr.as_mut().unwrap();
+      let mut stderr_lines =
+        std::io::BufReader::new(stderr).lines().map(|r| r.unwrap());
+
+      assert!(stdout_lines.next().unwrap().contains("run 1"));
+      wait_for_process_finished("Process", &mut stderr_lines);
+      std::thread::sleep(std::time::Duration::from_secs(1));
+
+      // A file in the extra directory restarts the process.
+      std::fs::write(&data_file, "{ \"a\": 1 }").expect("error writing file");
+      std::thread::sleep(std::time::Duration::from_secs(1));
+      assert!(stderr_lines.next().unwrap().contains("Restarting"));
+      assert!(stdout_lines.next().unwrap().contains("run 1"));
+      wait_for_process_finished("Process", &mut stderr_lines);
+
+      // An excluded file doesn't, so the next run is the one of the change
+      // to the module.
+      std::fs::write(&ignored_file, "{ \"a\": 1 }")
+        .expect("error writing file");
+      std::thread::sleep(std::time::Duration::from_secs(1));
+      std::fs::write(&file_to_watch, "console.log('run 2');")
+        .expect("error writing file");
+      std::thread::sleep(std::time::Duration::from_secs(1));
+      assert!(stderr_lines.next().unwrap().contains("Restarting"));
+      assert!(stdout_lines.next().unwrap().contains("run 2"));
+      wait_for_process_finished("Process", &mut stderr_lines);
+
+      child.kill().unwrap();
+      drop(t);
+    }
+
+    #[test]
+    fn run_watch_debounces_rapid_writes() {
+      let t = TempDir::new().expect("tempdir fail");
+      let file_to_watch = t.path().join("file_to_watch.js");
+      std::fs::write(&file_to_watch, "console.log('initial');")
+        .expect("error writing file");
+
+      let mut child = util::deno_cmd()
+        .current_dir(util::root_path())
+        .arg("run")
+        .arg("--watch")
+        .arg("--unstable")
+        .arg(&file_to_watch)
+        .env("NO_COLOR", "1")
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped())
+        .spawn()
+        .expect("failed to spawn script");
+
+      let stdout = child.stdout.as_mut().unwrap();
+      let mut stdout_lines =
+        std::io::BufReader::new(stdout).lines().map(|r| r.unwrap());
+      let stderr = child.stderr.as_mut().unwrap();
+      let mut stderr_lines =
+        std::io::BufReader::new(stderr).lines().map(|r| r.unwrap());
+
+      assert!(stdout_lines.next().unwrap().contains("initial"));
+      wait_for_process_finished("Process", &mut stderr_lines);
+      std::thread::sleep(std::time::Duration::from_secs(1));
+
+      for i in 0..5 {
+        std::fs::write(&file_to_watch, format!("console.log('write {}');", i))
+          .expect("error writing file");
+        std::thread::sleep(std::time::Duration::from_millis(20));
+      }
+      std::thread::sleep(std::time::Duration::from_secs(1));
+      assert!(stderr_lines.next().unwrap().contains("Restarting"));
+      assert!(stdout_lines.next().unwrap().contains("write 4"));
+      wait_for_process_finished("Process", &mut stderr_lines);
+
+      // There was a single restart, so the next output is the one of the
+      // next change.
+      std::fs::write(&file_to_watch, "console.log('final');")
+        .expect("error writing file");
+      std::thread::sleep(std::time::Duration::from_secs(1));
+      assert!(stderr_lines.next().unwrap().contains("Restarting"));
+      assert!(stdout_lines.next().unwrap().contains("final"));
+      wait_for_process_finished("Process", &mut stderr_lines);
+
+      child.kill().unwrap();
+      drop(t);
+    }
+
+    #[test]
+    fn test_watch() {
+      let t = TempDir::new().expect("tempdir fail");
+      let test_file = t.path().join("foo_test.js");
+      std::fs::write(&test_file, "Deno.test('foo', () => {});")
+        .expect("error writing file");
+
+      let mut child = util::deno_cmd()
+        .current_dir(t.path())
+        .arg("test")
+        .arg("--unstable")
+        .arg("--watch")
+        .env("NO_COLOR", "1")
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped())
+        .spawn()
+        .expect("failed to spawn script");
+
+      let stdout = child.stdout.as_mut().unwrap();
+      let mut stdout_lines =
+        std::io::BufReader::new(stdout).lines().map(|r| r.unwrap());
+      let stderr = child.stderr.as_mut().unwrap();
+      let mut stderr_lines =
+        std::io::BufReader::new(stderr).lines().map(|r| r.unwrap());
+
+      let mut wait_for_result = |expected: &str| loop {
+        let line = stdout_lines.next().unwrap();
+        if line.contains("test result") {
+          assert!(line.contains(expected), "{}", line);
+          break;
+        }
+      };
+
+      wait_for_result("1 passed");
+      wait_for_process_finished("Test", &mut stderr_lines);
+
+      // A failing test doesn't stop the watcher.
+      std::fs::write(
+        &test_file,
+        "Deno.test('foo', () => {}); Deno.test('bar', () => { throw 1; });",
+      )
+      .expect("error writing file");
+      std::thread::sleep(std::time::Duration::from_secs(1));
+      wait_for_result("1 failed");
+      wait_for_process_finished("Test", &mut stderr_lines);
+
+      assert!(child.try_wait().unwrap().is_none());
+      child.kill().unwrap();
+      drop(t);
+    }
   }
 
   #[test]
@@ -1224,6 +1528,104 @@ mod integration {
       .ends_with("hello, foo"));
   }
 
+  #[test]
+  fn node_specifiers() {
+    let _g = util::http_server();
+    let run = |script: &str| {
+      util::deno_cmd()
+        .current_dir(util::root_path())
+        .arg("run")
+        .arg("--reload")
+        .arg(script)
+        .env(
+          "DENO_NODE_COMPAT_URL",
+          "http://localhost:4545/cli/tests/node_compat/",
+        )
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped())
+        .spawn()
+        .unwrap()
+        .wait_with_output()
+        .unwrap()
+    };
+
+    let output = run("cli/tests/node_specifier.ts");
+    assert!(output.status.success());
+    assert_eq!(std::str::from_utf8(&output.stdout).unwrap().trim(), "a/b");
+
+    let output = run("cli/tests/node_specifier_unknown.ts");
+    assert!(!output.status.success());
+    let stderr = std::str::from_utf8(&output.stderr).unwrap();
+    assert!(stderr.contains("Unknown Node built-in module \"node:nope\""));
+    assert!(stderr.contains("The known modules are: assert, buffer"));
+  }
+
+  #[test]
+  fn installer_test_import_map_list_uninstall() {
+    let temp_dir = TempDir::new().expect("tempdir fail");
+    let bin_dir = temp_dir.path().join("bin");
+    let fixtures = util::tests_path().join("install_import_map");
+    let status = util::deno_cmd()
+      .current_dir(&fixtures)
+      .arg("install")
+      .arg("--allow-env")
+      .arg("--import-map")
+      .arg("import_map.json")
+      .arg("--env-file")
+      .arg(".env")
+      .arg("--name")
+      .arg("greet")
+      .arg("--root")
+      .arg(temp_dir.path())
+      .arg("main.ts")
+      .arg("from")
+      .spawn()
+      .unwrap()
+      .wait()
+      .unwrap();
+    assert!(status.success());
+    let mut file_path = bin_dir.join("greet");
+    if cfg!(windows) {
+      file_path = file_path.with_extension("cmd");
+    }
+    // The import map and the env file are used from another directory.
+    let output = Command::new(&file_path)
+      .current_dir(temp_dir.path())
+      .arg("install")
+      .env("PATH", util::target_dir())
+      .output()
+      .expect("failed to spawn script");
+    assert!(output.status.success());
+    let stdout_str = std::str::from_utf8(&output.stdout).unwrap().trim();
+    assert_eq!(stdout_str, "hello, deno, from install");
+
+    let output = util::deno_cmd()
+      .arg("install")
+      .arg("--list")
+      .arg("--root")
+      .arg(temp_dir.path())
+      .output()
+      .unwrap();
+    assert!(output.status.success());
+    let stdout_str = std::str::from_utf8(&output.stdout).unwrap();
+    let main_url = url::Url::from_file_path(fixtures.join("main.ts")).unwrap();
+    assert!(stdout_str.starts_with(&format!("greet {}\n", main_url)));
+    assert!(stdout_str.contains("  args: from\n"));
+
+    let status = util::deno_cmd()
+      .arg("uninstall")
+      .arg("--root")
+      .arg(temp_dir.path())
+      .arg("greet")
+      .spawn()
+      .unwrap()
+      .wait()
+      .unwrap();
+    assert!(status.success());
+    let remaining: Vec<_> = std::fs::read_dir(&bin_dir).unwrap().collect();
+    assert!(remaining.is_empty());
+  }
+
   #[test]
   fn ts_dependency_recompilation() {
     let t = TempDir::new().expect("tempdir fail");
@@ -1254,6 +1656,7 @@ mod integration {
       .current_dir(util::root_path())
       .env("NO_COLOR", "1")
       .arg("run")
+      .arg("--check")
       .arg(&ats)
       .output()
       .expect("failed to spawn script");
@@ -1276,6 +1679,7 @@ mod integration {
       .current_dir(util::root_path())
       .env("NO_COLOR", "1")
       .arg("run")
+      .arg("--check")
       .arg(&ats)
       .output()
       .expect("failed to spawn script");
@@ -1700,24 +2104,129 @@ mod integration {
   }
 
   #[test]
-  fn info_with_compiled_source() {
+  fn info_cache_by_origin_and_prune() {
     let _g = util::http_server();
-    let module_path = "http://127.0.0.1:4545/cli/tests/048_media_types_jsx.ts";
-    let t = TempDir::new().expect("tempdir fail");
-
-    let mut deno = util::deno_cmd()
-      .env("DENO_DIR", t.path())
+    let deno_dir = TempDir::new().expect("tempdir fail");
+    let output = util::deno_cmd()
+      .env("DENO_DIR", deno_dir.path())
       .current_dir(util::root_path())
       .arg("cache")
-      .arg(&module_path)
-      .spawn()
+      .arg("http://localhost:4545/cli/tests/subdir/print_hello.ts")
+      .arg("http://127.0.0.1:4545/cli/tests/subdir/mod2.ts")
+      .output()
       .expect("failed to spawn script");
-    let status = deno.wait().expect("failed to wait for the child process");
-    assert!(status.success());
+    assert!(output.status.success());
 
-    let output = util::deno_cmd()
-      .env("DENO_DIR", t.path())
-      .env("NO_COLOR", "1")
+    let info_cache = || {
+      let output = util::deno_cmd()
+        .env("DENO_DIR", deno_dir.path())
+        .current_dir(util::root_path())
+        .arg("info")
+        .arg("--unstable")
+        .arg("--json")
+        .arg("--cache")
+        .output()
+        .expect("failed to spawn script");
+      assert!(output.status.success());
+      let info: serde_json::Value =
+        serde_json::from_slice(&output.stdout).unwrap();
+      info["origins"].clone()
+    };
+    let fixture_size =
+      |path: &str| fs::metadata(util::root_path().join(path)).unwrap().len();
+    let print_hello_size = fixture_size("cli/tests/subdir/print_hello.ts");
+    let mod2_size = fixture_size("cli/tests/subdir/mod2.ts");
+    assert_eq!(
+      info_cache(),
+      serde_json::json!([
+        {
+          "origin": "http://127.0.0.1:4545",
+          "modules": 2,
+          "size": print_hello_size + mod2_size,
+        },
+        {
+          "origin": "http://localhost:4545",
+          "modules": 1,
+          "size": print_hello_size,
+        },
+      ])
+    );
+
+    let output = util::deno_cmd()
+      .env("DENO_DIR", deno_dir.path())
+      .env("NO_COLOR", "1")
+      .current_dir(util::root_path())
+      .arg("cache")
+      .arg("--prune=0s")
+      .output()
+      .expect("failed to spawn script");
+    assert!(output.status.success());
+    let stdout = std::str::from_utf8(&output.stdout).unwrap();
+    assert!(stdout.starts_with("Removed 3 cached modules"));
+    assert_eq!(info_cache(), serde_json::json!([]));
+  }
+
+  #[tokio::test]
+  async fn cached_only_reload_prefix() {
+    let _g = util::http_server();
+    let deno_dir = TempDir::new().expect("tempdir fail");
+    let request_count = |host: &'static str| async move {
+      let url = format!(
+        "http://localhost:4545/request_count/{}/cli/tests/reload_prefix/a.ts",
+        host
+      );
+      let resp = reqwest::get(&url).await.unwrap();
+      resp.text().await.unwrap().parse::<usize>().unwrap()
+    };
+    let cache = |args: &[&str]| {
+      util::deno_cmd()
+        .env("DENO_DIR", deno_dir.path())
+        .current_dir(util::root_path())
+        .arg("cache")
+        .args(args)
+        .arg("cli/tests/reload_prefix/mod.ts")
+        .output()
+        .expect("failed to spawn script")
+    };
+    let localhost = request_count("localhost:4545").await;
+    let loopback = request_count("127.0.0.1:4545").await;
+
+    assert!(cache(&[]).status.success());
+    assert_eq!(request_count("localhost:4545").await, localhost + 1);
+    assert_eq!(request_count("127.0.0.1:4545").await, loopback + 1);
+
+    // Only the modules of the origin given to `--reload` are downloaded
+    // again, everything else comes from the cache.
+    let output = cache(&["--cached-only", "--reload=http://127.0.0.1:4545"]);
+    assert!(output.status.success());
+    assert_eq!(request_count("localhost:4545").await, localhost + 1);
+    assert_eq!(request_count("127.0.0.1:4545").await, loopback + 2);
+
+    // A prefix has to match whole path segments.
+    let output = cache(&["--reload=http://127.0.0.1:4545/cli/tests/reload"]);
+    assert!(output.status.success());
+    assert_eq!(request_count("127.0.0.1:4545").await, loopback + 2);
+  }
+
+  #[test]
+  fn info_with_compiled_source() {
+    let _g = util::http_server();
+    let module_path = "http://127.0.0.1:4545/cli/tests/048_media_types_jsx.ts";
+    let t = TempDir::new().expect("tempdir fail");
+
+    let mut deno = util::deno_cmd()
+      .env("DENO_DIR", t.path())
+      .current_dir(util::root_path())
+      .arg("cache")
+      .arg(&module_path)
+      .spawn()
+      .expect("failed to spawn script");
+    let status = deno.wait().expect("failed to wait for the child process");
+    assert!(status.success());
+
+    let output = util::deno_cmd()
+      .env("DENO_DIR", t.path())
+      .env("NO_COLOR", "1")
       .current_dir(util::root_path())
       .arg("info")
       .arg(&module_path)
@@ -1858,6 +2367,118 @@ mod integration {
       }
     }
 
+    #[cfg(unix)]
+    #[test]
+    fn pty_complete_deno_members() {
+      use std::io::Read;
+      use util::pty::fork::*;
+      let deno_exe = util::deno_exe_path();
+      let fork = Fork::from_ptmx().unwrap();
+      if let Ok(mut master) = fork.is_parent() {
+        master.write_all(b"Deno.exi\t\n").unwrap();
+        master.write_all(b"close();\n").unwrap();
+
+        let mut output = String::new();
+        master.read_to_string(&mut output).unwrap();
+
+        assert!(output.contains("[Function: exit]"));
+
+        fork.wait().unwrap();
+      } else {
+        std::env::set_var("NO_COLOR", "1");
+        let err = exec::Command::new(deno_exe).arg("repl").exec();
+        println!("err {}", err);
+        unreachable!()
+      }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn pty_complete_object_properties() {
+      use std::io::Read;
+      use util::pty::fork::*;
+      let deno_exe = util::deno_exe_path();
+      let fork = Fork::from_ptmx().unwrap();
+      if let Ok(mut master) = fork.is_parent() {
+        master
+          .write_all(b"const obj = { fooBar: 40 + 2, _fooBaz: 0 };\n")
+          .unwrap();
+        master.write_all(b"obj.fooB\t\n").unwrap();
+        master.write_all(b"obj.hasOwnPr\t(\"fooBar\")\n").unwrap();
+        master.write_all(b"close();\n").unwrap();
+
+        let mut output = String::new();
+        master.read_to_string(&mut output).unwrap();
+
+        assert!(output.contains("42"));
+        assert!(output.contains("true"));
+
+        fork.wait().unwrap();
+      } else {
+        std::env::set_var("NO_COLOR", "1");
+        let err = exec::Command::new(deno_exe).arg("repl").exec();
+        println!("err {}", err);
+        unreachable!()
+      }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn pty_complete_side_effect_getter() {
+      use std::io::Read;
+      use util::pty::fork::*;
+      let deno_exe = util::deno_exe_path();
+      let fork = Fork::from_ptmx().unwrap();
+      if let Ok(mut master) = fork.is_parent() {
+        master
+          .write_all(b"globalThis.count = 0; const obj = { get inc() { count++; return { value: 1 }; } };\n")
+          .unwrap();
+        master.write_all(b"obj.inc.val\t\n").unwrap();
+        master.write_all(b"\"count:\" + count\n").unwrap();
+        master.write_all(b"close();\n").unwrap();
+
+        let mut output = String::new();
+        master.read_to_string(&mut output).unwrap();
+
+        // Only the evaluation of the completed line invokes the getter.
+        assert!(output.contains("\"count:1\""));
+
+        fork.wait().unwrap();
+      } else {
+        std::env::set_var("NO_COLOR", "1");
+        let err = exec::Command::new(deno_exe).arg("repl").exec();
+        println!("err {}", err);
+        unreachable!()
+      }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn pty_complete_import_path() {
+      use std::io::Read;
+      use util::pty::fork::*;
+      let deno_exe = util::deno_exe_path();
+      let fork = Fork::from_ptmx().unwrap();
+      if let Ok(mut master) = fork.is_parent() {
+        master
+          .write_all(b"await import(\"./tests/subdir/print_hel\t\")\n")
+          .unwrap();
+        master.write_all(b"close();\n").unwrap();
+
+        let mut output = String::new();
+        master.read_to_string(&mut output).unwrap();
+
+        assert!(output.contains("printHello: [Function: printHello]"));
+
+        fork.wait().unwrap();
+      } else {
+        std::env::set_var("NO_COLOR", "1");
+        let err = exec::Command::new(deno_exe).arg("repl").exec();
+        println!("err {}", err);
+        unreachable!()
+      }
+    }
+
     #[test]
     fn console_log() {
       let (out, err) = util::run_and_collect_output(
@@ -2204,6 +2825,93 @@ mod integration {
       ));
       assert!(err.is_empty());
     }
+
+    #[test]
+    fn history_across_sessions() {
+      let temp_dir = TempDir::new().expect("tempdir fail");
+      let history_file = temp_dir.path().join("nested").join("history");
+      let envs = vec![
+        ("NO_COLOR".to_owned(), "1".to_owned()),
+        (
+          "DENO_REPL_HISTORY".to_owned(),
+          history_file.to_string_lossy().to_string(),
+        ),
+      ];
+      let (_, err) = util::run_and_collect_output(
+        true,
+        "repl",
+        Some(vec!["1 + 1", "1 + 1", "'second'"]),
+        Some(envs.clone()),
+        false,
+      );
+      assert!(err.is_empty());
+      let history = std::fs::read_to_string(&history_file).unwrap();
+      assert_eq!(history.matches("1 + 1").count(), 1);
+      assert!(history.contains("'second'"));
+
+      let (_, err) = util::run_and_collect_output(
+        true,
+        "repl",
+        Some(vec!["'third'"]),
+        Some(envs),
+        false,
+      );
+      assert!(err.is_empty());
+      let history = std::fs::read_to_string(&history_file).unwrap();
+      assert!(history.contains("1 + 1"));
+      assert!(history.contains("'third'"));
+    }
+
+    #[test]
+    fn history_disabled() {
+      let deno_dir = TempDir::new().expect("tempdir fail");
+      let (out, err) = util::run_and_collect_output(
+        true,
+        "repl",
+        Some(vec!["1"]),
+        Some(vec![
+          ("NO_COLOR".to_owned(), "1".to_owned()),
+          ("DENO_REPL_HISTORY".to_owned(), "".to_owned()),
+          (
+            "DENO_DIR".to_owned(),
+            deno_dir.path().to_string_lossy().to_string(),
+          ),
+        ]),
+        false,
+      );
+      assert!(out.ends_with("1\n"));
+      assert!(err.is_empty());
+      assert!(!deno_dir.path().join("history").exists());
+    }
+
+    #[test]
+    fn eval_file_and_eval() {
+      let (out, err) = util::run_and_collect_output(
+        true,
+        "repl --eval-file=./repl_eval_file.js --eval=evalFileValue+=1",
+        Some(vec!["evalFileValue"]),
+        Some(vec![("NO_COLOR".to_owned(), "1".to_owned())]),
+        false,
+      );
+      assert!(out.ends_with("43\n"));
+      assert!(!out.contains("error in"));
+      assert!(err.is_empty());
+    }
+
+    #[test]
+    fn eval_file_error() {
+      let (out, err) = util::run_and_collect_output(
+        true,
+        "repl --eval-file=./does_not_exist.js --eval=throw_new_Error",
+        Some(vec!["1 + 1"]),
+        Some(vec![("NO_COLOR".to_owned(), "1".to_owned())]),
+        false,
+      );
+      assert!(out.contains("error in --eval-file file ./does_not_exist.js"));
+      assert!(out.contains("error in --eval flag: Uncaught ReferenceError"));
+      assert!(out.ends_with("2\n"));
+      assert!(err.is_empty());
+    }
   }
 
   #[test]
@@ -2372,12 +3080,464 @@ mod integration {
     output: "deno_test.out",
   });
 
+  itest!(deno_test_steps_passing {
+    args: "test test_steps_passing.ts",
+    output: "test_steps_passing.out",
+  });
+
+  itest!(deno_test_steps_failing {
+    args: "test test_steps_failing.ts",
+    exit_code: 1,
+    output: "test_steps_failing.out",
+  });
+
+  itest!(deno_test_steps_unawaited {
+    args: "test test_steps_unawaited.ts",
+    exit_code: 1,
+    output: "test_steps_unawaited.out",
+  });
+
+  itest!(deno_test_shuffle {
+    args: "test --unstable --shuffle=42 test_shuffle",
+    output: "test_shuffle/seed_42.out",
+  });
+
+  itest!(deno_test_shuffle_other_seed {
+    args: "test --unstable --shuffle=43 test_shuffle",
+    output: "test_shuffle/seed_43.out",
+  });
+
+  itest!(deno_test_shuffle_filter {
+    args: "test --unstable --shuffle=42 --filter case test_shuffle",
+    output: "test_shuffle/seed_42_filter.out",
+  });
+
+  itest!(deno_test_filter_regex {
+    args: "test --filter /^integration:.*db$/ test_filter",
+    output: "test_filter/regex.out",
+  });
+
+  itest!(deno_test_filter_exact {
+    args: "test --filter \"unit\" test_filter",
+    output: "test_filter/exact.out",
+  });
+
+  itest!(deno_test_filter_negation {
+    args: "test --filter !flaky test_filter",
+    output: "test_filter/negation.out",
+  });
+
+  itest!(deno_test_filter_skip {
+    args: "test --filter integration --skip flaky --skip /http$/ test_filter",
+    output: "test_filter/include_exclude.out",
+  });
+
+  itest!(deno_test_filter_invalid {
+    args: "test --filter /(/ test_filter",
+    exit_code: 1,
+    output: "test_filter/invalid.out",
+  });
+
+  itest!(deno_bench {
+    args: "bench --unstable bench/passing_bench.js",
+    output: "bench/passing.out",
+  });
+
+  itest!(deno_bench_failing {
+    args: "bench --unstable bench/failing_bench.js",
+    exit_code: 1,
+    output: "bench/failing.out",
+  });
+
+  #[test]
+  fn deno_bench_json() {
+    let output = util::deno_cmd()
+      .current_dir(util::tests_path())
+      .arg("bench")
+      .arg("--unstable")
+      .arg("--json")
+      .arg("bench/passing_bench.js")
+      .output()
+      .unwrap();
+    assert!(output.status.success());
+    let report: serde_json::Value =
+      serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(report["passed"], 4);
+    assert_eq!(report["ignored"], 1);
+
+    let benches = report["benches"].as_array().unwrap();
+    let names: Vec<&str> = benches
+      .iter()
+      .map(|b| b["name"].as_str().unwrap())
+      .collect();
+    assert_eq!(
+      names,
+      vec!["sum", "async", "parse small", "parse large", "ignored"]
+    );
+    let mean = |name: &str| {
+      benches.iter().find(|b| b["name"] == name).unwrap()["stats"]["mean"]
+        .as_f64()
+        .unwrap()
+    };
+    for bench in &benches[..4] {
+      let stats = &bench["stats"];
+      for key in &["n", "min", "max", "mean", "p75", "p99", "iterPerSec"] {
+        assert!(stats[key].as_f64().unwrap() > 0.0, "{} of {}", key, bench);
+      }
+      let baseline = if bench["group"] == "parse" {
+        "parse small"
+      } else {
+        "sum"
+      };
+      let relative = bench["relative"].as_f64().unwrap();
+      let expected = mean(bench["name"].as_str().unwrap()) / mean(baseline);
+      assert!((relative - expected).abs() < 1e-9, "{}", bench);
+    }
+    assert_eq!(benches[4]["status"], "ignored");
+    assert!(benches[4]["stats"].is_null());
+  }
+
+  itest!(task_echo {
+    args: "task --unstable --config task/deno.json echo",
+    output: "task/echo.out",
+  });
+
+  itest!(task_args {
+    args: "task --unstable --config task/deno.json args one --flag",
+    output: "task/args.out",
+  });
+
+  itest!(task_env {
+    args: "task --unstable --config task/deno.json env",
+    output: "task/env.out",
+  });
+
+  itest!(task_exit_codes {
+    args: "task --unstable --config task/deno.json exit_codes",
+    output: "task/exit_codes.out",
+  });
+
+  itest!(task_cwd {
+    args: "task --unstable --config task/deno.json cwd",
+    output: "task/cwd.out",
+  });
+
+  itest!(task_nested {
+    args: "task --unstable --config task/deno.json nested",
+    output: "task/nested.out",
+  });
+
+  itest!(task_fail {
+    args: "task --unstable --config task/deno.json fail",
+    exit_code: 2,
+    output: "task/fail.out",
+  });
+
+  itest!(task_not_found {
+    args: "task --unstable --config task/deno.json ech",
+    exit_code: 1,
+    output: "task/not_found.out",
+  });
+
+  itest!(task_list {
+    args: "task --unstable --config task/deno.json",
+    output: "task/list.out",
+  });
+
+  #[test]
+  fn vendor_run_offline() {
+    let _g = util::http_server();
+    let dir = TempDir::new().expect("tempdir fail");
+    let main = util::tests_path().join("vendor/main.ts");
+    let vendor = |force: bool| {
+      let mut cmd = util::deno_cmd();
+      cmd.current_dir(dir.path()).arg("vendor").arg("--unstable");
+      if force {
+        cmd.arg("--force");
+      }
+      cmd
+        .arg(&main)
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped())
+        .spawn()
+        .unwrap()
+        .wait_with_output()
+        .unwrap()
+    };
+
+    let output = vendor(false);
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("Vendored 2 modules into vendor/ directory."));
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("are not statically analyzable"));
+    assert!(stderr
+      .contains("at http://localhost:4545/cli/tests/vendor/remote/mod.ts:8:"));
+
+    let vendor_dir = dir.path().join("vendor");
+    let import_map: serde_json::Value = serde_json::from_str(
+      &std::fs::read_to_string(vendor_dir.join("import_map.json")).unwrap(),
+    )
+    .unwrap();
+    let imports = &import_map["imports"];
+    // The redirect points at the copy of the module it was redirected to.
+    assert_eq!(
+      imports["http://localhost:4546/cli/tests/vendor/remote/mod.ts"],
+      "./localhost_4545/cli/tests/vendor/remote/mod.ts"
+    );
+    assert!(vendor_dir
+      .join("localhost_4545/cli/tests/vendor/remote/mod.ts")
+      .is_file());
+    let util = imports
+      ["http://localhost:4545/cli/tests/vendor/remote/util.ts?v=2"]
+      .as_str()
+      .unwrap();
+    assert!(util.starts_with("./localhost_4545/cli/tests/vendor/remote/util_"));
+    assert!(vendor_dir.join(util).is_file());
+
+    let output = util::deno_cmd()
+      .current_dir(dir.path())
+      .arg("run")
+      .arg("--unstable")
+      .arg("--no-remote")
+      .arg("--import-map")
+      .arg("vendor/import_map.json")
+      .arg(&main)
+      .output()
+      .unwrap();
+    assert!(output.status.success());
+    assert_eq!(
+      String::from_utf8(output.stdout).unwrap(),
+      "Hello, vendored world\n"
+    );
+
+    let output = vendor(false);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("Directory \"vendor\" already exists"));
+    assert!(vendor(true).status.success());
+  }
+
   itest!(deno_test_unresolved_promise {
-    args: "test test_unresolved_promise.js",
+    args: "test --check test_unresolved_promise.js",
     exit_code: 1,
     output: "deno_test_unresolved_promise.out",
   });
 
+  #[test]
+  fn deno_test_shuffle_random_seed() {
+    let output = util::deno_cmd()
+      .current_dir(util::tests_path())
+      .env("NO_COLOR", "1")
+      .arg("test")
+      .arg("--unstable")
+      .arg("--shuffle")
+      .arg("test_shuffle")
+      .output()
+      .unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let seed = stdout
+      .lines()
+      .find_map(|line| {
+        line
+          .strip_prefix("running 11 tests in random order (seed: ")?
+          .strip_suffix(")")
+      })
+      .expect("seed is printed");
+    assert!(seed.parse::<u64>().is_ok());
+  }
+
+  #[test]
+  fn deno_test_jobs() {
+    use std::time::Duration;
+    use std::time::Instant;
+
+    let start = Instant::now();
+    let output = util::deno_cmd()
+      .current_dir(util::tests_path())
+      .env("NO_COLOR", "1")
+      .arg("test")
+      .arg("--unstable")
+      .arg("--jobs=4")
+      .arg("test_jobs/slow")
+      .output()
+      .unwrap();
+    let elapsed = start.elapsed();
+    assert!(output.status.success());
+    // Each of the four modules takes a second, so running them one after the
+    // other would take at least four.
+    assert!(elapsed < Duration::from_secs(3), "took {:?}", elapsed);
+
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    for name in &["a", "b", "c", "d"] {
+      assert!(stdout.contains(&format!("test slow {} ... ok (", name)));
+    }
+    // Reports are printed a module at a time, so no line mixes the output of
+    // two of them.
+    for line in stdout.lines().filter(|line| line.starts_with("test ")) {
+      assert!(line.ends_with("ms)"), "interleaved line: {:?}", line);
+    }
+    assert!(stdout.contains(
+      "test result: ok. 4 passed; 0 failed; 0 ignored; 0 measured; 0 filtered out"
+    ));
+  }
+
+  #[test]
+  fn deno_test_jobs_failure() {
+    let output = util::deno_cmd()
+      .current_dir(util::tests_path())
+      .env("NO_COLOR", "1")
+      .arg("test")
+      .arg("--unstable")
+      .arg("--jobs=2")
+      .arg("test_jobs/failing")
+      .output()
+      .unwrap();
+    assert_eq!(output.status.code(), Some(1));
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("test failing ... FAILED"));
+    assert!(stdout.contains(
+      "test result: FAILED. 1 passed; 1 failed; 0 ignored; 0 measured; 0 filtered out"
+    ));
+  }
+
+  /// Checks that `xml` is well-formed, with a `<testsuites>` root, and returns
+  /// the names of its elements in document order.
+  fn check_junit_xml(xml: &str) -> Vec<String> {
+    let xml = xml
+      .strip_prefix(r#"<?xml version="1.0" encoding="UTF-8"?>"#)
+      .expect("XML declaration");
+    let mut elements = vec![];
+    let mut open: Vec<String> = vec![];
+    let mut rest = xml;
+    while let Some(start) = rest.find('<') {
+      assert!(!rest[..start].contains('>'), "stray > in {:?}", rest);
+      let end = start + rest[start..].find('>').expect("unclosed tag");
+      let tag = &rest[start + 1..end];
+      if let Some(name) = tag.strip_prefix('/') {
+        assert_eq!(open.pop().as_deref(), Some(name), "mismatched tag");
+      } else {
+        let name = tag
+          .trim_end_matches('/')
+          .split_whitespace()
+          .next()
+          .expect("tag name")
+          .to_string();
+        // Attribute values are quoted, with no quotes left unescaped.
+        assert_eq!(tag.matches('"').count() % 2, 0, "bad attributes: {}", tag);
+        if open.is_empty() {
+          assert_eq!(name, "testsuites");
+          assert!(elements.is_empty(), "more than one root element");
+        }
+        if !tag.ends_with('/') {
+          open.push(name.clone());
+        }
+        elements.push(name);
+      }
+      rest = &rest[end + 1..];
+    }
+    assert!(open.is_empty(), "unclosed elements: {:?}", open);
+    assert!(rest.trim().is_empty());
+    elements
+  }
+
+  #[test]
+  fn deno_test_reporter_junit() {
+    let t = TempDir::new().expect("tempdir fail");
+    let report_path = t.path().join("report.xml");
+    let output = util::deno_cmd()
+      .current_dir(util::tests_path())
+      .env("NO_COLOR", "1")
+      .arg("test")
+      .arg("--unstable")
+      .arg("--reporter=junit")
+      .arg(format!("--output={}", report_path.display()))
+      .arg("test_reporter")
+      .output()
+      .unwrap();
+    assert_eq!(output.status.code(), Some(1));
+
+    let xml = std::fs::read_to_string(&report_path).unwrap();
+    let elements = check_junit_xml(&xml);
+    assert_eq!(elements.iter().filter(|e| *e == "testsuite").count(), 2);
+    assert_eq!(elements.iter().filter(|e| *e == "testcase").count(), 7);
+    assert_eq!(elements.iter().filter(|e| *e == "failure").count(), 3);
+    assert_eq!(elements.iter().filter(|e| *e == "skipped").count(), 1);
+
+    assert!(xml.contains(
+      r#"<testsuites name="deno test" tests="7" failures="3" errors="0" skipped="1""#
+    ));
+    assert!(xml
+      .contains(r#"a_test.ts" tests="6" failures="3" errors="0" skipped="1""#));
+    assert!(xml.contains(r#"<testcase name="steps &gt; failing step""#));
+    assert!(xml.contains(r#"<failure message="Error: boom">"#));
+    // The stack trace points at the TypeScript source, not the emitted code.
+    assert!(xml.contains("a_test.ts:9:9"), "{}", xml);
+    assert!(xml.contains("a_test.ts:17:11"), "{}", xml);
+  }
+
+  #[test]
+  fn deno_test_reporter_tap() {
+    let output = util::deno_cmd()
+      .current_dir(util::tests_path())
+      .env("NO_COLOR", "1")
+      .arg("test")
+      .arg("--unstable")
+      .arg("--reporter=tap")
+      .arg("test_reporter")
+      .output()
+      .unwrap();
+    assert_eq!(output.status.code(), Some(1));
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let lines: Vec<&str> = stdout.lines().collect();
+    assert_eq!(lines[0], "TAP version 13");
+
+    // Tests are numbered in the order they end, across modules.
+    let results: Vec<&str> = lines
+      .iter()
+      .filter(|line| line.starts_with("ok ") || line.starts_with("not ok "))
+      .copied()
+      .collect();
+    assert_eq!(results.len(), 5);
+    for (i, line) in results.iter().enumerate() {
+      let line = line.strip_prefix("not ").unwrap_or(line);
+      assert!(line.starts_with(&format!("ok {} - ", i + 1)), "{}", line);
+    }
+    assert!(results.iter().any(|l| l.ends_with(" - passes")));
+    assert!(results
+      .iter()
+      .any(|l| l.starts_with("not ok ") && l.ends_with(" - fails")));
+    assert!(results.iter().any(|l| l.ends_with(" - ignored # SKIP")));
+
+    // Steps are indented subtests with their own numbering and plan, before
+    // their parent.
+    let steps = stdout.find("    # Subtest: steps\n").expect("subtest");
+    assert!(stdout[steps..].starts_with(
+      "    # Subtest: steps\n    ok 1 - passing step\n    not ok 2 - failing step\n      ---\n"
+    ));
+    assert!(stdout[steps..].contains("    1..2\nnot ok "));
+    assert!(stdout
+      .contains("  message: \"Error: boom\"\n  stack: |-\n    Error: boom\n"));
+    assert!(stdout.ends_with("1..5\n# tests 5\n# pass 2\n# fail 2\n# skip 1\n"));
+  }
+
+  #[test]
+  fn deno_test_reporter_dot() {
+    let output = util::deno_cmd()
+      .current_dir(util::tests_path())
+      .env("NO_COLOR", "1")
+      .arg("test")
+      .arg("--unstable")
+      .arg("--reporter=dot")
+      .arg("test_reporter/b_test.js")
+      .output()
+      .unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.starts_with(".\n\ntest result: ok. 1 passed; 0 failed;"));
+  }
+
   #[test]
   fn timeout_clear() {
     // https://github.com/denoland/deno/issues/7599
@@ -2482,22 +3642,61 @@ console.log("finish");
     output: "030_eval_ts.out",
   });
 
-  itest!(_031_info_ts_error {
-    args: "info 031_info_ts_error.ts",
-    output: "031_info_ts_error.out",
+  itest!(_031_info_ts_error {
+    args: "info 031_info_ts_error.ts",
+    output: "031_info_ts_error.out",
+  });
+
+  itest!(_033_import_map {
+    args:
+      "run --quiet --reload --import-map=import_maps/import_map.json --unstable import_maps/test.ts",
+    output: "033_import_map.out",
+  });
+
+  itest!(_033_import_map_remote {
+    args:
+      "run --quiet --reload --import-map=http://127.0.0.1:4545/cli/tests/import_maps/import_map_remote.json --unstable import_maps/test_remote.ts",
+    output: "033_import_map_remote.out",
+    http_server: true,
+  });
+
+  itest!(_033_import_map_config {
+    args:
+      "run --quiet --reload --config=import_maps/config.json --unstable import_maps/test.ts",
+    output: "033_import_map.out",
+  });
+
+  itest!(import_map_config_cache {
+    args:
+      "cache --quiet --reload --config=import_maps/config.json --unstable import_maps/test.ts",
+    output: "036_import_map_fetch.out",
+  });
+
+  itest!(import_map_config_test {
+    args:
+      "test --quiet --reload --config=import_maps/config.json --unstable import_maps/config_test.ts",
+    output: "import_maps/config_test.out",
+  });
+
+  itest!(import_map_config_and_import_map {
+    args:
+      "run --quiet --reload --import-map=import_maps/import_map.json --config=import_maps/config.json --unstable import_maps/test.ts",
+    output: "import_maps/config_and_import_map.out",
+    exit_code: 1,
   });
 
-  itest!(_033_import_map {
+  itest!(import_map_unmapped_bare_specifier {
     args:
-      "run --quiet --reload --import-map=import_maps/import_map.json --unstable import_maps/test.ts",
-    output: "033_import_map.out",
+      "run --quiet --reload --config=import_maps/config.json --unstable import_maps/typo.ts",
+    output: "import_maps/typo.out",
+    exit_code: 1,
   });
 
-  itest!(_033_import_map_remote {
+  itest!(import_map_missing_address {
     args:
-      "run --quiet --reload --import-map=http://127.0.0.1:4545/cli/tests/import_maps/import_map_remote.json --unstable import_maps/test_remote.ts",
-    output: "033_import_map_remote.out",
-    http_server: true,
+      "run --quiet --reload --import-map=import_maps/missing.json --unstable import_maps/missing.ts",
+    output: "import_maps/missing.out",
+    exit_code: 1,
   });
 
   itest!(_034_onload {
@@ -2527,7 +3726,8 @@ console.log("finish");
 
   itest!(_038_checkjs {
     // checking if JS file is run through TS compiler
-    args: "run --reload --config 038_checkjs.tsconfig.json 038_checkjs.js",
+    args:
+      "run --check --reload --config 038_checkjs.tsconfig.json 038_checkjs.js",
     exit_code: 1,
     output: "038_checkjs.js.out",
   });
@@ -2606,6 +3806,12 @@ console.log("finish");
     exit_code: 0,
   });
 
+  itest!(info_reverse {
+    args: "info --quiet --reverse subdir/print_hello.ts 005_more_imports.ts",
+    output: "info_reverse.out",
+    exit_code: 0,
+  });
+
   itest!(_056_make_temp_file_write_perm {
     args:
       "run --quiet --allow-read --allow-write=./subdir/ 056_make_temp_file_write_perm.ts",
@@ -2677,7 +3883,7 @@ console.log("finish");
   }
 
   itest!(_067_test_no_run_type_error {
-    args: "test --unstable --no-run test_type_error",
+    args: "test --check --unstable --no-run test_type_error",
     output: "067_test_no_run_type_error.out",
     exit_code: 1,
   });
@@ -2844,6 +4050,82 @@ console.log("finish");
     http_server: true,
   });
 
+  #[test]
+  fn lock_next_to_config_file() {
+    let _g = util::http_server();
+    let dir = TempDir::new().expect("tempdir fail");
+    let config_path = dir.path().join("deno.json");
+    std::fs::write(&config_path, "{}").unwrap();
+    let lock_path = dir.path().join("deno.lock");
+    let read_lock = || -> serde_json::Value {
+      serde_json::from_str(&std::fs::read_to_string(&lock_path).unwrap())
+        .unwrap()
+    };
+    let cache = |url: &str, frozen: bool| {
+      let mut cmd = util::deno_cmd();
+      cmd
+        .current_dir(util::tests_path())
+        .arg("cache")
+        .arg("--config")
+        .arg(&config_path);
+      if frozen {
+        cmd.arg("--frozen-lockfile");
+      }
+      cmd
+        .arg(url)
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped())
+        .spawn()
+        .unwrap()
+        .wait_with_output()
+        .unwrap()
+    };
+    let main = "http://127.0.0.1:4545/cli/tests/003_relative_import.ts";
+    let mod1 = "http://127.0.0.1:4545/cli/tests/subdir/mod1.ts";
+    let mod2 = "http://127.0.0.1:4545/cli/tests/subdir/subdir2/mod2.ts";
+    let print_hello = "http://127.0.0.1:4545/cli/tests/subdir/print_hello.ts";
+
+    // A lock file in the original format is read and upgraded.
+    std::fs::copy(util::tests_path().join("lock_check_ok.json"), &lock_path)
+      .unwrap();
+    let output = cache(main, false);
+    assert!(output.status.success());
+    let lock = read_lock();
+    assert_eq!(lock["version"], "2");
+    assert_eq!(
+      lock["remote"][print_hello],
+      "fe7bbccaedb6579200a8b582f905139296402d06b1b91109d6e12c41a23125da"
+    );
+    assert_eq!(lock["remote"].as_object().unwrap().len(), 2);
+
+    // A frozen lock file isn't updated with new modules.
+    let output = cache(mod1, true);
+    assert_eq!(output.status.code(), Some(10));
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("The module is missing from the frozen lock file."));
+    assert_eq!(read_lock(), lock);
+
+    // Otherwise, new modules are added.
+    let output = cache(mod1, false);
+    assert!(output.status.success());
+    let lock = read_lock();
+    assert_eq!(lock["remote"].as_object().unwrap().len(), 4);
+    let hash = lock["remote"][mod2].as_str().unwrap().to_string();
+
+    // Modules that changed fail the check, with both hashes.
+    let mut changed = lock;
+    changed["remote"][mod2] = serde_json::json!("bad");
+    std::fs::write(&lock_path, changed.to_string()).unwrap();
+    let output = cache(mod1, true);
+    assert_eq!(output.status.code(), Some(10));
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains(&format!(
+      "does not match the expected hash in the lock file.\n  Specifier: {}",
+      mod2
+    )));
+    assert!(stderr.contains(&format!("  Expected: bad\n  Actual: {}", hash)));
+  }
+
   itest!(async_error {
     exit_code: 1,
     args: "run --reload async_error.ts",
@@ -2917,16 +4199,53 @@ console.log("finish");
   });
 
   itest!(config {
-    args: "run --reload --config config.tsconfig.json config.ts",
+    args: "run --check --reload --config config.tsconfig.json config.ts",
     exit_code: 1,
     output: "config.ts.out",
   });
 
   itest!(emtpy_typescript {
-    args: "run --reload subdir/empty.ts",
+    args: "run --check --reload subdir/empty.ts",
     output_str: Some("Check file:[WILDCARD]tests/subdir/empty.ts\n"),
   });
 
+  itest!(check_local_type_error {
+    args: "check --reload check/local_type_error.ts",
+    output: "check/local_type_error.out",
+    exit_code: 1,
+  });
+
+  itest!(run_local_type_error_without_check {
+    args: "run --quiet --reload check/local_type_error.ts",
+    output: "check/local_type_error_no_check.out",
+  });
+
+  itest!(run_check_local_type_error {
+    args: "run --check --reload check/local_type_error.ts",
+    output: "check/local_type_error.out",
+    exit_code: 1,
+  });
+
+  itest!(check_ignores_remote_type_error {
+    args: "check --reload check/remote_type_error.ts",
+    output_str: Some("[WILDCARD]"),
+    http_server: true,
+  });
+
+  itest!(check_remote_type_error {
+    args: "check --remote --reload check/remote_type_error.ts",
+    output: "check/remote_type_error.out",
+    http_server: true,
+    exit_code: 1,
+  });
+
+  itest!(run_check_all_remote_type_error {
+    args: "run --check=all --reload check/remote_type_error.ts",
+    output: "check/remote_type_error.out",
+    http_server: true,
+    exit_code: 1,
+  });
+
   itest!(error_001 {
     args: "run --reload error_001.ts",
     exit_code: 1,
@@ -2940,7 +4259,7 @@ console.log("finish");
   });
 
   itest!(error_003_typescript {
-    args: "run --reload error_003_typescript.ts",
+    args: "run --check --reload error_003_typescript.ts",
     exit_code: 1,
     output: "error_003_typescript.ts.out",
   });
@@ -2950,7 +4269,7 @@ console.log("finish");
   // should result in the same output.
   // https://github.com/denoland/deno/issues/2436
   itest!(error_003_typescript2 {
-    args: "run error_003_typescript.ts",
+    args: "run --check error_003_typescript.ts",
     exit_code: 1,
     output: "error_003_typescript.ts.out",
   });
@@ -2999,7 +4318,7 @@ console.log("finish");
   });
 
   itest!(error_012_bad_dynamic_import_specifier {
-    args: "run --reload error_012_bad_dynamic_import_specifier.ts",
+    args: "run --check --reload error_012_bad_dynamic_import_specifier.ts",
     exit_code: 1,
     output: "error_012_bad_dynamic_import_specifier.ts.out",
   });
@@ -3031,7 +4350,7 @@ console.log("finish");
   });
 
   itest!(error_017_hide_long_source_ts {
-    args: "run --reload error_017_hide_long_source_ts.ts",
+    args: "run --check --reload error_017_hide_long_source_ts.ts",
     output: "error_017_hide_long_source_ts.ts.out",
     exit_code: 1,
   });
@@ -3103,6 +4422,43 @@ console.log("finish");
     exit_code: 1,
   });
 
+  itest!(run_stdin_type_only_import {
+    args: "run --check --reload -",
+    input: Some("import type { Greeting } from \"./subdir/stdin_types.ts\";\nconst greeting: Greeting = { text: \"Hello from stdin\" };\nconsole.log(greeting.text);\n"),
+    output: "stdin_type_only_import.out",
+  });
+
+  itest!(run_stdin_type_error {
+    args: "run --check --reload -",
+    input: Some("const a: string = 1;\nconsole.log(a);\n"),
+    exit_code: 1,
+    output: "stdin_type_error.out",
+  });
+
+  itest!(run_stdin_syntax_error {
+    args: "run --reload -",
+    input: Some("const a: number = 1;\n\nconst b: = 2;\n"),
+    exit_code: 1,
+    output: "stdin_syntax_error.out",
+  });
+
+  itest!(run_stdin_relative_import {
+    args: "run --reload -",
+    input: Some("import { printHello } from \"./subdir/print_hello.ts\";\nprintHello();\n"),
+    output: "stdin_relative_import.out",
+  });
+
+  itest!(run_stdin_ext_js {
+    args: "run --reload --ext=js -",
+    input: Some("console.log(typeof Deno);\n"),
+    output_str: Some("object\n"),
+  });
+
+  itest!(eval_ext_ts {
+    args: "eval --ext=ts console.log(<number>1)",
+    output_str: Some("1\n"),
+  });
+
   itest!(error_syntax {
     args: "run --reload error_syntax.js",
     exit_code: 1,
@@ -3213,7 +4569,7 @@ console.log("finish");
   });
 
   itest!(type_definitions_for_export {
-    args: "run --reload type_definitions_for_export.ts",
+    args: "run --check --reload type_definitions_for_export.ts",
     output: "type_definitions_for_export.ts.out",
     exit_code: 1,
   });
@@ -3236,25 +4592,25 @@ console.log("finish");
   });
 
   itest!(type_directives_redirect {
-    args: "run --reload type_directives_redirect.ts",
+    args: "run --check --reload type_directives_redirect.ts",
     output: "type_directives_redirect.ts.out",
     http_server: true,
   });
 
   itest!(type_headers_deno_types {
-    args: "run --reload type_headers_deno_types.ts",
+    args: "run --check --reload type_headers_deno_types.ts",
     output: "type_headers_deno_types.ts.out",
     http_server: true,
   });
 
   itest!(ts_type_imports {
-    args: "run --reload ts_type_imports.ts",
+    args: "run --check --reload ts_type_imports.ts",
     output: "ts_type_imports.ts.out",
     exit_code: 1,
   });
 
   itest!(ts_decorators {
-    args: "run --reload -c tsconfig.decorators.json ts_decorators.ts",
+    args: "run --check --reload -c tsconfig.decorators.json ts_decorators.ts",
     output: "ts_decorators.ts.out",
   });
 
@@ -3264,7 +4620,7 @@ console.log("finish");
   });
 
   itest!(ts_type_only_import {
-    args: "run --reload ts_type_only_import.ts",
+    args: "run --check --reload ts_type_only_import.ts",
     output: "ts_type_only_import.ts.out",
   });
 
@@ -3387,7 +4743,7 @@ console.log("finish");
   });
 
   itest!(unstable_disabled {
-    args: "run --reload unstable.ts",
+    args: "run --check --reload unstable.ts",
     exit_code: 1,
     output: "unstable_disabled.out",
   });
@@ -3408,7 +4764,7 @@ console.log("finish");
   });
 
   itest!(unstable_disabled_ts2551 {
-    args: "run --reload unstable_ts2551.ts",
+    args: "run --check --reload unstable_ts2551.ts",
     exit_code: 1,
     output: "unstable_disabled_ts2551.out",
   });
@@ -3484,7 +4840,7 @@ console.log("finish");
   });
 
   itest!(tsx_imports {
-    args: "run --reload tsx_imports.ts",
+    args: "run --check --reload tsx_imports.ts",
     output: "tsx_imports.ts.out",
   });
 
@@ -3540,7 +4896,7 @@ console.log("finish");
   });
 
   itest!(single_compile_with_reload {
-    args: "run --reload --allow-read single_compile_with_reload.ts",
+    args: "run --check --reload --allow-read single_compile_with_reload.ts",
     output: "single_compile_with_reload.ts.out",
   });
 
@@ -3562,7 +4918,7 @@ console.log("finish");
   });
 
   itest!(deno_test_coverage {
-    args: "test --coverage --unstable test_coverage.ts",
+    args: "test --check --coverage --unstable test_coverage.ts",
     output: "test_coverage.out",
     exit_code: 0,
   });
@@ -3574,31 +4930,76 @@ console.log("finish");
   });
 
   itest!(deno_test_branch_coverage {
-    args: "test --coverage --unstable test_branch_coverage.ts",
+    args: "test --check --coverage --unstable test_branch_coverage.ts",
     output: "test_branch_coverage.out",
     exit_code: 0,
   });
 
+  #[test]
+  fn deno_test_branch_coverage_lcov_and_html() {
+    let temp_dir = TempDir::new().expect("tempdir fail");
+    let lcov = temp_dir.path().join("cov.lcov");
+    let html = temp_dir.path().join("html");
+    let status = util::deno_cmd()
+      .current_dir(util::tests_path())
+      .arg("test")
+      .arg("--unstable")
+      .arg(format!(
+        "--coverage={}",
+        temp_dir.path().join("cov").display()
+      ))
+      .arg(format!("--lcov={}", lcov.display()))
+      .arg(format!("--html={}", html.display()))
+      .arg("test_branch_coverage.ts")
+      .spawn()
+      .unwrap()
+      .wait()
+      .unwrap();
+    assert!(status.success());
+
+    // Only the `if` arm of `branch()` runs, its `else` block on line 4 never.
+    let lcov = std::fs::read_to_string(lcov).unwrap();
+    assert!(lcov.contains("subdir/branch.ts\n"));
+    assert!(lcov.contains("FN:1,branch\nFNDA:1,branch\nFNF:1\nFNH:1\n"));
+    assert!(lcov.contains("BRDA:4,1,0,0\nBRF:1\nBRH:0\n"));
+    assert!(lcov.contains("DA:4,0\nDA:5,0\nDA:6,0\n"));
+    assert!(lcov.contains("LF:7\nLH:4\nend_of_record\n"));
+
+    let index = std::fs::read_to_string(html.join("index.html")).unwrap();
+    assert!(index.contains("branch.ts</a></td><td>57.143% (4/7)</td>"));
+    let page_name = index
+      .split("<a href=\"")
+      .nth(1)
+      .and_then(|rest| rest.split('"').next())
+      .unwrap();
+    let page = std::fs::read_to_string(html.join(page_name)).unwrap();
+    assert!(page.contains(
+      "<tr class=\"miss\"><td class=\"line\">5</td><td class=\"count\">0</td><td><pre>    return false;</pre></td></tr>"
+    ));
+  }
+
   itest!(deno_test_coverage_explicit {
-    args: "test --coverage=.test_coverage --unstable test_coverage.ts",
+    args: "test --check --coverage=.test_coverage --unstable test_coverage.ts",
     output: "test_coverage.out",
     exit_code: 0,
   });
 
   itest!(deno_test_run_test_coverage {
-    args: "test --allow-all --coverage --unstable test_run_test_coverage.ts",
+    args:
+      "test --check --allow-all --coverage --unstable test_run_test_coverage.ts",
     output: "test_run_test_coverage.out",
     exit_code: 0,
   });
 
   itest!(deno_test_run_run_coverage {
-    args: "test --allow-all --coverage --unstable test_run_run_coverage.ts",
+    args:
+      "test --check --allow-all --coverage --unstable test_run_run_coverage.ts",
     output: "test_run_run_coverage.out",
     exit_code: 0,
   });
 
   itest!(deno_test_run_combined_coverage {
-    args: "test --allow-all --coverage --unstable test_run_run_coverage.ts test_run_test_coverage.ts",
+    args: "test --check --allow-all --coverage --unstable test_run_run_coverage.ts test_run_test_coverage.ts",
     output: "test_run_combined_coverage.out",
     exit_code: 0,
   });
@@ -4998,6 +6399,125 @@ console.log("finish");
     assert_eq!(output.stderr, b"Checked 0 file\n");
   }
 
+  #[test]
+  fn lint_fix() {
+    let t = TempDir::new().expect("tempdir fail");
+    let file = t.path().join("fixable.ts");
+    std::fs::write(
+      &file,
+      "let a: any = 1;\nlet b = 2;\nexport { a, b };\nif (a) {}\n",
+    )
+    .unwrap();
+
+    let output = util::deno_cmd()
+      .current_dir(t.path())
+      .arg("lint")
+      .arg("--unstable")
+      .arg("--fix")
+      .arg("fixable.ts")
+      .env("NO_COLOR", "1")
+      .output()
+      .expect("Failed to spawn script");
+    // `no-empty` has no fix.
+    assert!(!output.status.success());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("no-empty"));
+    assert!(stderr.contains("Found 1 problem"));
+    assert_eq!(
+      std::fs::read_to_string(&file).unwrap(),
+      "const a: unknown = 1;\nconst b = 2;\nexport { a, b };\nif (a) {}\n"
+    );
+  }
+
+  #[test]
+  fn lint_json_fixes() {
+    #[derive(serde::Deserialize)]
+    struct Report {
+      diagnostics: Vec<Diagnostic>,
+    }
+    #[derive(serde::Deserialize)]
+    struct Diagnostic {
+      code: String,
+      fix: Option<Fix>,
+    }
+    #[derive(serde::Deserialize)]
+    struct Fix {
+      description: String,
+      edits: Vec<Edit>,
+    }
+    #[derive(serde::Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    struct Edit {
+      range: Range,
+      new_text: String,
+    }
+    #[derive(serde::Deserialize)]
+    struct Range {
+      start: Position,
+      end: Position,
+    }
+    #[derive(serde::Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    struct Position {
+      line: usize,
+      col: usize,
+      byte_pos: usize,
+    }
+
+    let t = TempDir::new().expect("tempdir fail");
+    std::fs::write(
+      t.path().join("fixable.ts"),
+      "export let a: any;\nif (a) {}\n",
+    )
+    .unwrap();
+    let output = util::deno_cmd()
+      .current_dir(t.path())
+      .arg("lint")
+      .arg("--unstable")
+      .arg("--json")
+      .arg("fixable.ts")
+      .output()
+      .expect("Failed to spawn script");
+    assert!(!output.status.success());
+    let report: Report = serde_json::from_slice(&output.stderr).unwrap();
+    assert_eq!(report.diagnostics.len(), 2);
+
+    let any = &report.diagnostics[0];
+    assert_eq!(any.code, "no-explicit-any");
+    let fix = any.fix.as_ref().unwrap();
+    assert_eq!(fix.description, "Replace `any` with `unknown`");
+    assert_eq!(fix.edits.len(), 1);
+    assert_eq!(fix.edits[0].new_text, "unknown");
+    let range = &fix.edits[0].range;
+    assert_eq!(
+      (range.start.line, range.start.col, range.start.byte_pos),
+      (1, 14, 14)
+    );
+    assert_eq!(
+      (range.end.line, range.end.col, range.end.byte_pos),
+      (1, 17, 17)
+    );
+
+    let empty = &report.diagnostics[1];
+    assert_eq!(empty.code, "no-empty");
+    assert!(empty.fix.is_none());
+  }
+
+  #[test]
+  fn lint_fix_stdin() {
+    let output = util::deno_cmd()
+      .arg("lint")
+      .arg("--unstable")
+      .arg("--fix")
+      .arg("-")
+      .stdin(std::process::Stdio::null())
+      .output()
+      .expect("Failed to spawn script");
+    assert!(!output.status.success());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("--fix can't be used when linting stdin"));
+  }
+
   #[test]
   fn fmt_ignore_unexplicit_files() {
     let output = util::deno_cmd()
@@ -5152,6 +6672,199 @@ console.log("finish");
       .contains("Self-contained binaries don't support module loading"));
   }
 
+  #[test]
+  fn standalone_include_assets() {
+    let dir = TempDir::new().expect("tempdir fail");
+    let exe = if cfg!(windows) {
+      dir.path().join("assets.exe")
+    } else {
+      dir.path().join("assets")
+    };
+    let output = util::deno_cmd()
+      .current_dir(util::tests_path())
+      .arg("compile")
+      .arg("--unstable")
+      .arg("--include")
+      .arg("standalone_assets/hello.txt")
+      .arg("--include")
+      .arg("standalone_assets/nested")
+      .arg("--output")
+      .arg(&exe)
+      .arg("./standalone_assets.ts")
+      .stdout(std::process::Stdio::piped())
+      .spawn()
+      .unwrap()
+      .wait_with_output()
+      .unwrap();
+    assert!(output.status.success());
+    // Run from another directory, so the assets can only come from the
+    // executable.
+    let output = Command::new(exe)
+      .current_dir(dir.path())
+      .stdout(std::process::Stdio::piped())
+      .spawn()
+      .unwrap()
+      .wait_with_output()
+      .unwrap();
+    assert!(output.status.success());
+    assert_eq!(
+      std::str::from_utf8(&output.stdout).unwrap(),
+      "Hello from an embedded file\n{ nested: true }\nfrom \nPermissionDenied\n"
+    );
+  }
+
+  #[test]
+  fn compile_include_outside_cwd_error() {
+    let dir = TempDir::new().expect("tempdir fail");
+    let exe = dir.path().join("assets");
+    let output = util::deno_cmd()
+      .current_dir(util::tests_path().join("standalone_assets"))
+      .arg("compile")
+      .arg("--unstable")
+      .arg("--include")
+      .arg("../standalone_assets.ts")
+      .arg("--output")
+      .arg(&exe)
+      .arg("../standalone_assets.ts")
+      .stderr(std::process::Stdio::piped())
+      .spawn()
+      .unwrap()
+      .wait_with_output()
+      .unwrap();
+    assert!(!output.status.success());
+    let stderr_str = String::from_utf8(output.stderr).unwrap();
+    assert!(util::strip_ansi_codes(&stderr_str).contains(
+      "Could not compile: \"../standalone_assets.ts\" is not inside the current directory."
+    ));
+  }
+
+  // Seeds $DENO_DIR/dl with a fake Windows base binary, so cross-compiling
+  // to it doesn't need the network.
+  #[cfg(unix)]
+  fn seed_windows_base_binary(deno_dir: &std::path::Path, checksum: &str) {
+    let dl_dir = deno_dir
+      .join("dl/release")
+      .join(format!("v{}", env!("CARGO_PKG_VERSION")));
+    std::fs::create_dir_all(&dl_dir).unwrap();
+    std::fs::copy(
+      util::tests_path()
+        .join("standalone_target/deno-x86_64-pc-windows-msvc.zip"),
+      dl_dir.join("deno-x86_64-pc-windows-msvc.zip"),
+    )
+    .unwrap();
+    std::fs::write(
+      dl_dir.join("deno-x86_64-pc-windows-msvc.zip.sha256sum"),
+      format!("{}  deno-x86_64-pc-windows-msvc.zip\n", checksum),
+    )
+    .unwrap();
+  }
+
+  #[cfg(unix)]
+  #[test]
+  fn compile_cross_target() {
+    let deno_dir = TempDir::new().expect("tempdir fail");
+    seed_windows_base_binary(
+      deno_dir.path(),
+      "f812cec872a55c79aacfe702342db2e8abb11023ecb623dbe284aa8e445c676f",
+    );
+    let dir = TempDir::new().expect("tempdir fail");
+    let output = util::deno_cmd()
+      .current_dir(util::root_path())
+      .env("DENO_DIR", deno_dir.path())
+      .arg("compile")
+      .arg("--unstable")
+      .arg("--cached-only")
+      .arg("--target")
+      .arg("x86_64-pc-windows-msvc")
+      .arg("--output")
+      .arg(dir.path().join("welcome"))
+      .arg("./test_util/std/examples/welcome.ts")
+      .stdout(std::process::Stdio::piped())
+      .spawn()
+      .unwrap()
+      .wait_with_output()
+      .unwrap();
+    assert!(output.status.success());
+
+    // The executable can't run here, but its layout can be checked.
+    let exe = std::fs::read(dir.path().join("welcome.exe")).unwrap();
+    let trailer = &exe[exe.len() - 24..];
+    assert_eq!(&trailer[..8], b"d3n0l4nd");
+    let mut pos = [0; 8];
+    pos.copy_from_slice(&trailer[8..16]);
+    let bundle_pos = u64::from_be_bytes(pos) as usize;
+    pos.copy_from_slice(&trailer[16..]);
+    let metadata_pos = u64::from_be_bytes(pos) as usize;
+    assert!(bundle_pos < metadata_pos && metadata_pos < exe.len() - 24);
+    let metadata: serde_json::Value =
+      serde_json::from_slice(&exe[metadata_pos..exe.len() - 24]).unwrap();
+    assert!(metadata["unstable"].as_bool().unwrap());
+
+    let base_binary = &exe[..bundle_pos];
+    let digest = ring::digest::digest(&ring::digest::SHA256, base_binary);
+    let digest: String = digest
+      .as_ref()
+      .iter()
+      .map(|b| format!("{:02x}", b))
+      .collect();
+    assert_eq!(
+      digest,
+      "1ecdf9e09141fb2f24f163ac94f792b657fcd4db4fbfc55306214d30b2c220bc"
+    );
+  }
+
+  #[cfg(unix)]
+  #[test]
+  fn compile_cross_target_checksum_mismatch() {
+    let deno_dir = TempDir::new().expect("tempdir fail");
+    seed_windows_base_binary(deno_dir.path(), &"0".repeat(64));
+    let dir = TempDir::new().expect("tempdir fail");
+    let output = util::deno_cmd()
+      .current_dir(util::root_path())
+      .env("DENO_DIR", deno_dir.path())
+      .arg("compile")
+      .arg("--unstable")
+      .arg("--cached-only")
+      .arg("--target")
+      .arg("x86_64-pc-windows-msvc")
+      .arg("--output")
+      .arg(dir.path().join("welcome"))
+      .arg("./test_util/std/examples/welcome.ts")
+      .stderr(std::process::Stdio::piped())
+      .spawn()
+      .unwrap()
+      .wait_with_output()
+      .unwrap();
+    assert!(!output.status.success());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("Checksum mismatch for the base binary"));
+    assert!(!dir.path().join("welcome.exe").exists());
+  }
+
+  #[test]
+  fn compile_cross_target_not_cached() {
+    let dir = TempDir::new().expect("tempdir fail");
+    let output = util::deno_cmd()
+      .current_dir(util::root_path())
+      .arg("compile")
+      .arg("--unstable")
+      .arg("--cached-only")
+      .arg("--target")
+      .arg("aarch64-unknown-linux-gnu")
+      .arg("--output")
+      .arg(dir.path().join("welcome"))
+      .arg("./test_util/std/examples/welcome.ts")
+      .stderr(std::process::Stdio::piped())
+      .spawn()
+      .unwrap()
+      .wait_with_output()
+      .unwrap();
+    assert!(!output.status.success());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("The base binary for aarch64-unknown-linux-gnu"));
+    assert!(stderr.contains("--cached-only is specified"));
+  }
+
   #[test]
   fn compile_with_directory_exists_error() {
     let dir = TempDir::new().expect("tempdir fail");
@@ -5486,6 +7199,7 @@ console.log("finish");
         .current_dir(util::tests_path())
         .env("NO_COLOR", "1")
         .arg("run")
+        .arg("--check")
         .arg("--allow-net")
         .arg("--unstable")
         .arg("resolve_dns.ts")
@@ -5512,6 +7226,7 @@ console.log("finish");
         .current_dir(util::tests_path())
         .env("NO_COLOR", "1")
         .arg("run")
+        .arg("--check")
         .arg("--allow-net=127.0.0.1:4553")
         .arg("--unstable")
         .arg("resolve_dns.ts")
@@ -5538,6 +7253,7 @@ console.log("finish");
         .current_dir(util::tests_path())
         .env("NO_COLOR", "1")
         .arg("run")
+        .arg("--check")
         .arg("--allow-net=deno.land")
         .arg("--unstable")
         .arg("resolve_dns.ts")
@@ -5561,6 +7277,7 @@ console.log("finish");
         .current_dir(util::tests_path())
         .env("NO_COLOR", "1")
         .arg("run")
+        .arg("--check")
         .arg("--unstable")
         .arg("resolve_dns.ts")
         .stdout(std::process::Stdio::piped())
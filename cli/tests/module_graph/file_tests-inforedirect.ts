@@ -0,0 +1,5 @@
+import { a } from "https://deno.land/x/a.ts";
+import { a as b } from "https://deno.land/x/redirect.ts";
+import * as mod from "https://deno.land/x/mod.ts";
+
+console.log(a, b, mod);
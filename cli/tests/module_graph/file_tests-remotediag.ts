@@ -0,0 +1,3 @@
+import { a } from "https://deno.land/x/diag.ts";
+
+console.log(a);
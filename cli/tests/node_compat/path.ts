@@ -0,0 +1,6 @@
+// A stand-in for the path polyfill of std/node.
+export function join(...paths: string[]): string {
+  return paths.join("/");
+}
+
+export default { join };
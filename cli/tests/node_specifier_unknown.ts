@@ -0,0 +1 @@
+import "node:nope";
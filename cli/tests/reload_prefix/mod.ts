@@ -0,0 +1,4 @@
+import { a as localhost } from "http://localhost:4545/cli/tests/reload_prefix/a.ts";
+import { a as loopback } from "http://127.0.0.1:4545/cli/tests/reload_prefix/a.ts";
+
+console.log(localhost, loopback);
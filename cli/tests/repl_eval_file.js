@@ -0,0 +1,2 @@
+// deno-lint-ignore no-unused-vars
+var evalFileValue = 42;
@@ -38,5 +38,16 @@ console.log(JSON.stringify(txt));
 try {
   await Deno.resolveDns("not-found-example.com", "A", nameServer);
 } catch (e) {
-  console.log("Error thrown for not-found-example.com");
+  console.log(`Error ${e.name} thrown for not-found-example.com`);
+}
+
+const controller = new AbortController();
+controller.abort();
+try {
+  await Deno.resolveDns("www.example.com", "A", {
+    ...nameServer,
+    signal: controller.signal,
+  });
+} catch (e) {
+  console.log(`Error ${e.name} thrown for aborted lookup`);
 }
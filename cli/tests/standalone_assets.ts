@@ -0,0 +1,17 @@
+console.log(Deno.readTextFileSync("standalone_assets/hello.txt").trim());
+
+const data = await Deno.readTextFile("./standalone_assets/nested/data.json");
+console.log(JSON.parse(data));
+
+const file = await Deno.open("standalone_assets/nested/../hello.txt");
+const buf = new Uint8Array(5);
+await file.seek(6, Deno.SeekMode.Start);
+await file.read(buf);
+file.close();
+console.log(new TextDecoder().decode(buf));
+
+try {
+  await Deno.readTextFile("../standalone_assets/hello.txt");
+} catch (err) {
+  console.log(err.name);
+}
@@ -0,0 +1,5 @@
+Deno.test("integration: db", () => {});
+Deno.test("integration: http", () => {});
+Deno.test("integration: flaky db", () => {});
+Deno.test("unit: db", () => {});
+Deno.test("unit", () => {});
@@ -0,0 +1 @@
+Deno.test("passing", () => {});
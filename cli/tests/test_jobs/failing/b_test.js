@@ -0,0 +1,3 @@
+Deno.test("failing", () => {
+  throw new Error("Fail.");
+});
@@ -0,0 +1,3 @@
+Deno.test("slow b", async () => {
+  await new Promise((resolve) => setTimeout(resolve, 1000));
+});
@@ -0,0 +1,3 @@
+Deno.test("slow c", async () => {
+  await new Promise((resolve) => setTimeout(resolve, 1000));
+});
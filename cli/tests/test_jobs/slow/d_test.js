@@ -0,0 +1,3 @@
+Deno.test("slow d", async () => {
+  await new Promise((resolve) => setTimeout(resolve, 1000));
+});
@@ -0,0 +1,19 @@
+interface Shape {
+  name: string;
+}
+
+Deno.test("passes", () => {});
+
+Deno.test("fails", () => {
+  const shape: Shape = { name: "boom" };
+  throw new Error(shape.name);
+});
+
+Deno.test({ name: "ignored", ignore: true, fn() {} });
+
+Deno.test("steps", async (t) => {
+  await t.step("passing step", () => {});
+  await t.step("failing step", () => {
+    throw new Error("step failed");
+  });
+});
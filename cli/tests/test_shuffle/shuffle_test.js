@@ -0,0 +1,9 @@
+for (let i = 0; i < 10; i++) {
+  Deno.test(`case ${i}`, () => {});
+}
+
+Deno.test({
+  name: "ignored",
+  ignore: true,
+  fn() {},
+});
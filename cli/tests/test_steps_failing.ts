@@ -0,0 +1,9 @@
+Deno.test("failing step", async (t) => {
+  await t.step("step 1", async (t) => {
+    await t.step("inner 1", () => {});
+    await t.step("inner 2", () => {
+      throw new Error("Fail.");
+    });
+  });
+  await t.step("step 2", () => {});
+});
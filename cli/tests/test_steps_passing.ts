@@ -0,0 +1,23 @@
+Deno.test("description", async (t) => {
+  const success = await t.step("step 1", async (t) => {
+    await t.step("inner 1", () => {});
+    await t.step("inner 2", () => {});
+  });
+
+  if (!success) throw new Error("Expected the step to return true.");
+});
+
+Deno.test("step options", async (t) => {
+  await t.step({
+    name: "step 1",
+    fn: async () => {
+      await new Promise((resolve) => setTimeout(resolve, 10));
+    },
+    sanitizeOps: false,
+  });
+  await t.step({
+    name: "step 2",
+    ignore: true,
+    fn: () => {},
+  });
+});
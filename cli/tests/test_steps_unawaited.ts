@@ -0,0 +1,8 @@
+Deno.test("unawaited step", (t) => {
+  t.step("step 1", () => new Promise(() => {}));
+});
+
+Deno.test("sibling steps", (t) => {
+  t.step("step 1", () => new Promise(() => {}));
+  t.step("step 2", () => {});
+});
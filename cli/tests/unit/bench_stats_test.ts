@@ -0,0 +1,34 @@
+import { assertEquals, unitTest } from "./test_util.ts";
+
+// @ts-expect-error TypeScript (as of 3.7) does not support indexing namespaces by symbol
+const { computeBenchStats, applyBenchBaselines } = Deno[Deno.internal];
+
+unitTest(function benchStats(): void {
+  const samples = [4, 1, 3, 2];
+  const stats = computeBenchStats(samples, 10);
+  assertEquals(stats, {
+    n: 40,
+    min: 1,
+    max: 4,
+    mean: 2.5,
+    p75: 3,
+    p99: 4,
+    iterPerSec: 400,
+  });
+});
+
+unitTest(function benchBaselinesPerGroup(): void {
+  const stats = (mean: number) => ({ mean });
+  const results = applyBenchBaselines([
+    { name: "a", group: null, baseline: false, stats: stats(4) },
+    { name: "b", group: null, baseline: true, stats: stats(2) },
+    { name: "c", group: "x", baseline: true, stats: stats(1) },
+    { name: "d", group: "x", baseline: false, stats: stats(3) },
+    { name: "e", group: "y", baseline: false, stats: stats(3) },
+    { name: "f", group: "x", baseline: false, stats: null },
+  ]);
+  assertEquals(
+    results.map(({ relative }: { relative: number | null }) => relative),
+    [2, 1, 1, 3, null, null],
+  );
+});
@@ -226,7 +226,7 @@ unitTest(function consoleTestStringifyCircular(): void {
   };
 
   nestedObj.o = circularObj;
-  const nestedObjExpected = `{
+  const nestedObjExpected = `<ref *1> {
   num: 1,
   bool: true,
   str: "a",
@@ -246,9 +246,9 @@ unitTest(function consoleTestStringifyCircular(): void {
     method: [Function: method],
     un: undefined,
     nu: null,
-    nested: [Circular],
+    nested: [Circular *1],
     emptyObj: {},
-    arr: [ 1, "s", false, null, [Circular] ],
+    arr: [ 1, "s", false, null, [Circular *1] ],
     baseClass: Base { a: 1 }
   }
 }`;
@@ -382,14 +382,14 @@ unitTest(function consoleTestStringifyFunctionWithProperties(): void {
   assertEquals(
     stringify({ f }),
     `{
-  f: [Function: f] {
+  f: <ref *1> [Function: f] {
     x: [Function],
     y: 3,
     z: [Function],
     b: [Function: bar],
     a: Map {},
-    s: [Circular],
-    t: [Function: t] { x: [Circular] }
+    s: [Circular *1],
+    t: [Function: t] { x: [Circular *1] }
   }
 }`,
   );
@@ -871,6 +871,25 @@ unitTest(function consoleTestWithCustomInspector(): void {
   assertEquals(stringify(new A()), "b");
 });
 
+unitTest(function consoleTestWithCustomInspectorOptions(): void {
+  class Point {
+    constructor(public x: number, public y: number) {}
+
+    [customInspect](
+      inspect: typeof Deno.inspect,
+      options: Deno.InspectOptions,
+    ): string {
+      return `Point ${inspect({ x: this.x, y: this.y }, options)}`;
+    }
+  }
+
+  assertEquals(stringify(new Point(1, 2)), "Point { x: 1, y: 2 }");
+  assertEquals(
+    stripColor(Deno.inspect({ a: { p: new Point(1, 2) } }, { depth: 2 })),
+    "{ a: { p: Point [Object] } }",
+  );
+});
+
 unitTest(function consoleTestWithCustomInspectorError(): void {
   class A {
     [customInspect](): never {
@@ -1736,6 +1755,89 @@ unitTest(function inspectProxy(): void {
   );
 });
 
+unitTest(function inspectProxyThrowingTraps(): void {
+  const handler = new Proxy({}, {
+    get(): never {
+      throw new Error("trap");
+    },
+  });
+  const proxy = new Proxy({ a: 1 }, handler);
+  assertEquals(stripColor(Deno.inspect(proxy)), "{ a: 1 }");
+  assertEquals(
+    stripColor(Deno.inspect({ nested: [proxy] })),
+    "{ nested: [ { a: 1 } ] }",
+  );
+  assertEquals(stringify(proxy), "{ a: 1 }");
+});
+
+unitTest(function inspectGettersNotEvaluated(): void {
+  let called = false;
+  const obj = {
+    get value(): number {
+      called = true;
+      return 1;
+    },
+  };
+  assertEquals(stripColor(Deno.inspect(obj)), "{ value: [Getter] }");
+  assertEquals(called, false);
+  assertEquals(
+    stripColor(Deno.inspect(obj, { getters: true })),
+    "{ value: 1 }",
+  );
+  assertEquals(called, true);
+});
+
+unitTest(function inspectCircularArrays(): void {
+  // deno-lint-ignore no-explicit-any
+  const a: any[] = [1];
+  a.push(a);
+  assertEquals(stripColor(Deno.inspect(a)), "<ref *1> [ 1, [Circular *1] ]");
+
+  // deno-lint-ignore no-explicit-any
+  const b: any[] = [];
+  // deno-lint-ignore no-explicit-any
+  const c: any[] = [b];
+  b.push(c, b);
+  assertEquals(
+    stripColor(Deno.inspect({ b })),
+    "{ b: <ref *1> [ [ [Circular *1] ], [Circular *1] ] }",
+  );
+});
+
+unitTest(function inspectDepthTruncation(): void {
+  const nested = { a: { b: { c: { d: 1 } } }, list: [[[["deep"]]]] };
+  assertEquals(
+    stripColor(Deno.inspect(nested, { depth: 1 })),
+    "{ a: [Object], list: [Array] }",
+  );
+  assertEquals(stripColor(Deno.inspect(nested, { depth: 0 })), "[Object]");
+});
+
+unitTest(function inspectStrAbbreviateSize(): void {
+  assertEquals(
+    stripColor(Deno.inspect(["abcdef"], { strAbbreviateSize: 3 })),
+    `[ "abc..." ]`,
+  );
+  assertEquals(Deno.inspect("abcdef", { strAbbreviateSize: 3 }), "abcdef");
+});
+
+unitTest(function inspectRequestResponse(): void {
+  assertEquals(
+    stripColor(Deno.inspect(new Request("https://example.com/"))),
+    `Request {
+  bodyUsed: false,
+  headers: Headers {},
+  method: "GET",
+  url: "https://example.com/",
+  credentials: "omit"
+}`,
+  );
+  assertStringIncludes(
+    stripColor(Deno.inspect(new Response(null, { status: 404 }))),
+    "status: 404",
+  );
+});
+
 unitTest(function inspectColors(): void {
   assertEquals(Deno.inspect(1), "1");
   assertStringIncludes(Deno.inspect(1, { colors: true }), "\x1b[");
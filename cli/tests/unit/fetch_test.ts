@@ -66,6 +66,41 @@ unitTest({ perms: { net: true } }, async function fetchJsonSuccess(): Promise<
   assertEquals(json.name, "deno");
 });
 
+unitTest(
+  { perms: { net: true } },
+  async function fetchAbortedSignal(): Promise<void> {
+    const controller = new AbortController();
+    controller.abort();
+    await assertThrowsAsync(
+      async (): Promise<void> => {
+        await fetch("http://localhost:4545/cli/tests/fixture.json", {
+          signal: controller.signal,
+        });
+      },
+      DOMException,
+      "Ongoing fetch was aborted.",
+    );
+  },
+);
+
+unitTest(
+  { perms: { net: true } },
+  async function fetchAbortWhilePending(): Promise<void> {
+    const controller = new AbortController();
+    const promise = fetch("http://localhost:4545/cli/tests/fixture.json", {
+      signal: controller.signal,
+    });
+    controller.abort();
+    await assertThrowsAsync(
+      async (): Promise<void> => {
+        await promise;
+      },
+      DOMException,
+      "Ongoing fetch was aborted.",
+    );
+  },
+);
+
 unitTest(async function fetchPerm(): Promise<void> {
   await assertThrowsAsync(async () => {
     await fetch("http://localhost:4545/cli/tests/fixture.json");
@@ -50,3 +50,24 @@ unitTest(function filterAsEscapedREGEX(): void {
   const filteredTests = tests.filter(filterFn);
   assertEquals(filteredTests.length, 2);
 });
+
+unitTest(function filterAsStructuredPatterns(): void {
+  const filterFn = createFilterFn({
+    include: [
+      { type: "regex", pattern: "^integration: " },
+      { type: "exact", pattern: "unit" },
+    ],
+    exclude: [{ type: "substring", pattern: "flaky" }],
+  });
+  const tests = [
+    "integration: db",
+    "integration: flaky db",
+    "unit",
+    "unit: db",
+  ].map((name) => ({ fn(): void {}, name }));
+  const filteredTests = tests.filter(filterFn);
+  assertEquals(filteredTests.map(({ name }) => name), [
+    "integration: db",
+    "unit",
+  ]);
+});
@@ -0,0 +1,94 @@
+// Copyright 2018-2021 the Deno authors. All rights reserved. MIT license.
+import {
+  assert,
+  assertEquals,
+  assertThrows,
+  assertThrowsAsync,
+  unitTest,
+} from "./test_util.ts";
+
+unitTest(
+  { perms: { read: true, write: true } },
+  function flockSyncContended(): void {
+    const filename = Deno.makeTempDirSync() + "/file.txt";
+    const file1 = Deno.openSync(filename, { create: true, write: true });
+    const file2 = Deno.openSync(filename, { write: true });
+
+    Deno.flockSync(file1.rid, true);
+    assertEquals(Deno.tryFlockSync(file2.rid, true), false);
+    assertEquals(Deno.tryFlockSync(file2.rid), false);
+
+    Deno.funlockSync(file1.rid);
+    assertEquals(Deno.tryFlockSync(file2.rid, true), true);
+    Deno.funlockSync(file2.rid);
+
+    file1.close();
+    file2.close();
+  },
+);
+
+unitTest(
+  { perms: { read: true, write: true } },
+  function flockSyncShared(): void {
+    const filename = Deno.makeTempDirSync() + "/file.txt";
+    const file1 = Deno.openSync(filename, { create: true, write: true });
+    const file2 = Deno.openSync(filename, { read: true });
+
+    Deno.flockSync(file1.rid);
+    assert(Deno.tryFlockSync(file2.rid));
+    assertEquals(Deno.tryFlockSync(file2.rid, true), false);
+
+    file1.close();
+    file2.close();
+  },
+);
+
+unitTest(
+  { perms: { read: true, write: true } },
+  async function flockWaitsForRelease(): Promise<void> {
+    const filename = (await Deno.makeTempDir()) + "/file.txt";
+    const file1 = await Deno.open(filename, { create: true, write: true });
+    const file2 = await Deno.open(filename, { write: true });
+
+    await Deno.flock(file1.rid, true);
+    assertEquals(await Deno.tryFlock(file2.rid, true), false);
+
+    let acquired = false;
+    const pending = Deno.flock(file2.rid, true).then(() => {
+      acquired = true;
+    });
+    await new Promise((resolve) => setTimeout(resolve, 100));
+    assertEquals(acquired, false);
+
+    await Deno.funlock(file1.rid);
+    await pending;
+    assert(acquired);
+    await Deno.funlock(file2.rid);
+
+    file1.close();
+    file2.close();
+  },
+);
+
+unitTest(
+  { perms: { read: true, write: true } },
+  async function funlockWithoutLock(): Promise<void> {
+    const filename = (await Deno.makeTempDir()) + "/file.txt";
+    const file = await Deno.open(filename, { create: true, write: true });
+
+    assertThrows(() => {
+      Deno.funlockSync(file.rid);
+    }, Deno.errors.BadResource);
+    await assertThrowsAsync(async () => {
+      await Deno.funlock(file.rid);
+    }, Deno.errors.BadResource);
+
+    Deno.flockSync(file.rid);
+    Deno.funlockSync(file.rid);
+    assertThrows(() => {
+      Deno.funlockSync(file.rid);
+    }, Deno.errors.BadResource);
+
+    file.close();
+  },
+);
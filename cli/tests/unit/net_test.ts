@@ -254,6 +254,84 @@ unitTest(
   },
 );
 
+unitTest(
+  { perms: { net: true } },
+  async function netTcpSetNoDelay(): Promise<void> {
+    const listener = Deno.listen({ port: 3505 });
+    const acceptPromise = listener.accept();
+    const conn = await Deno.connect({ port: 3505 });
+    const accepted = await acceptPromise;
+    // deno-lint-ignore no-explicit-any
+    const getNoDelay = (rid: number) =>
+      (Deno as any).core.jsonOpAsync("op_net_get_nodelay", { rid });
+
+    for (const c of [conn, accepted]) {
+      await c.setNoDelay(true);
+      assertEquals(await getNoDelay(c.rid), true);
+      await c.setNoDelay(false);
+      assertEquals(await getNoDelay(c.rid), false);
+    }
+
+    conn.close();
+    accepted.close();
+    listener.close();
+  },
+);
+
+unitTest(
+  { perms: { net: true } },
+  async function netTcpSetKeepAlive(): Promise<void> {
+    const listener = Deno.listen({ port: 3506 });
+    const acceptPromise = listener.accept();
+    const conn = await Deno.connect({ port: 3506 });
+    const accepted = await acceptPromise;
+
+    await conn.setKeepAlive(true, 10);
+    await conn.setKeepAlive();
+    await accepted.setKeepAlive(true, 60);
+    await accepted.setKeepAlive(false);
+
+    conn.close();
+    accepted.close();
+    listener.close();
+  },
+);
+
+unitTest(
+  { perms: { net: true } },
+  async function netTcpSocketOptionsBadResource(): Promise<void> {
+    const listener = Deno.listen({ port: 3507 });
+    const acceptPromise = listener.accept();
+    const conn = await Deno.connect({ port: 3507 });
+    const accepted = await acceptPromise;
+    conn.close();
+    await assertThrowsAsync(
+      () => conn.setNoDelay(true),
+      Deno.errors.BadResource,
+    );
+    await assertThrowsAsync(
+      () => conn.setKeepAlive(true),
+      Deno.errors.BadResource,
+    );
+    accepted.close();
+    listener.close();
+  },
+);
+
+unitTest(
+  { perms: { net: true } },
+  async function netTcpListenBacklog(): Promise<void> {
+    const listener = Deno.listen({ port: 3508, backlog: 1 });
+    const acceptPromise = listener.accept();
+    const conn = await Deno.connect({ port: 3508 });
+    const accepted = await acceptPromise;
+    assertEquals(accepted.localAddr.port, 3508);
+    conn.close();
+    accepted.close();
+    listener.close();
+  },
+);
+
 unitTest(
   { perms: { net: true } },
   async function netUdpSendReceive(): Promise<void> {
@@ -323,6 +401,99 @@ unitTest(
   },
 );
 
+unitTest(
+  { perms: { net: true } },
+  async function netUdpMulticastV4(): Promise<void> {
+    const group = "224.0.0.251";
+    const alice = Deno.listenDatagram({
+      hostname: "0.0.0.0",
+      port: 5353,
+      transport: "udp",
+    });
+    const bob = Deno.listenDatagram({
+      hostname: "0.0.0.0",
+      port: 5354,
+      transport: "udp",
+    });
+
+    const membership = await alice.joinMulticastV4(group, "127.0.0.1");
+    await membership.setLoopback(true);
+    await membership.setTTL(1);
+
+    const recvPromise = alice.receive();
+    await bob.send(new Uint8Array([1, 2, 3]), {
+      hostname: group,
+      port: 5353,
+      transport: "udp",
+    });
+    const [recvd, remote] = await recvPromise;
+    assertEquals(recvd, new Uint8Array([1, 2, 3]));
+    assertEquals(remote.port, 5354);
+
+    await membership.leave();
+    alice.close();
+    bob.close();
+  },
+);
+
+unitTest(
+  { perms: { net: true } },
+  async function netUdpMulticastInvalidGroup(): Promise<void> {
+    const socket = Deno.listenDatagram({ port: 4502, transport: "udp" });
+    await assertThrowsAsync(
+      () => socket.joinMulticastV4("127.0.0.1", "0.0.0.0"),
+      TypeError,
+      "not a multicast group address",
+    );
+    await assertThrowsAsync(
+      () => socket.joinMulticastV4("not an address", "0.0.0.0"),
+      TypeError,
+      "Invalid IP address",
+    );
+    socket.close();
+  },
+);
+
+unitTest(
+  { perms: { net: true } },
+  async function netUdpMulticastOnTcpRid(): Promise<void> {
+    const listener = Deno.listen({ port: 4503 });
+    await assertThrowsAsync(
+      () =>
+        // deno-lint-ignore no-explicit-any
+        (Deno as any).core.jsonOpAsync("op_net_join_multi_v4", {
+          rid: listener.rid,
+          address: "224.0.0.251",
+          multiInterface: "0.0.0.0",
+        }),
+      Deno.errors.BadResource,
+    );
+    listener.close();
+  },
+);
+
+unitTest(
+  { perms: { net: true } },
+  async function netUdpBroadcast(): Promise<void> {
+    const socket = Deno.listenDatagram({
+      hostname: "0.0.0.0",
+      port: 4504,
+      transport: "udp",
+    });
+    const addr: Deno.NetAddr = {
+      hostname: "255.255.255.255",
+      port: 4505,
+      transport: "udp",
+    };
+    await assertThrowsAsync(
+      () => socket.send(new Uint8Array([1]), addr),
+    );
+    await socket.setBroadcast(true);
+    assertEquals(await socket.send(new Uint8Array([1]), addr), 1);
+    socket.close();
+  },
+);
+
 unitTest(
   { ignore: Deno.build.os === "windows", perms: { read: true, write: true } },
   async function netUnixPacketSendReceive(): Promise<void> {
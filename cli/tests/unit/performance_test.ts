@@ -98,3 +98,82 @@ unitTest(function performanceMeasureIllegalConstructor() {
     "Illegal constructor.",
   );
 });
+
+unitTest(function performanceMarkDetail() {
+  const detail = { foo: "bar", date: new Date(0) };
+  const mark = performance.mark("detailMark", { detail });
+  assert(mark.detail !== detail);
+  assertEquals(mark.detail, detail);
+  assert(mark.detail.date instanceof Date);
+  const entries = performance.getEntriesByName("detailMark", "mark");
+  assert(entries[entries.length - 1] === mark);
+  performance.clearMarks("detailMark");
+  assertEquals(performance.getEntriesByName("detailMark"), []);
+});
+
+unitTest(function performanceMeasureOptions() {
+  performance.mark("optionsStart", { startTime: 10 });
+  performance.mark("optionsEnd", { startTime: 25 });
+  const measure = performance.measure("optionsMeasure", {
+    start: "optionsStart",
+    end: "optionsEnd",
+    detail: [1, 2, 3],
+  });
+  assertEquals(measure.startTime, 10);
+  assertEquals(measure.duration, 15);
+  assertEquals(measure.detail, [1, 2, 3]);
+  const fromDuration = performance.measure("optionsMeasure", {
+    end: "optionsEnd",
+    duration: 5,
+  });
+  assertEquals(fromDuration.startTime, 20);
+  assertEquals(fromDuration.duration, 5);
+  const measures = performance.getEntriesByType("measure");
+  assert(measures.includes(measure));
+  assert(measures.every((entry) => entry instanceof PerformanceMeasure));
+  assert(
+    performance.getEntriesByType("mark").every((entry) =>
+      entry instanceof PerformanceMark
+    ),
+  );
+  performance.clearMarks("optionsStart");
+  performance.clearMarks("optionsEnd");
+  performance.clearMeasures("optionsMeasure");
+});
+
+unitTest(function performanceMeasureErrors() {
+  assertThrows(
+    () => performance.measure("missing", "noSuchMark"),
+    DOMException,
+    'Cannot find mark: "noSuchMark".',
+  );
+  performance.mark("errorsStart");
+  assertThrows(
+    () =>
+      performance.measure("negative", { start: "errorsStart", duration: -1 }),
+    TypeError,
+    "Duration cannot be negative.",
+  );
+  assertThrows(
+    () => performance.measure("negative", { start: -1 }),
+    TypeError,
+    "Mark cannot be negative.",
+  );
+  performance.clearMarks("errorsStart");
+});
+
+unitTest(function performanceSetResourceTimingBufferSize() {
+  performance.clearMarks();
+  performance.clearMeasures();
+  const marks = [1, 2, 3].map((i) => performance.mark(`bufferMark${i}`));
+  performance.setResourceTimingBufferSize(2);
+  let entries = performance.getEntries();
+  assertEquals(entries.length, 2);
+  assert(entries[0] === marks[1] && entries[1] === marks[2]);
+  const mark4 = performance.mark("bufferMark4");
+  entries = performance.getEntries();
+  assertEquals(entries.length, 2);
+  assert(entries[0] === marks[2] && entries[1] === mark4);
+  performance.setResourceTimingBufferSize(0xffffffff);
+  performance.clearMarks();
+});
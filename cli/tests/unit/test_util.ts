@@ -193,6 +193,11 @@ export function unitTest(
 
 const encoder = new TextEncoder();
 
+// deno-lint-ignore no-explicit-any
+function serializeSteps(steps: any[] | undefined) {
+  return steps?.map((step) => ({ ...step, error: step.error?.stack }));
+}
+
 // Replace functions with null, errors with their stack strings, and JSONify.
 // deno-lint-ignore no-explicit-any
 function serializeTestMessage(message: any): string {
@@ -205,9 +210,15 @@ function serializeTestMessage(message: any): string {
       })),
     },
     testStart: message.testStart && { ...message.testStart, fn: null },
+    testStepStart: message.testStepStart,
+    testStepEnd: message.testStepEnd && {
+      ...message.testStepEnd,
+      error: message.testStepEnd.error?.stack,
+    },
     testEnd: message.testEnd && {
       ...message.testEnd,
       error: String(message.testEnd.error?.stack),
+      steps: serializeSteps(message.testEnd.steps),
     },
     end: message.end && {
       ...message.end,
@@ -215,6 +226,7 @@ function serializeTestMessage(message: any): string {
       results: message.end.results.map((result: any) => ({
         ...result,
         error: result.error?.stack,
+        steps: serializeSteps(result.steps),
       })),
     },
   });
@@ -394,3 +394,92 @@ unitTest(
     conn.close();
   },
 );
+
+/** Accepts a plaintext connection on `port`, sends a greeting, and then
+ * forwards the raw bytes to a TLS echo server, so that the client can upgrade
+ * the connection with `Deno.startTls()`. */
+function startTlsTestServer(port: number): Promise<void> {
+  const tlsListener = Deno.listenTls({
+    hostname: "localhost",
+    port: port + 1,
+    certFile: "cli/tests/tls/localhost.crt",
+    keyFile: "cli/tests/tls/localhost.key",
+  });
+  const listener = Deno.listen({ hostname: "localhost", port });
+
+  const echo = (async (): Promise<void> => {
+    try {
+      const conn = await tlsListener.accept();
+      const buf = new Uint8Array(1024);
+      const n = await conn.read(buf);
+      if (n !== null) {
+        await conn.write(buf.subarray(0, n));
+      }
+      conn.close();
+    } catch {
+      // The handshake is expected to fail in some tests.
+    }
+    tlsListener.close();
+  })();
+
+  const front = (async (): Promise<void> => {
+    const conn = await listener.accept();
+    await conn.write(encoder.encode("READY"));
+    const backend = await Deno.connect({
+      hostname: "localhost",
+      port: port + 1,
+    });
+    await Promise.race([
+      Deno.copy(conn, backend).catch(() => {}),
+      Deno.copy(backend, conn).catch(() => {}),
+    ]);
+    conn.close();
+    backend.close();
+    listener.close();
+  })();
+
+  return Promise.all([echo, front]).then(() => {});
+}
+
+unitTest(
+  { perms: { read: true, net: true } },
+  async function startTlsLocalUpgrade(): Promise<void> {
+    const server = startTlsTestServer(3590);
+    const conn = await Deno.connect({ hostname: "localhost", port: 3590 });
+    const greeting = new Uint8Array(5);
+    assertEquals(await conn.read(greeting), 5);
+    assertEquals(decoder.decode(greeting), "READY");
+
+    const caCert = await Deno.readTextFile("cli/tests/tls/RootCA.pem");
+    const tlsConn = await Deno.startTls(conn, {
+      hostname: "localhost",
+      caCerts: [caCert],
+    });
+    assertEquals(tlsConn.rid, conn.rid);
+
+    await tlsConn.write(encoder.encode("hello"));
+    const buf = new Uint8Array(5);
+    assertEquals(await tlsConn.read(buf), 5);
+    assertEquals(decoder.decode(buf), "hello");
+    tlsConn.close();
+    await server;
+  },
+);
+
+unitTest(
+  { perms: { read: true, net: true } },
+  async function startTlsHostnameMismatch(): Promise<void> {
+    const server = startTlsTestServer(3592);
+    const conn = await Deno.connect({ hostname: "localhost", port: 3592 });
+    const greeting = new Uint8Array(5);
+    assertEquals(await conn.read(greeting), 5);
+
+    const caCert = await Deno.readTextFile("cli/tests/tls/RootCA.pem");
+    await assertThrowsAsync(
+      () => Deno.startTls(conn, { hostname: "deno.land", caCerts: [caCert] }),
+      Deno.errors.InvalidData,
+      "deno.land",
+    );
+    await server;
+  },
+);
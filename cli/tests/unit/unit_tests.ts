@@ -5,6 +5,7 @@
 // Test runner automatically spawns subprocesses for each required permissions combination.
 
 import "./abort_controller_test.ts";
+import "./bench_stats_test.ts";
 import "./blob_test.ts";
 import "./body_test.ts";
 import "./buffer_test.ts";
@@ -25,6 +26,7 @@ import "./file_test.ts";
 import "./filereader_test.ts";
 import "./files_test.ts";
 import "./filter_function_test.ts";
+import "./flock_test.ts";
 import "./form_data_test.ts";
 import "./format_error_test.ts";
 import "./fs_events_test.ts";
@@ -69,6 +69,25 @@ unitTest(
   },
 );
 
+unitTest(
+  { ignore: Deno.build.os !== "linux", perms: { read: true, write: true } },
+  function utimeSyncFractionalSeconds(): void {
+    const testDir = Deno.makeTempDirSync();
+    const filename = testDir + "/file.txt";
+    Deno.writeFileSync(filename, new TextEncoder().encode("hello"), {
+      mode: 0o666,
+    });
+
+    const atime = 1000.25;
+    const mtime = new Date(50000125);
+    Deno.utimeSync(filename, atime, mtime);
+
+    const fileInfo = Deno.statSync(filename);
+    assertEquals(fileInfo.atime, new Date(1000250));
+    assertEquals(fileInfo.mtime, mtime);
+  },
+);
+
 unitTest(
   { perms: { read: true, write: true } },
   function utimeSyncDirectorySuccess(): void {
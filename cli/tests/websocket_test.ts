@@ -3,9 +3,16 @@ import {
   assert,
   assertEquals,
   assertThrows,
+  assertThrowsAsync,
   fail,
 } from "../../test_util/std/testing/asserts.ts";
 import { deferred } from "../../test_util/std/async/deferred.ts";
+import { delay } from "../../test_util/std/async/delay.ts";
+import { serve } from "../../test_util/std/http/server.ts";
+import {
+  acceptWebSocket,
+  WebSocket as StdWebSocket,
+} from "../../test_util/std/ws/mod.ts";
 
 Deno.test("invalid scheme", () => {
   assertThrows(() => new WebSocket("foo://localhost:4242"));
@@ -295,3 +302,108 @@ Deno.test("Event Handlers order", async () => {
   };
   await promise;
 });
+
+Deno.test("WebSocketStream echo & close", async () => {
+  const wss = new WebSocketStream("ws://localhost:4242");
+  const { readable, writable } = await wss.connection;
+  const writer = writable.getWriter();
+  const reader = readable.getReader();
+  await writer.write("foo");
+  assertEquals((await reader.read()).value, "foo");
+  await writer.write(new Uint8Array([1, 2, 3]));
+  assertEquals((await reader.read()).value, new Uint8Array([1, 2, 3]));
+  wss.close({ code: 1000, reason: "done" });
+  assertEquals(await wss.closed, { code: 1000, reason: "" });
+  assert((await reader.read()).done);
+});
+
+Deno.test("WebSocketStream connect & abort", async () => {
+  const controller = new AbortController();
+  const wss = new WebSocketStream("ws://localhost:4242", {
+    signal: controller.signal,
+  });
+  controller.abort();
+  await assertThrowsAsync(
+    () => wss.connection,
+    DOMException,
+    "The WebSocket connection was aborted.",
+  );
+  await assertThrowsAsync(() => wss.closed, DOMException);
+});
+
+Deno.test("WebSocketStream connect to invalid server", async () => {
+  const wss = new WebSocketStream("ws://localhost:2121");
+  await assertThrowsAsync(() => wss.connection, TypeError);
+});
+
+// Serves a single WebSocket connection on `port` with `handler`.
+async function serveWebSocketOnce(
+  port: number,
+  handler: (sock: StdWebSocket) => Promise<void>,
+): Promise<void> {
+  const server = serve({ port });
+  for await (const req of server) {
+    const sock = await acceptWebSocket({
+      conn: req.conn,
+      bufReader: req.r,
+      bufWriter: req.w,
+      headers: req.headers,
+    });
+    await handler(sock);
+    break;
+  }
+  server.close();
+}
+
+Deno.test("WebSocketStream server close with code and reason", async () => {
+  const served = serveWebSocketOnce(4253, async (sock) => {
+    await sock.send("bye");
+    await sock.close(3001, "server done");
+  });
+  const wss = new WebSocketStream("ws://localhost:4253");
+  const { readable } = await wss.connection;
+  const reader = readable.getReader();
+  assertEquals((await reader.read()).value, "bye");
+  assert((await reader.read()).done);
+  assertEquals(await wss.closed, { code: 3001, reason: "server done" });
+  await served;
+});
+
+Deno.test("WebSocketStream backpressure paces the server", async () => {
+  const payload = "x".repeat(16 * 1024);
+  let sent = 0;
+  const served = serveWebSocketOnce(4254, async (sock) => {
+    // Answers the client's close frame, which ends the send loop below.
+    const receiving = (async () => {
+      for await (const _ of sock) {
+        // Discard.
+      }
+    })();
+    try {
+      for (; sent < 10000; sent++) {
+        await sock.send(payload);
+      }
+    } catch {
+      // The connection was closed by the client.
+    }
+    await receiving;
+  });
+
+  const wss = new WebSocketStream("ws://localhost:4254");
+  const { readable } = await wss.connection;
+  const reader = readable.getReader();
+  let received = 0;
+  for (; received < 100; received++) {
+    const { value } = await reader.read();
+    assertEquals(value, payload);
+    await delay(5);
+  }
+  // Without backpressure the server would have sent all 10k messages by now;
+  // with it, it can only be as far ahead as the socket buffers allow.
+  assert(sent - received < 1000, `${sent - received} messages in flight`);
+  reader.releaseLock();
+  wss.close({ code: 1000 });
+  await wss.closed;
+  await served;
+  assert(sent < 10000);
+});
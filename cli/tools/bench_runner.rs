@@ -0,0 +1,92 @@
+// Copyright 2018-2021 the Deno authors. All rights reserved. MIT license.
+
+use crate::tools::test_runner::collect_module_urls;
+use crate::tools::test_runner::TestFilter;
+use deno_core::error::AnyError;
+use deno_core::serde_json::json;
+use deno_core::url::Url;
+use std::path::Path;
+use std::path::PathBuf;
+
+fn is_supported(p: &Path) -> bool {
+  use std::path::Component;
+  if let Some(Component::Normal(basename_os_str)) = p.components().next_back() {
+    let basename = basename_os_str.to_string_lossy();
+    ["ts", "tsx", "js", "mjs", "jsx"].iter().any(|ext| {
+      basename.ends_with(&format!("_bench.{}", ext))
+        || basename.ends_with(&format!(".bench.{}", ext))
+        || basename == format!("bench.{}", ext)
+    })
+  } else {
+    false
+  }
+}
+
+pub fn prepare_bench_modules_urls(
+  include: Vec<String>,
+  root_path: &PathBuf,
+) -> Result<Vec<Url>, AnyError> {
+  collect_module_urls(include, root_path, is_supported)
+}
+
+pub fn render_bench_file(
+  modules: Vec<Url>,
+  filter: &TestFilter,
+  json: bool,
+) -> String {
+  let mut bench_file = "".to_string();
+
+  for module in modules {
+    bench_file.push_str(&format!("import \"{}\";\n", module.to_string()));
+  }
+
+  let mut options = json!({ "json": json });
+  if !filter.is_empty() {
+    options["filter"] = json!(filter);
+  }
+
+  bench_file.push_str("// @ts-ignore\n");
+
+  bench_file.push_str(&format!(
+    "await Deno[Deno.internal].runBenches({});\n",
+    options
+  ));
+
+  bench_file
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_is_supported() {
+    assert!(is_supported(Path::new("benches/foo_bench.ts")));
+    assert!(is_supported(Path::new("benches/foo_bench.mjs")));
+    assert!(is_supported(Path::new("bar/foo.bench.tsx")));
+    assert!(is_supported(Path::new("bar/foo.bench.js")));
+    assert!(is_supported(Path::new("foo/bar/bench.jsx")));
+    assert!(is_supported(Path::new("foo/bar/bench.ts")));
+    assert!(!is_supported(Path::new("README.md")));
+    assert!(!is_supported(Path::new("foo_test.ts")));
+    assert!(!is_supported(Path::new("notabench.js")));
+    assert!(!is_supported(Path::new("bench.d.ts")));
+  }
+
+  #[test]
+  fn test_render_bench_file() {
+    let filter = TestFilter::parse(Some("/^parse/"), &[]).unwrap();
+    let source = render_bench_file(
+      vec![Url::parse("file:///a_bench.ts").unwrap()],
+      &filter,
+      true,
+    );
+    assert_eq!(
+      source,
+      "import \"file:///a_bench.ts\";
+// @ts-ignore
+await Deno[Deno.internal].runBenches({\"json\":true,\"filter\":{\"include\":[{\"type\":\"regex\",\"pattern\":\"^parse\"}],\"exclude\":[]}});
+"
+    );
+  }
+}
@@ -117,182 +117,542 @@ pub struct GetScriptSourceResult {
   pub bytecode: Option<String>,
 }
 
-pub struct PrettyCoverageReporter {
-  quiet: bool,
+/// A named function of a script.
+pub struct FunctionCoverageItem {
+  pub name: String,
+  pub line_index: usize,
+  pub execution_count: usize,
 }
 
-// TODO(caspervonb) add support for lcov output (see geninfo(1) for format spec).
-impl PrettyCoverageReporter {
-  pub fn new(quiet: bool) -> PrettyCoverageReporter {
-    PrettyCoverageReporter { quiet }
+/// A block that V8 counted separately from the rest of its function, which is
+/// what lcov calls a branch.
+pub struct BranchCoverageItem {
+  pub line_index: usize,
+  /// Index of the function the block belongs to.
+  pub block_number: usize,
+  /// Index of the block within its function.
+  pub branch_number: usize,
+  /// How often the block ran, or `None` if its function never did.
+  pub taken: Option<usize>,
+}
+
+/// The coverage of a single script. Line indexes refer to the original source
+/// if the script has a source map, e.g. to TypeScript rather than the emitted
+/// JavaScript.
+pub struct CoverageReport {
+  pub url: String,
+  /// The lines of the (original) source.
+  pub lines: Vec<String>,
+  /// The index and hit count of every line that holds code.
+  pub found_lines: Vec<(usize, usize)>,
+  pub named_functions: Vec<FunctionCoverageItem>,
+  pub branches: Vec<BranchCoverageItem>,
+}
+
+impl CoverageReport {
+  fn lines_hit(&self) -> usize {
+    self
+      .found_lines
+      .iter()
+      .filter(|(_, count)| *count > 0)
+      .count()
   }
 
-  pub fn visit_coverage(
-    &mut self,
-    script_coverage: &ScriptCoverage,
-    script_source: &str,
-    maybe_source_map: Option<Vec<u8>>,
-    maybe_original_source: Option<String>,
-  ) {
-    let maybe_source_map = if let Some(source_map) = maybe_source_map {
-      Some(SourceMap::from_slice(&source_map).unwrap())
-    } else {
-      None
-    };
+  fn functions_hit(&self) -> usize {
+    self
+      .named_functions
+      .iter()
+      .filter(|f| f.execution_count > 0)
+      .count()
+  }
 
-    let mut ignored_spans: Vec<Span> = Vec::new();
-    for item in ast::lex("", script_source, &MediaType::JavaScript) {
-      if let TokenOrComment::Token(_) = item.inner {
-        continue;
-      }
+  fn branches_hit(&self) -> usize {
+    self
+      .branches
+      .iter()
+      .filter(|b| b.taken.map_or(false, |count| count > 0))
+      .count()
+  }
+}
 
-      ignored_spans.push(item.span);
+/// Formats `hit` out of `found` as a percentage, counting nothing found as
+/// fully covered.
+fn format_ratio(hit: usize, found: usize) -> String {
+  let ratio = if found == 0 {
+    1.0
+  } else {
+    hit as f32 / found as f32
+  };
+  format!("{:.3}% ({}/{})", ratio * 100.0, hit, found)
+}
+
+pub fn generate_coverage_report(
+  script_coverage: &ScriptCoverage,
+  script_source: &str,
+  maybe_source_map: Option<Vec<u8>>,
+  maybe_original_source: Option<String>,
+) -> CoverageReport {
+  let maybe_source_map = if let Some(source_map) = maybe_source_map {
+    Some(SourceMap::from_slice(&source_map).unwrap())
+  } else {
+    None
+  };
+
+  let mut ignored_spans: Vec<Span> = Vec::new();
+  for item in ast::lex("", script_source, &MediaType::JavaScript) {
+    if let TokenOrComment::Token(_) = item.inner {
+      continue;
     }
 
-    let lines = script_source.split('\n').collect::<Vec<_>>();
+    ignored_spans.push(item.span);
+  }
 
-    let line_offsets = {
-      let mut offsets: Vec<(usize, usize)> = Vec::new();
-      let mut index = 0;
+  let lines = script_source.split('\n').collect::<Vec<_>>();
 
-      for line in &lines {
-        offsets.push((index, index + line.len() + 1));
-        index += line.len() + 1;
-      }
+  let line_offsets = {
+    let mut offsets: Vec<(usize, usize)> = Vec::new();
+    let mut index = 0;
+
+    for line in &lines {
+      offsets.push((index, index + line.len() + 1));
+      index += line.len() + 1;
+    }
 
-      offsets
-    };
+    offsets
+  };
 
-    let line_counts = line_offsets
+  // Maps an offset into the script to the index of the line in the original
+  // source it came from.
+  let original_line_index = |offset: usize| -> usize {
+    let line_index = line_offsets
       .iter()
-      .enumerate()
-      .map(|(index, (line_start_offset, line_end_offset))| {
-        let ignore = ignored_spans.iter().any(|span| {
-          (span.lo.0 as usize) <= *line_start_offset
-            && (span.hi.0 as usize) >= *line_end_offset
-        });
-
-        if ignore {
-          return (index, 1);
-        }
+      .position(|(_, line_end_offset)| offset < *line_end_offset)
+      .unwrap_or(line_offsets.len() - 1);
+    if let Some(source_map) = maybe_source_map.as_ref() {
+      let column = offset - line_offsets[line_index].0;
+      source_map
+        .lookup_token(line_index as u32, column as u32)
+        .map_or(line_index, |token| token.get_src_line() as usize)
+    } else {
+      line_index
+    }
+  };
+
+  let named_functions = script_coverage
+    .functions
+    .iter()
+    .filter(|function| !function.function_name.is_empty())
+    .filter_map(|function| {
+      let range = function.ranges.first()?;
+      Some(FunctionCoverageItem {
+        name: function.function_name.clone(),
+        line_index: original_line_index(range.start_offset),
+        execution_count: range.count,
+      })
+    })
+    .collect();
+
+  // The first range of a function covers all of it, the others are the blocks
+  // whose count differs from that of the block enclosing them.
+  let mut branches = Vec::new();
+  for (block_number, function) in script_coverage.functions.iter().enumerate() {
+    let block_hits = function.ranges.first().map_or(0, |range| range.count);
+    for (branch_number, range) in function.ranges.iter().skip(1).enumerate() {
+      branches.push(BranchCoverageItem {
+        line_index: original_line_index(range.start_offset),
+        block_number,
+        branch_number,
+        taken: if block_hits > 0 {
+          Some(range.count)
+        } else {
+          None
+        },
+      });
+    }
+  }
 
-        let mut count = 0;
+  let line_counts = line_offsets
+    .iter()
+    .enumerate()
+    .map(|(index, (line_start_offset, line_end_offset))| {
+      let ignore = ignored_spans.iter().any(|span| {
+        (span.lo.0 as usize) <= *line_start_offset
+          && (span.hi.0 as usize) >= *line_end_offset
+      });
+
+      if ignore {
+        return (index, 1);
+      }
 
-        // Count the hits of ranges that include the entire line which will always be at-least one
-        // as long as the code has been evaluated.
-        for function in &script_coverage.functions {
-          for range in &function.ranges {
-            if range.start_offset <= *line_start_offset
-              && range.end_offset >= *line_end_offset
-            {
-              count += range.count;
-            }
+      let mut count = 0;
+
+      // Count the hits of ranges that include the entire line which will always be at-least one
+      // as long as the code has been evaluated.
+      for function in &script_coverage.functions {
+        for range in &function.ranges {
+          if range.start_offset <= *line_start_offset
+            && range.end_offset >= *line_end_offset
+          {
+            count += range.count;
           }
         }
+      }
 
-        // Reset the count if any block intersects with the current line has a count of
-        // zero.
-        //
-        // We check for intersection instead of inclusion here because a block may be anywhere
-        // inside a line.
-        for function in &script_coverage.functions {
-          for range in &function.ranges {
-            if range.count > 0 {
-              continue;
-            }
+      // Reset the count if any block intersects with the current line has a count of
+      // zero.
+      //
+      // We check for intersection instead of inclusion here because a block may be anywhere
+      // inside a line.
+      for function in &script_coverage.functions {
+        for range in &function.ranges {
+          if range.count > 0 {
+            continue;
+          }
 
-            if (range.start_offset < *line_start_offset
-              && range.end_offset > *line_start_offset)
-              || (range.start_offset < *line_end_offset
-                && range.end_offset > *line_end_offset)
-            {
-              count = 0;
-            }
+          if (range.start_offset < *line_start_offset
+            && range.end_offset > *line_start_offset)
+            || (range.start_offset < *line_end_offset
+              && range.end_offset > *line_end_offset)
+          {
+            count = 0;
           }
         }
+      }
+
+      (index, count)
+    })
+    .collect::<Vec<(usize, usize)>>();
 
-        (index, count)
+  let lines = if let Some(original_source) = maybe_original_source.as_ref() {
+    original_source.split('\n').collect::<Vec<_>>()
+  } else {
+    lines
+  };
+
+  let found_lines = if let Some(source_map) = maybe_source_map.as_ref() {
+    let mut found_lines = line_counts
+      .iter()
+      .map(|(index, count)| {
+        source_map
+          .tokens()
+          .filter(move |token| token.get_dst_line() as usize == *index)
+          .map(move |token| (token.get_src_line() as usize, *count))
       })
+      .flatten()
       .collect::<Vec<(usize, usize)>>();
 
-    let lines = if let Some(original_source) = maybe_original_source.as_ref() {
-      original_source.split('\n').collect::<Vec<_>>()
-    } else {
-      lines
-    };
+    found_lines.sort_unstable_by_key(|(index, _)| *index);
+    found_lines.dedup_by_key(|(index, _)| *index);
 
-    let line_counts = if let Some(source_map) = maybe_source_map.as_ref() {
-      let mut line_counts = line_counts
-        .iter()
-        .map(|(index, count)| {
-          source_map
-            .tokens()
-            .filter(move |token| token.get_dst_line() as usize == *index)
-            .map(move |token| (token.get_src_line() as usize, *count))
-        })
-        .flatten()
-        .collect::<Vec<(usize, usize)>>();
-
-      line_counts.sort_unstable_by_key(|(index, _)| *index);
-      line_counts.dedup_by_key(|(index, _)| *index);
-
-      line_counts
+    found_lines
+  } else {
+    line_counts
+  };
+
+  CoverageReport {
+    url: script_coverage.url.clone(),
+    lines: lines.into_iter().map(String::from).collect(),
+    found_lines,
+    named_functions,
+    branches,
+  }
+}
+
+pub trait CoverageReporter {
+  fn visit_report(&mut self, report: &CoverageReport) -> Result<(), AnyError>;
+
+  /// Called once all scripts have been visited.
+  fn done(&mut self) -> Result<(), AnyError> {
+    Ok(())
+  }
+}
+
+pub struct PrettyCoverageReporter {
+  quiet: bool,
+}
+
+impl PrettyCoverageReporter {
+  pub fn new(quiet: bool) -> PrettyCoverageReporter {
+    PrettyCoverageReporter { quiet }
+  }
+}
+
+impl CoverageReporter for PrettyCoverageReporter {
+  fn visit_report(&mut self, report: &CoverageReport) -> Result<(), AnyError> {
+    if self.quiet {
+      return Ok(());
+    }
+
+    print!("cover {} ... ", report.url);
+
+    let lines_found = report.found_lines.len();
+    let lines_hit = report.lines_hit();
+    let line_coverage = format_ratio(lines_hit, lines_found);
+
+    if lines_found == 0 || lines_hit as f32 / lines_found as f32 >= 0.9 {
+      println!("{}", colors::green(&line_coverage));
+    } else if lines_hit as f32 / lines_found as f32 >= 0.75 {
+      println!("{}", colors::yellow(&line_coverage));
     } else {
-      line_counts
-    };
+      println!("{}", colors::red(&line_coverage));
+    }
 
-    if !self.quiet {
-      print!("cover {} ... ", script_coverage.url);
+    let missed_lines = report
+      .found_lines
+      .iter()
+      .filter(|(_, count)| *count == 0)
+      .map(|(index, _)| *index);
+
+    let mut last_line = None;
+    for line_index in missed_lines {
+      const WIDTH: usize = 4;
+      const SEPERATOR: &str = "|";
+
+      // Put a horizontal separator between disjoint runs of lines
+      if let Some(last_line) = last_line {
+        if last_line + 1 != line_index {
+          let dash = colors::gray(&"-".repeat(WIDTH + 1));
+          println!("{}{}{}", dash, colors::gray(SEPERATOR), dash);
+        }
+      }
 
-      let hit_lines = line_counts
-        .iter()
-        .filter(|(_, count)| *count != 0)
-        .map(|(index, _)| *index);
+      println!(
+        "{:width$} {} {}",
+        line_index + 1,
+        colors::gray(SEPERATOR),
+        colors::red(&report.lines[line_index]),
+        width = WIDTH
+      );
+
+      last_line = Some(line_index);
+    }
+
+    Ok(())
+  }
+}
+
+/// Writes the coverage of all scripts to a single lcov tracefile, see
+/// geninfo(1) for the format.
+pub struct LcovCoverageReporter {
+  path: PathBuf,
+  output: String,
+}
+
+impl LcovCoverageReporter {
+  pub fn new(path: PathBuf) -> LcovCoverageReporter {
+    LcovCoverageReporter {
+      path,
+      output: String::new(),
+    }
+  }
+}
+
+impl CoverageReporter for LcovCoverageReporter {
+  fn visit_report(&mut self, report: &CoverageReport) -> Result<(), AnyError> {
+    let source_file = Url::parse(&report.url)
+      .ok()
+      .and_then(|url| url.to_file_path().ok())
+      .map_or(report.url.clone(), |path| {
+        path.to_string_lossy().to_string()
+      });
+    let out = &mut self.output;
+    out.push_str(&format!("SF:{}\n", source_file));
+
+    for function in &report.named_functions {
+      out.push_str(&format!(
+        "FN:{},{}\n",
+        function.line_index + 1,
+        function.name
+      ));
+    }
+    for function in &report.named_functions {
+      out.push_str(&format!(
+        "FNDA:{},{}\n",
+        function.execution_count, function.name
+      ));
+    }
+    out.push_str(&format!("FNF:{}\n", report.named_functions.len()));
+    out.push_str(&format!("FNH:{}\n", report.functions_hit()));
+
+    for branch in &report.branches {
+      let taken = branch
+        .taken
+        .map_or_else(|| "-".to_string(), |count| count.to_string());
+      out.push_str(&format!(
+        "BRDA:{},{},{},{}\n",
+        branch.line_index + 1,
+        branch.block_number,
+        branch.branch_number,
+        taken
+      ));
+    }
+    out.push_str(&format!("BRF:{}\n", report.branches.len()));
+    out.push_str(&format!("BRH:{}\n", report.branches_hit()));
 
-      let missed_lines = line_counts
+    for (index, count) in &report.found_lines {
+      out.push_str(&format!("DA:{},{}\n", index + 1, count));
+    }
+    out.push_str(&format!("LF:{}\n", report.found_lines.len()));
+    out.push_str(&format!("LH:{}\n", report.lines_hit()));
+    out.push_str("end_of_record\n");
+
+    Ok(())
+  }
+
+  fn done(&mut self) -> Result<(), AnyError> {
+    if let Some(parent) = self.path.parent() {
+      fs::create_dir_all(parent)?;
+    }
+    fs::write(&self.path, &self.output)?;
+    Ok(())
+  }
+}
+
+const HTML_STYLE: &str = "body { font-family: sans-serif; }
+table { border-collapse: collapse; }
+td, th { padding: 0 8px; text-align: left; }
+pre { margin: 0; }
+.source td.line, .source td.count { color: #888; text-align: right; }
+.source tr.hit { background: #e6ffec; }
+.source tr.miss { background: #ffebe9; }";
+
+fn escape_html(s: &str) -> String {
+  s.replace('&', "&amp;")
+    .replace('<', "&lt;")
+    .replace('>', "&gt;")
+    .replace('"', "&quot;")
+}
+
+/// Writes a static HTML report: one page per script showing its source with
+/// hit and missed lines, and an `index.html` summarizing all of them.
+pub struct HtmlCoverageReporter {
+  dir: PathBuf,
+  /// The rows of the index: the file name of each script's page, its URL and
+  /// its line, function and branch coverage.
+  summaries: Vec<(String, String, [String; 3])>,
+}
+
+impl HtmlCoverageReporter {
+  pub fn new(dir: PathBuf) -> HtmlCoverageReporter {
+    HtmlCoverageReporter {
+      dir,
+      summaries: Vec::new(),
+    }
+  }
+
+  fn page_name(url: &str) -> String {
+    let name = url
+      .trim_start_matches("file://")
+      .trim_start_matches('/')
+      .replace(
+        |c: char| !c.is_ascii_alphanumeric() && c != '.' && c != '-',
+        "_",
+      );
+    format!("{}.html", name)
+  }
+}
+
+impl CoverageReporter for HtmlCoverageReporter {
+  fn visit_report(&mut self, report: &CoverageReport) -> Result<(), AnyError> {
+    let summary = [
+      format_ratio(report.lines_hit(), report.found_lines.len()),
+      format_ratio(report.functions_hit(), report.named_functions.len()),
+      format_ratio(report.branches_hit(), report.branches.len()),
+    ];
+
+    let mut rows = String::new();
+    for (index, line) in report.lines.iter().enumerate() {
+      let count = report
+        .found_lines
         .iter()
-        .filter(|(_, count)| *count == 0)
-        .map(|(index, _)| *index);
-
-      let lines_found = line_counts.len();
-      let lines_hit = hit_lines.count();
-      let line_ratio = lines_hit as f32 / lines_found as f32;
-
-      let line_coverage =
-        format!("{:.3}% ({}/{})", line_ratio * 100.0, lines_hit, lines_found,);
-
-      if line_ratio >= 0.9 {
-        println!("{}", colors::green(&line_coverage));
-      } else if line_ratio >= 0.75 {
-        println!("{}", colors::yellow(&line_coverage));
-      } else {
-        println!("{}", colors::red(&line_coverage));
-      }
+        .find(|(line_index, _)| *line_index == index)
+        .map(|(_, count)| *count);
+      let (class, count) = match count {
+        Some(0) => ("miss", "0".to_string()),
+        Some(count) => ("hit", count.to_string()),
+        None => ("none", "".to_string()),
+      };
+      rows.push_str(&format!(
+        "<tr class=\"{}\"><td class=\"line\">{}</td><td class=\"count\">{}</td><td><pre>{}</pre></td></tr>\n",
+        class,
+        index + 1,
+        count,
+        escape_html(line)
+      ));
+    }
 
-      let mut last_line = None;
-      for line_index in missed_lines {
-        const WIDTH: usize = 4;
-        const SEPERATOR: &str = "|";
+    let url = escape_html(&report.url);
+    let page = format!(
+      "<!DOCTYPE html>
+<html>
+<head>
+<meta charset=\"utf-8\">
+<title>Coverage of {url}</title>
+<style>{style}</style>
+</head>
+<body>
+<p><a href=\"index.html\">All files</a></p>
+<h1>{url}</h1>
+<p>Lines: {lines} &middot; Functions: {functions} &middot; Branches: {branches}</p>
+<table class=\"source\">
+{rows}</table>
+</body>
+</html>
+",
+      url = url,
+      style = HTML_STYLE,
+      lines = summary[0],
+      functions = summary[1],
+      branches = summary[2],
+      rows = rows,
+    );
 
-        // Put a horizontal separator between disjoint runs of lines
-        if let Some(last_line) = last_line {
-          if last_line + 1 != line_index {
-            let dash = colors::gray(&"-".repeat(WIDTH + 1));
-            println!("{}{}{}", dash, colors::gray(SEPERATOR), dash);
-          }
-        }
+    let page_name = Self::page_name(&report.url);
+    fs::create_dir_all(&self.dir)?;
+    fs::write(self.dir.join(&page_name), page)?;
+    self
+      .summaries
+      .push((page_name, report.url.clone(), summary));
 
-        println!(
-          "{:width$} {} {}",
-          line_index + 1,
-          colors::gray(SEPERATOR),
-          colors::red(&lines[line_index]),
-          width = WIDTH
-        );
+    Ok(())
+  }
 
-        last_line = Some(line_index);
-      }
+  fn done(&mut self) -> Result<(), AnyError> {
+    let mut rows = String::new();
+    for (page_name, url, summary) in &self.summaries {
+      rows.push_str(&format!(
+        "<tr><td><a href=\"{}\">{}</a></td><td>{}</td><td>{}</td><td>{}</td></tr>\n",
+        page_name,
+        escape_html(url),
+        summary[0],
+        summary[1],
+        summary[2]
+      ));
     }
+
+    let index = format!(
+      "<!DOCTYPE html>
+<html>
+<head>
+<meta charset=\"utf-8\">
+<title>Coverage report</title>
+<style>{style}</style>
+</head>
+<body>
+<h1>Coverage report</h1>
+<table>
+<tr><th>File</th><th>Lines</th><th>Functions</th><th>Branches</th></tr>
+{rows}</table>
+</body>
+</html>
+",
+      style = HTML_STYLE,
+      rows = rows,
+    );
+
+    fs::create_dir_all(&self.dir)?;
+    fs::write(self.dir.join("index.html"), index)?;
+    Ok(())
   }
 }
 
@@ -376,16 +736,28 @@ fn filter_coverages(
     .collect::<Vec<ScriptCoverage>>()
 }
 
+/// Reports the coverages collected in `dir` to stdout and, if given, to an
+/// lcov tracefile at `maybe_lcov` and an HTML report in `maybe_html`.
 pub async fn report_coverages(
   program_state: Arc<ProgramState>,
   dir: &PathBuf,
   quiet: bool,
   exclude: Vec<Url>,
+  maybe_lcov: Option<PathBuf>,
+  maybe_html: Option<PathBuf>,
 ) -> Result<(), AnyError> {
   let coverages = collect_coverages(dir)?;
   let coverages = filter_coverages(coverages, exclude);
 
-  let mut coverage_reporter = PrettyCoverageReporter::new(quiet);
+  let mut reporters: Vec<Box<dyn CoverageReporter>> =
+    vec![Box::new(PrettyCoverageReporter::new(quiet))];
+  if let Some(path) = maybe_lcov {
+    reporters.push(Box::new(LcovCoverageReporter::new(path)));
+  }
+  if let Some(dir) = maybe_html {
+    reporters.push(Box::new(HtmlCoverageReporter::new(dir)));
+  }
+
   for script_coverage in coverages {
     let module_specifier =
       deno_core::resolve_url_or_path(&script_coverage.url)?;
@@ -408,13 +780,126 @@ pub async fn report_coverages(
       .get_source(&module_specifier)
       .map(|f| f.source);
 
-    coverage_reporter.visit_coverage(
+    let report = generate_coverage_report(
       &script_coverage,
       &script_source,
       maybe_source_map,
       maybe_cached_source,
     );
+    for reporter in reporters.iter_mut() {
+      reporter.visit_report(&report)?;
+    }
+  }
+
+  for reporter in reporters.iter_mut() {
+    reporter.done()?;
   }
 
   Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn range(
+    start_offset: usize,
+    end_offset: usize,
+    count: usize,
+  ) -> CoverageRange {
+    CoverageRange {
+      start_offset,
+      end_offset,
+      count,
+    }
+  }
+
+  // The coverage V8 reports for calling `branch(true)` once.
+  fn branch_coverage(source: &str) -> ScriptCoverage {
+    let else_start = source.find("{\n    return false").unwrap();
+    let else_end = source.find("}\n}").unwrap() + 1;
+    ScriptCoverage {
+      script_id: "1".to_string(),
+      url: "file:///branch.js".to_string(),
+      functions: vec![
+        FunctionCoverage {
+          function_name: "".to_string(),
+          ranges: vec![range(0, source.len(), 1)],
+          is_block_coverage: true,
+        },
+        FunctionCoverage {
+          function_name: "branch".to_string(),
+          ranges: vec![
+            range(source.find("function").unwrap(), source.len() - 1, 1),
+            range(else_start, else_end, 0),
+          ],
+          is_block_coverage: true,
+        },
+        FunctionCoverage {
+          function_name: "unused".to_string(),
+          ranges: vec![range(
+            source.rfind("function").unwrap(),
+            source.len(),
+            0,
+          )],
+          is_block_coverage: true,
+        },
+      ],
+    }
+  }
+
+  const BRANCH_SOURCE: &str = "export function branch(condition) {
+  if (condition) {
+    return true;
+  } else {
+    return false;
+  }
+}
+function unused() {}
+";
+
+  #[test]
+  fn lcov_with_branches() {
+    let script_coverage = branch_coverage(BRANCH_SOURCE);
+    let report =
+      generate_coverage_report(&script_coverage, BRANCH_SOURCE, None, None);
+    let dir = tempfile::TempDir::new().unwrap();
+    let path = dir.path().join("cov.lcov");
+    let mut reporter = LcovCoverageReporter::new(path.clone());
+    reporter.visit_report(&report).unwrap();
+    reporter.done().unwrap();
+
+    let lcov = fs::read_to_string(path).unwrap();
+    assert!(lcov.starts_with("SF:"));
+    assert!(lcov.contains(
+      "FN:1,branch\nFN:8,unused\nFNDA:1,branch\nFNDA:0,unused\nFNF:2\nFNH:1\n"
+    ));
+    assert!(lcov.contains("BRDA:4,1,0,0\nBRF:1\nBRH:0\n"));
+    assert!(lcov.contains("DA:3,2\nDA:4,0\nDA:5,0\nDA:6,0\nDA:7,2\n"));
+    assert!(lcov.ends_with("LF:9\nLH:5\nend_of_record\n"));
+  }
+
+  #[test]
+  fn html_report() {
+    let script_coverage = branch_coverage(BRANCH_SOURCE);
+    let report =
+      generate_coverage_report(&script_coverage, BRANCH_SOURCE, None, None);
+    let dir = tempfile::TempDir::new().unwrap();
+    let mut reporter = HtmlCoverageReporter::new(dir.path().to_path_buf());
+    reporter.visit_report(&report).unwrap();
+    reporter.done().unwrap();
+
+    let index = fs::read_to_string(dir.path().join("index.html")).unwrap();
+    assert!(index.contains(
+      "<tr><td><a href=\"branch.js.html\">file:///branch.js</a></td><td>55.556% (5/9)</td><td>50.000% (1/2)</td><td>0.000% (0/1)</td></tr>"
+    ));
+
+    let page = fs::read_to_string(dir.path().join("branch.js.html")).unwrap();
+    assert!(page.contains(
+      "<tr class=\"hit\"><td class=\"line\">2</td><td class=\"count\">2</td><td><pre>  if (condition) {</pre></td></tr>"
+    ));
+    assert!(page.contains(
+      "<tr class=\"miss\"><td class=\"line\">5</td><td class=\"count\">0</td><td><pre>    return false;</pre></td></tr>"
+    ));
+  }
+}
@@ -0,0 +1,1063 @@
+// Copyright 2018-2021 the Deno authors. All rights reserved. MIT license.
+
+//! The JSON output of `deno doc --json`.
+//!
+//! The nodes of [`deno_doc`](https://github.com/denoland/deno_doc) change
+//! shape between its releases, so they are converted to the types of this
+//! module, whose schema is identified by `DOC_JSON_VERSION`. Types are
+//! rendered as TypeScript and JSDoc comments are parsed into their tags.
+
+use deno_core::serde_json;
+use deno_core::serde_json::Value;
+use deno_doc::DocNode;
+use deno_doc::DocNodeKind;
+use deno_doc::ParamDef;
+use serde::Deserialize;
+use serde::Serialize;
+use std::collections::HashSet;
+use swc_ecmascript::ast;
+
+/// The version of the schema of `DocJson`, which is bumped whenever a field
+/// is changed or removed.
+pub const DOC_JSON_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DocJson {
+  pub version: u32,
+  pub nodes: Vec<Node>,
+}
+
+impl DocJson {
+  pub fn new(doc_nodes: &[DocNode]) -> Self {
+    Self {
+      version: DOC_JSON_VERSION,
+      nodes: doc_nodes.iter().map(Node::from).collect(),
+    }
+  }
+}
+
+/// Where a symbol is defined. Symbols that are re-exported are located in the
+/// module that defines them, not the one that re-exports them.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Location {
+  pub filename: String,
+  pub line: usize,
+  pub col: usize,
+}
+
+impl From<&deno_doc::Location> for Location {
+  fn from(location: &deno_doc::Location) -> Self {
+    Self {
+      filename: location.filename.clone(),
+      line: location.line,
+      col: location.col,
+    }
+  }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Node {
+  pub name: String,
+  pub location: Location,
+  pub js_doc: Option<JsDoc>,
+  #[serde(flatten)]
+  pub def: NodeDef,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum NodeDef {
+  Function(FunctionDef),
+  Variable(VariableDef),
+  Class(ClassDef),
+  Enum(EnumDef),
+  Interface(InterfaceDef),
+  TypeAlias(TypeAliasDef),
+  Namespace(NamespaceDef),
+  Import(ImportDef),
+}
+
+impl From<&DocNode> for Node {
+  fn from(node: &DocNode) -> Self {
+    // `deno_doc` sets the definition that matches the kind of the node.
+    let def = match node.kind {
+      DocNodeKind::Function => {
+        NodeDef::Function(node.function_def.as_ref().unwrap().into())
+      }
+      DocNodeKind::Variable => {
+        NodeDef::Variable(node.variable_def.as_ref().unwrap().into())
+      }
+      DocNodeKind::Class => {
+        NodeDef::Class(node.class_def.as_ref().unwrap().into())
+      }
+      DocNodeKind::Enum => {
+        NodeDef::Enum(node.enum_def.as_ref().unwrap().into())
+      }
+      DocNodeKind::Interface => {
+        NodeDef::Interface(node.interface_def.as_ref().unwrap().into())
+      }
+      DocNodeKind::TypeAlias => {
+        NodeDef::TypeAlias(node.type_alias_def.as_ref().unwrap().into())
+      }
+      DocNodeKind::Namespace => {
+        NodeDef::Namespace(node.namespace_def.as_ref().unwrap().into())
+      }
+      DocNodeKind::Import => {
+        let import_def = node.import_def.as_ref().unwrap();
+        NodeDef::Import(ImportDef {
+          src: import_def.src.clone(),
+          imported: import_def.imported.clone(),
+        })
+      }
+    };
+    Self {
+      name: node.name.clone(),
+      location: (&node.location).into(),
+      js_doc: to_js_doc(&node.js_doc),
+      def,
+    }
+  }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Param {
+  /// The name of the parameter, or its destructuring pattern, e.g.
+  /// `{a, b}`.
+  pub name: String,
+  pub ts_type: Option<String>,
+  pub optional: bool,
+  pub rest: bool,
+}
+
+impl From<&ParamDef> for Param {
+  fn from(param: &ParamDef) -> Self {
+    match param {
+      ParamDef::Identifier {
+        name,
+        optional,
+        ts_type,
+      } => Self {
+        name: name.clone(),
+        ts_type: to_type(ts_type),
+        optional: *optional,
+        rest: false,
+      },
+      ParamDef::Rest { arg, ts_type } => {
+        let arg = Self::from(&**arg);
+        Self {
+          name: arg.name,
+          ts_type: to_type(ts_type).or(arg.ts_type),
+          optional: false,
+          rest: true,
+        }
+      }
+      ParamDef::Assign { left, ts_type, .. } => {
+        let left = Self::from(&**left);
+        Self {
+          name: left.name,
+          ts_type: to_type(ts_type).or(left.ts_type),
+          optional: true,
+          rest: false,
+        }
+      }
+      ParamDef::Array {
+        elements,
+        optional,
+        ts_type,
+      } => Self {
+        name: ParamDef::Array {
+          elements: elements.clone(),
+          optional: false,
+          ts_type: None,
+        }
+        .to_string(),
+        ts_type: to_type(ts_type),
+        optional: *optional,
+        rest: false,
+      },
+      ParamDef::Object {
+        props,
+        optional,
+        ts_type,
+      } => Self {
+        name: ParamDef::Object {
+          props: props.clone(),
+          optional: false,
+          ts_type: None,
+        }
+        .to_string(),
+        ts_type: to_type(ts_type),
+        optional: *optional,
+        rest: false,
+      },
+    }
+  }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TypeParam {
+  pub name: String,
+  pub constraint: Option<String>,
+  pub default: Option<String>,
+}
+
+impl From<&deno_doc::ts_type_param::TsTypeParamDef> for TypeParam {
+  fn from(type_param: &deno_doc::ts_type_param::TsTypeParamDef) -> Self {
+    Self {
+      name: type_param.name.clone(),
+      constraint: to_type(&type_param.constraint),
+      default: to_type(&type_param.default),
+    }
+  }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FunctionDef {
+  pub params: Vec<Param>,
+  pub return_type: Option<String>,
+  pub is_async: bool,
+  pub is_generator: bool,
+  pub type_params: Vec<TypeParam>,
+}
+
+impl From<&deno_doc::function::FunctionDef> for FunctionDef {
+  fn from(function_def: &deno_doc::function::FunctionDef) -> Self {
+    Self {
+      params: function_def.params.iter().map(Param::from).collect(),
+      return_type: to_type(&function_def.return_type),
+      is_async: function_def.is_async,
+      is_generator: function_def.is_generator,
+      type_params: to_type_params(&function_def.type_params),
+    }
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum VariableKind {
+  Const,
+  Let,
+  Var,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VariableDef {
+  /// Named so that it does not clash with the `kind` of the node.
+  pub decl_kind: VariableKind,
+  pub ts_type: Option<String>,
+}
+
+impl From<&deno_doc::variable::VariableDef> for VariableDef {
+  fn from(variable_def: &deno_doc::variable::VariableDef) -> Self {
+    let decl_kind = match variable_def.kind {
+      ast::VarDeclKind::Const => VariableKind::Const,
+      ast::VarDeclKind::Let => VariableKind::Let,
+      ast::VarDeclKind::Var => VariableKind::Var,
+    };
+    Self {
+      decl_kind,
+      ts_type: to_type(&variable_def.ts_type),
+    }
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Accessibility {
+  Public,
+  Protected,
+  Private,
+}
+
+fn to_accessibility(
+  accessibility: &Option<ast::Accessibility>,
+) -> Option<Accessibility> {
+  accessibility.map(|accessibility| match accessibility {
+    ast::Accessibility::Public => Accessibility::Public,
+    ast::Accessibility::Protected => Accessibility::Protected,
+    ast::Accessibility::Private => Accessibility::Private,
+  })
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClassDef {
+  pub is_abstract: bool,
+  pub extends: Option<String>,
+  /// The type arguments of the class it extends.
+  pub super_type_params: Vec<String>,
+  pub implements: Vec<String>,
+  pub type_params: Vec<TypeParam>,
+  pub constructors: Vec<ClassConstructorDef>,
+  pub properties: Vec<ClassPropertyDef>,
+  pub index_signatures: Vec<IndexSignatureDef>,
+  pub methods: Vec<ClassMethodDef>,
+}
+
+impl From<&deno_doc::class::ClassDef> for ClassDef {
+  fn from(class_def: &deno_doc::class::ClassDef) -> Self {
+    Self {
+      is_abstract: class_def.is_abstract,
+      extends: class_def.extends.clone(),
+      super_type_params: to_types(&class_def.super_type_params),
+      implements: to_types(&class_def.implements),
+      type_params: to_type_params(&class_def.type_params),
+      constructors: class_def
+        .constructors
+        .iter()
+        .map(|constructor| ClassConstructorDef {
+          js_doc: to_js_doc(&constructor.js_doc),
+          accessibility: to_accessibility(&constructor.accessibility),
+          params: constructor.params.iter().map(Param::from).collect(),
+          location: (&constructor.location).into(),
+        })
+        .collect(),
+      properties: class_def
+        .properties
+        .iter()
+        .map(|property| ClassPropertyDef {
+          name: property.name.clone(),
+          js_doc: to_js_doc(&property.js_doc),
+          ts_type: to_type(&property.ts_type),
+          readonly: property.readonly,
+          accessibility: to_accessibility(&property.accessibility),
+          optional: property.optional,
+          is_abstract: property.is_abstract,
+          is_static: property.is_static,
+          location: (&property.location).into(),
+        })
+        .collect(),
+      index_signatures: class_def
+        .index_signatures
+        .iter()
+        .map(|signature| IndexSignatureDef {
+          readonly: signature.readonly,
+          params: signature.params.iter().map(Param::from).collect(),
+          ts_type: to_type(&signature.ts_type),
+        })
+        .collect(),
+      methods: class_def
+        .methods
+        .iter()
+        .map(|method| ClassMethodDef {
+          name: method.name.clone(),
+          js_doc: to_js_doc(&method.js_doc),
+          kind: match method.kind {
+            ast::MethodKind::Method => MethodKind::Method,
+            ast::MethodKind::Getter => MethodKind::Getter,
+            ast::MethodKind::Setter => MethodKind::Setter,
+          },
+          accessibility: to_accessibility(&method.accessibility),
+          optional: method.optional,
+          is_abstract: method.is_abstract,
+          is_static: method.is_static,
+          function_def: (&method.function_def).into(),
+          location: (&method.location).into(),
+        })
+        .collect(),
+    }
+  }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClassConstructorDef {
+  pub js_doc: Option<JsDoc>,
+  pub accessibility: Option<Accessibility>,
+  pub params: Vec<Param>,
+  pub location: Location,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClassPropertyDef {
+  pub name: String,
+  pub js_doc: Option<JsDoc>,
+  pub ts_type: Option<String>,
+  pub readonly: bool,
+  pub accessibility: Option<Accessibility>,
+  pub optional: bool,
+  pub is_abstract: bool,
+  pub is_static: bool,
+  pub location: Location,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum MethodKind {
+  Method,
+  Getter,
+  Setter,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClassMethodDef {
+  pub name: String,
+  pub js_doc: Option<JsDoc>,
+  pub kind: MethodKind,
+  pub accessibility: Option<Accessibility>,
+  pub optional: bool,
+  pub is_abstract: bool,
+  pub is_static: bool,
+  #[serde(flatten)]
+  pub function_def: FunctionDef,
+  pub location: Location,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IndexSignatureDef {
+  pub readonly: bool,
+  pub params: Vec<Param>,
+  pub ts_type: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EnumDef {
+  pub members: Vec<EnumMemberDef>,
+}
+
+impl From<&deno_doc::r#enum::EnumDef> for EnumDef {
+  fn from(enum_def: &deno_doc::r#enum::EnumDef) -> Self {
+    Self {
+      members: enum_def
+        .members
+        .iter()
+        .map(|member| EnumMemberDef {
+          name: member.name.clone(),
+          js_doc: to_js_doc(&member.js_doc),
+        })
+        .collect(),
+    }
+  }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EnumMemberDef {
+  pub name: String,
+  pub js_doc: Option<JsDoc>,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InterfaceDef {
+  pub extends: Vec<String>,
+  pub type_params: Vec<TypeParam>,
+  pub properties: Vec<InterfacePropertyDef>,
+  pub methods: Vec<InterfaceMethodDef>,
+  pub call_signatures: Vec<CallSignatureDef>,
+  pub index_signatures: Vec<IndexSignatureDef>,
+}
+
+impl From<&deno_doc::interface::InterfaceDef> for InterfaceDef {
+  fn from(interface_def: &deno_doc::interface::InterfaceDef) -> Self {
+    Self {
+      extends: to_types(&interface_def.extends),
+      type_params: to_type_params(&interface_def.type_params),
+      properties: interface_def
+        .properties
+        .iter()
+        .map(|property| InterfacePropertyDef {
+          name: property.name.clone(),
+          js_doc: to_js_doc(&property.js_doc),
+          ts_type: to_type(&property.ts_type),
+          optional: property.optional,
+          computed: property.computed,
+          location: (&property.location).into(),
+        })
+        .collect(),
+      methods: interface_def
+        .methods
+        .iter()
+        .map(|method| InterfaceMethodDef {
+          name: method.name.clone(),
+          js_doc: to_js_doc(&method.js_doc),
+          optional: method.optional,
+          params: method.params.iter().map(Param::from).collect(),
+          return_type: to_type(&method.return_type),
+          type_params: to_type_params(&method.type_params),
+          location: (&method.location).into(),
+        })
+        .collect(),
+      call_signatures: interface_def
+        .call_signatures
+        .iter()
+        .map(|signature| CallSignatureDef {
+          js_doc: to_js_doc(&signature.js_doc),
+          params: signature.params.iter().map(Param::from).collect(),
+          ts_type: to_type(&signature.ts_type),
+          type_params: to_type_params(&signature.type_params),
+          location: (&signature.location).into(),
+        })
+        .collect(),
+      index_signatures: interface_def
+        .index_signatures
+        .iter()
+        .map(|signature| IndexSignatureDef {
+          readonly: signature.readonly,
+          params: signature.params.iter().map(Param::from).collect(),
+          ts_type: to_type(&signature.ts_type),
+        })
+        .collect(),
+    }
+  }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InterfacePropertyDef {
+  pub name: String,
+  pub js_doc: Option<JsDoc>,
+  pub ts_type: Option<String>,
+  pub optional: bool,
+  pub computed: bool,
+  pub location: Location,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InterfaceMethodDef {
+  pub name: String,
+  pub js_doc: Option<JsDoc>,
+  pub optional: bool,
+  pub params: Vec<Param>,
+  pub return_type: Option<String>,
+  pub type_params: Vec<TypeParam>,
+  pub location: Location,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CallSignatureDef {
+  pub js_doc: Option<JsDoc>,
+  pub params: Vec<Param>,
+  pub ts_type: Option<String>,
+  pub type_params: Vec<TypeParam>,
+  pub location: Location,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TypeAliasDef {
+  pub type_params: Vec<TypeParam>,
+  pub ts_type: String,
+}
+
+impl From<&deno_doc::type_alias::TypeAliasDef> for TypeAliasDef {
+  fn from(type_alias_def: &deno_doc::type_alias::TypeAliasDef) -> Self {
+    Self {
+      type_params: to_type_params(&type_alias_def.type_params),
+      ts_type: type_alias_def.ts_type.to_string(),
+    }
+  }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NamespaceDef {
+  pub elements: Vec<Node>,
+}
+
+impl From<&deno_doc::namespace::NamespaceDef> for NamespaceDef {
+  fn from(namespace_def: &deno_doc::namespace::NamespaceDef) -> Self {
+    Self {
+      elements: namespace_def.elements.iter().map(Node::from).collect(),
+    }
+  }
+}
+
+/// An imported symbol, which is documented where `src` defines it.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportDef {
+  /// The resolved specifier of the module it is imported from.
+  pub src: String,
+  /// The name of the import, or `None` for a namespace import.
+  pub imported: Option<String>,
+}
+
+fn to_type(ts_type: &Option<deno_doc::ts_type::TsTypeDef>) -> Option<String> {
+  ts_type.as_ref().map(|ts_type| ts_type.to_string())
+}
+
+fn to_types(ts_types: &[deno_doc::ts_type::TsTypeDef]) -> Vec<String> {
+  ts_types.iter().map(|ts_type| ts_type.to_string()).collect()
+}
+
+fn to_type_params(
+  type_params: &[deno_doc::ts_type_param::TsTypeParamDef],
+) -> Vec<TypeParam> {
+  type_params.iter().map(TypeParam::from).collect()
+}
+
+fn to_js_doc(js_doc: &Option<String>) -> Option<JsDoc> {
+  js_doc.as_deref().map(JsDoc::parse)
+}
+
+/// A JSDoc comment, split into its description and its tags.
+#[derive(Debug, Default, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JsDoc {
+  /// The text before the first tag.
+  pub description: String,
+  pub params: Vec<JsDocParam>,
+  pub returns: Option<JsDocReturns>,
+  /// The text of the `@deprecated` tag, which may be empty.
+  pub deprecated: Option<String>,
+  pub examples: Vec<String>,
+  /// The tags not covered by the other fields, e.g. `@see`.
+  pub tags: Vec<JsDocTag>,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JsDocParam {
+  pub name: String,
+  pub ts_type: Option<String>,
+  /// Whether the name is in brackets, e.g. `[name]` or `[name=default]`.
+  pub optional: bool,
+  pub default: Option<String>,
+  pub description: String,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JsDocReturns {
+  pub ts_type: Option<String>,
+  pub description: String,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JsDocTag {
+  pub name: String,
+  pub value: String,
+}
+
+impl JsDoc {
+  /// Parses the text of a JSDoc comment, as extracted by `deno_doc`. A tag
+  /// starts at a line beginning with `@`, unless it is in a fenced code block
+  /// (e.g. a decorator in an example), and extends to the next one.
+  pub fn parse(text: &str) -> Self {
+    let mut description = Vec::new();
+    let mut tags: Vec<(&str, Vec<&str>)> = Vec::new();
+    let mut in_code_block = false;
+    for line in text.lines() {
+      let trimmed = line.trim_start();
+      if !in_code_block && trimmed.starts_with('@') {
+        let tag = &trimmed[1..];
+        let (name, rest) = match tag.find(char::is_whitespace) {
+          Some(index) => (&tag[..index], tag[index..].trim_start()),
+          None => (tag, ""),
+        };
+        tags.push((name, vec![rest]));
+      } else {
+        if trimmed.starts_with("```") {
+          in_code_block = !in_code_block;
+        }
+        match tags.last_mut() {
+          Some((_, lines)) => lines.push(line),
+          None => description.push(line),
+        }
+      }
+    }
+
+    let mut js_doc = Self {
+      description: description.join("\n").trim().to_string(),
+      ..Default::default()
+    };
+    for (name, lines) in tags {
+      let value = lines.join("\n").trim().to_string();
+      match name {
+        "param" | "arg" | "argument" => js_doc.params.push(parse_param(&value)),
+        "returns" | "return" => {
+          let (ts_type, description) = split_type(&value);
+          js_doc.returns = Some(JsDocReturns {
+            ts_type,
+            description: description.to_string(),
+          });
+        }
+        "deprecated" => js_doc.deprecated = Some(value),
+        "example" => js_doc.examples.push(value),
+        _ => js_doc.tags.push(JsDocTag {
+          name: name.to_string(),
+          value,
+        }),
+      }
+    }
+    js_doc
+  }
+}
+
+/// Splits a leading type in braces, e.g. `{string}`, from the rest of `text`.
+fn split_type(text: &str) -> (Option<String>, &str) {
+  if !text.starts_with('{') {
+    return (None, text);
+  }
+  let mut depth = 0;
+  for (index, c) in text.char_indices() {
+    match c {
+      '{' => depth += 1,
+      '}' => {
+        depth -= 1;
+        if depth == 0 {
+          let ts_type = text[1..index].trim().to_string();
+          return (Some(ts_type), text[index + 1..].trim_start());
+        }
+      }
+      _ => {}
+    }
+  }
+  (None, text)
+}
+
+fn parse_param(text: &str) -> JsDocParam {
+  let (ts_type, rest) = split_type(text);
+  let (name, optional, default, rest) = if rest.starts_with('[') {
+    let end = rest.find(']').unwrap_or_else(|| rest.len() - 1);
+    let inner = &rest[1..end.max(1)];
+    let (name, default) = match inner.find('=') {
+      Some(index) => (&inner[..index], Some(inner[index + 1..].trim())),
+      None => (inner, None),
+    };
+    (name, true, default, &rest[(end + 1).min(rest.len())..])
+  } else {
+    let end = rest.find(char::is_whitespace).unwrap_or_else(|| rest.len());
+    (&rest[..end], false, None, &rest[end..])
+  };
+  let description = rest.trim_start();
+  let description =
+    description.strip_prefix("- ").unwrap_or(description).trim();
+  JsDocParam {
+    name: name.trim().to_string(),
+    ts_type,
+    optional,
+    default: default.map(String::from),
+    description: description.to_string(),
+  }
+}
+
+/// Returns the nodes found by `filter`, a dot separated path to a symbol, along
+/// with the nodes of the symbols they reference in their types, transitively,
+/// e.g. the interface of the options of a function.
+pub fn find_nodes_with_references(
+  doc_nodes: &[DocNode],
+  filter: &str,
+) -> Vec<DocNode> {
+  let mut found = deno_doc::find_nodes_by_name_recursively(
+    doc_nodes.to_vec(),
+    filter.to_string(),
+  );
+  let mut seen = HashSet::new();
+  seen.insert(filter.to_string());
+  let mut pending = found.iter().flat_map(get_references).collect::<Vec<_>>();
+  while let Some(name) = pending.pop() {
+    if !seen.insert(name.clone()) {
+      continue;
+    }
+    for node in
+      deno_doc::find_nodes_by_name_recursively(doc_nodes.to_vec(), name)
+    {
+      pending.extend(get_references(&node));
+      found.push(node);
+    }
+  }
+  found
+}
+
+/// Returns the names of the symbols referenced by the types of `node`, except
+/// for its type parameters.
+fn get_references(node: &DocNode) -> Vec<String> {
+  let value = serde_json::to_value(node).unwrap();
+  let mut references = Vec::new();
+  let mut type_params = HashSet::new();
+  collect_references(&value, &mut references, &mut type_params);
+  references.retain(|name| !type_params.contains(name));
+  references
+}
+
+fn collect_references(
+  value: &Value,
+  references: &mut Vec<String>,
+  type_params: &mut HashSet<String>,
+) {
+  match value {
+    Value::Object(map) => {
+      for (key, value) in map {
+        match (key.as_str(), value) {
+          ("typeRef", Value::Object(type_ref)) => {
+            if let Some(Value::String(name)) = type_ref.get("typeName") {
+              references.push(name.clone());
+            }
+          }
+          // `typeof` queries and the class being extended.
+          ("typeQuery", Value::String(name))
+          | ("extends", Value::String(name)) => references.push(name.clone()),
+          ("typeParams", Value::Array(params)) => {
+            for param in params {
+              if let Some(Value::String(name)) = param.get("name") {
+                type_params.insert(name.clone());
+              }
+            }
+          }
+          _ => {}
+        }
+        collect_references(value, references, type_params);
+      }
+    }
+    Value::Array(values) => {
+      for value in values {
+        collect_references(value, references, type_params);
+      }
+    }
+    _ => {}
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use deno_core::futures::future;
+  use deno_core::futures::FutureExt;
+  use deno_doc::parser::DocFileLoader;
+  use deno_doc::DocError;
+  use deno_doc::DocParser;
+  use std::collections::HashMap;
+  use std::future::Future;
+  use std::pin::Pin;
+
+  struct MockLoader {
+    sources: HashMap<&'static str, &'static str>,
+  }
+
+  impl DocFileLoader for MockLoader {
+    fn resolve(
+      &self,
+      specifier: &str,
+      referrer: &str,
+    ) -> Result<String, DocError> {
+      deno_core::resolve_import(specifier, referrer)
+        .map(|specifier| specifier.to_string())
+        .map_err(|err| DocError::Resolve(err.to_string()))
+    }
+
+    fn load_source_code(
+      &self,
+      specifier: &str,
+    ) -> Pin<Box<dyn Future<Output = Result<String, DocError>>>> {
+      let result = self
+        .sources
+        .get(specifier)
+        .map(|source| source.to_string())
+        .ok_or_else(|| {
+          DocError::Io(std::io::Error::new(
+            std::io::ErrorKind::NotFound,
+            specifier.to_string(),
+          ))
+        });
+      future::ready(result).boxed_local()
+    }
+  }
+
+  async fn parse(
+    sources: Vec<(&'static str, &'static str)>,
+    specifier: &str,
+  ) -> Vec<DocNode> {
+    let loader = Box::new(MockLoader {
+      sources: sources.into_iter().collect(),
+    });
+    let media_type = crate::media_type::MediaType::TypeScript;
+    let syntax = crate::ast::get_syntax(&media_type);
+    DocParser::new(loader, false)
+      .parse_with_reexports(specifier, syntax)
+      .await
+      .unwrap()
+  }
+
+  #[tokio::test]
+  async fn test_doc_json() {
+    let doc_nodes = parse(
+      vec![(
+        "file:///a/mod.ts",
+        r#"/**
+ * Adds two numbers.
+ *
+ * @param {number} a - The first number.
+ * @param [b=1] The second number.
+ * @returns {number} The sum.
+ * @deprecated Use `plus` instead.
+ */
+export function add(a: number, b = 1): number {
+  return a + b;
+}
+
+export const VERSION: string = "1.0.0";
+"#,
+      )],
+      "file:///a/mod.ts",
+    )
+    .await;
+    let actual = serde_json::to_value(DocJson::new(&doc_nodes)).unwrap();
+    assert_eq!(
+      actual,
+      serde_json::json!({
+        "version": 1,
+        "nodes": [
+          {
+            "name": "add",
+            "location": {
+              "filename": "file:///a/mod.ts",
+              "line": 9,
+              "col": 0,
+            },
+            "jsDoc": {
+              "description": "Adds two numbers.",
+              "params": [
+                {
+                  "name": "a",
+                  "tsType": "number",
+                  "optional": false,
+                  "default": null,
+                  "description": "The first number.",
+                },
+                {
+                  "name": "b",
+                  "tsType": null,
+                  "optional": true,
+                  "default": "1",
+                  "description": "The second number.",
+                },
+              ],
+              "returns": {
+                "tsType": "number",
+                "description": "The sum.",
+              },
+              "deprecated": "Use `plus` instead.",
+              "examples": [],
+              "tags": [],
+            },
+            "kind": "function",
+            "params": [
+              {
+                "name": "a",
+                "tsType": "number",
+                "optional": false,
+                "rest": false,
+              },
+              {
+                "name": "b",
+                "tsType": null,
+                "optional": true,
+                "rest": false,
+              },
+            ],
+            "returnType": "number",
+            "isAsync": false,
+            "isGenerator": false,
+            "typeParams": [],
+          },
+          {
+            "name": "VERSION",
+            "location": {
+              "filename": "file:///a/mod.ts",
+              "line": 13,
+              "col": 0,
+            },
+            "jsDoc": null,
+            "kind": "variable",
+            "declKind": "const",
+            "tsType": "string",
+          },
+        ],
+      })
+    );
+  }
+
+  #[test]
+  fn test_js_doc_parse() {
+    let actual = JsDoc::parse(
+      "Does something.\n\n@example\n```ts\n@decorator\nclass A {}\n```\n@see other\n@deprecated",
+    );
+    assert_eq!(
+      actual,
+      JsDoc {
+        description: "Does something.".to_string(),
+        deprecated: Some("".to_string()),
+        examples: vec!["```ts\n@decorator\nclass A {}\n```".to_string()],
+        tags: vec![JsDocTag {
+          name: "see".to_string(),
+          value: "other".to_string(),
+        }],
+        ..Default::default()
+      }
+    );
+
+    let actual = JsDoc::parse("@param {{ a: string }} [options] The options.");
+    assert_eq!(
+      actual.params,
+      vec![JsDocParam {
+        name: "options".to_string(),
+        ts_type: Some("{ a: string }".to_string()),
+        optional: true,
+        default: None,
+        description: "The options.".to_string(),
+      }]
+    );
+  }
+
+  #[tokio::test]
+  async fn test_find_nodes_with_references() {
+    let doc_nodes = parse(
+      vec![(
+        "file:///a/mod.ts",
+        r#"export interface Options<T> {
+  value: T;
+  level: Level;
+}
+export enum Level { Low, High }
+export class Base {}
+export class Impl extends Base {}
+export function run<T>(options: Options<T>): Impl {
+  return new Impl();
+}
+export function unrelated(): void {}
+"#,
+      )],
+      "file:///a/mod.ts",
+    )
+    .await;
+    let mut actual = find_nodes_with_references(&doc_nodes, "run")
+      .into_iter()
+      .map(|node| node.name)
+      .collect::<Vec<_>>();
+    actual.sort();
+    assert_eq!(actual, vec!["Base", "Impl", "Level", "Options", "run"]);
+  }
+
+  #[tokio::test]
+  async fn test_doc_json_reexport_location() {
+    let doc_nodes = parse(
+      vec![
+        ("file:///a/mod.ts", r#"export { b } from "./b.ts";"#),
+        ("file:///a/b.ts", r#"export * from "./c.ts";"#),
+        ("file:///a/c.ts", "export const b = 1;\n"),
+      ],
+      "file:///a/mod.ts",
+    )
+    .await;
+    let actual = DocJson::new(&doc_nodes);
+    assert_eq!(actual.nodes.len(), 1);
+    assert_eq!(actual.nodes[0].name, "b");
+    assert_eq!(actual.nodes[0].location.filename, "file:///a/c.ts");
+  }
+}
@@ -10,12 +10,23 @@
 use crate::colors;
 use crate::diff::diff;
 use crate::file_watcher;
+use crate::file_watcher::WatcherOptions;
+use crate::flags::FmtOptions;
+use crate::fs_util::canonicalize_path;
+use crate::fs_util::glob_to_regex;
+use crate::fs_util::matches_glob;
 use crate::fs_util::{collect_files, get_extension, is_supported_ext_fmt};
 use crate::text_encoding;
+use crate::tools::task::find_config_file;
+use crate::tools::task::parse_jsonc;
 use deno_core::error::generic_error;
 use deno_core::error::AnyError;
+use deno_core::error::Context;
 use deno_core::futures;
 use deno_core::futures::FutureExt;
+use deno_core::serde::Deserialize;
+use deno_core::serde_json;
+use std::cell::RefCell;
 use std::fs;
 use std::io::stdin;
 use std::io::stdout;
@@ -28,32 +39,194 @@ use std::sync::{Arc, Mutex};
 
 const BOM_CHAR: char = '\u{FEFF}';
 
-/// Format JavaScript/TypeScript files.
+/// The `"fmt"` object of the configuration file.
+#[derive(Debug, Default, Deserialize, PartialEq)]
+#[serde(default, deny_unknown_fields, rename_all = "camelCase")]
+struct FmtConfig {
+  line_width: Option<u32>,
+  indent_width: Option<u8>,
+  use_tabs: Option<bool>,
+  single_quote: Option<bool>,
+  prose_wrap: Option<String>,
+  include: Vec<String>,
+  exclude: Vec<String>,
+}
+
+fn parse_fmt_config(text: &str) -> Result<FmtConfig, AnyError> {
+  match parse_jsonc(text)?.get("fmt") {
+    Some(fmt) => Ok(serde_json::from_value(fmt.clone())?),
+    None => Ok(FmtConfig::default()),
+  }
+}
+
+/// Reads the configuration file passed with `--config`, or else the first
+/// `deno.json` or `deno.jsonc` in the current directory or its parents, if
+/// any, and returns its canonicalized path and its `"fmt"` object.
+fn load_config(
+  maybe_config_path: &Option<String>,
+) -> Result<Option<(PathBuf, FmtConfig)>, AnyError> {
+  let config_path = match maybe_config_path {
+    Some(path) => PathBuf::from(path),
+    None => match find_config_file(&std::env::current_dir()?) {
+      Some(path) => path,
+      None => return Ok(None),
+    },
+  };
+  let config_path = canonicalize_path(&config_path)
+    .with_context(|| format!("Unable to find config file {:?}", config_path))?;
+  let text = fs::read_to_string(&config_path)
+    .with_context(|| format!("Unable to read config file {:?}", config_path))?;
+  let config = parse_fmt_config(&text).with_context(|| {
+    format!("Invalid \"fmt\" options in config file {:?}", config_path)
+  })?;
+  Ok(Some((config_path, config)))
+}
+
+/// The options passed on the command line, falling back to the ones of the
+/// configuration file.
+fn resolve_options(options: &FmtOptions, config: &FmtConfig) -> FmtOptions {
+  FmtOptions {
+    line_width: options.line_width.or(config.line_width),
+    indent_width: options.indent_width.or(config.indent_width),
+    use_tabs: options.use_tabs.or(config.use_tabs),
+    single_quote: options.single_quote.or(config.single_quote),
+    prose_wrap: options
+      .prose_wrap
+      .clone()
+      .or_else(|| config.prose_wrap.clone()),
+  }
+}
+
+/// Collects the files to format: the files in `args`, or else the ones
+/// included by the configuration file, or else the ones in the current
+/// directory. Files matching `ignore`, or else the globs excluded by the
+/// configuration file, are left out.
+fn collect_fmt_files(
+  args: &[PathBuf],
+  ignore: &[PathBuf],
+  config: &Option<(PathBuf, FmtConfig)>,
+) -> Result<Vec<PathBuf>, AnyError> {
+  let (config_dir, config) = match config {
+    Some((config_path, config)) => (config_path.parent().unwrap(), config),
+    None => return collect_files(args, ignore, is_supported_ext_fmt),
+  };
+  let compile_globs = |globs: &[String]| {
+    globs
+      .iter()
+      .map(|glob| glob_to_regex(glob))
+      .collect::<Result<Vec<_>, AnyError>>()
+  };
+  let (roots, include) = if args.is_empty() && !config.include.is_empty() {
+    (
+      vec![config_dir.to_path_buf()],
+      compile_globs(&config.include)?,
+    )
+  } else {
+    (args.to_vec(), vec![])
+  };
+  let exclude = if ignore.is_empty() {
+    compile_globs(&config.exclude)?
+  } else {
+    vec![]
+  };
+
+  let files = collect_files(&roots, ignore, is_supported_ext_fmt)?;
+  Ok(
+    files
+      .into_iter()
+      .filter(|file| {
+        let relative_path = file.strip_prefix(config_dir).ok();
+        let is_match = |globs: &[regex::Regex]| {
+          relative_path.map_or(false, |path| {
+            globs.iter().any(|glob| matches_glob(glob, path))
+          })
+        };
+        (include.is_empty() || is_match(&include)) && !is_match(&exclude)
+      })
+      .collect(),
+  )
+}
+
+/// The configurations of the formatters of each file type.
+#[derive(Clone)]
+struct FmtSettings {
+  typescript: dprint_plugin_typescript::configuration::Configuration,
+  markdown: dprint_plugin_markdown::configuration::Configuration,
+  json: dprint_plugin_json::configuration::Configuration,
+}
+
+fn get_settings(options: &FmtOptions) -> Result<FmtSettings, AnyError> {
+  Ok(FmtSettings {
+    typescript: get_typescript_config(options),
+    markdown: get_markdown_config(options)?,
+    json: get_json_config(options),
+  })
+}
+
+fn load_settings(
+  options: &FmtOptions,
+  maybe_config_path: &Option<String>,
+) -> Result<FmtSettings, AnyError> {
+  match load_config(maybe_config_path)? {
+    Some((_, config)) => get_settings(&resolve_options(options, &config)),
+    None => get_settings(options),
+  }
+}
+
+/// Format JavaScript/TypeScript, Markdown and JSON files.
 pub async fn format(
   args: Vec<PathBuf>,
   ignore: Vec<PathBuf>,
   check: bool,
-  watch: bool,
+  watch: Option<WatcherOptions>,
+  options: FmtOptions,
+  maybe_config_path: Option<String>,
 ) -> Result<(), AnyError> {
+  // The configuration file is read again whenever the watcher restarts and is
+  // watched too, so that changes to it are picked up. This is the path of the
+  // configuration file if it is only watched and not formatted.
+  let watched_config_path: RefCell<Option<PathBuf>> = RefCell::new(None);
   let target_file_resolver = || {
     // collect the files that are to be formatted
-    collect_files(&args, &ignore, is_supported_ext_fmt)
+    let config = load_config(&maybe_config_path)?;
+    let mut paths = collect_fmt_files(&args, &ignore, &config)?;
+    let mut watched_config_path = watched_config_path.borrow_mut();
+    *watched_config_path = None;
+    if let Some((config_path, _)) = config {
+      if !paths.contains(&config_path) {
+        paths.push(config_path.clone());
+        *watched_config_path = Some(config_path);
+      }
+    }
+    Ok(paths)
   };
   let operation = |paths: Vec<PathBuf>| {
-    let config = get_typescript_config();
+    let watched_config_path = watched_config_path.borrow().clone();
+    let paths = paths
+      .into_iter()
+      .filter(|path| Some(path) != watched_config_path.as_ref())
+      .collect();
+    let settings = load_settings(&options, &maybe_config_path);
     async move {
+      let settings = settings?;
       if check {
-        check_source_files(config, paths).await?;
+        check_source_files(settings, paths).await?;
       } else {
-        format_source_files(config, paths).await?;
+        format_source_files(settings, paths).await?;
       }
       Ok(())
     }
     .boxed_local()
   };
 
-  if watch {
-    file_watcher::watch_func(target_file_resolver, operation, "Fmt").await?;
+  if let Some(watcher_options) = watch {
+    file_watcher::watch_func(
+      target_file_resolver,
+      operation,
+      "Fmt",
+      watcher_options,
+    )
+    .await?;
   } else {
     operation(target_file_resolver()?).await?;
   }
@@ -65,12 +238,13 @@ pub async fn format(
 /// (ts/tsx, js/jsx).
 fn format_markdown(
   file_text: &str,
-  ts_config: dprint_plugin_typescript::configuration::Configuration,
+  settings: &FmtSettings,
 ) -> Result<String, String> {
-  let md_config = get_markdown_config();
+  let ts_config = settings.typescript.clone();
+  let json_config = settings.json.clone();
   dprint_plugin_markdown::format_text(
     &file_text,
-    &md_config,
+    &settings.markdown,
     Box::new(move |tag, text, line_width| {
       let tag = tag.to_lowercase();
       if matches!(
@@ -93,7 +267,7 @@ fn format_markdown(
         };
 
         if matches!(extension, "json" | "jsonc") {
-          let mut json_config = get_json_config();
+          let mut json_config = json_config.clone();
           json_config.line_width = line_width;
           dprint_plugin_json::format_text(&text, &json_config)
         } else {
@@ -117,39 +291,45 @@ fn format_markdown(
 /// Formats JSON and JSONC using the rules provided by .deno()
 /// of configuration builder of https://github.com/dprint/dprint-plugin-json.
 /// See https://git.io/Jt4ht for configuration.
-fn format_json(file_text: &str) -> Result<String, String> {
-  let json_config = get_json_config();
-  dprint_plugin_json::format_text(&file_text, &json_config)
+fn format_json(
+  file_text: &str,
+  settings: &FmtSettings,
+) -> Result<String, String> {
+  dprint_plugin_json::format_text(&file_text, &settings.json)
 }
 
 async fn check_source_files(
-  config: dprint_plugin_typescript::configuration::Configuration,
+  settings: FmtSettings,
   paths: Vec<PathBuf>,
 ) -> Result<(), AnyError> {
-  let not_formatted_files_count = Arc::new(AtomicUsize::new(0));
+  let not_formatted_files = Arc::new(Mutex::new(Vec::new()));
   let checked_files_count = Arc::new(AtomicUsize::new(0));
 
   // prevent threads outputting at the same time
   let output_lock = Arc::new(Mutex::new(0));
 
   run_parallelized(paths, {
-    let not_formatted_files_count = not_formatted_files_count.clone();
+    let not_formatted_files = not_formatted_files.clone();
     let checked_files_count = checked_files_count.clone();
     move |file_path| {
       checked_files_count.fetch_add(1, Ordering::Relaxed);
       let file_text = read_file_contents(&file_path)?.text;
       let ext = get_extension(&file_path).unwrap_or_else(String::new);
       let r = if ext == "md" {
-        format_markdown(&file_text, config.clone())
+        format_markdown(&file_text, &settings)
       } else if matches!(ext.as_str(), "json" | "jsonc") {
-        format_json(&file_text)
+        format_json(&file_text, &settings)
       } else {
-        dprint_plugin_typescript::format_text(&file_path, &file_text, &config)
+        dprint_plugin_typescript::format_text(
+          &file_path,
+          &file_text,
+          &settings.typescript,
+        )
       };
       match r {
         Ok(formatted_text) => {
           if formatted_text != file_text {
-            not_formatted_files_count.fetch_add(1, Ordering::Relaxed);
+            not_formatted_files.lock().unwrap().push(file_path.clone());
             let _g = output_lock.lock().unwrap();
             let diff = diff(&file_text, &formatted_text);
             info!("");
@@ -168,8 +348,9 @@ async fn check_source_files(
   })
   .await?;
 
-  let not_formatted_files_count =
-    not_formatted_files_count.load(Ordering::Relaxed);
+  let mut not_formatted_files = not_formatted_files.lock().unwrap().clone();
+  not_formatted_files.sort();
+  let not_formatted_files_count = not_formatted_files.len();
   let checked_files_count = checked_files_count.load(Ordering::Relaxed);
   let checked_files_str =
     format!("{} {}", checked_files_count, files_str(checked_files_count));
@@ -177,6 +358,11 @@ async fn check_source_files(
     info!("Checked {}", checked_files_str);
     Ok(())
   } else {
+    info!("");
+    info!("{}", colors::bold("Not formatted files:"));
+    for file_path in &not_formatted_files {
+      info!("  {}", file_path.display());
+    }
     let not_formatted_files_str = files_str(not_formatted_files_count);
     Err(generic_error(format!(
       "Found {} not formatted {} in {}",
@@ -186,7 +372,7 @@ async fn check_source_files(
 }
 
 async fn format_source_files(
-  config: dprint_plugin_typescript::configuration::Configuration,
+  settings: FmtSettings,
   paths: Vec<PathBuf>,
 ) -> Result<(), AnyError> {
   let formatted_files_count = Arc::new(AtomicUsize::new(0));
@@ -201,14 +387,14 @@ async fn format_source_files(
       let file_contents = read_file_contents(&file_path)?;
       let ext = get_extension(&file_path).unwrap_or_else(String::new);
       let r = if ext == "md" {
-        format_markdown(&file_contents.text, config.clone())
+        format_markdown(&file_contents.text, &settings)
       } else if matches!(ext.as_str(), "json" | "jsonc") {
-        format_json(&file_contents.text)
+        format_json(&file_contents.text, &settings)
       } else {
         dprint_plugin_typescript::format_text(
           &file_path,
           &file_contents.text,
-          &config,
+          &settings.typescript,
         )
       };
       match r {
@@ -257,22 +443,27 @@ async fn format_source_files(
 /// Format stdin and write result to stdout.
 /// Treats input as TypeScript or as set by `--ext` flag.
 /// Compatible with `--check` flag.
-pub fn format_stdin(check: bool, ext: String) -> Result<(), AnyError> {
+pub fn format_stdin(
+  check: bool,
+  ext: String,
+  options: FmtOptions,
+  maybe_config_path: Option<String>,
+) -> Result<(), AnyError> {
   let mut source = String::new();
   if stdin().read_to_string(&mut source).is_err() {
     return Err(generic_error("Failed to read from stdin"));
   }
-  let config = get_typescript_config();
+  let settings = load_settings(&options, &maybe_config_path)?;
   let r = if ext.as_str() == "md" {
-    format_markdown(&source, config)
+    format_markdown(&source, &settings)
   } else if matches!(ext.as_str(), "json" | "jsonc") {
-    format_json(&source)
+    format_json(&source, &settings)
   } else {
     // dprint will fallback to jsx parsing if parsing this as a .ts file doesn't work
     dprint_plugin_typescript::format_text(
       &PathBuf::from("_stdin.ts"),
       &source,
-      &config,
+      &settings.typescript,
     )
   };
   match r {
@@ -301,27 +492,75 @@ fn files_str(len: usize) -> &'static str {
 }
 
 fn get_typescript_config(
+  options: &FmtOptions,
 ) -> dprint_plugin_typescript::configuration::Configuration {
-  dprint_plugin_typescript::configuration::ConfigurationBuilder::new()
-    .deno()
-    .build()
+  let mut builder =
+    dprint_plugin_typescript::configuration::ConfigurationBuilder::new();
+  builder.deno();
+  if let Some(line_width) = options.line_width {
+    builder.line_width(line_width);
+  }
+  if let Some(indent_width) = options.indent_width {
+    builder.indent_width(indent_width);
+  }
+  if let Some(use_tabs) = options.use_tabs {
+    builder.use_tabs(use_tabs);
+  }
+  if let Some(single_quote) = options.single_quote {
+    builder.quote_style(if single_quote {
+      dprint_plugin_typescript::configuration::QuoteStyle::PreferSingle
+    } else {
+      dprint_plugin_typescript::configuration::QuoteStyle::PreferDouble
+    });
+  }
+  builder.build()
 }
 
-fn get_markdown_config() -> dprint_plugin_markdown::configuration::Configuration
-{
-  dprint_plugin_markdown::configuration::ConfigurationBuilder::new()
+fn get_markdown_config(
+  options: &FmtOptions,
+) -> Result<dprint_plugin_markdown::configuration::Configuration, AnyError> {
+  use dprint_plugin_markdown::configuration::TextWrap;
+  let text_wrap = match options.prose_wrap.as_deref() {
     // Matches `.dprintrc.json` in the repository
-    .text_wrap(dprint_plugin_markdown::configuration::TextWrap::Always)
+    None | Some("always") => TextWrap::Always,
+    Some("never") => TextWrap::Never,
+    Some("preserve") => TextWrap::Maintain,
+    Some(prose_wrap) => {
+      return Err(generic_error(format!(
+        "Invalid proseWrap \"{}\", expected \"always\", \"never\" or \"preserve\"",
+        prose_wrap
+      )))
+    }
+  };
+  let mut builder =
+    dprint_plugin_markdown::configuration::ConfigurationBuilder::new();
+  builder
+    .text_wrap(text_wrap)
     .ignore_directive("deno-fmt-ignore")
     .ignore_start_directive("deno-fmt-ignore-start")
-    .ignore_end_directive("deno-fmt-ignore-end")
-    .build()
+    .ignore_end_directive("deno-fmt-ignore-end");
+  if let Some(line_width) = options.line_width {
+    builder.line_width(line_width);
+  }
+  Ok(builder.build())
 }
 
-fn get_json_config() -> dprint_plugin_json::configuration::Configuration {
-  dprint_plugin_json::configuration::ConfigurationBuilder::new()
-    .deno()
-    .build()
+fn get_json_config(
+  options: &FmtOptions,
+) -> dprint_plugin_json::configuration::Configuration {
+  let mut builder =
+    dprint_plugin_json::configuration::ConfigurationBuilder::new();
+  builder.deno();
+  if let Some(line_width) = options.line_width {
+    builder.line_width(line_width);
+  }
+  if let Some(indent_width) = options.indent_width {
+    builder.indent_width(indent_width);
+  }
+  if let Some(use_tabs) = options.use_tabs {
+    builder.use_tabs(use_tabs);
+  }
+  builder.build()
 }
 
 struct FileContents {
@@ -401,3 +640,183 @@ where
     Ok(())
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use dprint_plugin_markdown::configuration::TextWrap;
+  use dprint_plugin_typescript::configuration::QuoteStyle;
+
+  #[test]
+  fn fmt_config_options() {
+    let config = parse_fmt_config(
+      r#"{
+        // The other sections are ignored.
+        "tasks": { "build": "deno bundle mod.ts" },
+        "fmt": {
+          "lineWidth": 100,
+          "indentWidth": 4,
+          "useTabs": true,
+          "singleQuote": true,
+          "proseWrap": "preserve",
+          "include": ["src/"],
+          "exclude": ["src/**/*.generated.ts"]
+        }
+      }"#,
+    )
+    .unwrap();
+    assert_eq!(
+      config,
+      FmtConfig {
+        line_width: Some(100),
+        indent_width: Some(4),
+        use_tabs: Some(true),
+        single_quote: Some(true),
+        prose_wrap: Some("preserve".to_string()),
+        include: vec!["src/".to_string()],
+        exclude: vec!["src/**/*.generated.ts".to_string()],
+      }
+    );
+
+    let settings =
+      get_settings(&resolve_options(&FmtOptions::default(), &config)).unwrap();
+    assert_eq!(settings.typescript.line_width, 100);
+    assert_eq!(settings.typescript.indent_width, 4);
+    assert!(settings.typescript.use_tabs);
+    assert!(settings.typescript.quote_style == QuoteStyle::PreferSingle);
+    assert_eq!(settings.markdown.line_width, 100);
+    assert!(settings.markdown.text_wrap == TextWrap::Maintain);
+    assert_eq!(settings.json.line_width, 100);
+    assert_eq!(settings.json.indent_width, 4);
+    assert!(settings.json.use_tabs);
+
+    let settings = get_settings(&FmtOptions::default()).unwrap();
+    assert_eq!(settings.typescript.line_width, 80);
+    assert_eq!(settings.typescript.indent_width, 2);
+    assert!(!settings.typescript.use_tabs);
+    assert!(settings.typescript.quote_style == QuoteStyle::PreferDouble);
+    assert!(settings.markdown.text_wrap == TextWrap::Always);
+
+    assert_eq!(parse_fmt_config("{}").unwrap(), FmtConfig::default());
+    assert!(parse_fmt_config(r#"{ "fmt": { "lineWidht": 100 } }"#).is_err());
+    assert!(parse_fmt_config(r#"{ "fmt": { "useTabs": "yes" } }"#).is_err());
+    let config =
+      parse_fmt_config(r#"{ "fmt": { "proseWrap": "sometimes" } }"#).unwrap();
+    assert!(
+      get_settings(&resolve_options(&FmtOptions::default(), &config)).is_err()
+    );
+  }
+
+  #[test]
+  fn fmt_options_take_precedence() {
+    let config = FmtConfig {
+      line_width: Some(100),
+      indent_width: Some(4),
+      single_quote: Some(true),
+      prose_wrap: Some("never".to_string()),
+      ..Default::default()
+    };
+    let options = FmtOptions {
+      line_width: Some(60),
+      use_tabs: Some(true),
+      single_quote: Some(false),
+      ..Default::default()
+    };
+    assert_eq!(
+      resolve_options(&options, &config),
+      FmtOptions {
+        line_width: Some(60),
+        indent_width: Some(4),
+        use_tabs: Some(true),
+        single_quote: Some(false),
+        prose_wrap: Some("never".to_string()),
+      }
+    );
+  }
+
+  #[test]
+  fn format_markdown_code_blocks() {
+    let options = FmtOptions {
+      single_quote: Some(true),
+      prose_wrap: Some("never".to_string()),
+      ..Default::default()
+    };
+    let settings = get_settings(&options).unwrap();
+    let text = "# Title\n\nSome prose that is split\nover two lines.\n\n```ts\nconst a = {b:\"c\"}\n```\n";
+    assert_eq!(
+      format_markdown(text, &settings).unwrap(),
+      "# Title\n\nSome prose that is split over two lines.\n\n```ts\nconst a = { b: 'c' };\n```\n"
+    );
+  }
+
+  #[test]
+  fn format_jsonc_comments() {
+    let settings = get_settings(&FmtOptions::default()).unwrap();
+    let text =
+      "{\n// The name.\n\"name\":   \"deno\", /* inline */ \"n\": [1,2]}";
+    assert_eq!(
+      format_json(text, &settings).unwrap(),
+      "{\n  // The name.\n  \"name\": \"deno\", /* inline */\n  \"n\": [1, 2]\n}\n"
+    );
+  }
+
+  #[test]
+  fn collect_fmt_files_include_exclude() {
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    let dir = canonicalize_path(temp_dir.path()).unwrap();
+    for path in &[
+      "src/a.ts",
+      "src/b.generated.ts",
+      "src/sub/c.md",
+      "other/d.json",
+      "e.js",
+    ] {
+      let path = dir.join(path);
+      fs::create_dir_all(path.parent().unwrap()).unwrap();
+      fs::write(path, "").unwrap();
+    }
+    let config_path = dir.join("deno.json");
+    let config = Some((
+      config_path,
+      FmtConfig {
+        include: vec!["src".to_string(), "e.js".to_string()],
+        exclude: vec!["**/*.generated.ts".to_string()],
+        ..Default::default()
+      },
+    ));
+
+    let mut files = collect_fmt_files(&[], &[], &config).unwrap();
+    files.sort();
+    assert_eq!(
+      files,
+      vec![
+        dir.join("e.js"),
+        dir.join("src/a.ts"),
+        dir.join("src/sub/c.md")
+      ]
+    );
+
+    // Files passed on the command line replace the included ones, and
+    // `--ignore` the excluded ones.
+    let mut files =
+      collect_fmt_files(&[dir.join("other"), dir.join("src")], &[], &config)
+        .unwrap();
+    files.sort();
+    assert_eq!(
+      files,
+      vec![
+        dir.join("other/d.json"),
+        dir.join("src/a.ts"),
+        dir.join("src/sub/c.md")
+      ]
+    );
+    let mut files =
+      collect_fmt_files(&[dir.join("src")], &[dir.join("src/sub")], &config)
+        .unwrap();
+    files.sort();
+    assert_eq!(
+      files,
+      vec![dir.join("src/a.ts"), dir.join("src/b.generated.ts")]
+    );
+  }
+}
@@ -2,8 +2,13 @@
 
 use crate::flags::Flags;
 use crate::fs_util::canonicalize_path;
+use crate::fs_util::resolve_from_cwd;
+use crate::import_map::ImportMap;
+use crate::tools::task::parse_jsonc;
 use deno_core::error::generic_error;
 use deno_core::error::AnyError;
+use deno_core::serde_json;
+use deno_core::serde_json::Value;
 use deno_core::url::Url;
 use log::Level;
 use regex::{Regex, RegexBuilder};
@@ -14,6 +19,7 @@ use std::io;
 use std::io::Write;
 #[cfg(not(windows))]
 use std::os::unix::fs::PermissionsExt;
+use std::path::Path;
 use std::path::PathBuf;
 
 lazy_static! {
@@ -26,6 +32,27 @@ lazy_static! {
     ).case_insensitive(true).build().unwrap();
 }
 
+/// The line that marks the executables written by `deno install`, which are
+/// the only ones `deno install --list` and `deno uninstall` look at.
+const GENERATED_MARKER: &str = "generated by deno install";
+
+/// The extensions of the files that are copied next to an executable.
+const EXTRA_FILE_EXTENSIONS: [&str; 4] =
+  ["import_map.json", "tsconfig.json", "lock.json", "env"];
+
+/// The flags of `deno run` that take their value as the next argument, which
+/// are skipped to find the module URL of an installed executable.
+const FLAGS_WITH_VALUE: [&str; 8] = [
+  "--location",
+  "--cert",
+  "--log-level",
+  "--seed",
+  "--import-map",
+  "--config",
+  "--lock",
+  "--env-file",
+];
+
 pub fn is_remote_url(module_url: &str) -> bool {
   let lower = module_url.to_lowercase();
   lower.starts_with("http://") || lower.starts_with("https://")
@@ -51,12 +78,12 @@ fn generate_executable_file(
   mut file_path: PathBuf,
   args: Vec<String>,
 ) -> Result<(), AnyError> {
-  let args: Vec<String> = args.iter().map(|c| format!("\"{}\"", c)).collect();
+  use shell_escape::unix::escape;
   let template = format!(
     "% generated by deno install %\n@deno.exe {} %*\n",
     args
       .iter()
-      .map(|arg| arg.replace("%", "%%"))
+      .map(|arg| format!("\"{}\"", arg.replace("%", "%%")))
       .collect::<Vec<_>>()
       .join(" ")
   );
@@ -66,10 +93,14 @@ fn generate_executable_file(
   // write file for bash
   // create filepath without extensions
   file_path.set_extension("");
+  let args: Vec<String> = args
+    .into_iter()
+    .map(|c| escape(c.into()).into_owned())
+    .collect();
   let template = format!(
     r#"#!/bin/sh
 # generated by deno install
-deno {} "$@"
+exec deno {} "$@"
 "#,
     args.join(" "),
   );
@@ -104,6 +135,90 @@ exec deno {} "$@"
   Ok(())
 }
 
+/// Returns the paths of the executables of `name` in `installation_dir`: the
+/// `.cmd` and the shell script on Windows, and the shell script elsewhere.
+fn executable_paths(installation_dir: &Path, name: &str) -> Vec<PathBuf> {
+  let file_path = installation_dir.join(name);
+  if cfg!(windows) {
+    vec![file_path.with_extension("cmd"), file_path]
+  } else {
+    vec![file_path]
+  }
+}
+
+fn is_generated_executable(path: &Path) -> bool {
+  match fs::read_to_string(path) {
+    Ok(content) => content
+      .lines()
+      .take(2)
+      .any(|line| line.contains(GENERATED_MARKER)),
+    Err(_) => false,
+  }
+}
+
+/// Makes the relative addresses and URL-like keys of an import map, and the
+/// keys of its scopes, absolute by resolving them against `base`, so that the
+/// map can be moved away from its original location.
+fn rebase_import_map(import_map: &mut Value, base: &Url) {
+  fn is_relative(specifier: &str) -> bool {
+    specifier.starts_with('/')
+      || specifier.starts_with("./")
+      || specifier.starts_with("../")
+  }
+
+  fn rebase(specifier: &str, base: &Url) -> String {
+    match base.join(specifier) {
+      Ok(url) => url.to_string(),
+      Err(_) => specifier.to_string(),
+    }
+  }
+
+  fn rebase_specifier_map(map: &mut Value, base: &Url) {
+    let map = match map.as_object_mut() {
+      Some(map) => map,
+      None => return,
+    };
+    let entries = std::mem::take(map);
+    for (key, mut address) in entries {
+      let key = if is_relative(&key) {
+        rebase(&key, base)
+      } else {
+        key
+      };
+      match &mut address {
+        Value::String(specifier) if is_relative(specifier) => {
+          *specifier = rebase(specifier, base);
+        }
+        Value::Array(addresses) => {
+          for address in addresses {
+            if let Value::String(specifier) = address {
+              if is_relative(specifier) {
+                *specifier = rebase(specifier, base);
+              }
+            }
+          }
+        }
+        _ => {}
+      }
+      map.insert(key, address);
+    }
+  }
+
+  if let Some(imports) = import_map.get_mut("imports") {
+    rebase_specifier_map(imports, base);
+  }
+  if let Some(scopes) = import_map
+    .get_mut("scopes")
+    .and_then(|scopes| scopes.as_object_mut())
+  {
+    let entries = std::mem::take(scopes);
+    for (prefix, mut map) in entries {
+      rebase_specifier_map(&mut map, base);
+      scopes.insert(rebase(&prefix, base), map);
+    }
+  }
+}
+
 fn get_installer_root() -> Result<PathBuf, io::Error> {
   if let Ok(env_dir) = env::var("DENO_INSTALL_ROOT") {
     if !env_dir.is_empty() {
@@ -189,13 +304,10 @@ pub fn install(
   };
 
   validate_name(name.as_str())?;
-  let mut file_path = installation_dir.join(&name);
+  let executable_paths = executable_paths(&installation_dir, &name);
+  let mut file_path = executable_paths[0].clone();
 
-  if cfg!(windows) {
-    file_path = file_path.with_extension("cmd");
-  }
-
-  if file_path.exists() && !force {
+  if executable_paths.iter().any(|path| path.exists()) && !force {
     return Err(generic_error(
       "Existing installation found. Aborting (Use -f to overwrite).",
     ));
@@ -245,6 +357,10 @@ pub fn install(
     executable_args.push("--lock-write".to_string());
   }
 
+  if flags.frozen_lockfile {
+    executable_args.push("--frozen-lockfile".to_string());
+  }
+
   if flags.cached_only {
     executable_args.push("--cached-only".to_string());
   }
@@ -267,19 +383,47 @@ pub fn install(
   }
 
   if let Some(import_map_path) = flags.import_map_path {
-    let mut copy_path = file_path.clone();
-    copy_path.set_extension("import_map.json");
     executable_args.push("--import-map".to_string());
-    executable_args.push(copy_path.to_str().unwrap().to_string());
-    extra_files.push((copy_path, fs::read_to_string(import_map_path)?));
+    if is_remote_url(&import_map_path) {
+      executable_args.push(import_map_path);
+    } else {
+      let import_map_path = resolve_from_cwd(Path::new(&import_map_path))?;
+      let import_map_url = Url::from_file_path(&import_map_path).unwrap();
+      let mut import_map: Value = serde_json::from_str(&fs::read_to_string(
+        &import_map_path,
+      )?)
+      .map_err(|err| {
+        generic_error(format!(
+          "Unable to parse import map {}: {}",
+          import_map_path.display(),
+          err
+        ))
+      })?;
+      rebase_import_map(&mut import_map, &import_map_url);
+      let mut copy_path = file_path.clone();
+      copy_path.set_extension("import_map.json");
+      executable_args.push(copy_path.to_str().unwrap().to_string());
+      extra_files
+        .push((copy_path, serde_json::to_string_pretty(&import_map)? + "\n"));
+    }
   }
 
   if let Some(config_path) = flags.config_path {
+    let config_path = resolve_from_cwd(Path::new(&config_path))?;
+    let mut contents = fs::read_to_string(&config_path)?;
+    // The import map embedded in the configuration file is rebased like the
+    // one of --import-map. Other configuration files are copied as is.
+    let mut config = parse_jsonc(&contents)?;
+    if ImportMap::embedded_value(&config).is_some() {
+      let config_url = Url::from_file_path(&config_path).unwrap();
+      rebase_import_map(&mut config, &config_url);
+      contents = serde_json::to_string_pretty(&config)? + "\n";
+    }
     let mut copy_path = file_path.clone();
     copy_path.set_extension("tsconfig.json");
     executable_args.push("--config".to_string());
     executable_args.push(copy_path.to_str().unwrap().to_string());
-    extra_files.push((copy_path, fs::read_to_string(config_path)?));
+    extra_files.push((copy_path, contents));
   }
 
   if let Some(lock_path) = flags.lock {
@@ -290,9 +434,25 @@ pub fn install(
     extra_files.push((copy_path, fs::read_to_string(lock_path)?));
   }
 
+  if let Some(env_file) = flags.env_file {
+    let mut copy_path = file_path.clone();
+    copy_path.set_extension("env");
+    executable_args.push("--env-file".to_string());
+    executable_args.push(copy_path.to_str().unwrap().to_string());
+    extra_files.push((copy_path, fs::read_to_string(env_file)?));
+  }
+
   executable_args.push(module_url.to_string());
   executable_args.extend_from_slice(&args);
 
+  // Files copied by a previous installation that this one doesn't use would
+  // otherwise be left behind.
+  for extension in EXTRA_FILE_EXTENSIONS.iter() {
+    let extra_file_path = file_path.with_extension(extension);
+    if !extra_files.iter().any(|(path, _)| *path == extra_file_path) {
+      remove_file_if_exists(&extra_file_path)?;
+    }
+  }
   generate_executable_file(file_path.to_owned(), executable_args)?;
   for (path, contents) in extra_files {
     fs::write(path, contents)?;
@@ -318,6 +478,208 @@ pub fn install(
   Ok(())
 }
 
+/// An executable installed by `deno install`, as listed by
+/// `deno install --list`.
+#[derive(Debug, PartialEq)]
+struct InstalledExecutable {
+  name: String,
+  module_url: String,
+  /// The flags passed to `deno run`.
+  flags: Vec<String>,
+  /// The arguments passed to the module.
+  args: Vec<String>,
+}
+
+/// Returns the executables installed in `installation_dir`, sorted by name.
+/// They are read from the shell scripts, which are generated on all
+/// platforms.
+fn installed_executables(
+  installation_dir: &Path,
+) -> Result<Vec<InstalledExecutable>, AnyError> {
+  let entries = match fs::read_dir(installation_dir) {
+    Ok(entries) => entries,
+    Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(vec![]),
+    Err(err) => return Err(err.into()),
+  };
+  let mut executables = vec![];
+  for entry in entries {
+    let path = entry?.path();
+    let name = match path.file_name().and_then(|name| name.to_str()) {
+      Some(name) if EXEC_NAME_RE.is_match(name) => name.to_string(),
+      _ => continue,
+    };
+    let content = match fs::read_to_string(&path) {
+      Ok(content) => content,
+      Err(_) => continue,
+    };
+    if let Some(executable) = parse_executable(name, &content) {
+      executables.push(executable);
+    }
+  }
+  executables.sort_by(|a, b| a.name.cmp(&b.name));
+  Ok(executables)
+}
+
+/// Parses a shell script generated by `generate_executable_file()`.
+fn parse_executable(
+  name: String,
+  content: &str,
+) -> Option<InstalledExecutable> {
+  let mut lines = content.lines();
+  if lines.next()? != "#!/bin/sh" || !lines.next()?.contains(GENERATED_MARKER) {
+    return None;
+  }
+  let command = lines.next()?;
+  let command = command.strip_prefix("exec ").unwrap_or(command);
+  let command = command.strip_prefix("deno ")?;
+  let command = command.strip_suffix(r#" "$@""#).unwrap_or(command);
+  let words = split_shell_words(command);
+  let mut words = words.into_iter();
+  if words.next()? != "run" {
+    return None;
+  }
+  let mut flags = vec![];
+  while let Some(word) = words.next() {
+    if !word.starts_with('-') {
+      return Some(InstalledExecutable {
+        name,
+        module_url: word,
+        flags,
+        args: words.collect(),
+      });
+    }
+    let takes_value = FLAGS_WITH_VALUE.contains(&word.as_str());
+    flags.push(word);
+    if takes_value {
+      flags.push(words.next()?);
+    }
+  }
+  None
+}
+
+/// Splits a command line into words, undoing the quoting of
+/// `shell_escape::unix::escape()` and of the double-quoted arguments of the
+/// scripts generated by older versions.
+fn split_shell_words(command: &str) -> Vec<String> {
+  let mut words = vec![];
+  let mut word: Option<String> = None;
+  let mut chars = command.chars();
+  while let Some(c) = chars.next() {
+    match c {
+      ' ' | '\t' => {
+        if let Some(word) = word.take() {
+          words.push(word);
+        }
+      }
+      '\'' => {
+        let word = word.get_or_insert_with(String::new);
+        for c in &mut chars {
+          if c == '\'' {
+            break;
+          }
+          word.push(c);
+        }
+      }
+      '"' => {
+        let word = word.get_or_insert_with(String::new);
+        while let Some(c) = chars.next() {
+          match c {
+            '"' => break,
+            '\\' => match chars.next() {
+              Some(c @ '"') | Some(c @ '\\') | Some(c @ '$')
+              | Some(c @ '`') => word.push(c),
+              Some(c) => {
+                word.push('\\');
+                word.push(c);
+              }
+              None => word.push('\\'),
+            },
+            c => word.push(c),
+          }
+        }
+      }
+      '\\' => {
+        let word = word.get_or_insert_with(String::new);
+        if let Some(c) = chars.next() {
+          word.push(c);
+        }
+      }
+      c => word.get_or_insert_with(String::new).push(c),
+    }
+  }
+  if let Some(word) = word {
+    words.push(word);
+  }
+  words
+}
+
+pub fn list(root: Option<PathBuf>) -> Result<(), AnyError> {
+  let root = match root {
+    Some(root) => root,
+    None => get_installer_root()?,
+  };
+  let installation_dir = root.join("bin");
+  let executables = installed_executables(&installation_dir)?;
+  if executables.is_empty() {
+    println!("No executables installed in {}", installation_dir.display());
+    return Ok(());
+  }
+  for executable in executables {
+    println!("{} {}", executable.name, executable.module_url);
+    if !executable.flags.is_empty() {
+      println!("  flags: {}", executable.flags.join(" "));
+    }
+    if !executable.args.is_empty() {
+      println!("  args: {}", executable.args.join(" "));
+    }
+  }
+  Ok(())
+}
+
+/// Removes the executables of `name` and the files copied next to them.
+/// Files that weren't generated by `deno install` are left alone.
+pub fn uninstall(name: &str, root: Option<PathBuf>) -> Result<(), AnyError> {
+  validate_name(name)?;
+  let root = match root {
+    Some(root) => root,
+    None => get_installer_root()?,
+  };
+  let installation_dir = root.join("bin");
+
+  let mut removed = vec![];
+  for path in executable_paths(&installation_dir, name) {
+    if is_generated_executable(&path) {
+      fs::remove_file(&path)?;
+      removed.push(path);
+    }
+  }
+  if removed.is_empty() {
+    return Err(generic_error(format!("No installation found for {}", name)));
+  }
+  let file_path = installation_dir.join(name);
+  for extension in EXTRA_FILE_EXTENSIONS.iter() {
+    let extra_file_path = file_path.with_extension(extension);
+    if remove_file_if_exists(&extra_file_path)? {
+      removed.push(extra_file_path);
+    }
+  }
+
+  println!("✅ Successfully uninstalled {}", name);
+  for path in removed {
+    println!("deleted {}", path.display());
+  }
+  Ok(())
+}
+
+/// Returns whether the file existed.
+fn remove_file_if_exists(path: &Path) -> Result<bool, io::Error> {
+  match fs::remove_file(path) {
+    Ok(()) => Ok(true),
+    Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(false),
+    Err(err) => Err(err),
+  }
+}
+
 fn is_in_path(dir: &PathBuf) -> bool {
   if let Some(paths) = env::var_os("PATH") {
     for p in env::split_paths(&paths) {
@@ -868,4 +1230,198 @@ mod tests {
     let status = Command::new(file_path).spawn().unwrap().wait().unwrap();
     assert!(status.success());
   }
+
+  #[test]
+  fn install_with_import_map_and_env_file() {
+    let temp_dir = TempDir::new().expect("tempdir fail");
+    let bin_dir = temp_dir.path().join("bin");
+    let import_map_path = temp_dir.path().join("import_map.json");
+    fs::write(
+      &import_map_path,
+      r#"{ "imports": { "lib": "./lib.ts", "std/": "https://deno.land/std/" } }"#,
+    )
+    .unwrap();
+    let env_file_path = temp_dir.path().join(".env");
+    fs::write(&env_file_path, "GREETING=hello\n").unwrap();
+
+    install(
+      Flags {
+        import_map_path: Some(import_map_path.to_string_lossy().to_string()),
+        env_file: Some(env_file_path.to_string_lossy().to_string()),
+        ..Flags::default()
+      },
+      "http://localhost:4545/cli/tests/cat.ts",
+      vec![],
+      Some("echo_test".to_string()),
+      Some(temp_dir.path().to_path_buf()),
+      false,
+    )
+    .expect("Install failed");
+
+    let content =
+      fs::read_to_string(bin_dir.join("echo_test.import_map.json")).unwrap();
+    let import_map: Value = serde_json::from_str(&content).unwrap();
+    let lib_url = Url::from_file_path(temp_dir.path().join("lib.ts")).unwrap();
+    assert_eq!(import_map["imports"]["lib"], lib_url.to_string());
+    assert_eq!(import_map["imports"]["std/"], "https://deno.land/std/");
+    let content = fs::read_to_string(bin_dir.join("echo_test.env")).unwrap();
+    assert_eq!(content, "GREETING=hello\n");
+
+    let executables = installed_executables(&bin_dir).unwrap();
+    assert_eq!(executables.len(), 1);
+    assert_eq!(executables[0].name, "echo_test");
+    assert_eq!(
+      executables[0].module_url,
+      "http://localhost:4545/cli/tests/cat.ts"
+    );
+    assert_eq!(executables[0].flags[0], "--import-map");
+    assert_eq!(executables[0].flags[2], "--env-file");
+    assert_eq!(
+      Path::new(&executables[0].flags[3]),
+      bin_dir.join("echo_test.env")
+    );
+  }
+
+  #[test]
+  fn install_force_removes_stale_files() {
+    let temp_dir = TempDir::new().expect("tempdir fail");
+    let bin_dir = temp_dir.path().join("bin");
+    let config_file_path = temp_dir.path().join("tsconfig.json");
+    fs::write(&config_file_path, "{}").unwrap();
+
+    install(
+      Flags {
+        config_path: Some(config_file_path.to_string_lossy().to_string()),
+        ..Flags::default()
+      },
+      "http://localhost:4545/cli/tests/cat.ts",
+      vec![],
+      Some("echo_test".to_string()),
+      Some(temp_dir.path().to_path_buf()),
+      false,
+    )
+    .expect("Install failed");
+    assert!(bin_dir.join("echo_test.tsconfig.json").exists());
+
+    install(
+      Flags::default(),
+      "http://localhost:4545/cli/tests/cat.ts",
+      vec![],
+      Some("echo_test".to_string()),
+      Some(temp_dir.path().to_path_buf()),
+      true,
+    )
+    .expect("Install failed");
+    assert!(!bin_dir.join("echo_test.tsconfig.json").exists());
+  }
+
+  #[test]
+  fn install_with_config_import_map() {
+    let temp_dir = TempDir::new().expect("tempdir fail");
+    let bin_dir = temp_dir.path().join("bin");
+    let config_file_path = temp_dir.path().join("deno.json");
+    fs::write(
+      &config_file_path,
+      r#"{
+  // comments are allowed
+  "compilerOptions": { "strict": true },
+  "scopes": { "./vendor/": { "lib": "./lib.ts" } }
+}"#,
+    )
+    .unwrap();
+
+    install(
+      Flags {
+        config_path: Some(config_file_path.to_string_lossy().to_string()),
+        ..Flags::default()
+      },
+      "http://localhost:4545/cli/tests/cat.ts",
+      vec![],
+      Some("echo_test".to_string()),
+      Some(temp_dir.path().to_path_buf()),
+      false,
+    )
+    .expect("Install failed");
+
+    let content =
+      fs::read_to_string(bin_dir.join("echo_test.tsconfig.json")).unwrap();
+    let config: Value = serde_json::from_str(&content).unwrap();
+    let scope_url =
+      Url::from_directory_path(temp_dir.path().join("vendor")).unwrap();
+    let lib_url = Url::from_file_path(temp_dir.path().join("lib.ts")).unwrap();
+    assert_eq!(config["compilerOptions"]["strict"], true);
+    assert_eq!(
+      config["scopes"][scope_url.as_str()]["lib"],
+      lib_url.to_string()
+    );
+  }
+
+  #[test]
+  fn uninstall_removes_installation() {
+    let temp_dir = TempDir::new().expect("tempdir fail");
+    let bin_dir = temp_dir.path().join("bin");
+    let lock_file_path = temp_dir.path().join("lock.json");
+    fs::write(&lock_file_path, "{}").unwrap();
+
+    install(
+      Flags {
+        lock: Some(lock_file_path),
+        ..Flags::default()
+      },
+      "http://localhost:4545/cli/tests/cat.ts",
+      vec![],
+      Some("echo_test".to_string()),
+      Some(temp_dir.path().to_path_buf()),
+      false,
+    )
+    .expect("Install failed");
+    // An executable that wasn't installed by deno is left alone.
+    fs::write(bin_dir.join("other"), "#!/bin/sh\necho other\n").unwrap();
+
+    uninstall("echo_test", Some(temp_dir.path().to_path_buf()))
+      .expect("Uninstall failed");
+    assert!(!bin_dir.join("echo_test").exists());
+    assert!(!bin_dir.join("echo_test.cmd").exists());
+    assert!(!bin_dir.join("echo_test.lock.json").exists());
+    assert!(installed_executables(&bin_dir).unwrap().is_empty());
+
+    let err =
+      uninstall("echo_test", Some(temp_dir.path().to_path_buf())).unwrap_err();
+    assert_eq!(err.to_string(), "No installation found for echo_test");
+    let err =
+      uninstall("other", Some(temp_dir.path().to_path_buf())).unwrap_err();
+    assert_eq!(err.to_string(), "No installation found for other");
+    assert!(bin_dir.join("other").exists());
+  }
+
+  #[test]
+  fn parse_executable_shims() {
+    let unix = r#"#!/bin/sh
+# generated by deno install
+exec deno run '--allow-read' '--seed' '1' 'https://example.com/mod.ts' 'it'\''s' "$@"
+"#;
+    let windows = r#"#!/bin/sh
+# generated by deno install
+deno "run" "--allow-read" "--seed" "1" "https://example.com/mod.ts" "it's" "$@"
+"#;
+    for content in &[unix, windows] {
+      assert_eq!(
+        parse_executable("mod".to_string(), content),
+        Some(InstalledExecutable {
+          name: "mod".to_string(),
+          module_url: "https://example.com/mod.ts".to_string(),
+          flags: vec![
+            "--allow-read".to_string(),
+            "--seed".to_string(),
+            "1".to_string()
+          ],
+          args: vec!["it's".to_string()],
+        })
+      );
+    }
+    assert_eq!(
+      parse_executable("other".to_string(), "#!/bin/sh\necho other\n"),
+      None
+    );
+  }
 }
@@ -12,6 +12,8 @@ use crate::fmt_errors;
 use crate::fs_util::{collect_files, is_supported_ext};
 use crate::media_type::MediaType;
 use crate::tools::fmt::run_parallelized;
+use crate::tools::lint_fix;
+use crate::tools::lint_fix::LintFix;
 use deno_core::error::{generic_error, AnyError, JsStackFrame};
 use deno_core::serde_json;
 use deno_lint::diagnostic::LintDiagnostic;
@@ -20,6 +22,7 @@ use deno_lint::linter::LinterBuilder;
 use deno_lint::rules;
 use deno_lint::rules::LintRule;
 use serde::Serialize;
+use std::cmp::Ordering as CmpOrdering;
 use std::fs;
 use std::io::{stdin, Read};
 use std::path::PathBuf;
@@ -27,6 +30,13 @@ use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex};
 use swc_ecmascript::parser::Syntax;
 
+/// Fixes can conflict with each other or make new ones possible, so they are
+/// applied in passes, until there are none left or this many passes ran.
+const MAX_FIX_PASSES: usize = 10;
+
+/// The diagnostics of a file, along with their fixes.
+type FileDiagnostics = Vec<(LintDiagnostic, Option<LintFix>)>;
+
 pub enum LintReporterKind {
   Pretty,
   Json,
@@ -43,8 +53,12 @@ pub async fn lint_files(
   args: Vec<PathBuf>,
   ignore: Vec<PathBuf>,
   json: bool,
+  fix: bool,
 ) -> Result<(), AnyError> {
   if args.len() == 1 && args[0].to_string_lossy() == "-" {
+    if fix {
+      return Err(generic_error("--fix can't be used when linting stdin"));
+    }
     return lint_stdin(json);
   }
   let target_files = collect_files(&args, &ignore, is_supported_ext)?;
@@ -64,15 +78,18 @@ pub async fn lint_files(
     let reporter_lock = reporter_lock.clone();
     let has_error = has_error.clone();
     move |file_path| {
-      let r = lint_file(file_path.clone());
+      let r = lint_file(file_path.clone(), fix);
       let mut reporter = reporter_lock.lock().unwrap();
 
       match r {
-        Ok((mut file_diagnostics, source)) => {
-          sort_diagnostics(&mut file_diagnostics);
-          for d in file_diagnostics.iter() {
+        Ok((file_diagnostics, source)) => {
+          for (d, fix) in file_diagnostics.iter() {
             has_error.store(true, Ordering::Relaxed);
-            reporter.visit_diagnostic(&d, source.split('\n').collect());
+            reporter.visit_diagnostic(
+              &d,
+              fix.as_ref(),
+              source.split('\n').collect(),
+            );
           }
         }
         Err(err) => {
@@ -134,20 +151,68 @@ pub fn create_linter(syntax: Syntax, rules: Vec<Box<dyn LintRule>>) -> Linter {
     .build()
 }
 
+/// Lint `source` with the recommended rules, and get the diagnostics sorted
+/// by position along with their fixes.
+fn lint_source(
+  file_name: &str,
+  source: &str,
+  media_type: &MediaType,
+) -> Result<FileDiagnostics, AnyError> {
+  let syntax = ast::get_syntax(media_type);
+  let lint_rules = rules::get_recommended_rules();
+  let mut linter = create_linter(syntax, lint_rules);
+
+  let (_, mut diagnostics) =
+    linter.lint(file_name.to_string(), source.to_string())?;
+  diagnostics.sort_by(compare_diagnostics);
+  let fixes = lint_fix::get_fixes(file_name, source, media_type, &diagnostics);
+
+  Ok(diagnostics.into_iter().zip(fixes).collect())
+}
+
+/// Lint a file, applying the fixes to it first if `fix` is set, in which
+/// case the diagnostics are the ones left after fixing it.
 fn lint_file(
   file_path: PathBuf,
-) -> Result<(Vec<LintDiagnostic>, String), AnyError> {
+  fix: bool,
+) -> Result<(FileDiagnostics, String), AnyError> {
   let file_name = file_path.to_string_lossy().to_string();
-  let source_code = fs::read_to_string(&file_path)?;
+  let original_source = fs::read_to_string(&file_path)?;
   let media_type = MediaType::from(&file_path);
-  let syntax = ast::get_syntax(&media_type);
 
-  let lint_rules = rules::get_recommended_rules();
-  let mut linter = create_linter(syntax, lint_rules);
+  let mut source = original_source.clone();
+  let mut diagnostics = lint_source(&file_name, &source, &media_type)?;
+  if !fix {
+    return Ok((diagnostics, source));
+  }
+
+  let original_diagnostics = diagnostics.clone();
+  for _ in 0..MAX_FIX_PASSES {
+    let fixed_source = lint_fix::apply_fixes(
+      &source,
+      diagnostics.iter().filter_map(|(_, fix)| fix.as_ref()),
+    );
+    if fixed_source == source {
+      break;
+    }
+    source = fixed_source;
+    diagnostics = lint_source(&file_name, &source, &media_type)?;
+  }
 
-  let (_, file_diagnostics) = linter.lint(file_name, source_code.clone())?;
+  if source != original_source {
+    // Don't overwrite changes made while the file was being linted.
+    if fs::read_to_string(&file_path)? != original_source {
+      eprintln!(
+        "{} {} changed while it was being fixed, so it was left unchanged",
+        colors::yellow("Warning"),
+        file_name
+      );
+      return Ok((original_diagnostics, original_source));
+    }
+    fs::write(&file_path, &source)?;
+  }
 
-  Ok((file_diagnostics, source_code))
+  Ok((diagnostics, source))
 }
 
 /// Lint stdin and write result to stdout.
@@ -165,19 +230,17 @@ fn lint_stdin(json: bool) -> Result<(), AnyError> {
     LintReporterKind::Pretty
   };
   let mut reporter = create_reporter(reporter_kind);
-  let lint_rules = rules::get_recommended_rules();
-  let syntax = ast::get_syntax(&MediaType::TypeScript);
-  let mut linter = create_linter(syntax, lint_rules);
   let mut has_error = false;
   let pseudo_file_name = "_stdin.ts";
-  match linter
-    .lint(pseudo_file_name.to_string(), source.clone())
-    .map_err(|e| e.into())
-  {
-    Ok((_, diagnostics)) => {
-      for d in diagnostics {
+  match lint_source(pseudo_file_name, &source, &MediaType::TypeScript) {
+    Ok(diagnostics) => {
+      for (d, fix) in diagnostics {
         has_error = true;
-        reporter.visit_diagnostic(&d, source.split('\n').collect());
+        reporter.visit_diagnostic(
+          &d,
+          fix.as_ref(),
+          source.split('\n').collect(),
+        );
       }
     }
     Err(err) => {
@@ -196,7 +259,12 @@ fn lint_stdin(json: bool) -> Result<(), AnyError> {
 }
 
 trait LintReporter {
-  fn visit_diagnostic(&mut self, d: &LintDiagnostic, source_lines: Vec<&str>);
+  fn visit_diagnostic(
+    &mut self,
+    d: &LintDiagnostic,
+    fix: Option<&LintFix>,
+    source_lines: Vec<&str>,
+  );
   fn visit_error(&mut self, file_path: &str, err: &AnyError);
   fn close(&mut self, check_count: usize);
 }
@@ -218,7 +286,12 @@ impl PrettyLintReporter {
 }
 
 impl LintReporter for PrettyLintReporter {
-  fn visit_diagnostic(&mut self, d: &LintDiagnostic, source_lines: Vec<&str>) {
+  fn visit_diagnostic(
+    &mut self,
+    d: &LintDiagnostic,
+    _fix: Option<&LintFix>,
+    source_lines: Vec<&str>,
+  ) {
     self.lint_count += 1;
 
     let pretty_message =
@@ -311,9 +384,17 @@ pub fn format_diagnostic(
   }
 }
 
+#[derive(Serialize)]
+struct JsonLintDiagnostic {
+  #[serde(flatten)]
+  diagnostic: LintDiagnostic,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  fix: Option<LintFix>,
+}
+
 #[derive(Serialize)]
 struct JsonLintReporter {
-  diagnostics: Vec<LintDiagnostic>,
+  diagnostics: Vec<JsonLintDiagnostic>,
   errors: Vec<LintError>,
 }
 
@@ -327,8 +408,16 @@ impl JsonLintReporter {
 }
 
 impl LintReporter for JsonLintReporter {
-  fn visit_diagnostic(&mut self, d: &LintDiagnostic, _source_lines: Vec<&str>) {
-    self.diagnostics.push(d.clone());
+  fn visit_diagnostic(
+    &mut self,
+    d: &LintDiagnostic,
+    fix: Option<&LintFix>,
+    _source_lines: Vec<&str>,
+  ) {
+    self.diagnostics.push(JsonLintDiagnostic {
+      diagnostic: d.clone(),
+      fix: fix.cloned(),
+    });
   }
 
   fn visit_error(&mut self, file_path: &str, err: &AnyError) {
@@ -339,26 +428,26 @@ impl LintReporter for JsonLintReporter {
   }
 
   fn close(&mut self, _check_count: usize) {
-    sort_diagnostics(&mut self.diagnostics);
+    self
+      .diagnostics
+      .sort_by(|a, b| compare_diagnostics(&a.diagnostic, &b.diagnostic));
     let json = serde_json::to_string_pretty(&self);
     eprintln!("{}", json.unwrap());
   }
 }
 
-fn sort_diagnostics(diagnostics: &mut Vec<LintDiagnostic>) {
-  // Sort so that we guarantee a deterministic output which is useful for tests
-  diagnostics.sort_by(|a, b| {
-    use std::cmp::Ordering;
-    let file_order = a.filename.cmp(&b.filename);
-    match file_order {
-      Ordering::Equal => {
-        let line_order = a.range.start.line.cmp(&b.range.start.line);
-        match line_order {
-          Ordering::Equal => a.range.start.col.cmp(&b.range.start.col),
-          _ => line_order,
-        }
+// Diagnostics are sorted so that we guarantee a deterministic output which is
+// useful for tests, and so that fixes are applied in order.
+fn compare_diagnostics(a: &LintDiagnostic, b: &LintDiagnostic) -> CmpOrdering {
+  let file_order = a.filename.cmp(&b.filename);
+  match file_order {
+    CmpOrdering::Equal => {
+      let line_order = a.range.start.line.cmp(&b.range.start.line);
+      match line_order {
+        CmpOrdering::Equal => a.range.start.col.cmp(&b.range.start.col),
+        _ => line_order,
       }
-      _ => file_order,
     }
-  });
+    _ => file_order,
+  }
 }
@@ -0,0 +1,500 @@
+// Copyright 2018-2021 the Deno authors. All rights reserved. MIT license.
+
+//! Automatic fixes for some of the problems reported by `deno lint`.
+//!
+//! A fix is a set of text edits on the linted source. `deno lint --fix`
+//! applies them, and `deno lint --json` reports them with the diagnostics so
+//! that editors can offer them.
+use crate::ast;
+use crate::media_type::MediaType;
+use deno_lint::diagnostic::LintDiagnostic;
+use deno_lint::diagnostic::Position;
+use deno_lint::diagnostic::Range;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::collections::HashSet;
+use swc_ecmascript::ast::AssignPatProp;
+use swc_ecmascript::ast::ForInStmt;
+use swc_ecmascript::ast::ForOfStmt;
+use swc_ecmascript::ast::ImportNamedSpecifier;
+use swc_ecmascript::ast::ObjectPatProp;
+use swc_ecmascript::ast::Pat;
+use swc_ecmascript::ast::VarDecl;
+use swc_ecmascript::ast::VarDeclKind;
+use swc_ecmascript::ast::VarDeclOrPat;
+use swc_ecmascript::visit::Node;
+use swc_ecmascript::visit::Visit;
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TextEdit {
+  /// The replaced range, which is empty for insertions. Byte positions are
+  /// offsets in the source.
+  pub range: Range,
+  pub new_text: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct LintFix {
+  pub description: String,
+  pub edits: Vec<TextEdit>,
+}
+
+/// Get the fix of each of the diagnostics reported for `source`, for the
+/// rules that have one.
+pub fn get_fixes(
+  file_name: &str,
+  source: &str,
+  media_type: &MediaType,
+  diagnostics: &[LintDiagnostic],
+) -> Vec<Option<LintFix>> {
+  if diagnostics.is_empty() {
+    return vec![];
+  }
+  let parsed_module = match ast::parse(file_name, source, media_type) {
+    Ok(parsed_module) => parsed_module,
+    Err(_) => return diagnostics.iter().map(|_| None).collect(),
+  };
+  let mut collector = FixInfoCollector {
+    parsed_module: &parsed_module,
+    let_decls: HashMap::new(),
+    shorthand_props: HashSet::new(),
+    shorthand_imports: HashSet::new(),
+  };
+  parsed_module.visit_with(&mut collector);
+
+  let fixer = Fixer::new(source, collector, diagnostics);
+  diagnostics.iter().map(|d| fixer.get_fix(d)).collect()
+}
+
+/// Apply the edits of `fixes` to `source`. Fixes are taken in order, and
+/// those with edits that overlap the ones of an earlier fix are left out, so
+/// they can be tried again on the result.
+pub fn apply_fixes<'a>(
+  source: &str,
+  fixes: impl IntoIterator<Item = &'a LintFix>,
+) -> String {
+  let mut edits: Vec<&TextEdit> = vec![];
+  for fix in fixes {
+    let overlaps = fix
+      .edits
+      .iter()
+      .any(|edit| edits.iter().any(|other| overlap(&edit.range, &other.range)));
+    if !overlaps {
+      edits.extend(&fix.edits);
+    }
+  }
+  edits.sort_by_key(|edit| edit.range.start.byte_pos);
+
+  let mut result = String::with_capacity(source.len());
+  let mut last_end = 0;
+  for edit in edits {
+    result.push_str(&source[last_end..edit.range.start.byte_pos]);
+    result.push_str(&edit.new_text);
+    last_end = edit.range.end.byte_pos;
+  }
+  result.push_str(&source[last_end..]);
+  result
+}
+
+fn overlap(a: &Range, b: &Range) -> bool {
+  // Two insertions at the same position would depend on their order.
+  a.start.byte_pos == b.start.byte_pos
+    || (a.start.byte_pos < b.end.byte_pos && b.start.byte_pos < a.end.byte_pos)
+}
+
+/// A `let` declaration, which `prefer-const` may turn into a `const` one.
+struct LetDecl {
+  /// The offset of the `let` keyword.
+  start: usize,
+  /// Whether the declaration gives a value to each binding, which `const`
+  /// requires.
+  initialized: bool,
+  /// The offsets of the identifiers it binds.
+  bindings: Vec<usize>,
+}
+
+/// Collects the syntax that fixes depend on and diagnostics don't tell.
+struct FixInfoCollector<'a> {
+  parsed_module: &'a ast::ParsedModule,
+  /// The `let` declarations by their offset.
+  let_decls: HashMap<usize, LetDecl>,
+  /// The offsets of the identifiers in shorthand object patterns, like `a`
+  /// in `const { a } = b`.
+  shorthand_props: HashSet<usize>,
+  /// The offsets of the identifiers in named imports without `as`.
+  shorthand_imports: HashSet<usize>,
+}
+
+impl<'a> FixInfoCollector<'a> {
+  fn add_let_decl(&mut self, decl: &VarDecl, initialized: bool) {
+    if decl.kind != VarDeclKind::Let {
+      return;
+    }
+    let start = self.parsed_module.get_byte_offset(decl.span.lo);
+    if self.let_decls.contains_key(&start) {
+      return;
+    }
+    let mut bindings = vec![];
+    for declarator in &decl.decls {
+      self.collect_bindings(&declarator.name, &mut bindings);
+    }
+    self.let_decls.insert(
+      start,
+      LetDecl {
+        start,
+        initialized,
+        bindings,
+      },
+    );
+  }
+
+  fn collect_bindings(&self, pat: &Pat, bindings: &mut Vec<usize>) {
+    match pat {
+      Pat::Ident(ident) => {
+        bindings.push(self.parsed_module.get_byte_offset(ident.span.lo))
+      }
+      Pat::Array(array) => {
+        for elem in array.elems.iter().flatten() {
+          self.collect_bindings(elem, bindings);
+        }
+      }
+      Pat::Rest(rest) => self.collect_bindings(&rest.arg, bindings),
+      Pat::Object(object) => {
+        for prop in &object.props {
+          match prop {
+            ObjectPatProp::KeyValue(prop) => {
+              self.collect_bindings(&prop.value, bindings)
+            }
+            ObjectPatProp::Assign(prop) => bindings
+              .push(self.parsed_module.get_byte_offset(prop.key.span.lo)),
+            ObjectPatProp::Rest(rest) => {
+              self.collect_bindings(&rest.arg, bindings)
+            }
+          }
+        }
+      }
+      Pat::Assign(assign) => self.collect_bindings(&assign.left, bindings),
+      Pat::Invalid(_) | Pat::Expr(_) => {}
+    }
+  }
+
+  fn add_loop_head(&mut self, head: &VarDeclOrPat) {
+    // The binding of `for...in` and `for...of` loops gets a value on each
+    // iteration, so it can be `const` without an initializer.
+    if let VarDeclOrPat::VarDecl(decl) = head {
+      self.add_let_decl(decl, true);
+    }
+  }
+}
+
+impl<'a> Visit for FixInfoCollector<'a> {
+  fn visit_var_decl(&mut self, node: &VarDecl, parent: &dyn Node) {
+    let initialized = node.decls.iter().all(|d| d.init.is_some());
+    self.add_let_decl(node, initialized);
+    swc_ecmascript::visit::visit_var_decl(self, node, parent);
+  }
+
+  fn visit_for_in_stmt(&mut self, node: &ForInStmt, parent: &dyn Node) {
+    self.add_loop_head(&node.left);
+    swc_ecmascript::visit::visit_for_in_stmt(self, node, parent);
+  }
+
+  fn visit_for_of_stmt(&mut self, node: &ForOfStmt, parent: &dyn Node) {
+    self.add_loop_head(&node.left);
+    swc_ecmascript::visit::visit_for_of_stmt(self, node, parent);
+  }
+
+  fn visit_assign_pat_prop(&mut self, node: &AssignPatProp, parent: &dyn Node) {
+    self
+      .shorthand_props
+      .insert(self.parsed_module.get_byte_offset(node.key.span.lo));
+    swc_ecmascript::visit::visit_assign_pat_prop(self, node, parent);
+  }
+
+  fn visit_import_named_specifier(
+    &mut self,
+    node: &ImportNamedSpecifier,
+    _parent: &dyn Node,
+  ) {
+    if node.imported.is_none() {
+      self
+        .shorthand_imports
+        .insert(self.parsed_module.get_byte_offset(node.local.span.lo));
+    }
+  }
+}
+
+struct Fixer<'a> {
+  source: &'a str,
+  line_starts: Vec<usize>,
+  let_decls: Vec<LetDecl>,
+  /// The `let` declaration of each binding, by their offsets.
+  let_bindings: HashMap<usize, usize>,
+  shorthand_props: HashSet<usize>,
+  shorthand_imports: HashSet<usize>,
+  /// The offsets of the bindings reported by `prefer-const`.
+  never_reassigned: HashSet<usize>,
+}
+
+impl<'a> Fixer<'a> {
+  fn new(
+    source: &'a str,
+    collector: FixInfoCollector,
+    diagnostics: &[LintDiagnostic],
+  ) -> Self {
+    let line_starts = std::iter::once(0)
+      .chain(source.match_indices('\n').map(|(i, _)| i + 1))
+      .collect();
+    let let_decls: Vec<LetDecl> =
+      collector.let_decls.into_iter().map(|(_, d)| d).collect();
+    let mut let_bindings = HashMap::new();
+    for (index, decl) in let_decls.iter().enumerate() {
+      for binding in &decl.bindings {
+        let_bindings.insert(*binding, index);
+      }
+    }
+    let mut fixer = Fixer {
+      source,
+      line_starts,
+      let_decls,
+      let_bindings,
+      shorthand_props: collector.shorthand_props,
+      shorthand_imports: collector.shorthand_imports,
+      never_reassigned: HashSet::new(),
+    };
+    fixer.never_reassigned = diagnostics
+      .iter()
+      .filter(|d| d.code == "prefer-const")
+      .filter_map(|d| fixer.get_offset(&d.range.start))
+      .collect();
+    fixer
+  }
+
+  fn get_fix(&self, diagnostic: &LintDiagnostic) -> Option<LintFix> {
+    let start = self.get_offset(&diagnostic.range.start)?;
+    let end = self.get_offset(&diagnostic.range.end)?;
+    match diagnostic.code.as_str() {
+      "no-explicit-any" if &self.source[start..end] == "any" => {
+        Some(self.fix("Replace `any` with `unknown`", start, end, "unknown"))
+      }
+      "no-unused-vars" => {
+        let name: String = self.source[start..]
+          .chars()
+          .take_while(|c| c.is_alphanumeric() || *c == '_' || *c == '$')
+          .collect();
+        let description = format!("Rename `{}` to `_{}`", name, name);
+        let name_end = start + name.len();
+        if self.shorthand_props.contains(&start) {
+          let new_text = format!("{}: _{}", name, name);
+          Some(self.fix(&description, start, name_end, &new_text))
+        } else if self.shorthand_imports.contains(&start) {
+          let new_text = format!("{} as _{}", name, name);
+          Some(self.fix(&description, start, name_end, &new_text))
+        } else {
+          Some(self.fix(&description, start, start, "_"))
+        }
+      }
+      "prefer-const" => {
+        let decl = &self.let_decls[*self.let_bindings.get(&start)?];
+        // Every binding of the declaration has to be `const`.
+        if !decl.initialized
+          || !decl
+            .bindings
+            .iter()
+            .all(|binding| self.never_reassigned.contains(binding))
+        {
+          return None;
+        }
+        Some(self.fix(
+          "Use `const` instead of `let`",
+          decl.start,
+          decl.start + "let".len(),
+          "const",
+        ))
+      }
+      _ => None,
+    }
+  }
+
+  fn fix(
+    &self,
+    description: &str,
+    start: usize,
+    end: usize,
+    new_text: &str,
+  ) -> LintFix {
+    LintFix {
+      description: description.to_string(),
+      edits: vec![TextEdit {
+        range: Range {
+          start: self.get_position(start),
+          end: self.get_position(end),
+        },
+        new_text: new_text.to_string(),
+      }],
+    }
+  }
+
+  /// Columns of diagnostics count characters, not bytes.
+  fn get_offset(&self, position: &Position) -> Option<usize> {
+    let line_start = *self.line_starts.get(position.line.checked_sub(1)?)?;
+    let line = self.source[line_start..].split('\n').next().unwrap();
+    let col = line
+      .char_indices()
+      .nth(position.col)
+      .map(|(i, _)| i)
+      .unwrap_or_else(|| line.len());
+    Some(line_start + col)
+  }
+
+  fn get_position(&self, offset: usize) -> Position {
+    let line = match self.line_starts.binary_search(&offset) {
+      Ok(line) => line,
+      Err(line) => line - 1,
+    };
+    let line_start = self.line_starts[line];
+    Position {
+      line: line + 1,
+      col: self.source[line_start..offset].chars().count(),
+      byte_pos: offset,
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::tools::lint::create_linter;
+  use deno_lint::rules::LintRule;
+
+  fn lint(source: &str) -> (Vec<LintDiagnostic>, Vec<Option<LintFix>>) {
+    let media_type = MediaType::TypeScript;
+    let rules: Vec<Box<dyn LintRule>> = vec![
+      deno_lint::rules::no_empty::NoEmpty::new(),
+      deno_lint::rules::no_explicit_any::NoExplicitAny::new(),
+      deno_lint::rules::no_unused_vars::NoUnusedVars::new(),
+      deno_lint::rules::prefer_const::PreferConst::new(),
+    ];
+    let mut linter = create_linter(ast::get_syntax(&media_type), rules);
+    let (_, mut diagnostics) = linter
+      .lint("test.ts".to_string(), source.to_string())
+      .unwrap();
+    diagnostics.sort_by_key(|d| d.range.start.byte_pos);
+    let fixes = get_fixes("test.ts", source, &media_type, &diagnostics);
+    (diagnostics, fixes)
+  }
+
+  fn fix(source: &str) -> String {
+    let (_, fixes) = lint(source);
+    apply_fixes(source, fixes.iter().flatten())
+  }
+
+  #[test]
+  fn fix_no_explicit_any() {
+    let (diagnostics, fixes) = lint("export let a: any;\n");
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(
+      fixes[0],
+      Some(LintFix {
+        description: "Replace `any` with `unknown`".to_string(),
+        edits: vec![TextEdit {
+          range: Range {
+            start: Position {
+              line: 1,
+              col: 14,
+              byte_pos: 14,
+            },
+            end: Position {
+              line: 1,
+              col: 17,
+              byte_pos: 17,
+            },
+          },
+          new_text: "unknown".to_string(),
+        }],
+      })
+    );
+    assert_eq!(fix("export let a: any;\n"), "export let a: unknown;\n");
+  }
+
+  #[test]
+  fn fix_no_unused_vars() {
+    assert_eq!(
+      fix("export function f(a: number) {}\n"),
+      "export function f(_a: number) {}\n"
+    );
+    assert_eq!(
+      fix("import { a, b as c } from \"./mod.ts\";\n"),
+      "import { a as _a, b as _c } from \"./mod.ts\";\n"
+    );
+    assert_eq!(
+      fix("export function f({ a, b = 1 }: any) {}\n"),
+      "export function f({ a: _a, b: _b = 1 }: unknown) {}\n"
+    );
+  }
+
+  #[test]
+  fn fix_prefer_const() {
+    assert_eq!(
+      fix("let a = 1, b = 2;\nexport { a, b };\n"),
+      "const a = 1, b = 2;\nexport { a, b };\n"
+    );
+    // `b` is reassigned.
+    let source = "let a = 1, b = 2;\nb++;\nexport { a, b };\n";
+    assert_eq!(fix(source), source);
+    assert_eq!(
+      fix("for (let x of [1]) {\n  console.log(x);\n}\n"),
+      "for (const x of [1]) {\n  console.log(x);\n}\n"
+    );
+  }
+
+  #[test]
+  fn no_fix_for_other_rules() {
+    let (diagnostics, fixes) = lint("if (true) {}\n");
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].code, "no-empty");
+    assert_eq!(fixes, vec![None]);
+  }
+
+  #[test]
+  fn fix_positions_count_characters() {
+    let source = "// é\nexport let a: any;\n";
+    let (_, fixes) = lint(source);
+    let range = &fixes[0].as_ref().unwrap().edits[0].range;
+    assert_eq!(range.start.line, 2);
+    assert_eq!(range.start.col, 14);
+    assert_eq!(range.start.byte_pos, 20);
+    assert_eq!(fix(source), "// é\nexport let a: unknown;\n");
+  }
+
+  #[test]
+  fn apply_fixes_skips_overlapping_edits() {
+    let edit = |start: usize, end: usize, new_text: &str| LintFix {
+      description: String::new(),
+      edits: vec![TextEdit {
+        range: Range {
+          start: Position {
+            line: 1,
+            col: start,
+            byte_pos: start,
+          },
+          end: Position {
+            line: 1,
+            col: end,
+            byte_pos: end,
+          },
+        },
+        new_text: new_text.to_string(),
+      }],
+    };
+    let fixes = vec![
+      edit(4, 7, "baz"),
+      edit(5, 6, "x"),
+      edit(0, 0, "_"),
+      edit(0, 0, "-"),
+      edit(8, 11, "qux"),
+    ];
+    assert_eq!(apply_fixes("foo bar qux", &fixes), "_foo baz qux");
+  }
+}
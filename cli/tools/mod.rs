@@ -1,10 +1,17 @@
 // Copyright 2018-2021 the Deno authors. All rights reserved. MIT license.
 
+pub mod bench_runner;
 pub mod coverage;
+pub mod doc;
 pub mod fmt;
 pub mod installer;
 pub mod lint;
+pub mod lint_fix;
 pub mod repl;
+pub mod shell;
 pub mod standalone;
+pub mod task;
+pub mod test_reporter;
 pub mod test_runner;
 pub mod upgrade;
+pub mod vendor;
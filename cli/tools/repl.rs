@@ -9,6 +9,7 @@ use deno_core::error::AnyError;
 use deno_core::serde_json::json;
 use deno_core::serde_json::Value;
 use deno_runtime::inspector::InspectorSession;
+use deno_runtime::permissions::Permissions;
 use deno_runtime::worker::MainWorker;
 use rustyline::completion::Completer;
 use rustyline::error::ReadlineError;
@@ -16,10 +17,13 @@ use rustyline::highlight::Highlighter;
 use rustyline::validate::ValidationContext;
 use rustyline::validate::ValidationResult;
 use rustyline::validate::Validator;
+use rustyline::Config;
 use rustyline::Context;
 use rustyline::Editor;
 use rustyline_derive::{Helper, Hinter};
 use std::borrow::Cow;
+use std::path::Path;
+use std::path::PathBuf;
 use std::sync::mpsc::channel;
 use std::sync::mpsc::sync_channel;
 use std::sync::mpsc::Receiver;
@@ -48,6 +52,79 @@ impl Helper {
     self.message_tx.send((method.to_string(), params))?;
     self.response_rx.recv()?
   }
+
+  /// The names of the properties of the object with `object_id`, including
+  /// the ones it inherits, in completion order. Getters aren't invoked since
+  /// only the property descriptors are requested.
+  fn get_property_names(&self, object_id: &Value) -> Vec<String> {
+    let mut names = Vec::new();
+    let mut maybe_object_id = Some(object_id.clone());
+    while let Some(object_id) = maybe_object_id.take() {
+      let response = match self.post_message(
+        "Runtime.getProperties",
+        Some(json!({
+          "objectId": object_id,
+          "ownProperties": true,
+        })),
+      ) {
+        Ok(response) => response,
+        Err(_) => break,
+      };
+
+      let properties = response
+        .get("result")
+        .and_then(|r| r.as_array())
+        .into_iter()
+        .flatten();
+      let internal_properties = response
+        .get("internalProperties")
+        .and_then(|r| r.as_array())
+        .into_iter()
+        .flatten();
+      for property in properties.chain(internal_properties) {
+        let name = match property.get("name").and_then(|n| n.as_str()) {
+          Some(name) => name,
+          None => continue,
+        };
+        if name == "__proto__" || name == "[[Prototype]]" {
+          maybe_object_id = property
+            .get("value")
+            .and_then(|v| v.get("objectId"))
+            .cloned();
+        } else if property.get("symbol").is_none() && is_identifier(name) {
+          names.push(name.to_string());
+        }
+      }
+    }
+
+    sort_completions(&mut names);
+    names
+  }
+
+  /// The names of the `let`, `const` and `class` declarations of the REPL,
+  /// which aren't properties of `globalThis`.
+  fn get_global_lexical_scope_names(&self) -> Vec<String> {
+    self
+      .post_message(
+        "Runtime.globalLexicalScopeNames",
+        Some(json!({
+          "executionContextId": self.context_id,
+        })),
+      )
+      .ok()
+      .and_then(|response| {
+        response
+          .get("names")
+          .and_then(|n| n.as_array())
+          .map(|names| {
+            names
+              .iter()
+              .filter_map(|name| name.as_str().map(|name| name.to_string()))
+              .collect()
+          })
+      })
+      .unwrap_or_default()
+  }
 }
 
 fn is_word_boundary(c: char) -> bool {
@@ -58,6 +135,68 @@ fn is_word_boundary(c: char) -> bool {
   }
 }
 
+/// Sorts `names` and removes duplicates, e.g. shadowed properties. Underscore
+/// prefixed names, which are usually private, are listed last.
+fn sort_completions(names: &mut Vec<String>) {
+  names.sort_by(|a, b| (a.starts_with('_'), a).cmp(&(b.starts_with('_'), b)));
+  names.dedup();
+}
+
+fn is_identifier(name: &str) -> bool {
+  let mut chars = name.chars();
+  match chars.next() {
+    Some(c) if c.is_alphabetic() || c == '_' || c == '$' => {}
+    _ => return false,
+  }
+  chars.all(|c| c.is_alphanumeric() || c == '_' || c == '$')
+}
+
+/// The partial specifier of the import that is being typed at the end of
+/// `text`, e.g. `./mod` for `import("./mod` or `import { a } from "./mod`.
+fn get_import_specifier(text: &str) -> Option<&str> {
+  let line = &text[text.rfind('\n').map_or(0, |i| i + 1)..];
+  let quote = line.rfind(|c| c == '"' || c == '\'' || c == '`')?;
+  let before = line[..quote].trim_end();
+  if before.ends_with("import(")
+    || before.ends_with("import")
+    || before.ends_with("from")
+  {
+    Some(&line[quote + 1..])
+  } else {
+    None
+  }
+}
+
+/// The entries of the directory of `specifier`, relative to `cwd`, that
+/// complete its last path segment. Directories end with a `/`.
+fn complete_path(cwd: &Path, specifier: &str) -> (usize, Vec<String>) {
+  let (dir, prefix) = match specifier.rfind('/') {
+    Some(index) => specifier.split_at(index + 1),
+    None => ("", specifier),
+  };
+  let entries = match std::fs::read_dir(cwd.join(dir)) {
+    Ok(entries) => entries,
+    Err(_) => return (prefix.len(), Vec::new()),
+  };
+  let mut candidates: Vec<String> = entries
+    .filter_map(|entry| entry.ok())
+    .filter_map(|entry| {
+      let name = entry.file_name().to_string_lossy().to_string();
+      if !name.starts_with(prefix)
+        || (name.starts_with('.') && !prefix.starts_with('.'))
+      {
+        return None;
+      }
+      match entry.file_type() {
+        Ok(file_type) if file_type.is_dir() => Some(format!("{}/", name)),
+        _ => Some(name),
+      }
+    })
+    .collect();
+  candidates.sort();
+  (prefix.len(), candidates)
+}
+
 impl Completer for Helper {
   type Candidate = String;
 
@@ -67,6 +206,12 @@ impl Completer for Helper {
     pos: usize,
     _ctx: &Context<'_>,
   ) -> Result<(usize, Vec<String>), ReadlineError> {
+    if let Some(specifier) = get_import_specifier(&line[..pos]) {
+      let cwd = std::env::current_dir()?;
+      let (prefix_len, candidates) = complete_path(&cwd, specifier);
+      return Ok((pos - prefix_len, candidates));
+    }
+
     let start = line[..pos].rfind(is_word_boundary).map_or_else(|| 0, |i| i);
     let end = line[pos..]
       .rfind(is_word_boundary)
@@ -83,17 +228,20 @@ impl Completer for Helper {
       None => ("globalThis", fallback.as_str()),
     };
 
-    let evaluate_response = self
-      .post_message(
-        "Runtime.evaluate",
-        Some(json!({
-          "contextId": self.context_id,
-          "expression": prefix,
-          "throwOnSideEffect": true,
-          "timeout": 200,
-        })),
-      )
-      .unwrap();
+    // The receiver is only evaluated if that has no side effects, e.g. it
+    // doesn't invoke a getter that does.
+    let evaluate_response = match self.post_message(
+      "Runtime.evaluate",
+      Some(json!({
+        "contextId": self.context_id,
+        "expression": prefix,
+        "throwOnSideEffect": true,
+        "timeout": 200,
+      })),
+    ) {
+      Ok(response) => response,
+      Err(_) => return Ok((pos, Vec::new())),
+    };
 
     if evaluate_response.get("exceptionDetails").is_some() {
       let candidates = Vec::new();
@@ -102,26 +250,17 @@ impl Completer for Helper {
 
     if let Some(result) = evaluate_response.get("result") {
       if let Some(object_id) = result.get("objectId") {
-        let get_properties_response = self.post_message(
-          "Runtime.getProperties",
-          Some(json!({
-            "objectId": object_id,
-          })),
-        );
-
-        if let Ok(get_properties_response) = get_properties_response {
-          if let Some(result) = get_properties_response.get("result") {
-            let candidates = result
-              .as_array()
-              .unwrap()
-              .iter()
-              .map(|r| r.get("name").unwrap().as_str().unwrap().to_string())
-              .filter(|r| r.starts_with(&suffix[1..]))
-              .collect();
-
-            return Ok((pos - (suffix.len() - 1), candidates));
-          }
+        let mut names = self.get_property_names(object_id);
+        if word.rfind('.').is_none() {
+          names.extend(self.get_global_lexical_scope_names());
+          sort_completions(&mut names);
         }
+        let candidates = names
+          .into_iter()
+          .filter(|name| name.starts_with(&suffix[1..]))
+          .collect();
+
+        return Ok((pos - (suffix.len() - 1), candidates));
       }
     }
 
@@ -384,13 +523,168 @@ pub async fn is_closing(
   Ok(closed)
 }
 
+/// The maximum number of entries that are kept in the history file.
+const HISTORY_MAX_ENTRIES: usize = 1000;
+
+/// The history file of the REPL: `DENO_REPL_HISTORY` if it is set, unless it
+/// is empty, which disables the history, and `$DENO_DIR/history` otherwise.
+fn get_history_file(program_state: &ProgramState) -> Option<PathBuf> {
+  match std::env::var_os("DENO_REPL_HISTORY") {
+    Some(path) if path.is_empty() => None,
+    Some(path) => Some(PathBuf::from(path)),
+    None => Some(program_state.dir.root.join("history")),
+  }
+}
+
+/// Saves the history of `editor` to `history_file`. The history is written to
+/// a temporary file which then replaces `history_file`, so that a session that
+/// crashes while saving doesn't leave a truncated history behind.
+fn save_history(
+  editor: &mut Editor<Helper>,
+  history_file: &Path,
+) -> Result<(), AnyError> {
+  let dir = match history_file.parent() {
+    Some(dir) if !dir.as_os_str().is_empty() => dir.to_path_buf(),
+    _ => std::env::current_dir()?,
+  };
+  std::fs::create_dir_all(&dir)?;
+  let temp_file = tempfile::NamedTempFile::new_in(&dir)?;
+  editor.save_history(temp_file.path())?;
+  temp_file.persist(history_file)?;
+  Ok(())
+}
+
+/// Evaluates `line` in the REPL context like a line entered at the prompt,
+/// and returns its inspected result or, if it threw, the inspected exception
+/// prefixed with "Uncaught".
+async fn evaluate_line(
+  worker: &mut MainWorker,
+  session: &mut InspectorSession,
+  context_id: u64,
+  line: &str,
+) -> Result<Result<String, String>, AnyError> {
+  // It is a bit unexpected that { "foo": "bar" } is interpreted as a block
+  // statement rather than an object literal so we interpret it as an expression statement
+  // to match the behavior found in a typical prompt including browser developer tools.
+  let wrapped_line =
+    if line.trim_start().starts_with('{') && !line.trim_end().ends_with(';') {
+      format!("({})", &line)
+    } else {
+      line.to_string()
+    };
+
+  let evaluate_response = post_message_and_poll(
+    worker,
+    session,
+    "Runtime.evaluate",
+    Some(json!({
+      "expression": format!("'use strict'; void 0;\n{}", &wrapped_line),
+      "contextId": context_id,
+      "replMode": true,
+    })),
+  )
+  .await?;
+
+  // If that fails, we retry it without wrapping in parens letting the error bubble up to the
+  // user if it is still an error.
+  let evaluate_response = if evaluate_response.get("exceptionDetails").is_some()
+    && wrapped_line != line
+  {
+    post_message_and_poll(
+      worker,
+      session,
+      "Runtime.evaluate",
+      Some(json!({
+        "expression": format!("'use strict'; void 0;\n{}", &line),
+        "contextId": context_id,
+        "replMode": true,
+      })),
+    )
+    .await?
+  } else {
+    evaluate_response
+  };
+
+  let evaluate_result = evaluate_response.get("result").unwrap();
+  let evaluate_exception_details = evaluate_response.get("exceptionDetails");
+
+  if evaluate_exception_details.is_some() {
+    post_message_and_poll(
+              worker,
+              session,
+              "Runtime.callFunctionOn",
+              Some(json!({
+                "executionContextId": context_id,
+                "functionDeclaration": "function (object) { Deno[Deno.internal].lastThrownError = object; }",
+                "arguments": [
+                  evaluate_result,
+                ],
+              })),
+            ).await?;
+  } else {
+    post_message_and_poll(
+              worker,
+              session,
+              "Runtime.callFunctionOn",
+              Some(json!({
+                "executionContextId": context_id,
+                "functionDeclaration": "function (object) { Deno[Deno.internal].lastEvalResult = object; }",
+                "arguments": [
+                  evaluate_result,
+                ],
+              })),
+            ).await?;
+  }
+
+  // TODO(caspervonb) we should investigate using previews here but to keep things
+  // consistent with the previous implementation we just get the preview result from
+  // Deno.inspectArgs.
+  let inspect_response =
+    post_message_and_poll(
+      worker,
+      session,
+      "Runtime.callFunctionOn",
+      Some(json!({
+        "executionContextId": context_id,
+        "functionDeclaration": "function (object) { return Deno[Deno.internal].inspectArgs(['%o', object], { colors: !Deno.noColor }); }",
+        "arguments": [
+          evaluate_result,
+        ],
+      })),
+    ).await?;
+
+  let inspect_result = inspect_response.get("result").unwrap();
+
+  let value = inspect_result.get("value").unwrap().as_str().unwrap();
+  Ok(match evaluate_exception_details {
+    Some(_) => Err(format!("Uncaught {}", value)),
+    None => Ok(value.to_string()),
+  })
+}
+
+/// Reads the source of an `--eval-file`, which is either a path relative to
+/// the current directory or a URL.
+async fn read_eval_file(
+  program_state: &ProgramState,
+  eval_file: &str,
+) -> Result<String, AnyError> {
+  let specifier = deno_core::resolve_url_or_path(eval_file)?;
+  let file = program_state
+    .file_fetcher
+    .fetch(&specifier, &Permissions::allow_all())
+    .await?;
+  Ok(file.source)
+}
+
 pub async fn run(
   program_state: &ProgramState,
   mut worker: MainWorker,
+  maybe_eval: Option<String>,
+  maybe_eval_files: Option<Vec<String>>,
 ) -> Result<(), AnyError> {
   let mut session = worker.create_inspector_session();
 
-  let history_file = program_state.dir.root.join("deno_history.txt");
+  let history_file = get_history_file(program_state);
 
   post_message_and_poll(&mut worker, &mut session, "Runtime.enable", None)
     .await?;
@@ -423,162 +717,103 @@ pub async fn run(
     response_rx,
   };
 
-  let editor = Arc::new(Mutex::new(Editor::new()));
+  let config = Config::builder()
+    .max_history_size(HISTORY_MAX_ENTRIES)
+    .history_ignore_dups(true)
+    .build();
+  let editor = Arc::new(Mutex::new(Editor::with_config(config)));
 
   editor.lock().unwrap().set_helper(Some(helper));
 
-  editor
-    .lock()
-    .unwrap()
-    .load_history(history_file.to_str().unwrap())
-    .unwrap_or(());
+  if let Some(history_file) = &history_file {
+    editor
+      .lock()
+      .unwrap()
+      .load_history(history_file)
+      .unwrap_or(());
+  }
 
   println!("Deno {}", crate::version::deno());
   println!("exit using ctrl+d or close()");
 
   inject_prelude(&mut worker, &mut session, context_id).await?;
 
-  loop {
-    let line = read_line_and_poll(
-      &mut worker,
-      &mut session,
-      &message_rx,
-      &response_tx,
-      editor.clone(),
-    )
-    .await;
-    match line {
-      Ok(line) => {
-        // It is a bit unexpected that { "foo": "bar" } is interpreted as a block
-        // statement rather than an object literal so we interpret it as an expression statement
-        // to match the behavior found in a typical prompt including browser developer tools.
-        let wrapped_line = if line.trim_start().starts_with('{')
-          && !line.trim_end().ends_with(';')
-        {
-          format!("({})", &line)
-        } else {
-          line.clone()
-        };
+  // Errors in the code evaluated on startup are reported, but don't prevent
+  // the REPL from starting.
+  for eval_file in maybe_eval_files.unwrap_or_default() {
+    let source = match read_eval_file(program_state, &eval_file).await {
+      Ok(source) => source,
+      Err(err) => {
+        println!("error in --eval-file file {}: {}", eval_file, err);
+        continue;
+      }
+    };
+    let output =
+      evaluate_line(&mut worker, &mut session, context_id, &source).await?;
+    if let Err(err) = output {
+      println!("error in --eval-file file {}: {}", eval_file, err);
+    }
+  }
+  if let Some(eval) = maybe_eval {
+    let output =
+      evaluate_line(&mut worker, &mut session, context_id, &eval).await?;
+    if let Err(err) = output {
+      println!("error in --eval flag: {}", err);
+    }
+  }
 
-        let evaluate_response = post_message_and_poll(
-          &mut worker,
-          &mut session,
-          "Runtime.evaluate",
-          Some(json!({
-            "expression": format!("'use strict'; void 0;\n{}", &wrapped_line),
-            "contextId": context_id,
-            "replMode": true,
-          })),
-        )
-        .await?;
-
-        // If that fails, we retry it without wrapping in parens letting the error bubble up to the
-        // user if it is still an error.
-        let evaluate_response =
-          if evaluate_response.get("exceptionDetails").is_some()
-            && wrapped_line != line
-          {
-            post_message_and_poll(
-              &mut worker,
-              &mut session,
-              "Runtime.evaluate",
-              Some(json!({
-                "expression": format!("'use strict'; void 0;\n{}", &line),
-                "contextId": context_id,
-                "replMode": true,
-              })),
-            )
-            .await?
-          } else {
-            evaluate_response
-          };
-
-        // We check for close and break here instead of making it a loop condition to get
-        // consistent behavior in when the user evaluates a call to close().
-        if is_closing(&mut worker, &mut session, context_id).await? {
+  let mut history_changed = false;
+  if !is_closing(&mut worker, &mut session, context_id).await? {
+    loop {
+      let line = read_line_and_poll(
+        &mut worker,
+        &mut session,
+        &message_rx,
+        &response_tx,
+        editor.clone(),
+      )
+      .await;
+      match line {
+        Ok(line) => {
+          let output =
+            evaluate_line(&mut worker, &mut session, context_id, &line).await?;
+
+          // We check for close and break here instead of making it a loop condition to get
+          // consistent behavior in when the user evaluates a call to close().
+          if is_closing(&mut worker, &mut session, context_id).await? {
+            break;
+          }
+
+          match output {
+            Ok(output) | Err(output) => println!("{}", output),
+          }
+
+          if editor.lock().unwrap().add_history_entry(line.as_str()) {
+            history_changed = true;
+          }
+        }
+        Err(ReadlineError::Interrupted) => {
+          println!("exit using ctrl+d or close()");
+          continue;
+        }
+        Err(ReadlineError::Eof) => {
           break;
         }
-
-        let evaluate_result = evaluate_response.get("result").unwrap();
-        let evaluate_exception_details =
-          evaluate_response.get("exceptionDetails");
-
-        if evaluate_exception_details.is_some() {
-          post_message_and_poll(
-                    &mut worker,
-                    &mut session,
-                    "Runtime.callFunctionOn",
-                    Some(json!({
-                      "executionContextId": context_id,
-                      "functionDeclaration": "function (object) { Deno[Deno.internal].lastThrownError = object; }",
-                      "arguments": [
-                        evaluate_result,
-                      ],
-                    })),
-                  ).await?;
-        } else {
-          post_message_and_poll(
-                    &mut worker,
-                    &mut session,
-                    "Runtime.callFunctionOn",
-                    Some(json!({
-                      "executionContextId": context_id,
-                      "functionDeclaration": "function (object) { Deno[Deno.internal].lastEvalResult = object; }",
-                      "arguments": [
-                        evaluate_result,
-                      ],
-                    })),
-                  ).await?;
+        Err(err) => {
+          println!("Error: {:?}", err);
+          break;
         }
-
-        // TODO(caspervonb) we should investigate using previews here but to keep things
-        // consistent with the previous implementation we just get the preview result from
-        // Deno.inspectArgs.
-        let inspect_response =
-          post_message_and_poll(
-            &mut worker,
-            &mut session,
-            "Runtime.callFunctionOn",
-            Some(json!({
-              "executionContextId": context_id,
-              "functionDeclaration": "function (object) { return Deno[Deno.internal].inspectArgs(['%o', object], { colors: !Deno.noColor }); }",
-              "arguments": [
-                evaluate_result,
-              ],
-            })),
-          ).await?;
-
-        let inspect_result = inspect_response.get("result").unwrap();
-
-        let value = inspect_result.get("value").unwrap().as_str().unwrap();
-        let output = match evaluate_exception_details {
-          Some(_) => format!("Uncaught {}", value),
-          None => value.to_string(),
-        };
-
-        println!("{}", output);
-
-        editor.lock().unwrap().add_history_entry(line.as_str());
-      }
-      Err(ReadlineError::Interrupted) => {
-        println!("exit using ctrl+d or close()");
-        continue;
-      }
-      Err(ReadlineError::Eof) => {
-        break;
-      }
-      Err(err) => {
-        println!("Error: {:?}", err);
-        break;
       }
     }
   }
 
-  std::fs::create_dir_all(history_file.parent().unwrap())?;
-  editor
-    .lock()
-    .unwrap()
-    .save_history(history_file.to_str().unwrap())?;
+  // An unchanged history is not saved, since the editor only writes the
+  // history file when it has new entries.
+  if let Some(history_file) = &history_file {
+    if history_changed {
+      save_history(&mut editor.lock().unwrap(), history_file)?;
+    }
+  }
 
   Ok(())
 }
@@ -0,0 +1,712 @@
+// Copyright 2018-2021 the Deno authors. All rights reserved. MIT license.
+
+//! A small shell for `deno task`, so that tasks behave the same on every
+//! platform instead of depending on `sh` or `cmd.exe`.
+//!
+//! Supported are lists of commands separated by `&&`, `||`, `;` or newlines,
+//! single and double quotes, backslash escapes, `$NAME`, `${NAME}` and `$?`
+//! expansion, `NAME=value` assignments, the redirects `>`, `>>` and `<`,
+//! `#` comments and the builtins `cd`, `echo` and `exit`. Expansions are
+//! never split into several arguments and there is no globbing. Commands
+//! named `deno` run the current executable.
+
+use deno_core::error::generic_error;
+use deno_core::error::AnyError;
+use std::collections::HashMap;
+use std::fs::File;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::Command;
+use std::process::Stdio;
+
+#[derive(Debug, Clone, PartialEq)]
+enum WordPart {
+  Text(String),
+  Var(String),
+}
+
+/// A word, made up of literal text and expansions. `quoted` is set if any of
+/// it was quoted, so that `""` is an empty argument rather than none.
+/// `assignment` is set if it starts with an unquoted `NAME=`.
+#[derive(Debug, Clone, Default, PartialEq)]
+struct Word {
+  parts: Vec<WordPart>,
+  quoted: bool,
+  assignment: bool,
+}
+
+impl Word {
+  fn push_char(&mut self, c: char) {
+    match self.parts.last_mut() {
+      Some(WordPart::Text(text)) => text.push(c),
+      _ => self.parts.push(WordPart::Text(c.to_string())),
+    }
+  }
+
+  fn is_empty(&self) -> bool {
+    self.parts.is_empty() && !self.quoted
+  }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+  Word(Word),
+  And,
+  Or,
+  Separator,
+  Redirect(RedirectKind),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum RedirectKind {
+  Write,
+  Append,
+  Read,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Operator {
+  And,
+  Or,
+  Separator,
+}
+
+#[derive(Debug, Default, PartialEq)]
+struct SimpleCommand {
+  assignments: Vec<(String, Word)>,
+  words: Vec<Word>,
+  redirects: Vec<(RedirectKind, Word)>,
+}
+
+/// A list of commands, each run depending on the operator before it and the
+/// exit code of the previous command.
+#[derive(Debug, PartialEq)]
+struct Script {
+  commands: Vec<(Operator, SimpleCommand)>,
+}
+
+fn is_name_start(c: char) -> bool {
+  c.is_ascii_alphabetic() || c == '_'
+}
+
+fn is_name_char(c: char) -> bool {
+  c.is_ascii_alphanumeric() || c == '_'
+}
+
+fn is_name(name: &str) -> bool {
+  let mut chars = name.chars();
+  chars.next().map_or(false, is_name_start) && chars.all(is_name_char)
+}
+
+fn tokenize(script: &str) -> Result<Vec<Token>, AnyError> {
+  let mut tokens = vec![];
+  let mut chars = script.chars().peekable();
+  let mut word = Word::default();
+
+  macro_rules! finish_word {
+    () => {
+      if !word.is_empty() {
+        tokens.push(Token::Word(std::mem::take(&mut word)));
+      }
+    };
+  }
+
+  // Reads the name of a variable after a `$`, or returns `None` if the `$`
+  // doesn't start an expansion.
+  fn read_var(
+    chars: &mut std::iter::Peekable<std::str::Chars>,
+  ) -> Result<Option<String>, AnyError> {
+    match chars.peek() {
+      Some('?') => {
+        chars.next();
+        Ok(Some("?".to_string()))
+      }
+      Some('{') => {
+        chars.next();
+        let mut name = String::new();
+        loop {
+          match chars.next() {
+            Some('}') => break,
+            Some(c) if is_name_char(c) => name.push(c),
+            _ => return Err(generic_error("Bad substitution")),
+          }
+        }
+        if name.is_empty() || !is_name_start(name.chars().next().unwrap()) {
+          return Err(generic_error("Bad substitution"));
+        }
+        Ok(Some(name))
+      }
+      Some(&c) if is_name_start(c) => {
+        let mut name = String::new();
+        while let Some(&c) = chars.peek() {
+          if !is_name_char(c) {
+            break;
+          }
+          name.push(c);
+          chars.next();
+        }
+        Ok(Some(name))
+      }
+      _ => Ok(None),
+    }
+  }
+
+  while let Some(c) = chars.next() {
+    match c {
+      ' ' | '\t' | '\r' => finish_word!(),
+      '\n' | ';' => {
+        finish_word!();
+        tokens.push(Token::Separator);
+      }
+      '#' if word.is_empty() => {
+        while let Some(&c) = chars.peek() {
+          if c == '\n' {
+            break;
+          }
+          chars.next();
+        }
+      }
+      '&' => {
+        finish_word!();
+        if chars.peek() != Some(&'&') {
+          return Err(generic_error("Background commands are not supported"));
+        }
+        chars.next();
+        tokens.push(Token::And);
+      }
+      '|' => {
+        finish_word!();
+        if chars.peek() != Some(&'|') {
+          return Err(generic_error("Pipes are not supported"));
+        }
+        chars.next();
+        tokens.push(Token::Or);
+      }
+      '>' => {
+        finish_word!();
+        if chars.peek() == Some(&'>') {
+          chars.next();
+          tokens.push(Token::Redirect(RedirectKind::Append));
+        } else {
+          tokens.push(Token::Redirect(RedirectKind::Write));
+        }
+      }
+      '<' => {
+        finish_word!();
+        tokens.push(Token::Redirect(RedirectKind::Read));
+      }
+      '\\' => match chars.next() {
+        // A line continuation.
+        Some('\n') => {}
+        Some(c) => {
+          word.quoted = true;
+          word.push_char(c);
+        }
+        None => word.push_char('\\'),
+      },
+      '\'' => {
+        word.quoted = true;
+        loop {
+          match chars.next() {
+            Some('\'') => break,
+            Some(c) => word.push_char(c),
+            None => return Err(generic_error("Unterminated single quote")),
+          }
+        }
+      }
+      '"' => {
+        word.quoted = true;
+        loop {
+          match chars.next() {
+            Some('"') => break,
+            Some('\\') => match chars.peek() {
+              Some(&c) if c == '"' || c == '\\' || c == '$' => {
+                chars.next();
+                word.push_char(c);
+              }
+              Some('\n') => {
+                chars.next();
+              }
+              _ => word.push_char('\\'),
+            },
+            Some('$') => match read_var(&mut chars)? {
+              Some(name) => word.parts.push(WordPart::Var(name)),
+              None => word.push_char('$'),
+            },
+            Some(c) => word.push_char(c),
+            None => return Err(generic_error("Unterminated double quote")),
+          }
+        }
+      }
+      '$' => match read_var(&mut chars)? {
+        Some(name) => word.parts.push(WordPart::Var(name)),
+        None => word.push_char('$'),
+      },
+      c => {
+        if c == '=' && !word.quoted && !word.assignment {
+          word.assignment = matches!(
+            word.parts.as_slice(),
+            [WordPart::Text(name)] if is_name(name)
+          );
+        }
+        word.push_char(c);
+      }
+    }
+  }
+  finish_word!();
+  Ok(tokens)
+}
+
+/// Splits `NAME=value` into the name and the value.
+fn as_assignment(word: &Word) -> Option<(String, Word)> {
+  if !word.assignment {
+    return None;
+  }
+  let text = match word.parts.first() {
+    Some(WordPart::Text(text)) => text,
+    _ => return None,
+  };
+  let eq = text.find('=')?;
+  let mut value = Word {
+    parts: word.parts[1..].to_vec(),
+    quoted: word.quoted,
+    assignment: false,
+  };
+  let rest = &text[eq + 1..];
+  if !rest.is_empty() {
+    value.parts.insert(0, WordPart::Text(rest.to_string()));
+  }
+  Some((text[..eq].to_string(), value))
+}
+
+fn parse(script: &str) -> Result<Script, AnyError> {
+  let mut commands = vec![];
+  let mut operator = Operator::Separator;
+  let mut command = SimpleCommand::default();
+  let mut tokens = tokenize(script)?.into_iter().peekable();
+  let mut is_empty = true;
+
+  while let Some(token) = tokens.next() {
+    match token {
+      Token::Word(word) => {
+        if command.words.is_empty() {
+          if let Some(assignment) = as_assignment(&word) {
+            command.assignments.push(assignment);
+            is_empty = false;
+            continue;
+          }
+        }
+        command.words.push(word);
+        is_empty = false;
+      }
+      Token::Redirect(kind) => match tokens.next() {
+        Some(Token::Word(target)) => {
+          command.redirects.push((kind, target));
+          is_empty = false;
+        }
+        _ => return Err(generic_error("Expected a file name after redirect")),
+      },
+      Token::And | Token::Or | Token::Separator => {
+        let next_operator = match token {
+          Token::And => Operator::And,
+          Token::Or => Operator::Or,
+          _ => Operator::Separator,
+        };
+        if is_empty {
+          if next_operator == Operator::Separator
+            && operator == Operator::Separator
+          {
+            // Empty lines and stray `;` are fine.
+            continue;
+          }
+          return Err(generic_error(format!(
+            "Expected a command before \"{}\"",
+            operator_str(next_operator)
+          )));
+        }
+        commands.push((operator, std::mem::take(&mut command)));
+        operator = next_operator;
+        is_empty = true;
+      }
+    }
+  }
+
+  if is_empty {
+    if operator != Operator::Separator {
+      return Err(generic_error(format!(
+        "Expected a command after \"{}\"",
+        operator_str(operator)
+      )));
+    }
+  } else {
+    commands.push((operator, command));
+  }
+  Ok(Script { commands })
+}
+
+fn operator_str(operator: Operator) -> &'static str {
+  match operator {
+    Operator::And => "&&",
+    Operator::Or => "||",
+    Operator::Separator => ";",
+  }
+}
+
+/// Quotes `arg` so the shell reads it back as a single, literal argument.
+pub fn quote(arg: &str) -> String {
+  format!("'{}'", arg.replace('\'', "'\\''"))
+}
+
+/// The environment and working directory commands run with.
+pub struct ShellState {
+  cwd: PathBuf,
+  env: HashMap<String, String>,
+  last_exit_code: i32,
+}
+
+impl ShellState {
+  /// A state inheriting the environment of this process.
+  pub fn new(cwd: PathBuf) -> Self {
+    let mut state = Self {
+      cwd,
+      env: HashMap::new(),
+      last_exit_code: 0,
+    };
+    for (name, value) in std::env::vars() {
+      state.set_var(&name, value);
+    }
+    state
+  }
+
+  // Environment variable names are case insensitive on Windows.
+  fn env_key(name: &str) -> String {
+    if cfg!(windows) {
+      name.to_uppercase()
+    } else {
+      name.to_string()
+    }
+  }
+
+  pub fn set_var(&mut self, name: &str, value: String) {
+    self.env.insert(Self::env_key(name), value);
+  }
+
+  fn get_var(&self, name: &str) -> String {
+    if name == "?" {
+      return self.last_exit_code.to_string();
+    }
+    self
+      .env
+      .get(&Self::env_key(name))
+      .cloned()
+      .unwrap_or_default()
+  }
+
+  /// Expands a word. Returns `None` for unquoted words that expand to
+  /// nothing, which don't make an argument.
+  fn expand(&self, word: &Word) -> Option<String> {
+    let mut expanded = String::new();
+    for part in &word.parts {
+      match part {
+        WordPart::Text(text) => expanded.push_str(text),
+        WordPart::Var(name) => expanded.push_str(&self.get_var(name)),
+      }
+    }
+    if expanded.is_empty() && !word.quoted {
+      None
+    } else {
+      Some(expanded)
+    }
+  }
+
+  fn resolve(&self, path: &str) -> PathBuf {
+    self.cwd.join(path)
+  }
+}
+
+/// What a command asks the script to do next.
+enum Flow {
+  Continue(i32),
+  Exit(i32),
+}
+
+/// Runs `script` and returns the exit code of the last command that ran.
+pub fn execute(script: &str, state: &mut ShellState) -> Result<i32, AnyError> {
+  let script = parse(script)?;
+  for (operator, command) in &script.commands {
+    let skip = match operator {
+      Operator::And => state.last_exit_code != 0,
+      Operator::Or => state.last_exit_code == 0,
+      Operator::Separator => false,
+    };
+    if skip {
+      continue;
+    }
+    match run_command(command, state)? {
+      Flow::Continue(code) => state.last_exit_code = code,
+      Flow::Exit(code) => {
+        state.last_exit_code = code;
+        break;
+      }
+    }
+  }
+  Ok(state.last_exit_code)
+}
+
+fn run_command(
+  command: &SimpleCommand,
+  state: &mut ShellState,
+) -> Result<Flow, AnyError> {
+  let assignments: Vec<(String, String)> = command
+    .assignments
+    .iter()
+    .map(|(name, value)| {
+      (name.clone(), state.expand(value).unwrap_or_default())
+    })
+    .collect();
+  let words: Vec<String> = command
+    .words
+    .iter()
+    .filter_map(|w| state.expand(w))
+    .collect();
+
+  let mut stdin = None;
+  let mut stdout = None;
+  for (kind, target) in &command.redirects {
+    let target = state.expand(target).unwrap_or_default();
+    let path = state.resolve(&target);
+    let result = match kind {
+      RedirectKind::Read => File::open(&path).map(|f| stdin = Some(f)),
+      RedirectKind::Write => File::create(&path).map(|f| stdout = Some(f)),
+      RedirectKind::Append => OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .map(|f| stdout = Some(f)),
+    };
+    if let Err(err) = result {
+      eprintln!("{}: {}", target, err);
+      return Ok(Flow::Continue(1));
+    }
+  }
+
+  if words.is_empty() {
+    // Only assignments: they apply to the rest of the script.
+    for (name, value) in assignments {
+      state.set_var(&name, value);
+    }
+    return Ok(Flow::Continue(0));
+  }
+
+  let name = words[0].as_str();
+  let args = &words[1..];
+  match name {
+    "cd" => {
+      let dir = match args {
+        [dir] => state.resolve(dir),
+        _ => {
+          eprintln!("cd: expected one directory");
+          return Ok(Flow::Continue(1));
+        }
+      };
+      if !dir.is_dir() {
+        eprintln!("cd: {}: No such directory", args[0]);
+        return Ok(Flow::Continue(1));
+      }
+      state.cwd = dir;
+      Ok(Flow::Continue(0))
+    }
+    "echo" => {
+      let line = format!("{}\n", args.join(" "));
+      match stdout {
+        Some(mut file) => file.write_all(line.as_bytes())?,
+        None => {
+          let stdout = std::io::stdout();
+          let mut stdout = stdout.lock();
+          stdout.write_all(line.as_bytes())?;
+          stdout.flush()?;
+        }
+      }
+      Ok(Flow::Continue(0))
+    }
+    "exit" => match args {
+      [] => Ok(Flow::Exit(state.last_exit_code)),
+      [code] => match code.parse::<i32>() {
+        Ok(code) => Ok(Flow::Exit(code)),
+        Err(_) => {
+          eprintln!("exit: {}: numeric argument required", code);
+          Ok(Flow::Exit(2))
+        }
+      },
+      _ => {
+        eprintln!("exit: too many arguments");
+        Ok(Flow::Continue(1))
+      }
+    },
+    _ => {
+      let program = if name == "deno" {
+        std::env::current_exe()?
+      } else {
+        PathBuf::from(name)
+      };
+      let mut child = Command::new(program);
+      child
+        .args(args)
+        .current_dir(&state.cwd)
+        .env_clear()
+        .envs(&state.env)
+        .envs(assignments);
+      if let Some(stdin) = stdin {
+        child.stdin(Stdio::from(stdin));
+      }
+      if let Some(stdout) = stdout {
+        child.stdout(Stdio::from(stdout));
+      }
+      match child.status() {
+        Ok(status) => Ok(Flow::Continue(status.code().unwrap_or(1))),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+          eprintln!("{}: command not found", name);
+          Ok(Flow::Continue(127))
+        }
+        Err(err) => Err(err.into()),
+      }
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use tempfile::TempDir;
+
+  fn words(script: &str) -> Vec<String> {
+    let state = ShellState::new(PathBuf::from("."));
+    let script = parse(script).unwrap();
+    assert_eq!(script.commands.len(), 1);
+    script.commands[0]
+      .1
+      .words
+      .iter()
+      .filter_map(|w| state.expand(w))
+      .collect()
+  }
+
+  /// Runs `script` in a temporary directory and returns its exit code and
+  /// what it wrote to `out.txt`.
+  fn run(script: &str) -> (i32, String) {
+    let dir = TempDir::new().unwrap();
+    let mut state = ShellState::new(dir.path().to_path_buf());
+    let code = execute(script, &mut state).unwrap();
+    let out =
+      std::fs::read_to_string(dir.path().join("out.txt")).unwrap_or_default();
+    (code, out)
+  }
+
+  #[test]
+  fn quoting() {
+    assert_eq!(words("echo a  b"), vec!["echo", "a", "b"]);
+    assert_eq!(words("echo 'a  b' \"c d\""), vec!["echo", "a  b", "c d"]);
+    assert_eq!(words(r#"echo a\ b"#), vec!["echo", "a b"]);
+    assert_eq!(
+      words(r#"echo "a \"b\" \\ \$c \d""#),
+      vec!["echo", r#"a "b" \ $c \d"#]
+    );
+    assert_eq!(words(r#"echo 'a \"b'"#), vec!["echo", r#"a \"b"#]);
+    assert_eq!(words("echo 'a'\"b\"c"), vec!["echo", "abc"]);
+    assert_eq!(words("echo '' \"\""), vec!["echo", "", ""]);
+    assert_eq!(words("echo 'it'\\''s'"), vec!["echo", "it's"]);
+    assert_eq!(words("echo a#b # comment"), vec!["echo", "a#b"]);
+    assert_eq!(words("echo '&&' \"||\" ';'"), vec!["echo", "&&", "||", ";"]);
+    assert_eq!(
+      words(&format!("echo {}", quote("a 'b' $c"))),
+      vec!["echo", "a 'b' $c"]
+    );
+    assert!(parse("echo 'a").is_err());
+    assert!(parse("echo \"a").is_err());
+    assert!(parse("echo a | cat").is_err());
+    assert!(parse("echo a &").is_err());
+    assert!(parse("&& echo a").is_err());
+    assert!(parse("echo a &&").is_err());
+    assert!(parse("echo a >").is_err());
+  }
+
+  #[test]
+  fn assignments() {
+    let script = parse("A=1 B='2 3' echo $A").unwrap();
+    let command = &script.commands[0].1;
+    assert_eq!(
+      command
+        .assignments
+        .iter()
+        .map(|(name, _)| name.as_str())
+        .collect::<Vec<_>>(),
+      vec!["A", "B"]
+    );
+    assert_eq!(command.words.len(), 2);
+    // Quoted names and words after the command name aren't assignments.
+    assert!(parse("'A'=1").unwrap().commands[0].1.assignments.is_empty());
+    assert!(parse("echo A=1").unwrap().commands[0]
+      .1
+      .assignments
+      .is_empty());
+    assert!(parse("1A=1").unwrap().commands[0].1.assignments.is_empty());
+  }
+
+  #[test]
+  fn env_var_expansion() {
+    let (_, out) = run("A=hello; echo $A ${A}! \"$A world\" '$A' > out.txt");
+    assert_eq!(out, "hello hello! hello world $A\n");
+
+    // Unquoted expansions to nothing don't make an argument.
+    let (_, out) =
+      run("echo a $DENO_TASK_UNSET b \"$DENO_TASK_UNSET\" > out.txt");
+    assert_eq!(out, "a b \n");
+
+    // Variables are looked up case-insensitively on Windows.
+    let (_, out) = run("Deno_Task_Var=1; echo $DENO_TASK_VAR > out.txt");
+    if cfg!(windows) {
+      assert_eq!(out, "1\n");
+    } else {
+      assert_eq!(out, "\n");
+    }
+
+    let path_var = if cfg!(windows) { "Path" } else { "PATH" };
+    let (_, out) = run(&format!("echo ${} > out.txt", path_var));
+    assert_eq!(out.trim_end(), std::env::var("PATH").unwrap());
+  }
+
+  #[test]
+  fn short_circuiting() {
+    let (code, out) = run("exit 3 && echo no > out.txt");
+    assert_eq!((code, out.as_str()), (3, ""));
+    let (code, out) = run("echo a > out.txt || echo no >> out.txt");
+    assert_eq!((code, out.as_str()), (0, "a\n"));
+    let (code, out) =
+      run("cd missing && echo no > out.txt || echo $? >> out.txt");
+    assert_eq!((code, out.as_str()), (0, "1\n"));
+    let (code, out) = run("echo a > out.txt; exit 4; echo no >> out.txt");
+    assert_eq!((code, out.as_str()), (4, "a\n"));
+    let (code, out) =
+      run("deno_task_missing_command || echo $? > out.txt && exit 5");
+    assert_eq!((code, out.as_str()), (5, "127\n"));
+  }
+
+  #[test]
+  fn cd_and_redirects() {
+    let dir = TempDir::new().unwrap();
+    std::fs::create_dir(dir.path().join("sub")).unwrap();
+    let mut state = ShellState::new(dir.path().to_path_buf());
+    let code = execute(
+      "cd sub && echo one > a.txt\necho two >> a.txt; cd ..; echo x > b.txt",
+      &mut state,
+    )
+    .unwrap();
+    assert_eq!(code, 0);
+    assert_eq!(
+      std::fs::read_to_string(dir.path().join("sub/a.txt")).unwrap(),
+      "one\ntwo\n"
+    );
+    assert!(dir.path().join("b.txt").exists());
+  }
+}
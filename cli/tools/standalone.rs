@@ -1,12 +1,17 @@
 // Copyright 2018-2021 the Deno authors. All rights reserved. MIT license.
 
+use crate::checksum;
 use crate::deno_dir::DenoDir;
 use crate::flags::DenoSubcommand;
 use crate::flags::Flags;
+use crate::flags::TypeCheckMode;
 use deno_core::error::bail;
+use deno_core::error::generic_error;
 use deno_core::error::AnyError;
 use deno_core::serde_json;
 use deno_runtime::deno_fetch::reqwest::Client;
+use deno_runtime::embedded_files::embedded_path;
+use deno_runtime::fs_util::canonicalize_path;
 use std::env;
 use std::fs::read;
 use std::fs::File;
@@ -16,14 +21,31 @@ use std::io::SeekFrom;
 use std::io::Write;
 use std::path::Path;
 use std::path::PathBuf;
+use walkdir::WalkDir;
 
+use crate::standalone::parse_trailer;
+use crate::standalone::EmbeddedAsset;
 use crate::standalone::Metadata;
 use crate::standalone::MAGIC_TRAILER;
 
+/// The targets `deno compile --target` can produce executables for.
+pub const SUPPORTED_TARGETS: &[&str] = &[
+  "x86_64-unknown-linux-gnu",
+  "aarch64-unknown-linux-gnu",
+  "x86_64-pc-windows-msvc",
+  "x86_64-apple-darwin",
+  "aarch64-apple-darwin",
+];
+
+/// Returns the binary the output of `deno compile` is based on. That is the
+/// current executable, unless another `target` or the lite runtime is
+/// requested. Those are downloaded from dl.deno.land into `$DENO_DIR/dl`,
+/// together with their checksums, which are verified on every use.
 pub async fn get_base_binary(
   deno_dir: &DenoDir,
   target: Option<String>,
   lite: bool,
+  cached_only: bool,
 ) -> Result<Vec<u8>, AnyError> {
   if target.is_none() && !lite {
     let path = std::env::current_exe()?;
@@ -31,6 +53,13 @@ pub async fn get_base_binary(
   }
 
   let target = target.unwrap_or_else(|| env!("TARGET").to_string());
+  if !SUPPORTED_TARGETS.contains(&target.as_str()) {
+    bail!(
+      "Unsupported target \"{}\". The supported targets are: {}",
+      target,
+      SUPPORTED_TARGETS.join(", ")
+    );
+  }
   let exe_name = if lite { "denort" } else { "deno" };
   let binary_name = format!("{}-{}.zip", exe_name, target);
 
@@ -42,12 +71,29 @@ pub async fn get_base_binary(
 
   let download_directory = deno_dir.root.join("dl");
   let binary_path = download_directory.join(&binary_path_suffix);
+  let checksum_path = checksum_path(&binary_path);
 
-  if !binary_path.exists() {
+  if !binary_path.exists() || !checksum_path.exists() {
+    if cached_only {
+      bail!(
+        "The base binary for {} is not in the cache at {:?}, \
+        --cached-only is specified.",
+        target,
+        binary_path
+      );
+    }
     download_base_binary(&download_directory, &binary_path_suffix).await?;
   }
 
-  let archive_data = tokio::fs::read(binary_path).await?;
+  let archive_data = tokio::fs::read(&binary_path).await?;
+  let expected_checksum = tokio::fs::read_to_string(&checksum_path).await?;
+  verify_checksum(&archive_data, &expected_checksum).map_err(|err| {
+    generic_error(format!(
+      "{} Remove the cached base binary at {:?} to download it again.",
+      err, binary_path
+    ))
+  })?;
+
   let base_binary_path = crate::tools::upgrade::unpack(
     archive_data,
     exe_name,
@@ -57,6 +103,28 @@ pub async fn get_base_binary(
   Ok(base_binary)
 }
 
+/// The `sha256sum` output stored next to a downloaded base binary.
+fn checksum_path(binary_path: &Path) -> PathBuf {
+  let mut path = binary_path.as_os_str().to_owned();
+  path.push(".sha256sum");
+  PathBuf::from(path)
+}
+
+/// Checks `data` against `expected`, which is either a bare SHA-256 hex
+/// digest or a line of `sha256sum` output.
+fn verify_checksum(data: &[u8], expected: &str) -> Result<(), AnyError> {
+  let expected = expected.split_whitespace().next().unwrap_or("");
+  let actual = checksum::gen(&[data]);
+  if !actual.eq_ignore_ascii_case(expected) {
+    bail!(
+      "Checksum mismatch for the base binary: expected {}, got {}.",
+      expected,
+      actual
+    );
+  }
+  Ok(())
+}
+
 async fn download_base_binary(
   output_directory: &Path,
   binary_path_suffix: &str,
@@ -68,31 +136,86 @@ async fn download_base_binary(
 
   println!("Checking {}", &download_url);
 
-  let res = client.get(&download_url).send().await?;
+  let binary_content = download(&client, &download_url).await?;
+  let checksum_url = format!("{}.sha256sum", download_url);
+  let checksum_content = download(&client, &checksum_url).await?;
+  println!("Download has been found");
 
-  let binary_content = if res.status().is_success() {
-    println!("Download has been found");
-    res.bytes().await?.to_vec()
-  } else {
-    println!("Download could not be found, aborting");
-    std::process::exit(1)
-  };
-
-  std::fs::create_dir_all(&output_directory)?;
   let output_path = output_directory.join(binary_path_suffix);
   std::fs::create_dir_all(&output_path.parent().unwrap())?;
-  tokio::fs::write(output_path, binary_content).await?;
+  tokio::fs::write(&output_path, binary_content).await?;
+  tokio::fs::write(checksum_path(&output_path), checksum_content).await?;
   Ok(())
 }
 
+async fn download(client: &Client, url: &str) -> Result<Vec<u8>, AnyError> {
+  let res = client.get(url).send().await.map_err(|err| {
+    generic_error(format!(
+      "Could not download {}: {}\nThe base binaries for other targets have \
+      to be downloaded once; after that they are cached in $DENO_DIR/dl.",
+      url, err
+    ))
+  })?;
+  if !res.status().is_success() {
+    bail!("Download could not be found: {} ({})", url, res.status());
+  }
+  Ok(res.bytes().await?.to_vec())
+}
+
+/// Reads the files passed to `--include`, walking directories recursively.
+/// Returns their paths relative to the current directory, which is the
+/// embedded root, together with their contents.
+pub fn collect_assets(
+  include: &[PathBuf],
+) -> Result<Vec<(String, Vec<u8>)>, AnyError> {
+  let cwd = canonicalize_path(&env::current_dir()?)?;
+  let mut assets = vec![];
+  for path in include {
+    if !path.exists() {
+      bail!("Could not compile: {:?} does not exist.", path);
+    }
+    for entry in
+      WalkDir::new(path).sort_by(|a, b| a.file_name().cmp(b.file_name()))
+    {
+      let entry = entry?;
+      if !entry.file_type().is_file() {
+        continue;
+      }
+      let asset_path = canonicalize_path(entry.path())?;
+      let asset_name =
+        asset_path.strip_prefix(&cwd).ok().and_then(embedded_path);
+      match asset_name {
+        Some(asset_name) => assets.push((asset_name, read(&asset_path)?)),
+        None => bail!(
+          "Could not compile: {:?} is not inside the current directory.",
+          entry.path()
+        ),
+      }
+    }
+  }
+  Ok(assets)
+}
+
 /// This functions creates a standalone deno binary by appending a bundle
-/// and magic trailer to the currently executing binary.
+/// and magic trailer to the currently executing binary. The `assets` are
+/// stored right before the bundle.
 pub fn create_standalone_binary(
   mut original_bin: Vec<u8>,
   source_code: String,
+  assets: Vec<(String, Vec<u8>)>,
   flags: Flags,
 ) -> Result<Vec<u8>, AnyError> {
   let mut source_code = source_code.as_bytes().to_vec();
+  let mut assets_bin = vec![];
+  let mut embedded_assets = vec![];
+  for (path, mut contents) in assets {
+    embedded_assets.push(EmbeddedAsset {
+      path,
+      offset: (original_bin.len() + assets_bin.len()) as u64,
+      len: contents.len() as u64,
+    });
+    assets_bin.append(&mut contents);
+  }
   let ca_data = match &flags.ca_file {
     Some(ca_file) => Some(read(ca_file)?),
     None => None,
@@ -106,18 +229,23 @@ pub fn create_standalone_binary(
     v8_flags: flags.v8_flags.clone(),
     log_level: flags.log_level,
     ca_data,
+    assets: embedded_assets,
   };
   let mut metadata = serde_json::to_string(&metadata)?.as_bytes().to_vec();
 
-  let bundle_pos = original_bin.len();
+  let bundle_pos = original_bin.len() + assets_bin.len();
   let metadata_pos = bundle_pos + source_code.len();
+  // The positions are always stored as big-endian u64s, whatever the width
+  // and byte order of the host or the target.
   let mut trailer = MAGIC_TRAILER.to_vec();
-  trailer.write_all(&bundle_pos.to_be_bytes())?;
-  trailer.write_all(&metadata_pos.to_be_bytes())?;
+  trailer.write_all(&(bundle_pos as u64).to_be_bytes())?;
+  trailer.write_all(&(metadata_pos as u64).to_be_bytes())?;
 
-  let mut final_bin =
-    Vec::with_capacity(original_bin.len() + source_code.len() + trailer.len());
+  let mut final_bin = Vec::with_capacity(
+    bundle_pos + source_code.len() + metadata.len() + trailer.len(),
+  );
   final_bin.append(&mut original_bin);
+  final_bin.append(&mut assets_bin);
   final_bin.append(&mut source_code);
   final_bin.append(&mut metadata);
   final_bin.append(&mut trailer);
@@ -125,19 +253,22 @@ pub fn create_standalone_binary(
   Ok(final_bin)
 }
 
+/// Appends `.exe` to `output` when the executable is for Windows, whatever
+/// the host is, unless it already has that extension.
+pub fn output_path(output: PathBuf, windows: bool) -> PathBuf {
+  if windows && output.extension().unwrap_or_default() != "exe" {
+    PathBuf::from(output.display().to_string() + ".exe")
+  } else {
+    output
+  }
+}
+
 /// This function writes out a final binary to specified path. If output path
 /// is not already standalone binary it will return error instead.
 pub async fn write_standalone_binary(
   output: PathBuf,
   final_bin: Vec<u8>,
 ) -> Result<(), AnyError> {
-  let output =
-    if cfg!(windows) && output.extension().unwrap_or_default() != "exe" {
-      PathBuf::from(output.display().to_string() + ".exe")
-    } else {
-      output
-    };
-
   if output.exists() {
     // If the output is a directory, throw error
     if output.is_dir() {
@@ -153,8 +284,7 @@ pub async fn write_standalone_binary(
     if output_file.seek(SeekFrom::End(-24)).is_ok() {
       let mut trailer = [0; 24];
       output_file.read_exact(&mut trailer)?;
-      let (magic_trailer, _) = trailer.split_at(8);
-      has_trailer = magic_trailer == MAGIC_TRAILER;
+      has_trailer = parse_trailer(&trailer).is_some();
     }
     if !has_trailer {
       bail!("Could not compile: cannot overwrite {:?}.", &output);
@@ -198,8 +328,11 @@ pub fn compile_to_runtime_flags(
     cache_blocklist: vec![],
     ca_file: flags.ca_file,
     cached_only: false,
+    compat: false,
     config_path: None,
     coverage_dir: flags.coverage_dir,
+    env_file: None,
+    ext: None,
     ignore: vec![],
     import_map_path: None,
     inspect: None,
@@ -207,6 +340,7 @@ pub fn compile_to_runtime_flags(
     location: flags.location,
     lock: None,
     lock_write: false,
+    frozen_lockfile: false,
     log_level: flags.log_level,
     no_check: false,
     no_prompts: flags.no_prompts,
@@ -214,9 +348,81 @@ pub fn compile_to_runtime_flags(
     reload: false,
     repl: false,
     seed: flags.seed,
+    type_check_mode: TypeCheckMode::All,
     unstable: flags.unstable,
     v8_flags: flags.v8_flags,
     version: false,
     watch: false,
+    watch_paths: vec![],
+    watch_exclude: vec![],
+    no_clear_screen: false,
   })
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::convert::TryInto;
+
+  #[test]
+  fn standalone_binary_layout() {
+    let base = b"base binary".to_vec();
+    let assets = vec![("assets/a.txt".to_string(), b"asset".to_vec())];
+    let final_bin = create_standalone_binary(
+      base.clone(),
+      "console.log(1);".to_string(),
+      assets,
+      Flags::default(),
+    )
+    .unwrap();
+    assert!(final_bin.starts_with(&base));
+
+    let trailer: &[u8; 24] =
+      final_bin[final_bin.len() - 24..].try_into().unwrap();
+    let (bundle_pos, metadata_pos) = parse_trailer(trailer).unwrap();
+    let bundle = &final_bin[bundle_pos as usize..metadata_pos as usize];
+    assert_eq!(bundle, b"console.log(1);");
+    let metadata: Metadata = serde_json::from_slice(
+      &final_bin[metadata_pos as usize..final_bin.len() - 24],
+    )
+    .unwrap();
+    let asset = &metadata.assets[0];
+    assert_eq!(asset.path, "assets/a.txt");
+    let start = asset.offset as usize;
+    assert_eq!(&final_bin[start..start + asset.len as usize], b"asset");
+
+    assert!(parse_trailer(&[0; 24]).is_none());
+  }
+
+  #[test]
+  fn base_binary_checksum() {
+    let digest =
+      "b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9";
+    assert!(verify_checksum(b"hello world", digest).is_ok());
+    let sha256sum_line = format!("{}  deno-x86_64-apple-darwin.zip\n", digest);
+    assert!(verify_checksum(b"hello world", &sha256sum_line).is_ok());
+    let err = verify_checksum(b"hello world!", digest).unwrap_err();
+    assert!(err.to_string().starts_with("Checksum mismatch"));
+    assert!(verify_checksum(b"hello world", "").is_err());
+  }
+
+  #[test]
+  fn windows_output_path() {
+    assert_eq!(
+      output_path(PathBuf::from("out/colors"), true),
+      PathBuf::from("out/colors.exe")
+    );
+    assert_eq!(
+      output_path(PathBuf::from("colors.exe"), true),
+      PathBuf::from("colors.exe")
+    );
+    assert_eq!(
+      output_path(PathBuf::from("colors"), false),
+      PathBuf::from("colors")
+    );
+    assert_eq!(
+      checksum_path(Path::new("dl/deno-x86_64-apple-darwin.zip")),
+      PathBuf::from("dl/deno-x86_64-apple-darwin.zip.sha256sum")
+    );
+  }
+}
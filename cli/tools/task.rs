@@ -0,0 +1,223 @@
+// Copyright 2018-2021 the Deno authors. All rights reserved. MIT license.
+
+//! `deno task`: runs the commands defined in the `"tasks"` object of a
+//! `deno.json` configuration file with the shell in `tools::shell`.
+
+use crate::colors;
+use crate::tools::shell;
+use crate::tools::shell::ShellState;
+use deno_core::error::generic_error;
+use deno_core::error::AnyError;
+use deno_core::error::Context;
+use deno_core::serde_json::Value;
+use deno_runtime::fs_util::canonicalize_path;
+use std::path::Path;
+use std::path::PathBuf;
+
+const CONFIG_FILE_NAMES: &[&str] = &["deno.json", "deno.jsonc"];
+
+/// Looks for a `deno.json` or `deno.jsonc` in `dir` and its ancestors.
+pub fn find_config_file(dir: &Path) -> Option<PathBuf> {
+  for dir in dir.ancestors() {
+    for name in CONFIG_FILE_NAMES {
+      let path = dir.join(name);
+      if path.is_file() {
+        return Some(path);
+      }
+    }
+  }
+  None
+}
+
+/// Reads the `"tasks"` object of the configuration file at `path`, sorted by
+/// task name.
+fn read_tasks(path: &Path) -> Result<Vec<(String, String)>, AnyError> {
+  let text = std::fs::read_to_string(path)
+    .with_context(|| format!("Unable to read config file {:?}", path))?;
+  let config = parse_jsonc(&text)
+    .with_context(|| format!("Unable to parse config file {:?}", path))?;
+  let tasks = match config.get("tasks") {
+    Some(Value::Object(tasks)) => tasks,
+    Some(_) => {
+      return Err(generic_error(format!(
+        "\"tasks\" in {:?} must be an object of task names to commands",
+        path
+      )))
+    }
+    None => return Ok(vec![]),
+  };
+  let mut tasks = tasks
+    .iter()
+    .map(|(name, command)| match command {
+      Value::String(command) => Ok((name.clone(), command.clone())),
+      _ => Err(generic_error(format!(
+        "Task \"{}\" in {:?} must be a command string",
+        name, path
+      ))),
+    })
+    .collect::<Result<Vec<_>, AnyError>>()?;
+  tasks.sort();
+  Ok(tasks)
+}
+
+pub fn parse_jsonc(text: &str) -> Result<Value, AnyError> {
+  match jsonc_parser::parse_to_value(text)? {
+    Some(value) => Ok(crate::tsc_config::jsonc_to_serde(value)),
+    None => Ok(Value::Null),
+  }
+}
+
+fn levenshtein(a: &str, b: &str) -> usize {
+  let b: Vec<char> = b.chars().collect();
+  let mut row: Vec<usize> = (0..=b.len()).collect();
+  for (i, a_char) in a.chars().enumerate() {
+    let mut prev = row[0];
+    row[0] = i + 1;
+    for (j, b_char) in b.iter().enumerate() {
+      let substitution = prev + if a_char == *b_char { 0 } else { 1 };
+      prev = row[j + 1];
+      row[j + 1] = substitution.min(prev + 1).min(row[j] + 1);
+    }
+  }
+  row[b.len()]
+}
+
+/// The name of the task closest to `name`, if any is close enough to be a
+/// likely typo.
+fn suggest<'a>(name: &str, tasks: &'a [(String, String)]) -> Option<&'a str> {
+  let max_distance = std::cmp::max(2, name.chars().count() / 3);
+  tasks
+    .iter()
+    .map(|(task, _)| (levenshtein(name, task), task))
+    .filter(|(distance, _)| *distance <= max_distance)
+    .min_by_key(|(distance, _)| *distance)
+    .map(|(_, task)| task.as_str())
+}
+
+fn print_tasks(config_path: &Path, tasks: &[(String, String)]) {
+  if tasks.is_empty() {
+    println!("No tasks found in {}", config_path.display());
+    return;
+  }
+  println!("Available tasks:");
+  for (name, command) in tasks {
+    println!("- {}", colors::green(name));
+    println!("    {}", command);
+  }
+}
+
+/// Runs `task` with `args` appended to its command, in the directory of the
+/// configuration file, and returns its exit code. Without a task, lists the
+/// tasks that are defined.
+pub fn run_task(
+  maybe_config_path: Option<String>,
+  task: Option<String>,
+  args: Vec<String>,
+) -> Result<i32, AnyError> {
+  let config_path = match maybe_config_path {
+    Some(path) => PathBuf::from(path),
+    None => find_config_file(&std::env::current_dir()?).ok_or_else(|| {
+      generic_error(
+        "No deno.json found in the current directory or its parents. \
+        Pass a configuration file with --config.",
+      )
+    })?,
+  };
+  let config_path = canonicalize_path(&config_path)
+    .with_context(|| format!("Unable to find config file {:?}", config_path))?;
+  let tasks = read_tasks(&config_path)?;
+
+  let task = match task {
+    Some(task) => task,
+    None => {
+      print_tasks(&config_path, &tasks);
+      return Ok(0);
+    }
+  };
+
+  let command = match tasks.iter().find(|(name, _)| *name == task) {
+    Some((_, command)) => command,
+    None => {
+      let mut msg = format!("Task \"{}\" not found.", task);
+      if let Some(suggestion) = suggest(&task, &tasks) {
+        msg.push_str(&format!(" Did you mean \"{}\"?", suggestion));
+      }
+      return Err(generic_error(msg));
+    }
+  };
+
+  let script = args.iter().fold(command.clone(), |script, arg| {
+    format!("{} {}", script, shell::quote(arg))
+  });
+  eprintln!("{} {} {}", colors::green("Task"), task, script);
+
+  let cwd = config_path.parent().unwrap().to_path_buf();
+  let mut state = ShellState::new(cwd);
+  shell::execute(&script, &mut state)
+    .with_context(|| format!("Unable to run task \"{}\"", task))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn tasks(names: &[&str]) -> Vec<(String, String)> {
+    names
+      .iter()
+      .map(|name| (name.to_string(), String::new()))
+      .collect()
+  }
+
+  #[test]
+  fn unknown_task_suggestions() {
+    let tasks = tasks(&["build", "bundle", "test", "start:dev"]);
+    assert_eq!(suggest("buidl", &tasks), Some("build"));
+    assert_eq!(suggest("buil", &tasks), Some("build"));
+    assert_eq!(suggest("bundel", &tasks), Some("bundle"));
+    assert_eq!(suggest("tset", &tasks), Some("test"));
+    assert_eq!(suggest("start-dev", &tasks), Some("start:dev"));
+    assert_eq!(suggest("deploy", &tasks), None);
+    assert_eq!(suggest("x", &[]), None);
+  }
+
+  #[test]
+  fn levenshtein_distance() {
+    assert_eq!(levenshtein("", ""), 0);
+    assert_eq!(levenshtein("abc", ""), 3);
+    assert_eq!(levenshtein("kitten", "sitting"), 3);
+    assert_eq!(levenshtein("build", "build"), 0);
+  }
+
+  #[test]
+  fn parse_tasks() {
+    let dir = tempfile::TempDir::new().unwrap();
+    let path = dir.path().join("deno.jsonc");
+    std::fs::write(
+      &path,
+      r#"{
+        // A comment.
+        "compilerOptions": {},
+        "tasks": { "b": "echo b", "a": "echo a" }
+      }"#,
+    )
+    .unwrap();
+    assert_eq!(
+      read_tasks(&path).unwrap(),
+      vec![
+        ("a".to_string(), "echo a".to_string()),
+        ("b".to_string(), "echo b".to_string())
+      ]
+    );
+    let sub_dir = dir.path().join("sub");
+    std::fs::create_dir(&sub_dir).unwrap();
+    assert_eq!(find_config_file(&sub_dir), Some(path.clone()));
+
+    std::fs::write(&path, r#"{ "tasks": { "a": 1 } }"#).unwrap();
+    let err = read_tasks(&path).unwrap_err();
+    assert!(err.to_string().contains("must be a command string"));
+    std::fs::write(&path, r#"{ "tasks": ["a"] }"#).unwrap();
+    assert!(read_tasks(&path).is_err());
+    std::fs::write(&path, "{}").unwrap();
+    assert_eq!(read_tasks(&path).unwrap(), vec![]);
+  }
+}
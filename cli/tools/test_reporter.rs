@@ -0,0 +1,971 @@
+// Copyright 2018-2021 the Deno authors. All rights reserved. MIT license.
+
+//! The reporters of `deno test --reporter`, which turn the messages of the JS
+//! test runner of each test module into a report.
+//!
+//! Except for the pretty one, which prints the text formatted by the JS
+//! runner, reporters only get structured messages, see `TestMessage`.
+
+use crate::colors;
+use crate::flags::TestReporterKind;
+use crate::tools::test_runner::TestSummary;
+use deno_core::url::Url;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::io::Result;
+use std::io::Write;
+use std::time::Duration;
+
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum TestStatus {
+  Passed,
+  Failed,
+  Ignored,
+  /// A step that was still running when its parent finished.
+  Incomplete,
+}
+
+/// An error thrown by a test or step, formatted by the JS runner.
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+pub struct TestError {
+  /// A single line describing the error, like `Error: message`.
+  pub message: String,
+  /// The error as `console.log()` prints it, including its source mapped
+  /// stack trace.
+  pub formatted: String,
+}
+
+/// A message of the JS test runner of a test module, as serialized by
+/// `serializeTestMessage()` in `40_testing.js`. The steps of a test are
+/// reported after it ran, between its `TestStart` and its `TestEnd`, with
+/// the start and end of each step around those of its own steps.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum TestMessage {
+  Start {
+    tests: usize,
+    seed: Option<String>,
+  },
+  TestStart {
+    name: String,
+  },
+  TestStepStart {
+    name: String,
+    level: usize,
+  },
+  TestStepEnd {
+    name: String,
+    level: usize,
+    status: TestStatus,
+    duration: u64,
+    error: Option<TestError>,
+  },
+  TestEnd {
+    name: String,
+    status: TestStatus,
+    duration: u64,
+    error: Option<TestError>,
+  },
+  End(TestSummary),
+}
+
+pub trait TestReporter {
+  /// Text formatted by the JS runner of `module`, which is only sent when
+  /// the pretty reporter is used.
+  fn visit_output(&mut self, module: &Url, text: &str) -> Result<()>;
+  fn visit_message(
+    &mut self,
+    module: &Url,
+    message: &TestMessage,
+  ) -> Result<()>;
+  /// The module is done, possibly with an error that kept it from finishing
+  /// its tests.
+  fn visit_module_end(
+    &mut self,
+    module: &Url,
+    maybe_error: Option<&str>,
+  ) -> Result<()>;
+  /// The test run is over. `has_error` tells whether any of the modules
+  /// ended with an error.
+  fn close(
+    &mut self,
+    summary: &TestSummary,
+    has_error: bool,
+    elapsed: Duration,
+  ) -> Result<()>;
+}
+
+pub fn create_reporter(
+  kind: TestReporterKind,
+  writer: Box<dyn Write>,
+) -> Box<dyn TestReporter> {
+  match kind {
+    TestReporterKind::Pretty => Box::new(PrettyTestReporter::new(writer)),
+    TestReporterKind::Dot => Box::new(DotTestReporter::new(writer)),
+    TestReporterKind::Junit => Box::new(JunitTestReporter::new(writer)),
+    TestReporterKind::Tap => Box::new(TapTestReporter::new(writer)),
+  }
+}
+
+fn format_summary(
+  summary: &TestSummary,
+  has_error: bool,
+  elapsed: Duration,
+) -> String {
+  let failed = has_error || summary.failed > 0 || summary.used_only;
+  let mut text = format!(
+    "\ntest result: {}. {} passed; {} failed; {} ignored; {} measured; {} filtered out {}\n\n",
+    if failed {
+      colors::red("FAILED").to_string()
+    } else {
+      colors::green("ok").to_string()
+    },
+    summary.passed,
+    summary.failed,
+    summary.ignored,
+    summary.measured,
+    summary.filtered,
+    colors::gray(&format!("({}ms)", elapsed.as_millis())),
+  );
+  if summary.used_only && !has_error && summary.failed == 0 {
+    text.push_str(&format!(
+      "{} because the \"only\" option was used\n\n",
+      colors::red("FAILED")
+    ));
+  }
+  text
+}
+
+/// A test or a test step, once it ended.
+#[derive(Debug)]
+struct TestCase {
+  /// The name of the test, followed by the names of the steps down to this
+  /// one.
+  path: Vec<String>,
+  status: TestStatus,
+  duration: u64,
+  error: Option<TestError>,
+}
+
+impl TestCase {
+  fn name(&self) -> String {
+    self.path.join(" > ")
+  }
+}
+
+/// Tracks the test and step that the messages of a module are about.
+#[derive(Default)]
+struct TestPath(Vec<String>);
+
+impl TestPath {
+  /// Returns the test or step that `message` ends, if any.
+  fn visit(&mut self, message: &TestMessage) -> Option<TestCase> {
+    match message {
+      TestMessage::TestStart { name } => {
+        self.0 = vec![name.clone()];
+        None
+      }
+      TestMessage::TestStepStart { name, level } => {
+        self.0.truncate(*level);
+        self.0.push(name.clone());
+        None
+      }
+      TestMessage::TestStepEnd {
+        name,
+        level,
+        status,
+        duration,
+        error,
+      } => {
+        self.0.truncate(*level);
+        self.0.push(name.clone());
+        let case = TestCase {
+          path: self.0.clone(),
+          status: *status,
+          duration: *duration,
+          error: error.clone(),
+        };
+        self.0.pop();
+        Some(case)
+      }
+      TestMessage::TestEnd {
+        name,
+        status,
+        duration,
+        error,
+      } => {
+        self.0 = vec![];
+        Some(TestCase {
+          path: vec![name.clone()],
+          status: *status,
+          duration: *duration,
+          error: error.clone(),
+        })
+      }
+      TestMessage::Start { .. } | TestMessage::End(_) => None,
+    }
+  }
+}
+
+/// Prints the report formatted by the JS runner of each module in one piece
+/// once the module is done, so the reports of modules running at the same
+/// time never interleave.
+pub struct PrettyTestReporter<W: Write> {
+  writer: W,
+  outputs: HashMap<Url, String>,
+}
+
+impl<W: Write> PrettyTestReporter<W> {
+  pub fn new(writer: W) -> Self {
+    Self {
+      writer,
+      outputs: HashMap::new(),
+    }
+  }
+}
+
+impl<W: Write> TestReporter for PrettyTestReporter<W> {
+  fn visit_output(&mut self, module: &Url, text: &str) -> Result<()> {
+    self
+      .outputs
+      .entry(module.clone())
+      .or_default()
+      .push_str(text);
+    Ok(())
+  }
+
+  fn visit_message(
+    &mut self,
+    _module: &Url,
+    _message: &TestMessage,
+  ) -> Result<()> {
+    Ok(())
+  }
+
+  fn visit_module_end(
+    &mut self,
+    module: &Url,
+    maybe_error: Option<&str>,
+  ) -> Result<()> {
+    let mut output = self.outputs.remove(module).unwrap_or_default();
+    if let Some(error) = maybe_error {
+      output.push_str(&format!("{}: {}\n", colors::red_bold("error"), error));
+    }
+    self.writer.write_all(output.as_bytes())?;
+    self.writer.flush()
+  }
+
+  fn close(
+    &mut self,
+    summary: &TestSummary,
+    has_error: bool,
+    elapsed: Duration,
+  ) -> Result<()> {
+    write!(
+      self.writer,
+      "{}",
+      format_summary(summary, has_error, elapsed)
+    )?;
+    self.writer.flush()
+  }
+}
+
+/// Prints a character per test as they end, `.` if it passed, `F` if it
+/// failed and `i` if it was ignored, followed by the failures once the run
+/// is over.
+pub struct DotTestReporter<W: Write> {
+  writer: W,
+  paths: HashMap<Url, TestPath>,
+  /// The failed tests and steps, and the errors of modules, along with
+  /// their module.
+  failures: Vec<(Url, Option<TestCase>, Option<String>)>,
+}
+
+impl<W: Write> DotTestReporter<W> {
+  pub fn new(writer: W) -> Self {
+    Self {
+      writer,
+      paths: HashMap::new(),
+      failures: vec![],
+    }
+  }
+}
+
+impl<W: Write> TestReporter for DotTestReporter<W> {
+  fn visit_output(&mut self, _module: &Url, _text: &str) -> Result<()> {
+    Ok(())
+  }
+
+  fn visit_message(
+    &mut self,
+    module: &Url,
+    message: &TestMessage,
+  ) -> Result<()> {
+    let path = self.paths.entry(module.clone()).or_default();
+    let case = match path.visit(message) {
+      Some(case) => case,
+      None => return Ok(()),
+    };
+    let is_test = case.path.len() == 1;
+    if is_test {
+      let c = match case.status {
+        TestStatus::Passed => colors::green(".").to_string(),
+        TestStatus::Failed | TestStatus::Incomplete => {
+          colors::red("F").to_string()
+        }
+        TestStatus::Ignored => colors::yellow("i").to_string(),
+      };
+      write!(self.writer, "{}", c)?;
+      self.writer.flush()?;
+    }
+    if case.error.is_some() {
+      self.failures.push((module.clone(), Some(case), None));
+    }
+    Ok(())
+  }
+
+  fn visit_module_end(
+    &mut self,
+    module: &Url,
+    maybe_error: Option<&str>,
+  ) -> Result<()> {
+    self.paths.remove(module);
+    if let Some(error) = maybe_error {
+      write!(self.writer, "{}", colors::red("E"))?;
+      self.writer.flush()?;
+      self
+        .failures
+        .push((module.clone(), None, Some(error.to_string())));
+    }
+    Ok(())
+  }
+
+  fn close(
+    &mut self,
+    summary: &TestSummary,
+    has_error: bool,
+    elapsed: Duration,
+  ) -> Result<()> {
+    writeln!(self.writer)?;
+    if !self.failures.is_empty() {
+      writeln!(self.writer, "\nfailures:\n")?;
+      for (module, maybe_case, maybe_error) in &self.failures {
+        if let Some(case) = maybe_case {
+          writeln!(self.writer, "{} > {}", module, case.name())?;
+          if let Some(error) = &case.error {
+            writeln!(self.writer, "{}", error.formatted)?;
+          }
+        }
+        if let Some(error) = maybe_error {
+          writeln!(self.writer, "{}", module)?;
+          writeln!(self.writer, "{}: {}", colors::red_bold("error"), error)?;
+        }
+        writeln!(self.writer)?;
+      }
+    }
+    write!(
+      self.writer,
+      "{}",
+      format_summary(summary, has_error, elapsed)
+    )?;
+    self.writer.flush()
+  }
+}
+
+/// Escapes `text` for XML, without the ANSI escape codes and the control
+/// characters XML can't hold.
+fn xml_escape(text: &str) -> String {
+  let mut escaped = String::with_capacity(text.len());
+  for c in colors::strip_ansi_codes(text).chars() {
+    match c {
+      '&' => escaped.push_str("&amp;"),
+      '<' => escaped.push_str("&lt;"),
+      '>' => escaped.push_str("&gt;"),
+      '"' => escaped.push_str("&quot;"),
+      '\'' => escaped.push_str("&apos;"),
+      '\n' => escaped.push_str("&#10;"),
+      '\t' | '\r' => escaped.push(c),
+      c if c.is_control() => {}
+      c => escaped.push(c),
+    }
+  }
+  escaped
+}
+
+/// Like `xml_escape()`, but keeps line breaks, for text content.
+fn xml_escape_text(text: &str) -> String {
+  xml_escape(text).replace("&#10;", "\n")
+}
+
+fn format_seconds(ms: u64) -> String {
+  format!("{:.3}", ms as f64 / 1000.0)
+}
+
+#[derive(Default)]
+struct JunitTestSuite {
+  path: TestPath,
+  /// The tests of the module, each followed by its steps.
+  cases: Vec<TestCase>,
+  /// The steps of the test that is being reported, which come before it.
+  steps: Vec<TestCase>,
+  error: Option<String>,
+}
+
+impl JunitTestSuite {
+  fn count(&self, status: TestStatus) -> usize {
+    self.cases.iter().filter(|c| c.status == status).count()
+  }
+
+  fn failures(&self) -> usize {
+    self.count(TestStatus::Failed) + self.count(TestStatus::Incomplete)
+  }
+
+  fn tests(&self) -> usize {
+    self.cases.len() + self.error.iter().count()
+  }
+
+  fn duration(&self) -> u64 {
+    // The duration of a test includes the one of its steps.
+    self
+      .cases
+      .iter()
+      .filter(|c| c.path.len() == 1)
+      .map(|c| c.duration)
+      .sum()
+  }
+}
+
+/// Writes a JUnit XML report once the run is over, with a test suite per
+/// module and a test case per test and step. Steps are named after the path
+/// to them, like `test > step`, and follow their test.
+pub struct JunitTestReporter<W: Write> {
+  writer: W,
+  suites: HashMap<Url, JunitTestSuite>,
+}
+
+impl<W: Write> JunitTestReporter<W> {
+  pub fn new(writer: W) -> Self {
+    Self {
+      writer,
+      suites: HashMap::new(),
+    }
+  }
+
+  fn write_case(&mut self, classname: &str, case: &TestCase) -> Result<()> {
+    write!(
+      self.writer,
+      r#"    <testcase name="{}" classname="{}" time="{}""#,
+      xml_escape(&case.name()),
+      classname,
+      format_seconds(case.duration)
+    )?;
+    match case.status {
+      TestStatus::Passed => writeln!(self.writer, "/>"),
+      TestStatus::Ignored => {
+        writeln!(self.writer, ">")?;
+        writeln!(self.writer, "      <skipped/>")?;
+        writeln!(self.writer, "    </testcase>")
+      }
+      TestStatus::Failed => {
+        writeln!(self.writer, ">")?;
+        match &case.error {
+          Some(error) => writeln!(
+            self.writer,
+            r#"      <failure message="{}">{}</failure>"#,
+            xml_escape(&error.message),
+            xml_escape_text(&error.formatted)
+          )?,
+          None => writeln!(self.writer, "      <failure/>")?,
+        }
+        writeln!(self.writer, "    </testcase>")
+      }
+      TestStatus::Incomplete => {
+        writeln!(self.writer, ">")?;
+        writeln!(
+          self.writer,
+          r#"      <failure message="The step was still running when its parent finished"/>"#
+        )?;
+        writeln!(self.writer, "    </testcase>")
+      }
+    }
+  }
+}
+
+impl<W: Write> TestReporter for JunitTestReporter<W> {
+  fn visit_output(&mut self, _module: &Url, _text: &str) -> Result<()> {
+    Ok(())
+  }
+
+  fn visit_message(
+    &mut self,
+    module: &Url,
+    message: &TestMessage,
+  ) -> Result<()> {
+    let suite = self.suites.entry(module.clone()).or_default();
+    if let Some(case) = suite.path.visit(message) {
+      if case.path.len() == 1 {
+        suite.cases.push(case);
+        suite.cases.append(&mut suite.steps);
+      } else {
+        suite.steps.push(case);
+      }
+    }
+    Ok(())
+  }
+
+  fn visit_module_end(
+    &mut self,
+    module: &Url,
+    maybe_error: Option<&str>,
+  ) -> Result<()> {
+    let suite = self.suites.entry(module.clone()).or_default();
+    suite.error = maybe_error.map(String::from);
+    Ok(())
+  }
+
+  fn close(
+    &mut self,
+    _summary: &TestSummary,
+    _has_error: bool,
+    elapsed: Duration,
+  ) -> Result<()> {
+    let mut suites: Vec<(Url, JunitTestSuite)> = self.suites.drain().collect();
+    suites.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+    writeln!(self.writer, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+    writeln!(
+      self.writer,
+      r#"<testsuites name="deno test" tests="{}" failures="{}" errors="{}" skipped="{}" time="{}">"#,
+      suites.iter().map(|(_, s)| s.tests()).sum::<usize>(),
+      suites.iter().map(|(_, s)| s.failures()).sum::<usize>(),
+      suites.iter().filter(|(_, s)| s.error.is_some()).count(),
+      suites
+        .iter()
+        .map(|(_, s)| s.count(TestStatus::Ignored))
+        .sum::<usize>(),
+      format_seconds(elapsed.as_millis() as u64),
+    )?;
+    for (module, suite) in &suites {
+      let name = xml_escape(module.as_str());
+      writeln!(
+        self.writer,
+        r#"  <testsuite name="{}" tests="{}" failures="{}" errors="{}" skipped="{}" time="{}">"#,
+        name,
+        suite.tests(),
+        suite.failures(),
+        suite.error.iter().count(),
+        suite.count(TestStatus::Ignored),
+        format_seconds(suite.duration()),
+      )?;
+      for case in &suite.cases {
+        self.write_case(&name, case)?;
+      }
+      if let Some(error) = &suite.error {
+        writeln!(
+          self.writer,
+          r#"    <testcase name="{}" classname="{}" time="0.000">"#,
+          name, name
+        )?;
+        writeln!(
+          self.writer,
+          r#"      <error message="{}">{}</error>"#,
+          xml_escape(error.lines().next().unwrap_or_default()),
+          xml_escape_text(error)
+        )?;
+        writeln!(self.writer, "    </testcase>")?;
+      }
+      writeln!(self.writer, "  </testsuite>")?;
+    }
+    writeln!(self.writer, "</testsuites>")?;
+    self.writer.flush()
+  }
+}
+
+#[derive(Default)]
+struct TapModule {
+  path: TestPath,
+  /// The number of steps that ended so far at each level below the current
+  /// test, which number them.
+  step_counts: Vec<usize>,
+  /// The lines of the current test, which are written at once when it ends
+  /// so that tests of modules running at the same time never interleave.
+  lines: Vec<String>,
+}
+
+/// Writes a TAP version 13 report, a line per test as they end. Steps are
+/// reported as indented subtests before their parent.
+pub struct TapTestReporter<W: Write> {
+  writer: W,
+  modules: HashMap<Url, TapModule>,
+  started: bool,
+  count: usize,
+}
+
+impl<W: Write> TapTestReporter<W> {
+  pub fn new(writer: W) -> Self {
+    Self {
+      writer,
+      modules: HashMap::new(),
+      started: false,
+      count: 0,
+    }
+  }
+
+  fn start(&mut self) -> Result<()> {
+    if !self.started {
+      self.started = true;
+      writeln!(self.writer, "TAP version 13")?;
+    }
+    Ok(())
+  }
+}
+
+/// Formats the result line of a test or step, followed by a YAML block with
+/// the error if it failed.
+fn tap_result(
+  indent: usize,
+  number: usize,
+  name: &str,
+  status: TestStatus,
+  duration: u64,
+  error: Option<&TestError>,
+) -> Vec<String> {
+  let indent = "    ".repeat(indent);
+  let name = name.replace('#', "\\#");
+  let mut lines = vec![];
+  match status {
+    TestStatus::Passed => {
+      lines.push(format!("{}ok {} - {}", indent, number, name))
+    }
+    TestStatus::Ignored => {
+      lines.push(format!("{}ok {} - {} # SKIP", indent, number, name))
+    }
+    TestStatus::Failed | TestStatus::Incomplete => {
+      lines.push(format!("{}not ok {} - {}", indent, number, name));
+      lines.push(format!("{}  ---", indent));
+      lines.push(format!("{}  duration_ms: {}", indent, duration));
+      if status == TestStatus::Incomplete {
+        lines.push(format!(
+          "{}  message: \"The step was still running when its parent finished\"",
+          indent
+        ));
+      }
+      if let Some(error) = error {
+        let message = colors::strip_ansi_codes(&error.message).to_string();
+        lines.push(format!(
+          "{}  message: {}",
+          indent,
+          deno_core::serde_json::to_string(&message).unwrap()
+        ));
+        lines.push(format!("{}  stack: |-", indent));
+        for line in colors::strip_ansi_codes(&error.formatted).lines() {
+          lines.push(format!("{}    {}", indent, line));
+        }
+      }
+      lines.push(format!("{}  ...", indent));
+    }
+  }
+  lines
+}
+
+impl<W: Write> TestReporter for TapTestReporter<W> {
+  fn visit_output(&mut self, _module: &Url, _text: &str) -> Result<()> {
+    Ok(())
+  }
+
+  fn visit_message(
+    &mut self,
+    module: &Url,
+    message: &TestMessage,
+  ) -> Result<()> {
+    self.start()?;
+    if let TestMessage::Start { .. } = message {
+      writeln!(self.writer, "# {}", module)?;
+      return self.writer.flush();
+    }
+    let tap_module = self.modules.entry(module.clone()).or_default();
+    match message {
+      TestMessage::TestStart { .. } => tap_module.step_counts = vec![0],
+      TestMessage::TestStepStart { level, .. } => {
+        tap_module.step_counts.resize(level + 1, 0);
+        if tap_module.step_counts[*level] == 0 {
+          let parent = tap_module.path.0.get(level - 1).cloned();
+          tap_module.lines.push(format!(
+            "{}# Subtest: {}",
+            "    ".repeat(*level),
+            parent.unwrap_or_default()
+          ));
+        }
+      }
+      _ => {}
+    }
+    // The path is updated after the subtest comment, which names the parent.
+    let case = match tap_module.path.visit(message) {
+      Some(case) => case,
+      None => return Ok(()),
+    };
+
+    let level = case.path.len() - 1;
+    if let Some(steps) = tap_module.step_counts.get(level + 1).copied() {
+      if steps > 0 {
+        tap_module.lines.push(format!(
+          "{}1..{}",
+          "    ".repeat(level + 1),
+          steps
+        ));
+      }
+    }
+    tap_module.step_counts.truncate(level + 1);
+    let number = if level == 0 {
+      self.count += 1;
+      self.count
+    } else {
+      tap_module.step_counts[level] += 1;
+      tap_module.step_counts[level]
+    };
+    let name = case.path.last().unwrap();
+    tap_module.lines.extend(tap_result(
+      level,
+      number,
+      name,
+      case.status,
+      case.duration,
+      case.error.as_ref(),
+    ));
+
+    if level == 0 {
+      for line in tap_module.lines.drain(..) {
+        writeln!(self.writer, "{}", line)?;
+      }
+      self.writer.flush()?;
+    }
+    Ok(())
+  }
+
+  fn visit_module_end(
+    &mut self,
+    module: &Url,
+    maybe_error: Option<&str>,
+  ) -> Result<()> {
+    self.modules.remove(module);
+    if let Some(error) = maybe_error {
+      self.start()?;
+      self.count += 1;
+      let error = TestError {
+        message: error.lines().next().unwrap_or_default().to_string(),
+        formatted: error.to_string(),
+      };
+      let lines = tap_result(
+        0,
+        self.count,
+        module.as_str(),
+        TestStatus::Failed,
+        0,
+        Some(&error),
+      );
+      for line in lines {
+        writeln!(self.writer, "{}", line)?;
+      }
+      self.writer.flush()?;
+    }
+    Ok(())
+  }
+
+  fn close(
+    &mut self,
+    summary: &TestSummary,
+    has_error: bool,
+    _elapsed: Duration,
+  ) -> Result<()> {
+    self.start()?;
+    writeln!(self.writer, "1..{}", self.count)?;
+    writeln!(self.writer, "# tests {}", self.count)?;
+    writeln!(self.writer, "# pass {}", summary.passed)?;
+    writeln!(self.writer, "# fail {}", summary.failed)?;
+    writeln!(self.writer, "# skip {}", summary.ignored)?;
+    if has_error {
+      writeln!(self.writer, "# error: some modules failed to run")?;
+    }
+    self.writer.flush()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use deno_core::serde_json;
+  use deno_core::serde_json::json;
+
+  fn messages() -> Vec<TestMessage> {
+    let error = |message: &str| {
+      json!({
+        "message": message,
+        "formatted": format!("{}\n    at file:///a_test.ts:7:9", message),
+      })
+    };
+    vec![
+      json!({ "start": { "tests": 3, "seed": null } }),
+      json!({ "testStart": { "name": "passes" } }),
+      json!({ "testEnd": { "name": "passes", "status": "passed", "duration": 2, "error": null } }),
+      json!({ "testStart": { "name": "fails <badly>" } }),
+      json!({ "testStepStart": { "name": "step 1", "level": 1 } }),
+      json!({ "testStepStart": { "name": "inner", "level": 2 } }),
+      json!({ "testStepEnd": { "name": "inner", "level": 2, "status": "failed", "duration": 1, "error": error("Error: inner") } }),
+      json!({ "testStepEnd": { "name": "step 1", "level": 1, "status": "failed", "duration": 1, "error": error("Error: 1 test step failed.") } }),
+      json!({ "testStepStart": { "name": "step 2", "level": 1 } }),
+      json!({ "testStepEnd": { "name": "step 2", "level": 1, "status": "passed", "duration": 0, "error": null } }),
+      json!({ "testEnd": { "name": "fails <badly>", "status": "failed", "duration": 4, "error": error("Error: 1 test step failed.") } }),
+      json!({ "testStart": { "name": "ignored" } }),
+      json!({ "testEnd": { "name": "ignored", "status": "ignored", "duration": 0, "error": null } }),
+      json!({ "end": { "passed": 1, "failed": 1, "ignored": 1, "measured": 0, "filtered": 0, "usedOnly": false, "duration": 6 } }),
+    ]
+    .into_iter()
+    .map(|message| serde_json::from_value(message).unwrap())
+    .collect()
+  }
+
+  fn summary() -> TestSummary {
+    TestSummary {
+      passed: 1,
+      failed: 1,
+      ignored: 1,
+      ..Default::default()
+    }
+  }
+
+  fn report(reporter: &mut dyn TestReporter) {
+    let module = Url::parse("file:///a_test.ts").unwrap();
+    for message in messages() {
+      reporter.visit_message(&module, &message).unwrap();
+    }
+    reporter.visit_module_end(&module, None).unwrap();
+    let module = Url::parse("file:///b_test.ts").unwrap();
+    reporter
+      .visit_module_end(&module, Some("error: Uncaught Error: oops"))
+      .unwrap();
+    reporter
+      .close(&summary(), true, Duration::from_millis(1500))
+      .unwrap();
+  }
+
+  #[test]
+  fn test_junit_reporter() {
+    let mut reporter = JunitTestReporter::new(vec![]);
+    report(&mut reporter);
+    let xml = String::from_utf8(reporter.writer).unwrap();
+    assert_eq!(
+      xml,
+      r#"<?xml version="1.0" encoding="UTF-8"?>
+<testsuites name="deno test" tests="7" failures="3" errors="1" skipped="1" time="1.500">
+  <testsuite name="file:///a_test.ts" tests="6" failures="3" errors="0" skipped="1" time="0.006">
+    <testcase name="passes" classname="file:///a_test.ts" time="0.002"/>
+    <testcase name="fails &lt;badly&gt;" classname="file:///a_test.ts" time="0.004">
+      <failure message="Error: 1 test step failed.">Error: 1 test step failed.
+    at file:///a_test.ts:7:9</failure>
+    </testcase>
+    <testcase name="fails &lt;badly&gt; &gt; step 1 &gt; inner" classname="file:///a_test.ts" time="0.001">
+      <failure message="Error: inner">Error: inner
+    at file:///a_test.ts:7:9</failure>
+    </testcase>
+    <testcase name="fails &lt;badly&gt; &gt; step 1" classname="file:///a_test.ts" time="0.001">
+      <failure message="Error: 1 test step failed.">Error: 1 test step failed.
+    at file:///a_test.ts:7:9</failure>
+    </testcase>
+    <testcase name="fails &lt;badly&gt; &gt; step 2" classname="file:///a_test.ts" time="0.000"/>
+    <testcase name="ignored" classname="file:///a_test.ts" time="0.000">
+      <skipped/>
+    </testcase>
+  </testsuite>
+  <testsuite name="file:///b_test.ts" tests="1" failures="0" errors="1" skipped="0" time="0.000">
+    <testcase name="file:///b_test.ts" classname="file:///b_test.ts" time="0.000">
+      <error message="error: Uncaught Error: oops">error: Uncaught Error: oops</error>
+    </testcase>
+  </testsuite>
+</testsuites>
+"#
+    );
+  }
+
+  #[test]
+  fn test_tap_reporter() {
+    let mut reporter = TapTestReporter::new(vec![]);
+    report(&mut reporter);
+    let tap = String::from_utf8(reporter.writer).unwrap();
+    assert_eq!(
+      tap,
+      r#"TAP version 13
+# file:///a_test.ts
+ok 1 - passes
+    # Subtest: fails <badly>
+        # Subtest: step 1
+        not ok 1 - inner
+          ---
+          duration_ms: 1
+          message: "Error: inner"
+          stack: |-
+            Error: inner
+                at file:///a_test.ts:7:9
+          ...
+        1..1
+    not ok 1 - step 1
+      ---
+      duration_ms: 1
+      message: "Error: 1 test step failed."
+      stack: |-
+        Error: 1 test step failed.
+            at file:///a_test.ts:7:9
+      ...
+    ok 2 - step 2
+    1..2
+not ok 2 - fails <badly>
+  ---
+  duration_ms: 4
+  message: "Error: 1 test step failed."
+  stack: |-
+    Error: 1 test step failed.
+        at file:///a_test.ts:7:9
+  ...
+ok 3 - ignored # SKIP
+not ok 4 - file:///b_test.ts
+  ---
+  duration_ms: 0
+  message: "error: Uncaught Error: oops"
+  stack: |-
+    error: Uncaught Error: oops
+  ...
+1..4
+# tests 4
+# pass 1
+# fail 1
+# skip 1
+# error: some modules failed to run
+"#
+    );
+  }
+
+  #[test]
+  fn test_dot_reporter() {
+    let mut reporter = DotTestReporter::new(vec![]);
+    report(&mut reporter);
+    let text = String::from_utf8(reporter.writer).unwrap();
+    let text = colors::strip_ansi_codes(&text);
+    assert!(text.starts_with(".FiE\n\nfailures:\n\n"));
+    assert!(text.contains(
+      "file:///a_test.ts > fails <badly> > step 1 > inner\nError: inner\n"
+    ));
+    assert!(text.contains("file:///b_test.ts\nerror: error: Uncaught Error"));
+    assert!(text.contains(
+      "test result: FAILED. 1 passed; 1 failed; 1 ignored; 0 measured; 0 filtered out (1500ms)"
+    ));
+  }
+
+  #[test]
+  fn test_xml_escape() {
+    assert_eq!(
+      xml_escape("\u{1b}[31m<a href=\"x\">&'</a>\u{1b}[0m\u{7}\n"),
+      "&lt;a href=&quot;x&quot;&gt;&amp;&apos;&lt;/a&gt;&#10;"
+    );
+    assert_eq!(xml_escape_text("a\nb"), "a\nb");
+  }
+}
@@ -1,12 +1,33 @@
 // Copyright 2018-2021 the Deno authors. All rights reserved. MIT license.
 
 use crate::fs_util;
+use crate::program_state::ProgramState;
+use crate::tokio_util;
 use crate::tools::installer::is_remote_url;
+use crate::tools::test_reporter::TestMessage;
+use crate::tools::test_reporter::TestReporter;
+use deno_core::error::generic_error;
 use deno_core::error::AnyError;
 use deno_core::serde_json::json;
+use deno_core::serde_json::Value;
 use deno_core::url::Url;
+use deno_core::v8;
+use deno_core::ModuleSpecifier;
+use deno_runtime::permissions::Permissions;
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+use regex::Regex;
+use serde::Deserialize;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::panic::AssertUnwindSafe;
 use std::path::Path;
 use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Instant;
+use tokio::sync::mpsc::unbounded_channel;
+use tokio::sync::mpsc::UnboundedSender;
 
 fn is_supported(p: &Path) -> bool {
   use std::path::Component;
@@ -35,6 +56,16 @@ fn is_supported(p: &Path) -> bool {
 pub fn prepare_test_modules_urls(
   include: Vec<String>,
   root_path: &PathBuf,
+) -> Result<Vec<Url>, AnyError> {
+  collect_module_urls(include, root_path, is_supported)
+}
+
+/// Resolves `include` against `root_path`, expanding directories to the files
+/// in them that `is_supported` accepts. Remote URLs are kept as they are.
+pub fn collect_module_urls(
+  include: Vec<String>,
+  root_path: &PathBuf,
+  is_supported: fn(&Path) -> bool,
 ) -> Result<Vec<Url>, AnyError> {
   let (include_paths, include_urls): (Vec<String>, Vec<String>) =
     include.into_iter().partition(|n| !is_remote_url(n));
@@ -64,11 +95,109 @@ pub fn prepare_test_modules_urls(
   Ok(prepared)
 }
 
+/// Puts the test modules into the order given by `seed`. The same set of
+/// modules and seed always gives the same order.
+pub fn shuffle_test_modules(modules: &mut [Url], seed: u64) {
+  modules.sort();
+  modules.shuffle(&mut StdRng::seed_from_u64(seed));
+}
+
+/// A single pattern given to `deno test --filter` or `--skip`.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(tag = "type", content = "pattern", rename_all = "camelCase")]
+pub enum FilterPattern {
+  /// Matches test names containing the string.
+  Substring(String),
+  /// `"name"`, matches only the test called exactly `name`.
+  Exact(String),
+  /// `/regex/`, matches test names the regex matches.
+  Regex(String),
+}
+
+impl FilterPattern {
+  fn parse(pattern: &str) -> Result<Self, AnyError> {
+    if pattern.len() >= 2 && pattern.starts_with('/') && pattern.ends_with('/')
+    {
+      let regex = &pattern[1..pattern.len() - 1];
+      if let Err(err) = Regex::new(regex) {
+        return Err(generic_error(format!(
+          "Invalid filter pattern \"{}\": {}",
+          pattern, err
+        )));
+      }
+      Ok(FilterPattern::Regex(regex.to_string()))
+    } else if pattern.len() >= 2
+      && pattern.starts_with('"')
+      && pattern.ends_with('"')
+    {
+      Ok(FilterPattern::Exact(
+        pattern[1..pattern.len() - 1].to_string(),
+      ))
+    } else {
+      Ok(FilterPattern::Substring(pattern.to_string()))
+    }
+  }
+}
+
+/// Decides which tests run, passed to `runTests()` in JS. Tests that don't
+/// pass are counted as filtered out.
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+pub struct TestFilter {
+  /// If not empty, a test has to match at least one of these.
+  pub include: Vec<FilterPattern>,
+  /// A test must not match any of these.
+  pub exclude: Vec<FilterPattern>,
+}
+
+impl TestFilter {
+  /// Parses the values of `--filter` and `--skip`. A `--filter` starting
+  /// with `!` excludes the tests matching the rest of it.
+  pub fn parse(
+    maybe_filter: Option<&str>,
+    skip: &[String],
+  ) -> Result<Self, AnyError> {
+    let mut filter = TestFilter::default();
+    if let Some(pattern) = maybe_filter {
+      if let Some(pattern) = pattern.strip_prefix('!') {
+        filter.exclude.push(FilterPattern::parse(pattern)?);
+      } else {
+        filter.include.push(FilterPattern::parse(pattern)?);
+      }
+    }
+    for pattern in skip {
+      filter.exclude.push(FilterPattern::parse(pattern)?);
+    }
+    Ok(filter)
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.include.is_empty() && self.exclude.is_empty()
+  }
+}
+
+fn test_options(
+  fail_fast: bool,
+  quiet: bool,
+  filter: &TestFilter,
+  shuffle: Option<u64>,
+) -> Value {
+  let mut options = json!({ "failFast": fail_fast, "reportToConsole": !quiet, "disableLog": quiet });
+  if !filter.is_empty() {
+    options["filter"] = json!(filter);
+  }
+  // Passed as a string since JS numbers can't hold every u64.
+  if let Some(seed) = shuffle {
+    options["shuffle"] = json!(seed.to_string());
+  }
+  options
+}
+
 pub fn render_test_file(
   modules: Vec<Url>,
   fail_fast: bool,
   quiet: bool,
-  filter: Option<String>,
+  filter: &TestFilter,
+  shuffle: Option<u64>,
 ) -> String {
   let mut test_file = "".to_string();
 
@@ -76,11 +205,7 @@ pub fn render_test_file(
     test_file.push_str(&format!("import \"{}\";\n", module.to_string()));
   }
 
-  let options = if let Some(filter) = filter {
-    json!({ "failFast": fail_fast, "reportToConsole": !quiet, "disableLog": quiet, "filter": filter })
-  } else {
-    json!({ "failFast": fail_fast, "reportToConsole": !quiet, "disableLog": quiet })
-  };
+  let options = test_options(fail_fast, quiet, filter, shuffle);
 
   test_file.push_str("// @ts-ignore\n");
 
@@ -92,6 +217,240 @@ pub fn render_test_file(
   test_file
 }
 
+/// Like `render_test_file()`, but for a single module that runs in its own
+/// worker as part of `deno test --jobs` or `--reporter`. Instead of writing to
+/// stdout, the report is handed to `run_tests_concurrently()` through the ops
+/// in `ops::testing`: the formatted text, or with `structured`, the messages
+/// of the test runner for the reporters in `test_reporter`.
+pub fn render_concurrent_test_file(
+  module: Url,
+  fail_fast: bool,
+  quiet: bool,
+  filter: &TestFilter,
+  shuffle: Option<u64>,
+  structured: bool,
+) -> String {
+  let options = test_options(fail_fast, quiet, filter, shuffle);
+  let report = if structured {
+    r#"reportToConsole: false,
+  onMessage: (message) =>
+    Deno.core.jsonOpSync(
+      "op_test_message",
+      Deno[Deno.internal].serializeTestMessage(message),
+    ),"#
+  } else {
+    r#"onOutput: (text) => Deno.core.jsonOpSync("op_test_output", { text }),"#
+  };
+
+  format!(
+    r#"import "{}";
+const {{ results: _, ...summary }} = await Deno[Deno.internal].runTests({{
+  ...{},
+  exitOnFail: false,
+  {}
+}});
+Deno.core.jsonOpSync("op_test_summary", summary);
+"#,
+    module, options, report
+  )
+}
+
+/// The counts reported by `runTests()` for a single test module.
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TestSummary {
+  pub passed: usize,
+  pub failed: usize,
+  pub ignored: usize,
+  pub measured: usize,
+  pub filtered: usize,
+  pub used_only: bool,
+}
+
+impl TestSummary {
+  fn add(&mut self, other: &TestSummary) {
+    self.passed += other.passed;
+    self.failed += other.failed;
+    self.ignored += other.ignored;
+    self.measured += other.measured;
+    self.filtered += other.filtered;
+    self.used_only |= other.used_only;
+  }
+}
+
+pub enum TestEvent {
+  /// The worker running the module has been created; the handle is used to
+  /// terminate it when `--fail-fast` kicks in.
+  Started(v8::IsolateHandle),
+  /// Report output, formatted by the JS runner.
+  Output(String),
+  /// A message of the JS runner, for reporters that format it themselves.
+  Message(TestMessage),
+  Summary(TestSummary),
+  /// The module is done, possibly with an error that kept it from finishing
+  /// its tests.
+  Finished(Option<String>),
+}
+
+/// Sends the events of a single test module, see `TestEvent`. Put into the
+/// `OpState` of the module's worker.
+#[derive(Clone)]
+pub struct TestEventSender {
+  index: usize,
+  sender: UnboundedSender<(usize, TestEvent)>,
+}
+
+impl TestEventSender {
+  pub fn send(&self, event: TestEvent) {
+    // The receiver only goes away once the test run is over, in which case
+    // nobody is interested in the event anymore.
+    let _ = self.sender.send((self.index, event));
+  }
+}
+
+async fn run_test_module(
+  program_state: Arc<ProgramState>,
+  main_module: ModuleSpecifier,
+  permissions: Permissions,
+  sender: TestEventSender,
+) -> Result<(), AnyError> {
+  let mut worker =
+    crate::create_main_worker(&program_state, main_module.clone(), permissions);
+  worker
+    .js_runtime
+    .op_state()
+    .borrow_mut()
+    .put(sender.clone());
+  sender.send(TestEvent::Started(worker.js_runtime.isolate_handle()));
+
+  worker.execute_module(&main_module).await?;
+  worker.execute("window.dispatchEvent(new Event('load'))")?;
+  worker.run_event_loop().await?;
+  worker.execute("window.dispatchEvent(new Event('unload'))")?;
+  worker.run_event_loop().await?;
+  Ok(())
+}
+
+fn spawn_test_module(
+  program_state: Arc<ProgramState>,
+  main_module: ModuleSpecifier,
+  permissions: Permissions,
+  sender: TestEventSender,
+) {
+  std::thread::spawn(move || {
+    // The scheduler waits for `Finished`, so it's sent even if the worker
+    // panics.
+    let result = std::panic::catch_unwind(AssertUnwindSafe(|| {
+      tokio_util::run_basic(run_test_module(
+        program_state,
+        main_module,
+        permissions,
+        sender.clone(),
+      ))
+    }));
+    let maybe_error = match result {
+      Ok(result) => result.err().map(|err| err.to_string()),
+      Err(_) => Some("test module panicked".to_string()),
+    };
+    sender.send(TestEvent::Finished(maybe_error));
+  });
+}
+
+/// Runs each of `main_modules`, as rendered by `render_concurrent_test_file()`
+/// for the test module paired with it, in its own worker thread, with at most
+/// `concurrent_jobs` of them at a time. Their events are handed to
+/// `reporter` as they come.
+///
+/// Returns whether the test run failed.
+pub async fn run_tests_concurrently(
+  program_state: Arc<ProgramState>,
+  permissions: Permissions,
+  main_modules: Vec<(ModuleSpecifier, Url)>,
+  concurrent_jobs: usize,
+  fail_fast: bool,
+  mut reporter: Box<dyn TestReporter>,
+) -> Result<bool, AnyError> {
+  let start = Instant::now();
+  let (sender, mut receiver) = unbounded_channel();
+  let test_modules: Vec<Url> = main_modules
+    .iter()
+    .map(|(_, module)| module.clone())
+    .collect();
+  let mut pending = main_modules.into_iter().enumerate();
+  // Returns whether there was a module left to spawn.
+  let mut spawn_next = || {
+    if let Some((index, (main_module, _))) = pending.next() {
+      spawn_test_module(
+        program_state.clone(),
+        main_module,
+        permissions.clone(),
+        TestEventSender {
+          index,
+          sender: sender.clone(),
+        },
+      );
+      true
+    } else {
+      false
+    }
+  };
+  let mut running = 0;
+  for _ in 0..concurrent_jobs {
+    if spawn_next() {
+      running += 1;
+    }
+  }
+
+  let mut isolates: HashMap<usize, v8::IsolateHandle> = HashMap::new();
+  let mut summaries: HashMap<usize, TestSummary> = HashMap::new();
+  let mut summary = TestSummary::default();
+  let mut has_error = false;
+
+  while running > 0 {
+    let (index, event) = receiver.recv().await.unwrap();
+    let test_module = &test_modules[index];
+    match event {
+      TestEvent::Started(isolate) => {
+        isolates.insert(index, isolate);
+      }
+      TestEvent::Output(text) => {
+        reporter.visit_output(test_module, &text)?;
+      }
+      TestEvent::Message(message) => {
+        reporter.visit_message(test_module, &message)?;
+      }
+      TestEvent::Summary(module_summary) => {
+        summary.add(&module_summary);
+        summaries.insert(index, module_summary);
+      }
+      TestEvent::Finished(maybe_error) => {
+        running -= 1;
+        isolates.remove(&index);
+        let failed = summaries.get(&index).map_or(false, |s| s.failed > 0)
+          || maybe_error.is_some();
+        has_error |= maybe_error.is_some();
+        reporter.visit_module_end(test_module, maybe_error.as_deref())?;
+
+        if fail_fast && failed {
+          // The remaining workers are abandoned; the process exits as soon as
+          // the summary is printed.
+          for isolate in isolates.values() {
+            isolate.terminate_execution();
+          }
+          break;
+        }
+        if spawn_next() {
+          running += 1;
+        }
+      }
+    }
+  }
+
+  reporter.close(&summary, has_error, start.elapsed())?;
+
+  Ok(has_error || summary.failed > 0 || summary.used_only)
+}
+
 #[cfg(test)]
 mod tests {
   use super::*;
@@ -127,6 +486,79 @@ mod tests {
     assert_eq!(matched_urls, expected);
   }
 
+  #[test]
+  fn test_filter_parse() {
+    let filter = TestFilter::parse(Some("/^integration: .*db$/"), &[]).unwrap();
+    assert_eq!(
+      filter.include,
+      vec![FilterPattern::Regex("^integration: .*db$".to_string())]
+    );
+    assert!(filter.exclude.is_empty());
+
+    let filter = TestFilter::parse(Some("!flaky"), &[]).unwrap();
+    assert!(filter.include.is_empty());
+    assert_eq!(
+      filter.exclude,
+      vec![FilterPattern::Substring("flaky".to_string())]
+    );
+
+    let filter = TestFilter::parse(
+      Some("\"exact name\""),
+      &["/slow$/".to_string(), "net".to_string()],
+    )
+    .unwrap();
+    assert_eq!(
+      filter,
+      TestFilter {
+        include: vec![FilterPattern::Exact("exact name".to_string())],
+        exclude: vec![
+          FilterPattern::Regex("slow$".to_string()),
+          FilterPattern::Substring("net".to_string()),
+        ],
+      }
+    );
+    assert_eq!(
+      json!(filter),
+      json!({
+        "include": [{ "type": "exact", "pattern": "exact name" }],
+        "exclude": [
+          { "type": "regex", "pattern": "slow$" },
+          { "type": "substring", "pattern": "net" },
+        ],
+      })
+    );
+
+    // A lone slash or quote is just a substring.
+    assert_eq!(
+      TestFilter::parse(Some("/"), &[]).unwrap().include,
+      vec![FilterPattern::Substring("/".to_string())]
+    );
+
+    let err = TestFilter::parse(Some("/(/"), &[]).unwrap_err();
+    assert!(err
+      .to_string()
+      .starts_with("Invalid filter pattern \"/(/\": regex parse error"));
+    assert!(TestFilter::parse(None, &["/[/".to_string()]).is_err());
+  }
+
+  #[test]
+  fn test_shuffle_test_modules() {
+    let modules: Vec<Url> = (0..10)
+      .map(|i| Url::parse(&format!("file:///{}_test.ts", i)).unwrap())
+      .collect();
+    let shuffled = |seed| {
+      let mut modules = modules.clone();
+      modules.reverse();
+      shuffle_test_modules(&mut modules, seed);
+      modules
+    };
+    assert_eq!(shuffled(1), shuffled(1));
+    assert_ne!(shuffled(1), shuffled(2));
+    let mut sorted = shuffled(1);
+    sorted.sort();
+    assert_eq!(sorted, modules);
+  }
+
   #[test]
   fn test_is_supported() {
     assert!(is_supported(Path::new("tests/subdir/foo_test.ts")));
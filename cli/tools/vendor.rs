@@ -0,0 +1,590 @@
+// Copyright 2018-2021 the Deno authors. All rights reserved. MIT license.
+
+//! `deno vendor`: copies the remote modules of a module graph into a
+//! `vendor` directory, together with an import map that resolves the
+//! original specifiers to the copies.
+
+use crate::ast;
+use crate::colors;
+use crate::media_type::MediaType;
+use crate::module_graph::GraphBuilder;
+use crate::program_state::read_embedded_import_map;
+use crate::program_state::ProgramState;
+use crate::specifier_handler::FetchHandler;
+use deno_core::error::generic_error;
+use deno_core::error::AnyError;
+use deno_core::error::Context;
+use deno_core::resolve_url_or_path;
+use deno_core::serde_json;
+use deno_core::serde_json::Map;
+use deno_core::serde_json::Value;
+use deno_core::url::Url;
+use deno_core::ModuleSpecifier;
+use deno_runtime::permissions::Permissions;
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+use std::sync::Mutex;
+
+pub const VENDOR_DIR: &str = "vendor";
+const IMPORT_MAP_FILE: &str = "import_map.json";
+
+fn is_remote(specifier: &ModuleSpecifier) -> bool {
+  specifier.scheme() == "http" || specifier.scheme() == "https"
+}
+
+/// Replaces the characters that aren't safe in file names on every platform,
+/// or that would need percent-encoding in a file URL, with `_`.
+fn sanitize(name: &str) -> String {
+  name
+    .chars()
+    .map(|c| {
+      if c.is_ascii_alphanumeric() || "-_.@~+=,".contains(c) {
+        c
+      } else {
+        '_'
+      }
+    })
+    .collect()
+}
+
+/// The directory of the copy of `specifier`, relative to the vendor
+/// directory, and the last segment of its path as it appears in the URL.
+fn split_local_path(specifier: &ModuleSpecifier) -> (String, String) {
+  let mut dir = sanitize(specifier.host_str().unwrap_or(""));
+  if let Some(port) = specifier.port() {
+    dir.push_str(&format!("_{}", port));
+  }
+  let mut segments: Vec<&str> = specifier
+    .path_segments()
+    .map(|segments| segments.collect())
+    .unwrap_or_default();
+  let name = segments.pop().unwrap_or("").to_string();
+  for segment in segments {
+    dir.push('/');
+    dir.push_str(&sanitize(segment));
+  }
+  (dir, name)
+}
+
+fn query_suffix(specifier: &ModuleSpecifier) -> String {
+  match specifier.query() {
+    Some(query) => format!("?{}", query),
+    None => String::new(),
+  }
+}
+
+/// Assigns each remote module a path in the vendor directory.
+#[derive(Default)]
+struct LocalPaths {
+  /// The lowercased paths that are taken, so that modules whose URLs only
+  /// differ in case don't overwrite each other on case-insensitive file
+  /// systems.
+  taken: HashSet<String>,
+}
+
+impl LocalPaths {
+  /// Returns the path of the copy of `specifier`, relative to the vendor
+  /// directory and with `/` as the separator. It is the host followed by the
+  /// path of the URL, unless that path has a query, characters that aren't
+  /// safe in file names, an extension that doesn't match `media_type` or is
+  /// already taken. The file name then gets a hash of the URL and the
+  /// extension of `media_type`.
+  fn insert(
+    &mut self,
+    specifier: &ModuleSpecifier,
+    media_type: MediaType,
+  ) -> String {
+    let (dir, name) = split_local_path(specifier);
+    let path = format!("{}/{}", dir, name);
+    if specifier.query().is_none()
+      && sanitize(&name) == name
+      && MediaType::from(&name) == media_type
+      && self.taken.insert(path.to_lowercase())
+    {
+      return path;
+    }
+
+    let ext = media_type.as_ts_extension();
+    let name = if name.is_empty() { "index" } else { &name };
+    let stem = sanitize(name.strip_suffix(ext).unwrap_or(name));
+    let hash = crate::checksum::gen(&[specifier.as_str()]);
+    let mut len = 8;
+    loop {
+      let path = format!("{}/{}_{}{}", dir, stem, &hash[..len], ext);
+      if self.taken.insert(path.to_lowercase()) || len == hash.len() {
+        return path;
+      }
+      len = hash.len();
+    }
+  }
+}
+
+/// The import map entries that resolve the remote modules to their copies.
+/// The vendored modules keep importing each other by their original
+/// specifiers, so besides the absolute URLs, the relative specifiers they
+/// use are mapped wherever the copy isn't where the specifier points to.
+#[derive(Default)]
+struct Mappings {
+  prefixes: BTreeMap<String, String>,
+  exact: BTreeMap<String, String>,
+}
+
+impl Mappings {
+  /// Maps `specifier` to the copy at `local_path` of the module it resolves
+  /// to, which is a different module for redirects.
+  fn insert(&mut self, specifier: &ModuleSpecifier, local_path: &str) {
+    let (dir, name) = split_local_path(specifier);
+    let local = format!("./{}", local_path);
+    let mut origin = specifier.clone();
+    origin.set_path("/");
+    origin.set_query(None);
+    let host_dir = dir.split('/').next().unwrap().to_string();
+    // Redirects don't get a prefix, as nothing is copied to their host.
+    if local_path.starts_with(&format!("{}/", host_dir)) {
+      self
+        .prefixes
+        .insert(origin.to_string(), format!("./{}/", host_dir));
+    }
+
+    let prefixed = format!(
+      "./{}{}{}",
+      host_dir,
+      specifier.path(),
+      query_suffix(specifier)
+    );
+    if prefixed != local {
+      self.exact.insert(specifier.to_string(), local.clone());
+    }
+    let relative = format!("./{}/{}{}", dir, name, query_suffix(specifier));
+    if relative != local {
+      self.exact.insert(relative, local);
+    }
+  }
+
+  /// The copy of the remote `specifier`, if it was vendored.
+  fn resolve(&self, specifier: &ModuleSpecifier) -> Option<String> {
+    if let Some(local) = self.exact.get(specifier.as_str()) {
+      return Some(local.clone());
+    }
+    let (origin, local_dir) = self
+      .prefixes
+      .iter()
+      .find(|(origin, _)| specifier.as_str().starts_with(origin.as_str()))?;
+    Some(format!(
+      "{}{}",
+      local_dir,
+      &specifier.as_str()[origin.len()..]
+    ))
+  }
+}
+
+/// Returns `path` relative to `dir`, as an import map address.
+fn relative_specifier(dir: &Path, path: &Path) -> String {
+  let dir: Vec<_> = dir.components().collect();
+  let path: Vec<_> = path.components().collect();
+  let common = dir
+    .iter()
+    .zip(path.iter())
+    .take_while(|(a, b)| a == b)
+    .count();
+  let mut parts: Vec<String> = vec![".".to_string()];
+  parts.extend((common..dir.len()).map(|_| "..".to_string()));
+  parts.extend(
+    path[common..]
+      .iter()
+      .map(|c| c.as_os_str().to_string_lossy().to_string()),
+  );
+  if parts.len() > 1 && parts[1] == ".." {
+    parts.remove(0);
+  }
+  parts.join("/")
+}
+
+/// Rewrites a URL-like specifier or address of the user's import map at
+/// `base` for the import map in `vendor_dir`: remote modules that were
+/// vendored point at their copies and local files are made relative to the
+/// vendor directory. Anything else, like bare specifiers, is kept.
+fn rewrite_specifier(
+  specifier: &str,
+  base: &Url,
+  vendor_dir: &Path,
+  mappings: &Mappings,
+) -> String {
+  let is_url_like = specifier.starts_with('/')
+    || specifier.starts_with("./")
+    || specifier.starts_with("../");
+  let maybe_url = if is_url_like {
+    base.join(specifier)
+  } else {
+    Url::parse(specifier)
+  };
+  let url = match maybe_url {
+    Ok(url) => url,
+    Err(_) => return specifier.to_string(),
+  };
+  if is_remote(&url) {
+    mappings
+      .resolve(&url)
+      .unwrap_or_else(|| specifier.to_string())
+  } else if let Ok(path) = url.to_file_path() {
+    let mut local = relative_specifier(vendor_dir, &path);
+    if specifier.ends_with('/') {
+      local.push('/');
+    }
+    local
+  } else {
+    specifier.to_string()
+  }
+}
+
+fn rewrite_specifier_map(
+  map: &Map<String, Value>,
+  base: &Url,
+  vendor_dir: &Path,
+  mappings: &Mappings,
+) -> Map<String, Value> {
+  map
+    .iter()
+    .map(|(key, address)| {
+      let key = if key.starts_with("file:")
+        || key.starts_with('/')
+        || key.starts_with("./")
+        || key.starts_with("../")
+      {
+        rewrite_specifier(key, base, vendor_dir, mappings)
+      } else {
+        key.clone()
+      };
+      let address = match address {
+        Value::String(address) => {
+          Value::String(rewrite_specifier(address, base, vendor_dir, mappings))
+        }
+        address => address.clone(),
+      };
+      (key, address)
+    })
+    .collect()
+}
+
+/// Builds the import map for the vendor directory, starting out with the
+/// rewritten entries of the user's import map, if any.
+fn build_import_map(
+  maybe_user_import_map: Option<(Url, Value)>,
+  vendor_dir: &Path,
+  mappings: &Mappings,
+) -> Value {
+  let mut imports = Map::new();
+  let mut scopes = Map::new();
+  if let Some((base, user_import_map)) = maybe_user_import_map {
+    if let Some(Value::Object(user_imports)) = user_import_map.get("imports") {
+      imports =
+        rewrite_specifier_map(user_imports, &base, vendor_dir, mappings);
+    }
+    if let Some(Value::Object(user_scopes)) = user_import_map.get("scopes") {
+      for (scope, scope_imports) in user_scopes {
+        if let Value::Object(scope_imports) = scope_imports {
+          scopes.insert(
+            rewrite_specifier(scope, &base, vendor_dir, mappings),
+            Value::Object(rewrite_specifier_map(
+              scope_imports,
+              &base,
+              vendor_dir,
+              mappings,
+            )),
+          );
+        }
+      }
+    }
+  }
+
+  for (specifier, local) in mappings.prefixes.iter().chain(&mappings.exact) {
+    imports.insert(specifier.clone(), Value::String(local.clone()));
+  }
+  let mut import_map = Map::new();
+  import_map.insert("imports".to_string(), Value::Object(imports));
+  if !scopes.is_empty() {
+    import_map.insert("scopes".to_string(), Value::Object(scopes));
+  }
+  Value::Object(import_map)
+}
+
+async fn read_user_import_map(
+  program_state: &Arc<ProgramState>,
+) -> Result<Option<(Url, Value)>, AnyError> {
+  let import_map_path = match program_state.flags.import_map_path.as_ref() {
+    Some(path) => path,
+    None => return read_embedded_import_map(&program_state.flags),
+  };
+  let specifier = resolve_url_or_path(import_map_path)?;
+  let file = program_state
+    .file_fetcher
+    .fetch(&specifier, &Permissions::allow_all())
+    .await?;
+  let import_map = serde_json::from_str(&file.source)
+    .with_context(|| format!("Unable to parse import map {}", specifier))?;
+  Ok(Some((specifier, import_map)))
+}
+
+/// Vendors the remote modules of the graph of `entrypoints` into the `vendor`
+/// directory of the current directory. An existing vendor directory is only
+/// replaced if `force` is set.
+pub async fn vendor(
+  program_state: Arc<ProgramState>,
+  entrypoints: Vec<String>,
+  force: bool,
+) -> Result<(), AnyError> {
+  let vendor_dir = std::env::current_dir()?.join(VENDOR_DIR);
+  if vendor_dir.exists() && !force {
+    return Err(generic_error(format!(
+      "Directory \"{}\" already exists. Use --force to overwrite it.",
+      VENDOR_DIR
+    )));
+  }
+
+  let handler = Arc::new(Mutex::new(FetchHandler::new(
+    &program_state,
+    Permissions::allow_all(),
+  )?));
+  let mut builder = GraphBuilder::new(
+    handler,
+    program_state.maybe_import_map.clone(),
+    program_state.lockfile.clone(),
+  );
+  builder.set_compat(program_state.flags.compat);
+  for entrypoint in &entrypoints {
+    builder
+      .add(&resolve_url_or_path(entrypoint)?, false)
+      .await?;
+  }
+  let graph = builder.get_graph();
+
+  let mut modules = graph.get_modules();
+  modules.sort();
+  let mut non_analyzable = vec![];
+  let mut local_paths = LocalPaths::default();
+  let mut vendored: HashMap<ModuleSpecifier, String> = HashMap::new();
+  let mut files = vec![];
+  for specifier in &modules {
+    let (source, media_type) =
+      match (graph.get_source(specifier), graph.get_media_type(specifier)) {
+        (Some(source), Some(media_type)) => (source, media_type),
+        _ => continue,
+      };
+    if let Ok(parsed_module) =
+      ast::parse(specifier.as_str(), &source, &media_type)
+    {
+      non_analyzable.extend(parsed_module.get_non_analyzable_dynamic_imports());
+    }
+    if is_remote(specifier) {
+      let local_path = local_paths.insert(specifier, media_type);
+      files.push((local_path.clone(), source));
+      vendored.insert(specifier.clone(), local_path);
+    }
+  }
+
+  let mut mappings = Mappings::default();
+  for (specifier, local_path) in &vendored {
+    mappings.insert(specifier, local_path);
+  }
+  for (specifier, redirect) in graph.get_redirects() {
+    if let Some(local_path) = vendored.get(&redirect) {
+      if is_remote(&specifier) {
+        mappings.insert(&specifier, local_path);
+      }
+    }
+  }
+  let maybe_user_import_map = read_user_import_map(&program_state).await?;
+  let import_map =
+    build_import_map(maybe_user_import_map, &vendor_dir, &mappings);
+
+  if vendor_dir.exists() {
+    fs::remove_dir_all(&vendor_dir)?;
+  }
+  fs::create_dir_all(&vendor_dir)?;
+  for (local_path, source) in &files {
+    let path = vendor_dir.join(local_path);
+    fs::create_dir_all(path.parent().unwrap())?;
+    fs::write(&path, source)
+      .with_context(|| format!("Unable to write {}", path.display()))?;
+  }
+  fs::write(
+    vendor_dir.join(IMPORT_MAP_FILE),
+    format!("{}\n", serde_json::to_string_pretty(&import_map)?),
+  )?;
+
+  if !non_analyzable.is_empty() {
+    eprintln!(
+      "{} These dynamic imports are not statically analyzable, so the modules they load were not vendored:",
+      colors::yellow("Warning")
+    );
+    for location in non_analyzable {
+      eprintln!("  at {}", location);
+    }
+  }
+  println!(
+    "Vendored {} {} into {}/ directory.\n\nTo use the vendored modules, run with these flags:\n  --unstable --no-remote --import-map {}/{}",
+    files.len(),
+    if files.len() == 1 { "module" } else { "modules" },
+    VENDOR_DIR,
+    VENDOR_DIR,
+    IMPORT_MAP_FILE
+  );
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn url(specifier: &str) -> ModuleSpecifier {
+    Url::parse(specifier).unwrap()
+  }
+
+  #[test]
+  fn local_paths() {
+    let mut local_paths = LocalPaths::default();
+    let mut insert = |specifier: &str, media_type| {
+      local_paths.insert(&url(specifier), media_type)
+    };
+    assert_eq!(
+      insert("https://deno.land/std/path/mod.ts", MediaType::TypeScript),
+      "deno.land/std/path/mod.ts"
+    );
+    assert_eq!(
+      insert("http://localhost:4545/a/b.js", MediaType::JavaScript),
+      "localhost_4545/a/b.js"
+    );
+    // A query, an extension that doesn't match the media type, unsafe
+    // characters and a path that only differs in case get a hash.
+    let with_query = insert(
+      "https://deno.land/std/path/mod.ts?v=1",
+      MediaType::TypeScript,
+    );
+    assert!(with_query.starts_with("deno.land/std/path/mod_"));
+    assert!(with_query.ends_with(".ts"));
+    assert_eq!(with_query.len(), "deno.land/std/path/mod_12345678.ts".len());
+    let no_ext = insert("https://esm.sh/react@17", MediaType::JavaScript);
+    assert!(no_ext.starts_with("esm.sh/react@17_"));
+    assert!(no_ext.ends_with(".js"));
+    let dts = insert("https://esm.sh/react.d.ts?x", MediaType::Dts);
+    assert!(dts.starts_with("esm.sh/react_"));
+    assert!(dts.ends_with(".d.ts"));
+    let unsafe_chars =
+      insert("https://a.com/dir:x/a%20b.ts", MediaType::TypeScript);
+    assert!(unsafe_chars.starts_with("a.com/dir_x/a_20b_"));
+    let other_case =
+      insert("https://deno.land/std/path/MOD.ts", MediaType::TypeScript);
+    assert!(other_case.starts_with("deno.land/std/path/MOD_"));
+  }
+
+  #[test]
+  fn mappings() {
+    let mut mappings = Mappings::default();
+    mappings.insert(
+      &url("http://localhost:4545/a/mod.ts"),
+      "localhost_4545/a/mod.ts",
+    );
+    mappings.insert(
+      &url("http://localhost:4545/a/util.ts?v=2"),
+      "localhost_4545/a/util_12345678.ts",
+    );
+    // A redirect, to the copy of the module it was redirected to.
+    mappings.insert(
+      &url("http://localhost:4546/a/mod.ts"),
+      "localhost_4545/a/mod.ts",
+    );
+    assert_eq!(
+      mappings.prefixes,
+      vec![(
+        "http://localhost:4545/".to_string(),
+        "./localhost_4545/".to_string()
+      ),]
+      .into_iter()
+      .collect()
+    );
+    let exact: Vec<(&str, &str)> = mappings
+      .exact
+      .iter()
+      .map(|(k, v)| (k.as_str(), v.as_str()))
+      .collect();
+    assert_eq!(
+      exact,
+      vec![
+        (
+          "./localhost_4545/a/util.ts?v=2",
+          "./localhost_4545/a/util_12345678.ts"
+        ),
+        ("./localhost_4546/a/mod.ts", "./localhost_4545/a/mod.ts"),
+        (
+          "http://localhost:4545/a/util.ts?v=2",
+          "./localhost_4545/a/util_12345678.ts"
+        ),
+        (
+          "http://localhost:4546/a/mod.ts",
+          "./localhost_4545/a/mod.ts"
+        ),
+      ]
+    );
+    assert_eq!(
+      mappings.resolve(&url("http://localhost:4545/a/mod.ts")),
+      Some("./localhost_4545/a/mod.ts".to_string())
+    );
+    assert_eq!(
+      mappings.resolve(&url("http://localhost:4545/a/util.ts?v=2")),
+      Some("./localhost_4545/a/util_12345678.ts".to_string())
+    );
+    assert_eq!(mappings.resolve(&url("https://deno.land/x/a.ts")), None);
+  }
+
+  #[test]
+  fn user_import_map() {
+    let mut mappings = Mappings::default();
+    mappings.insert(
+      &url("https://deno.land/std/path/mod.ts"),
+      "deno.land/std/path/mod.ts",
+    );
+    let (base, vendor_dir) = if cfg!(windows) {
+      (
+        url("file:///C:/project/import_map.json"),
+        "C:\\project\\vendor",
+      )
+    } else {
+      (url("file:///project/import_map.json"), "/project/vendor")
+    };
+    let user_import_map = serde_json::json!({
+      "imports": {
+        "std/": "https://deno.land/std/",
+        "path": "https://deno.land/std/path/mod.ts",
+        "other": "https://example.com/other.ts",
+        "local/": "./src/",
+      },
+      "scopes": {
+        "https://deno.land/std/": { "path": "./src/path.ts" },
+      },
+    });
+    let import_map = build_import_map(
+      Some((base, user_import_map)),
+      Path::new(vendor_dir),
+      &mappings,
+    );
+    assert_eq!(
+      import_map,
+      serde_json::json!({
+        "imports": {
+          "std/": "./deno.land/std/",
+          "path": "./deno.land/std/path/mod.ts",
+          "other": "https://example.com/other.ts",
+          "local/": "../src/",
+          "https://deno.land/": "./deno.land/",
+        },
+        "scopes": {
+          "./deno.land/std/": { "path": "../src/path.ts" },
+        },
+      })
+    );
+  }
+}
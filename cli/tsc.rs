@@ -867,7 +867,7 @@ mod tests {
     assert!(actual.diagnostics.is_empty());
     assert!(actual.emitted_files.is_empty());
     assert!(actual.maybe_tsbuildinfo.is_some());
-    assert_eq!(actual.stats.0.len(), 12);
+    assert_eq!(actual.stats.0.len(), 13);
   }
 
   #[tokio::test]
@@ -879,7 +879,7 @@ mod tests {
     assert!(actual.diagnostics.is_empty());
     assert!(actual.emitted_files.is_empty());
     assert!(actual.maybe_tsbuildinfo.is_some());
-    assert_eq!(actual.stats.0.len(), 12);
+    assert_eq!(actual.stats.0.len(), 13);
   }
 
   #[tokio::test]
@@ -405,9 +405,9 @@ delete Object.prototype.__proto__;
   }
 
   /**
-   * @param {{ program: ts.Program | ts.EmitAndSemanticDiagnosticsBuilderProgram, fileCount?: number }} options
+   * @param {{ program: ts.Program | ts.EmitAndSemanticDiagnosticsBuilderProgram, fileCount?: number, checkedFileCount?: number }} options
    */
-  function performanceProgram({ program, fileCount }) {
+  function performanceProgram({ program, fileCount, checkedFileCount }) {
     if (program) {
       if ("getProgram" in program) {
         program = program.getProgram();
@@ -421,6 +421,9 @@ delete Object.prototype.__proto__;
     } else if (fileCount != null) {
       stats.push(["Files", fileCount]);
     }
+    if (checkedFileCount != null) {
+      stats.push(["Checked files", checkedFileCount]);
+    }
     const programTime = ts.performance.getDuration("Program");
     const bindTime = ts.performance.getDuration("Bind");
     const checkTime = ts.performance.getDuration("Check");
@@ -493,6 +496,18 @@ delete Object.prototype.__proto__;
       configFileParsingDiagnostics,
     });
 
+    // Unchanged files that were checked by a previous build, whose state is
+    // loaded from the build info, are not affected and aren't checked again.
+    let checkedFileCount = 0;
+    let affected;
+    while (
+      (affected = program.getSemanticDiagnosticsOfNextAffectedFile())
+    ) {
+      checkedFileCount += "fileName" in affected.affected
+        ? 1
+        : affected.affected.getSourceFiles().length;
+    }
+
     const { diagnostics: emitDiagnostics } = program.emit();
 
     const diagnostics = [
@@ -503,7 +518,7 @@ delete Object.prototype.__proto__;
       ...program.getSemanticDiagnostics(),
       ...emitDiagnostics,
     ].filter(({ code }) => !IGNORED_DIAGNOSTICS.includes(code));
-    performanceProgram({ program });
+    performanceProgram({ program, checkedFileCount });
 
     core.jsonOpSync("op_respond", {
       diagnostics: fromTypeScriptDiagnostic(diagnostics),
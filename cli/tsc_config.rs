@@ -148,7 +148,7 @@ pub fn json_merge(a: &mut Value, b: &Value) {
 }
 
 /// Convert a jsonc libraries `JsonValue` to a serde `Value`.
-fn jsonc_to_serde(j: JsonValue) -> Value {
+pub fn jsonc_to_serde(j: JsonValue) -> Value {
   match j {
     JsonValue::Array(arr) => {
       let vec = arr.into_iter().map(jsonc_to_serde).collect();
@@ -0,0 +1,94 @@
+// Copyright 2018-2021 the Deno authors. All rights reserved. MIT license.
+
+use crate::CancelHandle;
+use crate::Resource;
+use std::borrow::Cow;
+use std::rc::Rc;
+
+/// Creates the `AbortController` and `AbortSignal` returned by
+/// `op_abort_signal_new`; see `JsRuntime::set_abort_signal_factory()`.
+pub type AbortSignalFactory = dyn Fn() -> (AbortController, AbortSignal);
+
+/// Returns an `AbortController` and the `AbortSignal` it aborts, which share
+/// a `CancelHandle`. This is the default `AbortSignalFactory`.
+pub fn new_abort_signal() -> (AbortController, AbortSignal) {
+  let cancel_handle = CancelHandle::new_rc();
+  (
+    AbortController::new(cancel_handle.clone()),
+    AbortSignal::new(cancel_handle),
+  )
+}
+
+/// The Rust side of a JavaScript `AbortController`.
+pub struct AbortController {
+  cancel_handle: Rc<CancelHandle>,
+}
+
+impl AbortController {
+  pub fn new(cancel_handle: Rc<CancelHandle>) -> Self {
+    Self { cancel_handle }
+  }
+
+  /// Aborts the signal, which cancels every future that is waiting on it.
+  pub fn cancel(&self) {
+    self.cancel_handle.cancel()
+  }
+}
+
+impl Resource for AbortController {
+  fn name(&self) -> Cow<str> {
+    "abortController".into()
+  }
+}
+
+/// The Rust side of a JavaScript `AbortSignal`. Ops that take a `signal`
+/// option look it up in the resource table and cancel their futures with
+/// `cancel_handle()`, e.g.
+/// `fut.try_or_cancel(signal.cancel_handle()).await`.
+#[derive(Clone)]
+pub struct AbortSignal {
+  cancel_handle: Rc<CancelHandle>,
+}
+
+impl AbortSignal {
+  pub fn new(cancel_handle: Rc<CancelHandle>) -> Self {
+    Self { cancel_handle }
+  }
+
+  pub fn is_aborted(&self) -> bool {
+    self.cancel_handle.is_canceled()
+  }
+
+  pub fn cancel_handle(&self) -> Rc<CancelHandle> {
+    self.cancel_handle.clone()
+  }
+}
+
+impl Resource for AbortSignal {
+  fn name(&self) -> Cow<str> {
+    "abortSignal".into()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::CancelFuture;
+  use futures::future::pending;
+  use futures::future::ready;
+  use futures::FutureExt;
+
+  #[test]
+  fn abort_signal() {
+    let (controller, signal) = new_abort_signal();
+    assert!(!signal.is_aborted());
+    let fut = pending::<()>().or_cancel(signal.cancel_handle());
+    controller.cancel();
+    assert!(signal.is_aborted());
+    assert!(fut.now_or_never().unwrap().is_err());
+
+    // Futures that start waiting after the signal was aborted are cancelled.
+    let fut = ready(()).or_cancel(signal.cancel_handle());
+    assert!(fut.now_or_never().unwrap().is_err());
+  }
+}
@@ -2,11 +2,13 @@
 
 use crate::error::AnyError;
 use crate::runtime::JsRuntimeState;
+use crate::ErrorEvent;
 use crate::JsRuntime;
 use crate::Op;
 use crate::OpId;
 use crate::OpTable;
 use crate::ZeroCopyBuf;
+use crate::DEFAULT_OP_PRIORITY;
 use futures::future::FutureExt;
 use rusty_v8 as v8;
 use std::cell::Cell;
@@ -55,9 +57,15 @@ lazy_static! {
       v8::ExternalReference {
         function: get_promise_details.map_fn_to()
       },
+      v8::ExternalReference {
+        function: v8_op_sync.map_fn_to()
+      },
       v8::ExternalReference {
         function: get_proxy_details.map_fn_to()
       },
+      v8::ExternalReference {
+        function: call_native_function.map_fn_to()
+      },
     ]);
 }
 
@@ -121,6 +129,7 @@ pub fn initialize_context<'s>(
   set_func(scope, core_val, "print", print);
   set_func(scope, core_val, "recv", recv);
   set_func(scope, core_val, "send", send);
+  set_func(scope, core_val, "v8OpSync", v8_op_sync);
   set_func(
     scope,
     core_val,
@@ -232,6 +241,21 @@ pub extern "C" fn host_initialize_import_meta_object_callback(
   meta.create_data_property(scope, main_key.into(), main_val.into());
 }
 
+/// Calls the hooks registered with `JsRuntime::register_promise_hook()`.
+#[cfg(feature = "debug_hooks")]
+pub extern "C" fn promise_hook(
+  hook_type: v8::PromiseHookType,
+  promise: v8::Local<v8::Promise>,
+  _parent: v8::Local<v8::Value>,
+) {
+  let scope = &mut unsafe { v8::CallbackScope::new(promise) };
+  // The hooks are cloned so they can create promises of their own.
+  let hooks = JsRuntime::state(scope).borrow().promise_hooks.clone();
+  for hook in hooks {
+    hook(hook_type, promise);
+  }
+}
+
 pub extern "C" fn promise_reject_callback(message: v8::PromiseRejectMessage) {
   let scope = &mut unsafe { v8::CallbackScope::new(&message) };
 
@@ -383,12 +407,17 @@ fn send<'s>(
     }
     Op::Sync(_) => {}
     Op::Async(fut) => {
-      let fut2 = fut.map(move |buf| (op_id, buf));
+      let fut2 = fut.map(move |buf| (DEFAULT_OP_PRIORITY, op_id, buf));
+      state.pending_ops.push(fut2.boxed_local());
+      state.have_unpolled_ops.set(true);
+    }
+    Op::AsyncPriority(priority, fut) => {
+      let fut2 = fut.map(move |buf| (priority, op_id, buf));
       state.pending_ops.push(fut2.boxed_local());
       state.have_unpolled_ops.set(true);
     }
     Op::AsyncUnref(fut) => {
-      let fut2 = fut.map(move |buf| (op_id, buf));
+      let fut2 = fut.map(move |buf| (DEFAULT_OP_PRIORITY, op_id, buf));
       state.pending_unref_ops.push(fut2.boxed_local());
       state.have_unpolled_ops.set(true);
     }
@@ -401,6 +430,110 @@ fn send<'s>(
   }
 }
 
+fn v8_op_sync(
+  scope: &mut v8::HandleScope,
+  args: v8::FunctionCallbackArguments,
+  mut rv: v8::ReturnValue,
+) {
+  let name = match v8::Local::<v8::String>::try_from(args.get(0)) {
+    Ok(name) => name.to_rust_string_lossy(scope),
+    Err(err) => return throw_type_error(scope, err.to_string()),
+  };
+
+  let state_rc = JsRuntime::state(scope);
+  let (maybe_op_fn, op_state) = {
+    let state = state_rc.borrow();
+    (state.v8_ops.get(&name).cloned(), state.op_state.clone())
+  };
+  let op_fn = match maybe_op_fn {
+    Some(op_fn) => op_fn,
+    None => return throw_type_error(scope, format!("Unknown op: {}", name)),
+  };
+
+  let arg = v8::Local::new(scope, args.get(1));
+  let result = op_fn(scope, &mut op_state.borrow_mut(), arg);
+  match result {
+    Ok(value) => rv.set(value),
+    Err(exception) => {
+      scope.throw_exception(exception);
+    }
+  }
+}
+
+/// A template for a function that calls the Rust function at `index` in
+/// `JsRuntimeState::native_functions`.
+pub fn native_function_template<'s>(
+  scope: &mut v8::HandleScope<'s>,
+  index: usize,
+) -> v8::Local<'s, v8::FunctionTemplate> {
+  let data = v8::Integer::new(scope, index as i32);
+  v8::FunctionTemplate::builder(call_native_function)
+    .data(data.into())
+    .build(scope)
+}
+
+/// The callback of the functions installed by
+/// `JsRuntime::register_native_function()`. Their data is the index of the
+/// Rust function in `JsRuntimeState::native_functions`.
+pub fn call_native_function(
+  scope: &mut v8::HandleScope,
+  args: v8::FunctionCallbackArguments,
+  mut rv: v8::ReturnValue,
+) {
+  let index = args
+    .data()
+    .and_then(|data| v8::Local::<v8::Integer>::try_from(data).ok())
+    .expect("native function without an index")
+    .value() as usize;
+  let state_rc = JsRuntime::state(scope);
+  let (native_fn, op_state) = {
+    let state = state_rc.borrow();
+    (
+      state.native_functions[index].clone(),
+      state.op_state.clone(),
+    )
+  };
+
+  let arg = v8::Local::new(scope, args.get(0));
+  let arg = if arg.is_undefined() {
+    serde_json::Value::Null
+  } else {
+    // A `None` means that `JSON.stringify()` threw, and the exception is
+    // rethrown to the caller.
+    let json = match v8::json::stringify(scope, arg) {
+      Some(json) => json.to_rust_string_lossy(scope),
+      None => return,
+    };
+    match serde_json::from_str(&json) {
+      Ok(arg) => arg,
+      Err(_) => {
+        return throw_type_error(scope, "The argument is not serializable")
+      }
+    }
+  };
+
+  match native_fn(arg) {
+    Ok(value) => {
+      let json = v8::String::new(scope, &value.to_string()).unwrap();
+      if let Some(value) = v8::json::parse(scope, json) {
+        rv.set(value);
+      }
+    }
+    Err(err) => {
+      let class = (op_state.borrow().get_error_class_fn)(&err);
+      let message = v8::String::new(scope, &err.to_string()).unwrap();
+      let exception = match class {
+        "TypeError" => v8::Exception::type_error(scope, message),
+        "RangeError" => v8::Exception::range_error(scope, message),
+        "SyntaxError" => v8::Exception::syntax_error(scope, message),
+        "ReferenceError" => v8::Exception::reference_error(scope, message),
+        _ => v8::Exception::error(scope, message),
+      };
+      scope.throw_exception(exception);
+    }
+  }
+}
+
 fn set_macrotask_callback(
   scope: &mut v8::HandleScope,
   args: v8::FunctionCallbackArguments,
@@ -726,6 +859,41 @@ fn queue_microtask(
   };
 }
 
+pub fn onerror(
+  scope: &mut v8::HandleScope,
+  args: v8::FunctionCallbackArguments,
+  mut rv: v8::ReturnValue,
+) {
+  let state_rc = JsRuntime::state(scope);
+  let maybe_callback = state_rc.borrow().error_event_callback.clone();
+  let callback = match maybe_callback {
+    Some(callback) => callback,
+    None => {
+      rv.set(v8::Boolean::new(scope, false).into());
+      return;
+    }
+  };
+
+  let message = args.get(0).to_rust_string_lossy(scope);
+  let filename = args.get(1);
+  let filename = if filename.is_undefined() {
+    String::new()
+  } else {
+    filename.to_rust_string_lossy(scope)
+  };
+  let lineno = args.get(2).uint32_value(scope).unwrap_or(0);
+  let colno = args.get(3).uint32_value(scope).unwrap_or(0);
+  let error = v8::Global::new(scope, args.get(4));
+  let handled = callback(ErrorEvent {
+    message,
+    filename,
+    lineno,
+    colno,
+    error,
+  });
+  rv.set(v8::Boolean::new(scope, handled).into())
+}
+
 fn shared_getter(
   scope: &mut v8::HandleScope,
   _name: v8::Local<v8::Name>,
@@ -784,8 +952,11 @@ pub fn module_resolve_callback<'s>(
     )
     .expect("Module should have been already resolved");
 
+  let referrer_id = referrer_info.id;
   if let Some(id) = state.modules.get_id(resolved_specifier.as_str()) {
     if let Some(handle) = state.modules.get_handle(id) {
+      drop(state);
+      state_rc.borrow_mut().modules.add_importer(id, referrer_id);
       return Some(v8::Local::new(scope, handle));
     }
   }
@@ -0,0 +1,57 @@
+// Copyright 2018-2021 the Deno authors. All rights reserved. MIT license.
+"use strict";
+
+((window) => {
+  const core = window.Deno.core;
+
+  function inspect(value) {
+    if (typeof value === "string") {
+      return value;
+    }
+    if (value instanceof Error) {
+      return value.stack ?? String(value);
+    }
+    if (typeof value === "object" && value !== null) {
+      try {
+        return JSON.stringify(value);
+      } catch {
+        // Circular or otherwise not serializable.
+      }
+    }
+    return String(value);
+  }
+
+  function send(level, args) {
+    core.jsonOpSync("op_console", {
+      level,
+      message: args.map(inspect).join(" "),
+    });
+  }
+
+  const console = {
+    log: (...args) => send("log", args),
+    info: (...args) => send("log", args),
+    warn: (...args) => send("warn", args),
+    error: (...args) => send("error", args),
+    debug: (...args) => send("debug", args),
+    trace: (...args) => {
+      const err = new Error(args.map(inspect).join(" "));
+      err.name = "Trace";
+      send("debug", [err.stack]);
+    },
+    assert: (condition = false, ...args) => {
+      if (!condition) {
+        send(
+          "error",
+          args.length ? ["Assertion failed:", ...args] : ["Assertion failed"],
+        );
+      }
+    },
+  };
+
+  Object.defineProperty(window, "console", {
+    value: console,
+    writable: true,
+    configurable: true,
+  });
+})(this);
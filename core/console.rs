@@ -0,0 +1,60 @@
+// Copyright 2018-2021 the Deno authors. All rights reserved. MIT license.
+
+//! Support for `JsRuntime::set_console_handler()`, which replaces the global
+//! `console` with one that forwards its output to a Rust callback.
+
+use crate::error::AnyError;
+use crate::json_op_sync;
+use crate::JsRuntime;
+use crate::OpState;
+use crate::ZeroCopyBuf;
+use serde::Deserialize;
+use serde_json::json;
+use serde_json::Value;
+
+/// The severity of a message logged through the JS `console`.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ConsoleLevel {
+  /// `console.log()` and `console.info()`.
+  Log,
+  /// `console.warn()`.
+  Warn,
+  /// `console.error()` and failed `console.assert()` calls.
+  Error,
+  /// `console.debug()` and `console.trace()`.
+  Debug,
+}
+
+pub type ConsoleHandler = dyn Fn(ConsoleLevel, String);
+
+struct ConsoleHandlerState(Box<ConsoleHandler>);
+
+pub(crate) fn set_handler(rt: &mut JsRuntime, handler: Box<ConsoleHandler>) {
+  let op_state = rt.op_state();
+  let installed = op_state.borrow().has::<ConsoleHandlerState>();
+  op_state.borrow_mut().put(ConsoleHandlerState(handler));
+  if installed {
+    return;
+  }
+  rt.add_op_at_runtime("op_console", json_op_sync(op_console))
+    .unwrap();
+  rt.execute("deno:core/console.js", include_str!("console.js"))
+    .unwrap();
+}
+
+#[derive(Deserialize)]
+struct ConsoleArgs {
+  level: ConsoleLevel,
+  message: String,
+}
+
+fn op_console(
+  state: &mut OpState,
+  args: Value,
+  _zero_copy: &mut [ZeroCopyBuf],
+) -> Result<Value, AnyError> {
+  let args: ConsoleArgs = serde_json::from_value(args)?;
+  (state.borrow::<ConsoleHandlerState>().0)(args.level, args.message);
+  Ok(json!({}))
+}
@@ -171,8 +171,16 @@ SharedQueue Binary Layout
     }
   }
 
+  function opId(opName) {
+    const id = opsCache[opName];
+    if (id === undefined) {
+      throw new TypeError(`${opName} is not a function`);
+    }
+    return id;
+  }
+
   function dispatch(opName, control, ...zeroCopy) {
-    return send(opsCache[opName], control, ...zeroCopy);
+    return send(opId(opName), control, ...zeroCopy);
   }
 
   function registerErrorClass(errorName, className) {
@@ -214,7 +222,7 @@ SharedQueue Binary Layout
   }
 
   async function jsonOpAsync(opName, args = null, ...zeroCopy) {
-    setAsyncHandler(opsCache[opName], jsonOpAsyncHandler);
+    setAsyncHandler(opId(opName), jsonOpAsyncHandler);
 
     const promiseId = nextPromiseId++;
     const reqBuf = core.encode("\0".repeat(8) + JSON.stringify(args));
@@ -12,6 +12,7 @@ use std::fmt;
 use std::fmt::Debug;
 use std::fmt::Display;
 use std::fmt::Formatter;
+use std::time::Duration;
 
 /// A generic wrapper that can encapsulate any concrete error type.
 pub type AnyError = anyhow::Error;
@@ -424,6 +425,82 @@ impl Debug for ErrWithV8Handle {
   }
 }
 
+/// Returned by `JsRuntime::run_event_loop_until_idle()` when ops and
+/// microtasks keep scheduling each other: draining the microtask queue
+/// dispatched new work `cycles` times.
+#[derive(Debug)]
+pub struct InfiniteLoop {
+  pub cycles: usize,
+}
+
+impl Error for InfiniteLoop {}
+
+impl Display for InfiniteLoop {
+  fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+    write!(
+      f,
+      "Event loop did not become idle after {} microtask drains; ops and microtasks keep scheduling each other",
+      self.cycles
+    )
+  }
+}
+
+/// Returned by `JsRuntime::mod_evaluate()` when the synchronous part of a
+/// module's evaluation runs for longer than the timeout set with
+/// `JsRuntime::set_module_evaluation_timeout()`.
+#[derive(Debug)]
+pub struct ModuleEvaluationTimeout {
+  pub specifier: String,
+  pub timeout: Duration,
+}
+
+impl Error for ModuleEvaluationTimeout {}
+
+impl Display for ModuleEvaluationTimeout {
+  fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+    write!(
+      f,
+      "Evaluation of module \"{}\" timed out after {}ms",
+      self.specifier,
+      self.timeout.as_millis()
+    )
+  }
+}
+
+/// Returned by `JsRuntime::snapshot_includes_ops_check()` when the ops that
+/// were registered when the startup snapshot was taken don't have the same
+/// ids in the runtime. Both lists hold op names, indexed by op id.
+#[derive(Debug)]
+pub struct SnapshotOpMismatch {
+  pub expected: Vec<String>,
+  pub actual: Vec<String>,
+}
+
+impl Error for SnapshotOpMismatch {}
+
+impl Display for SnapshotOpMismatch {
+  fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+    write!(
+      f,
+      "The ops registered in the runtime don't match the ops of the startup snapshot:"
+    )?;
+    for (id, expected) in self.expected.iter().enumerate() {
+      match self.actual.get(id) {
+        Some(actual) if actual == expected => {}
+        Some(actual) => write!(
+          f,
+          "\n  op {}: expected \"{}\", found \"{}\"",
+          id, expected, actual
+        )?,
+        None => {
+          write!(f, "\n  op {}: expected \"{}\", found none", id, expected)?
+        }
+      }
+    }
+    Ok(())
+  }
+}
+
 #[cfg(test)]
 mod tests {
   use super::*;
@@ -439,4 +516,24 @@ mod tests {
     let err = bad_resource_id();
     assert_eq!(err.to_string(), "Bad resource ID");
   }
+
+  #[test]
+  fn test_snapshot_op_mismatch() {
+    let names = |names: &[&str]| {
+      names
+        .iter()
+        .map(|name| name.to_string())
+        .collect::<Vec<_>>()
+    };
+    let err = SnapshotOpMismatch {
+      expected: names(&["ops", "op_a", "op_b", "op_c"]),
+      actual: names(&["ops", "op_a", "op_c"]),
+    };
+    assert_eq!(
+      err.to_string(),
+      "The ops registered in the runtime don't match the ops of the startup snapshot:
+  op 2: expected \"op_b\", found \"op_c\"
+  op 3: expected \"op_c\", found none"
+    );
+  }
 }
@@ -6,10 +6,23 @@
 use std::any::Any;
 use std::any::TypeId;
 use std::collections::HashMap;
+use std::collections::HashSet;
+
+type CloneFn = fn(&dyn Any) -> Box<dyn Any>;
 
 #[derive(Default)]
 pub struct GothamState {
   data: HashMap<TypeId, Box<dyn Any>>,
+  /// Clone functions for the values stored with `put_cloneable()`.
+  cloners: HashMap<TypeId, CloneFn>,
+}
+
+/// A copy of the cloneable values in a `GothamState`, taken by
+/// `GothamState::snapshot()`.
+pub(crate) struct Snapshot {
+  type_ids: HashSet<TypeId>,
+  data: HashMap<TypeId, Box<dyn Any>>,
+  cloners: HashMap<TypeId, CloneFn>,
 }
 
 impl GothamState {
@@ -20,6 +33,17 @@ impl GothamState {
     let type_id = TypeId::of::<T>();
     trace!(" inserting record to state for type_id `{:?}`", type_id);
     self.data.insert(type_id, Box::new(t));
+    self.cloners.remove(&type_id);
+  }
+
+  /// Like `put()`, but the value is also restored when an
+  /// `OpState::transaction()` fails.
+  pub fn put_cloneable<T: Clone + 'static>(&mut self, t: T) {
+    let type_id = TypeId::of::<T>();
+    self.put(t);
+    self.cloners.insert(type_id, |value| {
+      Box::new(value.downcast_ref::<T>().unwrap().clone())
+    });
   }
 
   /// Determines if the current value exists in `GothamState` storage.
@@ -63,6 +87,7 @@ impl GothamState {
       " taking ownership from state data for type_id `{:?}`",
       type_id
     );
+    self.cloners.remove(&type_id);
     self
       .data
       .remove(&type_id)
@@ -80,6 +105,31 @@ impl GothamState {
       .try_take()
       .expect("required type is not present in GothamState container")
   }
+
+  /// Clones the values stored with `put_cloneable()` and records which types
+  /// are present.
+  pub(crate) fn snapshot(&self) -> Snapshot {
+    let data = self
+      .cloners
+      .iter()
+      .map(|(type_id, clone)| (*type_id, clone(self.data[type_id].as_ref())))
+      .collect();
+    Snapshot {
+      type_ids: self.data.keys().copied().collect(),
+      data,
+      cloners: self.cloners.clone(),
+    }
+  }
+
+  /// Puts back the values of a snapshot and removes the values of types that
+  /// were not present when it was taken. Values that are not cloneable keep
+  /// their current state.
+  pub(crate) fn restore(&mut self, snapshot: Snapshot) {
+    let type_ids = snapshot.type_ids;
+    self.data.retain(|type_id, _| type_ids.contains(type_id));
+    self.data.extend(snapshot.data);
+    self.cloners = snapshot.cloners;
+  }
 }
 
 #[cfg(test)]
@@ -169,6 +219,41 @@ mod tests {
     assert!(state.try_borrow::<MyStruct>().is_none());
   }
 
+  #[derive(Clone)]
+  struct Counter(u32);
+
+  #[test]
+  fn snapshot_restore() {
+    let mut state = GothamState::default();
+    state.put_cloneable(Counter(1));
+    state.put(MyStruct { value: 1 });
+    let snapshot = state.snapshot();
+
+    state.borrow_mut::<Counter>().0 = 2;
+    state.borrow_mut::<MyStruct>().value = 2;
+    state.put(AnotherStruct { value: "new" });
+    state.restore(snapshot);
+
+    assert_eq!(state.borrow::<Counter>().0, 1);
+    assert_eq!(state.borrow::<MyStruct>().value, 2);
+    assert!(!state.has::<AnotherStruct>());
+
+    let snapshot = state.snapshot();
+    state.take::<Counter>();
+    state.restore(snapshot);
+    assert_eq!(state.borrow::<Counter>().0, 1);
+    state.take::<Counter>();
+    state.snapshot();
+    state.put_cloneable(Counter(1));
+
+    // Overwriting with `put()` makes the value non-cloneable.
+    state.put(Counter(3));
+    let snapshot = state.snapshot();
+    state.borrow_mut::<Counter>().0 = 4;
+    state.restore(snapshot);
+    assert_eq!(state.borrow::<Counter>().0, 4);
+  }
+
   #[test]
   fn type_alias() {
     let mut state = GothamState::default();
@@ -0,0 +1,204 @@
+// Copyright 2018-2021 the Deno authors. All rights reserved. MIT license.
+
+//! Typed handles to JS objects. The `js_object!` macro declares a Rust type
+//! for objects with a known set of methods, and `JsRuntime::eval_and_bind()`
+//! evaluates a script and wraps the object it evaluates to, e.g.:
+//!
+//! ```ignore
+//! js_object! {
+//!   pub struct Counter {
+//!     fn increment(by: u32) -> u32;
+//!   }
+//! }
+//!
+//! let counter = runtime.eval_and_bind::<Counter>(
+//!   "({ count: 0, increment(by) { return this.count += by; } })",
+//! )?;
+//! assert_eq!(counter.increment(&mut runtime, 2)?, 2);
+//! ```
+
+use crate::bindings;
+use crate::error::type_error;
+use crate::error::AnyError;
+use crate::runtime::exception_to_err_result;
+use crate::JsRuntime;
+use rusty_v8 as v8;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::Value;
+use std::convert::TryFrom;
+use std::ops::Deref;
+
+/// A Rust type for JS objects, usually declared with `js_object!`.
+pub trait JsObject: Sized {
+  fn from_global(object: v8::Global<v8::Object>) -> Self;
+
+  fn global(&self) -> &v8::Global<v8::Object>;
+}
+
+/// A handle to a JS object returned by `JsRuntime::eval_and_bind()`, which
+/// dereferences to `T` to call the methods it declares. The object is kept
+/// alive as long as the handle.
+pub struct TypedJsHandle<T: JsObject> {
+  inner: T,
+}
+
+impl<T: JsObject> TypedJsHandle<T> {
+  pub fn global(&self) -> &v8::Global<v8::Object> {
+    self.inner.global()
+  }
+
+  pub fn into_inner(self) -> T {
+    self.inner
+  }
+}
+
+impl<T: JsObject> Deref for TypedJsHandle<T> {
+  type Target = T;
+
+  fn deref(&self) -> &T {
+    &self.inner
+  }
+}
+
+/// Declares a struct that implements `JsObject`, with a Rust method for each
+/// of the listed JS methods. The methods take the runtime in addition to
+/// their arguments, which are converted to JS through JSON, as is their
+/// result.
+#[macro_export]
+macro_rules! js_object {
+  (
+    $(#[$attr:meta])*
+    $vis:vis struct $name:ident {
+      $(
+        $(#[$method_attr:meta])*
+        fn $method:ident($($arg:ident: $arg_ty:ty),* $(,)?) -> $ret:ty;
+      )*
+    }
+  ) => {
+    $(#[$attr])*
+    $vis struct $name {
+      object: $crate::v8::Global<$crate::v8::Object>,
+    }
+
+    impl $crate::JsObject for $name {
+      fn from_global(object: $crate::v8::Global<$crate::v8::Object>) -> Self {
+        Self { object }
+      }
+
+      fn global(&self) -> &$crate::v8::Global<$crate::v8::Object> {
+        &self.object
+      }
+    }
+
+    impl $name {
+      $(
+        $(#[$method_attr])*
+        $vis fn $method(
+          &self,
+          runtime: &mut $crate::JsRuntime,
+          $($arg: $arg_ty),*
+        ) -> ::std::result::Result<$ret, $crate::error::AnyError> {
+          runtime.call_method(&self.object, stringify!($method), ($($arg,)*))
+        }
+      )*
+    }
+  };
+}
+
+pub(crate) fn eval_and_bind<T: JsObject>(
+  runtime: &mut JsRuntime,
+  code: &str,
+) -> Result<TypedJsHandle<T>, AnyError> {
+  runtime.with_scope(|scope| {
+    let source = v8::String::new(scope, code).unwrap();
+    let name = v8::String::new(scope, "eval_and_bind").unwrap();
+    let origin = bindings::script_origin(scope, name);
+
+    let tc_scope = &mut v8::TryCatch::new(scope);
+
+    let value = match v8::Script::compile(tc_scope, source, Some(&origin))
+      .and_then(|script| script.run(tc_scope))
+    {
+      Some(value) => value,
+      None => {
+        let exception = tc_scope.exception().unwrap();
+        return exception_to_err_result(tc_scope, exception, false);
+      }
+    };
+    let object = v8::Local::<v8::Object>::try_from(value)
+      .map_err(|_| type_error("The script did not evaluate to an object"))?;
+    Ok(TypedJsHandle {
+      inner: T::from_global(v8::Global::new(tc_scope, object)),
+    })
+  })
+}
+
+pub(crate) fn call_method<A, R>(
+  runtime: &mut JsRuntime,
+  object: &v8::Global<v8::Object>,
+  name: &str,
+  args: A,
+) -> Result<R, AnyError>
+where
+  A: Serialize,
+  R: DeserializeOwned,
+{
+  // A tuple of arguments is serialized as an array, and `()` as null.
+  let args = match serde_json::to_value(args)? {
+    Value::Array(args) => args,
+    Value::Null => vec![],
+    arg => vec![arg],
+  };
+
+  let result = runtime.with_scope(|scope| {
+    let object = v8::Local::new(scope, object);
+    let key = v8::String::new(scope, name).unwrap();
+    let tc_scope = &mut v8::TryCatch::new(scope);
+
+    let method = object.get(tc_scope, key.into());
+    let method = match method {
+      Some(method) => method,
+      None => {
+        let exception = tc_scope.exception().unwrap();
+        return exception_to_err_result(tc_scope, exception, false);
+      }
+    };
+    let method = v8::Local::<v8::Function>::try_from(method)
+      .map_err(|_| type_error(format!("{} is not a function", name)))?;
+
+    let args = args
+      .iter()
+      .map(|arg| {
+        let json = v8::String::new(tc_scope, &arg.to_string()).unwrap();
+        v8::json::parse(tc_scope, json).unwrap()
+      })
+      .collect::<Vec<_>>();
+    let value = match method.call(tc_scope, object.into(), &args) {
+      Some(value) => value,
+      None => {
+        let exception = tc_scope.exception().unwrap();
+        return exception_to_err_result(tc_scope, exception, false);
+      }
+    };
+
+    // `JSON.stringify()` doesn't return a string for these.
+    if value.is_undefined() || value.is_function() || value.is_symbol() {
+      return Ok(Value::Null);
+    }
+    match v8::json::stringify(tc_scope, value) {
+      Some(json) => Ok(
+        serde_json::from_str(&json.to_rust_string_lossy(tc_scope))
+          .unwrap_or(Value::Null),
+      ),
+      None => {
+        let exception = tc_scope.exception().unwrap();
+        exception_to_err_result(tc_scope, exception, false)
+      }
+    }
+  })?;
+
+  serde_json::from_value(result).map_err(|err| {
+    type_error(format!("Invalid result of method {}: {}", name, err))
+  })
+}
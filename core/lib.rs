@@ -5,20 +5,30 @@ extern crate lazy_static;
 #[macro_use]
 extern crate log;
 
+mod abort_signal;
 mod async_cancel;
 mod async_cell;
 mod bindings;
+mod console;
 pub mod error;
 mod flags;
 mod gotham_state;
+mod import_map;
+mod js_object;
 mod module_specifier;
 mod modules;
 mod normalize_path;
+mod object_template;
 mod ops;
+mod performance;
 pub mod plugin_api;
+#[cfg(feature = "profiling")]
+mod profiler;
 mod resources;
 mod runtime;
 mod shared_queue;
+mod wasm;
+mod worker;
 mod zero_copy_buf;
 
 // Re-exports
@@ -28,6 +38,10 @@ pub use serde;
 pub use serde_json;
 pub use url;
 
+pub use crate::abort_signal::new_abort_signal;
+pub use crate::abort_signal::AbortController;
+pub use crate::abort_signal::AbortSignal;
+pub use crate::abort_signal::AbortSignalFactory;
 pub use crate::async_cancel::CancelFuture;
 pub use crate::async_cancel::CancelHandle;
 pub use crate::async_cancel::CancelTryFuture;
@@ -41,7 +55,13 @@ pub use crate::async_cell::AsyncRefCell;
 pub use crate::async_cell::AsyncRefFuture;
 pub use crate::async_cell::RcLike;
 pub use crate::async_cell::RcRef;
+pub use crate::console::ConsoleHandler;
+pub use crate::console::ConsoleLevel;
 pub use crate::flags::v8_set_flags;
+pub use crate::import_map::ImportMap;
+pub use crate::import_map::ImportMapError;
+pub use crate::js_object::JsObject;
+pub use crate::js_object::TypedJsHandle;
 pub use crate::module_specifier::resolve_import;
 pub use crate::module_specifier::resolve_path;
 pub use crate::module_specifier::resolve_url;
@@ -55,27 +75,61 @@ pub use crate::modules::ModuleLoadId;
 pub use crate::modules::ModuleLoader;
 pub use crate::modules::ModuleSource;
 pub use crate::modules::ModuleSourceFuture;
+pub use crate::modules::ModuleStatus;
 pub use crate::modules::NoopModuleLoader;
 pub use crate::modules::RecursiveModuleLoad;
 pub use crate::normalize_path::normalize_path;
+pub use crate::object_template::ObjectTemplateBuilder;
 pub use crate::ops::json_op_async;
+pub use crate::ops::json_op_async_buffered;
 pub use crate::ops::json_op_sync;
+pub use crate::ops::op_abort;
+pub use crate::ops::op_abort_signal_new;
+pub use crate::ops::op_async_priority;
+pub use crate::ops::op_batch;
 pub use crate::ops::op_close;
 pub use crate::ops::op_resources;
+pub use crate::ops::op_sync_result_v8;
+pub use crate::ops::BatchOpHandler;
 pub use crate::ops::Op;
 pub use crate::ops::OpAsyncFuture;
 pub use crate::ops::OpFn;
 pub use crate::ops::OpId;
+pub use crate::ops::OpPair;
 pub use crate::ops::OpState;
 pub use crate::ops::OpTable;
+pub use crate::ops::V8OpFn;
+pub use crate::ops::DEFAULT_OP_PRIORITY;
+pub use crate::performance::PerformanceEntry;
+#[cfg(feature = "profiling")]
+pub use crate::profiler::CallFrame;
+#[cfg(feature = "profiling")]
+pub use crate::profiler::CpuProfile;
+#[cfg(feature = "profiling")]
+pub use crate::profiler::CpuProfileNode;
+#[cfg(feature = "profiling")]
+pub use crate::profiler::PositionTick;
 pub use crate::resources::Resource;
 pub use crate::resources::ResourceId;
 pub use crate::resources::ResourceTable;
+pub use crate::runtime::set_v8_stack_size;
+pub use crate::runtime::CancellationToken;
+pub use crate::runtime::CompiledScript;
+pub use crate::runtime::ErrorEvent;
+pub use crate::runtime::ErrorEventCallback;
 pub use crate::runtime::GetErrorClassFn;
+pub use crate::runtime::GlobalErrorHandler;
 pub use crate::runtime::JsErrorCreateFn;
 pub use crate::runtime::JsRuntime;
+#[cfg(feature = "debug_hooks")]
+pub use crate::runtime::PromiseHookFn;
 pub use crate::runtime::RuntimeOptions;
 pub use crate::runtime::Snapshot;
+pub use crate::wasm::WasmInstantiateCb;
+pub use crate::worker::WorkerHandle;
+pub use crate::worker::WorkerInitFn;
+pub use crate::worker::WorkerMessageHandler;
+pub use crate::worker::WorkerOptions;
 pub use crate::zero_copy_buf::BufVec;
 pub use crate::zero_copy_buf::ZeroCopyBuf;
 
@@ -83,6 +137,11 @@ pub fn v8_version() -> &'static str {
   v8::V8::get_version()
 }
 
+/// The version of this `deno_core` crate, as declared in its `Cargo.toml`.
+pub fn js_runtime_version() -> &'static str {
+  env!("CARGO_PKG_VERSION")
+}
+
 #[cfg(test)]
 mod tests {
   use super::*;
@@ -91,4 +150,10 @@ mod tests {
   fn test_v8_version() {
     assert!(v8_version().len() > 3);
   }
+
+  #[test]
+  fn test_js_runtime_version() {
+    assert_eq!(js_runtime_version(), env!("CARGO_PKG_VERSION"));
+    assert_eq!(JsRuntime::js_runtime_version(), js_runtime_version());
+  }
 }
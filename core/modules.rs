@@ -28,6 +28,30 @@ lazy_static! {
 pub type ModuleId = i32;
 pub type ModuleLoadId = i32;
 
+/// The status of a module in the module map, see `JsRuntime::module_status()`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ModuleStatus {
+  Uninstantiated,
+  Instantiating,
+  Instantiated,
+  Evaluating,
+  Evaluated,
+  Errored,
+}
+
+impl From<v8::ModuleStatus> for ModuleStatus {
+  fn from(status: v8::ModuleStatus) -> Self {
+    match status {
+      v8::ModuleStatus::Uninstantiated => Self::Uninstantiated,
+      v8::ModuleStatus::Instantiating => Self::Instantiating,
+      v8::ModuleStatus::Instantiated => Self::Instantiated,
+      v8::ModuleStatus::Evaluating => Self::Evaluating,
+      v8::ModuleStatus::Evaluated => Self::Evaluated,
+      v8::ModuleStatus::Errored => Self::Errored,
+    }
+  }
+}
+
 /// EsModule source code that will be loaded into V8.
 ///
 /// Users can implement `Into<ModuleInfo>` for different file types that
@@ -437,6 +461,24 @@ impl ModuleNameMap {
     self.inner.insert(name, SymbolicModule::Alias(target));
   }
 
+  /// Forget a name, so that the module it pointed to has to be loaded again.
+  #[cfg(debug_assertions)]
+  pub fn remove(&mut self, name: &str) {
+    self.inner.remove(name);
+  }
+
+  /// Forget every name that points directly at one of `ids`. Aliases to
+  /// those names are kept, but no longer resolve until the names are
+  /// registered again.
+  pub fn remove_ids(&mut self, ids: &HashSet<ModuleId>) {
+    self
+      .inner
+      .retain(|_, symbolic_module| match symbolic_module {
+        SymbolicModule::Mod(id) => !ids.contains(id),
+        SymbolicModule::Alias(_) => true,
+      });
+  }
+
   /// Check if a name is an alias to another module.
   #[cfg(test)]
   pub fn is_alias(&self, name: &str) -> bool {
@@ -452,6 +494,9 @@ pub struct Modules {
   handles_by_id: HashMap<ModuleId, v8::Global<v8::Module>>,
   info: HashMap<ModuleId, ModuleInfo>,
   by_name: ModuleNameMap,
+  /// The modules each module has been linked into, recorded as imports are
+  /// resolved during instantiation.
+  importers: HashMap<ModuleId, Vec<ModuleId>>,
   next_module_id: ModuleId,
 }
 
@@ -462,6 +507,7 @@ impl Modules {
       ids_by_handle: HashMap::new(),
       info: HashMap::new(),
       by_name: ModuleNameMap::new(),
+      importers: HashMap::new(),
       next_module_id: 1,
     }
   }
@@ -507,11 +553,95 @@ impl Modules {
     self.by_name.alias(name.to_owned(), target.to_owned());
   }
 
+  /// Record that `importer` has been linked against `id`.
+  pub fn add_importer(&mut self, id: ModuleId, importer: ModuleId) {
+    let importers = self.importers.entry(id).or_default();
+    if !importers.contains(&importer) {
+      importers.push(importer);
+    }
+  }
+
+  /// Forget the module registered as `name` together with every module that
+  /// was linked against it, directly or through other modules, so that they
+  /// are compiled and evaluated again the next time they are loaded. Their V8
+  /// modules stay alive for as long as something references them. Returns
+  /// the ids of the forgotten modules, which are empty if `name` isn't
+  /// registered.
+  pub fn invalidate(&mut self, name: &str) -> HashSet<ModuleId> {
+    let mut invalidated = HashSet::new();
+    let mut pending: Vec<ModuleId> =
+      self.by_name.get(name).into_iter().collect();
+    while let Some(id) = pending.pop() {
+      if invalidated.insert(id) {
+        if let Some(importers) = self.importers.remove(&id) {
+          pending.extend(importers);
+        }
+      }
+    }
+    self.by_name.remove_ids(&invalidated);
+    invalidated
+  }
+
+  /// Point `name` at an already registered module.
+  #[cfg(debug_assertions)]
+  pub fn set_id(&mut self, name: &str, id: ModuleId) {
+    self.by_name.insert(name.to_owned(), id);
+  }
+
+  #[cfg(debug_assertions)]
+  pub fn remove_name(&mut self, name: &str) {
+    self.by_name.remove(name);
+  }
+
   #[cfg(test)]
   pub fn is_alias(&self, name: &str) -> bool {
     self.by_name.is_alias(name)
   }
 
+  /// Walks the imports of the module `id` and of the modules it imports,
+  /// transitively. Returns the names of the imports of each module, keyed by
+  /// the name of the module, and the names of the modules in an order where
+  /// each module comes after the modules it imports, except in import cycles,
+  /// which is the order they are evaluated in.
+  pub fn get_import_graph(
+    &self,
+    id: ModuleId,
+  ) -> (HashMap<String, Vec<String>>, Vec<String>) {
+    let mut imports = HashMap::new();
+    let mut order = vec![];
+    let mut visited = HashSet::new();
+    self.visit_imports(id, &mut visited, &mut imports, &mut order);
+    (imports, order)
+  }
+
+  fn visit_imports(
+    &self,
+    id: ModuleId,
+    visited: &mut HashSet<ModuleId>,
+    imports: &mut HashMap<String, Vec<String>>,
+    order: &mut Vec<String>,
+  ) {
+    if !visited.insert(id) {
+      return;
+    }
+    let info = match self.info.get(&id) {
+      Some(info) => info,
+      None => return,
+    };
+    let mut names = vec![];
+    for specifier in &info.import_specifiers {
+      match self.get_id(specifier.as_str()) {
+        Some(child_id) => {
+          names.push(self.info[&child_id].name.clone());
+          self.visit_imports(child_id, visited, imports, order);
+        }
+        None => names.push(specifier.to_string()),
+      }
+    }
+    imports.insert(info.name.clone(), names);
+    order.push(info.name.clone());
+  }
+
   pub fn get_handle(&self, id: ModuleId) -> Option<v8::Global<v8::Module>> {
     self.handles_by_id.get(&id).cloned()
   }
@@ -755,6 +885,133 @@ mod tests {
     assert_eq!(modules.get_children(d_id), Some(&vec![]));
   }
 
+  #[test]
+  fn test_serialize_module_graph() {
+    let loader = MockLoader::new();
+    let mut runtime = JsRuntime::new(RuntimeOptions {
+      module_loader: Some(loader),
+      ..Default::default()
+    });
+    let spec = crate::resolve_url("file:///a.js").unwrap();
+    futures::executor::block_on(runtime.load_module(&spec, None))
+      .expect("Failed to load");
+
+    let graph = runtime.serialize_module_graph("file:///a.js").unwrap();
+    assert_eq!(
+      graph,
+      serde_json::json!({
+        "modules": {
+          "file:///a.js": ["file:///b.js", "file:///c.js"],
+          "file:///b.js": ["file:///c.js"],
+          "file:///c.js": ["file:///d.js"],
+          "file:///d.js": [],
+        },
+        "topological_order": [
+          "file:///d.js",
+          "file:///c.js",
+          "file:///b.js",
+          "file:///a.js",
+        ],
+      })
+    );
+
+    let spec = crate::resolve_url("file:///circular1.js").unwrap();
+    futures::executor::block_on(runtime.load_module(&spec, None))
+      .expect("Failed to load");
+    let graph = runtime
+      .serialize_module_graph("file:///circular1.js")
+      .unwrap();
+    assert_eq!(
+      graph["topological_order"],
+      serde_json::json!([
+        "file:///circular3.js",
+        "file:///circular2.js",
+        "file:///circular1.js",
+      ])
+    );
+
+    let err = runtime.serialize_module_graph("file:///e.js").unwrap_err();
+    assert_eq!(
+      err.to_string(),
+      "Module \"file:///e.js\" has not been loaded"
+    );
+  }
+
+  #[test]
+  fn test_module_status() {
+    let loader = MockLoader::new();
+    let mut runtime = JsRuntime::new(RuntimeOptions {
+      module_loader: Some(loader),
+      ..Default::default()
+    });
+    assert!(!runtime.has_module("file:///a.js"));
+    assert_eq!(runtime.module_status("file:///a.js"), None);
+
+    let spec = crate::resolve_url("file:///a.js").unwrap();
+    let a_id = futures::executor::block_on(runtime.load_module(&spec, None))
+      .expect("Failed to load");
+    assert!(runtime.has_module("file:///a.js"));
+    assert!(runtime.has_module("file:///d.js"));
+    assert!(!runtime.has_module("file:///e.js"));
+    assert_eq!(
+      runtime.module_status("file:///a.js"),
+      Some(ModuleStatus::Instantiated)
+    );
+
+    futures::executor::block_on(runtime.mod_evaluate(a_id)).unwrap();
+    assert_eq!(
+      runtime.module_status("file:///a.js"),
+      Some(ModuleStatus::Evaluated)
+    );
+    assert_eq!(
+      runtime.module_status("file:///d.js"),
+      Some(ModuleStatus::Evaluated)
+    );
+    assert_eq!(runtime.module_status("file:///e.js"), None);
+  }
+
+  #[test]
+  fn test_invalidate_module() {
+    let loader = MockLoader::new();
+    let loads = loader.loads.clone();
+    let mut runtime = JsRuntime::new(RuntimeOptions {
+      module_loader: Some(loader),
+      ..Default::default()
+    });
+    let spec = crate::resolve_url("file:///a.js").unwrap();
+    let a_id = futures::executor::block_on(runtime.load_module(&spec, None))
+      .expect("Failed to load");
+    futures::executor::block_on(runtime.mod_evaluate(a_id)).unwrap();
+    let d_id = {
+      let state_rc = JsRuntime::state(runtime.v8_isolate());
+      let state = state_rc.borrow();
+      state.modules.get_id("file:///d.js").unwrap()
+    };
+
+    // a.js and b.js import c.js, which imports d.js.
+    assert!(runtime.invalidate_module("file:///c.js"));
+    assert!(!runtime.invalidate_module("file:///c.js"));
+    {
+      let state_rc = JsRuntime::state(runtime.v8_isolate());
+      let state = state_rc.borrow();
+      assert_eq!(state.modules.get_id("file:///a.js"), None);
+      assert_eq!(state.modules.get_id("file:///b.js"), None);
+      assert_eq!(state.modules.get_id("file:///c.js"), None);
+      assert_eq!(state.modules.get_id("file:///d.js"), Some(d_id));
+    }
+
+    loads.lock().unwrap().clear();
+    let new_a_id =
+      futures::executor::block_on(runtime.load_module(&spec, None))
+        .expect("Failed to load");
+    assert_ne!(new_a_id, a_id);
+    futures::executor::block_on(runtime.mod_evaluate(new_a_id)).unwrap();
+    assert_eq!(
+      loads.lock().unwrap().to_vec(),
+      vec!["file:///a.js", "file:///b.js", "file:///c.js"]
+    );
+  }
+
   const CIRCULAR1_SRC: &str = r#"
     import "/circular2.js";
     Deno.core.print("circular1");
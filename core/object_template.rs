@@ -0,0 +1,229 @@
+// Copyright 2018-2021 the Deno authors. All rights reserved. MIT license.
+
+//! `ObjectTemplateBuilder`, returned by `JsRuntime::create_object_template()`,
+//! builds an object whose functions and accessors are backed by Rust and
+//! installs it as a global, e.g.:
+//!
+//! ```ignore
+//! runtime
+//!   .create_object_template()
+//!   .set_function("add", |(a, b): (i32, i32)| Ok(a + b))
+//!   .set_value("version", json!("1.0.0"))
+//!   .install_as_global("MyLib")?;
+//! ```
+
+use crate::bindings;
+use crate::error::generic_error;
+use crate::error::AnyError;
+use crate::runtime::exception_to_err_result;
+use crate::JsRuntime;
+use rusty_v8 as v8;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::Value;
+use std::convert::TryFrom;
+
+enum Property {
+  /// The index of the function in `JsRuntimeState::native_functions`.
+  Function(usize),
+  Value(Value),
+  /// The indices of the getter and the optional setter in
+  /// `JsRuntimeState::native_functions`.
+  Accessor(usize, Option<usize>),
+}
+
+pub struct ObjectTemplateBuilder<'a> {
+  runtime: &'a mut JsRuntime,
+  properties: Vec<(String, Property)>,
+}
+
+impl<'a> ObjectTemplateBuilder<'a> {
+  pub(crate) fn new(runtime: &'a mut JsRuntime) -> Self {
+    Self {
+      runtime,
+      properties: vec![],
+    }
+  }
+
+  /// Adds the method `name`, which calls `f` like the functions installed by
+  /// `JsRuntime::register_native_function()`.
+  pub fn set_function<A, R>(
+    mut self,
+    name: &str,
+    f: impl Fn(A) -> Result<R, AnyError> + 'static,
+  ) -> Self
+  where
+    A: DeserializeOwned,
+    R: Serialize,
+  {
+    let index = self.runtime.add_native_function(f);
+    self
+      .properties
+      .push((name.to_string(), Property::Function(index)));
+    self
+  }
+
+  /// Adds the property `name` with a copy of `value`.
+  pub fn set_value(mut self, name: &str, value: Value) -> Self {
+    self
+      .properties
+      .push((name.to_string(), Property::Value(value)));
+    self
+  }
+
+  /// Adds the property `name`, which calls `getter` when it is read and
+  /// `setter` with the new value when it is assigned.
+  pub fn set_accessor<R, A>(
+    mut self,
+    name: &str,
+    getter: impl Fn() -> Result<R, AnyError> + 'static,
+    setter: impl Fn(A) -> Result<(), AnyError> + 'static,
+  ) -> Self
+  where
+    R: Serialize,
+    A: DeserializeOwned,
+  {
+    let getter = self.runtime.add_native_function(move |()| getter());
+    let setter = self.runtime.add_native_function(setter);
+    self
+      .properties
+      .push((name.to_string(), Property::Accessor(getter, Some(setter))));
+    self
+  }
+
+  /// Adds the read-only property `name`, which calls `getter` when it is
+  /// read. Assignments are ignored, or throw in strict mode code.
+  pub fn set_getter<R>(
+    mut self,
+    name: &str,
+    getter: impl Fn() -> Result<R, AnyError> + 'static,
+  ) -> Self
+  where
+    R: Serialize,
+  {
+    let getter = self.runtime.add_native_function(move |()| getter());
+    self
+      .properties
+      .push((name.to_string(), Property::Accessor(getter, None)));
+    self
+  }
+
+  /// Creates the object and sets it as `globalThis[name]`. Fails if JS
+  /// throws while it is installed, e.g. from a setter of `globalThis[name]`.
+  pub fn install_as_global(self, name: &str) -> Result<(), AnyError> {
+    let properties = self.properties;
+    let state_rc = JsRuntime::state(self.runtime.v8_isolate());
+    let define_property = state_rc
+      .borrow()
+      .object_define_property
+      .clone()
+      .expect("Object.defineProperty was not captured");
+    self.runtime.with_scope(|scope| {
+      let tc_scope = &mut v8::TryCatch::new(scope);
+      let result = install(tc_scope, &define_property, name, &properties);
+      if result.is_none() {
+        if let Some(exception) = tc_scope.exception() {
+          return exception_to_err_result(tc_scope, exception, false);
+        }
+        return Err(generic_error(format!(
+          "Failed to install \"{}\" as a global",
+          name
+        )));
+      }
+      Ok(())
+    })
+  }
+}
+
+/// Captures the intrinsic `Object.defineProperty` for `install()`. Called
+/// when the runtime is created, before any other JS runs.
+pub(crate) fn init(rt: &mut JsRuntime) {
+  let define_property = rt.with_scope(|scope| {
+    let global = scope.get_current_context().global(scope);
+    let object_key = v8::String::new(scope, "Object").unwrap();
+    let object_ctor = global.get(scope, object_key.into()).unwrap();
+    let object_ctor = v8::Local::<v8::Object>::try_from(object_ctor).unwrap();
+    let define_key = v8::String::new(scope, "defineProperty").unwrap();
+    let define = object_ctor.get(scope, define_key.into()).unwrap();
+    let define = v8::Local::<v8::Function>::try_from(define).unwrap();
+    v8::Global::new(scope, define)
+  });
+  JsRuntime::state(rt.v8_isolate())
+    .borrow_mut()
+    .object_define_property = Some(define_property);
+}
+
+/// Returns `None` if V8 threw or failed to allocate.
+fn install(
+  scope: &mut v8::HandleScope,
+  define_property: &v8::Global<v8::Function>,
+  name: &str,
+  properties: &[(String, Property)],
+) -> Option<()> {
+  // Functions are part of the template. Values are set on the instance,
+  // since templates only take primitives, and V8 doesn't expose accessor
+  // properties with data on templates, so those are defined with
+  // `Object.defineProperty()`.
+  let template = v8::ObjectTemplate::new(scope);
+  for (name, property) in properties {
+    if let Property::Function(index) = property {
+      let key = v8::String::new(scope, name)?;
+      let function = bindings::native_function_template(scope, *index);
+      template.set(key.into(), function.into());
+    }
+  }
+  let object = template.new_instance(scope)?;
+
+  for (name, property) in properties {
+    let key = v8::String::new(scope, name)?;
+    match property {
+      Property::Function(_) => {}
+      Property::Value(value) => {
+        let json = v8::String::new(scope, &value.to_string())?;
+        let value = v8::json::parse(scope, json)?;
+        object.create_data_property(scope, key.into(), value)?;
+      }
+      Property::Accessor(getter, setter) => {
+        let getter = bindings::native_function_template(scope, *getter)
+          .get_function(scope)?;
+        let true_value = v8::Boolean::new(scope, true);
+        let mut names = vec![];
+        let mut values = vec![];
+        names.push(v8::String::new(scope, "get")?.into());
+        values.push(getter.into());
+        if let Some(setter) = setter {
+          let setter = bindings::native_function_template(scope, *setter)
+            .get_function(scope)?;
+          names.push(v8::String::new(scope, "set")?.into());
+          values.push(setter.into());
+        }
+        for attribute in &["enumerable", "configurable"] {
+          names.push(v8::String::new(scope, attribute)?.into());
+          values.push(true_value.into());
+        }
+        // Without a prototype, so properties added to `Object.prototype`
+        // can't alter the descriptor.
+        let null = v8::null(scope);
+        let descriptor = v8::Object::with_prototype_and_properties(
+          scope,
+          null.into(),
+          &names,
+          &values,
+        );
+
+        let define = v8::Local::new(scope, define_property);
+        let receiver = v8::undefined(scope);
+        define.call(
+          scope,
+          receiver.into(),
+          &[object.into(), key.into(), descriptor.into()],
+        )?;
+      }
+    }
+  }
+
+  let key = v8::String::new(scope, name)?;
+  let global = scope.get_current_context().global(scope);
+  global.set(scope, key.into(), object.into())?;
+  Some(())
+}
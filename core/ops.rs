@@ -1,6 +1,10 @@
 // Copyright 2018-2021 the Deno authors. All rights reserved. MIT license.
 
+use crate::abort_signal::new_abort_signal;
+use crate::abort_signal::AbortController;
+use crate::abort_signal::AbortSignalFactory;
 use crate::error::bad_resource_id;
+use crate::error::custom_error;
 use crate::error::type_error;
 use crate::error::AnyError;
 use crate::gotham_state::GothamState;
@@ -9,7 +13,10 @@ use crate::runtime::GetErrorClassFn;
 use crate::BufVec;
 use crate::ZeroCopyBuf;
 use futures::Future;
+use futures::Stream;
+use futures::TryStreamExt;
 use indexmap::IndexMap;
+use rusty_v8 as v8;
 use serde::de::DeserializeOwned;
 use serde::Serialize;
 use serde_json::json;
@@ -17,6 +24,7 @@ use serde_json::Value;
 use std::cell::RefCell;
 use std::collections::HashMap;
 use std::convert::TryInto;
+use std::io;
 use std::iter::once;
 use std::ops::Deref;
 use std::ops::DerefMut;
@@ -33,14 +41,54 @@ pub enum Op {
   /// AsyncUnref is the variation of Async, which doesn't block the program
   /// exiting.
   AsyncUnref(OpAsyncFuture),
+  /// An Async op with a priority, see `op_async_priority()`. There is no
+  /// unref counterpart, so `AsyncUnref` ops always have
+  /// `DEFAULT_OP_PRIORITY`.
+  ///
+  /// Code that matches on `Op` exhaustively, like op middleware that wraps
+  /// the futures of other ops, has to handle this variant as well.
+  AsyncPriority(u8, OpAsyncFuture),
   NotFound,
 }
 
+/// The priority of the results of `Op::Async` and `Op::AsyncUnref` ops.
+pub const DEFAULT_OP_PRIORITY: u8 = 128;
+
+/// Wraps `op_fn` so that its async results are dispatched to JS with
+/// `priority`. Of the results that are ready in the same turn of the event
+/// loop, those with a numerically lower priority are dispatched first, like
+/// UNIX nice levels; results with the same priority are dispatched in the order
+/// they completed. Sync results are returned unchanged.
+///
+/// Only `Op::Async` results are given `priority`. `Op::AsyncUnref` results
+/// are returned unchanged too, and keep `DEFAULT_OP_PRIORITY`, since
+/// `Op::AsyncPriority` ops keep the event loop alive.
+///
+/// ```ignore
+/// runtime.register_op("op_timer", op_async_priority(64, json_op_async(op_timer)));
+/// ```
+pub fn op_async_priority<F>(priority: u8, op_fn: F) -> Box<OpFn>
+where
+  F: Fn(Rc<RefCell<OpState>>, BufVec) -> Op + 'static,
+{
+  Box::new(move |state: Rc<RefCell<OpState>>, bufs: BufVec| -> Op {
+    match op_fn(state, bufs) {
+      Op::Async(fut) | Op::AsyncPriority(_, fut) => {
+        Op::AsyncPriority(priority, fut)
+      }
+      op => op,
+    }
+  })
+}
+
 /// Maintains the resources and ops inside a JS runtime.
 pub struct OpState {
   pub resource_table: ResourceTable,
   pub op_table: OpTable,
   pub get_error_class_fn: GetErrorClassFn,
+  /// Maximum size in bytes of a serialized JSON op result, see
+  /// `JsRuntime::set_max_op_response_size()`.
+  pub max_op_response_size: Option<usize>,
   gotham_state: GothamState,
 }
 
@@ -50,9 +98,26 @@ impl OpState {
       resource_table: Default::default(),
       op_table: OpTable::default(),
       get_error_class_fn: &|_| "Error",
+      max_op_response_size: None,
       gotham_state: Default::default(),
     }
   }
+
+  /// Runs `f` and, if it returns an error, rolls back the values stored with
+  /// `put_cloneable()` to what they were before the call and removes values
+  /// of types that were not present. Values stored with `put()` and the
+  /// resource table are not snapshotted, so changes `f` makes to them persist.
+  pub fn transaction<R, F>(&mut self, f: F) -> Result<R, AnyError>
+  where
+    F: FnOnce(&mut OpState) -> Result<R, AnyError>,
+  {
+    let snapshot = self.gotham_state.snapshot();
+    let result = f(self);
+    if result.is_err() {
+      self.gotham_state.restore(snapshot);
+    }
+    result
+  }
 }
 
 impl Deref for OpState {
@@ -69,28 +134,74 @@ impl DerefMut for OpState {
   }
 }
 
+/// Borrows values of several types from an `OpState` at once and returns a
+/// tuple of shared references, in the order the types are listed.
+///
+/// ```ignore
+/// let (permissions, client) = deno_core::get!(state, Permissions, Client);
+/// ```
+///
+/// `state` may be an `OpState`, a reference to one or a `Ref<OpState>`.
+/// Like `OpState::borrow()`, it panics if one of the types is not present.
+#[macro_export]
+macro_rules! get {
+  ($state:expr, $($ty:ty),+ $(,)?) => {{
+    let state: &$crate::OpState = &$state;
+    ($(state.borrow::<$ty>(),)+)
+  }};
+}
+
 /// Collection for storing registered ops. The special 'get_op_catalog'
 /// op with OpId `0` is automatically added when the OpTable is created.
-pub struct OpTable(IndexMap<String, Rc<OpFn>>);
+///
+/// Removed ops keep their slot as `None`, so that the ids of the other ops
+/// don't change.
+pub struct OpTable(IndexMap<String, Option<Rc<OpFn>>>);
 
 impl OpTable {
   pub fn register_op<F>(&mut self, name: &str, op_fn: F) -> OpId
   where
     F: Fn(Rc<RefCell<OpState>>, BufVec) -> Op + 'static,
   {
-    let (op_id, prev) = self.0.insert_full(name.to_owned(), Rc::new(op_fn));
-    assert!(prev.is_none());
+    let (op_id, prev) =
+      self.0.insert_full(name.to_owned(), Some(Rc::new(op_fn)));
+    assert!(prev.flatten().is_none());
     op_id
   }
 
+  pub(crate) fn contains(&self, name: &str) -> bool {
+    matches!(self.0.get(name), Some(Some(_)))
+  }
+
+  /// Removes the op `name`, returning whether it was registered. Calls to
+  /// its id are routed to `Op::NotFound` from then on.
+  pub(crate) fn remove(&mut self, name: &str) -> bool {
+    match self.0.get_mut(name) {
+      Some(op_fn) => op_fn.take().is_some(),
+      None => false,
+    }
+  }
+
+  /// The names of the ops, in the order of their ids, including removed ones.
+  pub(crate) fn op_names(&self) -> Vec<String> {
+    self.0.keys().cloned().collect()
+  }
+
   pub fn route_op(
     op_id: OpId,
     state: Rc<RefCell<OpState>>,
     bufs: BufVec,
   ) -> Op {
     if op_id == 0 {
-      let ops: HashMap<String, OpId> =
-        state.borrow().op_table.0.keys().cloned().zip(0..).collect();
+      let ops: HashMap<String, OpId> = state
+        .borrow()
+        .op_table
+        .0
+        .iter()
+        .zip(0..)
+        .filter(|((_, op_fn), _)| op_fn.is_some())
+        .map(|((name, _), op_id)| (name.clone(), op_id))
+        .collect();
       let buf = serde_json::to_vec(&ops).map(Into::into).unwrap();
       Op::Sync(buf)
     } else {
@@ -99,7 +210,7 @@ impl OpTable {
         .op_table
         .0
         .get_index(op_id)
-        .map(|(_, op_fn)| op_fn.clone());
+        .and_then(|(_, op_fn)| op_fn.clone());
       match op_fn {
         Some(f) => (f)(state, bufs),
         None => Op::NotFound,
@@ -113,7 +224,7 @@ impl Default for OpTable {
     fn dummy(_state: Rc<RefCell<OpState>>, _bufs: BufVec) -> Op {
       unreachable!()
     }
-    Self(once(("ops".to_owned(), Rc::new(dummy) as _)).collect())
+    Self(once(("ops".to_owned(), Some(Rc::new(dummy) as _))).collect())
   }
 }
 
@@ -150,8 +261,7 @@ where
     let result = serde_json::from_slice(&bufs[0])
       .map_err(AnyError::from)
       .and_then(|args| op_fn(&mut state.borrow_mut(), args, &mut bufs[1..]));
-    let buf =
-      json_serialize_op_result(None, result, state.borrow().get_error_class_fn);
+    let buf = json_serialize_op_result(None, result, &state.borrow());
     Op::Sync(buf)
   })
 }
@@ -197,11 +307,7 @@ where
       let bufs = bufs[1..].into();
       use crate::futures::FutureExt;
       let fut = op_fn(state.clone(), args, bufs).map(move |result| {
-        json_serialize_op_result(
-          Some(promise_id),
-          result,
-          state.borrow().get_error_class_fn,
-        )
+        json_serialize_op_result(Some(promise_id), result, &state.borrow())
       });
       Ok(Op::Async(Box::pin(fut)))
     };
@@ -212,27 +318,182 @@ where
       Err(err) => Op::Sync(json_serialize_op_result(
         None,
         Err::<(), AnyError>(err),
-        state.borrow().get_error_class_fn,
+        &state.borrow(),
       )),
     }
   })
 }
 
+/// Creates an op that collects every item of a stream before resolving.
+///
+/// This works like [json_op_async()](fn.json_op_async.html), except that
+/// `op_fn` returns a stream of results instead of a future. All items are
+/// buffered into a `Vec` which resolves the JS promise as a single array; the
+/// first error ends the stream and rejects the promise instead.
+///
+/// Use this when the caller always needs the whole result anyway, so that JS
+/// doesn't have to pull the items one at a time.
+pub fn json_op_async_buffered<F, V, S, T>(op_fn: F) -> Box<OpFn>
+where
+  F: Fn(Rc<RefCell<OpState>>, V, BufVec) -> S + 'static,
+  V: DeserializeOwned,
+  S: Stream<Item = Result<T, AnyError>> + 'static,
+  T: Serialize + 'static,
+{
+  json_op_async(move |state, args: V, bufs| {
+    op_fn(state, args, bufs).try_collect::<Vec<T>>()
+  })
+}
+
+/// A sub-command of an op created with [op_batch()](fn.op_batch.html).
+/// It's passed the sub-command code along with its arguments.
+pub type BatchOpHandler =
+  dyn Fn(&mut OpState, u32, Value) -> Result<Value, AnyError> + 'static;
+
+/// A sync op and an async op serving the same sub-commands, each with the
+/// name to register it under, see [op_batch()](fn.op_batch.html).
+pub struct OpPair {
+  pub sync_op: (String, Box<OpFn>),
+  pub async_op: (String, Box<OpFn>),
+}
+
+/// Creates the ops for a family of sub-commands, e.g. connect/send/recv/close
+/// of a protocol, without registering each of them: a sync JSON op named
+/// `base_name`, and a JSON op named `{base_name}_async` that resolves a
+/// promise with the same result instead.
+///
+/// Both ops take a `[code, args]` pair, and `code` indexes into `handlers`,
+/// so that dispatch is an array lookup.
+///
+/// ```js
+/// Deno.core.ops();
+/// const OP_NET_CONNECT = 0;
+/// const rid = Deno.core.jsonOpSync("op_net", [OP_NET_CONNECT, { port }]);
+/// await Deno.core.jsonOpAsync("op_net_async", [OP_NET_CONNECT, { port }]);
+/// ```
+pub fn op_batch(
+  base_name: &'static str,
+  handlers: Vec<Box<BatchOpHandler>>,
+) -> OpPair {
+  let handlers = Rc::new(handlers);
+  let dispatch = move |state: &mut OpState, (code, args): (u32, Value)| {
+    let handler = handlers
+      .get(code as usize)
+      .ok_or_else(|| type_error(format!("Unknown sub-command {}", code)))?;
+    handler(state, code, args)
+  };
+  let dispatch = Rc::new(dispatch);
+  let sync_dispatch = dispatch.clone();
+  OpPair {
+    sync_op: (
+      base_name.to_string(),
+      json_op_sync(move |state, args, _zero_copy| sync_dispatch(state, args)),
+    ),
+    async_op: (
+      format!("{}_async", base_name),
+      json_op_async(move |state, args, _bufs| {
+        futures::future::ready(dispatch(&mut state.borrow_mut(), args))
+      }),
+    ),
+  }
+}
+
+/// An op that is called with a V8 scope and passes JS values in and out
+/// directly, see [op_sync_result_v8()](fn.op_sync_result_v8.html).
+pub type V8OpFn = dyn for<'s> Fn(
+    &mut v8::HandleScope<'s>,
+    &mut OpState,
+    v8::Local<'s, v8::Value>,
+  ) -> Result<v8::Local<'s, v8::Value>, v8::Local<'s, v8::Value>>
+  + 'static;
+
+/// Creates a sync op for `JsRuntime::register_v8_op()` whose failures are JS
+/// values rather than `AnyError`s. The `Err` value, typically an `Error`
+/// object built or caught by `op_fn`, is thrown as-is, so its type, stack
+/// trace and identity are preserved.
+///
+/// ```js
+/// const result = Deno.core.v8OpSync("op_compile", source);
+/// ```
+pub fn op_sync_result_v8<F>(op_fn: F) -> Box<V8OpFn>
+where
+  F: for<'s> Fn(
+      &mut v8::HandleScope<'s>,
+      &mut OpState,
+      v8::Local<'s, v8::Value>,
+    )
+      -> Result<v8::Local<'s, v8::Value>, v8::Local<'s, v8::Value>>
+    + 'static,
+{
+  Box::new(op_fn)
+}
+
+/// Collects serialized bytes, but fails as soon as they would exceed
+/// `limit`, so that an oversized op result is never buffered in full.
+struct LimitedWriter {
+  buf: Vec<u8>,
+  limit: Option<usize>,
+  exceeded: bool,
+}
+
+impl io::Write for LimitedWriter {
+  fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+    if let Some(limit) = self.limit {
+      if self.buf.len() + data.len() > limit {
+        self.exceeded = true;
+        return Err(io::Error::new(io::ErrorKind::Other, "limit exceeded"));
+      }
+    }
+    self.buf.extend_from_slice(data);
+    Ok(data.len())
+  }
+
+  fn flush(&mut self) -> io::Result<()> {
+    Ok(())
+  }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct OpResultOk<'a, R> {
+  ok: &'a R,
+  promise_id: Option<u64>,
+}
+
 fn json_serialize_op_result<R: Serialize>(
   promise_id: Option<u64>,
   result: Result<R, AnyError>,
-  get_error_class_fn: crate::runtime::GetErrorClassFn,
+  state: &OpState,
 ) -> Box<[u8]> {
-  let value = match result {
-    Ok(v) => serde_json::json!({ "ok": v, "promiseId": promise_id }),
-    Err(err) => serde_json::json!({
-      "promiseId": promise_id ,
-      "err": {
-        "className": (get_error_class_fn)(&err),
-        "message": err.to_string(),
+  let err = match result {
+    Ok(v) => {
+      let mut writer = LimitedWriter {
+        buf: vec![],
+        limit: state.max_op_response_size,
+        exceeded: false,
+      };
+      let value = OpResultOk { ok: &v, promise_id };
+      match serde_json::to_writer(&mut writer, &value) {
+        Ok(()) => return writer.buf.into_boxed_slice(),
+        Err(_) if writer.exceeded => custom_error(
+          "RangeError",
+          format!(
+            "Op response exceeds the maximum size of {} bytes",
+            state.max_op_response_size.unwrap()
+          ),
+        ),
+        Err(err) => err.into(),
       }
-    }),
+    }
+    Err(err) => err,
   };
+  let value = serde_json::json!({
+    "promiseId": promise_id ,
+    "err": {
+      "className": (state.get_error_class_fn)(&err),
+      "message": err.to_string(),
+    }
+  });
   serde_json::to_vec(&value).unwrap().into_boxed_slice()
 }
 
@@ -274,10 +535,98 @@ pub fn op_close(
   Ok(json!({}))
 }
 
+/// Create an `AbortController` resource and the `AbortSignal` resource it
+/// aborts, using the factory set by `JsRuntime::set_abort_signal_factory()`.
+///
+/// This op must be wrapped in `json_op_sync`.
+pub fn op_abort_signal_new(
+  state: &mut OpState,
+  _args: Value,
+  _zero_copy: &mut [ZeroCopyBuf],
+) -> Result<Value, AnyError> {
+  let (controller, signal) = match state.try_borrow::<Box<AbortSignalFactory>>()
+  {
+    Some(factory) => factory(),
+    None => new_abort_signal(),
+  };
+  let controller_rid = state.resource_table.add(controller);
+  let signal_rid = state.resource_table.add(signal);
+  Ok(json!({
+    "controllerRid": controller_rid,
+    "signalRid": signal_rid,
+  }))
+}
+
+/// Abort the signal of an `AbortController` resource.
+///
+/// This op must be wrapped in `json_op_sync`.
+pub fn op_abort(
+  state: &mut OpState,
+  args: Value,
+  _zero_copy: &mut [ZeroCopyBuf],
+) -> Result<Value, AnyError> {
+  let rid = args
+    .get("rid")
+    .and_then(Value::as_u64)
+    .ok_or_else(|| type_error("missing or invalid `rid`"))?;
+
+  state
+    .resource_table
+    .get::<AbortController>(rid as u32)
+    .ok_or_else(bad_resource_id)?
+    .cancel();
+
+  Ok(json!({}))
+}
+
 #[cfg(test)]
 mod tests {
   use super::*;
 
+  #[test]
+  fn get_macro() {
+    struct Name(&'static str);
+    struct Count(u32);
+
+    let state = Rc::new(RefCell::new(OpState::new()));
+    state.borrow_mut().put(Name("deno"));
+    state.borrow_mut().put(Count(3));
+
+    let state = state.borrow();
+    let (name, count) = crate::get!(state, Name, Count);
+    assert_eq!(name.0, "deno");
+    assert_eq!(count.0, 3);
+    let (count,) = crate::get!(&*state, Count);
+    assert_eq!(count.0, 3);
+  }
+
+  #[test]
+  fn transaction() {
+    #[derive(Clone)]
+    struct Balance(i64);
+    #[derive(Clone)]
+    struct Log(Vec<i64>);
+
+    let mut state = OpState::new();
+    state.put_cloneable(Balance(10));
+    state.put_cloneable(Log(vec![]));
+
+    let withdraw = |state: &mut OpState, amount: i64| {
+      state.borrow_mut::<Log>().0.push(-amount);
+      let balance = state.borrow_mut::<Balance>();
+      balance.0 -= amount;
+      if balance.0 < 0 {
+        return Err(crate::error::type_error("insufficient funds"));
+      }
+      Ok(balance.0)
+    };
+
+    assert_eq!(state.transaction(|state| withdraw(state, 4)).unwrap(), 6);
+    assert!(state.transaction(|state| withdraw(state, 7)).is_err());
+    assert_eq!(state.borrow::<Balance>().0, 6);
+    assert_eq!(state.borrow::<Log>().0, vec![-4]);
+  }
+
   #[test]
   fn op_table() {
     let state = Rc::new(RefCell::new(OpState::new()));
@@ -314,4 +663,24 @@ mod tests {
       ]
     );
   }
+
+  #[test]
+  fn max_op_response_size() {
+    let mut state = OpState::new();
+    state.max_op_response_size = Some(64);
+
+    let buf = json_serialize_op_result(Some(1), Ok(vec![0u8; 8]), &state);
+    let value: Value = serde_json::from_slice(&buf).unwrap();
+    assert_eq!(value["ok"], json!([0, 0, 0, 0, 0, 0, 0, 0]));
+    assert_eq!(value["promiseId"], json!(1));
+
+    let buf = json_serialize_op_result(Some(2), Ok(vec![0u8; 64]), &state);
+    let value: Value = serde_json::from_slice(&buf).unwrap();
+    assert!(value.get("ok").is_none());
+    assert_eq!(value["promiseId"], json!(2));
+    assert_eq!(
+      value["err"]["message"],
+      json!("Op response exceeds the maximum size of 64 bytes")
+    );
+  }
 }
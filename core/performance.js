@@ -0,0 +1,175 @@
+// Copyright 2018-2021 the Deno authors. All rights reserved. MIT license.
+"use strict";
+
+((window) => {
+  const core = window.Deno.core;
+
+  // Missing marks are reported with the class the CLI uses for them.
+  if (core.getErrorClass("DOMExceptionSyntaxError") === undefined) {
+    core.registerErrorClass("DOMExceptionSyntaxError", SyntaxError);
+  }
+
+  class PerformanceEntry {
+    #name = "";
+    #entryType = "";
+    #startTime = 0;
+    #duration = 0;
+    #detail = null;
+
+    get name() {
+      return this.#name;
+    }
+
+    get entryType() {
+      return this.#entryType;
+    }
+
+    get startTime() {
+      return this.#startTime;
+    }
+
+    get duration() {
+      return this.#duration;
+    }
+
+    get detail() {
+      return this.#detail;
+    }
+
+    constructor({ name, entryType, startTime, duration, detail = null }) {
+      this.#name = name;
+      this.#entryType = entryType;
+      this.#startTime = startTime;
+      this.#duration = duration;
+      this.#detail = detail;
+    }
+
+    toJSON() {
+      return {
+        name: this.#name,
+        entryType: this.#entryType,
+        startTime: this.#startTime,
+        duration: this.#duration,
+        detail: this.#detail,
+      };
+    }
+
+    get [Symbol.toStringTag]() {
+      return this.constructor.name;
+    }
+  }
+
+  class PerformanceMark extends PerformanceEntry {}
+
+  class PerformanceMeasure extends PerformanceEntry {}
+
+  function toEntry(entry) {
+    return entry.entryType === "mark"
+      ? new PerformanceMark(entry)
+      : new PerformanceMeasure(entry);
+  }
+
+  function getEntries(name, entryType) {
+    return core.jsonOpSync("op_perf_get_entries", { name, entryType })
+      .map(toEntry);
+  }
+
+  function now() {
+    return core.jsonOpSync("op_perf_now");
+  }
+
+  class Performance {
+    now() {
+      return now();
+    }
+
+    mark(name, { startTime = now(), detail = null } = {}) {
+      name = String(name);
+      core.jsonOpSync("op_perf_mark", { name, startTime, detail });
+      return new PerformanceMark({
+        name,
+        entryType: "mark",
+        startTime,
+        duration: 0,
+        detail,
+      });
+    }
+
+    measure(name, startMark, endMark) {
+      name = String(name);
+      const { startTime, duration } = core.jsonOpSync("op_perf_measure", {
+        name,
+        start: startMark === undefined ? undefined : String(startMark),
+        end: endMark === undefined ? undefined : String(endMark),
+        now: now(),
+      });
+      return new PerformanceMeasure({
+        name,
+        entryType: "measure",
+        startTime,
+        duration,
+      });
+    }
+
+    getEntries() {
+      return getEntries();
+    }
+
+    getEntriesByName(name, type) {
+      return getEntries(String(name), type);
+    }
+
+    getEntriesByType(type) {
+      return getEntries(undefined, type);
+    }
+
+    clearMarks(name) {
+      core.jsonOpSync("op_perf_clear", { entryType: "mark", name });
+    }
+
+    clearMeasures(name) {
+      core.jsonOpSync("op_perf_clear", { entryType: "measure", name });
+    }
+
+    setResourceTimingBufferSize(maxSize) {
+      core.jsonOpSync("op_perf_set_buffer_size", { maxSize: maxSize >>> 0 });
+    }
+
+    toJSON() {
+      return {};
+    }
+
+    get [Symbol.toStringTag]() {
+      return "Performance";
+    }
+  }
+
+  Object.defineProperties(window, {
+    Performance: {
+      value: Performance,
+      writable: true,
+      configurable: true,
+    },
+    PerformanceEntry: {
+      value: PerformanceEntry,
+      writable: true,
+      configurable: true,
+    },
+    PerformanceMark: {
+      value: PerformanceMark,
+      writable: true,
+      configurable: true,
+    },
+    PerformanceMeasure: {
+      value: PerformanceMeasure,
+      writable: true,
+      configurable: true,
+    },
+    performance: {
+      value: new Performance(),
+      writable: true,
+      configurable: true,
+      enumerable: true,
+    },
+  });
+})(this);
@@ -0,0 +1,472 @@
+// Copyright 2018-2021 the Deno authors. All rights reserved. MIT license.
+
+//! The entry buffer behind `performance.mark()` and `performance.measure()`,
+//! and a minimal `performance` global on top of it, installed on demand by
+//! `JsRuntime::install_performance_api()`.
+//!
+//! Keeping the buffer in `OpState` lets Rust code (the bench runner, embedder
+//! telemetry) read the marks and measures made by JS through
+//! `JsRuntime::get_performance_entries()`, whether they were made through the
+//! global installed here or through an embedder's own `performance` global
+//! built on the ops registered by `JsRuntime::register_performance_ops()`.
+
+use crate::error::custom_error;
+use crate::error::type_error;
+use crate::error::AnyError;
+use crate::json_op_sync;
+use crate::JsRuntime;
+use crate::OpFn;
+use crate::OpState;
+use crate::ZeroCopyBuf;
+use serde::Deserialize;
+use serde::Serialize;
+use serde_json::json;
+use serde_json::Value;
+use std::collections::VecDeque;
+use std::time::Instant;
+
+/// A mark or measure recorded through the JS `performance` API.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PerformanceEntry {
+  /// Unique within a runtime. JS uses it to hand out the same entry object
+  /// every time.
+  pub id: u64,
+  pub name: String,
+  /// Either `"mark"` or `"measure"`.
+  pub entry_type: String,
+  /// Milliseconds since the time origin of the `performance` global.
+  pub start_time: f64,
+  /// Milliseconds; always `0` for marks.
+  pub duration: f64,
+  /// The JSON form of the entry's `detail`, or `null` if it had none or it
+  /// couldn't be represented as JSON.
+  pub detail: Value,
+}
+
+pub(crate) struct PerformanceState {
+  /// When the ops were registered, the time origin of `op_perf_now`.
+  time_origin: Instant,
+  /// Whether `performance.js` was executed.
+  installed: bool,
+  next_id: u64,
+  entries: VecDeque<PerformanceEntry>,
+  /// Set by `performance.setResourceTimingBufferSize()`. The oldest entries
+  /// are evicted once the buffer holds more.
+  max_size: Option<usize>,
+}
+
+impl PerformanceState {
+  fn now(&self) -> f64 {
+    self.time_origin.elapsed().as_secs_f64() * 1000.0
+  }
+
+  pub(crate) fn entries(&self) -> Vec<PerformanceEntry> {
+    self.entries.iter().cloned().collect()
+  }
+
+  /// Adds an entry and returns its id together with the ids of the entries
+  /// evicted to make room for it.
+  fn push(
+    &mut self,
+    name: String,
+    entry_type: &str,
+    start_time: f64,
+    duration: f64,
+    detail: Value,
+  ) -> (u64, Vec<u64>) {
+    let id = self.next_id;
+    self.next_id += 1;
+    self.entries.push_back(PerformanceEntry {
+      id,
+      name,
+      entry_type: entry_type.to_string(),
+      start_time,
+      duration,
+      detail,
+    });
+    (id, self.evict())
+  }
+
+  fn evict(&mut self) -> Vec<u64> {
+    let max_size = match self.max_size {
+      Some(max_size) => max_size,
+      None => return vec![],
+    };
+    let mut evicted = vec![];
+    while self.entries.len() > max_size {
+      evicted.push(self.entries.pop_front().unwrap().id);
+    }
+    evicted
+  }
+
+  /// Resolves a mark name to the start time of the most recent mark with
+  /// that name.
+  fn mark_time(&self, name: &str) -> Result<f64, AnyError> {
+    self
+      .entries
+      .iter()
+      .rev()
+      .find(|e| e.entry_type == "mark" && e.name == name)
+      .map(|e| e.start_time)
+      .ok_or_else(|| {
+        custom_error(
+          "DOMExceptionSyntaxError",
+          format!("Cannot find mark: \"{}\".", name),
+        )
+      })
+  }
+
+  fn timestamp(&self, mark: &MarkOrTime) -> Result<f64, AnyError> {
+    match mark {
+      MarkOrTime::Mark(name) => self.mark_time(name),
+      MarkOrTime::Time(time) => non_negative(*time, "Mark"),
+    }
+  }
+}
+
+fn non_negative(time: f64, what: &str) -> Result<f64, AnyError> {
+  if time < 0.0 {
+    Err(type_error(format!("{} cannot be negative.", what)))
+  } else {
+    Ok(time)
+  }
+}
+
+pub(crate) fn init_ops(rt: &mut JsRuntime) {
+  if rt.op_state().borrow().has::<PerformanceState>() {
+    return;
+  }
+  rt.op_state().borrow_mut().put(PerformanceState {
+    time_origin: Instant::now(),
+    installed: false,
+    next_id: 0,
+    entries: VecDeque::new(),
+    max_size: None,
+  });
+  let ops: Vec<(&'static str, Box<OpFn>)> = vec![
+    ("op_perf_now", json_op_sync(op_perf_now)),
+    ("op_perf_mark", json_op_sync(op_perf_mark)),
+    ("op_perf_measure", json_op_sync(op_perf_measure)),
+    ("op_perf_get_entries", json_op_sync(op_perf_get_entries)),
+    ("op_perf_clear", json_op_sync(op_perf_clear)),
+    (
+      "op_perf_set_buffer_size",
+      json_op_sync(op_perf_set_buffer_size),
+    ),
+  ];
+  for (name, op) in ops {
+    rt.add_op_at_runtime(name, op).unwrap();
+  }
+}
+
+pub(crate) fn init(rt: &mut JsRuntime) {
+  init_ops(rt);
+  {
+    let op_state = rt.op_state();
+    let mut op_state = op_state.borrow_mut();
+    let state = op_state.borrow_mut::<PerformanceState>();
+    if state.installed {
+      return;
+    }
+    state.installed = true;
+  }
+  rt.execute("deno:core/performance.js", include_str!("performance.js"))
+    .unwrap();
+}
+
+/// Returns the milliseconds since the ops were registered.
+#[allow(clippy::unnecessary_wraps)]
+fn op_perf_now(
+  state: &mut OpState,
+  _args: Value,
+  _zero_copy: &mut [ZeroCopyBuf],
+) -> Result<Value, AnyError> {
+  Ok(json!(state.borrow::<PerformanceState>().now()))
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct MarkArgs {
+  name: String,
+  start_time: f64,
+  #[serde(default)]
+  detail: Value,
+}
+
+/// Records a mark. Returns `{ id, evicted }`.
+fn op_perf_mark(
+  state: &mut OpState,
+  args: Value,
+  _zero_copy: &mut [ZeroCopyBuf],
+) -> Result<Value, AnyError> {
+  let args: MarkArgs = serde_json::from_value(args)?;
+  let start_time = non_negative(args.start_time, "startTime")?;
+  let (id, evicted) = state.borrow_mut::<PerformanceState>().push(
+    args.name,
+    "mark",
+    start_time,
+    0.0,
+    args.detail,
+  );
+  Ok(json!({ "id": id, "evicted": evicted }))
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum MarkOrTime {
+  Mark(String),
+  Time(f64),
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct MeasureArgs {
+  name: String,
+  start: Option<MarkOrTime>,
+  end: Option<MarkOrTime>,
+  duration: Option<f64>,
+  /// The current time, used as the end time if neither `end` nor `duration`
+  /// is given.
+  now: f64,
+  #[serde(default)]
+  detail: Value,
+}
+
+/// Records a measure, resolving `start` and `end` the way
+/// https://w3c.github.io/user-timing/#dom-performance-measure does. Returns
+/// `{ id, startTime, duration, evicted }`.
+fn op_perf_measure(
+  state: &mut OpState,
+  args: Value,
+  _zero_copy: &mut [ZeroCopyBuf],
+) -> Result<Value, AnyError> {
+  let args: MeasureArgs = serde_json::from_value(args)?;
+  let perf = state.borrow_mut::<PerformanceState>();
+  let duration = match args.duration {
+    Some(duration) => Some(non_negative(duration, "Duration")?),
+    None => None,
+  };
+
+  let end_time = match (&args.end, &args.start, duration) {
+    (Some(end), _, _) => perf.timestamp(end)?,
+    (None, Some(start), Some(duration)) => perf.timestamp(start)? + duration,
+    _ => args.now,
+  };
+  let start_time = match (&args.start, &args.end, duration) {
+    (Some(start), _, _) => perf.timestamp(start)?,
+    (None, Some(_), Some(duration)) => end_time - duration,
+    _ => 0.0,
+  };
+
+  let duration = end_time - start_time;
+  let (id, evicted) =
+    perf.push(args.name, "measure", start_time, duration, args.detail);
+  Ok(json!({
+    "id": id,
+    "startTime": start_time,
+    "duration": duration,
+    "evicted": evicted,
+  }))
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GetEntriesArgs {
+  name: Option<String>,
+  entry_type: Option<String>,
+}
+
+/// Returns the entries matching the optional `name` and `entryType`
+/// filters, oldest first.
+fn op_perf_get_entries(
+  state: &mut OpState,
+  args: Value,
+  _zero_copy: &mut [ZeroCopyBuf],
+) -> Result<Value, AnyError> {
+  let args: GetEntriesArgs = serde_json::from_value(args)?;
+  let entries: Vec<&PerformanceEntry> = state
+    .borrow::<PerformanceState>()
+    .entries
+    .iter()
+    .filter(|e| args.name.as_ref().map_or(true, |name| *name == e.name))
+    .filter(|e| {
+      args
+        .entry_type
+        .as_ref()
+        .map_or(true, |entry_type| *entry_type == e.entry_type)
+    })
+    .collect();
+  Ok(json!(entries))
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ClearArgs {
+  entry_type: String,
+  name: Option<String>,
+}
+
+/// Removes the entries of `entryType`, optionally only those called `name`.
+/// Returns the ids of the removed entries.
+fn op_perf_clear(
+  state: &mut OpState,
+  args: Value,
+  _zero_copy: &mut [ZeroCopyBuf],
+) -> Result<Value, AnyError> {
+  let args: ClearArgs = serde_json::from_value(args)?;
+  let mut removed = vec![];
+  state.borrow_mut::<PerformanceState>().entries.retain(|e| {
+    let matches = e.entry_type == args.entry_type
+      && args.name.as_ref().map_or(true, |name| *name == e.name);
+    if matches {
+      removed.push(e.id);
+    }
+    !matches
+  });
+  Ok(json!(removed))
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SetBufferSizeArgs {
+  max_size: usize,
+}
+
+/// Limits the number of buffered entries, evicting the oldest ones right
+/// away if there are more. Returns the ids of the evicted entries.
+fn op_perf_set_buffer_size(
+  state: &mut OpState,
+  args: Value,
+  _zero_copy: &mut [ZeroCopyBuf],
+) -> Result<Value, AnyError> {
+  let args: SetBufferSizeArgs = serde_json::from_value(args)?;
+  let perf = state.borrow_mut::<PerformanceState>();
+  perf.max_size = Some(args.max_size);
+  Ok(json!(perf.evict()))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn setup() -> JsRuntime {
+    let mut runtime = JsRuntime::new(Default::default());
+    runtime.register_performance_ops();
+    runtime
+  }
+
+  fn measure(state: &mut OpState, args: Value) -> Result<(f64, f64), AnyError> {
+    let result = op_perf_measure(state, args, &mut [])?;
+    Ok((
+      result["startTime"].as_f64().unwrap(),
+      result["duration"].as_f64().unwrap(),
+    ))
+  }
+
+  fn mark(state: &mut OpState, name: &str, start_time: f64) -> Value {
+    op_perf_mark(
+      state,
+      json!({ "name": name, "startTime": start_time }),
+      &mut [],
+    )
+    .unwrap()
+  }
+
+  #[test]
+  fn measure_resolution() {
+    let mut runtime = setup();
+    let state = runtime.op_state();
+    let mut state = state.borrow_mut();
+    mark(&mut state, "a", 10.0);
+    mark(&mut state, "b", 40.0);
+    mark(&mut state, "a", 20.0);
+
+    let (start, duration) = measure(
+      &mut state,
+      json!({ "name": "m", "start": "a", "now": 100.0 }),
+    )
+    .unwrap();
+    assert_eq!((start, duration), (20.0, 80.0));
+
+    let (start, duration) = measure(
+      &mut state,
+      json!({ "name": "m", "start": 5.0, "end": "b", "now": 100.0 }),
+    )
+    .unwrap();
+    assert_eq!((start, duration), (5.0, 35.0));
+
+    let (start, duration) = measure(
+      &mut state,
+      json!({ "name": "m", "end": "b", "duration": 15.0, "now": 100.0 }),
+    )
+    .unwrap();
+    assert_eq!((start, duration), (25.0, 15.0));
+
+    let (start, duration) = measure(
+      &mut state,
+      json!({ "name": "m", "start": "b", "duration": 2.5, "now": 100.0 }),
+    )
+    .unwrap();
+    assert_eq!((start, duration), (40.0, 2.5));
+
+    let (start, duration) =
+      measure(&mut state, json!({ "name": "m", "now": 100.0 })).unwrap();
+    assert_eq!((start, duration), (0.0, 100.0));
+
+    let err =
+      measure(&mut state, json!({ "name": "m", "start": "c", "now": 1.0 }))
+        .unwrap_err();
+    assert_eq!(err.to_string(), "Cannot find mark: \"c\".");
+    assert!(measure(
+      &mut state,
+      json!({ "name": "m", "start": "a", "duration": -1.0, "now": 1.0 })
+    )
+    .is_err());
+    assert!(measure(
+      &mut state,
+      json!({ "name": "m", "end": -1.0, "now": 1.0 })
+    )
+    .is_err());
+  }
+
+  #[test]
+  fn eviction() {
+    let mut runtime = setup();
+    {
+      let state = runtime.op_state();
+      let mut state = state.borrow_mut();
+      for i in 0..3 {
+        mark(&mut state, "m", i as f64);
+      }
+      let evicted =
+        op_perf_set_buffer_size(&mut state, json!({ "maxSize": 2 }), &mut [])
+          .unwrap();
+      assert_eq!(evicted, json!([0]));
+      assert_eq!(
+        mark(&mut state, "m", 3.0),
+        json!({ "id": 3, "evicted": [1] })
+      );
+    }
+
+    let start_times: Vec<f64> = runtime
+      .get_performance_entries()
+      .iter()
+      .map(|e| e.start_time)
+      .collect();
+    assert_eq!(start_times, vec![2.0, 3.0]);
+
+    {
+      let state = runtime.op_state();
+      let mut state = state.borrow_mut();
+      let removed = op_perf_clear(
+        &mut state,
+        json!({ "entryType": "mark", "name": "m" }),
+        &mut [],
+      )
+      .unwrap();
+      assert_eq!(removed, json!([2, 3]));
+    }
+    assert!(runtime.get_performance_entries().is_empty());
+  }
+}
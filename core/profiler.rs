@@ -0,0 +1,272 @@
+// Copyright 2018-2021 the Deno authors. All rights reserved. MIT license.
+
+//! CPU profiling of the JavaScript run by a `JsRuntime`; see
+//! `JsRuntime::profiler_start()`.
+//!
+//! rusty_v8 doesn't bind V8's `CpuProfiler`, so it is driven through the
+//! `Profiler` domain of an inspector session of its own, whose
+//! `Profiler.stop` returns the profile in the format of Chrome's
+//! `.cpuprofile` files.
+
+use crate::error::generic_error;
+use crate::error::AnyError;
+use rusty_v8 as v8;
+use serde::Deserialize;
+use serde::Serialize;
+use serde_json::json;
+use serde_json::Value;
+
+/// A CPU profile, as returned by `JsRuntime::profiler_stop()`. Times are in
+/// microseconds.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CpuProfile {
+  /// The title passed to `JsRuntime::profiler_start()`, which isn't part of
+  /// the Chrome format.
+  #[serde(skip)]
+  pub title: String,
+  pub nodes: Vec<CpuProfileNode>,
+  pub start_time: u64,
+  pub end_time: u64,
+  /// The ids of the nodes that were on top of the stack at each sample.
+  #[serde(default)]
+  pub samples: Vec<u32>,
+  /// The time between each sample and the previous one, or the start of the
+  /// profile.
+  #[serde(default)]
+  pub time_deltas: Vec<i64>,
+}
+
+impl CpuProfile {
+  /// Serializes the profile to the JSON of a `.cpuprofile` file, which can be
+  /// loaded by the performance panel of Chrome's DevTools.
+  pub fn to_chrome_json(&self) -> String {
+    serde_json::to_string(self).unwrap()
+  }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CpuProfileNode {
+  pub id: u32,
+  pub call_frame: CallFrame,
+  /// The number of samples in which this node was on top of the stack.
+  #[serde(default)]
+  pub hit_count: u32,
+  #[serde(default)]
+  pub children: Vec<u32>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub deopt_reason: Option<String>,
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  pub position_ticks: Vec<PositionTick>,
+}
+
+/// The function of a node. Lines and columns are 0-based.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CallFrame {
+  pub function_name: String,
+  pub script_id: String,
+  pub url: String,
+  pub line_number: i64,
+  pub column_number: i64,
+}
+
+/// The number of samples taken at a 1-based line of a node's function.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PositionTick {
+  pub line: u32,
+  pub ticks: u32,
+}
+
+/// The inspector client and session that profile the isolate. It must be
+/// dropped before the isolate.
+pub(crate) struct CpuProfiler {
+  v8_inspector_client: v8::inspector::V8InspectorClientBase,
+  v8_channel: v8::inspector::ChannelBase,
+  // The session is declared first so that it is dropped before the inspector.
+  v8_session: Option<v8::UniqueRef<v8::inspector::V8InspectorSession>>,
+  v8_inspector: Option<v8::UniqueRef<v8::inspector::V8Inspector>>,
+  next_message_id: i32,
+  response: Option<Value>,
+  title: String,
+}
+
+impl v8::inspector::V8InspectorClientImpl for CpuProfiler {
+  fn base(&self) -> &v8::inspector::V8InspectorClientBase {
+    &self.v8_inspector_client
+  }
+
+  fn base_mut(&mut self) -> &mut v8::inspector::V8InspectorClientBase {
+    &mut self.v8_inspector_client
+  }
+}
+
+impl v8::inspector::ChannelImpl for CpuProfiler {
+  fn base(&self) -> &v8::inspector::ChannelBase {
+    &self.v8_channel
+  }
+
+  fn base_mut(&mut self) -> &mut v8::inspector::ChannelBase {
+    &mut self.v8_channel
+  }
+
+  fn send_response(
+    &mut self,
+    _call_id: i32,
+    message: v8::UniquePtr<v8::inspector::StringBuffer>,
+  ) {
+    let raw_message = message.unwrap().string().to_string();
+    self.response = Some(serde_json::from_str(&raw_message).unwrap());
+  }
+
+  fn send_notification(
+    &mut self,
+    _message: v8::UniquePtr<v8::inspector::StringBuffer>,
+  ) {
+  }
+
+  fn flush_protocol_notifications(&mut self) {}
+}
+
+impl CpuProfiler {
+  const CONTEXT_GROUP_ID: i32 = 1;
+
+  /// Starts profiling the isolate. The profiler is boxed so that V8 can keep
+  /// pointers to it as the inspector client and channel.
+  pub fn start(
+    scope: &mut v8::HandleScope,
+    context: v8::Local<v8::Context>,
+    title: &str,
+  ) -> Result<Box<Self>, AnyError> {
+    let mut self_ = Box::new(Self {
+      v8_inspector_client: v8::inspector::V8InspectorClientBase::new::<Self>(),
+      v8_channel: v8::inspector::ChannelBase::new::<Self>(),
+      v8_session: None,
+      v8_inspector: None,
+      next_message_id: 0,
+      response: None,
+      title: title.to_string(),
+    });
+    let mut v8_inspector =
+      v8::inspector::V8Inspector::create(scope, &mut *self_);
+    let context_name = v8::inspector::StringView::from(&b"global context"[..]);
+    v8_inspector.context_created(context, Self::CONTEXT_GROUP_ID, context_name);
+    let v8_session = v8_inspector.connect(
+      Self::CONTEXT_GROUP_ID,
+      &mut *self_,
+      v8::inspector::StringView::empty(),
+    );
+    self_.v8_inspector = Some(v8_inspector);
+    self_.v8_session = Some(v8_session);
+
+    self_.post_message("Profiler.enable", json!({}))?;
+    self_.post_message("Profiler.start", json!({}))?;
+    Ok(self_)
+  }
+
+  /// Stops profiling and returns the profile. The profiler can't be started
+  /// again afterwards.
+  pub fn stop(&mut self) -> Result<CpuProfile, AnyError> {
+    let result = self.post_message("Profiler.stop", json!({}))?;
+    self.post_message("Profiler.disable", json!({}))?;
+    let mut profile: CpuProfile = serde_json::from_value(
+      result.get("profile").cloned().unwrap_or_default(),
+    )?;
+    profile.title = self.title.clone();
+    Ok(profile)
+  }
+
+  /// Dispatches a message to the session. V8 answers the messages of the
+  /// `Profiler` domain synchronously.
+  fn post_message(
+    &mut self,
+    method: &str,
+    params: Value,
+  ) -> Result<Value, AnyError> {
+    let id = self.next_message_id;
+    self.next_message_id += 1;
+    let message = json!({
+      "id": id,
+      "method": method,
+      "params": params,
+    });
+    let raw_message = serde_json::to_string(&message).unwrap();
+    let raw_message = v8::inspector::StringView::from(raw_message.as_bytes());
+    self
+      .v8_session
+      .as_mut()
+      .unwrap()
+      .dispatch_protocol_message(raw_message);
+
+    let response = self.response.take().ok_or_else(|| {
+      generic_error(format!("The inspector didn't answer {}", method))
+    })?;
+    if let Some(error) = response.get("error") {
+      return Err(generic_error(error.to_string()));
+    }
+    Ok(response.get("result").cloned().unwrap_or_default())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn cpu_profile_to_chrome_json() {
+    let profile = CpuProfile {
+      title: "test".to_string(),
+      nodes: vec![CpuProfileNode {
+        id: 1,
+        call_frame: CallFrame {
+          function_name: "(root)".to_string(),
+          script_id: "0".to_string(),
+          url: "".to_string(),
+          line_number: -1,
+          column_number: -1,
+        },
+        hit_count: 1,
+        children: vec![],
+        deopt_reason: None,
+        position_ticks: vec![],
+      }],
+      start_time: 100,
+      end_time: 200,
+      samples: vec![1],
+      time_deltas: vec![50],
+    };
+    let actual: Value =
+      serde_json::from_str(&profile.to_chrome_json()).unwrap();
+    assert_eq!(
+      actual,
+      json!({
+        "nodes": [{
+          "id": 1,
+          "callFrame": {
+            "functionName": "(root)",
+            "scriptId": "0",
+            "url": "",
+            "lineNumber": -1,
+            "columnNumber": -1,
+          },
+          "hitCount": 1,
+          "children": [],
+        }],
+        "startTime": 100,
+        "endTime": 200,
+        "samples": [1],
+        "timeDeltas": [50],
+      })
+    );
+    let parsed: CpuProfile = serde_json::from_value(actual).unwrap();
+    assert_eq!(
+      parsed,
+      CpuProfile {
+        title: "".to_string(),
+        ..profile
+      }
+    );
+  }
+}
@@ -97,6 +97,33 @@ impl ResourceTable {
     rid
   }
 
+  /// Puts a resource under an existing `rid`, returning the resource that was
+  /// stored there before, if any. Note that the old resource's `close()` method
+  /// is *not* called.
+  ///
+  /// This allows a resource to be upgraded in place, e.g. by `take()`ing a TCP
+  /// stream and storing a TLS stream wrapping it under the same `rid`, so that
+  /// references held by JavaScript keep working.
+  ///
+  /// Panics if `rid` was never handed out by this resource table.
+  pub fn replace<T: Resource>(
+    &mut self,
+    rid: ResourceId,
+    resource: T,
+  ) -> Option<Rc<dyn Resource>> {
+    self.replace_rc(rid, Rc::new(resource))
+  }
+
+  /// Puts a `Rc`-wrapped resource under an existing `rid`, like `replace()`.
+  pub fn replace_rc<T: Resource>(
+    &mut self,
+    rid: ResourceId,
+    resource: Rc<T>,
+  ) -> Option<Rc<dyn Resource>> {
+    assert!(rid < self.next_rid, "rid {} was never allocated", rid);
+    self.index.insert(rid, resource as Rc<dyn Resource>)
+  }
+
   /// Returns true if any resource with the given `rid` exists.
   pub fn has(&self, rid: ResourceId) -> bool {
     self.index.contains_key(&rid)
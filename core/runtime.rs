@@ -2,26 +2,47 @@
 
 use rusty_v8 as v8;
 
+use crate::abort_signal::AbortSignalFactory;
 use crate::bindings;
+use crate::console::ConsoleHandler;
 use crate::error::attach_handle_to_error;
 use crate::error::generic_error;
+use crate::error::type_error;
 use crate::error::AnyError;
 use crate::error::ErrWithV8Handle;
+use crate::error::InfiniteLoop;
 use crate::error::JsError;
+use crate::error::ModuleEvaluationTimeout;
+use crate::error::SnapshotOpMismatch;
 use crate::futures::FutureExt;
+use crate::import_map::ImportMap;
+use crate::import_map::ImportMapModuleLoader;
+use crate::js_object::JsObject;
+use crate::js_object::TypedJsHandle;
 use crate::module_specifier::ModuleSpecifier;
 use crate::modules::LoadState;
 use crate::modules::ModuleId;
 use crate::modules::ModuleLoadId;
 use crate::modules::ModuleLoader;
 use crate::modules::ModuleSource;
+use crate::modules::ModuleStatus;
 use crate::modules::Modules;
 use crate::modules::NoopModuleLoader;
 use crate::modules::PrepareLoadFuture;
 use crate::modules::RecursiveModuleLoad;
+use crate::object_template::ObjectTemplateBuilder;
 use crate::ops::*;
+use crate::performance::PerformanceEntry;
+use crate::performance::PerformanceState;
+#[cfg(feature = "profiling")]
+use crate::profiler::CpuProfile;
+#[cfg(feature = "profiling")]
+use crate::profiler::CpuProfiler;
 use crate::shared_queue::SharedQueue;
 use crate::shared_queue::RECOMMENDED_SIZE;
+use crate::wasm::WasmInstantiateCb;
+use crate::worker::WorkerHandle;
+use crate::worker::WorkerOptions;
 use crate::BufVec;
 use crate::OpState;
 use futures::channel::mpsc;
@@ -31,9 +52,13 @@ use futures::stream::StreamExt;
 use futures::stream::StreamFuture;
 use futures::task::AtomicWaker;
 use futures::Future;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
 use std::any::Any;
 use std::cell::Cell;
 use std::cell::RefCell;
+use std::cmp::Ordering as CmpOrdering;
+use std::collections::BinaryHeap;
 use std::collections::HashMap;
 use std::convert::TryFrom;
 use std::ffi::c_void;
@@ -41,11 +66,53 @@ use std::mem::forget;
 use std::option::Option;
 use std::pin::Pin;
 use std::rc::Rc;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
 use std::sync::Once;
 use std::task::Context;
 use std::task::Poll;
+use std::thread;
+use std::time::Duration;
+
+type PendingOpFuture = Pin<Box<dyn Future<Output = (u8, OpId, Box<[u8]>)>>>;
+
+/// The result of an async op that is ready to be dispatched to JS. In a
+/// `BinaryHeap`, the greatest result, which is dispatched first, is the one
+/// with the lowest priority value that completed first.
+pub(crate) struct PendingOpResult {
+  priority: u8,
+  /// The position of the result in the order the ops completed.
+  seq: u64,
+  op_id: OpId,
+  buf: Box<[u8]>,
+}
+
+impl Ord for PendingOpResult {
+  fn cmp(&self, other: &Self) -> CmpOrdering {
+    (other.priority, other.seq).cmp(&(self.priority, self.seq))
+  }
+}
+
+impl PartialOrd for PendingOpResult {
+  fn partial_cmp(&self, other: &Self) -> Option<CmpOrdering> {
+    Some(self.cmp(other))
+  }
+}
+
+impl PartialEq for PendingOpResult {
+  fn eq(&self, other: &Self) -> bool {
+    self.cmp(other) == CmpOrdering::Equal
+  }
+}
 
-type PendingOpFuture = Pin<Box<dyn Future<Output = (OpId, Box<[u8]>)>>>;
+impl Eq for PendingOpResult {}
+
+/// A function registered with `JsRuntime::register_native_function()`, with
+/// its argument and result converted to and from JSON.
+pub(crate) type NativeFunction =
+  dyn Fn(serde_json::Value) -> Result<serde_json::Value, AnyError>;
 
 pub enum Snapshot {
   Static(&'static [u8]),
@@ -53,11 +120,84 @@ pub enum Snapshot {
   Boxed(Box<[u8]>),
 }
 
+/// How many times draining the microtask queue may dispatch new work in
+/// `JsRuntime::run_event_loop_until_idle()` before it gives up with
+/// `InfiniteLoop`.
+const MAX_IDLE_CYCLES: usize = 10_000;
+
+/// Index of the snapshot isolate data that describes the modules stored by
+/// `JsRuntime::snapshot_with_partial_evaluation()`. The modules themselves
+/// follow it, in the same order.
+const SNAPSHOTTED_MODULES_INDEX: usize = 0;
+/// The index of the context data of the startup snapshot that holds the
+/// names of the ops that were registered when it was taken.
+const SNAPSHOT_OPS_INDEX: usize = 0;
+
+/// A script compiled by `JsRuntime::compile_expression()`. Running it with
+/// `JsRuntime::run_compiled_script()` skips parsing and compiling it again.
+pub struct CompiledScript {
+  script: v8::Global<v8::UnboundScript>,
+}
+
 pub type JsErrorCreateFn = dyn Fn(JsError) -> AnyError;
 
+/// See `JsRuntime::set_global_error_handler()`.
+pub type GlobalErrorHandler = dyn Fn(JsError);
+
+/// The arguments of `globalThis.onerror`, see
+/// `JsRuntime::set_error_event_callback()`.
+pub struct ErrorEvent {
+  pub message: String,
+  pub filename: String,
+  pub lineno: u32,
+  pub colno: u32,
+  /// The thrown value.
+  pub error: v8::Global<v8::Value>,
+}
+
+/// See `JsRuntime::set_error_event_callback()`. Returning `true` suppresses
+/// the default handling of the error.
+pub type ErrorEventCallback = dyn Fn(ErrorEvent) -> bool;
+
+/// See `JsRuntime::register_promise_hook()`.
+#[cfg(feature = "debug_hooks")]
+pub type PromiseHookFn = dyn Fn(v8::PromiseHookType, v8::Local<v8::Promise>);
+
 pub type GetErrorClassFn =
   &'static dyn for<'e> Fn(&'e AnyError) -> &'static str;
 
+/// Terminates the execution of a `JsRuntime` from any thread; see
+/// `JsRuntime::cancellation_token()`. Clones share their cancelled state.
+#[derive(Clone, Debug)]
+pub struct CancellationToken {
+  cancelled: Arc<AtomicBool>,
+  isolate_handle: v8::IsolateHandle,
+}
+
+impl CancellationToken {
+  pub fn new(isolate_handle: v8::IsolateHandle) -> Self {
+    Self {
+      cancelled: Arc::new(AtomicBool::new(false)),
+      isolate_handle,
+    }
+  }
+
+  /// Terminates the JavaScript that is running in the isolate, if any.
+  /// Only the first call terminates execution; it returns whether this was
+  /// that call.
+  pub fn cancel(&self) -> bool {
+    let already_cancelled = self.cancelled.swap(true, Ordering::SeqCst);
+    if !already_cancelled {
+      self.isolate_handle.terminate_execution();
+    }
+    !already_cancelled
+  }
+
+  pub fn is_cancelled(&self) -> bool {
+    self.cancelled.load(Ordering::SeqCst)
+  }
+}
+
 /// Objects that need to live as long as the isolate
 #[derive(Default)]
 struct IsolateAllocations {
@@ -82,6 +222,8 @@ pub struct JsRuntime {
   snapshot_creator: Option<v8::SnapshotCreator>,
   has_snapshotted: bool,
   allocations: IsolateAllocations,
+  #[cfg(feature = "profiling")]
+  profiler: Option<Box<CpuProfiler>>,
 }
 
 struct DynImportModEvaluate {
@@ -95,6 +237,43 @@ struct ModEvaluate {
   sender: mpsc::Sender<Result<(), AnyError>>,
 }
 
+/// Terminates the execution of the isolate from another thread unless it is
+/// stopped within `timeout`, see `JsRuntime::set_module_evaluation_timeout()`.
+struct EvaluationWatchdog {
+  // Dropping the sender wakes up the thread.
+  sender: std::sync::mpsc::Sender<()>,
+  thread: thread::JoinHandle<bool>,
+}
+
+impl EvaluationWatchdog {
+  fn start(isolate_handle: v8::IsolateHandle, timeout: Duration) -> Self {
+    let (sender, receiver) = std::sync::mpsc::channel::<()>();
+    let thread = thread::spawn(move || match receiver.recv_timeout(timeout) {
+      Err(std::sync::mpsc::RecvTimeoutError::Timeout) => {
+        isolate_handle.terminate_execution();
+        true
+      }
+      _ => false,
+    });
+    Self { sender, thread }
+  }
+
+  /// Returns whether the execution was terminated.
+  fn stop(self) -> bool {
+    drop(self.sender);
+    self.thread.join().unwrap()
+  }
+}
+
+/// See `JsRuntime::install_module_mock()`.
+#[cfg(debug_assertions)]
+struct ModuleMock {
+  source: String,
+  /// The module the specifier pointed to before the mock was compiled, if
+  /// any.
+  original_id: Option<ModuleId>,
+}
+
 /// Internal state for JsRuntime which is stored in one of v8::Isolate's
 /// embedder slots.
 pub(crate) struct JsRuntimeState {
@@ -106,10 +285,33 @@ pub(crate) struct JsRuntimeState {
     HashMap<v8::Global<v8::Promise>, v8::Global<v8::Value>>,
   pending_dyn_mod_evaluate: HashMap<ModuleLoadId, DynImportModEvaluate>,
   pending_mod_evaluate: Option<ModEvaluate>,
+  /// The promises returned by `v8::Module::evaluate()`, kept for
+  /// `JsRuntime::get_top_level_await_result()`.
+  module_evaluation_promises: HashMap<ModuleId, v8::Global<v8::Promise>>,
   pub(crate) js_error_create_fn: Rc<JsErrorCreateFn>,
+  global_error_handler: Option<Rc<GlobalErrorHandler>>,
+  pub(crate) error_event_callback: Option<Rc<ErrorEventCallback>>,
+  pub(crate) v8_ops: HashMap<String, Rc<V8OpFn>>,
+  pub(crate) native_functions: Vec<Rc<NativeFunction>>,
+  /// The intrinsic `Object.defineProperty`, captured before any other JS
+  /// runs, so replacing the global one can't intercept the accessors that
+  /// `ObjectTemplateBuilder` defines.
+  pub(crate) object_define_property: Option<v8::Global<v8::Function>>,
+  /// The names of the ops, indexed by op id, that were registered when the
+  /// startup snapshot was taken, if the runtime was created from one.
+  snapshot_ops: Option<Vec<String>>,
+  module_evaluation_timeout: Option<Duration>,
+  #[cfg(feature = "debug_hooks")]
+  pub(crate) promise_hooks: Vec<Rc<PromiseHookFn>>,
+  #[cfg(debug_assertions)]
+  module_mocks: HashMap<String, ModuleMock>,
   pub(crate) shared: SharedQueue,
   pub(crate) pending_ops: FuturesUnordered<PendingOpFuture>,
   pub(crate) pending_unref_ops: FuturesUnordered<PendingOpFuture>,
+  /// Results of async ops that didn't fit in the shared queue in the turn of
+  /// the event loop they completed in.
+  pending_op_results: BinaryHeap<PendingOpResult>,
+  next_op_result_seq: u64,
   pub(crate) have_unpolled_ops: Cell<bool>,
   //pub(crate) op_table: OpTable,
   pub(crate) op_state: Rc<RefCell<OpState>>,
@@ -124,6 +326,9 @@ pub(crate) struct JsRuntimeState {
 
 impl Drop for JsRuntime {
   fn drop(&mut self) {
+    // The profiler's inspector must be dropped before the isolate.
+    #[cfg(feature = "profiling")]
+    drop(self.profiler.take());
     if let Some(creator) = self.snapshot_creator.take() {
       // TODO(ry): in rusty_v8, `SnapShotCreator::get_owned_isolate()` returns
       // a `struct OwnedIsolate` which is not actually owned, hence the need
@@ -164,6 +369,73 @@ pub unsafe fn v8_init() {
   v8::V8::set_flags_from_command_line(argv);
 }
 
+/// The stack size set by `set_v8_stack_size()`, or 0, combined with
+/// `V8_INITIALIZED` once the first `JsRuntime` initialized V8. Keeping both in
+/// one atomic makes setting the size and checking that V8 isn't initialized
+/// yet a single compare-and-swap.
+static V8_STACK_SIZE: AtomicUsize = AtomicUsize::new(0);
+
+const V8_INITIALIZED: usize = !(usize::MAX >> 1);
+
+/// Limits how much of a thread's stack JavaScript may use, in bytes, in
+/// every runtime of the process. Recursing deeper throws a `RangeError:
+/// Maximum call stack size exceeded`, which JS can catch and which otherwise
+/// surfaces as the `JsError` returned by `execute()` or `mod_evaluate()`.
+///
+/// V8 only knows the stack size as its process-wide `--stack-size` flag, in
+/// KiB, so the size must be at least 1024 bytes, and this must be called
+/// before the first `JsRuntime` is created, and only once.
+///
+/// The limit must stay below the OS stack of the threads runtimes run on,
+/// typically 8 MiB for the main thread and 2 MiB for threads spawned by
+/// Rust. Otherwise deep recursion overflows the real stack and crashes the
+/// process instead of throwing. `JsRuntime::create_worker()` sizes worker
+/// threads to match.
+///
+/// The `RangeError` is thrown with the stack nearly exhausted. A `catch`
+/// block that calls back into deep code may overflow again, so keep such
+/// handlers shallow, or let the error propagate to Rust.
+pub fn set_v8_stack_size(size: usize) -> Result<(), AnyError> {
+  if size < 1024 || size & V8_INITIALIZED != 0 {
+    return Err(generic_error(format!(
+      "Invalid V8 stack size of {} bytes, it must be at least 1024 bytes",
+      size
+    )));
+  }
+  match V8_STACK_SIZE.compare_exchange(
+    0,
+    size,
+    Ordering::SeqCst,
+    Ordering::SeqCst,
+  ) {
+    Ok(_) => Ok(()),
+    Err(current) if current & V8_INITIALIZED != 0 => Err(generic_error(
+      "The V8 stack size must be set before the first JsRuntime is created",
+    )),
+    Err(current) => Err(generic_error(format!(
+      "The V8 stack size is already set to {} bytes",
+      current
+    ))),
+  }
+}
+
+/// Marks V8 as initialized, after which the stack size can't change, and
+/// sets the flag of the stack size, if any.
+fn init_v8_stack_size() {
+  let size = V8_STACK_SIZE.fetch_or(V8_INITIALIZED, Ordering::SeqCst);
+  if size != 0 {
+    v8::V8::set_flags_from_string(&format!("--stack-size={}", size / 1024));
+  }
+}
+
+/// Returns the stack size set by `set_v8_stack_size()`, if any.
+pub(crate) fn v8_stack_size() -> Option<usize> {
+  match V8_STACK_SIZE.load(Ordering::SeqCst) & !V8_INITIALIZED {
+    0 => None,
+    size => Some(size),
+  }
+}
+
 #[derive(Default)]
 pub struct RuntimeOptions {
   /// Allows a callback to be set whenever a V8 exception is made. This allows
@@ -194,6 +466,26 @@ pub struct RuntimeOptions {
 
   /// Isolate creation parameters.
   pub create_params: Option<v8::CreateParams>,
+
+  /// Called with the bytes of every WebAssembly module before it is compiled,
+  /// see `JsRuntime::set_wasm_instantiate_callback()`.
+  ///
+  /// Currently can't be used with `will_snapshot`.
+  pub wasm_instantiate_cb: Option<Box<WasmInstantiateCb>>,
+
+  /// Receives the output of the JS `console`, see
+  /// `JsRuntime::set_console_handler()`.
+  ///
+  /// Currently can't be used with `will_snapshot`.
+  pub console_handler: Option<Box<ConsoleHandler>>,
+
+  /// Maximum size in bytes of a serialized JSON op result, see
+  /// `JsRuntime::set_max_op_response_size()`.
+  pub max_op_response_size: Option<usize>,
+
+  /// Limits how long the synchronous part of a module's evaluation may run,
+  /// see `JsRuntime::set_module_evaluation_timeout()`.
+  pub module_evaluation_timeout: Option<Duration>,
 }
 
 impl JsRuntime {
@@ -209,6 +501,7 @@ impl JsRuntime {
       .is_ok());
 
       unsafe { v8_init() };
+      init_v8_stack_size();
     });
 
     let has_startup_snapshot = options.startup_snapshot.is_some();
@@ -273,12 +566,25 @@ impl JsRuntime {
     if let Some(get_error_class_fn) = options.get_error_class_fn {
       op_state.get_error_class_fn = get_error_class_fn;
     }
+    op_state.max_op_response_size = options.max_op_response_size;
 
     isolate.set_slot(Rc::new(RefCell::new(JsRuntimeState {
       global_context: Some(global_context),
       pending_promise_exceptions: HashMap::new(),
       pending_dyn_mod_evaluate: HashMap::new(),
       pending_mod_evaluate: None,
+      module_evaluation_promises: HashMap::new(),
+      global_error_handler: None,
+      error_event_callback: None,
+      v8_ops: HashMap::new(),
+      native_functions: vec![],
+      object_define_property: None,
+      snapshot_ops: None,
+      module_evaluation_timeout: options.module_evaluation_timeout,
+      #[cfg(feature = "debug_hooks")]
+      promise_hooks: vec![],
+      #[cfg(debug_assertions)]
+      module_mocks: HashMap::new(),
       shared_ab: None,
       js_recv_cb: None,
       js_macrotask_cb: None,
@@ -286,6 +592,8 @@ impl JsRuntime {
       shared: SharedQueue::new(RECOMMENDED_SIZE),
       pending_ops: FuturesUnordered::new(),
       pending_unref_ops: FuturesUnordered::new(),
+      pending_op_results: BinaryHeap::new(),
+      next_op_result_seq: 0,
       op_state: Rc::new(RefCell::new(op_state)),
       have_unpolled_ops: Cell::new(false),
       modules: Modules::new(),
@@ -301,9 +609,16 @@ impl JsRuntime {
       snapshot_creator: maybe_snapshot_creator,
       has_snapshotted: false,
       allocations: IsolateAllocations::default(),
+      #[cfg(feature = "profiling")]
+      profiler: None,
     };
 
-    if !has_startup_snapshot {
+    crate::object_template::init(&mut js_runtime);
+
+    if has_startup_snapshot {
+      js_runtime.restore_snapshotted_modules();
+      js_runtime.restore_snapshot_ops();
+    } else {
       js_runtime.js_init();
     }
 
@@ -311,9 +626,29 @@ impl JsRuntime {
       js_runtime.shared_queue_init();
     }
 
+    if let Some(cb) = options.wasm_instantiate_cb {
+      assert!(!options.will_snapshot);
+      js_runtime.set_wasm_instantiate_callback(cb);
+    }
+
+    if let Some(handler) = options.console_handler {
+      assert!(!options.will_snapshot);
+      js_runtime.set_console_handler(handler);
+    }
+
     js_runtime
   }
 
+  /// Returns the version of V8 that this runtime is built against.
+  pub fn v8_version() -> &'static str {
+    crate::v8_version()
+  }
+
+  /// Returns the version of the `deno_core` crate.
+  pub fn js_runtime_version() -> &'static str {
+    crate::js_runtime_version()
+  }
+
   pub fn global_context(&mut self) -> v8::Global<v8::Context> {
     let state = Self::state(self.v8_isolate());
     let state = state.borrow();
@@ -324,6 +659,18 @@ impl JsRuntime {
     self.v8_isolate.as_mut().unwrap()
   }
 
+  /// Returns a handle that can be sent to other threads to terminate the
+  /// execution of the isolate with `IsolateHandle::terminate_execution()`.
+  pub fn isolate_handle(&mut self) -> v8::IsolateHandle {
+    self.v8_isolate().thread_safe_handle()
+  }
+
+  /// Returns a token that terminates the execution of the isolate when it is
+  /// cancelled. Every call returns a new token.
+  pub fn cancellation_token(&mut self) -> CancellationToken {
+    CancellationToken::new(self.isolate_handle())
+  }
+
   fn setup_isolate(mut isolate: v8::OwnedIsolate) -> v8::OwnedIsolate {
     isolate.set_capture_stack_trace_for_uncaught_exceptions(true, 10);
     isolate.set_promise_reject_callback(bindings::promise_reject_callback);
@@ -374,6 +721,211 @@ impl JsRuntime {
     state.op_state.clone()
   }
 
+  /// Runs `f` with a `v8::HandleScope` that has the runtime's global context
+  /// entered, and returns its result.
+  ///
+  /// This is the recommended way to interact with V8 directly: the scope is
+  /// created in the right context and torn down when `f` returns, so callers
+  /// don't have to get the ordering and nesting of scopes right themselves.
+  pub fn with_scope<R, F>(&mut self, f: F) -> R
+  where
+    F: FnOnce(&mut v8::HandleScope) -> R,
+  {
+    let context = self.global_context();
+    let scope = &mut v8::HandleScope::with_context(self.v8_isolate(), context);
+    f(scope)
+  }
+
+  /// Installs `globalThis.performance` (together with the `Performance`,
+  /// `PerformanceEntry`, `PerformanceMark` and `PerformanceMeasure` classes).
+  /// Times are reported in milliseconds relative to the moment this method is
+  /// called.
+  ///
+  /// This is opt-in, since embedders that provide their own `performance`
+  /// global don't want it overwritten. Calling it again does nothing.
+  pub fn install_performance_api(&mut self) {
+    crate::performance::init(self);
+  }
+
+  /// Registers the `op_perf_*` ops that keep the entry buffer read by
+  /// `get_performance_entries()`, without installing the `performance`
+  /// global, for embedders that implement the global themselves. Calling it
+  /// again, or after `install_performance_api()`, does nothing.
+  pub fn register_performance_ops(&mut self) {
+    crate::performance::init_ops(self);
+  }
+
+  /// Returns the marks and measures recorded through `performance.mark()` and
+  /// `performance.measure()`, in the order they were created, minus the
+  /// cleared and evicted ones.
+  ///
+  /// Returns an empty list if neither `install_performance_api()` nor
+  /// `register_performance_ops()` has been called.
+  pub fn get_performance_entries(&mut self) -> Vec<PerformanceEntry> {
+    let op_state = self.op_state();
+    let op_state = op_state.borrow();
+    op_state
+      .try_borrow::<PerformanceState>()
+      .map(|state| state.entries())
+      .unwrap_or_default()
+  }
+
+  /// Sets a callback that is called with the bytes of every WebAssembly
+  /// module before `WebAssembly.compile()`, `WebAssembly.instantiate()` or
+  /// `new WebAssembly.Module()` compiles it, e.g. to audit or restrict which
+  /// modules are run. If the callback returns an error, the module isn't
+  /// compiled and a `WebAssembly.CompileError` with the error's message is
+  /// thrown (or the returned promise is rejected with it).
+  ///
+  /// Calling this again replaces the previous callback.
+  pub fn set_wasm_instantiate_callback(&mut self, cb: Box<WasmInstantiateCb>) {
+    crate::wasm::set_callback(self, cb);
+  }
+
+  /// Replaces the global `console` with one that passes every message to
+  /// `handler`, so that embedders can route JS output to their own logging.
+  /// The arguments of a call are joined with spaces; objects are formatted
+  /// as JSON where possible.
+  ///
+  /// Calling this again replaces the previous handler.
+  pub fn set_console_handler(&mut self, handler: Box<ConsoleHandler>) {
+    crate::console::set_handler(self, handler);
+  }
+
+  /// Passes exceptions that JS code doesn't catch while the event loop runs
+  /// to `handler` instead of returning them from `poll_event_loop()`, so the
+  /// event loop keeps going. This covers exceptions thrown by op response
+  /// callbacks and macrotasks (e.g. timers) and unhandled promise rejections.
+  /// Exceptions thrown by `execute()` or module evaluation are still returned
+  /// to the caller, and so is termination of the isolate.
+  ///
+  /// Calling this again replaces the previous handler.
+  pub fn set_global_error_handler(&mut self, handler: Box<GlobalErrorHandler>) {
+    let state_rc = Self::state(self.v8_isolate());
+    state_rc.borrow_mut().global_error_handler = Some(handler.into());
+  }
+
+  /// Sets the factory used by `op_abort_signal_new` to create the Rust side
+  /// of an `AbortController` and its `AbortSignal`, e.g. to tie every signal
+  /// to the lifetime of the runtime. By default each pair shares a new
+  /// `CancelHandle`; see `new_abort_signal()`.
+  ///
+  /// The ops are not registered by this function: embedders register
+  /// `op_abort_signal_new` and `op_abort` like `op_close`.
+  pub fn set_abort_signal_factory(&mut self, factory: Box<AbortSignalFactory>) {
+    self.op_state().borrow_mut().put(factory);
+  }
+
+  /// Installs `globalThis.onerror(message, source, lineno, colno, error)` as
+  /// in browsers, calling `callback` with its arguments. Uncaught exceptions
+  /// that would go to `set_global_error_handler()` (not unhandled promise
+  /// rejections) are first dispatched to `globalThis.onerror`, whether it is
+  /// this function or one assigned by JS; if it returns `true`, the error is
+  /// considered handled and isn't reported otherwise.
+  ///
+  /// Calling this again replaces the previous callback.
+  pub fn set_error_event_callback(
+    &mut self,
+    callback: Box<ErrorEventCallback>,
+  ) {
+    let state_rc = Self::state(self.v8_isolate());
+    state_rc.borrow_mut().error_event_callback = Some(callback.into());
+    self.with_scope(|scope| {
+      let global = scope.get_current_context().global(scope);
+      bindings::set_func(scope, global, "onerror", bindings::onerror);
+    });
+  }
+
+  /// Calls `hook` whenever a promise is created (`Init`) or resolved
+  /// (`Resolve`), and before and after each reaction job of a promise runs
+  /// (`Before` and `After`), e.g. to track async stack traces or promises
+  /// that never settle. Hooks are called in the order they were registered;
+  /// registering another one doesn't replace the previous ones.
+  ///
+  /// Hooks slow down every promise operation, so this requires the
+  /// `debug_hooks` feature.
+  #[cfg(feature = "debug_hooks")]
+  pub fn register_promise_hook(&mut self, hook: Box<PromiseHookFn>) {
+    let state_rc = Self::state(self.v8_isolate());
+    let is_first = {
+      let mut state = state_rc.borrow_mut();
+      state.promise_hooks.push(hook.into());
+      state.promise_hooks.len() == 1
+    };
+    if is_first {
+      self.v8_isolate().set_promise_hook(bindings::promise_hook);
+    }
+  }
+
+  /// Starts sampling the JavaScript run by the runtime with V8's CPU
+  /// profiler, until `profiler_stop()` is called. Only one profile can be
+  /// recorded at a time, and the profiler can't be combined with another
+  /// inspector attached to the isolate.
+  ///
+  /// This requires the `profiling` feature.
+  #[cfg(feature = "profiling")]
+  pub fn profiler_start(&mut self, title: &str) -> Result<(), AnyError> {
+    if self.profiler.is_some() {
+      return Err(generic_error("The CPU profiler is already started"));
+    }
+    let profiler = self.with_scope(|scope| {
+      let context = scope.get_current_context();
+      CpuProfiler::start(scope, context, title)
+    })?;
+    self.profiler = Some(profiler);
+    Ok(())
+  }
+
+  /// Stops the profiler started by `profiler_start()` and returns the
+  /// profile; see `CpuProfile::to_chrome_json()`.
+  ///
+  /// This requires the `profiling` feature.
+  #[cfg(feature = "profiling")]
+  pub fn profiler_stop(&mut self) -> Result<CpuProfile, AnyError> {
+    let mut profiler = self
+      .profiler
+      .take()
+      .ok_or_else(|| generic_error("The CPU profiler isn't started"))?;
+    profiler.stop()
+  }
+
+  /// Caps the size of the serialized results of ops created with
+  /// `json_op_sync()` and `json_op_async()`, in bytes. An op whose result
+  /// would be larger fails with a `RangeError` instead, before the whole
+  /// response is buffered. `None` removes the cap.
+  pub fn set_max_op_response_size(&mut self, size: Option<usize>) {
+    self.op_state().borrow_mut().max_op_response_size = size;
+  }
+
+  /// Limits how long `mod_evaluate()` lets module-level code run before it
+  /// returns control to Rust. Execution is terminated once the timeout
+  /// elapses, and `mod_evaluate()` fails with `ModuleEvaluationTimeout`.
+  /// The runtime can still be used afterwards.
+  ///
+  /// Only the synchronous part of the evaluation is covered. Once a module
+  /// awaits at the top level, the rest of its evaluation runs on the event
+  /// loop and isn't limited. `None` removes the limit.
+  pub fn set_module_evaluation_timeout(&mut self, timeout: Option<Duration>) {
+    Self::state(self.v8_isolate())
+      .borrow_mut()
+      .module_evaluation_timeout = timeout;
+  }
+
+  /// Spawns a worker: an OS thread with its own `JsRuntime` that evaluates
+  /// `options.source` as the module `specifier`, then runs its event loop
+  /// until it has nothing left to do.
+  ///
+  /// The worker sees `postMessage()` and `onmessage` globals to exchange JSON
+  /// messages with the returned handle. The event loop is driven by
+  /// `futures::executor::block_on()`, so ops registered by `options.init`
+  /// can't rely on a Tokio runtime being present.
+  pub fn create_worker(
+    specifier: &str,
+    options: WorkerOptions,
+  ) -> Result<WorkerHandle, AnyError> {
+    crate::worker::create(specifier, options)
+  }
+
   /// Executes traditional JavaScript code (traditional = not ES modules)
   ///
   /// The execution takes place on the current global context, so it is possible
@@ -387,32 +939,151 @@ impl JsRuntime {
     js_filename: &str,
     js_source: &str,
   ) -> Result<(), AnyError> {
-    let context = self.global_context();
-
-    let scope = &mut v8::HandleScope::with_context(self.v8_isolate(), context);
+    self.with_scope(|scope| {
+      let source = v8::String::new(scope, js_source).unwrap();
+      let name = v8::String::new(scope, js_filename).unwrap();
+      let origin = bindings::script_origin(scope, name);
+
+      let tc_scope = &mut v8::TryCatch::new(scope);
+
+      let script = match v8::Script::compile(tc_scope, source, Some(&origin)) {
+        Some(script) => script,
+        None => {
+          let exception = tc_scope.exception().unwrap();
+          return exception_to_err_result(tc_scope, exception, false);
+        }
+      };
 
-    let source = v8::String::new(scope, js_source).unwrap();
-    let name = v8::String::new(scope, js_filename).unwrap();
-    let origin = bindings::script_origin(scope, name);
+      match script.run(tc_scope) {
+        Some(_) => Ok(()),
+        None => {
+          assert!(tc_scope.has_caught());
+          let exception = tc_scope.exception().unwrap();
+          exception_to_err_result(tc_scope, exception, false)
+        }
+      }
+    })
+  }
 
-    let tc_scope = &mut v8::TryCatch::new(scope);
+  /// Compiles `code`, a traditional script such as a single expression,
+  /// without running it. `url` is the script name used in stack traces.
+  ///
+  /// `AnyError` can be downcast to a type that exposes additional information
+  /// about the V8 exception. By default this type is `JsError`, however it may
+  /// be a different type if `RuntimeOptions::js_error_create_fn` has been set.
+  pub fn compile_expression(
+    &mut self,
+    code: &str,
+    url: &str,
+  ) -> Result<CompiledScript, AnyError> {
+    self.with_scope(|scope| {
+      let source = v8::String::new(scope, code).unwrap();
+      let name = v8::String::new(scope, url).unwrap();
+      let origin = bindings::script_origin(scope, name);
+
+      let tc_scope = &mut v8::TryCatch::new(scope);
+
+      match v8::Script::compile(tc_scope, source, Some(&origin)) {
+        Some(script) => {
+          let script = script.get_unbound_script(tc_scope);
+          Ok(CompiledScript {
+            script: v8::Global::new(tc_scope, script),
+          })
+        }
+        None => {
+          let exception = tc_scope.exception().unwrap();
+          exception_to_err_result(tc_scope, exception, false)
+        }
+      }
+    })
+  }
 
-    let script = match v8::Script::compile(tc_scope, source, Some(&origin)) {
-      Some(script) => script,
-      None => {
-        let exception = tc_scope.exception().unwrap();
-        return exception_to_err_result(tc_scope, exception, false);
+  /// Runs a script compiled by `compile_expression()` on the global context
+  /// and returns its completion value. The script can be run any number of
+  /// times.
+  ///
+  /// `AnyError` can be downcast to a type that exposes additional information
+  /// about the V8 exception. By default this type is `JsError`, however it may
+  /// be a different type if `RuntimeOptions::js_error_create_fn` has been set.
+  pub fn run_compiled_script(
+    &mut self,
+    script: &CompiledScript,
+  ) -> Result<v8::Global<v8::Value>, AnyError> {
+    self.with_scope(|scope| {
+      let tc_scope = &mut v8::TryCatch::new(scope);
+
+      let script = v8::Local::new(tc_scope, &script.script)
+        .bind_to_current_context(tc_scope);
+      match script.run(tc_scope) {
+        Some(value) => Ok(v8::Global::new(tc_scope, value)),
+        None => {
+          assert!(tc_scope.has_caught());
+          let exception = tc_scope.exception().unwrap();
+          exception_to_err_result(tc_scope, exception, false)
+        }
       }
-    };
+    })
+  }
 
-    match script.run(tc_scope) {
-      Some(_) => Ok(()),
-      None => {
-        assert!(tc_scope.has_caught());
-        let exception = tc_scope.exception().unwrap();
-        exception_to_err_result(tc_scope, exception, false)
+  /// Evaluates `expr`, a single expression, with `locals` available to it as
+  /// local variables, and returns its value. The expression is wrapped in an
+  /// arrow function taking the locals as parameters, so they don't leak into
+  /// the global scope. The locals and the result are converted through JSON;
+  /// `undefined` results are converted from `null`.
+  ///
+  /// `AnyError` can be downcast to a type that exposes additional information
+  /// about the V8 exception. By default this type is `JsError`, however it may
+  /// be a different type if `RuntimeOptions::js_error_create_fn` has been set.
+  pub fn eval_expression_with_locals<T: DeserializeOwned>(
+    &mut self,
+    expr: &str,
+    locals: HashMap<&str, serde_json::Value>,
+  ) -> Result<T, AnyError> {
+    let mut locals = locals.into_iter().collect::<Vec<_>>();
+    locals.sort_by(|(a, _), (b, _)| a.cmp(b));
+    for (name, _) in &locals {
+      if !is_valid_identifier(name) {
+        return Err(type_error(format!(
+          "Invalid local variable name: \"{}\"",
+          name
+        )));
       }
     }
+    let names = locals
+      .iter()
+      .map(|(name, _)| *name)
+      .collect::<Vec<_>>()
+      .join(", ");
+    let values = locals
+      .iter()
+      .map(|(_, value)| value.to_string())
+      .collect::<Vec<_>>()
+      .join(", ");
+    // The expression is put on its own lines so that a trailing comment
+    // can't swallow the rest of the wrapper.
+    let code = format!(
+      "JSON.stringify((({}) => (\n{}\n))({}))",
+      names, expr, values
+    );
+
+    let script =
+      self.compile_expression(&code, "eval_expression_with_locals")?;
+    let value = self.run_compiled_script(&script)?;
+    let json = self.with_scope(|scope| {
+      let value = v8::Local::new(scope, value);
+      if value.is_string() {
+        Some(value.to_rust_string_lossy(scope))
+      } else {
+        None
+      }
+    });
+    let value = match json {
+      Some(json) => serde_json::from_str(&json)?,
+      None => serde_json::Value::Null,
+    };
+    serde_json::from_value(value).map_err(|err| {
+      type_error(format!("Invalid result of expression: {}", err))
+    })
   }
 
   /// Takes a snapshot. The isolate should have been created with will_snapshot
@@ -425,11 +1096,29 @@ impl JsRuntime {
     assert!(self.snapshot_creator.is_some());
     let state = Self::state(self.v8_isolate());
 
+    // The op names are kept for snapshot_includes_ops_check().
+    let op_names = state.borrow().op_state.borrow().op_table.op_names();
+    {
+      let global_context = self.global_context();
+      let isolate = self.v8_isolate.as_mut().unwrap();
+      let creator = self.snapshot_creator.as_mut().unwrap();
+      let scope = &mut v8::HandleScope::with_context(isolate, &global_context);
+      let op_names: Vec<v8::Local<v8::Value>> = op_names
+        .iter()
+        .map(|name| v8::String::new(scope, name).unwrap().into())
+        .collect();
+      let op_names = v8::Array::new_with_elements(scope, &op_names);
+      let context = v8::Local::new(scope, &global_context);
+      let index = creator.add_context_data(context, op_names);
+      assert_eq!(index, SNAPSHOT_OPS_INDEX);
+    }
+
     // Note: create_blob() method must not be called from within a HandleScope.
     // TODO(piscisaureus): The rusty_v8 type system should enforce this.
     state.borrow_mut().global_context.take();
 
     std::mem::take(&mut state.borrow_mut().modules);
+    state.borrow_mut().module_evaluation_promises.clear();
 
     let snapshot_creator = self.snapshot_creator.as_mut().unwrap();
     let snapshot = snapshot_creator
@@ -440,6 +1129,191 @@ impl JsRuntime {
     snapshot
   }
 
+  /// Compiles `modules`, given as `(specifier, source)` pairs, and takes a
+  /// snapshot that contains them. The isolate should have been created with
+  /// will_snapshot set to true.
+  ///
+  /// The modules are compiled but neither instantiated nor evaluated, so
+  /// their bytecode is kept in the snapshot while their top-level code runs
+  /// fresh in every runtime created from it. Such a runtime registers them
+  /// under their specifiers on startup; they are evaluated the first time
+  /// another module imports them. The modules may only import each other.
+  ///
+  /// Fails, before the snapshot is taken, if a module doesn't compile or
+  /// imports a module outside of `modules`.
+  pub fn snapshot_with_partial_evaluation(
+    &mut self,
+    modules: Vec<(String, String)>,
+  ) -> Result<Box<[u8]>, AnyError> {
+    assert!(self.snapshot_creator.is_some());
+    let state_rc = Self::state(self.v8_isolate());
+
+    let mut ids = vec![];
+    for (specifier, source) in &modules {
+      let id = self.mod_new(false, specifier, source).map_err(|err| {
+        generic_error(format!("Failed to compile {}: {}", specifier, err))
+      })?;
+      ids.push(id);
+    }
+
+    let mut entries: Vec<(
+      String,
+      Vec<ModuleSpecifier>,
+      v8::Global<v8::Module>,
+    )> = vec![];
+    {
+      let state = state_rc.borrow();
+      for id in ids {
+        let handle = state.modules.get_handle(id).unwrap();
+        let info = state.modules.get_info(&handle).unwrap();
+        if let Some(import) = info
+          .import_specifiers
+          .iter()
+          .find(|import| !state.modules.is_registered(import))
+        {
+          return Err(generic_error(format!(
+            "{} imports {}, which is not part of the snapshot",
+            info.name, import
+          )));
+        }
+        entries.push((
+          info.name.clone(),
+          info.import_specifiers.clone(),
+          handle,
+        ));
+      }
+    }
+
+    {
+      let global_context = self.global_context();
+      let isolate = self.v8_isolate.as_mut().unwrap();
+      let creator = self.snapshot_creator.as_mut().unwrap();
+      let scope = &mut v8::HandleScope::with_context(isolate, global_context);
+      // Each module is described by an array holding its specifier followed
+      // by the specifiers it imports.
+      let descriptions: Vec<v8::Local<v8::Value>> = entries
+        .iter()
+        .map(|(name, imports, _)| {
+          let strings: Vec<v8::Local<v8::Value>> =
+            std::iter::once(name.as_str())
+              .chain(imports.iter().map(|import| import.as_str()))
+              .map(|s| v8::String::new(scope, s).unwrap().into())
+              .collect();
+          v8::Array::new_with_elements(scope, &strings).into()
+        })
+        .collect();
+      let descriptions = v8::Array::new_with_elements(scope, &descriptions);
+      let index = creator.add_isolate_data(descriptions);
+      assert_eq!(index, SNAPSHOTTED_MODULES_INDEX);
+      for (_, _, handle) in &entries {
+        creator.add_isolate_data(v8::Local::new(scope, handle));
+      }
+    }
+    drop(entries);
+
+    let snapshot = self.snapshot();
+    Ok(Vec::from(&*snapshot).into_boxed_slice())
+  }
+
+  /// Registers the modules added to the startup snapshot by
+  /// `snapshot_with_partial_evaluation`, if there are any.
+  fn restore_snapshotted_modules(&mut self) {
+    let state_rc = Self::state(self.v8_isolate());
+    self.with_scope(|scope| {
+      let descriptions = match scope
+        .get_isolate_data_from_snapshot_once::<v8::Value>(
+          SNAPSHOTTED_MODULES_INDEX,
+        )
+        .ok()
+        .and_then(|data| v8::Local::<v8::Array>::try_from(data).ok())
+      {
+        Some(descriptions) => descriptions,
+        None => return,
+      };
+
+      for i in 0..descriptions.length() {
+        let description = descriptions.get_index(scope, i).unwrap();
+        let description =
+          v8::Local::<v8::Array>::try_from(description).unwrap();
+        let mut specifiers = (0..description.length()).map(|j| {
+          description
+            .get_index(scope, j)
+            .unwrap()
+            .to_rust_string_lossy(scope)
+        });
+        let name = specifiers.next().unwrap();
+        let imports = specifiers
+          .map(|import| crate::resolve_url(&import).unwrap())
+          .collect();
+        let module = scope
+          .get_isolate_data_from_snapshot_once::<v8::Module>(
+            SNAPSHOTTED_MODULES_INDEX + 1 + i as usize,
+          )
+          .unwrap();
+        let handle = v8::Global::new(scope, module);
+        state_rc
+          .borrow_mut()
+          .modules
+          .register(&name, false, handle, imports);
+      }
+    });
+  }
+
+  /// Reads the names of the ops that were registered when the startup
+  /// snapshot was taken.
+  fn restore_snapshot_ops(&mut self) {
+    let state_rc = Self::state(self.v8_isolate());
+    let op_names = self.with_scope(|scope| {
+      let op_names = scope
+        .get_context_data_from_snapshot_once::<v8::Value>(SNAPSHOT_OPS_INDEX)
+        .ok()
+        .and_then(|data| v8::Local::<v8::Array>::try_from(data).ok())?;
+      Some(
+        (0..op_names.length())
+          .map(|i| {
+            op_names
+              .get_index(scope, i)
+              .unwrap()
+              .to_rust_string_lossy(scope)
+          })
+          .collect(),
+      )
+    });
+    state_rc.borrow_mut().snapshot_ops = op_names;
+  }
+
+  /// Checks that the ops that were registered when the startup snapshot was
+  /// taken are registered with the same ids, which JS code in the snapshot may
+  /// rely on. This should be called once all ops are registered; ops that are
+  /// registered in addition to those are fine.
+  ///
+  /// Fails with `SnapshotOpMismatch` otherwise, e.g. when the snapshot was
+  /// taken with ops that the runtime doesn't register, or registers in a
+  /// different order. Runtimes that weren't created from a snapshot always
+  /// pass.
+  pub fn snapshot_includes_ops_check(&mut self) -> Result<(), AnyError> {
+    let state_rc = Self::state(self.v8_isolate());
+    let state = state_rc.borrow();
+    let expected = match &state.snapshot_ops {
+      Some(expected) => expected,
+      None => return Ok(()),
+    };
+    let actual = state.op_state.borrow().op_table.op_names();
+    if expected.iter().zip(&actual).all(|(a, b)| a == b)
+      && expected.len() <= actual.len()
+    {
+      Ok(())
+    } else {
+      Err(
+        SnapshotOpMismatch {
+          expected: expected.clone(),
+          actual,
+        }
+        .into(),
+      )
+    }
+  }
+
   /// Registers an op that can be called from JavaScript.
   ///
   /// The _op_ mechanism allows to expose Rust functions to the JS runtime,
@@ -461,26 +1335,194 @@ impl JsRuntime {
       .register_op(name, op_fn)
   }
 
-  /// Registers a callback on the isolate when the memory limits are approached.
-  /// Use this to prevent V8 from crashing the process when reaching the limit.
+  /// Registers an op after JavaScript started running, e.g. when a native
+  /// plugin is loaded, and refreshes the op ids returned by
+  /// `Deno.core.ops()`, so that the op can be called by name right away.
   ///
-  /// Calls the closure with the current heap limit and the initial heap limit.
-  /// The return value of the closure is set as the new limit.
-  pub fn add_near_heap_limit_callback<C>(&mut self, cb: C)
-  where
-    C: FnMut(usize, usize) -> usize + 'static,
-  {
-    let boxed_cb = Box::new(RefCell::new(cb));
-    let data = boxed_cb.as_ptr() as *mut c_void;
-
-    let prev = self
-      .allocations
-      .near_heap_limit_callback_data
-      .replace((boxed_cb, near_heap_limit_callback::<C>));
-    if let Some((_, prev_cb)) = prev {
-      self
-        .v8_isolate()
-        .remove_near_heap_limit_callback(prev_cb, 0);
+  /// Unlike `register_op()`, this fails if an op named `name` is already
+  /// registered rather than panicking.
+  pub fn add_op_at_runtime(
+    &mut self,
+    name: &'static str,
+    op: Box<OpFn>,
+  ) -> Result<(), AnyError> {
+    {
+      let state_rc = Self::state(self.v8_isolate());
+      let state = state_rc.borrow();
+      let mut op_state = state.op_state.borrow_mut();
+      if op_state.op_table.contains(name) {
+        return Err(generic_error(format!(
+          "An op named \"{}\" is already registered",
+          name
+        )));
+      }
+      op_state.op_table.register_op(name, op);
+    }
+    self.execute("deno:core/ops.js", "Deno.core.ops();")
+  }
+
+  /// Removes an op registered with `register_op()` or `add_op_at_runtime()`
+  /// and refreshes the op ids returned by `Deno.core.ops()`, so that calling
+  /// it by name throws a `TypeError` from then on. Returns whether the op
+  /// was registered.
+  pub fn remove_op_at_runtime(&mut self, name: &'static str) -> bool {
+    let removed = Self::state(self.v8_isolate())
+      .borrow()
+      .op_state
+      .borrow_mut()
+      .op_table
+      .remove(name);
+    if removed {
+      self
+        .execute("deno:core/ops.js", "Deno.core.ops();")
+        .expect("Failed to refresh the op ids");
+    }
+    removed
+  }
+
+  /// Registers an op created with
+  /// [op_sync_result_v8()](fn.op_sync_result_v8.html). Unlike the ops added
+  /// by `register_op()`, it is called by name with
+  /// `Deno.core.v8OpSync(name, arg)`, which returns the op's result or throws
+  /// its error value.
+  ///
+  /// Registering another op with the same name replaces the previous one.
+  pub fn register_v8_op(&mut self, name: &str, op_fn: Box<V8OpFn>) {
+    Self::state(self.v8_isolate())
+      .borrow_mut()
+      .v8_ops
+      .insert(name.to_string(), op_fn.into());
+  }
+
+  /// Installs `f` as the global function `globalThis[name]`, without an op.
+  /// The first argument of a call is deserialized into `A` by way of
+  /// `JSON.stringify()`, with `undefined` becoming `null`, and the result is
+  /// returned as the equivalent JS value. Errors are thrown as JS errors of
+  /// the class `RuntimeOptions::get_error_class_fn` picks for them, when it
+  /// is a builtin error class, and as plain `Error`s otherwise.
+  ///
+  /// The function is not part of snapshots; register it again after
+  /// creating a runtime from one.
+  pub fn register_native_function<A, R>(
+    &mut self,
+    name: &str,
+    f: impl Fn(A) -> Result<R, AnyError> + 'static,
+  ) where
+    A: DeserializeOwned,
+    R: Serialize,
+  {
+    let index = self.add_native_function(f);
+    self.with_scope(|scope| {
+      let function = bindings::native_function_template(scope, index)
+        .get_function(scope)
+        .unwrap();
+      let key = v8::String::new(scope, name).unwrap();
+      let global = scope.get_current_context().global(scope);
+      global.set(scope, key.into(), function.into());
+    });
+  }
+
+  /// Returns a builder for an object of functions, values and accessors
+  /// backed by Rust, to be installed as a global, e.g. as the namespace of a
+  /// JS API. The functions and accessors convert their arguments and results
+  /// like the functions installed by `register_native_function()`.
+  pub fn create_object_template(&mut self) -> ObjectTemplateBuilder {
+    ObjectTemplateBuilder::new(self)
+  }
+
+  /// Creates a `SharedArrayBuffer` that takes over the allocation of `data`,
+  /// so that Rust-owned memory becomes accessible to JS without being copied.
+  ///
+  /// Rust keeps access to the memory through the buffer's backing store, see
+  /// `v8::SharedArrayBuffer::get_backing_store()`, whose `Cell`s allow for
+  /// the writes of JS. The memory stays alive for as long as either JS holds
+  /// the buffer or Rust holds the backing store.
+  pub fn create_shared_array_buffer(
+    &mut self,
+    data: Box<[u8]>,
+  ) -> v8::Global<v8::SharedArrayBuffer> {
+    let backing_store =
+      v8::SharedArrayBuffer::new_backing_store_from_boxed_slice(data)
+        .make_shared();
+    self.with_scope(|scope| {
+      let buffer =
+        v8::SharedArrayBuffer::with_backing_store(scope, &backing_store);
+      v8::Global::new(scope, buffer)
+    })
+  }
+
+  /// Evaluates `code`, a traditional script, and returns a handle to the
+  /// object it evaluates to, typed as `T` (usually declared with
+  /// `js_object!`) to call its methods from Rust.
+  ///
+  /// `AnyError` can be downcast to a type that exposes additional information
+  /// about the V8 exception. By default this type is `JsError`, however it may
+  /// be a different type if `RuntimeOptions::js_error_create_fn` has been set.
+  pub fn eval_and_bind<T: JsObject>(
+    &mut self,
+    code: &str,
+  ) -> Result<TypedJsHandle<T>, AnyError> {
+    crate::js_object::eval_and_bind(self, code)
+  }
+
+  /// Calls the method `name` of `object` with `args`, a tuple of arguments,
+  /// and returns its result. The arguments and the result are converted
+  /// through JSON; `undefined` results are converted from `null`.
+  pub fn call_method<A, R>(
+    &mut self,
+    object: &v8::Global<v8::Object>,
+    name: &str,
+    args: A,
+  ) -> Result<R, AnyError>
+  where
+    A: Serialize,
+    R: DeserializeOwned,
+  {
+    crate::js_object::call_method(self, object, name, args)
+  }
+
+  /// Adds `f` to `JsRuntimeState::native_functions`, converting its argument
+  /// and result from and to JSON, and returns its index.
+  pub(crate) fn add_native_function<A, R>(
+    &mut self,
+    f: impl Fn(A) -> Result<R, AnyError> + 'static,
+  ) -> usize
+  where
+    A: DeserializeOwned,
+    R: Serialize,
+  {
+    let native_fn = move |arg: serde_json::Value| {
+      let arg = serde_json::from_value(arg).map_err(|err| {
+        crate::error::type_error(format!("Invalid argument: {}", err))
+      })?;
+      Ok(serde_json::to_value(f(arg)?)?)
+    };
+    let state_rc = Self::state(self.v8_isolate());
+    let mut state = state_rc.borrow_mut();
+    state.native_functions.push(Rc::new(native_fn));
+    state.native_functions.len() - 1
+  }
+
+  /// Registers a callback on the isolate when the memory limits are approached.
+  /// Use this to prevent V8 from crashing the process when reaching the limit.
+  ///
+  /// Calls the closure with the current heap limit and the initial heap limit.
+  /// The return value of the closure is set as the new limit.
+  pub fn add_near_heap_limit_callback<C>(&mut self, cb: C)
+  where
+    C: FnMut(usize, usize) -> usize + 'static,
+  {
+    let boxed_cb = Box::new(RefCell::new(cb));
+    let data = boxed_cb.as_ptr() as *mut c_void;
+
+    let prev = self
+      .allocations
+      .near_heap_limit_callback_data
+      .replace((boxed_cb, near_heap_limit_callback::<C>));
+    if let Some((_, prev_cb)) = prev {
+      self
+        .v8_isolate()
+        .remove_near_heap_limit_callback(prev_cb, 0);
     }
 
     self
@@ -506,6 +1548,48 @@ impl JsRuntime {
     poll_fn(|cx| self.poll_event_loop(cx)).await
   }
 
+  /// Like `run_event_loop()`, but once the event loop is done it also drains
+  /// the microtask queue and keeps going if that dispatched new ops, so that
+  /// it only returns when there is truly nothing left to do.
+  ///
+  /// Fails with `InfiniteLoop` if draining the microtask queue dispatches
+  /// new work more than `MAX_IDLE_CYCLES` times, which usually means that
+  /// ops and microtasks keep scheduling each other. Turns of the event loop
+  /// that wait for pending ops aren't counted, so event loops that are woken
+  /// up many times, like those of servers, can run for as long as they need.
+  pub async fn run_event_loop_until_idle(&mut self) -> Result<(), AnyError> {
+    let mut cycles = 0;
+    poll_fn(|cx| loop {
+      match self.poll_event_loop(cx) {
+        Poll::Ready(Ok(())) => {}
+        poll => return poll,
+      }
+
+      self.with_scope(|scope| scope.perform_microtask_checkpoint());
+      if let Err(err) = self.check_promise_exceptions() {
+        return Poll::Ready(Err(err));
+      }
+
+      {
+        let state_rc = Self::state(self.v8_isolate());
+        let state = state_rc.borrow();
+        if state.pending_ops.is_empty()
+          && state.pending_op_results.is_empty()
+          && state.preparing_dyn_imports.is_empty()
+          && state.pending_dyn_imports.is_empty()
+        {
+          return Poll::Ready(Ok(()));
+        }
+      }
+
+      cycles += 1;
+      if cycles == MAX_IDLE_CYCLES {
+        return Poll::Ready(Err(InfiniteLoop { cycles }.into()));
+      }
+    })
+    .await
+  }
+
   /// Runs a single tick of event loop
   pub fn poll_event_loop(
     &mut self,
@@ -542,7 +1626,8 @@ impl JsRuntime {
     self.evaluate_pending_module();
 
     let state = state_rc.borrow();
-    let has_pending_ops = !state.pending_ops.is_empty();
+    let has_pending_ops =
+      !state.pending_ops.is_empty() || !state.pending_op_results.is_empty();
 
     let has_pending_dyn_imports = !{
       state.preparing_dyn_imports.is_empty()
@@ -589,6 +1674,23 @@ impl JsRuntime {
 
     Poll::Pending
   }
+
+  /// Polls the pending op futures once and dispatches the results of those
+  /// that are ready to JS, without draining macrotasks, loading dynamic
+  /// imports or evaluating modules. This lets tests resolve op futures by hand
+  /// and assert on the state of JS promises without driving the event loop.
+  pub fn force_flush_op_results(&mut self) -> Result<(), AnyError> {
+    let mut cx = Context::from_waker(futures::task::noop_waker_ref());
+    loop {
+      let overflow_response = self.poll_pending_ops(&mut cx);
+      let done = overflow_response.is_none();
+      self.async_op_response(overflow_response)?;
+      if done {
+        break;
+      }
+    }
+    self.check_promise_exceptions()
+  }
 }
 
 extern "C" fn near_heap_limit_callback<F>(
@@ -604,6 +1706,17 @@ where
 }
 
 impl JsRuntimeState {
+  fn push_op_result(&mut self, (priority, op_id, buf): (u8, OpId, Box<[u8]>)) {
+    let seq = self.next_op_result_seq;
+    self.next_op_result_seq += 1;
+    self.pending_op_results.push(PendingOpResult {
+      priority,
+      seq,
+      op_id,
+      buf,
+    });
+  }
+
   // Called by V8 during `Isolate::mod_instantiate`.
   pub fn dyn_import_cb(
     &mut self,
@@ -626,6 +1739,69 @@ impl JsRuntimeState {
   }
 }
 
+/// Whether `name` can be used as the name of a local variable: an ASCII
+/// identifier that isn't a reserved word.
+fn is_valid_identifier(name: &str) -> bool {
+  const RESERVED_WORDS: &[&str] = &[
+    "arguments",
+    "await",
+    "break",
+    "case",
+    "catch",
+    "class",
+    "const",
+    "continue",
+    "debugger",
+    "default",
+    "delete",
+    "do",
+    "else",
+    "enum",
+    "eval",
+    "export",
+    "extends",
+    "false",
+    "finally",
+    "for",
+    "function",
+    "if",
+    "implements",
+    "import",
+    "in",
+    "instanceof",
+    "interface",
+    "let",
+    "new",
+    "null",
+    "package",
+    "private",
+    "protected",
+    "public",
+    "return",
+    "static",
+    "super",
+    "switch",
+    "this",
+    "throw",
+    "true",
+    "try",
+    "typeof",
+    "var",
+    "void",
+    "while",
+    "with",
+    "yield",
+  ];
+
+  let mut chars = name.chars();
+  match chars.next() {
+    Some(c) if c.is_ascii_alphabetic() || c == '_' || c == '$' => {}
+    _ => return false,
+  }
+  chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '$')
+    && !RESERVED_WORDS.contains(&name)
+}
+
 pub(crate) fn exception_to_err_result<'s, T>(
   scope: &mut v8::HandleScope<'s>,
   exception: v8::Local<v8::Value>,
@@ -646,13 +1822,7 @@ pub(crate) fn exception_to_err_result<'s, T>(
     }
   }
 
-  let mut js_error = JsError::from_v8_exception(scope, exception);
-  if in_promise {
-    js_error.message = format!(
-      "Uncaught (in promise) {}",
-      js_error.message.trim_start_matches("Uncaught ")
-    );
-  }
+  let js_error = to_js_error(scope, exception, in_promise);
 
   let state_rc = JsRuntime::state(scope);
   let state = state_rc.borrow();
@@ -666,6 +1836,89 @@ pub(crate) fn exception_to_err_result<'s, T>(
   Err(js_error)
 }
 
+fn to_js_error(
+  scope: &mut v8::HandleScope,
+  exception: v8::Local<v8::Value>,
+  in_promise: bool,
+) -> JsError {
+  let mut js_error = JsError::from_v8_exception(scope, exception);
+  if in_promise {
+    js_error.message = format!(
+      "Uncaught (in promise) {}",
+      js_error.message.trim_start_matches("Uncaught ")
+    );
+  }
+  js_error
+}
+
+/// Like `exception_to_err_result()`, but the error goes to the handler set
+/// with `JsRuntime::set_global_error_handler()` instead, if there is one.
+fn report_uncaught_exception(
+  scope: &mut v8::HandleScope,
+  exception: v8::Local<v8::Value>,
+  in_promise: bool,
+) -> Result<(), AnyError> {
+  if !in_promise
+    && !scope.is_execution_terminating()
+    && dispatch_error_event(scope, exception)
+  {
+    return Ok(());
+  }
+
+  let state_rc = JsRuntime::state(scope);
+  let maybe_handler = state_rc.borrow().global_error_handler.clone();
+  match maybe_handler {
+    Some(handler) if !scope.is_execution_terminating() => {
+      handler(to_js_error(scope, exception, in_promise));
+      Ok(())
+    }
+    _ => exception_to_err_result(scope, exception, in_promise),
+  }
+}
+
+/// Calls `globalThis.onerror` for `exception`, if it is a function, and
+/// returns whether it handled the error by returning `true`. Exceptions thrown
+/// by `onerror` itself are ignored.
+fn dispatch_error_event(
+  scope: &mut v8::HandleScope,
+  exception: v8::Local<v8::Value>,
+) -> bool {
+  let global = scope.get_current_context().global(scope);
+  let key = v8::String::new(scope, "onerror").unwrap();
+  let onerror = match global
+    .get(scope, key.into())
+    .and_then(|onerror| v8::Local::<v8::Function>::try_from(onerror).ok())
+  {
+    Some(onerror) => onerror,
+    None => return false,
+  };
+
+  let message = v8::Exception::create_message(scope, exception);
+  let text = message.get(scope);
+  let filename = match message.get_script_resource_name(scope) {
+    Some(name) if !name.is_undefined() => name,
+    _ => v8::String::empty(scope).into(),
+  };
+  let lineno = message.get_line_number(scope).unwrap_or(0) as i32;
+  let lineno = v8::Integer::new(scope, lineno);
+  // `colno` is 1-based, like `lineno`.
+  let colno = v8::Integer::new(scope, message.get_start_column() as i32 + 1);
+  let args = [
+    text.into(),
+    filename,
+    lineno.into(),
+    colno.into(),
+    exception,
+  ];
+
+  let tc_scope = &mut v8::TryCatch::new(scope);
+  let undefined = v8::undefined(tc_scope).into();
+  match onerror.call(tc_scope, undefined, &args) {
+    Some(result) => result.is_true(),
+    None => false,
+  }
+}
+
 // Related to module loading
 impl JsRuntime {
   /// Low-level module creation.
@@ -678,55 +1931,54 @@ impl JsRuntime {
     source: &str,
   ) -> Result<ModuleId, AnyError> {
     let state_rc = Self::state(self.v8_isolate());
-    let context = self.global_context();
-    let scope = &mut v8::HandleScope::with_context(self.v8_isolate(), context);
+    self.with_scope(|scope| {
+      let name_str = v8::String::new(scope, name).unwrap();
+      let source_str = v8::String::new(scope, source).unwrap();
 
-    let name_str = v8::String::new(scope, name).unwrap();
-    let source_str = v8::String::new(scope, source).unwrap();
+      let origin = bindings::module_origin(scope, name_str);
+      let source = v8::script_compiler::Source::new(source_str, &origin);
 
-    let origin = bindings::module_origin(scope, name_str);
-    let source = v8::script_compiler::Source::new(source_str, &origin);
+      let tc_scope = &mut v8::TryCatch::new(scope);
 
-    let tc_scope = &mut v8::TryCatch::new(scope);
+      let maybe_module = v8::script_compiler::compile_module(tc_scope, source);
 
-    let maybe_module = v8::script_compiler::compile_module(tc_scope, source);
+      if tc_scope.has_caught() {
+        assert!(maybe_module.is_none());
+        let e = tc_scope.exception().unwrap();
+        return exception_to_err_result(tc_scope, e, false);
+      }
 
-    if tc_scope.has_caught() {
-      assert!(maybe_module.is_none());
-      let e = tc_scope.exception().unwrap();
-      return exception_to_err_result(tc_scope, e, false);
-    }
+      let module = maybe_module.unwrap();
 
-    let module = maybe_module.unwrap();
+      let mut import_specifiers: Vec<ModuleSpecifier> = vec![];
+      let module_requests = module.get_module_requests();
+      for i in 0..module_requests.length() {
+        let module_request = v8::Local::<v8::ModuleRequest>::try_from(
+          module_requests.get(tc_scope, i).unwrap(),
+        )
+        .unwrap();
+        let import_specifier = module_request
+          .get_specifier()
+          .to_rust_string_lossy(tc_scope);
+        let state = state_rc.borrow();
+        let module_specifier = state.loader.resolve(
+          state.op_state.clone(),
+          &import_specifier,
+          name,
+          false,
+        )?;
+        import_specifiers.push(module_specifier);
+      }
 
-    let mut import_specifiers: Vec<ModuleSpecifier> = vec![];
-    let module_requests = module.get_module_requests();
-    for i in 0..module_requests.length() {
-      let module_request = v8::Local::<v8::ModuleRequest>::try_from(
-        module_requests.get(tc_scope, i).unwrap(),
-      )
-      .unwrap();
-      let import_specifier = module_request
-        .get_specifier()
-        .to_rust_string_lossy(tc_scope);
-      let state = state_rc.borrow();
-      let module_specifier = state.loader.resolve(
-        state.op_state.clone(),
-        &import_specifier,
+      let id = state_rc.borrow_mut().modules.register(
         name,
-        false,
-      )?;
-      import_specifiers.push(module_specifier);
-    }
-
-    let id = state_rc.borrow_mut().modules.register(
-      name,
-      main,
-      v8::Global::<v8::Module>::new(tc_scope, module),
-      import_specifiers,
-    );
+        main,
+        v8::Global::<v8::Module>::new(tc_scope, module),
+        import_specifiers,
+      );
 
-    Ok(id)
+      Ok(id)
+    })
   }
 
   /// Instantiates a ES module
@@ -736,31 +1988,30 @@ impl JsRuntime {
   /// be a different type if `RuntimeOptions::js_error_create_fn` has been set.
   fn mod_instantiate(&mut self, id: ModuleId) -> Result<(), AnyError> {
     let state_rc = Self::state(self.v8_isolate());
-    let context = self.global_context();
-
-    let scope = &mut v8::HandleScope::with_context(self.v8_isolate(), context);
-    let tc_scope = &mut v8::TryCatch::new(scope);
+    self.with_scope(|scope| {
+      let tc_scope = &mut v8::TryCatch::new(scope);
 
-    let module = state_rc
-      .borrow()
-      .modules
-      .get_handle(id)
-      .map(|handle| v8::Local::new(tc_scope, handle))
-      .expect("ModuleInfo not found");
+      let module = state_rc
+        .borrow()
+        .modules
+        .get_handle(id)
+        .map(|handle| v8::Local::new(tc_scope, handle))
+        .expect("ModuleInfo not found");
 
-    if module.get_status() == v8::ModuleStatus::Errored {
-      exception_to_err_result(tc_scope, module.get_exception(), false)?
-    }
+      if module.get_status() == v8::ModuleStatus::Errored {
+        exception_to_err_result(tc_scope, module.get_exception(), false)?
+      }
 
-    let result =
-      module.instantiate_module(tc_scope, bindings::module_resolve_callback);
-    match result {
-      Some(_) => Ok(()),
-      None => {
-        let exception = tc_scope.exception().unwrap();
-        exception_to_err_result(tc_scope, exception, false)
+      let result =
+        module.instantiate_module(tc_scope, bindings::module_resolve_callback);
+      match result {
+        Some(_) => Ok(()),
+        None => {
+          let exception = tc_scope.exception().unwrap();
+          exception_to_err_result(tc_scope, exception, false)
+        }
       }
-    }
+    })
   }
 
   /// Evaluates an already instantiated ES module.
@@ -774,8 +2025,6 @@ impl JsRuntime {
     id: ModuleId,
   ) -> Result<(), AnyError> {
     let state_rc = Self::state(self.v8_isolate());
-    let context = self.global_context();
-    let context1 = self.global_context();
 
     let module_handle = state_rc
       .borrow()
@@ -783,12 +2032,7 @@ impl JsRuntime {
       .get_handle(id)
       .expect("ModuleInfo not found");
 
-    let status = {
-      let scope =
-        &mut v8::HandleScope::with_context(self.v8_isolate(), context);
-      let module = module_handle.get(scope);
-      module.get_status()
-    };
+    let status = self.with_scope(|scope| module_handle.get(scope).get_status());
 
     if status == v8::ModuleStatus::Instantiated {
       // IMPORTANT: Top-level-await is enabled, which means that return value
@@ -807,45 +2051,45 @@ impl JsRuntime {
       // For more details see:
       // https://github.com/denoland/deno/issues/4908
       // https://v8.dev/features/top-level-await#module-execution-order
-      let scope =
-        &mut v8::HandleScope::with_context(self.v8_isolate(), context1);
-      let module = v8::Local::new(scope, &module_handle);
-      let maybe_value = module.evaluate(scope);
-
-      // Update status after evaluating.
-      let status = module.get_status();
-
-      if let Some(value) = maybe_value {
-        assert!(
-          status == v8::ModuleStatus::Evaluated
-            || status == v8::ModuleStatus::Errored
-        );
-        let promise = v8::Local::<v8::Promise>::try_from(value)
-          .expect("Expected to get promise as module evaluation result");
-        let empty_fn = |_scope: &mut v8::HandleScope,
-                        _args: v8::FunctionCallbackArguments,
-                        _rv: v8::ReturnValue| {};
-        let empty_fn = v8::FunctionTemplate::new(scope, empty_fn);
-        let empty_fn = empty_fn.get_function(scope).unwrap();
-        promise.catch(scope, empty_fn);
-        let promise_global = v8::Global::new(scope, promise);
-        let mut state = state_rc.borrow_mut();
-        state.pending_promise_exceptions.remove(&promise_global);
-        let promise_global = v8::Global::new(scope, promise);
-        let module_global = v8::Global::new(scope, module);
-
-        let dyn_import_mod_evaluate = DynImportModEvaluate {
-          module_id: id,
-          promise: promise_global,
-          module: module_global,
-        };
-
-        state
-          .pending_dyn_mod_evaluate
-          .insert(load_id, dyn_import_mod_evaluate);
-      } else {
-        assert!(status == v8::ModuleStatus::Errored);
-      }
+      self.with_scope(|scope| {
+        let module = v8::Local::new(scope, &module_handle);
+        let maybe_value = module.evaluate(scope);
+
+        // Update status after evaluating.
+        let status = module.get_status();
+
+        if let Some(value) = maybe_value {
+          assert!(
+            status == v8::ModuleStatus::Evaluated
+              || status == v8::ModuleStatus::Errored
+          );
+          let promise = v8::Local::<v8::Promise>::try_from(value)
+            .expect("Expected to get promise as module evaluation result");
+          let empty_fn = |_scope: &mut v8::HandleScope,
+                          _args: v8::FunctionCallbackArguments,
+                          _rv: v8::ReturnValue| {};
+          let empty_fn = v8::FunctionTemplate::new(scope, empty_fn);
+          let empty_fn = empty_fn.get_function(scope).unwrap();
+          promise.catch(scope, empty_fn);
+          let promise_global = v8::Global::new(scope, promise);
+          let mut state = state_rc.borrow_mut();
+          state.pending_promise_exceptions.remove(&promise_global);
+          let promise_global = v8::Global::new(scope, promise);
+          let module_global = v8::Global::new(scope, module);
+
+          let dyn_import_mod_evaluate = DynImportModEvaluate {
+            module_id: id,
+            promise: promise_global,
+            module: module_global,
+          };
+
+          state
+            .pending_dyn_mod_evaluate
+            .insert(load_id, dyn_import_mod_evaluate);
+        } else {
+          assert!(status == v8::ModuleStatus::Errored);
+        }
+      });
     }
 
     if status == v8::ModuleStatus::Evaluated {
@@ -865,69 +2109,93 @@ impl JsRuntime {
     id: ModuleId,
   ) -> mpsc::Receiver<Result<(), AnyError>> {
     let state_rc = Self::state(self.v8_isolate());
-    let context = self.global_context();
-
-    let scope = &mut v8::HandleScope::with_context(self.v8_isolate(), context);
-
-    let module = state_rc
-      .borrow()
-      .modules
-      .get_handle(id)
-      .map(|handle| v8::Local::new(scope, handle))
-      .expect("ModuleInfo not found");
-    let mut status = module.get_status();
-
-    let (sender, receiver) = mpsc::channel(1);
-
-    if status == v8::ModuleStatus::Instantiated {
-      // IMPORTANT: Top-level-await is enabled, which means that return value
-      // of module evaluation is a promise.
-      //
-      // Because that promise is created internally by V8, when error occurs during
-      // module evaluation the promise is rejected, and since the promise has no rejection
-      // handler it will result in call to `bindings::promise_reject_callback` adding
-      // the promise to pending promise rejection table - meaning JsRuntime will return
-      // error on next poll().
-      //
-      // This situation is not desirable as we want to manually return error at the
-      // end of this function to handle it further. It means we need to manually
-      // remove this promise from pending promise rejection table.
-      //
-      // For more details see:
-      // https://github.com/denoland/deno/issues/4908
-      // https://v8.dev/features/top-level-await#module-execution-order
-      let maybe_value = module.evaluate(scope);
-
-      // Update status after evaluating.
-      status = module.get_status();
-
-      if let Some(value) = maybe_value {
-        assert!(
-          status == v8::ModuleStatus::Evaluated
-            || status == v8::ModuleStatus::Errored
-        );
-        let promise = v8::Local::<v8::Promise>::try_from(value)
-          .expect("Expected to get promise as module evaluation result");
-        let promise_global = v8::Global::new(scope, promise);
-        let mut state = state_rc.borrow_mut();
-        state.pending_promise_exceptions.remove(&promise_global);
-        let promise_global = v8::Global::new(scope, promise);
-        assert!(
-          state.pending_mod_evaluate.is_none(),
-          "There is already pending top level module evaluation"
-        );
+    let isolate_handle = self.isolate_handle();
+    self.with_scope(|scope| {
+      let module_handle = state_rc
+        .borrow()
+        .modules
+        .get_handle(id)
+        .expect("ModuleInfo not found");
+      let module = v8::Local::new(scope, &module_handle);
+      let mut status = module.get_status();
+
+      let (mut sender, receiver) = mpsc::channel(1);
+
+      if status == v8::ModuleStatus::Instantiated {
+        // IMPORTANT: Top-level-await is enabled, which means that return value
+        // of module evaluation is a promise.
+        //
+        // Because that promise is created internally by V8, when error occurs during
+        // module evaluation the promise is rejected, and since the promise has no rejection
+        // handler it will result in call to `bindings::promise_reject_callback` adding
+        // the promise to pending promise rejection table - meaning JsRuntime will return
+        // error on next poll().
+        //
+        // This situation is not desirable as we want to manually return error at the
+        // end of this function to handle it further. It means we need to manually
+        // remove this promise from pending promise rejection table.
+        //
+        // For more details see:
+        // https://github.com/denoland/deno/issues/4908
+        // https://v8.dev/features/top-level-await#module-execution-order
+        let maybe_timeout = state_rc.borrow().module_evaluation_timeout;
+        let watchdog = maybe_timeout
+          .map(|timeout| EvaluationWatchdog::start(isolate_handle, timeout));
+        let maybe_value = module.evaluate(scope);
+        let timed_out = watchdog.map_or(false, EvaluationWatchdog::stop);
+
+        // Update status after evaluating.
+        status = module.get_status();
+
+        if timed_out {
+          scope.cancel_terminate_execution();
+        }
 
-        state.pending_mod_evaluate = Some(ModEvaluate {
-          promise: promise_global,
-          sender,
-        });
-        scope.perform_microtask_checkpoint();
-      } else {
-        assert!(status == v8::ModuleStatus::Errored);
+        if timed_out && maybe_value.is_none() {
+          let specifier = state_rc
+            .borrow()
+            .modules
+            .get_info(&module_handle)
+            .map(|info| info.name.clone())
+            .unwrap_or_default();
+          let err = ModuleEvaluationTimeout {
+            specifier,
+            timeout: maybe_timeout.unwrap(),
+          };
+          sender
+            .try_send(Err(err.into()))
+            .expect("Failed to send module evaluation error.");
+        } else if let Some(value) = maybe_value {
+          assert!(
+            status == v8::ModuleStatus::Evaluated
+              || status == v8::ModuleStatus::Errored
+          );
+          let promise = v8::Local::<v8::Promise>::try_from(value)
+            .expect("Expected to get promise as module evaluation result");
+          let promise_global = v8::Global::new(scope, promise);
+          let mut state = state_rc.borrow_mut();
+          state.pending_promise_exceptions.remove(&promise_global);
+          let promise_global = v8::Global::new(scope, promise);
+          state
+            .module_evaluation_promises
+            .insert(id, promise_global.clone());
+          assert!(
+            state.pending_mod_evaluate.is_none(),
+            "There is already pending top level module evaluation"
+          );
+
+          state.pending_mod_evaluate = Some(ModEvaluate {
+            promise: promise_global,
+            sender,
+          });
+          scope.perform_microtask_checkpoint();
+        } else {
+          assert!(status == v8::ModuleStatus::Errored);
+        }
       }
-    }
 
-    receiver
+      receiver
+    })
   }
 
   pub async fn mod_evaluate(&mut self, id: ModuleId) -> Result<(), AnyError> {
@@ -948,60 +2216,263 @@ impl JsRuntime {
     .await
   }
 
-  fn dyn_import_error(&mut self, id: ModuleLoadId, err: AnyError) {
+  /// Evaluates the module registered as `specifier`, unless that already
+  /// happened, and drives the event loop until the promise returned by its
+  /// evaluation has settled. Returns the value the promise was fulfilled
+  /// with, or its rejection as an error. Unlike the module namespace, which
+  /// is available as soon as evaluation starts, this waits for the module's
+  /// top-level await to complete.
+  ///
+  /// The module must have been loaded with `JsRuntime::load_module()`, and
+  /// evaluated, if it already was, as the root of `mod_evaluate()` rather
+  /// than as a dependency of another module.
+  pub async fn get_top_level_await_result(
+    &mut self,
+    specifier: &str,
+  ) -> Result<v8::Global<v8::Value>, AnyError> {
     let state_rc = Self::state(self.v8_isolate());
-    let context = self.global_context();
+    let id = state_rc.borrow().modules.get_id(specifier).ok_or_else(|| {
+      generic_error(format!("Module \"{}\" has not been loaded", specifier))
+    })?;
+    let module_handle = state_rc
+      .borrow()
+      .modules
+      .get_handle(id)
+      .expect("ModuleInfo not found");
 
-    let scope = &mut v8::HandleScope::with_context(self.v8_isolate(), context);
+    let status = self.with_scope(|scope| module_handle.get(scope).get_status());
+    match status {
+      v8::ModuleStatus::Instantiated => self.mod_evaluate(id).await?,
+      v8::ModuleStatus::Errored => {
+        return self.with_scope(|scope| {
+          let exception = module_handle.get(scope).get_exception();
+          exception_to_err_result(scope, exception, false)
+        })
+      }
+      v8::ModuleStatus::Evaluating | v8::ModuleStatus::Evaluated => {}
+      _ => {
+        return Err(generic_error(format!(
+          "Module \"{}\" has not been instantiated",
+          specifier
+        )))
+      }
+    }
 
-    let resolver_handle = state_rc
-      .borrow_mut()
-      .dyn_import_map
-      .remove(&id)
-      .expect("Invalid dyn import id");
-    let resolver = resolver_handle.get(scope);
-
-    let exception = err
-      .downcast_ref::<ErrWithV8Handle>()
-      .map(|err| err.get_handle(scope))
-      .unwrap_or_else(|| {
-        let message = err.to_string();
-        let message = v8::String::new(scope, &message).unwrap();
-        v8::Exception::type_error(scope, message)
-      });
+    let promise = state_rc
+      .borrow()
+      .module_evaluation_promises
+      .get(&id)
+      .cloned()
+      .ok_or_else(|| {
+        generic_error(format!(
+          "Module \"{}\" was evaluated as a dependency of another module",
+          specifier
+        ))
+      })?;
+
+    // The module may have been evaluated earlier without its evaluation being
+    // awaited, in which case its top-level await can still be pending.
+    poll_fn(|cx| {
+      let is_pending = |rt: &mut Self| {
+        rt.with_scope(|scope| promise.get(scope).state())
+          == v8::PromiseState::Pending
+      };
+      if !is_pending(self) {
+        return Poll::Ready(Ok(()));
+      }
+      let idle = self.poll_event_loop(cx)?.is_ready();
+      if !is_pending(self) {
+        Poll::Ready(Ok(()))
+      } else if idle {
+        Poll::Ready(Err(generic_error(format!(
+          "Top-level await of module \"{}\" is still pending but there are no pending ops or dynamic imports",
+          specifier
+        ))))
+      } else {
+        Poll::Pending
+      }
+    })
+    .await?;
 
-    resolver.reject(scope, exception).unwrap();
-    scope.perform_microtask_checkpoint();
+    self.with_scope(|scope| {
+      let promise = promise.get(scope);
+      let value = promise.result(scope);
+      if promise.state() == v8::PromiseState::Rejected {
+        exception_to_err_result(scope, value, false)
+      } else {
+        Ok(v8::Global::new(scope, value))
+      }
+    })
   }
 
-  fn dyn_import_done(&mut self, id: ModuleLoadId, mod_id: ModuleId) {
+  /// Serializes the import graph of the loaded module `entry` for bundlers
+  /// and other tools, as
+  /// `{ "modules": { [specifier]: [imports] }, "topological_order": [...] }`.
+  /// `modules` has an entry for `entry` and every module it imports,
+  /// transitively, and `topological_order` lists them in the order they are
+  /// evaluated in, where each module comes after its imports, except in
+  /// import cycles.
+  pub fn serialize_module_graph(
+    &mut self,
+    entry: &str,
+  ) -> Result<serde_json::Value, AnyError> {
     let state_rc = Self::state(self.v8_isolate());
-    let context = self.global_context();
+    let state = state_rc.borrow();
+    let id = state.modules.get_id(entry).ok_or_else(|| {
+      generic_error(format!("Module \"{}\" has not been loaded", entry))
+    })?;
+    let (imports, order) = state.modules.get_import_graph(id);
+    Ok(serde_json::json!({
+      "modules": imports,
+      "topological_order": order,
+    }))
+  }
 
-    debug!("dyn_import_done {} {:?}", id, mod_id);
-    let scope = &mut v8::HandleScope::with_context(self.v8_isolate(), context);
+  /// Returns whether a module is registered as `specifier` in the module map,
+  /// i.e. it has been loaded, whether or not it has been evaluated.
+  pub fn has_module(&mut self, specifier: &str) -> bool {
+    let state_rc = Self::state(self.v8_isolate());
+    let has_module = state_rc.borrow().modules.get_id(specifier).is_some();
+    has_module
+  }
 
-    let resolver_handle = state_rc
-      .borrow_mut()
-      .dyn_import_map
-      .remove(&id)
-      .expect("Invalid dyn import id");
-    let resolver = resolver_handle.get(scope);
+  /// Returns the status of the module registered as `specifier`, or `None` if
+  /// it hasn't been loaded. A module is `Evaluating` while its top-level code,
+  /// including a top-level `await`, hasn't completed.
+  pub fn module_status(&mut self, specifier: &str) -> Option<ModuleStatus> {
+    let state_rc = Self::state(self.v8_isolate());
+    let handle = {
+      let state = state_rc.borrow();
+      let id = state.modules.get_id(specifier)?;
+      state.modules.get_handle(id)?
+    };
+    self.with_scope(|scope| {
+      let module = v8::Local::new(scope, handle);
+      Some(module.get_status().into())
+    })
+  }
+
+  /// Removes the module registered as `specifier` from the module map, so
+  /// that the next import of it loads its source again. Unlike replacing the
+  /// whole runtime, every other module stays loaded, except that modules
+  /// which imported it, directly or transitively, are removed as well: they
+  /// are linked against the old module and need to be evaluated again.
+  ///
+  /// Returns `false` if no module is registered as `specifier`.
+  pub fn invalidate_module(&mut self, specifier: &str) -> bool {
+    let state_rc = Self::state(self.v8_isolate());
+    let invalidated = state_rc.borrow_mut().modules.invalidate(specifier);
+    !invalidated.is_empty()
+  }
 
-    let module = {
+  /// Makes `specifier` load as `mock_source` instead of the source returned
+  /// by the module loader. Only available in debug builds, it's meant for
+  /// tests.
+  ///
+  /// If the module has already been compiled, the mock is compiled right away
+  /// and used for every import from then on; modules that were already linked
+  /// against the original keep using it. In that case the mock may only import
+  /// modules that have already been loaded.
+  #[cfg(debug_assertions)]
+  pub fn install_module_mock(
+    &mut self,
+    specifier: &str,
+    mock_source: &str,
+  ) -> Result<(), AnyError> {
+    let specifier = crate::resolve_url(specifier)?.to_string();
+    let state_rc = Self::state(self.v8_isolate());
+    let (maybe_id, original_id) = {
       let state = state_rc.borrow();
-      state
-        .modules
-        .get_handle(mod_id)
-        .map(|handle| v8::Local::new(scope, handle))
-        .expect("Dyn import module info not found")
+      let maybe_id = state.modules.get_id(&specifier);
+      let original_id = match state.module_mocks.get(&specifier) {
+        Some(mock) => mock.original_id,
+        None => maybe_id,
+      };
+      (maybe_id, original_id)
     };
-    // Resolution success
-    assert_eq!(module.get_status(), v8::ModuleStatus::Evaluated);
 
-    let module_namespace = module.get_module_namespace();
-    resolver.resolve(scope, module_namespace).unwrap();
-    scope.perform_microtask_checkpoint();
+    if maybe_id.is_some() {
+      self.mod_new(false, &specifier, mock_source)?;
+    }
+    state_rc.borrow_mut().module_mocks.insert(
+      specifier,
+      ModuleMock {
+        source: mock_source.to_string(),
+        original_id,
+      },
+    );
+    Ok(())
+  }
+
+  /// Undoes `install_module_mock()`: imports of `specifier` resolve to the
+  /// original module again, loading it if it hadn't been loaded yet.
+  #[cfg(debug_assertions)]
+  pub fn remove_module_mock(
+    &mut self,
+    specifier: &str,
+  ) -> Result<(), AnyError> {
+    let specifier = crate::resolve_url(specifier)?.to_string();
+    let state_rc = Self::state(self.v8_isolate());
+    let mut state = state_rc.borrow_mut();
+    if let Some(mock) = state.module_mocks.remove(&specifier) {
+      match mock.original_id {
+        Some(id) => state.modules.set_id(&specifier, id),
+        None => state.modules.remove_name(&specifier),
+      }
+    }
+    Ok(())
+  }
+
+  fn dyn_import_error(&mut self, id: ModuleLoadId, err: AnyError) {
+    let state_rc = Self::state(self.v8_isolate());
+    self.with_scope(|scope| {
+      let resolver_handle = state_rc
+        .borrow_mut()
+        .dyn_import_map
+        .remove(&id)
+        .expect("Invalid dyn import id");
+      let resolver = resolver_handle.get(scope);
+
+      let exception = err
+        .downcast_ref::<ErrWithV8Handle>()
+        .map(|err| err.get_handle(scope))
+        .unwrap_or_else(|| {
+          let message = err.to_string();
+          let message = v8::String::new(scope, &message).unwrap();
+          v8::Exception::type_error(scope, message)
+        });
+
+      resolver.reject(scope, exception).unwrap();
+      scope.perform_microtask_checkpoint();
+    })
+  }
+
+  fn dyn_import_done(&mut self, id: ModuleLoadId, mod_id: ModuleId) {
+    let state_rc = Self::state(self.v8_isolate());
+    debug!("dyn_import_done {} {:?}", id, mod_id);
+    self.with_scope(|scope| {
+      let resolver_handle = state_rc
+        .borrow_mut()
+        .dyn_import_map
+        .remove(&id)
+        .expect("Invalid dyn import id");
+      let resolver = resolver_handle.get(scope);
+
+      let module = {
+        let state = state_rc.borrow();
+        state
+          .modules
+          .get_handle(mod_id)
+          .map(|handle| v8::Local::new(scope, handle))
+          .expect("Dyn import module info not found")
+      };
+      // Resolution success
+      assert_eq!(module.get_status(), v8::ModuleStatus::Evaluated);
+
+      let module_namespace = module.get_module_namespace();
+      resolver.resolve(scope, module_namespace).unwrap();
+      scope.perform_microtask_checkpoint();
+    })
   }
 
   fn prepare_dyn_imports(
@@ -1118,11 +2589,7 @@ impl JsRuntime {
   fn evaluate_pending_module(&mut self) {
     let state_rc = Self::state(self.v8_isolate());
 
-    let context = self.global_context();
-    {
-      let scope =
-        &mut v8::HandleScope::with_context(self.v8_isolate(), context);
-
+    self.with_scope(|scope| {
       let mut state = state_rc.borrow_mut();
 
       if let Some(module_evaluation) = state.pending_mod_evaluate.as_ref() {
@@ -1152,18 +2619,14 @@ impl JsRuntime {
           }
         }
       }
-    };
+    })
   }
 
   fn evaluate_dyn_imports(&mut self) {
     let state_rc = Self::state(self.v8_isolate());
 
     loop {
-      let context = self.global_context();
-      let maybe_result = {
-        let scope =
-          &mut v8::HandleScope::with_context(self.v8_isolate(), context);
-
+      let maybe_result = self.with_scope(|scope| {
         let mut state = state_rc.borrow_mut();
         if let Some(&dyn_import_id) =
           state.pending_dyn_mod_evaluate.keys().next()
@@ -1200,7 +2663,7 @@ impl JsRuntime {
         } else {
           None
         }
-      };
+      });
 
       if let Some(result) = maybe_result {
         match result {
@@ -1266,7 +2729,15 @@ impl JsRuntime {
         id
       }
       // Module not registered yet, do it now.
-      None => self.mod_new(is_main, &module_url_found, &code)?,
+      None => {
+        #[cfg(debug_assertions)]
+        let code = state_rc
+          .borrow()
+          .module_mocks
+          .get(&module_url_found)
+          .map_or(code, |mock| mock.source.clone());
+        self.mod_new(is_main, &module_url_found, &code)?
+      }
     };
 
     // Now we must iterate over all imports of the module and load them.
@@ -1335,82 +2806,164 @@ impl JsRuntime {
     self.mod_instantiate(root_id).map(|_| root_id)
   }
 
+  /// Evaluates `code` as an ES module, resolving its imports (and those of its
+  /// dependencies) through `import_map` the way `--import-map` does in the
+  /// CLI, so importing an unmapped bare specifier fails. `import_map` is the
+  /// JSON source of an import map. The modules are loaded by the runtime's
+  /// module loader.
+  ///
+  /// The module gets the specifier `file:///$deno$eval.js`, which is also the
+  /// base URL for relative addresses in the import map. The import map only
+  /// applies while the module is being loaded and evaluated.
+  pub async fn eval_with_import_map(
+    &mut self,
+    code: &str,
+    import_map: &str,
+  ) -> Result<(), AnyError> {
+    let specifier = crate::resolve_url("file:///$deno$eval.js")?;
+    let import_map = ImportMap::from_json(specifier.as_str(), import_map)?;
+
+    let state_rc = Self::state(self.v8_isolate());
+    let inner = state_rc.borrow().loader.clone();
+    let loader = Rc::new(ImportMapModuleLoader {
+      import_map,
+      inner: inner.clone(),
+    });
+    state_rc.borrow_mut().loader = loader;
+
+    let result = async {
+      let id = self.load_module(&specifier, Some(code.to_string())).await?;
+      self.mod_evaluate(id).await
+    }
+    .await;
+
+    state_rc.borrow_mut().loader = inner;
+    result
+  }
+
+  /// Evaluates `body` as the body of an async function, wrapped in
+  /// `(async () => { ... })()`, so that it can use `await` without top-level
+  /// await support. Drives the event loop until the function's promise
+  /// settles and returns the value the body returned, or `undefined`.
+  ///
+  /// An exception thrown by the body, before or after an `await`, is returned
+  /// as the error instead of being reported as an unhandled promise
+  /// rejection.
+  ///
+  /// `AnyError` can be downcast to a type that exposes additional information
+  /// about the V8 exception. By default this type is `JsError`, however it may
+  /// be a different type if `RuntimeOptions::js_error_create_fn` has been set.
+  pub async fn eval_async_iife(
+    &mut self,
+    body: &str,
+  ) -> Result<v8::Global<v8::Value>, AnyError> {
+    // The body is put on its own lines so that a trailing comment can't
+    // swallow the rest of the wrapper.
+    let code = format!("(async () => {{\n{}\n}})()", body);
+    let script = self.compile_expression(&code, "eval_async_iife")?;
+    let value = self.run_compiled_script(&script)?;
+    let promise = self.with_scope(|scope| {
+      let value = v8::Local::new(scope, value);
+      let promise = v8::Local::<v8::Promise>::try_from(value)
+        .expect("Expected an async function to return a promise");
+      // The rejection is handled by returning it, so it mustn't be reported
+      // as unhandled too.
+      let empty_fn = |_scope: &mut v8::HandleScope,
+                      _args: v8::FunctionCallbackArguments,
+                      _rv: v8::ReturnValue| {};
+      let empty_fn = v8::FunctionTemplate::new(scope, empty_fn);
+      let empty_fn = empty_fn.get_function(scope).unwrap();
+      promise.catch(scope, empty_fn);
+      v8::Global::new(scope, promise)
+    });
+
+    poll_fn(|cx| {
+      let is_event_loop_done = self.poll_event_loop(cx)?.is_ready();
+      let maybe_result = self.with_scope(|scope| {
+        scope.perform_microtask_checkpoint();
+        let promise = promise.get(scope);
+        match promise.state() {
+          v8::PromiseState::Pending => None,
+          v8::PromiseState::Fulfilled => {
+            let value = promise.result(scope);
+            Some(Ok(v8::Global::new(scope, value)))
+          }
+          v8::PromiseState::Rejected => {
+            let exception = promise.result(scope);
+            Some(
+              exception_to_err_result(scope, exception, false)
+                .map_err(|err| attach_handle_to_error(scope, err, exception)),
+            )
+          }
+        }
+      });
+      match maybe_result {
+        Some(result) => Poll::Ready(result),
+        None if is_event_loop_done => {
+          let msg = "The async function is still pending but there are no pending ops or dynamic imports. This situation is often caused by unresolved promise.";
+          Poll::Ready(Err(generic_error(msg)))
+        }
+        None => Poll::Pending,
+      }
+    })
+    .await
+  }
+
   fn poll_pending_ops(
     &mut self,
     cx: &mut Context,
   ) -> Option<(OpId, Box<[u8]>)> {
     let state_rc = Self::state(self.v8_isolate());
-    let mut overflow_response: Option<(OpId, Box<[u8]>)> = None;
+    let mut state = state_rc.borrow_mut();
+    // Now handle actual ops.
+    state.have_unpolled_ops.set(false);
 
-    loop {
-      let mut state = state_rc.borrow_mut();
-      // Now handle actual ops.
-      state.have_unpolled_ops.set(false);
-
-      let pending_r = state.pending_ops.poll_next_unpin(cx);
-      match pending_r {
-        Poll::Ready(None) => break,
-        Poll::Pending => break,
-        Poll::Ready(Some((op_id, buf))) => {
-          let successful_push = state.shared.push(op_id, &buf);
-          if !successful_push {
-            // If we couldn't push the response to the shared queue, because
-            // there wasn't enough size, we will return the buffer via the
-            // legacy route, using the argument of deno_respond.
-            overflow_response = Some((op_id, buf));
-            break;
-          }
-        }
-      };
+    // All the results that are ready are collected before any is dispatched,
+    // so that they are dispatched by priority rather than completion order.
+    while let Poll::Ready(Some(result)) = state.pending_ops.poll_next_unpin(cx)
+    {
+      state.push_op_result(result);
+    }
+    while let Poll::Ready(Some(result)) =
+      state.pending_unref_ops.poll_next_unpin(cx)
+    {
+      state.push_op_result(result);
     }
 
-    loop {
-      let mut state = state_rc.borrow_mut();
-      let unref_r = state.pending_unref_ops.poll_next_unpin(cx);
-      #[allow(clippy::match_wild_err_arm)]
-      match unref_r {
-        Poll::Ready(None) => break,
-        Poll::Pending => break,
-        Poll::Ready(Some((op_id, buf))) => {
-          let successful_push = state.shared.push(op_id, &buf);
-          if !successful_push {
-            // If we couldn't push the response to the shared queue, because
-            // there wasn't enough size, we will return the buffer via the
-            // legacy route, using the argument of deno_respond.
-            overflow_response = Some((op_id, buf));
-            break;
-          }
+    while let Some(result) = state.pending_op_results.pop() {
+      let successful_push = state.shared.push(result.op_id, &result.buf);
+      if !successful_push {
+        // If we couldn't push the response to the shared queue, because
+        // there wasn't enough size, we will return the buffer via the
+        // legacy route, using the argument of deno_respond. The remaining
+        // results are dispatched in the next turn of the event loop.
+        if !state.pending_op_results.is_empty() {
+          state.have_unpolled_ops.set(true);
         }
-      };
+        return Some((result.op_id, result.buf));
+      }
     }
 
-    overflow_response
+    None
   }
 
   fn check_promise_exceptions(&mut self) -> Result<(), AnyError> {
     let state_rc = Self::state(self.v8_isolate());
-    let mut state = state_rc.borrow_mut();
-
-    if state.pending_promise_exceptions.is_empty() {
-      return Ok(());
-    }
-
-    let key = {
-      state
-        .pending_promise_exceptions
-        .keys()
-        .next()
-        .unwrap()
-        .clone()
-    };
-    let handle = state.pending_promise_exceptions.remove(&key).unwrap();
-    drop(state);
 
-    let context = self.global_context();
-    let scope = &mut v8::HandleScope::with_context(self.v8_isolate(), context);
+    loop {
+      let mut state = state_rc.borrow_mut();
+      let key = match state.pending_promise_exceptions.keys().next() {
+        Some(key) => key.clone(),
+        None => return Ok(()),
+      };
+      let handle = state.pending_promise_exceptions.remove(&key).unwrap();
+      drop(state);
 
-    let exception = v8::Local::new(scope, handle);
-    exception_to_err_result(scope, exception, true)
+      self.with_scope(|scope| {
+        let exception = v8::Local::new(scope, handle);
+        report_uncaught_exception(scope, exception, true)
+      })?;
+    }
   }
 
   // Respond using shared queue and optionally overflown response
@@ -1435,34 +2988,36 @@ impl JsRuntime {
       .clone()
       .expect("Deno.core.recv has not been called.");
 
-    let context = self.global_context();
-    let scope = &mut v8::HandleScope::with_context(self.v8_isolate(), context);
-    let context = scope.get_current_context();
-    let global: v8::Local<v8::Value> = context.global(scope).into();
-    let js_recv_cb = js_recv_cb_handle.get(scope);
+    self.with_scope(|scope| {
+      let context = scope.get_current_context();
+      let global: v8::Local<v8::Value> = context.global(scope).into();
+      let js_recv_cb = js_recv_cb_handle.get(scope);
 
-    let tc_scope = &mut v8::TryCatch::new(scope);
+      let tc_scope = &mut v8::TryCatch::new(scope);
 
-    if shared_queue_size > 0 {
-      js_recv_cb.call(tc_scope, global, &[]);
-      // The other side should have shifted off all the messages.
-      let shared_queue_size = state_rc.borrow().shared.size();
-      assert_eq!(shared_queue_size, 0);
-    }
+      if shared_queue_size > 0 {
+        js_recv_cb.call(tc_scope, global, &[]);
+        // The other side should have shifted off all the messages.
+        let shared_queue_size = state_rc.borrow().shared.size();
+        assert_eq!(shared_queue_size, 0);
+      }
 
-    if let Some(overflown_response) = maybe_overflown_response {
-      let (op_id, buf) = overflown_response;
-      let op_id: v8::Local<v8::Value> =
-        v8::Integer::new(tc_scope, op_id as i32).into();
-      let ui8: v8::Local<v8::Value> =
-        bindings::boxed_slice_to_uint8array(tc_scope, buf).into();
-      js_recv_cb.call(tc_scope, global, &[op_id, ui8]);
-    }
+      if let Some(overflown_response) = maybe_overflown_response {
+        let (op_id, buf) = overflown_response;
+        let op_id: v8::Local<v8::Value> =
+          v8::Integer::new(tc_scope, op_id as i32).into();
+        let ui8: v8::Local<v8::Value> =
+          bindings::boxed_slice_to_uint8array(tc_scope, buf).into();
+        js_recv_cb.call(tc_scope, global, &[op_id, ui8]);
+      }
 
-    match tc_scope.exception() {
-      None => Ok(()),
-      Some(exception) => exception_to_err_result(tc_scope, exception, false),
-    }
+      match tc_scope.exception() {
+        None => Ok(()),
+        Some(exception) => {
+          report_uncaught_exception(tc_scope, exception, false)
+        }
+      }
+    })
   }
 
   fn drain_macrotasks(&mut self) -> Result<(), AnyError> {
@@ -1472,31 +3027,34 @@ impl JsRuntime {
         None => return Ok(()),
       };
 
-    let context = self.global_context();
-    let scope = &mut v8::HandleScope::with_context(self.v8_isolate(), context);
-    let context = scope.get_current_context();
-    let global: v8::Local<v8::Value> = context.global(scope).into();
-    let js_macrotask_cb = js_macrotask_cb_handle.get(scope);
+    self.with_scope(|scope| {
+      let context = scope.get_current_context();
+      let global: v8::Local<v8::Value> = context.global(scope).into();
+      let js_macrotask_cb = js_macrotask_cb_handle.get(scope);
 
-    // Repeatedly invoke macrotask callback until it returns true (done),
-    // such that ready microtasks would be automatically run before
-    // next macrotask is processed.
-    let tc_scope = &mut v8::TryCatch::new(scope);
+      // Repeatedly invoke macrotask callback until it returns true (done),
+      // such that ready microtasks would be automatically run before
+      // next macrotask is processed.
+      let tc_scope = &mut v8::TryCatch::new(scope);
 
-    loop {
-      let is_done = js_macrotask_cb.call(tc_scope, global, &[]);
+      loop {
+        let is_done = js_macrotask_cb.call(tc_scope, global, &[]);
 
-      if let Some(exception) = tc_scope.exception() {
-        return exception_to_err_result(tc_scope, exception, false);
-      }
+        if let Some(exception) = tc_scope.exception() {
+          report_uncaught_exception(tc_scope, exception, false)?;
+          // The exception was handled, carry on with the next macrotask.
+          tc_scope.reset();
+          continue;
+        }
 
-      let is_done = is_done.unwrap();
-      if is_done.is_true() {
-        break;
+        let is_done = is_done.unwrap();
+        if is_done.is_true() {
+          break;
+        }
       }
-    }
 
-    Ok(())
+      Ok(())
+    })
   }
 }
 
@@ -1505,8 +3063,10 @@ pub mod tests {
   use super::*;
   use crate::modules::ModuleSourceFuture;
   use crate::BufVec;
+  use crate::ConsoleLevel;
   use futures::future::lazy;
   use futures::FutureExt;
+  use serde::Deserialize;
   use std::io;
   use std::ops::FnOnce;
   use std::rc::Rc;
@@ -1822,6 +3382,35 @@ pub mod tests {
     terminator_thread.join().unwrap();
   }
 
+  #[test]
+  fn cancellation_token() {
+    let (mut isolate, _dispatch_count) = setup(Mode::Async);
+    let token = isolate.cancellation_token();
+    assert!(!token.is_cancelled());
+
+    let cancel_token = token.clone();
+    let terminator_thread = std::thread::spawn(move || {
+      // allow deno to boot and run
+      std::thread::sleep(std::time::Duration::from_millis(100));
+      assert!(cancel_token.cancel());
+      assert!(!cancel_token.cancel());
+    });
+
+    match isolate.execute("infinite_loop.js", "for(;;) {}") {
+      Ok(_) => panic!("execution should be terminated"),
+      Err(e) => {
+        assert_eq!(e.to_string(), "Uncaught Error: execution terminated")
+      }
+    };
+    terminator_thread.join().unwrap();
+    assert!(token.is_cancelled());
+
+    assert!(isolate.isolate_handle().cancel_terminate_execution());
+    isolate
+      .execute("simple.js", "1 + 1")
+      .expect("execution should be possible again");
+  }
+
   #[test]
   fn dangling_shared_isolate() {
     let v8_isolate_handle = {
@@ -2005,47 +3594,882 @@ pub mod tests {
   }
 
   #[test]
-  fn core_test_js() {
-    run_in_task(|mut cx| {
-      let (mut runtime, _dispatch_count) = setup(Mode::Async);
-      runtime
-        .execute("core_test.js", include_str!("core_test.js"))
-        .unwrap();
-      if let Poll::Ready(Err(_)) = runtime.poll_event_loop(&mut cx) {
-        unreachable!();
-      }
-    });
+  fn test_json_op_async_buffered() {
+    let mut runtime = JsRuntime::new(Default::default());
+    runtime.register_op(
+      "op_buffered_ok",
+      crate::json_op_async_buffered(|_, _: serde_json::Value, _| {
+        futures::stream::iter(vec![Ok(1), Ok(2), Ok(3)])
+      }),
+    );
+    runtime.register_op(
+      "op_buffered_err",
+      crate::json_op_async_buffered(|_, _: serde_json::Value, _| {
+        futures::stream::iter(vec![Ok(1), Err(generic_error("boom")), Ok(3)])
+      }),
+    );
+    runtime
+      .execute(
+        "buffered.js",
+        r#"
+        Deno.core.ops();
+        Deno.core.registerErrorClass("Error", Error);
+        Deno.core.jsonOpAsync("op_buffered_ok").then((v) => {
+          globalThis.okResult = v;
+        });
+        Deno.core.jsonOpAsync("op_buffered_err").catch((e) => {
+          globalThis.errResult = e.message;
+        });
+        "#,
+      )
+      .unwrap();
+    poll_until_ready(&mut runtime, 10).unwrap();
+    runtime
+      .execute(
+        "check.js",
+        r#"
+        if (JSON.stringify(okResult) !== "[1,2,3]") {
+          throw new Error(`unexpected result ${okResult}`);
+        }
+        if (errResult !== "boom") {
+          throw new Error(`unexpected error ${errResult}`);
+        }
+        "#,
+      )
+      .unwrap();
   }
 
   #[test]
-  fn syntax_error() {
+  fn test_op_batch() {
     let mut runtime = JsRuntime::new(Default::default());
-    let src = "hocuspocus(";
-    let r = runtime.execute("i.js", src);
-    let e = r.unwrap_err();
-    let js_error = e.downcast::<JsError>().unwrap();
-    assert_eq!(js_error.end_column, Some(11));
+    runtime.op_state().borrow_mut().put(0u64);
+    let ops = crate::op_batch(
+      "op_counter",
+      vec![
+        Box::new(|state, _, args| {
+          *state.borrow_mut::<u64>() += args.as_u64().unwrap();
+          Ok(serde_json::Value::Null)
+        }),
+        Box::new(|state, code, _| {
+          Ok(serde_json::json!([code, state.borrow::<u64>()]))
+        }),
+      ],
+    );
+    assert_eq!(ops.sync_op.0, "op_counter");
+    assert_eq!(ops.async_op.0, "op_counter_async");
+    runtime.register_op(&ops.sync_op.0, ops.sync_op.1);
+    runtime.register_op(&ops.async_op.0, ops.async_op.1);
+    runtime
+      .execute(
+        "batch.js",
+        r#"
+        Deno.core.ops();
+        Deno.core.registerErrorClass("Error", Error);
+        Deno.core.jsonOpSync("op_counter", [0, 2]);
+        Deno.core.jsonOpSync("op_counter", [0, 3]);
+        const result = Deno.core.jsonOpSync("op_counter", [1, null]);
+        if (JSON.stringify(result) !== "[1,5]") {
+          throw new Error(`unexpected result ${JSON.stringify(result)}`);
+        }
+        let error;
+        try {
+          Deno.core.jsonOpSync("op_counter", [2, null]);
+        } catch (e) {
+          error = e;
+        }
+        if (!(error instanceof Error)) {
+          throw new Error("expected unknown sub-command to throw");
+        }
+        Deno.core.jsonOpAsync("op_counter_async", [0, 1]).then(() =>
+          Deno.core.jsonOpAsync("op_counter_async", [1, null])
+        ).then((result) => {
+          globalThis.asyncResult = JSON.stringify(result);
+        });
+        "#,
+      )
+      .unwrap();
+    futures::executor::block_on(runtime.run_event_loop()).unwrap();
+    runtime
+      .execute(
+        "check.js",
+        "if (asyncResult !== '[1,6]') throw new Error(asyncResult);",
+      )
+      .unwrap();
   }
 
   #[test]
-  fn test_encode_decode() {
-    run_in_task(|mut cx| {
-      let (mut runtime, _dispatch_count) = setup(Mode::Async);
-      runtime
-        .execute(
-          "encode_decode_test.js",
-          include_str!("encode_decode_test.js"),
-        )
-        .unwrap();
-      if let Poll::Ready(Err(_)) = runtime.poll_event_loop(&mut cx) {
-        unreachable!();
-      }
-    });
+  fn test_add_op_at_runtime() {
+    let mut runtime = JsRuntime::new(Default::default());
+    runtime.execute("ops.js", "Deno.core.ops();").unwrap();
+
+    runtime
+      .add_op_at_runtime(
+        "op_plugin",
+        crate::json_op_sync(|_state, args: serde_json::Value, _bufs| {
+          Ok(serde_json::json!(args.as_u64().unwrap() * 2))
+        }),
+      )
+      .unwrap();
+    runtime
+      .execute(
+        "plugin.js",
+        r#"
+        if (Deno.core.jsonOpSync("op_plugin", 21) !== 42) {
+          throw new Error("expected 42");
+        }
+        "#,
+      )
+      .unwrap();
+
+    let err = runtime
+      .add_op_at_runtime(
+        "op_plugin",
+        crate::json_op_sync(|_state, _args: serde_json::Value, _bufs| {
+          Ok(serde_json::Value::Null)
+        }),
+      )
+      .unwrap_err();
+    assert_eq!(
+      err.to_string(),
+      "An op named \"op_plugin\" is already registered"
+    );
   }
 
   #[test]
-  fn test_serialize_deserialize() {
-    run_in_task(|mut cx| {
+  fn test_remove_op_at_runtime() {
+    let mut runtime = JsRuntime::new(Default::default());
+    let op = || {
+      crate::json_op_sync(|_state, _args: serde_json::Value, _bufs| {
+        Ok(serde_json::json!(42))
+      })
+    };
+    runtime.register_op("op_open", op());
+    runtime.register_op("op_read", op());
+    runtime.execute("ops.js", "Deno.core.ops();").unwrap();
+
+    assert!(runtime.remove_op_at_runtime("op_open"));
+    assert!(!runtime.remove_op_at_runtime("op_open"));
+    assert!(!runtime.remove_op_at_runtime("op_missing"));
+    runtime
+      .execute(
+        "removed.js",
+        r#"
+        if ("op_open" in Deno.core.ops()) {
+          throw new Error("op_open wasn't removed");
+        }
+        let thrown;
+        try {
+          Deno.core.jsonOpSync("op_open");
+        } catch (e) {
+          thrown = e;
+        }
+        if (String(thrown) !== "TypeError: op_open is not a function") {
+          throw new Error(`unexpected error: ${thrown}`);
+        }
+        if (Deno.core.jsonOpSync("op_read") !== 42) {
+          throw new Error("expected 42");
+        }
+        "#,
+      )
+      .unwrap();
+
+    // The op can be added again.
+    runtime.add_op_at_runtime("op_open", op()).unwrap();
+    runtime
+      .execute("readded.js", "Deno.core.jsonOpSync('op_open');")
+      .unwrap();
+  }
+
+  #[test]
+  fn test_op_sync_result_v8() {
+    let mut runtime = JsRuntime::new(Default::default());
+    runtime.op_state().borrow_mut().put(0i32);
+    runtime.register_v8_op(
+      "op_increment",
+      crate::op_sync_result_v8(|scope, state, arg| {
+        let by = match v8::Local::<v8::Integer>::try_from(arg) {
+          Ok(by) => by.value() as i32,
+          // Hand the offending value back to JS as the exception.
+          Err(_) => return Err(arg),
+        };
+        let counter = state.borrow_mut::<i32>();
+        *counter += by;
+        Ok(v8::Integer::new(scope, *counter).into())
+      }),
+    );
+    runtime
+      .execute(
+        "v8_op.js",
+        r#"
+        if (Deno.core.v8OpSync("op_increment", 2) !== 2) {
+          throw new Error("expected 2");
+        }
+        if (Deno.core.v8OpSync("op_increment", 3) !== 5) {
+          throw new Error("expected 5");
+        }
+        const error = new SyntaxError("not a number");
+        let thrown;
+        try {
+          Deno.core.v8OpSync("op_increment", error);
+        } catch (e) {
+          thrown = e;
+        }
+        if (thrown !== error) {
+          throw new Error("expected the original exception to be rethrown");
+        }
+        let unknown;
+        try {
+          Deno.core.v8OpSync("op_unknown", 1);
+        } catch (e) {
+          unknown = e;
+        }
+        if (!(unknown instanceof TypeError)) {
+          throw new Error("expected unknown op to throw a TypeError");
+        }
+        "#,
+      )
+      .unwrap();
+    assert_eq!(*runtime.op_state().borrow().borrow::<i32>(), 5);
+  }
+
+  #[test]
+  fn test_register_native_function() {
+    #[derive(Deserialize)]
+    struct Point {
+      x: i32,
+      y: i32,
+    }
+
+    let mut runtime = JsRuntime::new(Default::default());
+    runtime.register_native_function("add", |(a, b): (i32, i32)| Ok(a + b));
+    runtime.register_native_function("norm", |p: Point| {
+      Ok(serde_json::json!({ "sum": p.x.abs() + p.y.abs() }))
+    });
+    runtime.register_native_function("fail", |msg: String| -> Result<(), _> {
+      Err(generic_error(msg))
+    });
+    runtime.register_native_function("double", |n: Option<i32>| {
+      Ok(n.map(|n| n * 2))
+    });
+    runtime
+      .execute(
+        "native_function.js",
+        r#"
+        if (add([2, 3]) !== 5) {
+          throw new Error("expected 5");
+        }
+        if (norm({ x: -1, y: 2 }).sum !== 3) {
+          throw new Error("expected 3");
+        }
+        if (double() !== null || double(4) !== 8) {
+          throw new Error("expected null and 8");
+        }
+        let thrown;
+        try {
+          fail("boom");
+        } catch (e) {
+          thrown = e;
+        }
+        if (!(thrown instanceof Error) || thrown.message !== "boom") {
+          throw new Error("expected the error to be thrown");
+        }
+        try {
+          add("not a pair");
+        } catch (e) {
+          thrown = e;
+        }
+        if (!(thrown instanceof TypeError)) {
+          throw new Error("expected a TypeError for an invalid argument");
+        }
+        "#,
+      )
+      .unwrap();
+  }
+
+  #[test]
+  fn test_run_event_loop_until_idle() {
+    let mut runtime = JsRuntime::new(Default::default());
+    runtime.register_op(
+      "op_tick",
+      crate::json_op_async(|_state, _args: serde_json::Value, _bufs| async {
+        Ok(serde_json::json!(null))
+      }),
+    );
+    runtime
+      .execute(
+        "ticks.js",
+        r#"
+        Deno.core.ops();
+        var ticks = 0;
+        function tick() {
+          if (++ticks < 10) {
+            Deno.core.jsonOpAsync("op_tick", {}).then(tick);
+          }
+        }
+        tick();
+        "#,
+      )
+      .unwrap();
+    futures::executor::block_on(runtime.run_event_loop_until_idle()).unwrap();
+    runtime
+      .execute("check.js", "if (ticks !== 10) throw new Error();")
+      .unwrap();
+
+    // With explicit microtasks, the callbacks of resolved ops only run when
+    // `run_event_loop_until_idle()` drains the queue after the event loop
+    // returned, which then dispatches the next op.
+    runtime
+      .v8_isolate()
+      .set_microtasks_policy(v8::MicrotasksPolicy::Explicit);
+    runtime
+      .execute(
+        "forever.js",
+        r#"
+        function forever() {
+          Deno.core.jsonOpAsync("op_tick", {}).then(forever);
+        }
+        forever();
+        "#,
+      )
+      .unwrap();
+    let err = futures::executor::block_on(runtime.run_event_loop_until_idle())
+      .unwrap_err();
+    let err = err.downcast::<InfiniteLoop>().unwrap();
+    assert_eq!(err.cycles, MAX_IDLE_CYCLES);
+  }
+
+  #[test]
+  fn test_run_event_loop_until_idle_many_wakeups() {
+    // Resolves after waking the event loop up more often than the cycle
+    // limit, like a timer that fires many times.
+    struct Wakeups(usize);
+
+    impl Future for Wakeups {
+      type Output = ();
+
+      fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<()> {
+        if self.0 == 0 {
+          return Poll::Ready(());
+        }
+        self.0 -= 1;
+        cx.waker().wake_by_ref();
+        Poll::Pending
+      }
+    }
+
+    let mut runtime = JsRuntime::new(Default::default());
+    runtime.register_op(
+      "op_wakeups",
+      crate::json_op_async(|_state, _args: serde_json::Value, _bufs| async {
+        Wakeups(MAX_IDLE_CYCLES * 2).await;
+        Ok(serde_json::json!(null))
+      }),
+    );
+    runtime
+      .execute(
+        "wakeups.js",
+        r#"
+        Deno.core.ops();
+        var done = false;
+        Deno.core.jsonOpAsync("op_wakeups", {}).then(() => {
+          done = true;
+        });
+        "#,
+      )
+      .unwrap();
+    futures::executor::block_on(runtime.run_event_loop_until_idle()).unwrap();
+    runtime
+      .execute("check.js", "if (!done) throw new Error();")
+      .unwrap();
+  }
+
+  #[test]
+  fn test_force_flush_op_results() {
+    let mut runtime = JsRuntime::new(Default::default());
+    let (tx, rx) = futures::channel::oneshot::channel::<u64>();
+    runtime.op_state().borrow_mut().put(Some(rx));
+    runtime.register_op(
+      "op_wait",
+      crate::json_op_async(
+        |state, _args: serde_json::Value, _bufs| async move {
+          let rx = state
+            .borrow_mut()
+            .borrow_mut::<Option<futures::channel::oneshot::Receiver<u64>>>()
+            .take()
+            .unwrap();
+          Ok(serde_json::json!(rx.await.unwrap()))
+        },
+      ),
+    );
+    runtime
+      .execute(
+        "flush.js",
+        r#"
+        Deno.core.ops();
+        var result = null;
+        Deno.core.jsonOpAsync("op_wait", {}).then((value) => {
+          result = value;
+        });
+        "#,
+      )
+      .unwrap();
+
+    let check = |runtime: &mut JsRuntime, expected: &str| {
+      runtime
+        .execute(
+          "check.js",
+          &format!(
+            "if (result !== {0}) throw new Error(`expected {0}, got ${{result}}`);",
+            expected
+          ),
+        )
+        .unwrap();
+    };
+
+    runtime.force_flush_op_results().unwrap();
+    check(&mut runtime, "null");
+
+    tx.send(42).unwrap();
+    runtime.force_flush_op_results().unwrap();
+    check(&mut runtime, "42");
+  }
+
+  #[test]
+  fn test_op_async_priority() {
+    run_in_task(|cx| {
+      let mut runtime = JsRuntime::new(Default::default());
+      let op = |_state, _args: serde_json::Value, _bufs| async { Ok(()) };
+      runtime.register_op("op_default", crate::json_op_async(op));
+      runtime.register_op(
+        "op_low",
+        crate::op_async_priority(200, crate::json_op_async(op)),
+      );
+      runtime.register_op(
+        "op_high",
+        crate::op_async_priority(10, crate::json_op_async(op)),
+      );
+      runtime
+        .execute(
+          "priority.js",
+          r#"
+          Deno.core.ops();
+          var order = [];
+          for (const op of ["op_low", "op_default", "op_high", "op_default"]) {
+            Deno.core.jsonOpAsync(op, {}).then(() => order.push(op));
+          }
+          "#,
+        )
+        .unwrap();
+      assert!(matches!(runtime.poll_event_loop(cx), Poll::Ready(Ok(_))));
+      runtime
+        .execute(
+          "check.js",
+          r#"
+          const expected = "op_high,op_default,op_default,op_low";
+          if (order.join() !== expected) {
+            throw new Error(`unexpected order: ${order}`);
+          }
+          "#,
+        )
+        .unwrap();
+    });
+  }
+
+  #[test]
+  fn core_test_js() {
+    run_in_task(|mut cx| {
+      let (mut runtime, _dispatch_count) = setup(Mode::Async);
+      runtime
+        .execute("core_test.js", include_str!("core_test.js"))
+        .unwrap();
+      if let Poll::Ready(Err(_)) = runtime.poll_event_loop(&mut cx) {
+        unreachable!();
+      }
+    });
+  }
+
+  #[test]
+  fn syntax_error() {
+    let mut runtime = JsRuntime::new(Default::default());
+    let src = "hocuspocus(";
+    let r = runtime.execute("i.js", src);
+    let e = r.unwrap_err();
+    let js_error = e.downcast::<JsError>().unwrap();
+    assert_eq!(js_error.end_column, Some(11));
+  }
+
+  #[test]
+  fn test_with_scope() {
+    let mut runtime = JsRuntime::new(Default::default());
+    runtime.execute("a.js", "var a = 40 + 2;").unwrap();
+    let a = runtime.with_scope(|scope| {
+      let global = scope.get_current_context().global(scope);
+      let key = v8::String::new(scope, "a").unwrap();
+      let value = global.get(scope, key.into()).unwrap();
+      value.int32_value(scope).unwrap()
+    });
+    assert_eq!(a, 42);
+  }
+
+  #[test]
+  fn test_performance_api() {
+    let mut runtime = JsRuntime::new(Default::default());
+    assert!(runtime.get_performance_entries().is_empty());
+    runtime.install_performance_api();
+    // Installing it again doesn't register the ops twice.
+    runtime.install_performance_api();
+    runtime
+      .execute(
+        "performance_test.js",
+        r#"
+        if (typeof performance.now() !== "number") {
+          throw new Error("performance.now() must return a number");
+        }
+        const start = performance.mark("start", { startTime: 10 });
+        if (!(start instanceof PerformanceMark)) {
+          throw new Error("mark() must return a PerformanceMark");
+        }
+        performance.mark("end", { startTime: 15 });
+        const measure = performance.measure("span", "start", "end");
+        if (measure.duration !== 5) {
+          throw new Error(`unexpected duration ${measure.duration}`);
+        }
+        if (performance.getEntriesByType("mark").length !== 2) {
+          throw new Error("expected two marks");
+        }
+        "#,
+      )
+      .unwrap();
+    let entries = runtime.get_performance_entries();
+    assert_eq!(entries.len(), 3);
+    assert_eq!(entries[0].name, "start");
+    assert_eq!(entries[0].entry_type, "mark");
+    assert_eq!(entries[2].name, "span");
+    assert_eq!(entries[2].entry_type, "measure");
+    assert!((entries[2].duration - 5.0).abs() < f64::EPSILON);
+
+    runtime
+      .execute("clear.js", "performance.clearMarks()")
+      .unwrap();
+    let entries = runtime.get_performance_entries();
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0].entry_type, "measure");
+  }
+
+  #[test]
+  fn test_wasm_instantiate_callback() {
+    let checked = Arc::new(AtomicUsize::new(0));
+    let checked_ = checked.clone();
+    let mut runtime = JsRuntime::new(RuntimeOptions {
+      wasm_instantiate_cb: Some(Box::new(move |bytes: &[u8]| {
+        checked_.fetch_add(1, Ordering::Relaxed);
+        // Only the empty module (magic number and version) is allowed.
+        if bytes.len() > 8 {
+          return Err(generic_error("module not allowed"));
+        }
+        Ok(())
+      })),
+      ..Default::default()
+    });
+    runtime
+      .execute(
+        "wasm.js",
+        r#"
+        const empty = new Uint8Array([0, 97, 115, 109, 1, 0, 0, 0]);
+        const other = new Uint8Array([...empty, 0, 0]);
+        if (!(new WebAssembly.Module(empty) instanceof WebAssembly.Module)) {
+          throw new Error("expected a WebAssembly.Module");
+        }
+        try {
+          new WebAssembly.Module(other);
+          throw new Error("expected the module to be blocked");
+        } catch (err) {
+          if (!(err instanceof WebAssembly.CompileError)) throw err;
+          if (err.message !== "module not allowed") throw err;
+        }
+        // The unchecked constructor isn't reachable through the prototype.
+        const constructors = [
+          WebAssembly.Module.prototype.constructor,
+          Object.getPrototypeOf(new WebAssembly.Module(empty)).constructor,
+        ];
+        for (const Module of constructors) {
+          try {
+            new Module(other);
+            throw new Error("expected the module to be blocked");
+          } catch (err) {
+            if (!(err instanceof WebAssembly.CompileError)) throw err;
+          }
+        }
+        // Shared sources are copied before they are checked and compiled.
+        const shared = new Uint8Array(new SharedArrayBuffer(empty.length));
+        shared.set(empty);
+        new WebAssembly.Module(shared);
+        WebAssembly.instantiate(empty.buffer).then(() => {
+          globalThis.instantiated = true;
+        });
+        WebAssembly.compile(other).catch((err) => {
+          globalThis.rejected = err instanceof WebAssembly.CompileError;
+        });
+        "#,
+      )
+      .unwrap();
+    poll_until_ready(&mut runtime, 10).unwrap();
+    runtime
+      .execute(
+        "check.js",
+        r#"
+        if (!globalThis.instantiated || !globalThis.rejected) {
+          throw new Error("unexpected promise results");
+        }
+        "#,
+      )
+      .unwrap();
+    assert_eq!(checked.load(Ordering::Relaxed), 8);
+  }
+
+  #[test]
+  fn test_console_handler() {
+    let output = Rc::new(RefCell::new(Vec::new()));
+    let output_ = output.clone();
+    let mut runtime = JsRuntime::new(RuntimeOptions {
+      console_handler: Some(Box::new(move |level, message| {
+        output_.borrow_mut().push((level, message));
+      })),
+      ..Default::default()
+    });
+    runtime
+      .execute(
+        "console.js",
+        r#"
+        console.log("hello", 42, { a: 1 });
+        console.warn("careful");
+        console.error("oops");
+        console.debug("details");
+        console.assert(1 === 2, "math");
+        "#,
+      )
+      .unwrap();
+    assert_eq!(
+      *output.borrow(),
+      vec![
+        (ConsoleLevel::Log, "hello 42 {\"a\":1}".to_string()),
+        (ConsoleLevel::Warn, "careful".to_string()),
+        (ConsoleLevel::Error, "oops".to_string()),
+        (ConsoleLevel::Debug, "details".to_string()),
+        (ConsoleLevel::Error, "Assertion failed: math".to_string()),
+      ]
+    );
+  }
+
+  #[test]
+  fn test_global_error_handler() {
+    let errors: Rc<RefCell<Vec<String>>> = Default::default();
+    let errors_ = errors.clone();
+    let mut runtime = JsRuntime::new(Default::default());
+    runtime.set_global_error_handler(Box::new(move |error| {
+      errors_.borrow_mut().push(error.message);
+    }));
+    runtime
+      .execute(
+        "errors.js",
+        r#"
+        var calls = 0;
+        Deno.core.setMacrotaskCallback(() => {
+          calls++;
+          if (calls === 1) {
+            throw new TypeError("in macrotask");
+          }
+          return true;
+        });
+        Promise.reject(new RangeError("in promise"));
+        "#,
+      )
+      .unwrap();
+
+    poll_until_ready(&mut runtime, 2).unwrap();
+    assert_eq!(
+      *errors.borrow(),
+      vec![
+        "Uncaught TypeError: in macrotask".to_string(),
+        "Uncaught (in promise) RangeError: in promise".to_string(),
+      ]
+    );
+    // The macrotask callback kept being called after the exception.
+    runtime
+      .execute("check.js", "if (calls !== 2) throw new Error('calls');")
+      .unwrap();
+  }
+
+  #[test]
+  fn test_abort_signal_factory() {
+    let mut runtime = JsRuntime::new(Default::default());
+    let signals = Rc::new(RefCell::new(Vec::new()));
+    let signals_ = signals.clone();
+    runtime.set_abort_signal_factory(Box::new(move || {
+      let (controller, signal) = crate::new_abort_signal();
+      signals_.borrow_mut().push(signal.clone());
+      (controller, signal)
+    }));
+    runtime.register_op(
+      "op_abort_signal_new",
+      crate::json_op_sync(crate::op_abort_signal_new),
+    );
+    runtime.register_op("op_abort", crate::json_op_sync(crate::op_abort));
+    runtime
+      .execute(
+        "abort.js",
+        r#"
+        Deno.core.ops();
+        const { controllerRid, signalRid } =
+          Deno.core.jsonOpSync("op_abort_signal_new", {});
+        if (controllerRid === signalRid) {
+          throw new Error("expected separate resources");
+        }
+        Deno.core.jsonOpSync("op_abort", { rid: controllerRid });
+        "#,
+      )
+      .unwrap();
+    let signals = signals.borrow();
+    assert_eq!(signals.len(), 1);
+    assert!(signals[0].is_aborted());
+  }
+
+  #[test]
+  fn test_error_event_callback() {
+    let events: Rc<RefCell<Vec<(String, String, u32)>>> = Default::default();
+    let events_ = events.clone();
+    let errors: Rc<RefCell<Vec<String>>> = Default::default();
+    let errors_ = errors.clone();
+    let mut runtime = JsRuntime::new(Default::default());
+    runtime.set_global_error_handler(Box::new(move |error| {
+      errors_.borrow_mut().push(error.message);
+    }));
+    runtime.set_error_event_callback(Box::new(move |event| {
+      let handled = event.message.ends_with(": handled");
+      events_
+        .borrow_mut()
+        .push((event.message, event.filename, event.lineno));
+      handled
+    }));
+    runtime
+      .execute(
+        "errors.js",
+        r#"var calls = 0;
+if (onerror("manual", "manual.js", 7, 2, new Error()) !== false) {
+  throw new Error("onerror");
+}
+Deno.core.setMacrotaskCallback(() => {
+  calls++;
+  if (calls === 1) throw new TypeError("handled");
+  if (calls === 2) throw new TypeError("unhandled");
+  if (calls === 3) {
+    globalThis.onerror = () => false;
+    throw new RangeError("assigned");
+  }
+  return true;
+});"#,
+      )
+      .unwrap();
+
+    poll_until_ready(&mut runtime, 4).unwrap();
+    assert_eq!(
+      *events.borrow(),
+      vec![
+        ("manual".to_string(), "manual.js".to_string(), 7),
+        (
+          "Uncaught TypeError: handled".to_string(),
+          "errors.js".to_string(),
+          7
+        ),
+        (
+          "Uncaught TypeError: unhandled".to_string(),
+          "errors.js".to_string(),
+          8
+        ),
+      ]
+    );
+    assert_eq!(
+      *errors.borrow(),
+      vec![
+        "Uncaught TypeError: unhandled".to_string(),
+        "Uncaught RangeError: assigned".to_string(),
+      ]
+    );
+  }
+
+  #[test]
+  fn test_create_worker() {
+    let (message_sender, messages) = std::sync::mpsc::channel();
+    let worker = JsRuntime::create_worker(
+      "file:///worker.js",
+      WorkerOptions {
+        source: r#"
+          onmessage = ({ data }) => postMessage(data * 2);
+          postMessage("ready");
+        "#
+        .to_string(),
+        init: None,
+        message_sender,
+      },
+    )
+    .unwrap();
+    let timeout = std::time::Duration::from_secs(10);
+    assert_eq!(messages.recv_timeout(timeout).unwrap(), "ready");
+
+    let handled = Arc::new(AtomicUsize::new(0));
+    let handled_ = handled.clone();
+    worker.on_message(move |_| {
+      handled_.fetch_add(1, Ordering::Relaxed);
+    });
+    worker.post_message(serde_json::json!(21)).unwrap();
+    assert_eq!(messages.recv_timeout(timeout).unwrap(), 42);
+    assert_eq!(handled.load(Ordering::Relaxed), 1);
+
+    worker.terminate();
+    assert!(worker.post_message(serde_json::json!(1)).is_err());
+    let _ = worker.join();
+  }
+
+  #[test]
+  fn test_join_worker() {
+    let (message_sender, messages) = std::sync::mpsc::channel();
+    let worker = JsRuntime::create_worker(
+      "file:///worker.js",
+      WorkerOptions {
+        source: r#"
+          await Promise.resolve();
+          postMessage("done");
+        "#
+        .to_string(),
+        init: None,
+        message_sender,
+      },
+    )
+    .unwrap();
+    // Joining a worker that wasn't terminated waits for it to finish.
+    worker.join().unwrap();
+    assert_eq!(messages.try_recv().unwrap(), "done");
+  }
+
+  #[test]
+  fn test_encode_decode() {
+    run_in_task(|mut cx| {
+      let (mut runtime, _dispatch_count) = setup(Mode::Async);
+      runtime
+        .execute(
+          "encode_decode_test.js",
+          include_str!("encode_decode_test.js"),
+        )
+        .unwrap();
+      if let Poll::Ready(Err(_)) = runtime.poll_event_loop(&mut cx) {
+        unreachable!();
+      }
+    });
+  }
+
+  #[test]
+  fn test_serialize_deserialize() {
+    run_in_task(|mut cx| {
       let (mut runtime, _dispatch_count) = setup(Mode::Async);
       runtime
         .execute(
@@ -2059,6 +4483,32 @@ pub mod tests {
     });
   }
 
+  #[test]
+  fn test_compiled_script() {
+    let mut runtime = JsRuntime::new(Default::default());
+    let script = runtime
+      .compile_expression("globalThis.x * 2", "expr.js")
+      .unwrap();
+    for x in 1..4 {
+      runtime
+        .execute("set_x.js", &format!("globalThis.x = {};", x))
+        .unwrap();
+      let value = runtime.run_compiled_script(&script).unwrap();
+      runtime.with_scope(|scope| {
+        let value = v8::Local::new(scope, value);
+        assert_eq!(value.integer_value(scope), Some(x * 2));
+      });
+    }
+
+    let script = runtime
+      .compile_expression("undefinedVariable + 1", "expr.js")
+      .unwrap();
+    let err = runtime.run_compiled_script(&script).unwrap_err();
+    assert!(err.to_string().contains("undefinedVariable is not defined"));
+
+    assert!(runtime.compile_expression("1 +", "expr.js").is_err());
+  }
+
   #[test]
   fn will_snapshot() {
     let snapshot = {
@@ -2080,6 +4530,44 @@ pub mod tests {
       .unwrap();
   }
 
+  #[test]
+  fn test_snapshot_includes_ops_check() {
+    fn op(_state: Rc<RefCell<OpState>>, _bufs: BufVec) -> Op {
+      Op::Sync(Box::new([]))
+    }
+
+    let snapshot: Box<[u8]> = {
+      let mut runtime = JsRuntime::new(RuntimeOptions {
+        will_snapshot: true,
+        ..Default::default()
+      });
+      runtime.register_op("op_a", op);
+      runtime.register_op("op_b", op);
+      Vec::from(&*runtime.snapshot()).into_boxed_slice()
+    };
+    let from_snapshot = |ops: &[&str]| {
+      let mut runtime = JsRuntime::new(RuntimeOptions {
+        startup_snapshot: Some(Snapshot::Boxed(snapshot.clone())),
+        ..Default::default()
+      });
+      for name in ops {
+        runtime.register_op(name, op);
+      }
+      runtime.snapshot_includes_ops_check()
+    };
+
+    from_snapshot(&["op_a", "op_b"]).unwrap();
+    from_snapshot(&["op_a", "op_b", "op_c"]).unwrap();
+    let err = from_snapshot(&["op_b"]).unwrap_err();
+    let err = err.downcast::<SnapshotOpMismatch>().unwrap();
+    assert_eq!(err.expected, vec!["ops", "op_a", "op_b"]);
+    assert_eq!(err.actual, vec!["ops", "op_b"]);
+    assert!(from_snapshot(&["op_b", "op_a"]).is_err());
+
+    let mut runtime = JsRuntime::new(Default::default());
+    runtime.snapshot_includes_ops_check().unwrap();
+  }
+
   #[test]
   fn test_from_boxed_snapshot() {
     let snapshot = {
@@ -2097,9 +4585,138 @@ pub mod tests {
       startup_snapshot: Some(snapshot),
       ..Default::default()
     });
-    runtime2
-      .execute("check.js", "if (a != 3) throw Error('x')")
-      .unwrap();
+    runtime2
+      .execute("check.js", "if (a != 3) throw Error('x')")
+      .unwrap();
+  }
+
+  #[test]
+  fn test_snapshot_with_partial_evaluation() {
+    struct ResolveOnlyLoader;
+
+    impl ModuleLoader for ResolveOnlyLoader {
+      fn resolve(
+        &self,
+        _op_state: Rc<RefCell<OpState>>,
+        specifier: &str,
+        referrer: &str,
+        _is_main: bool,
+      ) -> Result<ModuleSpecifier, AnyError> {
+        Ok(crate::resolve_import(specifier, referrer)?)
+      }
+
+      fn load(
+        &self,
+        _op_state: Rc<RefCell<OpState>>,
+        _module_specifier: &ModuleSpecifier,
+        _maybe_referrer: Option<ModuleSpecifier>,
+        _is_dyn_import: bool,
+      ) -> Pin<Box<ModuleSourceFuture>> {
+        unreachable!("snapshotted modules are never fetched")
+      }
+    }
+
+    let snapshot = {
+      let mut runtime = JsRuntime::new(RuntimeOptions {
+        will_snapshot: true,
+        module_loader: Some(Rc::new(ResolveOnlyLoader)),
+        ..Default::default()
+      });
+      runtime
+        .snapshot_with_partial_evaluation(vec![
+          (
+            "file:///ext/a.js".to_string(),
+            "globalThis.evaluated = (globalThis.evaluated ?? 0) + 1;
+          export const a = 1;"
+              .to_string(),
+          ),
+          (
+            "file:///ext/b.js".to_string(),
+            "import { a } from './a.js'; export const b = a + 1;".to_string(),
+          ),
+        ])
+        .unwrap()
+    };
+
+    // The deferred module runs in every runtime restored from the snapshot,
+    // when it is first imported.
+    let mut runtime = JsRuntime::new(RuntimeOptions {
+      startup_snapshot: Some(Snapshot::Boxed(snapshot.clone())),
+      module_loader: Some(Rc::new(ResolveOnlyLoader)),
+      ..Default::default()
+    });
+    let id = futures::executor::block_on(runtime.load_module(
+      &crate::resolve_url("file:///main.js").unwrap(),
+      Some(
+        "import { a } from './ext/a.js'; if (a !== 1) throw Error(a);".into(),
+      ),
+    ))
+    .unwrap();
+    futures::executor::block_on(runtime.mod_evaluate(id)).unwrap();
+    runtime
+      .execute(
+        "check.js",
+        "if (globalThis.evaluated !== 1) throw Error(globalThis.evaluated)",
+      )
+      .unwrap();
+
+    let mut runtime = JsRuntime::new(RuntimeOptions {
+      startup_snapshot: Some(Snapshot::Boxed(snapshot)),
+      module_loader: Some(Rc::new(ResolveOnlyLoader)),
+      ..Default::default()
+    });
+    runtime
+      .execute(
+        "check.js",
+        "if (globalThis.evaluated !== undefined) throw Error('evaluated')",
+      )
+      .unwrap();
+    let id = futures::executor::block_on(runtime.load_module(
+      &crate::resolve_url("file:///main.js").unwrap(),
+      Some(
+        "import { b } from './ext/b.js'; if (b !== 2) throw Error(b);".into(),
+      ),
+    ))
+    .unwrap();
+    futures::executor::block_on(runtime.mod_evaluate(id)).unwrap();
+    runtime
+      .execute(
+        "check.js",
+        "if (globalThis.evaluated !== 1) throw Error(globalThis.evaluated)",
+      )
+      .unwrap();
+
+    // Invalid module sets are rejected instead of being snapshotted.
+    let mut runtime = JsRuntime::new(RuntimeOptions {
+      will_snapshot: true,
+      module_loader: Some(Rc::new(ResolveOnlyLoader)),
+      ..Default::default()
+    });
+    let err = runtime
+      .snapshot_with_partial_evaluation(vec![(
+        "file:///ext/a.js".to_string(),
+        "export const a = ;".to_string(),
+      )])
+      .unwrap_err();
+    assert!(err
+      .to_string()
+      .starts_with("Failed to compile file:///ext/a.js"));
+
+    let mut runtime = JsRuntime::new(RuntimeOptions {
+      will_snapshot: true,
+      module_loader: Some(Rc::new(ResolveOnlyLoader)),
+      ..Default::default()
+    });
+    let err = runtime
+      .snapshot_with_partial_evaluation(vec![(
+        "file:///ext/b.js".to_string(),
+        "import { a } from './a.js'; export const b = a;".to_string(),
+      )])
+      .unwrap_err();
+    assert_eq!(
+      err.to_string(),
+      "file:///ext/b.js imports file:///ext/a.js, which is not part of the snapshot"
+    );
   }
 
   #[test]
@@ -2386,120 +5003,467 @@ pub mod tests {
       Ok(s)
     }
 
-    fn load(
-      &self,
-      _op_state: Rc<RefCell<OpState>>,
-      specifier: &ModuleSpecifier,
-      _maybe_referrer: Option<ModuleSpecifier>,
-      _is_dyn_import: bool,
-    ) -> Pin<Box<ModuleSourceFuture>> {
-      self.load_count.fetch_add(1, Ordering::Relaxed);
-      let info = ModuleSource {
-        module_url_specified: specifier.to_string(),
-        module_url_found: specifier.to_string(),
-        code: "export function b() { return 'b' }".to_owned(),
-      };
-      async move { Ok(info) }.boxed()
+    fn load(
+      &self,
+      _op_state: Rc<RefCell<OpState>>,
+      specifier: &ModuleSpecifier,
+      _maybe_referrer: Option<ModuleSpecifier>,
+      _is_dyn_import: bool,
+    ) -> Pin<Box<ModuleSourceFuture>> {
+      self.load_count.fetch_add(1, Ordering::Relaxed);
+      let info = ModuleSource {
+        module_url_specified: specifier.to_string(),
+        module_url_found: specifier.to_string(),
+        code: "export function b() { return 'b' }".to_owned(),
+      };
+      async move { Ok(info) }.boxed()
+    }
+
+    fn prepare_load(
+      &self,
+      _op_state: Rc<RefCell<OpState>>,
+      _load_id: ModuleLoadId,
+      _module_specifier: &ModuleSpecifier,
+      _maybe_referrer: Option<String>,
+      _is_dyn_import: bool,
+    ) -> Pin<Box<dyn Future<Output = Result<(), AnyError>>>> {
+      self.prepare_load_count.fetch_add(1, Ordering::Relaxed);
+      async { Ok(()) }.boxed_local()
+    }
+  }
+
+  #[test]
+  fn dyn_import_ok() {
+    run_in_task(|cx| {
+      let loader = Rc::new(DynImportOkLoader::default());
+      let prepare_load_count = loader.prepare_load_count.clone();
+      let resolve_count = loader.resolve_count.clone();
+      let load_count = loader.load_count.clone();
+      let mut runtime = JsRuntime::new(RuntimeOptions {
+        module_loader: Some(loader),
+        ..Default::default()
+      });
+
+      // Dynamically import mod_b
+      runtime
+        .execute(
+          "file:///dyn_import3.js",
+          r#"
+          (async () => {
+            let mod = await import("./b.js");
+            if (mod.b() !== 'b') {
+              throw Error("bad1");
+            }
+            // And again!
+            mod = await import("./b.js");
+            if (mod.b() !== 'b') {
+              throw Error("bad2");
+            }
+          })();
+          "#,
+        )
+        .unwrap();
+
+      // First poll runs `prepare_load` hook.
+      assert!(matches!(runtime.poll_event_loop(cx), Poll::Pending));
+      assert_eq!(prepare_load_count.load(Ordering::Relaxed), 1);
+
+      // Second poll actually loads modules into the isolate.
+      assert!(matches!(runtime.poll_event_loop(cx), Poll::Ready(Ok(_))));
+      assert_eq!(resolve_count.load(Ordering::Relaxed), 4);
+      assert_eq!(load_count.load(Ordering::Relaxed), 2);
+      assert!(matches!(runtime.poll_event_loop(cx), Poll::Ready(Ok(_))));
+      assert_eq!(resolve_count.load(Ordering::Relaxed), 4);
+      assert_eq!(load_count.load(Ordering::Relaxed), 2);
+    })
+  }
+
+  #[test]
+  fn dyn_import_borrow_mut_error() {
+    // https://github.com/denoland/deno/issues/6054
+    run_in_task(|cx| {
+      let loader = Rc::new(DynImportOkLoader::default());
+      let prepare_load_count = loader.prepare_load_count.clone();
+      let mut runtime = JsRuntime::new(RuntimeOptions {
+        module_loader: Some(loader),
+        ..Default::default()
+      });
+      runtime
+        .execute(
+          "file:///dyn_import3.js",
+          r#"
+          (async () => {
+            let mod = await import("./b.js");
+            if (mod.b() !== 'b') {
+              throw Error("bad");
+            }
+            // Now do any op
+            Deno.core.ops();
+          })();
+          "#,
+        )
+        .unwrap();
+      // First poll runs `prepare_load` hook.
+      let _ = runtime.poll_event_loop(cx);
+      assert_eq!(prepare_load_count.load(Ordering::Relaxed), 1);
+      // Second poll triggers error
+      let _ = runtime.poll_event_loop(cx);
+    })
+  }
+
+  #[test]
+  fn es_snapshot() {
+    #[derive(Default)]
+    struct ModsLoader;
+
+    impl ModuleLoader for ModsLoader {
+      fn resolve(
+        &self,
+        _op_state: Rc<RefCell<OpState>>,
+        specifier: &str,
+        referrer: &str,
+        _is_main: bool,
+      ) -> Result<ModuleSpecifier, AnyError> {
+        assert_eq!(specifier, "file:///main.js");
+        assert_eq!(referrer, ".");
+        let s = crate::resolve_import(specifier, referrer).unwrap();
+        Ok(s)
+      }
+
+      fn load(
+        &self,
+        _op_state: Rc<RefCell<OpState>>,
+        _module_specifier: &ModuleSpecifier,
+        _maybe_referrer: Option<ModuleSpecifier>,
+        _is_dyn_import: bool,
+      ) -> Pin<Box<ModuleSourceFuture>> {
+        unreachable!()
+      }
+    }
+
+    let loader = std::rc::Rc::new(ModsLoader::default());
+    let mut runtime = JsRuntime::new(RuntimeOptions {
+      module_loader: Some(loader),
+      will_snapshot: true,
+      ..Default::default()
+    });
+
+    let specifier = crate::resolve_url("file:///main.js").unwrap();
+    let source_code = "Deno.core.print('hello\\n')".to_string();
+
+    let module_id = futures::executor::block_on(
+      runtime.load_module(&specifier, Some(source_code)),
+    )
+    .unwrap();
+
+    futures::executor::block_on(runtime.mod_evaluate(module_id)).unwrap();
+
+    let _snapshot = runtime.snapshot();
+  }
+
+  #[test]
+  fn test_get_top_level_await_result() {
+    struct TlaLoader;
+
+    impl ModuleLoader for TlaLoader {
+      fn resolve(
+        &self,
+        _op_state: Rc<RefCell<OpState>>,
+        specifier: &str,
+        referrer: &str,
+        _is_main: bool,
+      ) -> Result<ModuleSpecifier, AnyError> {
+        Ok(crate::resolve_import(specifier, referrer)?)
+      }
+
+      fn load(
+        &self,
+        _op_state: Rc<RefCell<OpState>>,
+        _module_specifier: &ModuleSpecifier,
+        _maybe_referrer: Option<ModuleSpecifier>,
+        _is_dyn_import: bool,
+      ) -> Pin<Box<ModuleSourceFuture>> {
+        unreachable!()
+      }
+    }
+
+    let mut runtime = JsRuntime::new(RuntimeOptions {
+      module_loader: Some(Rc::new(TlaLoader)),
+      ..Default::default()
+    });
+    runtime.register_op(
+      "op_answer",
+      crate::json_op_async(|_state, _args: serde_json::Value, _bufs| async {
+        Ok(serde_json::json!(42))
+      }),
+    );
+    runtime.execute("ops.js", "Deno.core.ops();").unwrap();
+
+    let specifier = "file:///tla.js";
+    let source =
+      "globalThis.answer = await Deno.core.jsonOpAsync('op_answer', {});";
+    let id = futures::executor::block_on(runtime.load_module(
+      &crate::resolve_url(specifier).unwrap(),
+      Some(source.to_string()),
+    ))
+    .unwrap();
+
+    // Start the evaluation without driving the event loop, so the await is
+    // still pending when the result is asked for.
+    let _receiver = runtime.mod_evaluate_inner(id);
+    runtime
+      .execute("pending.js", "if ('answer' in globalThis) throw 'settled';")
+      .unwrap();
+
+    let result = futures::executor::block_on(
+      runtime.get_top_level_await_result(specifier),
+    )
+    .unwrap();
+    runtime.with_scope(|scope| {
+      assert!(result.get(scope).is_undefined());
+    });
+    runtime
+      .execute("settled.js", "if (answer !== 42) throw answer;")
+      .unwrap();
+
+    let specifier = "file:///tla_reject.js";
+    let source = "await Promise.resolve(); throw new Error('boom');";
+    futures::executor::block_on(runtime.load_module(
+      &crate::resolve_url(specifier).unwrap(),
+      Some(source.to_string()),
+    ))
+    .unwrap();
+    let err = futures::executor::block_on(
+      runtime.get_top_level_await_result(specifier),
+    )
+    .unwrap_err();
+    assert!(err.to_string().contains("boom"));
+
+    assert!(futures::executor::block_on(
+      runtime.get_top_level_await_result("file:///missing.js")
+    )
+    .is_err());
+  }
+
+  #[test]
+  fn test_module_evaluation_timeout() {
+    struct NoLoader;
+
+    impl ModuleLoader for NoLoader {
+      fn resolve(
+        &self,
+        _op_state: Rc<RefCell<OpState>>,
+        specifier: &str,
+        referrer: &str,
+        _is_main: bool,
+      ) -> Result<ModuleSpecifier, AnyError> {
+        Ok(crate::resolve_import(specifier, referrer)?)
+      }
+
+      fn load(
+        &self,
+        _op_state: Rc<RefCell<OpState>>,
+        _module_specifier: &ModuleSpecifier,
+        _maybe_referrer: Option<ModuleSpecifier>,
+        _is_dyn_import: bool,
+      ) -> Pin<Box<ModuleSourceFuture>> {
+        unreachable!()
+      }
+    }
+
+    let mut runtime = JsRuntime::new(RuntimeOptions {
+      module_loader: Some(Rc::new(NoLoader)),
+      module_evaluation_timeout: Some(Duration::from_millis(100)),
+      ..Default::default()
+    });
+
+    let specifier = crate::resolve_url("file:///loop.js").unwrap();
+    let id = futures::executor::block_on(
+      runtime.load_module(&specifier, Some("while (true) {}".to_string())),
+    )
+    .unwrap();
+    let err = futures::executor::block_on(runtime.mod_evaluate(id))
+      .expect_err("module evaluation should time out");
+    let err = err.downcast::<ModuleEvaluationTimeout>().unwrap();
+    assert_eq!(err.specifier, "file:///loop.js");
+    assert_eq!(err.timeout, Duration::from_millis(100));
+
+    // The runtime is still usable.
+    runtime
+      .execute("check.js", "if (1 + 1 !== 2) throw 1;")
+      .unwrap();
+
+    let specifier = crate::resolve_url("file:///quick.js").unwrap();
+    let id = futures::executor::block_on(
+      runtime.load_module(&specifier, Some("globalThis.x = 1;".to_string())),
+    )
+    .unwrap();
+    futures::executor::block_on(runtime.mod_evaluate(id)).unwrap();
+
+    runtime.set_module_evaluation_timeout(None);
+    let specifier = crate::resolve_url("file:///slow.js").unwrap();
+    let source = "const end = Date.now() + 200; while (Date.now() < end) {}";
+    let id = futures::executor::block_on(
+      runtime.load_module(&specifier, Some(source.to_string())),
+    )
+    .unwrap();
+    futures::executor::block_on(runtime.mod_evaluate(id)).unwrap();
+  }
+
+  #[cfg(debug_assertions)]
+  #[test]
+  fn test_module_mock() {
+    struct DepLoader;
+
+    impl ModuleLoader for DepLoader {
+      fn resolve(
+        &self,
+        _op_state: Rc<RefCell<OpState>>,
+        specifier: &str,
+        referrer: &str,
+        _is_main: bool,
+      ) -> Result<ModuleSpecifier, AnyError> {
+        Ok(crate::resolve_import(specifier, referrer)?)
+      }
+
+      fn load(
+        &self,
+        _op_state: Rc<RefCell<OpState>>,
+        module_specifier: &ModuleSpecifier,
+        _maybe_referrer: Option<ModuleSpecifier>,
+        _is_dyn_import: bool,
+      ) -> Pin<Box<ModuleSourceFuture>> {
+        assert_eq!(module_specifier.as_str(), "file:///dep.js");
+        let info = ModuleSource {
+          module_url_specified: module_specifier.to_string(),
+          module_url_found: module_specifier.to_string(),
+          code: "export const value = 'real';".to_string(),
+        };
+        async move { Ok(info) }.boxed()
+      }
     }
 
-    fn prepare_load(
-      &self,
-      _op_state: Rc<RefCell<OpState>>,
-      _load_id: ModuleLoadId,
-      _module_specifier: &ModuleSpecifier,
-      _maybe_referrer: Option<String>,
-      _is_dyn_import: bool,
-    ) -> Pin<Box<dyn Future<Output = Result<(), AnyError>>>> {
-      self.prepare_load_count.fetch_add(1, Ordering::Relaxed);
-      async { Ok(()) }.boxed_local()
-    }
+    let mut runtime = JsRuntime::new(RuntimeOptions {
+      module_loader: Some(Rc::new(DepLoader)),
+      ..Default::default()
+    });
+    let load_and_check = |runtime: &mut JsRuntime, name: &str, expected| {
+      let source = format!(
+        "import {{ value }} from './dep.js';
+        if (value !== '{0}') throw new Error(`expected {0}, got ${{value}}`);",
+        expected
+      );
+      let id = futures::executor::block_on(
+        runtime.load_module(&crate::resolve_url(name).unwrap(), Some(source)),
+      )
+      .unwrap();
+      futures::executor::block_on(runtime.mod_evaluate(id)).unwrap();
+    };
+
+    // Installed before the module is loaded.
+    runtime
+      .install_module_mock("file:///dep.js", "export const value = 'mock';")
+      .unwrap();
+    load_and_check(&mut runtime, "file:///a.js", "mock");
+    runtime.remove_module_mock("file:///dep.js").unwrap();
+    load_and_check(&mut runtime, "file:///b.js", "real");
+
+    // Installed after the module has been compiled.
+    runtime
+      .install_module_mock("file:///dep.js", "export const value = 'mock';")
+      .unwrap();
+    load_and_check(&mut runtime, "file:///c.js", "mock");
+    runtime.remove_module_mock("file:///dep.js").unwrap();
+    load_and_check(&mut runtime, "file:///d.js", "real");
   }
 
   #[test]
-  fn dyn_import_ok() {
-    run_in_task(|cx| {
-      let loader = Rc::new(DynImportOkLoader::default());
-      let prepare_load_count = loader.prepare_load_count.clone();
-      let resolve_count = loader.resolve_count.clone();
-      let load_count = loader.load_count.clone();
-      let mut runtime = JsRuntime::new(RuntimeOptions {
-        module_loader: Some(loader),
-        ..Default::default()
-      });
+  fn test_eval_async_iife() {
+    let mut runtime = JsRuntime::new(Default::default());
+    runtime.register_op(
+      "op_answer",
+      crate::json_op_async(|_state, _args: serde_json::Value, _bufs| async {
+        Ok(serde_json::json!(42))
+      }),
+    );
+    runtime.execute("ops.js", "Deno.core.ops();").unwrap();
 
-      // Dynamically import mod_b
-      runtime
-        .execute(
-          "file:///dyn_import3.js",
-          r#"
-          (async () => {
-            let mod = await import("./b.js");
-            if (mod.b() !== 'b') {
-              throw Error("bad1");
-            }
-            // And again!
-            mod = await import("./b.js");
-            if (mod.b() !== 'b') {
-              throw Error("bad2");
-            }
-          })();
-          "#,
-        )
+    let value = futures::executor::block_on(runtime.eval_async_iife(
+      "const answer = await Deno.core.jsonOpAsync('op_answer', {});
+      return answer + 1; // the wrapper isn't commented out",
+    ))
+    .unwrap();
+    runtime.with_scope(|scope| {
+      let value = v8::Local::new(scope, value);
+      assert_eq!(value.integer_value(scope), Some(43));
+    });
+
+    let value =
+      futures::executor::block_on(runtime.eval_async_iife("await null;"))
         .unwrap();
+    runtime.with_scope(|scope| {
+      assert!(v8::Local::new(scope, value).is_undefined());
+    });
 
-      // First poll runs `prepare_load` hook.
-      assert!(matches!(runtime.poll_event_loop(cx), Poll::Pending));
-      assert_eq!(prepare_load_count.load(Ordering::Relaxed), 1);
+    // Exceptions thrown before and after the first `await` are both returned,
+    // and aren't reported as unhandled rejections afterwards.
+    for body in &[
+      "throw new TypeError('before await');",
+      "await Deno.core.jsonOpAsync('op_answer', {}); throw new Error('after');",
+    ] {
+      let err =
+        futures::executor::block_on(runtime.eval_async_iife(body)).unwrap_err();
+      let js_error = err.downcast::<JsError>().unwrap();
+      assert!(js_error.message.starts_with("Uncaught"));
+    }
+    futures::executor::block_on(runtime.run_event_loop()).unwrap();
 
-      // Second poll actually loads modules into the isolate.
-      assert!(matches!(runtime.poll_event_loop(cx), Poll::Ready(Ok(_))));
-      assert_eq!(resolve_count.load(Ordering::Relaxed), 4);
-      assert_eq!(load_count.load(Ordering::Relaxed), 2);
-      assert!(matches!(runtime.poll_event_loop(cx), Poll::Ready(Ok(_))));
-      assert_eq!(resolve_count.load(Ordering::Relaxed), 4);
-      assert_eq!(load_count.load(Ordering::Relaxed), 2);
-    })
+    let err = futures::executor::block_on(
+      runtime.eval_async_iife("await new Promise(() => {});"),
+    )
+    .unwrap_err();
+    assert!(err.to_string().contains("still pending"));
   }
 
   #[test]
-  fn dyn_import_borrow_mut_error() {
-    // https://github.com/denoland/deno/issues/6054
-    run_in_task(|cx| {
-      let loader = Rc::new(DynImportOkLoader::default());
-      let prepare_load_count = loader.prepare_load_count.clone();
-      let mut runtime = JsRuntime::new(RuntimeOptions {
-        module_loader: Some(loader),
-        ..Default::default()
-      });
-      runtime
-        .execute(
-          "file:///dyn_import3.js",
-          r#"
-          (async () => {
-            let mod = await import("./b.js");
-            if (mod.b() !== 'b') {
-              throw Error("bad");
-            }
-            // Now do any op
-            Deno.core.ops();
-          })();
-          "#,
-        )
-        .unwrap();
-      // First poll runs `prepare_load` hook.
-      let _ = runtime.poll_event_loop(cx);
-      assert_eq!(prepare_load_count.load(Ordering::Relaxed), 1);
-      // Second poll triggers error
-      let _ = runtime.poll_event_loop(cx);
-    })
+  fn test_create_shared_array_buffer() {
+    let mut runtime = JsRuntime::new(Default::default());
+    let buffer =
+      runtime.create_shared_array_buffer(vec![1, 2, 3].into_boxed_slice());
+    let backing_store = runtime.with_scope(|scope| {
+      let buffer = v8::Local::new(scope, &buffer);
+      assert_eq!(buffer.byte_length(), 3);
+      let key = v8::String::new(scope, "buffer").unwrap();
+      let global = scope.get_current_context().global(scope);
+      global.set(scope, key.into(), buffer.into());
+      buffer.get_backing_store()
+    });
+    runtime
+      .execute(
+        "buffer.js",
+        r#"
+        if (!(buffer instanceof SharedArrayBuffer)) {
+          throw new Error("not a SharedArrayBuffer");
+        }
+        const bytes = new Uint8Array(buffer);
+        if (bytes.join() !== "1,2,3") {
+          throw new Error("unexpected contents");
+        }
+        bytes[0] = 42;
+        "#,
+      )
+      .unwrap();
+
+    // Writes of JS are visible to Rust through the backing store.
+    let bytes: Vec<u8> = backing_store.iter().map(|byte| byte.get()).collect();
+    assert_eq!(bytes, vec![42, 2, 3]);
   }
 
   #[test]
-  fn es_snapshot() {
-    #[derive(Default)]
-    struct ModsLoader;
+  fn test_eval_with_import_map() {
+    struct ReactLoader;
 
-    impl ModuleLoader for ModsLoader {
+    impl ModuleLoader for ReactLoader {
       fn resolve(
         &self,
         _op_state: Rc<RefCell<OpState>>,
@@ -2507,41 +5471,47 @@ pub mod tests {
         referrer: &str,
         _is_main: bool,
       ) -> Result<ModuleSpecifier, AnyError> {
-        assert_eq!(specifier, "file:///main.js");
-        assert_eq!(referrer, ".");
-        let s = crate::resolve_import(specifier, referrer).unwrap();
-        Ok(s)
+        Ok(crate::resolve_import(specifier, referrer)?)
       }
 
       fn load(
         &self,
         _op_state: Rc<RefCell<OpState>>,
-        _module_specifier: &ModuleSpecifier,
+        module_specifier: &ModuleSpecifier,
         _maybe_referrer: Option<ModuleSpecifier>,
         _is_dyn_import: bool,
       ) -> Pin<Box<ModuleSourceFuture>> {
-        unreachable!()
+        assert_eq!(module_specifier.as_str(), "https://esm.sh/react");
+        let module = ModuleSource {
+          code: "export const name = 'react';".to_string(),
+          module_url_specified: module_specifier.to_string(),
+          module_url_found: module_specifier.to_string(),
+        };
+        async move { Ok(module) }.boxed()
       }
     }
 
-    let loader = std::rc::Rc::new(ModsLoader::default());
     let mut runtime = JsRuntime::new(RuntimeOptions {
-      module_loader: Some(loader),
-      will_snapshot: true,
+      module_loader: Some(Rc::new(ReactLoader)),
       ..Default::default()
     });
-
-    let specifier = crate::resolve_url("file:///main.js").unwrap();
-    let source_code = "Deno.core.print('hello\\n')".to_string();
-
-    let module_id = futures::executor::block_on(
-      runtime.load_module(&specifier, Some(source_code)),
-    )
+    futures::executor::block_on(runtime.eval_with_import_map(
+      "import { name } from 'react'; globalThis.imported = name;",
+      r#"{ "imports": { "react": "https://esm.sh/react" } }"#,
+    ))
     .unwrap();
+    runtime
+      .execute(
+        "check.js",
+        "if (imported !== 'react') throw new Error(imported);",
+      )
+      .unwrap();
 
-    futures::executor::block_on(runtime.mod_evaluate(module_id)).unwrap();
-
-    let _snapshot = runtime.snapshot();
+    let err = futures::executor::block_on(
+      runtime.eval_with_import_map("import 'react';", "not json"),
+    )
+    .unwrap_err();
+    assert_eq!(err.to_string(), "Unable to parse import map JSON");
   }
 
   #[test]
@@ -2641,4 +5611,251 @@ main();
     // Test that the script specifier is a URL: `deno:<repo-relative path>`.
     assert!(error_string.contains("deno:core/core.js"));
   }
+
+  #[cfg(feature = "debug_hooks")]
+  #[test]
+  fn test_register_promise_hook() {
+    let mut runtime = JsRuntime::new(Default::default());
+    let events = Rc::new(RefCell::new(Vec::new()));
+    let events_ = events.clone();
+    runtime.register_promise_hook(Box::new(move |hook_type, _promise| {
+      events_.borrow_mut().push(("first", hook_type));
+    }));
+    let events_ = events.clone();
+    runtime.register_promise_hook(Box::new(move |hook_type, _promise| {
+      events_.borrow_mut().push(("second", hook_type));
+    }));
+    runtime
+      .execute("promise_hook.js", "new Promise((resolve) => resolve(1));")
+      .unwrap();
+    assert_eq!(
+      *events.borrow(),
+      vec![
+        ("first", v8::PromiseHookType::Init),
+        ("second", v8::PromiseHookType::Init),
+        ("first", v8::PromiseHookType::Resolve),
+        ("second", v8::PromiseHookType::Resolve),
+      ]
+    );
+
+    events.borrow_mut().clear();
+    runtime
+      .execute("promise_hook_then.js", "Promise.resolve().then(() => {});")
+      .unwrap();
+    runtime.v8_isolate().perform_microtask_checkpoint();
+    let events = events.borrow();
+    for hook_type in &[v8::PromiseHookType::Before, v8::PromiseHookType::After]
+    {
+      assert!(events.contains(&("first", *hook_type)));
+      assert!(events.contains(&("second", *hook_type)));
+    }
+  }
+
+  #[cfg(feature = "profiling")]
+  #[test]
+  fn test_profiler() {
+    let mut runtime = JsRuntime::new(Default::default());
+    assert!(runtime.profiler_stop().is_err());
+    runtime.profiler_start("test").unwrap();
+    assert!(runtime.profiler_start("again").is_err());
+    runtime
+      .execute(
+        "profiler.js",
+        r#"
+          function spin() {
+            let n = 0;
+            for (let i = 0; i < 1e7; i++) n += i;
+            return n;
+          }
+          spin();
+        "#,
+      )
+      .unwrap();
+    let profile = runtime.profiler_stop().unwrap();
+    assert_eq!(profile.title, "test");
+    assert!(profile.end_time >= profile.start_time);
+    assert!(profile
+      .nodes
+      .iter()
+      .any(|node| node.call_frame.function_name == "(root)"));
+    assert!(profile.to_chrome_json().starts_with("{\"nodes\":"));
+
+    // The profiler can be started again once stopped.
+    runtime.profiler_start("test2").unwrap();
+    runtime.profiler_stop().unwrap();
+  }
+
+  #[test]
+  fn test_create_object_template() {
+    let mut runtime = JsRuntime::new(Default::default());
+    let counter = Rc::new(Cell::new(0));
+    let counter_ = counter.clone();
+    let counter__ = counter.clone();
+    // The accessors must not go through the JS-visible globals.
+    runtime
+      .execute(
+        "tamper.js",
+        r#"
+        Object.defineProperty = () => {
+          throw new Error("replaced defineProperty was called");
+        };
+        Object.prototype.value = 1;
+        "#,
+      )
+      .unwrap();
+    runtime
+      .create_object_template()
+      .set_function("add", |(a, b): (i32, i32)| Ok(a + b))
+      .set_value("config", serde_json::json!({ "name": "lib", "n": [1, 2] }))
+      .set_accessor(
+        "counter",
+        move || Ok(counter_.get()),
+        move |n: i32| {
+          counter__.set(n);
+          Ok(())
+        },
+      )
+      .set_getter("version", || Ok("1.0.0"))
+      .install_as_global("MyLib")
+      .unwrap();
+    runtime
+      .execute(
+        "object_template.js",
+        r#"
+        "use strict";
+        if (MyLib.add([2, 3]) !== 5) {
+          throw new Error("expected 5");
+        }
+        if (MyLib.config.name !== "lib" || MyLib.config.n[1] !== 2) {
+          throw new Error("unexpected config");
+        }
+        MyLib.counter = MyLib.counter + 42;
+        if (MyLib.counter !== 42 || MyLib.version !== "1.0.0") {
+          throw new Error("unexpected accessors");
+        }
+        let thrown;
+        try {
+          MyLib.version = "2.0.0";
+        } catch (e) {
+          thrown = e;
+        }
+        if (!(thrown instanceof TypeError)) {
+          throw new Error("expected the read-only property to throw");
+        }
+        const keys = Object.keys(MyLib).sort().join();
+        if (keys !== "add,config,counter,version") {
+          throw new Error(`unexpected keys: ${keys}`);
+        }
+        "#,
+      )
+      .unwrap();
+    assert_eq!(counter.get(), 42);
+
+    runtime
+      .execute(
+        "setter.js",
+        r#"
+        delete Object.prototype.value;
+        Reflect.defineProperty(globalThis, "Trap", {
+          set() { throw new Error("trap"); },
+        });
+        "#,
+      )
+      .unwrap();
+    let err = runtime
+      .create_object_template()
+      .set_getter("version", || Ok("1.0.0"))
+      .install_as_global("Trap")
+      .unwrap_err();
+    assert!(err.to_string().contains("trap"));
+  }
+
+  crate::js_object! {
+    struct Counter {
+      fn increment(by: u32) -> u32;
+      fn reset() -> ();
+      fn describe(prefix: String, suffix: String) -> String;
+      fn fail() -> ();
+    }
+  }
+
+  #[test]
+  fn test_eval_and_bind() {
+    let mut runtime = JsRuntime::new(Default::default());
+    let counter = runtime
+      .eval_and_bind::<Counter>(
+        r#"({
+          count: 0,
+          increment(by) { return this.count += by; },
+          reset() { this.count = 0; },
+          describe(prefix, suffix) { return prefix + this.count + suffix; },
+          fail() { throw new Error("failed"); },
+        })"#,
+      )
+      .unwrap();
+    assert_eq!(counter.increment(&mut runtime, 2).unwrap(), 2);
+    assert_eq!(counter.increment(&mut runtime, 3).unwrap(), 5);
+    assert_eq!(
+      counter
+        .describe(&mut runtime, "<".to_string(), ">".to_string())
+        .unwrap(),
+      "<5>"
+    );
+    counter.reset(&mut runtime).unwrap();
+    assert_eq!(counter.increment(&mut runtime, 1).unwrap(), 1);
+    let err = counter.fail(&mut runtime).unwrap_err();
+    assert_eq!(err.to_string(), "Uncaught Error: failed");
+
+    let err = runtime.eval_and_bind::<Counter>("1 + 1").err().unwrap();
+    assert_eq!(err.to_string(), "The script did not evaluate to an object");
+    let missing = runtime.eval_and_bind::<Counter>("({})").unwrap();
+    let err = missing.increment(&mut runtime, 1).unwrap_err();
+    assert_eq!(err.to_string(), "increment is not a function");
+  }
+
+  #[test]
+  fn test_eval_expression_with_locals() {
+    let mut runtime = JsRuntime::new(Default::default());
+    let mut locals = HashMap::new();
+    locals.insert("a", serde_json::json!(2));
+    locals.insert("b", serde_json::json!({ "c": [3, 4] }));
+    let sum: u32 = runtime
+      .eval_expression_with_locals("a + b.c[0] + b.c[1] // sum", locals)
+      .unwrap();
+    assert_eq!(sum, 9);
+
+    // The locals don't leak into the global scope.
+    let defined: bool = runtime
+      .eval_expression_with_locals("typeof a !== 'undefined'", HashMap::new())
+      .unwrap();
+    assert!(!defined);
+    let result: Option<u32> = runtime
+      .eval_expression_with_locals("undefined", HashMap::new())
+      .unwrap();
+    assert_eq!(result, None);
+
+    for name in &["1a", "a-b", "", "a); globalThis.x = (1", "this"] {
+      let mut locals = HashMap::new();
+      locals.insert(*name, serde_json::json!(1));
+      let err = runtime
+        .eval_expression_with_locals::<u32>("1", locals)
+        .unwrap_err();
+      assert_eq!(
+        err.to_string(),
+        format!("Invalid local variable name: \"{}\"", name)
+      );
+    }
+
+    let err = runtime
+      .eval_expression_with_locals::<u32>(
+        "(() => { throw new Error('failed'); })()",
+        HashMap::new(),
+      )
+      .unwrap_err();
+    assert_eq!(err.to_string(), "Uncaught Error: failed");
+    let err = runtime
+      .eval_expression_with_locals::<u32>("'a'", HashMap::new())
+      .unwrap_err();
+    assert!(err.to_string().starts_with("Invalid result of expression"));
+  }
 }
@@ -0,0 +1,46 @@
+// Copyright 2018-2021 the Deno authors. All rights reserved. MIT license.
+
+// The stack size is process-wide, so this test runs in a process of its own.
+
+use deno_core::set_v8_stack_size;
+use deno_core::JsRuntime;
+use deno_core::WorkerOptions;
+
+#[test]
+fn test_v8_stack_size() {
+  assert!(set_v8_stack_size(0).is_err());
+  assert!(set_v8_stack_size(1000).is_err());
+  set_v8_stack_size(512 * 1024).unwrap();
+  assert!(set_v8_stack_size(1024 * 1024).is_err());
+
+  let (message_sender, messages) = std::sync::mpsc::channel();
+  let worker = JsRuntime::create_worker(
+    "file:///stack.js",
+    WorkerOptions {
+      source: r#"
+        let depth = 0;
+        function recurse() {
+          depth++;
+          recurse();
+        }
+        try {
+          recurse();
+        } catch (e) {
+          postMessage([e instanceof RangeError, depth]);
+        }
+      "#
+      .to_string(),
+      init: None,
+      message_sender,
+    },
+  )
+  .unwrap();
+  let timeout = std::time::Duration::from_secs(10);
+  let message = messages.recv_timeout(timeout).unwrap();
+  assert_eq!(message[0], true);
+  assert!(message[1].as_u64().unwrap() > 0);
+  worker.join().unwrap();
+
+  // Runtimes exist now, so the size can't be set anymore.
+  assert!(set_v8_stack_size(512 * 1024).is_err());
+}
@@ -0,0 +1,101 @@
+// Copyright 2018-2021 the Deno authors. All rights reserved. MIT license.
+"use strict";
+
+((window) => {
+  const core = window.Deno.core;
+  const { WebAssembly } = window;
+
+  // Throws if the embedder's callback rejects the module, and otherwise
+  // returns the source to compile. Bytes are copied into a fresh ArrayBuffer
+  // before they are checked, and the copy is what gets compiled, so the
+  // source can't be changed in between, e.g. through a SharedArrayBuffer.
+  // Instances of `WebAssembly.Module` are returned as they are: their bytes
+  // were checked when they were compiled.
+  function check(source) {
+    let bytes;
+    if (ArrayBuffer.isView(source)) {
+      bytes = new Uint8Array(
+        source.buffer,
+        source.byteOffset,
+        source.byteLength,
+      );
+    } else if (
+      source instanceof ArrayBuffer ||
+      source instanceof SharedArrayBuffer
+    ) {
+      bytes = new Uint8Array(source);
+    } else {
+      return source;
+    }
+    const copy = new Uint8Array(bytes.length);
+    copy.set(bytes);
+    const message = core.jsonOpSync("op_wasm_instantiate_check", null, copy);
+    if (message !== null) {
+      throw new WebAssembly.CompileError(message);
+    }
+    return copy.buffer;
+  }
+
+  const { compile, instantiate, compileStreaming, instantiateStreaming } =
+    WebAssembly;
+
+  function defineMethod(name, fn) {
+    Object.defineProperty(WebAssembly, name, {
+      value: fn,
+      writable: true,
+      configurable: true,
+    });
+  }
+
+  defineMethod("compile", function compileChecked(source) {
+    try {
+      source = check(source);
+    } catch (err) {
+      return Promise.reject(err);
+    }
+    return compile.call(WebAssembly, source);
+  });
+
+  defineMethod("instantiate", function instantiateChecked(source, imports) {
+    try {
+      source = check(source);
+    } catch (err) {
+      return Promise.reject(err);
+    }
+    return instantiate.call(WebAssembly, source, imports);
+  });
+
+  // The streaming variants, if present, are buffered so that the callback
+  // sees the whole module before compilation starts.
+  if (compileStreaming) {
+    defineMethod("compileStreaming", async function (source) {
+      const response = await source;
+      return WebAssembly.compile(await response.arrayBuffer());
+    });
+  }
+
+  if (instantiateStreaming) {
+    defineMethod("instantiateStreaming", async function (source, imports) {
+      const response = await source;
+      return WebAssembly.instantiate(await response.arrayBuffer(), imports);
+    });
+  }
+
+  // A proxy keeps `instanceof WebAssembly.Module`, the prototype and the
+  // static methods working.
+  const Module = new Proxy(WebAssembly.Module, {
+    construct(target, args, newTarget) {
+      const source = check(args[0]);
+      return Reflect.construct(target, [source, ...args.slice(1)], newTarget);
+    },
+  });
+  defineMethod("Module", Module);
+  // Otherwise the unchecked constructor would stay reachable through
+  // `WebAssembly.Module.prototype.constructor` and the modules' prototype.
+  Object.defineProperty(WebAssembly.Module.prototype, "constructor", {
+    value: Module,
+    writable: false,
+    enumerable: false,
+    configurable: false,
+  });
+})(this);
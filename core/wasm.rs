@@ -0,0 +1,52 @@
+// Copyright 2018-2021 the Deno authors. All rights reserved. MIT license.
+
+//! Support for `JsRuntime::set_wasm_instantiate_callback()`.
+//!
+//! V8 doesn't offer a hook that runs before a WebAssembly module is compiled,
+//! so `wasm.js` wraps the `WebAssembly` entry points that accept bytes and
+//! hands those bytes to the callback through `op_wasm_instantiate_check`.
+
+use crate::error::AnyError;
+use crate::json_op_sync;
+use crate::JsRuntime;
+use crate::OpState;
+use crate::ZeroCopyBuf;
+use serde_json::json;
+use serde_json::Value;
+
+pub type WasmInstantiateCb = dyn Fn(&[u8]) -> Result<(), AnyError>;
+
+struct WasmInstantiateCallback(Box<WasmInstantiateCb>);
+
+pub(crate) fn set_callback(rt: &mut JsRuntime, cb: Box<WasmInstantiateCb>) {
+  let op_state = rt.op_state();
+  let installed = op_state.borrow().has::<WasmInstantiateCallback>();
+  op_state.borrow_mut().put(WasmInstantiateCallback(cb));
+  if installed {
+    return;
+  }
+  rt.add_op_at_runtime(
+    "op_wasm_instantiate_check",
+    json_op_sync(op_wasm_instantiate_check),
+  )
+  .unwrap();
+  rt.execute("deno:core/wasm.js", include_str!("wasm.js"))
+    .unwrap();
+}
+
+/// Returns `null` if the module may be compiled, or the message of the error
+/// returned by the callback otherwise. The JS side turns that message into a
+/// `WebAssembly.CompileError`, regardless of the error's class.
+#[allow(clippy::unnecessary_wraps)]
+fn op_wasm_instantiate_check(
+  state: &mut OpState,
+  _args: Value,
+  zero_copy: &mut [ZeroCopyBuf],
+) -> Result<Value, AnyError> {
+  let bytes: &[u8] = zero_copy.get(0).map(|buf| &buf[..]).unwrap_or(&[]);
+  let cb = &state.borrow::<WasmInstantiateCallback>().0;
+  match cb(bytes) {
+    Ok(()) => Ok(json!(null)),
+    Err(err) => Ok(json!(err.to_string())),
+  }
+}
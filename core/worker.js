@@ -0,0 +1,37 @@
+// Copyright 2018-2021 the Deno authors. All rights reserved. MIT license.
+"use strict";
+
+((window) => {
+  const core = window.Deno.core;
+
+  function postMessage(message) {
+    core.jsonOpSync("op_worker_post_message", { message });
+  }
+
+  async function messageLoop() {
+    while (true) {
+      const next = await core.jsonOpAsync("op_worker_get_message");
+      if (next === null) {
+        return;
+      }
+      if (typeof window.onmessage === "function") {
+        window.onmessage({ data: next.message });
+      }
+    }
+  }
+
+  Object.defineProperties(window, {
+    postMessage: {
+      value: postMessage,
+      writable: true,
+      configurable: true,
+    },
+    onmessage: {
+      value: null,
+      writable: true,
+      configurable: true,
+    },
+  });
+
+  messageLoop();
+})(this);
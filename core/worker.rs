@@ -0,0 +1,222 @@
+// Copyright 2018-2021 the Deno authors. All rights reserved. MIT license.
+
+//! Workers created by `JsRuntime::create_worker()`. A worker evaluates a
+//! module in its own `JsRuntime` on its own OS thread, and exchanges JSON
+//! messages with the thread that created it.
+
+use crate::error::generic_error;
+use crate::error::AnyError;
+use crate::json_op_async;
+use crate::json_op_sync;
+use crate::resolve_url;
+use crate::runtime::v8_stack_size;
+use crate::AsyncRefCell;
+use crate::BufVec;
+use crate::JsRuntime;
+use crate::OpState;
+use crate::RuntimeOptions;
+use crate::ZeroCopyBuf;
+use futures::channel::mpsc;
+use futures::StreamExt;
+use rusty_v8 as v8;
+use serde::Deserialize;
+use serde_json::json;
+use serde_json::Value;
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::sync::mpsc::Sender;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::thread;
+
+/// Called on the worker thread with the worker's runtime before its module
+/// is loaded, e.g. to register the ops the worker may use.
+pub type WorkerInitFn = dyn FnOnce(&mut JsRuntime) + Send;
+
+/// Called on the worker thread for every message the worker posts.
+pub type WorkerMessageHandler = dyn Fn(&Value) + Send;
+
+pub struct WorkerOptions {
+  /// Source code of the worker's main module.
+  pub source: String,
+
+  /// Sets up the worker's runtime; see `WorkerInitFn`.
+  pub init: Option<Box<WorkerInitFn>>,
+
+  /// Receives the messages the worker posts with `postMessage()`.
+  pub message_sender: Sender<Value>,
+}
+
+/// Thread stack reserved on top of the stack size set by
+/// `set_v8_stack_size()`, for the native frames below the JS ones.
+const WORKER_STACK_HEADROOM: usize = 1024 * 1024;
+
+/// The creating thread's side of a worker.
+///
+/// Dropping the handle stops delivering messages to the worker, which lets
+/// an idle worker's event loop finish, but doesn't interrupt running code.
+pub struct WorkerHandle {
+  sender: mpsc::UnboundedSender<Value>,
+  handlers: Arc<Mutex<Vec<Box<WorkerMessageHandler>>>>,
+  isolate_handle: v8::IsolateHandle,
+  thread: Option<thread::JoinHandle<Result<(), AnyError>>>,
+}
+
+impl WorkerHandle {
+  /// Sends a message to the worker, where it's passed to `onmessage` as the
+  /// `data` property of the event.
+  pub fn post_message(&self, msg: Value) -> Result<(), AnyError> {
+    self
+      .sender
+      .unbounded_send(msg)
+      .map_err(|_| generic_error("Worker has been terminated"))
+  }
+
+  /// Adds a handler that is called with every message the worker posts, in
+  /// addition to sending it to `WorkerOptions::message_sender`.
+  pub fn on_message<F>(&self, handler: F)
+  where
+    F: Fn(&Value) + Send + 'static,
+  {
+    self.handlers.lock().unwrap().push(Box::new(handler));
+  }
+
+  /// Stops the worker: running JavaScript is interrupted and no more
+  /// messages are delivered to it.
+  pub fn terminate(&self) {
+    self.sender.close_channel();
+    self.isolate_handle.terminate_execution();
+  }
+
+  /// Stops delivering messages to the worker and waits for its thread to
+  /// exit, which happens once the worker's event loop has nothing left to do.
+  /// Returns the error that stopped the worker, if any. A terminated worker
+  /// usually returns an "execution terminated" error.
+  pub fn join(mut self) -> Result<(), AnyError> {
+    // The worker's pending `op_worker_get_message` keeps its event loop alive
+    // until the channel is closed.
+    self.sender.close_channel();
+    match self.thread.take().unwrap().join() {
+      Ok(result) => result,
+      Err(_) => Err(generic_error("Worker thread panicked")),
+    }
+  }
+}
+
+impl Drop for WorkerHandle {
+  fn drop(&mut self) {
+    self.sender.close_channel();
+  }
+}
+
+struct WorkerState {
+  receiver: Rc<AsyncRefCell<mpsc::UnboundedReceiver<Value>>>,
+  message_sender: Sender<Value>,
+  handlers: Arc<Mutex<Vec<Box<WorkerMessageHandler>>>>,
+}
+
+pub(crate) fn create(
+  specifier: &str,
+  options: WorkerOptions,
+) -> Result<WorkerHandle, AnyError> {
+  let specifier = resolve_url(specifier)?;
+  let (sender, receiver) = mpsc::unbounded();
+  let handlers: Arc<Mutex<Vec<Box<WorkerMessageHandler>>>> = Default::default();
+  let (handle_tx, handle_rx) = std::sync::mpsc::sync_channel(1);
+
+  let WorkerOptions {
+    source,
+    init,
+    message_sender,
+  } = options;
+  let worker_handlers = handlers.clone();
+  let mut builder =
+    thread::Builder::new().name(format!("worker {}", specifier));
+  if let Some(size) = v8_stack_size() {
+    builder = builder.stack_size(size + WORKER_STACK_HEADROOM);
+  }
+  let thread = builder.spawn(move || {
+    let mut runtime = JsRuntime::new(RuntimeOptions::default());
+    handle_tx.send(runtime.isolate_handle()).unwrap();
+    init_runtime(&mut runtime, receiver, message_sender, worker_handlers);
+    if let Some(init) = init {
+      init(&mut runtime);
+    }
+    futures::executor::block_on(async {
+      let id = runtime.load_module(&specifier, Some(source)).await?;
+      runtime.mod_evaluate(id).await?;
+      runtime.run_event_loop().await
+    })
+  })?;
+
+  let isolate_handle = handle_rx
+    .recv()
+    .map_err(|_| generic_error("Worker failed to start"))?;
+
+  Ok(WorkerHandle {
+    sender,
+    handlers,
+    isolate_handle,
+    thread: Some(thread),
+  })
+}
+
+fn init_runtime(
+  rt: &mut JsRuntime,
+  receiver: mpsc::UnboundedReceiver<Value>,
+  message_sender: Sender<Value>,
+  handlers: Arc<Mutex<Vec<Box<WorkerMessageHandler>>>>,
+) {
+  rt.op_state().borrow_mut().put(WorkerState {
+    receiver: Rc::new(AsyncRefCell::new(receiver)),
+    message_sender,
+    handlers,
+  });
+  rt.add_op_at_runtime(
+    "op_worker_post_message",
+    json_op_sync(op_worker_post_message),
+  )
+  .unwrap();
+  rt.add_op_at_runtime(
+    "op_worker_get_message",
+    json_op_async(op_worker_get_message),
+  )
+  .unwrap();
+  rt.execute("deno:core/worker.js", include_str!("worker.js"))
+    .unwrap();
+}
+
+#[derive(Deserialize)]
+struct PostMessageArgs {
+  message: Value,
+}
+
+fn op_worker_post_message(
+  state: &mut OpState,
+  args: Value,
+  _zero_copy: &mut [ZeroCopyBuf],
+) -> Result<Value, AnyError> {
+  let args: PostMessageArgs = serde_json::from_value(args)?;
+  let worker = state.borrow::<WorkerState>();
+  for handler in worker.handlers.lock().unwrap().iter() {
+    handler(&args.message);
+  }
+  // The creating thread may not be interested in messages anymore.
+  let _ = worker.message_sender.send(args.message);
+  Ok(json!({}))
+}
+
+/// Resolves to `{ message }` with the next message for the worker, or to
+/// `null` once the handle was terminated or dropped.
+async fn op_worker_get_message(
+  state: Rc<RefCell<OpState>>,
+  _args: Value,
+  _bufs: BufVec,
+) -> Result<Value, AnyError> {
+  let receiver = state.borrow().borrow::<WorkerState>().receiver.clone();
+  let mut receiver = receiver.borrow_mut().await;
+  match receiver.next().await {
+    Some(message) => Ok(json!({ "message": message })),
+    None => Ok(json!(null)),
+  }
+}
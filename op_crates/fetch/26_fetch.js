@@ -1085,6 +1085,17 @@
     set credentials(_) {
       // can not set credentials
     }
+
+    [Symbol.for("Deno.customInspect")](inspect, inspectOptions) {
+      const object = {
+        bodyUsed: this.bodyUsed,
+        headers: this.headers,
+        method: this.method,
+        url: this.url,
+        credentials: this.credentials,
+      };
+      return `${this.constructor.name} ${inspect(object, inspectOptions)}`;
+    }
   }
 
   const responseData = new WeakMap();
@@ -1213,6 +1224,19 @@
       });
     }
 
+    [Symbol.for("Deno.customInspect")](inspect, inspectOptions) {
+      const object = {
+        bodyUsed: this.bodyUsed,
+        headers: this.headers,
+        ok: this.ok,
+        redirected: this.redirected,
+        status: this.status,
+        statusText: this.statusText,
+        url: this.url,
+      };
+      return `${this.constructor.name} ${inspect(object, inspectOptions)}`;
+    }
+
     /**
      * @param {string } url 
      * @param {number} status
@@ -1245,9 +1269,14 @@
    * @param {Headers} headers 
    * @param {ReadableStream<Uint8Array> | ArrayBufferView | undefined} body 
    * @param {number | null} clientRid
+   * @param {AbortSignal | null} signal
    * @returns {Promise<{status: number, statusText: string, headers: Record<string,string[]>, url: string, responseRid: number}>}
    */
-  async function sendFetchReq(url, method, headers, body, clientRid) {
+  async function sendFetchReq(url, method, headers, body, clientRid, signal) {
+    if (signal?.aborted) {
+      throw new DOMException("Ongoing fetch was aborted.", "AbortError");
+    }
+
     /** @type {[string, string][]} */
     let headerArray = [];
     if (headers) {
@@ -1288,7 +1317,28 @@
       body.pipeTo(writer);
     }
 
-    return await opFetchSend({ rid: requestRid });
+    if (!signal) {
+      return await opFetchSend({ rid: requestRid });
+    }
+
+    // The signal is mirrored by a Rust `AbortSignal`, which cancels the op.
+    const { controllerRid, signalRid } = core.jsonOpSync(
+      "op_abort_signal_new",
+    );
+    const onAbort = () => core.jsonOpSync("op_abort", { rid: controllerRid });
+    signal.addEventListener("abort", onAbort);
+    try {
+      return await opFetchSend({ rid: requestRid, signalRid });
+    } catch (err) {
+      if (signal.aborted) {
+        throw new DOMException("Ongoing fetch was aborted.", "AbortError");
+      }
+      throw err;
+    } finally {
+      signal.removeEventListener("abort", onAbort);
+      core.close(controllerRid);
+      core.close(signalRid);
+    }
   }
 
   /**
@@ -1303,6 +1353,7 @@
     let headers = null;
     let body;
     let clientRid = null;
+    let signal = null;
     let redirected = false;
     let remRedirectCount = 20; // TODO(bartlomieju): use a better way to handle
 
@@ -1362,6 +1413,8 @@
         if (init.client instanceof HttpClient) {
           clientRid = init.client.rid;
         }
+
+        signal = init.signal ?? null;
       }
     } else {
       url = input.url;
@@ -1382,6 +1435,7 @@
         headers ?? new Headers(),
         body,
         clientRid,
+        signal,
       );
       const rid = fetchResp.responseRid;
 
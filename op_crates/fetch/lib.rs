@@ -13,6 +13,7 @@ use deno_core::serde_json;
 use deno_core::serde_json::json;
 use deno_core::serde_json::Value;
 use deno_core::url::Url;
+use deno_core::AbortSignal;
 use deno_core::AsyncRefCell;
 use deno_core::BufVec;
 use deno_core::CancelFuture;
@@ -212,6 +213,7 @@ pub async fn op_fetch_send(
   #[serde(rename_all = "camelCase")]
   struct Args {
     rid: u32,
+    signal_rid: Option<u32>,
   }
 
   let args: Args = serde_json::from_value(args)?;
@@ -226,7 +228,18 @@ pub async fn op_fetch_send(
     .ok()
     .expect("multiple op_fetch_send ongoing");
 
-  let res = match request.0.await {
+  let result = match args.signal_rid {
+    Some(signal_rid) => {
+      let signal = state
+        .borrow()
+        .resource_table
+        .get::<AbortSignal>(signal_rid)
+        .ok_or_else(bad_resource_id)?;
+      request.0.or_cancel(signal.cancel_handle()).await?
+    }
+    None => request.0.await,
+  };
+  let res = match result {
     Ok(res) => res,
     Err(e) => return Err(type_error(e.to_string())),
   };
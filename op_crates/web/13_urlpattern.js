@@ -0,0 +1,142 @@
+// Copyright 2018-2021 the Deno authors. All rights reserved. MIT license.
+"use strict";
+
+((window) => {
+  const core = window.Deno.core;
+  const { URL } = window.__bootstrap.url;
+
+  const COMPONENTS = [
+    "protocol",
+    "username",
+    "password",
+    "hostname",
+    "port",
+    "pathname",
+    "search",
+    "hash",
+  ];
+
+  /** Converts the arguments of the constructor, `test()` and `exec()` into
+   * the form the ops expect. */
+  function toInput(input, baseURL) {
+    if (typeof input === "string" || input instanceof URL) {
+      return {
+        input: String(input),
+        baseURL: baseURL === undefined ? undefined : String(baseURL),
+      };
+    }
+    if (input === null || typeof input !== "object") {
+      throw new TypeError(
+        "URLPattern input must be a string or a URLPatternInit object",
+      );
+    }
+    const init = {};
+    for (const key of [...COMPONENTS, "baseURL"]) {
+      if (input[key] !== undefined) {
+        init[key] = String(input[key]);
+      }
+    }
+    return {
+      input: init,
+      baseURL: baseURL === undefined ? undefined : String(baseURL),
+    };
+  }
+
+  // https://wicg.github.io/urlpattern/
+  class URLPattern {
+    #components = {};
+
+    constructor(input = {}, baseURL = undefined) {
+      const components = core.jsonOpSync(
+        "op_urlpattern_parse",
+        toInput(input, baseURL),
+      );
+      for (const key of COMPONENTS) {
+        const { patternString, regexpString, groupNameList } = components[key];
+        let regexp;
+        try {
+          regexp = new RegExp(regexpString, "u");
+        } catch (e) {
+          throw new TypeError(
+            `Invalid ${key} pattern "${patternString}": ${e.message}`,
+          );
+        }
+        this.#components[key] = { patternString, regexp, groupNameList };
+      }
+    }
+
+    get protocol() {
+      return this.#components.protocol.patternString;
+    }
+
+    get username() {
+      return this.#components.username.patternString;
+    }
+
+    get password() {
+      return this.#components.password.patternString;
+    }
+
+    get hostname() {
+      return this.#components.hostname.patternString;
+    }
+
+    get port() {
+      return this.#components.port.patternString;
+    }
+
+    get pathname() {
+      return this.#components.pathname.patternString;
+    }
+
+    get search() {
+      return this.#components.search.patternString;
+    }
+
+    get hash() {
+      return this.#components.hash.patternString;
+    }
+
+    test(input = {}, baseURL = undefined) {
+      return this.exec(input, baseURL) !== null;
+    }
+
+    exec(input = {}, baseURL = undefined) {
+      const args = toInput(input, baseURL);
+      const values = core.jsonOpSync(
+        "op_urlpattern_process_match_input",
+        args,
+      );
+      if (values === null) {
+        return null;
+      }
+
+      const inputs = [input];
+      if (baseURL !== undefined) {
+        inputs.push(baseURL);
+      }
+      const result = { inputs };
+      for (const key of COMPONENTS) {
+        const { regexp, groupNameList } = this.#components[key];
+        const match = regexp.exec(values[key]);
+        if (match === null) {
+          return null;
+        }
+        const groups = {};
+        for (let i = 0; i < groupNameList.length; i++) {
+          groups[groupNameList[i]] = match[i + 1];
+        }
+        result[key] = { input: values[key], groups };
+      }
+      return result;
+    }
+
+    get [Symbol.toStringTag]() {
+      return "URLPattern";
+    }
+  }
+
+  window.__bootstrap.urlPattern = {
+    URLPattern,
+  };
+})(this);
@@ -12,6 +12,11 @@ use idna::domain_to_ascii_strict;
 use serde::Deserialize;
 use std::path::PathBuf;
 
+mod urlpattern;
+
+pub use urlpattern::op_urlpattern_parse;
+pub use urlpattern::op_urlpattern_process_match_input;
+
 pub fn op_domain_to_ascii(
   _state: &mut deno_core::OpState,
   args: Value,
@@ -69,6 +74,10 @@ pub fn init(isolate: &mut JsRuntime) {
       "deno:op_crates/web/12_location.js",
       include_str!("12_location.js"),
     ),
+    (
+      "deno:op_crates/web/13_urlpattern.js",
+      include_str!("13_urlpattern.js"),
+    ),
     (
       "deno:op_crates/web/21_filereader.js",
       include_str!("21_filereader.js"),
@@ -85,6 +94,7 @@ pub fn get_declaration() -> PathBuf {
 
 #[cfg(test)]
 mod tests {
+  use deno_core::json_op_sync;
   use deno_core::JsRuntime;
   use futures::future::lazy;
   use futures::task::Context;
@@ -99,6 +109,15 @@ mod tests {
 
   fn setup() -> JsRuntime {
     let mut isolate = JsRuntime::new(Default::default());
+    isolate.register_op(
+      "op_urlpattern_parse",
+      json_op_sync(crate::op_urlpattern_parse),
+    );
+    isolate.register_op(
+      "op_urlpattern_process_match_input",
+      json_op_sync(crate::op_urlpattern_process_match_input),
+    );
+    isolate.execute("ops.js", "Deno.core.ops();").unwrap();
     crate::init(&mut isolate);
     isolate
   }
@@ -163,4 +182,17 @@ mod tests {
       }
     });
   }
+
+  #[test]
+  fn test_url_pattern() {
+    run_in_task(|mut cx| {
+      let mut isolate = setup();
+      isolate
+        .execute("urlpattern_test.js", include_str!("urlpattern_test.js"))
+        .unwrap();
+      if let Poll::Ready(Err(_)) = isolate.poll_event_loop(&mut cx) {
+        unreachable!();
+      }
+    });
+  }
 }
@@ -0,0 +1,1715 @@
+// Copyright 2018-2021 the Deno authors. All rights reserved. MIT license.
+
+//! The pattern compiler behind `URLPattern`, following
+//! https://wicg.github.io/urlpattern/.
+//!
+//! `op_urlpattern_parse` turns a constructor string or `URLPatternInit` into
+//! one pattern string, regular expression source and group name list per
+//! URL component; the regular expressions are then compiled and matched in
+//! JavaScript. `op_urlpattern_process_match_input` splits the input of
+//! `test()` and `exec()` into the same components.
+
+use deno_core::error::type_error;
+use deno_core::error::AnyError;
+use deno_core::serde_json;
+use deno_core::serde_json::json;
+use deno_core::serde_json::Value;
+use deno_core::url::Url;
+use deno_core::OpState;
+use deno_core::ZeroCopyBuf;
+use serde::Deserialize;
+use serde::Serialize;
+
+const COMPONENTS: [&str; 8] = [
+  "protocol", "username", "password", "hostname", "port", "pathname", "search",
+  "hash",
+];
+
+const SPECIAL_SCHEMES: [&str; 6] =
+  ["ftp", "file", "http", "https", "ws", "wss"];
+
+const FULL_WILDCARD_REGEXP: &str = ".*";
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum TokenType {
+  Open,
+  Close,
+  Regexp,
+  Name,
+  Char,
+  EscapedChar,
+  OtherModifier,
+  Asterisk,
+  End,
+  InvalidChar,
+}
+
+#[derive(Debug)]
+struct Token {
+  kind: TokenType,
+  /// Offset of the token in the input, in chars.
+  index: usize,
+  value: String,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum TokenizePolicy {
+  Strict,
+  Lenient,
+}
+
+fn is_name_start(c: char) -> bool {
+  c == '$' || c == '_' || c.is_alphabetic()
+}
+
+fn is_name_char(c: char) -> bool {
+  is_name_start(c) || c.is_numeric() || c == '\u{200C}' || c == '\u{200D}'
+}
+
+fn tokenize(
+  input: &str,
+  policy: TokenizePolicy,
+) -> Result<Vec<Token>, AnyError> {
+  let chars: Vec<char> = input.chars().collect();
+  let mut tokens = vec![];
+  let mut i = 0;
+
+  // Records an error; in lenient mode the offending char becomes an
+  // invalid-char token instead.
+  macro_rules! error {
+    ($message:expr) => {
+      match policy {
+        TokenizePolicy::Strict => {
+          return Err(type_error(format!(
+            "Invalid URL pattern \"{}\": {}",
+            input, $message
+          )))
+        }
+        TokenizePolicy::Lenient => {
+          tokens.push(Token {
+            kind: TokenType::InvalidChar,
+            index: i,
+            value: chars[i].to_string(),
+          });
+          i += 1;
+          continue;
+        }
+      }
+    };
+  }
+
+  while i < chars.len() {
+    let c = chars[i];
+    let simple = match c {
+      '*' => Some(TokenType::Asterisk),
+      '+' | '?' => Some(TokenType::OtherModifier),
+      '{' => Some(TokenType::Open),
+      '}' => Some(TokenType::Close),
+      _ => None,
+    };
+    if let Some(kind) = simple {
+      tokens.push(Token {
+        kind,
+        index: i,
+        value: c.to_string(),
+      });
+      i += 1;
+      continue;
+    }
+
+    match c {
+      '\\' => {
+        if i + 1 == chars.len() {
+          error!("trailing backslash");
+        }
+        tokens.push(Token {
+          kind: TokenType::EscapedChar,
+          index: i,
+          value: chars[i + 1].to_string(),
+        });
+        i += 2;
+      }
+      ':' => {
+        let mut end = i + 1;
+        while end < chars.len()
+          && (if end == i + 1 {
+            is_name_start(chars[end])
+          } else {
+            is_name_char(chars[end])
+          })
+        {
+          end += 1;
+        }
+        if end == i + 1 {
+          error!("missing parameter name");
+        }
+        tokens.push(Token {
+          kind: TokenType::Name,
+          index: i,
+          value: chars[i + 1..end].iter().collect(),
+        });
+        i = end;
+      }
+      '(' => {
+        let mut depth = 1;
+        let mut pos = i + 1;
+        let mut failure = None;
+        while pos < chars.len() {
+          let c = chars[pos];
+          if !c.is_ascii() {
+            failure = Some("non-ASCII character in regexp group");
+            break;
+          }
+          if pos == i + 1 && c == '?' {
+            failure = Some("regexp group must not start with \"?\"");
+            break;
+          }
+          if c == '\\' {
+            if pos == chars.len() - 1 || !chars[pos + 1].is_ascii() {
+              failure = Some("invalid escape in regexp group");
+              break;
+            }
+            pos += 2;
+            continue;
+          }
+          if c == ')' {
+            depth -= 1;
+            if depth == 0 {
+              pos += 1;
+              break;
+            }
+          } else if c == '(' {
+            depth += 1;
+            if pos == chars.len() - 1 || chars[pos + 1] != '?' {
+              failure = Some("capturing groups are not allowed in regexps");
+              break;
+            }
+          }
+          pos += 1;
+        }
+        if let Some(message) = failure {
+          error!(message);
+        }
+        if depth != 0 {
+          error!("unbalanced regexp group");
+        }
+        if pos - i - 2 == 0 {
+          error!("empty regexp group");
+        }
+        tokens.push(Token {
+          kind: TokenType::Regexp,
+          index: i,
+          value: chars[i + 1..pos - 1].iter().collect(),
+        });
+        i = pos;
+      }
+      _ => {
+        tokens.push(Token {
+          kind: TokenType::Char,
+          index: i,
+          value: c.to_string(),
+        });
+        i += 1;
+      }
+    }
+  }
+
+  tokens.push(Token {
+    kind: TokenType::End,
+    index: chars.len(),
+    value: String::new(),
+  });
+  Ok(tokens)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum PartType {
+  FixedText,
+  Regexp,
+  SegmentWildcard,
+  FullWildcard,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum PartModifier {
+  None,
+  Optional,
+  ZeroOrMore,
+  OneOrMore,
+}
+
+impl PartModifier {
+  fn as_str(self) -> &'static str {
+    match self {
+      PartModifier::None => "",
+      PartModifier::Optional => "?",
+      PartModifier::ZeroOrMore => "*",
+      PartModifier::OneOrMore => "+",
+    }
+  }
+}
+
+#[derive(Debug)]
+struct Part {
+  kind: PartType,
+  value: String,
+  modifier: PartModifier,
+  name: String,
+  prefix: String,
+  suffix: String,
+}
+
+#[derive(Clone, Copy)]
+struct PatternOptions {
+  delimiter: Option<char>,
+  prefix: Option<char>,
+}
+
+const DEFAULT_OPTIONS: PatternOptions = PatternOptions {
+  delimiter: None,
+  prefix: None,
+};
+
+const HOSTNAME_OPTIONS: PatternOptions = PatternOptions {
+  delimiter: Some('.'),
+  prefix: None,
+};
+
+const PATHNAME_OPTIONS: PatternOptions = PatternOptions {
+  delimiter: Some('/'),
+  prefix: Some('/'),
+};
+
+impl PatternOptions {
+  fn segment_wildcard_regexp(&self) -> String {
+    let delimiter = self.delimiter.map(String::from).unwrap_or_default();
+    format!("[^{}]+?", escape_regexp_string(&delimiter))
+  }
+}
+
+type EncodingCallback<'a> = &'a dyn Fn(&str) -> Result<String, AnyError>;
+
+struct PatternParser<'a> {
+  tokens: Vec<Token>,
+  index: usize,
+  encoding: EncodingCallback<'a>,
+  segment_wildcard_regexp: String,
+  options: PatternOptions,
+  parts: Vec<Part>,
+  pending_fixed_value: String,
+  next_numeric_name: usize,
+}
+
+impl<'a> PatternParser<'a> {
+  fn try_consume(&mut self, kind: TokenType) -> Option<String> {
+    let token = &self.tokens[self.index];
+    if token.kind != kind {
+      return None;
+    }
+    self.index += 1;
+    Some(token.value.clone())
+  }
+
+  fn try_consume_modifier(&mut self) -> Option<String> {
+    self
+      .try_consume(TokenType::OtherModifier)
+      .or_else(|| self.try_consume(TokenType::Asterisk))
+  }
+
+  /// Returns the token type together with the value, to tell `*` apart from
+  /// a `(*)` regexp.
+  fn try_consume_regexp_or_wildcard(
+    &mut self,
+    name: &Option<String>,
+  ) -> Option<(TokenType, String)> {
+    if let Some(regexp) = self.try_consume(TokenType::Regexp) {
+      return Some((TokenType::Regexp, regexp));
+    }
+    if name.is_none() {
+      if let Some(asterisk) = self.try_consume(TokenType::Asterisk) {
+        return Some((TokenType::Asterisk, asterisk));
+      }
+    }
+    None
+  }
+
+  fn consume_required(&mut self, kind: TokenType) -> Result<(), AnyError> {
+    if self.try_consume(kind).is_none() {
+      let token = &self.tokens[self.index];
+      return Err(type_error(format!(
+        "Invalid URL pattern: unexpected {} at index {}",
+        if token.kind == TokenType::End {
+          "end of pattern".to_string()
+        } else {
+          format!("\"{}\"", token.value)
+        },
+        token.index
+      )));
+    }
+    Ok(())
+  }
+
+  fn consume_text(&mut self) -> String {
+    let mut result = String::new();
+    while let Some(value) = self
+      .try_consume(TokenType::Char)
+      .or_else(|| self.try_consume(TokenType::EscapedChar))
+    {
+      result.push_str(&value);
+    }
+    result
+  }
+
+  fn maybe_add_part_from_pending_fixed_value(
+    &mut self,
+  ) -> Result<(), AnyError> {
+    if self.pending_fixed_value.is_empty() {
+      return Ok(());
+    }
+    let value = std::mem::take(&mut self.pending_fixed_value);
+    let encoded = (self.encoding)(&value)?;
+    if encoded.is_empty() {
+      return Ok(());
+    }
+    self.parts.push(Part {
+      kind: PartType::FixedText,
+      value: encoded,
+      modifier: PartModifier::None,
+      name: String::new(),
+      prefix: String::new(),
+      suffix: String::new(),
+    });
+    Ok(())
+  }
+
+  fn add_part(
+    &mut self,
+    prefix: String,
+    name: Option<String>,
+    regexp_or_wildcard: Option<(TokenType, String)>,
+    suffix: String,
+    modifier: Option<String>,
+  ) -> Result<(), AnyError> {
+    let modifier = match modifier.as_deref() {
+      Some("?") => PartModifier::Optional,
+      Some("*") => PartModifier::ZeroOrMore,
+      Some("+") => PartModifier::OneOrMore,
+      _ => PartModifier::None,
+    };
+    if name.is_none()
+      && regexp_or_wildcard.is_none()
+      && modifier == PartModifier::None
+    {
+      self.pending_fixed_value.push_str(&prefix);
+      return Ok(());
+    }
+    self.maybe_add_part_from_pending_fixed_value()?;
+    if name.is_none() && regexp_or_wildcard.is_none() {
+      if prefix.is_empty() {
+        return Ok(());
+      }
+      let value = (self.encoding)(&prefix)?;
+      self.parts.push(Part {
+        kind: PartType::FixedText,
+        value,
+        modifier,
+        name: String::new(),
+        prefix: String::new(),
+        suffix: String::new(),
+      });
+      return Ok(());
+    }
+
+    let mut regexp_value = match &regexp_or_wildcard {
+      None => self.segment_wildcard_regexp.clone(),
+      Some((TokenType::Asterisk, _)) => FULL_WILDCARD_REGEXP.to_string(),
+      Some((_, regexp)) => regexp.clone(),
+    };
+    let mut kind = PartType::Regexp;
+    if regexp_value == self.segment_wildcard_regexp {
+      kind = PartType::SegmentWildcard;
+      regexp_value = String::new();
+    } else if regexp_value == FULL_WILDCARD_REGEXP {
+      kind = PartType::FullWildcard;
+      regexp_value = String::new();
+    }
+
+    let name = match name {
+      Some(name) => name,
+      None => {
+        let name = self.next_numeric_name.to_string();
+        self.next_numeric_name += 1;
+        name
+      }
+    };
+    if self.parts.iter().any(|part| part.name == name) {
+      return Err(type_error(format!(
+        "Invalid URL pattern: duplicate group name \"{}\"",
+        name
+      )));
+    }
+
+    let prefix = (self.encoding)(&prefix)?;
+    let suffix = (self.encoding)(&suffix)?;
+    self.parts.push(Part {
+      kind,
+      value: regexp_value,
+      modifier,
+      name,
+      prefix,
+      suffix,
+    });
+    Ok(())
+  }
+}
+
+fn parse_pattern_string(
+  input: &str,
+  options: PatternOptions,
+  encoding: EncodingCallback,
+) -> Result<Vec<Part>, AnyError> {
+  let mut parser = PatternParser {
+    tokens: tokenize(input, TokenizePolicy::Strict)?,
+    index: 0,
+    encoding,
+    segment_wildcard_regexp: options.segment_wildcard_regexp(),
+    options,
+    parts: vec![],
+    pending_fixed_value: String::new(),
+    next_numeric_name: 0,
+  };
+
+  while parser.index < parser.tokens.len() {
+    let char_token = parser.try_consume(TokenType::Char);
+    let name = parser.try_consume(TokenType::Name);
+    let regexp_or_wildcard = parser.try_consume_regexp_or_wildcard(&name);
+    if name.is_some() || regexp_or_wildcard.is_some() {
+      let mut prefix = char_token.unwrap_or_default();
+      let is_prefix_char = parser
+        .options
+        .prefix
+        .map_or(false, |c| prefix == c.to_string());
+      if !prefix.is_empty() && !is_prefix_char {
+        parser.pending_fixed_value.push_str(&prefix);
+        prefix = String::new();
+      }
+      parser.maybe_add_part_from_pending_fixed_value()?;
+      let modifier = parser.try_consume_modifier();
+      parser.add_part(
+        prefix,
+        name,
+        regexp_or_wildcard,
+        String::new(),
+        modifier,
+      )?;
+      continue;
+    }
+
+    let fixed =
+      char_token.or_else(|| parser.try_consume(TokenType::EscapedChar));
+    if let Some(fixed) = fixed {
+      parser.pending_fixed_value.push_str(&fixed);
+      continue;
+    }
+
+    if parser.try_consume(TokenType::Open).is_some() {
+      let prefix = parser.consume_text();
+      let name = parser.try_consume(TokenType::Name);
+      let regexp_or_wildcard = parser.try_consume_regexp_or_wildcard(&name);
+      let suffix = parser.consume_text();
+      parser.consume_required(TokenType::Close)?;
+      let modifier = parser.try_consume_modifier();
+      parser.add_part(prefix, name, regexp_or_wildcard, suffix, modifier)?;
+      continue;
+    }
+
+    parser.maybe_add_part_from_pending_fixed_value()?;
+    parser.consume_required(TokenType::End)?;
+  }
+
+  Ok(parser.parts)
+}
+
+fn escape_regexp_string(input: &str) -> String {
+  let mut result = String::new();
+  for c in input.chars() {
+    if ".+*?^${}()[]|/\\".contains(c) {
+      result.push('\\');
+    }
+    result.push(c);
+  }
+  result
+}
+
+fn escape_pattern_string(input: &str) -> String {
+  let mut result = String::new();
+  for c in input.chars() {
+    if "+*?:{}()\\".contains(c) {
+      result.push('\\');
+    }
+    result.push(c);
+  }
+  result
+}
+
+fn generate_regexp_and_name_list(
+  parts: &[Part],
+  options: PatternOptions,
+) -> (String, Vec<String>) {
+  let mut result = String::from("^");
+  let mut names = vec![];
+  for part in parts {
+    let modifier = part.modifier.as_str();
+    if part.kind == PartType::FixedText {
+      if part.modifier == PartModifier::None {
+        result.push_str(&escape_regexp_string(&part.value));
+      } else {
+        result.push_str(&format!(
+          "(?:{}){}",
+          escape_regexp_string(&part.value),
+          modifier
+        ));
+      }
+      continue;
+    }
+
+    names.push(part.name.clone());
+    let regexp = match part.kind {
+      PartType::SegmentWildcard => options.segment_wildcard_regexp(),
+      PartType::FullWildcard => FULL_WILDCARD_REGEXP.to_string(),
+      _ => part.value.clone(),
+    };
+    let single =
+      matches!(part.modifier, PartModifier::None | PartModifier::Optional);
+
+    if part.prefix.is_empty() && part.suffix.is_empty() {
+      if single {
+        result.push_str(&format!("({}){}", regexp, modifier));
+      } else {
+        result.push_str(&format!("((?:{}){})", regexp, modifier));
+      }
+      continue;
+    }
+
+    let prefix = escape_regexp_string(&part.prefix);
+    let suffix = escape_regexp_string(&part.suffix);
+    if single {
+      result
+        .push_str(&format!("(?:{}({}){}){}", prefix, regexp, suffix, modifier));
+      continue;
+    }
+    result.push_str(&format!(
+      "(?:{}((?:{})(?:{}{}(?:{}))*){})",
+      prefix, regexp, suffix, prefix, regexp, suffix
+    ));
+    if part.modifier == PartModifier::ZeroOrMore {
+      result.push('?');
+    }
+  }
+  result.push('$');
+  (result, names)
+}
+
+fn generate_pattern_string(parts: &[Part], options: PatternOptions) -> String {
+  let mut result = String::new();
+  let prefix_char = options.prefix.map(String::from).unwrap_or_default();
+  for (index, part) in parts.iter().enumerate() {
+    let previous = if index > 0 {
+      parts.get(index - 1)
+    } else {
+      None
+    };
+    let next = parts.get(index + 1);
+
+    if part.kind == PartType::FixedText {
+      if part.modifier == PartModifier::None {
+        result.push_str(&escape_pattern_string(&part.value));
+      } else {
+        result.push_str(&format!(
+          "{{{}}}{}",
+          escape_pattern_string(&part.value),
+          part.modifier.as_str()
+        ));
+      }
+      continue;
+    }
+
+    let custom_name = !part
+      .name
+      .chars()
+      .next()
+      .map_or(false, |c| c.is_ascii_digit());
+    let mut needs_grouping = !part.suffix.is_empty()
+      || (!part.prefix.is_empty() && part.prefix != prefix_char);
+    if !needs_grouping
+      && custom_name
+      && part.kind == PartType::SegmentWildcard
+      && part.modifier == PartModifier::None
+    {
+      if let Some(next) = next {
+        if next.prefix.is_empty() && next.suffix.is_empty() {
+          needs_grouping = if next.kind == PartType::FixedText {
+            next.value.chars().next().map_or(false, is_name_char)
+          } else {
+            next
+              .name
+              .chars()
+              .next()
+              .map_or(false, |c| c.is_ascii_digit())
+          };
+        }
+      }
+    }
+    if !needs_grouping && part.prefix.is_empty() {
+      if let Some(previous) = previous {
+        if previous.kind == PartType::FixedText
+          && !prefix_char.is_empty()
+          && previous.value.ends_with(&prefix_char)
+        {
+          needs_grouping = true;
+        }
+      }
+    }
+
+    if needs_grouping {
+      result.push('{');
+    }
+    result.push_str(&escape_pattern_string(&part.prefix));
+    if custom_name {
+      result.push(':');
+      result.push_str(&part.name);
+    }
+    match part.kind {
+      PartType::Regexp => result.push_str(&format!("({})", part.value)),
+      PartType::SegmentWildcard if !custom_name => {
+        result.push_str(&format!("({})", options.segment_wildcard_regexp()))
+      }
+      PartType::FullWildcard => {
+        let plain = previous.map_or(true, |previous| {
+          previous.kind == PartType::FixedText
+            || previous.modifier != PartModifier::None
+        });
+        if !custom_name && (plain || needs_grouping || !part.prefix.is_empty())
+        {
+          result.push('*');
+        } else {
+          result.push_str(&format!("({})", FULL_WILDCARD_REGEXP));
+        }
+      }
+      _ => {}
+    }
+    if part.kind == PartType::SegmentWildcard
+      && custom_name
+      && part.suffix.chars().next().map_or(false, is_name_char)
+    {
+      result.push('\\');
+    }
+    result.push_str(&escape_pattern_string(&part.suffix));
+    if needs_grouping {
+      result.push('}');
+    }
+    result.push_str(part.modifier.as_str());
+  }
+  result
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Component {
+  pattern_string: String,
+  regexp_string: String,
+  group_name_list: Vec<String>,
+}
+
+fn compile_component(
+  input: &str,
+  encoding: EncodingCallback,
+  options: PatternOptions,
+) -> Result<Component, AnyError> {
+  let parts = parse_pattern_string(input, options, encoding)?;
+  let (regexp_string, group_name_list) =
+    generate_regexp_and_name_list(&parts, options);
+  Ok(Component {
+    pattern_string: generate_pattern_string(&parts, options),
+    regexp_string,
+    group_name_list,
+  })
+}
+
+/// Translates the JavaScript regular expression syntax that the generated
+/// sources use into the `regex` crate's, which rejects `\/` and `[^]`.
+fn to_rust_regexp(source: &str) -> String {
+  let chars: Vec<char> = source.chars().collect();
+  let mut result = String::new();
+  let mut i = 0;
+  while i < chars.len() {
+    match chars[i] {
+      '\\' if chars.get(i + 1) == Some(&'/') => {
+        result.push('/');
+        i += 2;
+      }
+      '\\' => {
+        result.extend(chars.get(i..i + 2).unwrap_or(&chars[i..]));
+        i += 2;
+      }
+      '[' if chars.get(i + 1..i + 3) == Some(&['^', ']']) => {
+        result.push_str(r"[\s\S]");
+        i += 3;
+      }
+      c => {
+        result.push(c);
+        i += 1;
+      }
+    }
+  }
+  result
+}
+
+/// Whether a compiled protocol component matches any special scheme.
+/// User-supplied groups that Rust can't compile (e.g. lookarounds) count as
+/// not matching.
+fn protocol_component_matches_special_scheme(component: &Component) -> bool {
+  let source = to_rust_regexp(&component.regexp_string);
+  match regex::Regex::new(&source) {
+    Ok(re) => SPECIAL_SCHEMES.iter().any(|scheme| re.is_match(scheme)),
+    Err(_) => false,
+  }
+}
+
+fn default_port(scheme: &str) -> Option<&'static str> {
+  match scheme {
+    "ftp" => Some("21"),
+    "http" | "ws" => Some("80"),
+    "https" | "wss" => Some("443"),
+    _ => None,
+  }
+}
+
+fn dummy_url() -> Url {
+  Url::parse("http://dummy.test").unwrap()
+}
+
+fn canonicalize_protocol(value: &str) -> Result<String, AnyError> {
+  if value.is_empty() {
+    return Ok(String::new());
+  }
+  Url::parse(&format!("{}://dummy.test", value))
+    .map(|url| url.scheme().to_string())
+    .map_err(|_| type_error(format!("Invalid protocol \"{}\"", value)))
+}
+
+fn canonicalize_username(value: &str) -> Result<String, AnyError> {
+  if value.is_empty() {
+    return Ok(String::new());
+  }
+  let mut url = dummy_url();
+  url
+    .set_username(value)
+    .map_err(|_| type_error(format!("Invalid username \"{}\"", value)))?;
+  Ok(url.username().to_string())
+}
+
+fn canonicalize_password(value: &str) -> Result<String, AnyError> {
+  if value.is_empty() {
+    return Ok(String::new());
+  }
+  let mut url = dummy_url();
+  url
+    .set_password(Some(value))
+    .map_err(|_| type_error(format!("Invalid password \"{}\"", value)))?;
+  Ok(url.password().unwrap_or_default().to_string())
+}
+
+fn canonicalize_hostname(value: &str) -> Result<String, AnyError> {
+  if value.is_empty() {
+    return Ok(String::new());
+  }
+  let mut url = dummy_url();
+  url
+    .set_host(Some(value))
+    .map_err(|_| type_error(format!("Invalid hostname \"{}\"", value)))?;
+  Ok(url.host_str().unwrap_or_default().to_string())
+}
+
+fn canonicalize_port(
+  value: &str,
+  protocol: Option<&str>,
+) -> Result<String, AnyError> {
+  if value.is_empty() {
+    return Ok(String::new());
+  }
+  let port: u16 = value
+    .parse()
+    .ok()
+    .filter(|_| value.chars().all(|c| c.is_ascii_digit()))
+    .ok_or_else(|| type_error(format!("Invalid port \"{}\"", value)))?;
+  let port = port.to_string();
+  if protocol.and_then(default_port) == Some(port.as_str()) {
+    return Ok(String::new());
+  }
+  Ok(port)
+}
+
+#[allow(clippy::unnecessary_wraps)]
+fn canonicalize_pathname(value: &str) -> Result<String, AnyError> {
+  if value.is_empty() {
+    return Ok(String::new());
+  }
+  // A path that doesn't start with a slash is a piece of a pattern, e.g. the
+  // text between two groups. Give it a leading segment so that it's not
+  // resolved as a relative path, then strip that again.
+  let leading_slash = value.starts_with('/');
+  let modified = if leading_slash {
+    value.to_string()
+  } else {
+    format!("/-{}", value)
+  };
+  let mut url = dummy_url();
+  url.set_path(&modified);
+  let path = url.path();
+  Ok(if leading_slash {
+    path.to_string()
+  } else {
+    path[2..].to_string()
+  })
+}
+
+fn canonicalize_opaque_pathname(value: &str) -> Result<String, AnyError> {
+  if value.is_empty() {
+    return Ok(String::new());
+  }
+  Url::parse(&format!("data:{}", value))
+    .map(|url| url.path().to_string())
+    .map_err(|_| type_error(format!("Invalid pathname \"{}\"", value)))
+}
+
+#[allow(clippy::unnecessary_wraps)]
+fn canonicalize_search(value: &str) -> Result<String, AnyError> {
+  if value.is_empty() {
+    return Ok(String::new());
+  }
+  let mut url = dummy_url();
+  url.set_query(Some(value));
+  Ok(url.query().unwrap_or_default().to_string())
+}
+
+#[allow(clippy::unnecessary_wraps)]
+fn canonicalize_hash(value: &str) -> Result<String, AnyError> {
+  if value.is_empty() {
+    return Ok(String::new());
+  }
+  let mut url = dummy_url();
+  url.set_fragment(Some(value));
+  Ok(url.fragment().unwrap_or_default().to_string())
+}
+
+/// A `URLPatternInit`; `None` means that the component wasn't given.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct UrlPatternInit {
+  protocol: Option<String>,
+  username: Option<String>,
+  password: Option<String>,
+  hostname: Option<String>,
+  port: Option<String>,
+  pathname: Option<String>,
+  search: Option<String>,
+  hash: Option<String>,
+  #[serde(rename = "baseURL")]
+  base_url: Option<String>,
+}
+
+impl UrlPatternInit {
+  fn get(&self, component: &str) -> &Option<String> {
+    match component {
+      "protocol" => &self.protocol,
+      "username" => &self.username,
+      "password" => &self.password,
+      "hostname" => &self.hostname,
+      "port" => &self.port,
+      "pathname" => &self.pathname,
+      "search" => &self.search,
+      "hash" => &self.hash,
+      _ => unreachable!(),
+    }
+  }
+
+  fn get_mut(&mut self, component: &str) -> &mut Option<String> {
+    match component {
+      "protocol" => &mut self.protocol,
+      "username" => &mut self.username,
+      "password" => &mut self.password,
+      "hostname" => &mut self.hostname,
+      "port" => &mut self.port,
+      "pathname" => &mut self.pathname,
+      "search" => &mut self.search,
+      "hash" => &mut self.hash,
+      _ => unreachable!(),
+    }
+  }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum InitType {
+  Pattern,
+  Url,
+}
+
+/// https://wicg.github.io/urlpattern/#process-a-urlpatterninit
+fn process_init(
+  init: &UrlPatternInit,
+  kind: InitType,
+) -> Result<UrlPatternInit, AnyError> {
+  let mut result = UrlPatternInit::default();
+  let base_url =
+    match &init.base_url {
+      Some(base_url) => Some(Url::parse(base_url).map_err(|_| {
+        type_error(format!("Invalid base URL \"{}\"", base_url))
+      })?),
+      None => None,
+    };
+
+  let escape = |value: &str| match kind {
+    InitType::Pattern => escape_pattern_string(value),
+    InitType::Url => value.to_string(),
+  };
+
+  if let Some(base_url) = &base_url {
+    // A component is only inherited from the base URL if none of the
+    // components before it were given.
+    let given =
+      |components: &[&str]| components.iter().any(|c| init.get(c).is_some());
+    if !given(&["protocol"]) {
+      result.protocol = Some(escape(base_url.scheme()));
+    }
+    if kind != InitType::Pattern
+      && !given(&["protocol", "hostname", "port", "username"])
+    {
+      result.username = Some(escape(base_url.username()));
+    }
+    if kind != InitType::Pattern
+      && !given(&["protocol", "hostname", "port", "username", "password"])
+    {
+      result.password = Some(escape(base_url.password().unwrap_or_default()));
+    }
+    if !given(&["protocol", "hostname"]) {
+      result.hostname = Some(escape(base_url.host_str().unwrap_or_default()));
+    }
+    if !given(&["protocol", "hostname", "port"]) {
+      result.port = Some(
+        base_url
+          .port()
+          .map(|port| port.to_string())
+          .unwrap_or_default(),
+      );
+    }
+    if !given(&["protocol", "hostname", "port", "pathname"]) {
+      result.pathname = Some(escape(base_url.path()));
+    }
+    if !given(&["protocol", "hostname", "port", "pathname", "search"]) {
+      result.search = Some(escape(base_url.query().unwrap_or_default()));
+    }
+    if !given(&["protocol", "hostname", "port", "pathname", "search", "hash"]) {
+      result.hash = Some(escape(base_url.fragment().unwrap_or_default()));
+    }
+  }
+
+  if let Some(protocol) = &init.protocol {
+    let protocol = protocol.strip_suffix(':').unwrap_or(protocol);
+    result.protocol = Some(match kind {
+      InitType::Pattern => protocol.to_string(),
+      InitType::Url => canonicalize_protocol(protocol)?,
+    });
+  }
+  if let Some(username) = &init.username {
+    result.username = Some(match kind {
+      InitType::Pattern => username.clone(),
+      InitType::Url => canonicalize_username(username)?,
+    });
+  }
+  if let Some(password) = &init.password {
+    result.password = Some(match kind {
+      InitType::Pattern => password.clone(),
+      InitType::Url => canonicalize_password(password)?,
+    });
+  }
+  if let Some(hostname) = &init.hostname {
+    result.hostname = Some(match kind {
+      InitType::Pattern => hostname.clone(),
+      InitType::Url => canonicalize_hostname(hostname)?,
+    });
+  }
+  if let Some(port) = &init.port {
+    result.port = Some(match kind {
+      InitType::Pattern => port.clone(),
+      InitType::Url => canonicalize_port(port, result.protocol.as_deref())?,
+    });
+  }
+  if let Some(pathname) = &init.pathname {
+    let mut pathname = pathname.clone();
+    if let Some(base_url) = &base_url {
+      let is_absolute = pathname.starts_with('/')
+        || (kind == InitType::Pattern
+          && (pathname.starts_with("\\/") || pathname.starts_with("{/")));
+      if !base_url.cannot_be_a_base() && !is_absolute {
+        let base_path = escape(base_url.path());
+        if let Some(slash) = base_path.rfind('/') {
+          pathname = format!("{}{}", &base_path[..=slash], pathname);
+        }
+      }
+    }
+    result.pathname = Some(match kind {
+      InitType::Pattern => pathname,
+      InitType::Url => {
+        let special = result
+          .protocol
+          .as_deref()
+          .map_or(true, |p| p.is_empty() || SPECIAL_SCHEMES.contains(&p));
+        if special {
+          canonicalize_pathname(&pathname)?
+        } else {
+          canonicalize_opaque_pathname(&pathname)?
+        }
+      }
+    });
+  }
+  if let Some(search) = &init.search {
+    let search = search.strip_prefix('?').unwrap_or(search);
+    result.search = Some(match kind {
+      InitType::Pattern => search.to_string(),
+      InitType::Url => canonicalize_search(search)?,
+    });
+  }
+  if let Some(hash) = &init.hash {
+    let hash = hash.strip_prefix('#').unwrap_or(hash);
+    result.hash = Some(match kind {
+      InitType::Pattern => hash.to_string(),
+      InitType::Url => canonicalize_hash(hash)?,
+    });
+  }
+  Ok(result)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum ParserState {
+  Init,
+  Protocol,
+  Authority,
+  Username,
+  Password,
+  Hostname,
+  Port,
+  Pathname,
+  Search,
+  Hash,
+  Done,
+}
+
+impl ParserState {
+  fn component(self) -> Option<&'static str> {
+    match self {
+      ParserState::Protocol => Some("protocol"),
+      ParserState::Username => Some("username"),
+      ParserState::Password => Some("password"),
+      ParserState::Hostname => Some("hostname"),
+      ParserState::Port => Some("port"),
+      ParserState::Pathname => Some("pathname"),
+      ParserState::Search => Some("search"),
+      ParserState::Hash => Some("hash"),
+      _ => None,
+    }
+  }
+}
+
+/// https://wicg.github.io/urlpattern/#constructor-string-parsing
+struct ConstructorStringParser {
+  input: Vec<char>,
+  tokens: Vec<Token>,
+  result: UrlPatternInit,
+  component_start: usize,
+  token_index: usize,
+  token_increment: usize,
+  group_depth: usize,
+  hostname_ipv6_bracket_depth: usize,
+  protocol_matches_special_scheme: bool,
+  state: ParserState,
+}
+
+impl ConstructorStringParser {
+  fn token(&self, index: usize) -> &Token {
+    // Out of range indices clamp to the end token.
+    let index = index.min(self.tokens.len() - 1);
+    &self.tokens[index]
+  }
+
+  fn is_non_special_pattern_char(&self, index: usize, value: &str) -> bool {
+    let token = self.token(index);
+    token.value == value
+      && matches!(
+        token.kind,
+        TokenType::Char | TokenType::EscapedChar | TokenType::InvalidChar
+      )
+  }
+
+  fn is_search_prefix(&self) -> bool {
+    if self.is_non_special_pattern_char(self.token_index, "?") {
+      return true;
+    }
+    if self.token(self.token_index).value != "?" {
+      return false;
+    }
+    if self.token_index == 0 {
+      return true;
+    }
+    // A "?" after a group is a modifier.
+    !matches!(
+      self.token(self.token_index - 1).kind,
+      TokenType::Name
+        | TokenType::Regexp
+        | TokenType::Close
+        | TokenType::Asterisk
+    )
+  }
+
+  fn is_char(&self, value: &str) -> bool {
+    self.is_non_special_pattern_char(self.token_index, value)
+  }
+
+  fn is_authority_slashes(&self) -> bool {
+    self.is_non_special_pattern_char(self.token_index + 1, "/")
+      && self.is_non_special_pattern_char(self.token_index + 2, "/")
+  }
+
+  fn make_component_string(&self) -> String {
+    let start = self.token(self.component_start).index;
+    let end = self.token(self.token_index).index;
+    self.input[start..end].iter().collect()
+  }
+
+  fn rewind(&mut self) {
+    self.token_index = self.component_start;
+    self.token_increment = 0;
+  }
+
+  fn rewind_and_set_state(&mut self, state: ParserState) {
+    self.rewind();
+    self.state = state;
+  }
+
+  fn change_state(&mut self, new_state: ParserState, skip: usize) {
+    use ParserState::*;
+    if let Some(component) = self.state.component() {
+      *self.result.get_mut(component) = Some(self.make_component_string());
+    }
+    if self.state != Init && new_state != Done {
+      if matches!(self.state, Protocol | Authority | Username | Password)
+        && matches!(new_state, Port | Pathname | Search | Hash)
+        && self.result.hostname.is_none()
+      {
+        self.result.hostname = Some(String::new());
+      }
+      if matches!(
+        self.state,
+        Protocol | Authority | Username | Password | Hostname | Port
+      ) && matches!(new_state, Search | Hash)
+        && self.result.pathname.is_none()
+      {
+        self.result.pathname = Some(if self.protocol_matches_special_scheme {
+          "/".to_string()
+        } else {
+          String::new()
+        });
+      }
+      if matches!(
+        self.state,
+        Protocol | Authority | Username | Password | Hostname | Port | Pathname
+      ) && new_state == Hash
+        && self.result.search.is_none()
+      {
+        self.result.search = Some(String::new());
+      }
+    }
+    self.state = new_state;
+    self.token_index += skip;
+    self.component_start = self.token_index;
+    self.token_increment = 0;
+  }
+
+  fn compute_protocol_matches_special_scheme(
+    &mut self,
+  ) -> Result<(), AnyError> {
+    let protocol = self.make_component_string();
+    let component =
+      compile_component(&protocol, &canonicalize_protocol, DEFAULT_OPTIONS)?;
+    self.protocol_matches_special_scheme =
+      protocol_component_matches_special_scheme(&component);
+    Ok(())
+  }
+
+  fn parse(input: &str) -> Result<UrlPatternInit, AnyError> {
+    use ParserState::*;
+    let mut parser = ConstructorStringParser {
+      input: input.chars().collect(),
+      tokens: tokenize(input, TokenizePolicy::Lenient)?,
+      result: UrlPatternInit::default(),
+      component_start: 0,
+      token_index: 0,
+      token_increment: 1,
+      group_depth: 0,
+      hostname_ipv6_bracket_depth: 0,
+      protocol_matches_special_scheme: false,
+      state: Init,
+    };
+
+    while parser.token_index < parser.tokens.len() {
+      parser.token_increment = 1;
+
+      if parser.token(parser.token_index).kind == TokenType::End {
+        if parser.state == Init {
+          parser.rewind();
+          if parser.is_char("#") {
+            parser.change_state(Hash, 1);
+          } else if parser.is_search_prefix() {
+            parser.change_state(Search, 1);
+          } else {
+            parser.change_state(Pathname, 0);
+          }
+          parser.token_index += parser.token_increment;
+          continue;
+        }
+        if parser.state == Authority {
+          parser.rewind_and_set_state(Hostname);
+          parser.token_index += parser.token_increment;
+          continue;
+        }
+        parser.change_state(Done, 0);
+        break;
+      }
+
+      let kind = parser.token(parser.token_index).kind;
+      if kind == TokenType::Open {
+        parser.group_depth += 1;
+        parser.token_index += parser.token_increment;
+        continue;
+      }
+      if parser.group_depth > 0 {
+        if kind == TokenType::Close {
+          parser.group_depth -= 1;
+        } else {
+          parser.token_index += parser.token_increment;
+          continue;
+        }
+      }
+
+      match parser.state {
+        Init => {
+          if parser.is_char(":") {
+            parser.rewind_and_set_state(Protocol);
+          }
+        }
+        Protocol => {
+          if parser.is_char(":") {
+            parser.compute_protocol_matches_special_scheme()?;
+            let (next_state, skip) = if parser.is_authority_slashes() {
+              (Authority, 3)
+            } else if parser.protocol_matches_special_scheme {
+              (Authority, 1)
+            } else {
+              (Pathname, 1)
+            };
+            parser.change_state(next_state, skip);
+          }
+        }
+        Authority => {
+          if parser.is_char("@") {
+            parser.rewind_and_set_state(Username);
+          } else if parser.is_char("/")
+            || parser.is_search_prefix()
+            || parser.is_char("#")
+          {
+            parser.rewind_and_set_state(Hostname);
+          }
+        }
+        Username => {
+          if parser.is_char(":") {
+            parser.change_state(Password, 1);
+          } else if parser.is_char("@") {
+            parser.change_state(Hostname, 1);
+          }
+        }
+        Password => {
+          if parser.is_char("@") {
+            parser.change_state(Hostname, 1);
+          }
+        }
+        Hostname => {
+          if parser.is_char("[") {
+            parser.hostname_ipv6_bracket_depth += 1;
+          } else if parser.is_char("]") {
+            parser.hostname_ipv6_bracket_depth =
+              parser.hostname_ipv6_bracket_depth.saturating_sub(1);
+          } else if parser.is_char(":")
+            && parser.hostname_ipv6_bracket_depth == 0
+          {
+            parser.change_state(Port, 1);
+          } else if parser.is_char("/") {
+            parser.change_state(Pathname, 0);
+          } else if parser.is_search_prefix() {
+            parser.change_state(Search, 1);
+          } else if parser.is_char("#") {
+            parser.change_state(Hash, 1);
+          }
+        }
+        Port => {
+          if parser.is_char("/") {
+            parser.change_state(Pathname, 0);
+          } else if parser.is_search_prefix() {
+            parser.change_state(Search, 1);
+          } else if parser.is_char("#") {
+            parser.change_state(Hash, 1);
+          }
+        }
+        Pathname => {
+          if parser.is_search_prefix() {
+            parser.change_state(Search, 1);
+          } else if parser.is_char("#") {
+            parser.change_state(Hash, 1);
+          }
+        }
+        Search => {
+          if parser.is_char("#") {
+            parser.change_state(Hash, 1);
+          }
+        }
+        Hash | Done => {}
+      }
+      parser.token_index += parser.token_increment;
+    }
+
+    Ok(parser.result)
+  }
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum UrlPatternInput {
+  String(String),
+  Init(UrlPatternInit),
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ParseArgs {
+  input: UrlPatternInput,
+  #[serde(rename = "baseURL")]
+  base_url: Option<String>,
+}
+
+fn parse(
+  input: UrlPatternInput,
+  base_url: Option<String>,
+) -> Result<Vec<(&'static str, Component)>, AnyError> {
+  let init = match input {
+    UrlPatternInput::String(input) => {
+      let mut init = ConstructorStringParser::parse(&input)?;
+      if init.protocol.is_none() && base_url.is_none() {
+        return Err(type_error(format!(
+          "Relative URL pattern \"{}\" requires a base URL",
+          input
+        )));
+      }
+      init.base_url = base_url;
+      init
+    }
+    UrlPatternInput::Init(init) => {
+      if base_url.is_some() {
+        return Err(type_error(
+          "A base URL can only be given with a constructor string",
+        ));
+      }
+      init
+    }
+  };
+
+  let mut init = process_init(&init, InitType::Pattern)?;
+  for component in COMPONENTS.iter() {
+    let value = init.get_mut(component);
+    if value.is_none() {
+      *value = Some("*".to_string());
+    }
+  }
+  let protocol = init.protocol.clone().unwrap();
+  if default_port(&protocol).is_some()
+    && init.port.as_deref() == default_port(&protocol)
+  {
+    init.port = Some(String::new());
+  }
+
+  let value = |component: &str| init.get(component).clone().unwrap();
+  let protocol = compile_component(
+    &value("protocol"),
+    &canonicalize_protocol,
+    DEFAULT_OPTIONS,
+  )?;
+  let special = protocol_component_matches_special_scheme(&protocol);
+  let port_protocol = value("protocol");
+  let canonicalize_port =
+    |port: &str| canonicalize_port(port, Some(port_protocol.as_str()));
+  let pathname = if special {
+    compile_component(
+      &value("pathname"),
+      &canonicalize_pathname,
+      PATHNAME_OPTIONS,
+    )?
+  } else {
+    compile_component(
+      &value("pathname"),
+      &canonicalize_opaque_pathname,
+      DEFAULT_OPTIONS,
+    )?
+  };
+
+  Ok(vec![
+    ("protocol", protocol),
+    (
+      "username",
+      compile_component(
+        &value("username"),
+        &canonicalize_username,
+        DEFAULT_OPTIONS,
+      )?,
+    ),
+    (
+      "password",
+      compile_component(
+        &value("password"),
+        &canonicalize_password,
+        DEFAULT_OPTIONS,
+      )?,
+    ),
+    (
+      "hostname",
+      compile_component(
+        &value("hostname"),
+        &canonicalize_hostname,
+        HOSTNAME_OPTIONS,
+      )?,
+    ),
+    (
+      "port",
+      compile_component(&value("port"), &canonicalize_port, DEFAULT_OPTIONS)?,
+    ),
+    ("pathname", pathname),
+    (
+      "search",
+      compile_component(
+        &value("search"),
+        &canonicalize_search,
+        DEFAULT_OPTIONS,
+      )?,
+    ),
+    (
+      "hash",
+      compile_component(&value("hash"), &canonicalize_hash, DEFAULT_OPTIONS)?,
+    ),
+  ])
+}
+
+/// Compiles a `URLPattern`. Returns a map from component name to its
+/// `{ patternString, regexpString, groupNameList }`.
+pub fn op_urlpattern_parse(
+  _state: &mut OpState,
+  args: Value,
+  _zero_copy: &mut [ZeroCopyBuf],
+) -> Result<Value, AnyError> {
+  let args: ParseArgs = serde_json::from_value(args)?;
+  let components = parse(args.input, args.base_url)?;
+  let mut result = serde_json::Map::new();
+  for (name, component) in components {
+    result.insert(name.to_string(), json!(component));
+  }
+  Ok(Value::Object(result))
+}
+
+fn process_match_input(
+  input: UrlPatternInput,
+  base_url: Option<String>,
+) -> Option<UrlPatternInit> {
+  match input {
+    UrlPatternInput::String(input) => {
+      let url = match base_url {
+        Some(base_url) => Url::parse(&base_url).ok()?.join(&input).ok()?,
+        None => Url::parse(&input).ok()?,
+      };
+      Some(UrlPatternInit {
+        protocol: Some(url.scheme().to_string()),
+        username: Some(url.username().to_string()),
+        password: Some(url.password().unwrap_or_default().to_string()),
+        hostname: Some(url.host_str().unwrap_or_default().to_string()),
+        port: Some(url.port().map(|p| p.to_string()).unwrap_or_default()),
+        pathname: Some(url.path().to_string()),
+        search: Some(url.query().unwrap_or_default().to_string()),
+        hash: Some(url.fragment().unwrap_or_default().to_string()),
+        base_url: None,
+      })
+    }
+    UrlPatternInput::Init(init) => {
+      if base_url.is_some() {
+        return None;
+      }
+      process_init(&init, InitType::Url).ok()
+    }
+  }
+}
+
+/// Splits the input of `URLPattern.test()` or `URLPattern.exec()` into its
+/// components, or returns `null` if it can't match any pattern (e.g. it's
+/// not a valid URL).
+pub fn op_urlpattern_process_match_input(
+  _state: &mut OpState,
+  args: Value,
+  _zero_copy: &mut [ZeroCopyBuf],
+) -> Result<Value, AnyError> {
+  let args: ParseArgs = serde_json::from_value(args)?;
+  let init = match process_match_input(args.input, args.base_url) {
+    Some(init) => init,
+    None => return Ok(Value::Null),
+  };
+  let mut result = serde_json::Map::new();
+  for component in COMPONENTS.iter() {
+    let value = init.get(component).clone().unwrap_or_default();
+    result.insert(component.to_string(), json!(value));
+  }
+  Ok(Value::Object(result))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn compile(input: &str) -> Component {
+    compile_component(input, &|s| Ok(s.to_string()), PATHNAME_OPTIONS).unwrap()
+  }
+
+  #[test]
+  fn pathname_regexps() {
+    let c = compile("/books/:id");
+    assert_eq!(c.regexp_string, r"^\/books(?:\/([^\/]+?))$");
+    assert_eq!(c.group_name_list, vec!["id"]);
+
+    let c = compile("/files/*");
+    assert_eq!(c.regexp_string, r"^\/files(?:\/(.*))$");
+    assert_eq!(c.group_name_list, vec!["0"]);
+
+    let c = compile("/:id(\\d+)?");
+    assert_eq!(c.regexp_string, r"^(?:\/(\d+))?$");
+
+    let c = compile("/:path+");
+    assert_eq!(c.regexp_string, r"^(?:\/((?:[^\/]+?)(?:\/(?:[^\/]+?))*))$");
+
+    let c = compile("{/prefix}?/foo");
+    assert_eq!(c.regexp_string, r"^(?:\/prefix)?\/foo$");
+  }
+
+  #[test]
+  fn pattern_strings() {
+    assert_eq!(compile("/books/:id").pattern_string, "/books/:id");
+    assert_eq!(compile("/files/*").pattern_string, "/files/*");
+    assert_eq!(compile("/:id(\\d+)?").pattern_string, "/:id(\\d+)?");
+    assert_eq!(compile("{/a:b}").pattern_string, "{/a:b}");
+    assert_eq!(compile("/:foo\\bar").pattern_string, "{/:foo}bar");
+  }
+
+  #[test]
+  fn invalid_patterns() {
+    let invalid = |input: &str| {
+      parse_pattern_string(input, PATHNAME_OPTIONS, &|s| Ok(s.to_string()))
+        .is_err()
+    };
+    assert!(invalid("/:id/:id"));
+    assert!(invalid("/(a(b))"));
+    assert!(invalid("/()"));
+    assert!(invalid("/(?x)"));
+    assert!(invalid("/:"));
+    assert!(invalid("/{foo"));
+    assert!(invalid("/foo\\"));
+  }
+
+  fn components(input: &str, base_url: Option<&str>) -> Vec<(String, String)> {
+    parse(
+      UrlPatternInput::String(input.to_string()),
+      base_url.map(String::from),
+    )
+    .unwrap()
+    .into_iter()
+    .map(|(name, c)| (name.to_string(), c.pattern_string))
+    .collect()
+  }
+
+  fn pattern(components: &[(String, String)], name: &str) -> String {
+    components
+      .iter()
+      .find(|(n, _)| n == name)
+      .map(|(_, p)| p.clone())
+      .unwrap()
+  }
+
+  #[test]
+  fn constructor_strings() {
+    let c = components("https://example.com:8080/foo/:bar\\?q=1#frag", None);
+    assert_eq!(pattern(&c, "protocol"), "https");
+    assert_eq!(pattern(&c, "username"), "*");
+    assert_eq!(pattern(&c, "hostname"), "example.com");
+    assert_eq!(pattern(&c, "port"), "8080");
+    assert_eq!(pattern(&c, "pathname"), "/foo/:bar");
+    assert_eq!(pattern(&c, "search"), "q=1");
+    assert_eq!(pattern(&c, "hash"), "frag");
+
+    // ":pass" would be a named group.
+    let c = components("https://user\\:pass@*.example.com/:rest*", None);
+    assert_eq!(pattern(&c, "username"), "user");
+    assert_eq!(pattern(&c, "password"), "pass");
+    assert_eq!(pattern(&c, "hostname"), "*.example.com");
+    assert_eq!(pattern(&c, "pathname"), "/:rest*");
+
+    // "?" after a group is a modifier, not the start of the search.
+    let c = components("https://example.com/:id?", None);
+    assert_eq!(pattern(&c, "pathname"), "/:id?");
+    assert_eq!(pattern(&c, "search"), "*");
+
+    // Default ports are dropped.
+    let c = components("https://example.com:443/", None);
+    assert_eq!(pattern(&c, "port"), "");
+  }
+
+  #[test]
+  fn relative_constructor_strings() {
+    let c = components("/api/:version/*", Some("https://example.com/app/"));
+    assert_eq!(pattern(&c, "protocol"), "https");
+    assert_eq!(pattern(&c, "hostname"), "example.com");
+    assert_eq!(pattern(&c, "pathname"), "/api/:version/*");
+    assert_eq!(pattern(&c, "search"), "*");
+
+    let c = components("books/:id", Some("https://example.com/app/index"));
+    assert_eq!(pattern(&c, "pathname"), "/app/books/:id");
+
+    assert!(parse(UrlPatternInput::String("/foo".to_string()), None).is_err());
+  }
+
+  #[test]
+  fn match_input() {
+    let init = process_match_input(
+      UrlPatternInput::String("https://example.com/a b?x#y".to_string()),
+      None,
+    )
+    .unwrap();
+    assert_eq!(init.pathname.as_deref(), Some("/a%20b"));
+    assert_eq!(init.port.as_deref(), Some(""));
+    assert_eq!(init.search.as_deref(), Some("x"));
+    assert!(process_match_input(
+      UrlPatternInput::String("not a url".to_string()),
+      None
+    )
+    .is_none());
+  }
+}
@@ -0,0 +1,232 @@
+// Copyright 2018-2021 the Deno authors. All rights reserved. MIT license.
+"use strict";
+const { URLPattern } = window.__bootstrap.urlPattern;
+
+function assert(cond, msg = "assert") {
+  if (!cond) {
+    throw Error(msg);
+  }
+}
+
+function assertThrows(fn) {
+  let thrown = false;
+  try {
+    fn();
+  } catch (e) {
+    thrown = e instanceof TypeError;
+  }
+  assert(thrown, "expected TypeError");
+}
+
+// A slice of the web-platform-tests URLPattern test data:
+// `[pattern, inputs, expected]`, where `expected` lists the groups of the
+// matched components, or is `null` if the input doesn't match.
+const cases = [
+  [
+    { pathname: "/foo/bar" },
+    [{ pathname: "/foo/bar" }],
+    { pathname: {} },
+  ],
+  [{ pathname: "/foo/bar" }, [{ pathname: "/foo/ba" }], null],
+  [{ pathname: "/foo/bar" }, [{ pathname: "/foo/bar/" }], null],
+  [
+    { pathname: "/foo/bar" },
+    ["https://example.com/foo/bar"],
+    { pathname: {} },
+  ],
+  [
+    { pathname: "/foo/:bar" },
+    [{ pathname: "/foo/bar" }],
+    { pathname: { bar: "bar" } },
+  ],
+  [{ pathname: "/foo/:bar" }, [{ pathname: "/foo/index.html/" }], null],
+  [
+    { pathname: "/foo/:bar" },
+    [{ pathname: "/foo/index.html" }],
+    { pathname: { bar: "index.html" } },
+  ],
+  [
+    { pathname: "/foo/(.*)" },
+    [{ pathname: "/foo/bar/baz" }],
+    { pathname: { 0: "bar/baz" } },
+  ],
+  [
+    { pathname: "/foo/*" },
+    [{ pathname: "/foo/" }],
+    { pathname: { 0: "" } },
+  ],
+  [
+    { pathname: "/foo/:bar?" },
+    [{ pathname: "/foo" }],
+    { pathname: { bar: undefined } },
+  ],
+  [
+    { pathname: "/foo/:bar+" },
+    [{ pathname: "/foo/a/b" }],
+    { pathname: { bar: "a/b" } },
+  ],
+  [{ pathname: "/foo/:bar+" }, [{ pathname: "/foo" }], null],
+  [
+    { pathname: "/foo/:bar*" },
+    [{ pathname: "/foo" }],
+    { pathname: { bar: undefined } },
+  ],
+  [
+    { pathname: "/foo/:bar(\\d+)" },
+    [{ pathname: "/foo/123" }],
+    { pathname: { bar: "123" } },
+  ],
+  [{ pathname: "/foo/:bar(\\d+)" }, [{ pathname: "/foo/abc" }], null],
+  [
+    { pathname: "/foo{/bar}?" },
+    [{ pathname: "/foo" }],
+    { pathname: {} },
+  ],
+  [
+    { pathname: "/foo{/bar}?" },
+    [{ pathname: "/foo/bar" }],
+    { pathname: {} },
+  ],
+  [
+    { hostname: "*.example.com" },
+    [{ hostname: "api.example.com" }],
+    { hostname: { 0: "api" } },
+  ],
+  [{ hostname: "*.example.com" }, [{ hostname: "example.com" }], null],
+  [
+    { hostname: "{*.}?example.com" },
+    [{ hostname: "example.com" }],
+    { hostname: { 0: undefined } },
+  ],
+  [
+    { protocol: "http{s}?" },
+    ["https://example.com/"],
+    { protocol: {} },
+  ],
+  [{ protocol: "http{s}?" }, ["ftp://example.com/"], null],
+  [{ port: "(80|443)" }, [{ port: "80" }], { port: { 0: "80" } }],
+  [{ port: "8080" }, ["https://example.com:8080/"], { port: {} }],
+  [
+    "https://example.com/books/:id",
+    ["https://example.com/books/123"],
+    { pathname: { id: "123" } },
+  ],
+  [
+    "https://example.com/books/:id",
+    ["https://example.org/books/123"],
+    null,
+  ],
+  [
+    { pathname: "/books/:id" },
+    ["/books/123", "https://example.com"],
+    { pathname: { id: "123" } },
+  ],
+  [
+    { pathname: "/foo", baseURL: "https://example.com?query#hash" },
+    ["https://example.com/foo"],
+    { pathname: {} },
+  ],
+  [
+    "https://example.com/:page\\?lang=:lang",
+    ["https://example.com/about?lang=en"],
+    { pathname: { page: "about" }, search: { lang: "en" } },
+  ],
+  [
+    { pathname: "/café" },
+    [{ pathname: "/caf%C3%A9" }],
+    { pathname: {} },
+  ],
+  [
+    { pathname: "/foo/bar" },
+    ["not a url"],
+    null,
+  ],
+];
+
+function urlPatternMatches() {
+  for (const [pattern, inputs, expected] of cases) {
+    const urlPattern = new URLPattern(pattern);
+    const result = urlPattern.exec(...inputs);
+    const description = `${JSON.stringify(pattern)} against ${
+      JSON.stringify(inputs)
+    }`;
+    assert(urlPattern.test(...inputs) === (expected !== null), description);
+    if (expected === null) {
+      assert(result === null, description);
+      continue;
+    }
+    assert(result !== null, description);
+    assert(result.inputs[0] === inputs[0], description);
+    for (const [component, groups] of Object.entries(expected)) {
+      const actual = result[component].groups;
+      const keys = Object.keys(groups);
+      assert(Object.keys(actual).length === keys.length, description);
+      for (const key of keys) {
+        assert(actual[key] === groups[key], description);
+      }
+    }
+  }
+}
+
+function urlPatternComponents() {
+  const pattern = new URLPattern("https://example.com:8443/foo/:bar?q#h");
+  assert(pattern.protocol === "https");
+  assert(pattern.username === "*");
+  assert(pattern.password === "*");
+  assert(pattern.hostname === "example.com");
+  assert(pattern.port === "8443");
+  assert(pattern.pathname === "/foo/:bar");
+  assert(pattern.search === "q");
+  assert(pattern.hash === "h");
+
+  const relative = new URLPattern("/api/*", "https://example.com");
+  assert(relative.protocol === "https");
+  assert(relative.hostname === "example.com");
+  assert(relative.pathname === "/api/*");
+  assert(relative.search === "*");
+
+  const empty = new URLPattern();
+  assert(empty.pathname === "*");
+  assert(empty.test("https://example.com/anything"));
+}
+
+function urlPatternRouting() {
+  const routes = [
+    [new URLPattern({ pathname: "/" }), "home"],
+    [new URLPattern({ pathname: "/users/:id(\\d+)" }), "user"],
+    [new URLPattern({ pathname: "/static/*" }), "static"],
+  ];
+  const route = (url) => {
+    for (const [pattern, name] of routes) {
+      const match = pattern.exec(url);
+      if (match) {
+        return [name, match.pathname.groups];
+      }
+    }
+    return null;
+  };
+  assert(route("https://example.com/")[0] === "home");
+  const [name, groups] = route("https://example.com/users/42");
+  assert(name === "user" && groups.id === "42");
+  assert(route("https://example.com/users/me") === null);
+  assert(route("https://example.com/static/js/app.js")[1][0] === "js/app.js");
+}
+
+function urlPatternInvalid() {
+  assertThrows(() => new URLPattern({ pathname: "/:id/:id" }));
+  assertThrows(() => new URLPattern({ pathname: "/(a(b))" }));
+  assertThrows(() => new URLPattern({ pathname: "/{foo" }));
+  assertThrows(() => new URLPattern({ port: "abc" }));
+  assertThrows(() => new URLPattern("/relative"));
+  assertThrows(() => new URLPattern({ pathname: "/foo" }, "https://a.com"));
+  assertThrows(() => new URLPattern(42));
+}
+
+function main() {
+  urlPatternMatches();
+  urlPatternComponents();
+  urlPatternRouting();
+  urlPatternInvalid();
+}
+
+main();
@@ -6,6 +6,8 @@
 
   // provided by "deno_web"
   const { URL } = window.__bootstrap.url;
+  // provided by "deno_fetch"
+  const { ReadableStream, WritableStream } = window.__bootstrap.streams;
 
   const CONNECTING = 0;
   const OPEN = 1;
@@ -61,6 +63,60 @@
     });
   }
 
+  function parseURL(url) {
+    const wsURL = new URL(url);
+
+    if (wsURL.protocol !== "ws:" && wsURL.protocol !== "wss:") {
+      throw new DOMException(
+        "Only ws & wss schemes are allowed in a WebSocket URL.",
+        "SyntaxError",
+      );
+    }
+
+    if (wsURL.hash !== "" || wsURL.href.endsWith("#")) {
+      throw new DOMException(
+        "Fragments are not allowed in a WebSocket URL.",
+        "SyntaxError",
+      );
+    }
+
+    return wsURL.href;
+  }
+
+  function parseProtocols(protocols) {
+    if (protocols && typeof protocols === "string") {
+      protocols = [protocols];
+    }
+
+    if (
+      protocols.some((x) => protocols.indexOf(x) !== protocols.lastIndexOf(x))
+    ) {
+      throw new DOMException(
+        "Can't supply multiple times the same protocol.",
+        "SyntaxError",
+      );
+    }
+
+    return protocols;
+  }
+
+  function checkCloseInfo(code, reason) {
+    if (code && !(code === 1000 || (3000 <= code && code < 5000))) {
+      throw new DOMException(
+        "The close code must be either 1000 or in the range of 3000 to 4999.",
+        "NotSupportedError",
+      );
+    }
+
+    const encoder = new TextEncoder();
+    if (reason && encoder.encode(reason).byteLength > 123) {
+      throw new DOMException(
+        "The close reason may not be longer than 123 bytes.",
+        "SyntaxError",
+      );
+    }
+  }
+
   class WebSocket extends EventTarget {
     #readyState = CONNECTING;
 
@@ -68,43 +124,16 @@
       super();
       requiredArguments("WebSocket", arguments.length, 1);
 
-      const wsURL = new URL(url);
-
-      if (wsURL.protocol !== "ws:" && wsURL.protocol !== "wss:") {
-        throw new DOMException(
-          "Only ws & wss schemes are allowed in a WebSocket URL.",
-          "SyntaxError",
-        );
-      }
-
-      if (wsURL.hash !== "" || wsURL.href.endsWith("#")) {
-        throw new DOMException(
-          "Fragments are not allowed in a WebSocket URL.",
-          "SyntaxError",
-        );
-      }
-
-      this.#url = wsURL.href;
+      this.#url = parseURL(url);
 
       core.jsonOpSync("op_ws_check_permission", {
         url: this.#url,
       });
 
-      if (protocols && typeof protocols === "string") {
-        protocols = [protocols];
-      }
-
-      if (
-        protocols.some((x) => protocols.indexOf(x) !== protocols.lastIndexOf(x))
-      ) {
-        throw new DOMException(
-          "Can't supply multiple times the same protocol.",
-          "SyntaxError",
-        );
-      }
+      protocols = parseProtocols(protocols);
 
       core.jsonOpAsync("op_ws_create", {
-        url: wsURL.href,
+        url: this.#url,
         protocols: protocols.join(", "),
       }).then((create) => {
         if (create.success) {
@@ -256,20 +285,7 @@
     }
 
     close(code, reason) {
-      if (code && !(code === 1000 || (3000 <= code && code < 5000))) {
-        throw new DOMException(
-          "The close code must be either 1000 or in the range of 3000 to 4999.",
-          "NotSupportedError",
-        );
-      }
-
-      const encoder = new TextEncoder();
-      if (reason && encoder.encode(reason).byteLength > 123) {
-        throw new DOMException(
-          "The close reason may not be longer than 123 bytes.",
-          "SyntaxError",
-        );
-      }
+      checkCloseInfo(code, reason);
 
       if (this.#readyState === CONNECTING) {
         this.#readyState = CLOSING;
@@ -392,5 +408,265 @@
   defineEventHandler(WebSocket.prototype, "close");
   defineEventHandler(WebSocket.prototype, "open");
 
-  window.__bootstrap.webSocket = { WebSocket };
+  function deferred() {
+    let resolve, reject;
+    const promise = new Promise((resolve_, reject_) => {
+      resolve = resolve_;
+      reject = reject_;
+    });
+    return { promise, resolve, reject };
+  }
+
+  function abortError() {
+    return new DOMException(
+      "The WebSocket connection was aborted.",
+      "AbortError",
+    );
+  }
+
+  // A promise-based WebSocket whose streams apply backpressure: a message is
+  // only read from the socket when the readable side is pulled, and a write
+  // only resolves once the frame has been flushed to the socket.
+  class WebSocketStream {
+    #rid;
+    #url = "";
+    #connection;
+    #closed;
+    #controller;
+    #closeSent = false;
+    #done = false;
+
+    constructor(url, options = {}) {
+      requiredArguments("WebSocketStream", arguments.length, 1);
+
+      this.#url = parseURL(url);
+
+      core.jsonOpSync("op_ws_check_permission", {
+        url: this.#url,
+      });
+
+      const protocols = parseProtocols(options.protocols ?? []);
+      const signal = options.signal;
+
+      this.#connection = deferred();
+      this.#closed = deferred();
+      // Only the connection promise needs handling when connecting fails.
+      this.#closed.promise.catch(() => {});
+
+      if (signal?.aborted) {
+        this.#fail(abortError());
+        return;
+      }
+
+      let cancelRid;
+      const onAbort = () => core.close(cancelRid);
+      if (signal) {
+        cancelRid = core.jsonOpSync("op_ws_cancel_handle");
+        signal.addEventListener("abort", onAbort);
+      }
+
+      core.jsonOpAsync("op_ws_create", {
+        url: this.#url,
+        protocols: protocols.join(", "),
+        cancelRid,
+      }).then((create) => {
+        if (!create.success) {
+          throw new TypeError(
+            `failed to connect to WebSocket: ${this.#url}`,
+          );
+        }
+        this.#rid = create.rid;
+        this.#connection.resolve({
+          readable: new ReadableStream({
+            start: (controller) => {
+              this.#controller = controller;
+            },
+            pull: () => this.#next(),
+            cancel: () => this.close(),
+          }, { highWaterMark: 0 }),
+          writable: new WritableStream({
+            write: (chunk) => this.#send(chunk),
+            close: () => this.close(),
+            abort: () => this.close(),
+          }),
+          extensions: create.extensions,
+          protocol: create.protocol,
+        });
+      }).catch((err) => {
+        this.#fail(signal?.aborted ? abortError() : err);
+      }).finally(() => {
+        if (signal) {
+          signal.removeEventListener("abort", onAbort);
+          if (!signal.aborted) {
+            core.close(cancelRid);
+          }
+        }
+      });
+    }
+
+    get url() {
+      return this.#url;
+    }
+
+    get connection() {
+      return this.#connection.promise;
+    }
+
+    get closed() {
+      return this.#closed.promise;
+    }
+
+    close(closeInfo = {}) {
+      const { code, reason } = closeInfo;
+      checkCloseInfo(code, reason);
+
+      if (this.#closeSent) {
+        return;
+      }
+      this.#closeSent = true;
+
+      this.#connection.promise.then(async () => {
+        try {
+          await core.jsonOpAsync("op_ws_close", {
+            rid: this.#rid,
+            code: code ?? (reason ? 1000 : undefined),
+            reason,
+          });
+        } catch (err) {
+          this.#fail(err);
+          return;
+        }
+        // Read until the server acknowledges the close, even if nobody is
+        // reading from the readable side.
+        while (!this.#done) {
+          await this.#next();
+        }
+      }, () => {});
+    }
+
+    async #send(chunk) {
+      if (chunk instanceof Blob) {
+        chunk = await chunk.arrayBuffer();
+      }
+      if (typeof chunk === "string") {
+        await core.jsonOpAsync("op_ws_send", {
+          rid: this.#rid,
+          kind: "text",
+          text: chunk,
+        });
+      } else if (ArrayBuffer.isView(chunk) || chunk instanceof ArrayBuffer) {
+        const bytes = chunk instanceof ArrayBuffer
+          ? new Uint8Array(chunk)
+          : new Uint8Array(chunk.buffer, chunk.byteOffset, chunk.byteLength);
+        await core.jsonOpAsync("op_ws_send", {
+          rid: this.#rid,
+          kind: "binary",
+        }, bytes);
+      } else {
+        throw new TypeError(
+          "A WebSocketStream chunk must be a string or a BufferSource.",
+        );
+      }
+    }
+
+    // Reads events until a message was enqueued or the connection is done.
+    async #next() {
+      while (!this.#done) {
+        let message;
+        try {
+          message = await core.jsonOpAsync(
+            "op_ws_next_event",
+            { rid: this.#rid },
+          );
+        } catch (err) {
+          this.#fail(err);
+          return;
+        }
+
+        switch (message.kind) {
+          case "string":
+            this.#controller.enqueue(message.data);
+            return;
+
+          case "binary":
+            this.#controller.enqueue(new Uint8Array(message.data));
+            return;
+
+          case "ping":
+            try {
+              await core.jsonOpAsync("op_ws_send", {
+                rid: this.#rid,
+                kind: "pong",
+              });
+            } catch (err) {
+              this.#fail(err);
+              return;
+            }
+            break;
+
+          case "pong":
+            break;
+
+          case "close": {
+            if (!this.#closeSent) {
+              // The server started the closing handshake; complete it.
+              this.#closeSent = true;
+              await core.jsonOpAsync("op_ws_close", { rid: this.#rid })
+                .catch(() => {});
+            }
+            this.#finish({
+              code: message.data?.code ?? 1005,
+              reason: message.data?.reason ?? "",
+            });
+            return;
+          }
+
+          case "closed":
+            // The resource was closed by `op_ws_next_event`.
+            this.#rid = undefined;
+            this.#fail(
+              new Error("WebSocket connection closed without a close frame."),
+            );
+            return;
+
+          case "error":
+            this.#fail(new Error("WebSocket connection failed."));
+            return;
+        }
+      }
+    }
+
+    #finish(closeInfo) {
+      if (this.#done) {
+        return;
+      }
+      this.#done = true;
+      try {
+        this.#controller.close();
+      } catch {
+        // The readable side was canceled.
+      }
+      this.#closed.resolve(closeInfo);
+      core.close(this.#rid);
+    }
+
+    #fail(err) {
+      if (this.#done) {
+        return;
+      }
+      this.#done = true;
+      this.#connection.reject(err);
+      this.#closed.reject(err);
+      try {
+        this.#controller?.error(err);
+      } catch {
+        // The readable side was canceled.
+      }
+      if (this.#rid !== undefined) {
+        core.close(this.#rid);
+      }
+    }
+  }
+
+  window.__bootstrap.webSocket = { WebSocket, WebSocketStream };
 })(this);
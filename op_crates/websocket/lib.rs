@@ -1,6 +1,7 @@
 // Copyright 2018-2021 the Deno authors. All rights reserved. MIT license.
 
 use deno_core::error::bad_resource_id;
+use deno_core::error::custom_error;
 use deno_core::error::type_error;
 use deno_core::error::AnyError;
 use deno_core::futures::stream::SplitSink;
@@ -112,6 +113,7 @@ where
 struct CreateArgs {
   url: String,
   protocols: String,
+  cancel_rid: Option<u32>,
 }
 
 pub async fn op_ws_create<WP>(
@@ -133,15 +135,76 @@ where
       );
   }
 
+  let cancel = match args.cancel_rid {
+    Some(rid) => Some(
+      state
+        .borrow()
+        .resource_table
+        .get::<WsCancelResource>(rid)
+        .ok_or_else(bad_resource_id)?,
+    ),
+    None => None,
+  };
+
   let ws_ca_data = state.borrow().try_borrow::<WsCaData>().cloned();
   let user_agent = state.borrow().borrow::<WsUserAgent>().0.clone();
-  let uri: Uri = args.url.parse()?;
+  let connection = connect(args.url, args.protocols, ws_ca_data, user_agent);
+  let connected = match cancel {
+    Some(cancel) => connection
+      .or_cancel(RcRef::map(&cancel, |r| &r.0))
+      .await
+      .map_err(|_| {
+      custom_error("Interrupted", "WebSocket connection was aborted")
+    })?,
+    None => connection.await,
+  }?;
+  let (stream, response) = match connected {
+    Some(connected) => connected,
+    None => return Ok(json!({ "success": false })),
+  };
+
+  let (ws_tx, ws_rx) = stream.split();
+  let resource = WsStreamResource {
+    rx: AsyncRefCell::new(ws_rx),
+    tx: AsyncRefCell::new(ws_tx),
+    cancel: Default::default(),
+  };
+  let mut state = state.borrow_mut();
+  let rid = state.resource_table.add(resource);
+
+  let protocol = match response.headers().get("Sec-WebSocket-Protocol") {
+    Some(header) => header.to_str().unwrap(),
+    None => "",
+  };
+  let extensions = response
+    .headers()
+    .get_all("Sec-WebSocket-Extensions")
+    .iter()
+    .map(|header| header.to_str().unwrap())
+    .collect::<String>();
+  Ok(json!({
+    "success": true,
+    "rid": rid,
+    "protocol": protocol,
+    "extensions": extensions
+  }))
+}
+
+/// Connects and performs the WebSocket handshake. Returns `None` if the TCP
+/// connection couldn't be established.
+async fn connect(
+  url: String,
+  protocols: String,
+  ws_ca_data: Option<WsCaData>,
+  user_agent: String,
+) -> Result<Option<(WsStream, Response)>, AnyError> {
+  let uri: Uri = url.parse()?;
   let mut request = Request::builder().method(Method::GET).uri(&uri);
 
   request = request.header("User-Agent", user_agent);
 
-  if !args.protocols.is_empty() {
-    request = request.header("Sec-WebSocket-Protocol", args.protocols);
+  if !protocols.is_empty() {
+    request = request.header("Sec-WebSocket-Protocol", protocols);
   }
 
   let request = request.body(())?;
@@ -155,7 +218,7 @@ where
   let try_socket = TcpStream::connect(addr).await;
   let tcp_socket = match try_socket.map_err(TungsteniteError::Io) {
     Ok(socket) => socket,
-    Err(_) => return Ok(json!({ "success": false })),
+    Err(_) => return Ok(None),
   };
 
   let socket: MaybeTlsStream = match uri.scheme_str() {
@@ -180,39 +243,37 @@ where
     _ => unreachable!(),
   };
 
-  let (stream, response): (WsStream, Response) =
-    client_async(request, socket).await.map_err(|err| {
-      type_error(format!(
-        "failed to connect to WebSocket: {}",
-        err.to_string()
-      ))
-    })?;
+  let connected = client_async(request, socket).await.map_err(|err| {
+    type_error(format!(
+      "failed to connect to WebSocket: {}",
+      err.to_string()
+    ))
+  })?;
+  Ok(Some(connected))
+}
+
+/// Lets JavaScript abort a pending `op_ws_create` by closing this resource.
+struct WsCancelResource(CancelHandle);
 
-  let (ws_tx, ws_rx) = stream.split();
-  let resource = WsStreamResource {
-    rx: AsyncRefCell::new(ws_rx),
-    tx: AsyncRefCell::new(ws_tx),
-    cancel: Default::default(),
-  };
-  let mut state = state.borrow_mut();
-  let rid = state.resource_table.add(resource);
+impl Resource for WsCancelResource {
+  fn name(&self) -> Cow<str> {
+    "webSocketCancelHandle".into()
+  }
 
-  let protocol = match response.headers().get("Sec-WebSocket-Protocol") {
-    Some(header) => header.to_str().unwrap(),
-    None => "",
-  };
-  let extensions = response
-    .headers()
-    .get_all("Sec-WebSocket-Extensions")
-    .iter()
-    .map(|header| header.to_str().unwrap())
-    .collect::<String>();
-  Ok(json!({
-    "success": true,
-    "rid": rid,
-    "protocol": protocol,
-    "extensions": extensions
-  }))
+  fn close(self: Rc<Self>) {
+    self.0.cancel()
+  }
+}
+
+pub fn op_ws_cancel_handle(
+  state: &mut OpState,
+  _args: Value,
+  _zero_copy: &mut [ZeroCopyBuf],
+) -> Result<Value, AnyError> {
+  let rid = state
+    .resource_table
+    .add(WsCancelResource(CancelHandle::default()));
+  Ok(json!(rid))
 }
 
 #[derive(Deserialize)]
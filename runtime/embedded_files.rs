@@ -0,0 +1,116 @@
+// Copyright 2018-2021 the Deno authors. All rights reserved. MIT license.
+
+//! Read-only files stored as byte ranges of another file, such as the assets
+//! `deno compile --include` packs into the executable. When an
+//! `EmbeddedFiles` is put into the `OpState`, `Deno.open()` (and everything
+//! built on it, like `Deno.readFile()`) serves read-only opens of the
+//! embedded paths from it, without checking read permissions.
+
+use deno_core::error::AnyError;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::Read;
+use std::io::Seek;
+use std::io::SeekFrom;
+use std::path::Component;
+use std::path::Path;
+use std::path::PathBuf;
+
+pub struct EmbeddedFiles {
+  source: PathBuf,
+  /// Maps the embedded paths, like `assets/index.html`, to the offset and
+  /// length of their contents in `source`.
+  files: HashMap<String, (u64, u64)>,
+}
+
+impl EmbeddedFiles {
+  pub fn new(source: PathBuf) -> Self {
+    Self {
+      source,
+      files: HashMap::new(),
+    }
+  }
+
+  /// Embeds the `len` bytes at `offset` in the source file as `path`, which
+  /// is relative to the embedded root and uses `/` as the separator.
+  pub fn insert(&mut self, path: String, offset: u64, len: u64) {
+    self.files.insert(path, (offset, len));
+  }
+
+  /// Returns the contents of `path` if it is embedded, or `None` if it
+  /// should be looked up on the file system instead.
+  pub fn read(&self, path: &Path) -> Option<Result<Vec<u8>, AnyError>> {
+    let (offset, len) = *self.files.get(&embedded_path(path)?)?;
+    Some(self.read_range(offset, len))
+  }
+
+  fn read_range(&self, offset: u64, len: u64) -> Result<Vec<u8>, AnyError> {
+    let mut file = File::open(&self.source)?;
+    file.seek(SeekFrom::Start(offset))?;
+    let mut contents = Vec::with_capacity(len as usize);
+    file.take(len).read_to_end(&mut contents)?;
+    Ok(contents)
+  }
+}
+
+/// Turns a relative `path` into the key of an embedded file. Returns `None`
+/// for absolute paths and for paths that leave the embedded root, so
+/// `../assets/a.txt` never resolves to the embedded `assets/a.txt`.
+pub fn embedded_path(path: &Path) -> Option<String> {
+  let mut components: Vec<&str> = vec![];
+  for component in path.components() {
+    match component {
+      Component::Prefix(..) | Component::RootDir => return None,
+      Component::CurDir => {}
+      Component::ParentDir => {
+        components.pop()?;
+      }
+      Component::Normal(c) => components.push(c.to_str()?),
+    }
+  }
+  if components.is_empty() {
+    None
+  } else {
+    Some(components.join("/"))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn embedded_path_normalization() {
+    let path = |p: &str| embedded_path(Path::new(p));
+    assert_eq!(path("assets/a.txt"), Some("assets/a.txt".to_string()));
+    assert_eq!(path("./assets/./a.txt"), Some("assets/a.txt".to_string()));
+    assert_eq!(
+      path("assets/nested/../a.txt"),
+      Some("assets/a.txt".to_string())
+    );
+    assert_eq!(path("../assets/a.txt"), None);
+    assert_eq!(path("assets/../../a.txt"), None);
+    assert_eq!(path("/assets/a.txt"), None);
+    assert_eq!(path("."), None);
+  }
+
+  #[test]
+  fn read_embedded_files() {
+    // This file starts with "// Copyright 2018-2021".
+    let source =
+      Path::new(env!("CARGO_MANIFEST_DIR")).join("embedded_files.rs");
+    let mut files = EmbeddedFiles::new(source);
+    files.insert("a.txt".to_string(), 3, 9);
+    files.insert("dir/b.txt".to_string(), 13, 4);
+    assert_eq!(
+      files.read(Path::new("a.txt")).unwrap().unwrap(),
+      b"Copyright"
+    );
+    assert_eq!(
+      files.read(Path::new("./dir/b.txt")).unwrap().unwrap(),
+      b"2018"
+    );
+    assert!(files.read(Path::new("dir/../../a.txt")).is_none());
+    assert!(files.read(Path::new("c.txt")).is_none());
+  }
+}
@@ -171,13 +171,13 @@
     colors: false,
     getters: false,
     showHidden: false,
+    strAbbreviateSize: 100,
   };
 
   const DEFAULT_INDENT = "  "; // Default indent string
 
   const LINE_BREAKING_LENGTH = 80;
   const MIN_GROUP_LENGTH = 6;
-  const STR_ABBREVIATE_SIZE = 100;
 
   const PROMISE_STRING_BASE_LENGTH = 12;
 
@@ -201,10 +201,47 @@
     return inspectOptions.colors ? fn : (s) => s;
   }
 
+  // Custom inspect implementations are passed `inspect` and the options left
+  // at this level, so that they can format their contents consistently.
+  function inspectCustom(value, symbol, level, inspectOptions) {
+    return String(value[symbol](inspect, {
+      ...inspectOptions,
+      depth: inspectOptions.depth - level,
+    }));
+  }
+
+  // Values that were found to be referenced circularly, mapped to the index
+  // shown in their `<ref *n>` marker, per inspection context.
+  const circularRefs = new WeakMap();
+
+  function inspectCircular(value, ctx, inspectOptions) {
+    const cyan = maybeColor(colors.cyan, inspectOptions);
+    let refs = circularRefs.get(ctx);
+    if (refs === undefined) {
+      refs = new Map();
+      circularRefs.set(ctx, refs);
+    }
+    let index = refs.get(value);
+    if (index === undefined) {
+      index = refs.size + 1;
+      refs.set(value, index);
+    }
+    return cyan(`[Circular *${index}]`);
+  }
+
+  function withRefMarker(value, ctx, inspectOptions, string) {
+    const index = circularRefs.get(ctx)?.get(value);
+    if (index === undefined) {
+      return string;
+    }
+    const cyan = maybeColor(colors.cyan, inspectOptions);
+    return `${cyan(`<ref *${index}>`)} ${string}`;
+  }
+
   function inspectFunction(value, ctx, level, inspectOptions) {
     const cyan = maybeColor(colors.cyan, inspectOptions);
     if (customInspect in value && typeof value[customInspect] === "function") {
-      return String(value[customInspect]());
+      return inspectCustom(value, customInspect, level, inspectOptions);
     }
     // Might be Function/AsyncFunction/GeneratorFunction/AsyncGeneratorFunction
     let cstrName = Object.getPrototypeOf(value)?.constructor?.name;
@@ -444,7 +481,6 @@
     const green = maybeColor(colors.green, inspectOptions);
     const yellow = maybeColor(colors.yellow, inspectOptions);
     const dim = maybeColor(colors.dim, inspectOptions);
-    const cyan = maybeColor(colors.cyan, inspectOptions);
     const bold = maybeColor(colors.bold, inspectOptions);
     const red = maybeColor(colors.red, inspectOptions);
 
@@ -464,22 +500,30 @@
         return yellow(`${value}n`);
       case "function": // Function string is cyan
         if (ctx.has(value)) {
-          // Circular string is cyan
-          return cyan("[Circular]");
+          return inspectCircular(value, ctx, inspectOptions);
         }
 
-        return inspectFunction(value, ctx, level, inspectOptions);
+        return withRefMarker(
+          value,
+          ctx,
+          inspectOptions,
+          inspectFunction(value, ctx, level, inspectOptions),
+        );
       case "object": // null is bold
         if (value === null) {
           return bold("null");
         }
 
         if (ctx.has(value)) {
-          // Circular string is cyan
-          return cyan("[Circular]");
+          return inspectCircular(value, ctx, inspectOptions);
         }
 
-        return inspectObject(value, ctx, level, inspectOptions);
+        return withRefMarker(
+          value,
+          ctx,
+          inspectOptions,
+          inspectObject(value, ctx, level, inspectOptions),
+        );
       default:
         // Not implemented is red
         return red("[Not Implemented]");
@@ -555,8 +599,9 @@
     const green = maybeColor(colors.green, inspectOptions);
     switch (typeof value) {
       case "string": {
-        const trunc = value.length > STR_ABBREVIATE_SIZE
-          ? value.slice(0, STR_ABBREVIATE_SIZE) + "..."
+        const { strAbbreviateSize } = inspectOptions;
+        const trunc = value.length > strAbbreviateSize
+          ? value.slice(0, strAbbreviateSize) + "..."
           : value;
         return green(quoteString(trunc)); // Quoted strings are green
       }
@@ -908,7 +953,7 @@
     inspectOptions,
   ) {
     if (customInspect in value && typeof value[customInspect] === "function") {
-      return String(value[customInspect]());
+      return inspectCustom(value, customInspect, level, inspectOptions);
     }
     // This non-unique symbol is used to support op_crates, ie.
     // in op_crates/web we don't want to depend on unique "Deno.customInspect"
@@ -923,7 +968,12 @@
       // inspect implementations in `op_crates` need it, but may not have access
       // to the `Deno` namespace in web workers. Remove when the `Deno`
       // namespace is always enabled.
-      return String(value[nonUniqueCustomInspect](inspect));
+      return inspectCustom(
+        value,
+        nonUniqueCustomInspect,
+        level,
+        inspectOptions,
+      );
     }
     if (value instanceof Error) {
       return String(value.stack);
@@ -274,9 +274,10 @@
   }
 
   function toUnixTimeFromEpoch(value) {
+    // Seconds are floored so that the nanoseconds part is never negative.
     if (value instanceof Date) {
       const time = value.valueOf();
-      const seconds = Math.trunc(time / 1e3);
+      const seconds = Math.floor(time / 1e3);
       const nanoseconds = Math.trunc(time - (seconds * 1e3)) * 1e6;
 
       return [
@@ -285,8 +286,8 @@
       ];
     }
 
-    const seconds = value;
-    const nanoseconds = 0;
+    const seconds = Math.floor(value);
+    const nanoseconds = Math.trunc((value - seconds) * 1e9);
 
     return [
       seconds,
@@ -318,6 +319,42 @@
     });
   }
 
+  function flockSync(rid, exclusive = false) {
+    core.jsonOpSync("op_flock_sync", { rid, exclusive, nonBlocking: false });
+  }
+
+  async function flock(rid, exclusive = false) {
+    await core.jsonOpAsync("op_flock_async", {
+      rid,
+      exclusive,
+      nonBlocking: false,
+    });
+  }
+
+  function tryFlockSync(rid, exclusive = false) {
+    return core.jsonOpSync("op_flock_sync", {
+      rid,
+      exclusive,
+      nonBlocking: true,
+    });
+  }
+
+  function tryFlock(rid, exclusive = false) {
+    return core.jsonOpAsync("op_flock_async", {
+      rid,
+      exclusive,
+      nonBlocking: true,
+    });
+  }
+
+  function funlockSync(rid) {
+    core.jsonOpSync("op_funlock_sync", { rid });
+  }
+
+  async function funlock(rid) {
+    await core.jsonOpAsync("op_funlock_async", { rid });
+  }
+
   function utimeSync(
     path,
     atime,
@@ -414,6 +451,12 @@
     linkSync,
     futime,
     futimeSync,
+    flock,
+    flockSync,
+    tryFlock,
+    tryFlockSync,
+    funlock,
+    funlockSync,
     utime,
     utimeSync,
     symlink,
@@ -34,8 +34,92 @@
     return core.jsonOpAsync("op_datagram_send", args, zeroCopy);
   }
 
-  function resolveDns(query, recordType, options) {
-    return core.jsonOpAsync("op_dns_resolve", { query, recordType, options });
+  function opJoinMulticastV4(rid, address, multiInterface) {
+    return core.jsonOpAsync("op_net_join_multi_v4", {
+      rid,
+      address,
+      multiInterface,
+    });
+  }
+
+  function opLeaveMulticastV4(rid, address, multiInterface) {
+    return core.jsonOpAsync("op_net_leave_multi_v4", {
+      rid,
+      address,
+      multiInterface,
+    });
+  }
+
+  function opJoinMulticastV6(rid, address, multiInterface) {
+    return core.jsonOpAsync("op_net_join_multi_v6", {
+      rid,
+      address,
+      multiInterface,
+    });
+  }
+
+  function opLeaveMulticastV6(rid, address, multiInterface) {
+    return core.jsonOpAsync("op_net_leave_multi_v6", {
+      rid,
+      address,
+      multiInterface,
+    });
+  }
+
+  function opSetMulticastLoopback(rid, isV4, loopback) {
+    return core.jsonOpAsync("op_net_set_multi_loopback", {
+      rid,
+      isV4,
+      loopback,
+    });
+  }
+
+  function opSetMulticastTtl(rid, ttl) {
+    return core.jsonOpAsync("op_net_set_multi_ttl", { rid, ttl });
+  }
+
+  function opSetBroadcast(rid, broadcast) {
+    return core.jsonOpAsync("op_net_set_broadcast", { rid, broadcast });
+  }
+
+  function abortError() {
+    return new DOMException("The DNS lookup was aborted.", "AbortError");
+  }
+
+  async function resolveDns(query, recordType, options = {}) {
+    const { signal, ...resolveOptions } = options ?? {};
+    if (!signal) {
+      return core.jsonOpAsync("op_dns_resolve", {
+        query,
+        recordType,
+        options: resolveOptions,
+      });
+    }
+
+    if (signal.aborted) {
+      throw abortError();
+    }
+    const cancelRid = core.jsonOpSync("op_dns_cancel_handle");
+    const onAbort = () => core.close(cancelRid);
+    signal.addEventListener("abort", onAbort);
+    try {
+      return await core.jsonOpAsync("op_dns_resolve", {
+        query,
+        recordType,
+        options: resolveOptions,
+        cancelRid,
+      });
+    } catch (e) {
+      if (signal.aborted) {
+        throw abortError();
+      }
+      throw e;
+    } finally {
+      signal.removeEventListener("abort", onAbort);
+      if (!signal.aborted) {
+        core.close(cancelRid);
+      }
+    }
   }
 
   class Conn {
@@ -75,6 +159,18 @@
     closeWrite() {
       return shutdown(this.rid);
     }
+
+    setNoDelay(nodelay = true) {
+      return core.jsonOpAsync("op_net_set_nodelay", { rid: this.rid, nodelay });
+    }
+
+    setKeepAlive(keepalive = true, intervalSecs) {
+      return core.jsonOpAsync("op_net_set_keepalive", {
+        rid: this.rid,
+        keepalive,
+        intervalSecs,
+      });
+    }
   }
 
   class Listener {
@@ -162,6 +258,29 @@
       return opSend(args, p);
     }
 
+    async joinMulticastV4(address, multiInterface) {
+      await opJoinMulticastV4(this.rid, address, multiInterface);
+      return {
+        leave: () => opLeaveMulticastV4(this.rid, address, multiInterface),
+        setLoopback: (loopback) =>
+          opSetMulticastLoopback(this.rid, true, loopback),
+        setTTL: (ttl) => opSetMulticastTtl(this.rid, ttl),
+      };
+    }
+
+    async joinMulticastV6(address, multiInterface) {
+      await opJoinMulticastV6(this.rid, address, multiInterface);
+      return {
+        leave: () => opLeaveMulticastV6(this.rid, address, multiInterface),
+        setLoopback: (loopback) =>
+          opSetMulticastLoopback(this.rid, false, loopback),
+      };
+    }
+
+    setBroadcast(broadcast) {
+      return opSetBroadcast(this.rid, broadcast);
+    }
+
     close() {
       core.close(this.rid);
     }
@@ -6,45 +6,34 @@
   const { cloneValue, illegalConstructorKey } = window.__bootstrap.webUtil;
   const { requiredArguments } = window.__bootstrap.webUtil;
 
+  const core = window.Deno.core;
   const customInspect = Symbol.for("Deno.customInspect");
-  let performanceEntries = [];
-
-  function findMostRecent(
-    name,
-    type,
-  ) {
-    return performanceEntries
-      .slice()
-      .reverse()
-      .find((entry) => entry.name === name && entry.entryType === type);
-  }
 
-  function convertMarkToTimestamp(mark) {
-    if (typeof mark === "string") {
-      const entry = findMostRecent(mark, "mark");
-      if (!entry) {
-        throw new DOMException(
-          `Cannot find mark: "${mark}".`,
-          "SyntaxError",
-        );
-      }
-      return entry.startTime;
-    }
-    if (mark < 0) {
-      throw new TypeError("Mark cannot be negative.");
+  // The entries themselves are buffered by the op layer so that they can be
+  // read from Rust. This maps entry ids to the objects handed out to JS, so
+  // that the same object is returned every time and `detail` keeps being a
+  // structured clone rather than its JSON form.
+  const entryObjects = new Map();
+
+  function forgetEntries(ids) {
+    for (const id of ids) {
+      entryObjects.delete(id);
     }
-    return mark;
   }
 
-  function filterByNameType(
-    name,
-    type,
-  ) {
-    return performanceEntries.filter(
-      (entry) =>
-        (name ? entry.name === name : true) &&
-        (type ? entry.entryType === type : true),
-    );
+  function getEntries(name, entryType) {
+    return core.jsonOpSync("op_perf_get_entries", {
+      name: name ? String(name) : undefined,
+      entryType: entryType ? String(entryType) : undefined,
+    }).map(({ id }) => entryObjects.get(id));
+  }
+
+  function detailToJson(detail) {
+    try {
+      return JSON.parse(JSON.stringify(detail)) ?? null;
+    } catch {
+      return null;
+    }
   }
 
   const now = opNow;
@@ -214,43 +203,43 @@
     }
 
     clearMarks(markName) {
-      if (markName == null) {
-        performanceEntries = performanceEntries.filter(
-          (entry) => entry.entryType !== "mark",
-        );
-      } else {
-        performanceEntries = performanceEntries.filter(
-          (entry) => !(entry.name === markName && entry.entryType === "mark"),
-        );
-      }
+      forgetEntries(core.jsonOpSync("op_perf_clear", {
+        entryType: "mark",
+        name: markName == null ? undefined : String(markName),
+      }));
     }
 
     clearMeasures(measureName) {
-      if (measureName == null) {
-        performanceEntries = performanceEntries.filter(
-          (entry) => entry.entryType !== "measure",
-        );
-      } else {
-        performanceEntries = performanceEntries.filter(
-          (entry) =>
-            !(entry.name === measureName && entry.entryType === "measure"),
-        );
-      }
+      forgetEntries(core.jsonOpSync("op_perf_clear", {
+        entryType: "measure",
+        name: measureName == null ? undefined : String(measureName),
+      }));
     }
 
     getEntries() {
-      return filterByNameType();
+      return getEntries();
     }
 
     getEntriesByName(
       name,
       type,
     ) {
-      return filterByNameType(name, type);
+      return getEntries(name, type);
     }
 
     getEntriesByType(type) {
-      return filterByNameType(undefined, type);
+      return getEntries(undefined, type);
+    }
+
+    setResourceTimingBufferSize(maxSize) {
+      requiredArguments(
+        "Performance.setResourceTimingBufferSize",
+        arguments.length,
+        1,
+      );
+      forgetEntries(core.jsonOpSync("op_perf_set_buffer_size", {
+        maxSize: maxSize >>> 0,
+      }));
     }
 
     mark(
@@ -262,7 +251,13 @@
       // throw a SyntaxError. - not implemented
       const entry = new PerformanceMark(markName, options);
       // 3.1.1.7 Queue entry - not implemented
-      performanceEntries.push(entry);
+      const { id, evicted } = core.jsonOpSync("op_perf_mark", {
+        name: String(entry.name),
+        startTime: entry.startTime,
+        detail: detailToJson(entry.detail),
+      });
+      entryObjects.set(id, entry);
+      forgetEntries(evicted);
       return entry;
     }
 
@@ -271,6 +266,10 @@
       startOrMeasureOptions = {},
       endMark,
     ) {
+      let start;
+      let end;
+      let duration;
+      let detail = null;
       if (
         startOrMeasureOptions && typeof startOrMeasureOptions === "object" &&
         Object.keys(startOrMeasureOptions).length > 0
@@ -295,55 +294,33 @@
             "Cannot specify start, end, and duration together in options.",
           );
         }
+        ({ start, end, duration } = startOrMeasureOptions);
+        detail = startOrMeasureOptions.detail ?? null;
+      } else if (typeof startOrMeasureOptions === "string") {
+        start = startOrMeasureOptions;
       }
-      let endTime;
       if (endMark) {
-        endTime = convertMarkToTimestamp(endMark);
-      } else if (
-        typeof startOrMeasureOptions === "object" &&
-        "end" in startOrMeasureOptions
-      ) {
-        endTime = convertMarkToTimestamp(startOrMeasureOptions.end);
-      } else if (
-        typeof startOrMeasureOptions === "object" &&
-        "start" in startOrMeasureOptions &&
-        "duration" in startOrMeasureOptions
-      ) {
-        const start = convertMarkToTimestamp(startOrMeasureOptions.start);
-        const duration = convertMarkToTimestamp(startOrMeasureOptions.duration);
-        endTime = start + duration;
-      } else {
-        endTime = now();
-      }
-      let startTime;
-      if (
-        typeof startOrMeasureOptions === "object" &&
-        "start" in startOrMeasureOptions
-      ) {
-        startTime = convertMarkToTimestamp(startOrMeasureOptions.start);
-      } else if (
-        typeof startOrMeasureOptions === "object" &&
-        "end" in startOrMeasureOptions &&
-        "duration" in startOrMeasureOptions
-      ) {
-        const end = convertMarkToTimestamp(startOrMeasureOptions.end);
-        const duration = convertMarkToTimestamp(startOrMeasureOptions.duration);
-        startTime = end - duration;
-      } else if (typeof startOrMeasureOptions === "string") {
-        startTime = convertMarkToTimestamp(startOrMeasureOptions);
-      } else {
-        startTime = 0;
+        end = String(endMark);
       }
+
+      const { id, startTime, duration: measureDuration, evicted } = core
+        .jsonOpSync("op_perf_measure", {
+          name: String(measureName),
+          start,
+          end,
+          duration,
+          now: now(),
+          detail: detailToJson(detail),
+        });
       const entry = new PerformanceMeasure(
         measureName,
         startTime,
-        endTime - startTime,
-        typeof startOrMeasureOptions === "object"
-          ? startOrMeasureOptions.detail ?? null
-          : null,
+        measureDuration,
+        detail,
         illegalConstructorKey,
       );
-      performanceEntries.push(entry);
+      entryObjects.set(id, entry);
+      forgetEntries(evicted);
       return entry;
     }
 
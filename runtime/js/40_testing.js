@@ -32,9 +32,9 @@
   // ops. Note that "unref" ops are ignored since in nature that are
   // optional.
   function assertOps(fn) {
-    return async function asyncOpSanitizer() {
+    return async function asyncOpSanitizer(...args) {
       const pre = metrics();
-      await fn();
+      await fn(...args);
       // Defer until next event loop turn - that way timeouts and intervals
       // cleared can actually be removed from resource table, otherwise
       // false positives may occur (https://github.com/denoland/deno/issues/4591)
@@ -66,9 +66,9 @@ finishing test case.`,
   function assertResources(
     fn,
   ) {
-    return async function resourceSanitizer() {
+    return async function resourceSanitizer(...args) {
       const pre = core.resources();
-      await fn();
+      await fn(...args);
       const post = core.resources();
 
       const preStr = JSON.stringify(pre, null, 2);
@@ -83,6 +83,174 @@ finishing test case.`;
     };
   }
 
+  // Wrap test or step function in an assertion that makes sure all of the
+  // steps it started have finished by the time it returns, and that fails it
+  // if any of them failed.
+  function assertSteps(fn) {
+    return async function stepSanitizer(t) {
+      await fn(t);
+      const node = contextNodes.get(t);
+
+      const running = node.children.filter((c) => c.status === "pending");
+      if (running.length > 0) {
+        for (const child of running) {
+          child.status = "incomplete";
+        }
+        const names = running.map(({ name }) => `"${name}"`).join(", ");
+        throw new Error(
+          `${node.level == 0 ? "Test" : "Test step"} finished while ` +
+            `${running.length} of its steps (${names}) ` +
+            `${running.length == 1 ? "was" : "were"} still running.
+
+Make sure to await all promises returned from t.step() before
+finishing test case.`,
+        );
+      }
+
+      const failed = node.children.filter((c) => c.status === "failed");
+      if (failed.length > 0) {
+        throw new Error(
+          `${failed.length} test step${failed.length == 1 ? "" : "s"} failed.`,
+        );
+      }
+    };
+  }
+
+  function wrapTestFn(def) {
+    let fn = assertSteps(def.fn);
+
+    if (def.sanitizeOps) {
+      fn = assertOps(fn);
+    }
+
+    if (def.sanitizeResources) {
+      fn = assertResources(fn);
+    }
+
+    return fn;
+  }
+
+  // Maps each `TestContext` to the node in the step tree it reports to.
+  const contextNodes = new WeakMap();
+
+  function createStepNode(name, level) {
+    return {
+      name,
+      level,
+      status: "pending",
+      duration: 0,
+      error: undefined,
+      children: [],
+    };
+  }
+
+  // Flattens the steps below `node` in the order they should be reported in,
+  // parents before their children.
+  function flattenSteps(node, parentPath = []) {
+    const steps = [];
+    for (const child of node.children) {
+      const path = [...parentPath, child.name];
+      const { children: _, ...step } = child;
+      steps.push({ ...step, path }, ...flattenSteps(child, path));
+    }
+    return steps;
+  }
+
+  // Yields the start and end messages of the steps below `node`, parents
+  // around their children.
+  function* stepMessages(node) {
+    for (const child of node.children) {
+      const { name, level, status, duration, error } = child;
+      yield { testStepStart: { name, level } };
+      yield* stepMessages(child);
+      yield { testStepEnd: { name, level, status, duration, error } };
+    }
+  }
+
+  class TestContext {
+    #sanitizeOps;
+    #sanitizeResources;
+
+    constructor(node, { sanitizeOps, sanitizeResources }) {
+      contextNodes.set(this, node);
+      this.#sanitizeOps = sanitizeOps;
+      this.#sanitizeResources = sanitizeResources;
+    }
+
+    // Runs a nested test step and resolves to whether it passed. Failures are
+    // reported under the step and fail the enclosing test, so the promise
+    // never rejects.
+    async step(t, fn) {
+      const parent = contextNodes.get(this);
+      const defaults = {
+        ignore: false,
+        sanitizeOps: this.#sanitizeOps,
+        sanitizeResources: this.#sanitizeResources,
+      };
+
+      let stepDef;
+      if (typeof t === "string") {
+        if (!fn || typeof fn != "function") {
+          throw new TypeError("Missing test step function");
+        }
+        if (!t) {
+          throw new TypeError("The test step name can't be empty");
+        }
+        stepDef = { fn: fn, name: t, ...defaults };
+      } else {
+        if (!t.fn) {
+          throw new TypeError("Missing test step function");
+        }
+        if (!t.name) {
+          throw new TypeError("The test step name can't be empty");
+        }
+        stepDef = { ...defaults, ...t };
+      }
+
+      const node = createStepNode(stepDef.name, parent.level + 1);
+      const running = parent.children.find((c) => c.status === "pending");
+      parent.children.push(node);
+
+      if (parent.status !== "pending") {
+        node.status = "failed";
+        node.error = new Error(
+          `Cannot run test step "${node.name}" because its parent has ` +
+            `already finished.`,
+        );
+        return false;
+      }
+      if (running) {
+        node.status = "failed";
+        node.error = new Error(
+          `Cannot start test step "${node.name}" while another step ` +
+            `("${running.name}") is still running. Make sure to await each ` +
+            `step before starting the next one.`,
+        );
+        return false;
+      }
+      if (stepDef.ignore) {
+        node.status = "ignored";
+        return true;
+      }
+
+      const start = +new Date();
+      try {
+        await wrapTestFn(stepDef)(new TestContext(node, stepDef));
+        // A step that outlived its parent has already been marked incomplete.
+        if (node.status === "pending") {
+          node.status = "passed";
+        }
+      } catch (err) {
+        if (node.status === "pending") {
+          node.status = "failed";
+          node.error = err;
+        }
+      }
+      node.duration = +new Date() - start;
+      return node.status === "passed";
+    }
+  }
+
   const TEST_REGISTRY = [];
 
   // Main test function provided by Deno, as you can see it merely
@@ -117,62 +285,93 @@ finishing test case.`;
       testDef = { ...defaults, ...t };
     }
 
-    if (testDef.sanitizeOps) {
-      testDef.fn = assertOps(testDef.fn);
-    }
-
-    if (testDef.sanitizeResources) {
-      testDef.fn = assertResources(testDef.fn);
-    }
+    testDef.fn = wrapTestFn(testDef);
 
     TEST_REGISTRY.push(testDef);
   }
 
   const encoder = new TextEncoder();
 
-  function log(msg, noNewLine = false) {
-    if (!noNewLine) {
-      msg += "\n";
-    }
-
+  function writeToStdout(msg) {
     // Using `stdout` here because it doesn't force new lines
     // compared to `console.log`; `core.print` on the other hand
     // is line-buffered and doesn't output message without newline
     stdout.writeSync(encoder.encode(msg));
   }
 
-  function reportToConsole(message) {
+  // Whether the last line written by `reportToConsole()` still waits for the
+  // status of the test or step it names.
+  let lineOpen = false;
+
+  function formatStatus(status, duration) {
     const green = colors.maybeColor(colors.green);
     const red = colors.maybeColor(colors.red);
     const yellow = colors.maybeColor(colors.yellow);
+    switch (status) {
+      case "passed":
+        return `${green("ok")} ${formatDuration(duration)}`;
+      case "failed":
+        return `${red("FAILED")} ${formatDuration(duration)}`;
+      case "ignored":
+        return `${yellow("ignored")} ${formatDuration(duration)}`;
+      case "incomplete":
+        return `${red("INCOMPLETE")} ${formatDuration(duration)}`;
+    }
+  }
+
+  function logStatus(log, level, status, duration) {
+    const indent = "  ".repeat(level);
+    const prefix = lineOpen ? " " : indent;
+    log(`${prefix}${formatStatus(status, duration)}`);
+    lineOpen = false;
+  }
+
+  // Formats `message` for humans and passes the result to `write`, which
+  // defaults to writing to stdout.
+  function reportToConsole(message, write = writeToStdout) {
+    const log = (msg, noNewLine = false) => write(noNewLine ? msg : msg + "\n");
+    const green = colors.maybeColor(colors.green);
+    const red = colors.maybeColor(colors.red);
     const redFailed = red("FAILED");
     const greenOk = green("ok");
-    const yellowIgnored = yellow("ignored");
     if (message.start != null) {
-      log(`running ${message.start.tests.length} tests`);
+      const { tests, seed } = message.start;
+      if (seed != null) {
+        log(`running ${tests.length} tests in random order (seed: ${seed})`);
+      } else {
+        log(`running ${tests.length} tests`);
+      }
     } else if (message.testStart != null) {
       const { name } = message.testStart;
 
-      log(`test ${name} ... `, true);
+      log(`test ${name} ...`, true);
+      lineOpen = true;
       return;
+    } else if (message.testStepStart != null) {
+      const { name, level } = message.testStepStart;
+      const indent = "  ".repeat(level);
+
+      log(`${lineOpen ? "\n" : ""}${indent}test ${name} ...`, true);
+      lineOpen = true;
+      return;
+    } else if (message.testStepEnd != null) {
+      const { level, status, duration } = message.testStepEnd;
+      logStatus(log, level, status, duration);
     } else if (message.testEnd != null) {
-      switch (message.testEnd.status) {
-        case "passed":
-          log(`${greenOk} ${formatDuration(message.testEnd.duration)}`);
-          break;
-        case "failed":
-          log(`${redFailed} ${formatDuration(message.testEnd.duration)}`);
-          break;
-        case "ignored":
-          log(`${yellowIgnored} ${formatDuration(message.testEnd.duration)}`);
-          break;
-      }
+      logStatus(log, 0, message.testEnd.status, message.testEnd.duration);
     } else if (message.end != null) {
       const failures = message.end.results.filter((m) => m.error != null);
       if (failures.length > 0) {
         log(`\nfailures:\n`);
 
-        for (const { name, error } of failures) {
+        for (const { name, error, steps = [] } of failures) {
+          for (const step of steps) {
+            if (step.error != null) {
+              log([name, ...step.path].join(" > "));
+              log(inspectArgs([step.error]));
+              log("");
+            }
+          }
           log(name);
           log(inspectArgs([error]));
           log("");
@@ -200,6 +399,83 @@ finishing test case.`;
 
   exposeForTest("reportToConsole", reportToConsole);
 
+  // Formats an error thrown by a test or step for the reporters of
+  // `deno test --reporter`, which can't inspect JS values. The stack trace
+  // has already been source mapped by `Error.prepareStackTrace`.
+  function serializeError(error) {
+    const message = error instanceof Error
+      ? `${error.name}: ${error.message}`
+      : inspectArgs([error]);
+    return { message, formatted: inspectArgs([error]) };
+  }
+
+  // Converts a message of `TestRunner` into plain data, which is sent to the
+  // reporters of `deno test --reporter` in Rust.
+  function serializeTestMessage(message) {
+    if (message.start != null) {
+      const { tests, seed } = message.start;
+      return { start: { tests: tests.length, seed: seed ?? null } };
+    } else if (message.testStart != null) {
+      return { testStart: { name: message.testStart.name } };
+    } else if (message.testStepStart != null) {
+      const { name, level } = message.testStepStart;
+      return { testStepStart: { name, level } };
+    } else if (message.testStepEnd != null) {
+      const { name, level, status, duration, error } = message.testStepEnd;
+      return {
+        testStepEnd: {
+          name,
+          level,
+          status,
+          duration,
+          error: status === "failed" ? serializeError(error) : null,
+        },
+      };
+    } else if (message.testEnd != null) {
+      const { name, status, duration, error } = message.testEnd;
+      return {
+        testEnd: {
+          name,
+          status,
+          duration,
+          error: status === "failed" ? serializeError(error) : null,
+        },
+      };
+    } else if (message.end != null) {
+      const { results: _, ...summary } = message.end;
+      return { end: summary };
+    }
+  }
+
+  exposeForTest("serializeTestMessage", serializeTestMessage);
+
+  // Returns a pseudo random number generator (mulberry32) for numbers in
+  // [0, 1), seeded with the FNV-1a hash of the string `seed`.
+  function createRng(seed) {
+    let state = 0x811c9dc5;
+    for (let i = 0; i < seed.length; i++) {
+      state ^= seed.charCodeAt(i);
+      state = Math.imul(state, 0x01000193);
+    }
+    return () => {
+      state = (state + 0x6d2b79f5) | 0;
+      let t = Math.imul(state ^ (state >>> 15), 1 | state);
+      t = (t + Math.imul(t ^ (t >>> 7), 61 | t)) ^ t;
+      return ((t ^ (t >>> 14)) >>> 0) / 4294967296;
+    };
+  }
+
+  // Shuffles `array` in place so that the same seed always yields the same
+  // order.
+  function shuffleWithSeed(array, seed) {
+    const rng = createRng(String(seed));
+    for (let i = array.length - 1; i > 0; i--) {
+      const j = Math.floor(rng() * (i + 1));
+      [array[i], array[j]] = [array[j], array[i]];
+    }
+    return array;
+  }
+
   // TODO(bartlomieju): already implements AsyncGenerator<RunTestsMessage>, but add as "implements to class"
   // TODO(bartlomieju): implements PromiseLike<RunTestsEndResult>
   class TestRunner {
@@ -209,6 +485,7 @@ finishing test case.`;
       tests,
       filterFn,
       failFast,
+      seed,
     ) {
       this.stats = {
         filtered: 0,
@@ -224,10 +501,14 @@ finishing test case.`;
       const unfilteredTests = this.#usedOnly ? onlyTests : tests;
       this.testsToRun = unfilteredTests.filter(filterFn);
       this.stats.filtered = unfilteredTests.length - this.testsToRun.length;
+      this.seed = seed;
+      if (seed != null) {
+        shuffleWithSeed(this.testsToRun, seed);
+      }
     }
 
     async *[Symbol.asyncIterator]() {
-      yield { start: { tests: this.testsToRun } };
+      yield { start: { tests: this.testsToRun, seed: this.seed } };
 
       const results = [];
       const suiteStart = +new Date();
@@ -241,9 +522,10 @@ finishing test case.`;
           endMessage.status = "ignored";
           this.stats.ignored++;
         } else {
+          const node = createStepNode(test.name, 0);
           const start = +new Date();
           try {
-            await test.fn();
+            await test.fn(new TestContext(node, test));
             endMessage.status = "passed";
             this.stats.passed++;
           } catch (err) {
@@ -251,7 +533,11 @@ finishing test case.`;
             endMessage.error = err;
             this.stats.failed++;
           }
+          node.status = endMessage.status;
           endMessage.duration = +new Date() - start;
+          endMessage.steps = flattenSteps(node);
+
+          yield* stepMessages(node);
         }
         results.push(endMessage);
         yield { testEnd: endMessage };
@@ -268,10 +554,40 @@ finishing test case.`;
     }
   }
 
+  // Turns a `{ type, pattern }` object, as sent by `deno test --filter` and
+  // `--skip`, into a function matching test names.
+  function createPatternMatcher({ type, pattern }) {
+    switch (type) {
+      case "substring":
+        return (name) => name.includes(pattern);
+      case "exact":
+        return (name) => name === pattern;
+      case "regex": {
+        const regex = new RegExp(pattern);
+        return (name) => regex.test(name);
+      }
+      default:
+        throw new TypeError(`Unknown filter pattern type: ${type}`);
+    }
+  }
+
   function createFilterFn(
     filter,
     skip,
   ) {
+    // Structured filter: a test has to match one of `include`, if there are
+    // any, and none of `exclude`.
+    if (filter != null && typeof filter === "object" &&
+      !(filter instanceof RegExp)) {
+      const include = (filter.include ?? []).map(createPatternMatcher);
+      const exclude = (filter.exclude ?? []).map(createPatternMatcher);
+      const skipFn = createFilterFn(undefined, skip);
+      return (def) =>
+        (include.length == 0 || include.some((matches) => matches(def.name))) &&
+        !exclude.some((matches) => matches(def.name)) &&
+        skipFn(def);
+    }
+
     return (def) => {
       let passes = true;
 
@@ -308,9 +624,16 @@ finishing test case.`;
     disableLog = false,
     reportToConsole: reportToConsole_ = true,
     onMessage = undefined,
+    onOutput = undefined,
+    shuffle = undefined,
   } = {}) {
     const filterFn = createFilterFn(filter, skip);
-    const testRunner = new TestRunner(TEST_REGISTRY, filterFn, failFast);
+    const testRunner = new TestRunner(
+      TEST_REGISTRY,
+      filterFn,
+      failFast,
+      shuffle,
+    );
 
     const originalConsole = globalThis.console;
 
@@ -325,7 +648,7 @@ finishing test case.`;
         await onMessage(message);
       }
       if (reportToConsole_) {
-        reportToConsole(message);
+        reportToConsole(message, onOutput);
       }
       if (message.end != null) {
         endMsg = message.end;
@@ -347,5 +670,6 @@ finishing test case.`;
 
   window.__bootstrap.testing = {
     test,
+    createFilterFn,
   };
 })(this);
@@ -51,6 +51,7 @@
     keyFile,
     hostname = "0.0.0.0",
     transport = "tcp",
+    backlog,
   }) {
     const res = opListenTls({
       port,
@@ -58,18 +59,21 @@
       keyFile,
       hostname,
       transport,
+      backlog,
     });
     return new TLSListener(res.rid, res.localAddr);
   }
 
   async function startTls(
     conn,
-    { hostname = "127.0.0.1", certFile } = {},
+    { hostname = "127.0.0.1", certFile, caCerts, alpnProtocols } = {},
   ) {
     const res = await opStartTls({
       rid: conn.rid,
       hostname,
       certFile,
+      caCerts,
+      alpnProtocols,
     });
     return new Conn(res.rid, res.remoteAddr, res.localAddr);
   }
@@ -0,0 +1,368 @@
+// Copyright 2018-2021 the Deno authors. All rights reserved. MIT license.
+"use strict";
+
+((window) => {
+  const colors = window.__bootstrap.colors;
+  const { exit } = window.__bootstrap.os;
+  const { inspectArgs } = window.__bootstrap.console;
+  const { stdout } = window.__bootstrap.files;
+  const { exposeForTest } = window.__bootstrap.internals;
+  const { opNow } = window.__bootstrap.timers;
+  const { createFilterFn } = window.__bootstrap.testing;
+
+  // How long each benchmark runs before it is measured, in milliseconds.
+  const WARMUP_TIME = 100;
+  // How long each benchmark is measured at least, in milliseconds.
+  const MEASURE_TIME = 500;
+  // Iterations are timed in batches that take at least this long, in
+  // milliseconds, so the 2ms precision of the clock without --allow-hrtime
+  // doesn't swamp the results.
+  const BATCH_TIME = 10;
+  // The least number of batches timed per benchmark.
+  const MIN_SAMPLES = 10;
+
+  const BENCH_REGISTRY = [];
+
+  // Registers a benchmark for `deno bench`, either as `bench(name, fn)`,
+  // `bench(name, options, fn)` or `bench({ name, fn, ...options })`.
+  function bench(
+    nameOrDef,
+    optionsOrFn,
+    maybeFn,
+  ) {
+    let benchDef;
+    const defaults = {
+      ignore: false,
+      only: false,
+      baseline: false,
+      group: undefined,
+    };
+
+    if (typeof nameOrDef === "string") {
+      const fn = typeof optionsOrFn === "function" ? optionsOrFn : maybeFn;
+      const options = typeof optionsOrFn === "function" ? {} : optionsOrFn;
+      if (!fn || typeof fn != "function") {
+        throw new TypeError("Missing bench function");
+      }
+      if (!nameOrDef) {
+        throw new TypeError("The bench name can't be empty");
+      }
+      benchDef = { ...defaults, ...options, fn, name: nameOrDef };
+    } else {
+      if (!nameOrDef.fn) {
+        throw new TypeError("Missing bench function");
+      }
+      if (!nameOrDef.name) {
+        throw new TypeError("The bench name can't be empty");
+      }
+      benchDef = { ...defaults, ...nameOrDef };
+    }
+
+    BENCH_REGISTRY.push(benchDef);
+  }
+
+  // Runs `fn` `count` times and returns how long that took.
+  async function runBatch(fn, count, isAsync) {
+    const start = opNow();
+    if (isAsync) {
+      for (let i = 0; i < count; i++) {
+        await fn();
+      }
+    } else {
+      for (let i = 0; i < count; i++) {
+        fn();
+      }
+    }
+    return opNow() - start;
+  }
+
+  // Nearest-rank percentile `p` of the ascending `sorted`.
+  function percentile(sorted, p) {
+    const rank = Math.ceil((p / 100) * sorted.length);
+    return sorted[Math.max(0, rank - 1)];
+  }
+
+  // `samples` are the average times of a single iteration in each of the
+  // batches of `batchSize` iterations, in milliseconds.
+  function computeStats(samples, batchSize) {
+    const sorted = [...samples].sort((a, b) => a - b);
+    const mean = samples.reduce((sum, s) => sum + s, 0) / samples.length;
+    return {
+      n: samples.length * batchSize,
+      min: sorted[0],
+      max: sorted[sorted.length - 1],
+      mean,
+      p75: percentile(sorted, 75),
+      p99: percentile(sorted, 99),
+      iterPerSec: 1000 / mean,
+    };
+  }
+
+  exposeForTest("computeBenchStats", computeStats);
+
+  // Warms `fn` up, grows the batch size until a batch takes at least
+  // `BATCH_TIME` and then times batches for at least `MEASURE_TIME`.
+  async function measure(fn) {
+    // The first call tells whether the benchmark is async.
+    const result = fn();
+    const isAsync = typeof result?.then === "function";
+    if (isAsync) {
+      await result;
+    }
+
+    const warmupStart = opNow();
+    let batchSize = 1;
+    while (await runBatch(fn, batchSize, isAsync) < BATCH_TIME) {
+      batchSize *= 2;
+    }
+    while (opNow() - warmupStart < WARMUP_TIME) {
+      await runBatch(fn, batchSize, isAsync);
+    }
+
+    const samples = [];
+    const measureStart = opNow();
+    while (
+      samples.length < MIN_SAMPLES || opNow() - measureStart < MEASURE_TIME
+    ) {
+      samples.push(await runBatch(fn, batchSize, isAsync) / batchSize);
+    }
+    return computeStats(samples, batchSize);
+  }
+
+  // Sets `relative` of each measured result to its mean time divided by the
+  // mean time of the baseline of its group. Results in a group without a
+  // baseline get `null`.
+  function applyBaselines(results) {
+    const baselines = new Map();
+    for (const { group, baseline, stats } of results) {
+      if (baseline && stats != null && !baselines.has(group)) {
+        baselines.set(group, stats.mean);
+      }
+    }
+    for (const result of results) {
+      const baseline = baselines.get(result.group);
+      result.relative = result.stats != null && baseline !== undefined
+        ? result.stats.mean / baseline
+        : null;
+    }
+    return results;
+  }
+
+  exposeForTest("applyBenchBaselines", applyBaselines);
+
+  const encoder = new TextEncoder();
+
+  function log(msg, noNewLine = false) {
+    stdout.writeSync(encoder.encode(noNewLine ? msg : msg + "\n"));
+  }
+
+  function formatTime(ms) {
+    if (ms < 0.001) {
+      return `${(ms * 1e6).toFixed(2)}ns`;
+    } else if (ms < 1) {
+      return `${(ms * 1e3).toFixed(2)}µs`;
+    } else if (ms < 1000) {
+      return `${ms.toFixed(2)}ms`;
+    }
+    return `${(ms / 1000).toFixed(2)}s`;
+  }
+
+  function formatDuration(time) {
+    const gray = colors.maybeColor(colors.gray);
+    const italic = colors.maybeColor(colors.italic);
+    return gray(italic(`(${time}ms)`));
+  }
+
+  function formatStatus(status, duration) {
+    const green = colors.maybeColor(colors.green);
+    const red = colors.maybeColor(colors.red);
+    const yellow = colors.maybeColor(colors.yellow);
+    switch (status) {
+      case "passed":
+        return `${green("ok")} ${formatDuration(duration)}`;
+      case "failed":
+        return `${red("FAILED")} ${formatDuration(duration)}`;
+      case "ignored":
+        return `${yellow("ignored")} ${formatDuration(duration)}`;
+    }
+  }
+
+  // Prints the measured results as a table, one section per group.
+  function logTable(results) {
+    const measured = results.filter(({ stats }) => stats != null);
+    if (measured.length == 0) {
+      return;
+    }
+    const hasRelative = measured.some(({ relative }) => relative != null);
+    const header = [
+      "benchmark",
+      "n",
+      "min",
+      "max",
+      "mean",
+      "p75",
+      "p99",
+      "iter/s",
+      ...(hasRelative ? ["relative"] : []),
+    ];
+    const rowOf = ({ name, baseline, stats, relative }) => {
+      const row = [
+        name,
+        String(stats.n),
+        formatTime(stats.min),
+        formatTime(stats.max),
+        formatTime(stats.mean),
+        formatTime(stats.p75),
+        formatTime(stats.p99),
+        String(Math.round(stats.iterPerSec)),
+      ];
+      if (hasRelative) {
+        if (relative == null) {
+          row.push("");
+        } else if (baseline) {
+          row.push("baseline");
+        } else {
+          row.push(`${relative.toFixed(2)}x`);
+        }
+      }
+      return row;
+    };
+
+    const groups = new Map();
+    for (const result of measured) {
+      if (!groups.has(result.group)) {
+        groups.set(result.group, []);
+      }
+      groups.get(result.group).push(rowOf(result));
+    }
+
+    const widths = header.map((cell) => cell.length);
+    for (const rows of groups.values()) {
+      for (const row of rows) {
+        row.forEach((cell, i) => widths[i] = Math.max(widths[i], cell.length));
+      }
+    }
+    const format = (row) =>
+      row
+        .map((cell, i) =>
+          i == 0 ? cell.padEnd(widths[i]) : cell.padStart(widths[i])
+        )
+        .join("  ")
+        .trimEnd();
+
+    log("");
+    log(format(header));
+    for (const [group, rows] of groups) {
+      if (group != null) {
+        log(`group ${group}`);
+      }
+      for (const row of rows) {
+        log(format(row));
+      }
+    }
+  }
+
+  async function runBenches({
+    exitOnFail = true,
+    filter = undefined,
+    json = false,
+  } = {}) {
+    const filterFn = createFilterFn(filter);
+    const onlyBenches = BENCH_REGISTRY.filter(({ only }) => only);
+    const usedOnly = onlyBenches.length > 0;
+    const unfilteredBenches = usedOnly ? onlyBenches : BENCH_REGISTRY;
+    const benches = unfilteredBenches.filter(filterFn);
+    const summary = {
+      passed: 0,
+      failed: 0,
+      ignored: 0,
+      filtered: unfilteredBenches.length - benches.length,
+      usedOnly,
+    };
+
+    if (!json) {
+      log(`running ${benches.length} benches`);
+    }
+
+    const results = [];
+    const suiteStart = opNow();
+    for (const { name, group = null, baseline, ignore, fn } of benches) {
+      const result = {
+        name,
+        group,
+        baseline,
+        status: "ignored",
+        error: null,
+        stats: null,
+        relative: null,
+      };
+      if (!json) {
+        log(`bench ${name} ...`, true);
+      }
+      const start = opNow();
+      if (ignore) {
+        summary.ignored++;
+      } else {
+        try {
+          result.stats = await measure(fn);
+          result.status = "passed";
+          summary.passed++;
+        } catch (err) {
+          result.status = "failed";
+          result.error = err;
+          summary.failed++;
+        }
+      }
+      if (!json) {
+        log(` ${formatStatus(result.status, Math.round(opNow() - start))}`);
+      }
+      results.push(result);
+    }
+    applyBaselines(results);
+    const duration = Math.round(opNow() - suiteStart);
+
+    if (json) {
+      const benches = results.map((result) => ({
+        ...result,
+        error: result.error == null ? null : inspectArgs([result.error]),
+      }));
+      log(JSON.stringify({ benches, ...summary }, null, 2));
+    } else {
+      logTable(results);
+
+      const failures = results.filter(({ status }) => status == "failed");
+      if (failures.length > 0) {
+        log(`\nfailures:\n`);
+        for (const { name, error } of failures) {
+          log(name);
+          log(inspectArgs([error]));
+          log("");
+        }
+      }
+
+      const red = colors.maybeColor(colors.red);
+      const green = colors.maybeColor(colors.green);
+      log(
+        `\nbench result: ${summary.failed ? red("FAILED") : green("ok")}. ` +
+          `${summary.passed} passed; ${summary.failed} failed; ` +
+          `${summary.ignored} ignored; ${summary.filtered} filtered out ` +
+          `${formatDuration(duration)}\n`,
+      );
+      if (usedOnly && summary.failed == 0) {
+        log(`${red("FAILED")} because the "only" option was used\n`);
+      }
+    }
+
+    if ((summary.failed > 0 || usedOnly) && exitOnFail) {
+      exit(1);
+    }
+
+    return { ...summary, results };
+  }
+
+  exposeForTest("runBenches", runBenches);
+
+  window.__bootstrap.bench = {
+    bench,
+  };
+})(this);
@@ -91,6 +91,7 @@
   };
 
   __bootstrap.denoNsUnstable = {
+    bench: __bootstrap.bench.bench,
     signal: __bootstrap.signals.signal,
     signals: __bootstrap.signals.signals,
     Signal: __bootstrap.signals.Signal,
@@ -126,6 +127,12 @@
     linkSync: __bootstrap.fs.linkSync,
     futime: __bootstrap.fs.futime,
     futimeSync: __bootstrap.fs.futimeSync,
+    flock: __bootstrap.fs.flock,
+    flockSync: __bootstrap.fs.flockSync,
+    tryFlock: __bootstrap.fs.tryFlock,
+    tryFlockSync: __bootstrap.fs.tryFlockSync,
+    funlock: __bootstrap.fs.funlock,
+    funlockSync: __bootstrap.fs.funlockSync,
     utime: __bootstrap.fs.utime,
     utimeSync: __bootstrap.fs.utimeSync,
     symlink: __bootstrap.fs.symlink,
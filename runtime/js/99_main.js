@@ -24,6 +24,7 @@ delete Object.prototype.__proto__;
   const performance = window.__bootstrap.performance;
   const crypto = window.__bootstrap.crypto;
   const url = window.__bootstrap.url;
+  const urlPattern = window.__bootstrap.urlPattern;
   const headers = window.__bootstrap.headers;
   const streams = window.__bootstrap.streams;
   const fileReader = window.__bootstrap.fileReader;
@@ -195,6 +196,12 @@ delete Object.prototype.__proto__;
     core.registerErrorClass("SyntaxError", SyntaxError);
     core.registerErrorClass("TypeError", TypeError);
     core.registerErrorClass("URIError", URIError);
+    core.registerErrorClass(
+      "DOMExceptionSyntaxError",
+      function DOMExceptionSyntaxError(msg) {
+        return new DOMException(msg, "SyntaxError");
+      },
+    );
   }
 
   // https://developer.mozilla.org/en-US/docs/Web/API/WindowOrWorkerGlobalScope
@@ -256,6 +263,12 @@ delete Object.prototype.__proto__;
   // structure, it might be worth it to define a helper in `util`
   windowOrWorkerGlobalScope.console.enumerable = false;
 
+  // Globals that are only available with `--unstable`.
+  const unstableWindowOrWorkerGlobalScope = {
+    URLPattern: util.nonEnumerable(urlPattern.URLPattern),
+    WebSocketStream: util.nonEnumerable(webSocket.WebSocketStream),
+  };
+
   const mainRuntimeGlobalProperties = {
     Location: location.locationConstructorDescriptor,
     location: location.locationDescriptor,
@@ -351,6 +364,7 @@ delete Object.prototype.__proto__;
 
     if (unstableFlag) {
       Object.assign(finalDenoNs, denoNsUnstable);
+      Object.defineProperties(globalThis, unstableWindowOrWorkerGlobalScope);
     }
 
     // Setup `Deno` global - we're actually overriding already
@@ -395,6 +409,10 @@ delete Object.prototype.__proto__;
     fetch.setBaseUrl(locationHref);
     registerErrors();
 
+    if (unstableFlag) {
+      Object.defineProperties(globalThis, unstableWindowOrWorkerGlobalScope);
+    }
+
     const finalDenoNs = {
       core,
       internal: internalSymbol,
@@ -13,6 +13,7 @@ pub use deno_web;
 pub use deno_websocket;
 
 pub mod colors;
+pub mod embedded_files;
 pub mod errors;
 pub mod fs_util;
 pub mod http_util;
@@ -114,6 +114,17 @@ pub fn metrics_op(op_fn: Box<OpFn>) -> Box<OpFn> {
           .boxed_local();
         Op::Async(fut)
       }
+      Op::AsyncPriority(priority, fut) => {
+        metrics.op_dispatched_async(bytes_sent_control, bytes_sent_data);
+        let fut = fut
+          .inspect(move |buf| {
+            let mut s = op_state_.borrow_mut();
+            let metrics = s.borrow_mut::<Metrics>();
+            metrics.op_completed_async(buf.len());
+          })
+          .boxed_local();
+        Op::AsyncPriority(priority, fut)
+      }
       Op::AsyncUnref(fut) => {
         metrics.op_dispatched_async_unref(bytes_sent_control, bytes_sent_data);
         let fut = fut
@@ -1,8 +1,11 @@
 // Copyright 2018-2021 the Deno authors. All rights reserved. MIT license.
 // Some deserializer fields are only used on Unix and Windows build fails without it
+use super::io::EmbeddedFileResource;
 use super::io::StdFileResource;
+use crate::embedded_files::EmbeddedFiles;
 use crate::fs_util::canonicalize_path;
 use crate::permissions::Permissions;
+use deno_core::error::bad_resource;
 use deno_core::error::bad_resource_id;
 use deno_core::error::custom_error;
 use deno_core::error::type_error;
@@ -107,6 +110,11 @@ pub fn init(rt: &mut deno_core::JsRuntime) {
 
   super::reg_json_sync(rt, "op_utime_sync", op_utime_sync);
   super::reg_json_async(rt, "op_utime_async", op_utime_async);
+
+  super::reg_json_sync(rt, "op_flock_sync", op_flock_sync);
+  super::reg_json_async(rt, "op_flock_async", op_flock_async);
+  super::reg_json_sync(rt, "op_funlock_sync", op_funlock_sync);
+  super::reg_json_async(rt, "op_funlock_async", op_funlock_async);
 }
 
 fn into_string(s: std::ffi::OsString) -> Result<String, AnyError> {
@@ -136,6 +144,37 @@ struct OpenOptions {
   create_new: bool,
 }
 
+/// Opens `args.path` from the `EmbeddedFiles` in `state`, if there are any
+/// and the file is embedded there and opened read-only. Embedded files are
+/// always readable, so no permissions are checked.
+fn open_embedded(
+  state: &mut OpState,
+  args: &Value,
+) -> Result<Option<u32>, AnyError> {
+  let embedded_files = match state.try_borrow::<EmbeddedFiles>() {
+    Some(embedded_files) => embedded_files,
+    None => return Ok(None),
+  };
+  let args: OpenArgs = serde_json::from_value(args.clone())?;
+  let options = args.options;
+  if options.write
+    || options.append
+    || options.create
+    || options.truncate
+    || options.create_new
+  {
+    return Ok(None);
+  }
+  let contents = match embedded_files.read(Path::new(&args.path)) {
+    Some(contents) => contents?,
+    None => return Ok(None),
+  };
+  let rid = state
+    .resource_table
+    .add(EmbeddedFileResource::new(contents));
+  Ok(Some(rid))
+}
+
 fn open_helper(
   state: &mut OpState,
   args: Value,
@@ -184,6 +223,9 @@ fn op_open_sync(
   args: Value,
   _zero_copy: &mut [ZeroCopyBuf],
 ) -> Result<Value, AnyError> {
+  if let Some(rid) = open_embedded(state, &args)? {
+    return Ok(json!(rid));
+  }
   let (path, open_options) = open_helper(state, args)?;
   let std_file = open_options.open(path)?;
   let tokio_file = tokio::fs::File::from_std(std_file);
@@ -197,6 +239,9 @@ async fn op_open_async(
   args: Value,
   _zero_copy: BufVec,
 ) -> Result<Value, AnyError> {
+  if let Some(rid) = open_embedded(&mut state.borrow_mut(), &args)? {
+    return Ok(json!(rid));
+  }
   let (path, open_options) = open_helper(&mut state.borrow_mut(), args)?;
   let tokio_file = tokio::fs::OpenOptions::from(open_options)
     .open(path)
@@ -238,6 +283,12 @@ fn op_seek_sync(
   _zero_copy: &mut [ZeroCopyBuf],
 ) -> Result<Value, AnyError> {
   let (rid, seek_from) = seek_helper(args)?;
+  if let Some(embedded_file) =
+    state.resource_table.get::<EmbeddedFileResource>(rid)
+  {
+    let pos = embedded_file.seek(seek_from)?;
+    return Ok(json!(pos));
+  }
   let pos = StdFileResource::with(state, rid, |r| match r {
     Ok(std_file) => std_file.seek(seek_from).map_err(AnyError::from),
     Err(_) => Err(type_error(
@@ -254,6 +305,15 @@ async fn op_seek_async(
 ) -> Result<Value, AnyError> {
   let (rid, seek_from) = seek_helper(args)?;
 
+  let embedded_file = state
+    .borrow()
+    .resource_table
+    .get::<EmbeddedFileResource>(rid);
+  if let Some(embedded_file) = embedded_file {
+    let pos = embedded_file.seek(seek_from)?;
+    return Ok(json!(pos));
+  }
+
   let resource = state
     .borrow_mut()
     .resource_table
@@ -1702,6 +1762,192 @@ async fn op_futime_async(
   .unwrap()
 }
 
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct FlockArgs {
+  rid: i32,
+  exclusive: bool,
+  non_blocking: bool,
+}
+
+/// Takes an advisory lock on `file` (`flock(2)` on Unix, `LockFileEx` on
+/// Windows). Returns `false` instead of an error if `non_blocking` is set and
+/// the lock is held elsewhere.
+fn lock_file(
+  file: &std::fs::File,
+  exclusive: bool,
+  non_blocking: bool,
+) -> Result<bool, AnyError> {
+  use fs2::FileExt;
+  let result = match (exclusive, non_blocking) {
+    (true, false) => file.lock_exclusive(),
+    (false, false) => file.lock_shared(),
+    (true, true) => file.try_lock_exclusive(),
+    (false, true) => file.try_lock_shared(),
+  };
+  match result {
+    Ok(()) => Ok(true),
+    Err(err)
+      if non_blocking
+        && err.raw_os_error() == fs2::lock_contended_error().raw_os_error() =>
+    {
+      Ok(false)
+    }
+    Err(err) => Err(err.into()),
+  }
+}
+
+fn op_flock_sync(
+  state: &mut OpState,
+  args: Value,
+  _zero_copy: &mut [ZeroCopyBuf],
+) -> Result<Value, AnyError> {
+  super::check_unstable(state, "Deno.flockSync");
+  let args: FlockArgs = serde_json::from_value(args)?;
+  let rid = args.rid as u32;
+
+  let acquired = StdFileResource::with(state, rid, |r| match r {
+    Ok(std_file) => lock_file(std_file, args.exclusive, args.non_blocking),
+    Err(_) => Err(type_error("cannot lock this type of resource")),
+  })?;
+
+  if acquired {
+    let resource = state
+      .resource_table
+      .get::<StdFileResource>(rid)
+      .ok_or_else(bad_resource_id)?;
+    resource.locked.set(true);
+  }
+
+  Ok(json!(acquired))
+}
+
+async fn op_flock_async(
+  state: Rc<RefCell<OpState>>,
+  args: Value,
+  _zero_copy: BufVec,
+) -> Result<Value, AnyError> {
+  super::check_unstable2(&state, "Deno.flock");
+  let args: FlockArgs = serde_json::from_value(args)?;
+  let rid = args.rid as u32;
+
+  let resource = state
+    .borrow_mut()
+    .resource_table
+    .get::<StdFileResource>(rid)
+    .ok_or_else(bad_resource_id)?;
+
+  if resource.fs_file.is_none() {
+    return Err(type_error("cannot lock this type of resource"));
+  }
+
+  let std_file = {
+    let mut fs_file = RcRef::map(&resource, |r| r.fs_file.as_ref().unwrap())
+      .borrow_mut()
+      .await;
+    (*fs_file)
+      .0
+      .as_mut()
+      .unwrap()
+      .try_clone()
+      .await?
+      .into_std()
+      .await
+  };
+
+  // A blocking lock may wait indefinitely for another process to release the
+  // file, so it must not run on the event loop thread.
+  let acquired = tokio::task::spawn_blocking(move || {
+    lock_file(&std_file, args.exclusive, args.non_blocking)
+  })
+  .await
+  .unwrap()?;
+
+  if acquired {
+    resource.locked.set(true);
+  }
+
+  Ok(json!(acquired))
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct FunlockArgs {
+  rid: i32,
+}
+
+fn unlock_file(
+  resource: &StdFileResource,
+  file: &std::fs::File,
+) -> Result<(), AnyError> {
+  use fs2::FileExt;
+  if !resource.locked.get() {
+    return Err(bad_resource("File is not locked"));
+  }
+  file.unlock()?;
+  resource.locked.set(false);
+  Ok(())
+}
+
+fn op_funlock_sync(
+  state: &mut OpState,
+  args: Value,
+  _zero_copy: &mut [ZeroCopyBuf],
+) -> Result<Value, AnyError> {
+  super::check_unstable(state, "Deno.funlockSync");
+  let args: FunlockArgs = serde_json::from_value(args)?;
+  let rid = args.rid as u32;
+
+  let resource = state
+    .resource_table
+    .get::<StdFileResource>(rid)
+    .ok_or_else(bad_resource_id)?;
+
+  StdFileResource::with(state, rid, |r| match r {
+    Ok(std_file) => unlock_file(&resource, std_file),
+    Err(_) => Err(type_error("cannot unlock this type of resource")),
+  })?;
+
+  Ok(json!({}))
+}
+
+async fn op_funlock_async(
+  state: Rc<RefCell<OpState>>,
+  args: Value,
+  _zero_copy: BufVec,
+) -> Result<Value, AnyError> {
+  super::check_unstable2(&state, "Deno.funlock");
+  let args: FunlockArgs = serde_json::from_value(args)?;
+  let rid = args.rid as u32;
+
+  let resource = state
+    .borrow_mut()
+    .resource_table
+    .get::<StdFileResource>(rid)
+    .ok_or_else(bad_resource_id)?;
+
+  if resource.fs_file.is_none() {
+    return Err(type_error("cannot unlock this type of resource"));
+  }
+
+  let mut fs_file = RcRef::map(&resource, |r| r.fs_file.as_ref().unwrap())
+    .borrow_mut()
+    .await;
+  let std_file = (*fs_file)
+    .0
+    .as_mut()
+    .unwrap()
+    .try_clone()
+    .await?
+    .into_std()
+    .await;
+
+  // Unlocking never blocks, so there is no need for a blocking task here.
+  unlock_file(&resource, &std_file)?;
+
+  Ok(json!({}))
+}
+
 #[derive(Deserialize)]
 #[serde(rename_all = "camelCase")]
 struct UtimeArgs {
@@ -23,6 +23,7 @@ use deno_core::Resource;
 use deno_core::ZeroCopyBuf;
 use serde::Deserialize;
 use std::borrow::Cow;
+use std::cell::Cell;
 use std::cell::RefCell;
 use std::convert::TryInto;
 use std::io::Read;
@@ -339,6 +340,20 @@ impl Resource for ChildStderrResource {
 pub type TcpStreamResource =
   FullDuplexResource<tcp::OwnedReadHalf, tcp::OwnedWriteHalf>;
 
+impl TcpStreamResource {
+  /// Runs `f` on the underlying `TcpStream`, e.g. to change socket options.
+  /// The write half is borrowed for this, since reads may stay pending for a
+  /// long time while writes complete quickly.
+  pub async fn with_stream<F, R>(self: &Rc<Self>, f: F) -> Result<R, AnyError>
+  where
+    F: FnOnce(&TcpStream) -> std::io::Result<R>,
+  {
+    let wr = self.wr_borrow_mut().await;
+    let stream: &tcp::OwnedWriteHalf = &wr;
+    Ok(f(stream.as_ref())?)
+  }
+}
+
 impl Resource for TcpStreamResource {
   fn name(&self) -> Cow<str> {
     "tcpStream".into()
@@ -412,6 +427,9 @@ impl Resource for UnixStreamResource {
 pub struct StdFileResource {
   pub fs_file:
     Option<AsyncRefCell<(Option<tokio::fs::File>, Option<FileMetadata>)>>,
+  /// Whether an advisory lock was taken through `Deno.flock()` and not yet
+  /// released with `Deno.funlock()`.
+  pub locked: Cell<bool>,
   cancel: CancelHandle,
   name: String,
 }
@@ -526,6 +544,34 @@ impl Resource for StdFileResource {
   }
 }
 
+/// A file opened from the `EmbeddedFiles` in the `OpState`. Its contents are
+/// read into memory when it is opened.
+pub struct EmbeddedFileResource {
+  contents: RefCell<std::io::Cursor<Vec<u8>>>,
+}
+
+impl EmbeddedFileResource {
+  pub fn new(contents: Vec<u8>) -> Self {
+    Self {
+      contents: RefCell::new(std::io::Cursor::new(contents)),
+    }
+  }
+
+  pub fn read(&self, buf: &mut [u8]) -> Result<usize, AnyError> {
+    Ok(Read::read(&mut *self.contents.borrow_mut(), buf)?)
+  }
+
+  pub fn seek(&self, pos: std::io::SeekFrom) -> Result<u64, AnyError> {
+    Ok(std::io::Seek::seek(&mut *self.contents.borrow_mut(), pos)?)
+  }
+}
+
+impl Resource for EmbeddedFileResource {
+  fn name(&self) -> Cow<str> {
+    "embeddedFile".into()
+  }
+}
+
 pub fn op_read(
   state: Rc<RefCell<OpState>>,
   is_sync: bool,
@@ -552,6 +598,14 @@ fn op_read_sync(
   mut buf: ZeroCopyBuf,
 ) -> Result<i32, AnyError> {
   let rid = rid.try_into().map_err(|_| bad_resource_id())?;
+  let embedded_file = state
+    .borrow()
+    .resource_table
+    .get::<EmbeddedFileResource>(rid);
+  if let Some(embedded_file) = embedded_file {
+    let nread = embedded_file.read(&mut buf)?;
+    return Ok(nread as i32);
+  }
   StdFileResource::with(&mut state.borrow_mut(), rid, move |r| match r {
     Ok(std_file) => std_file
       .read(&mut buf)
@@ -586,6 +640,8 @@ async fn op_read_async(
     s.read(&mut buf).await?
   } else if let Some(s) = resource.downcast_rc::<StdFileResource>() {
     s.read(&mut buf).await?
+  } else if let Some(s) = resource.downcast_rc::<EmbeddedFileResource>() {
+    s.read(&mut buf)?
   } else {
     return Err(not_supported());
   };
@@ -28,6 +28,7 @@ use crate::metrics::metrics_op;
 use deno_core::error::AnyError;
 use deno_core::json_op_async;
 use deno_core::json_op_sync;
+use deno_core::op_async_priority;
 use deno_core::serde::de::DeserializeOwned;
 use deno_core::serde::Serialize;
 use deno_core::BufVec;
@@ -51,6 +52,25 @@ pub fn reg_json_async<F, V, R, RV>(
   rt.register_op(name, metrics_op(json_op_async(op_fn)));
 }
 
+/// Like `reg_json_async()`, but the results of the op are dispatched with
+/// `priority`, see `deno_core::op_async_priority()`.
+pub fn reg_json_async_priority<F, V, R, RV>(
+  rt: &mut JsRuntime,
+  name: &'static str,
+  priority: u8,
+  op_fn: F,
+) where
+  F: Fn(Rc<RefCell<OpState>>, V, BufVec) -> R + 'static,
+  V: DeserializeOwned,
+  R: Future<Output = Result<RV, AnyError>> + 'static,
+  RV: Serialize,
+{
+  rt.register_op(
+    name,
+    metrics_op(op_async_priority(priority, json_op_async(op_fn))),
+  );
+}
+
 pub fn reg_json_sync<F, V, R>(rt: &mut JsRuntime, name: &'static str, op_fn: F)
 where
   F: Fn(&mut OpState, V, &mut [ZeroCopyBuf]) -> Result<R, AnyError> + 'static,
@@ -5,8 +5,10 @@ use crate::permissions::Permissions;
 use crate::resolve_addr::resolve_addr;
 use crate::resolve_addr::resolve_addr_sync;
 use deno_core::error::bad_resource;
+use deno_core::error::bad_resource_id;
 use deno_core::error::custom_error;
 use deno_core::error::generic_error;
+use deno_core::error::not_supported;
 use deno_core::error::type_error;
 use deno_core::error::AnyError;
 use deno_core::serde_json;
@@ -14,6 +16,7 @@ use deno_core::serde_json::json;
 use deno_core::serde_json::Value;
 use deno_core::AsyncRefCell;
 use deno_core::BufVec;
+use deno_core::CancelFuture;
 use deno_core::CancelHandle;
 use deno_core::CancelTryFuture;
 use deno_core::OpState;
@@ -22,18 +25,30 @@ use deno_core::Resource;
 use deno_core::ZeroCopyBuf;
 use serde::Deserialize;
 use serde::Serialize;
+use socket2::Domain;
+use socket2::Protocol;
+use socket2::Socket;
+use socket2::Type;
 use std::borrow::Cow;
 use std::cell::RefCell;
+use std::mem::ManuallyDrop;
+use std::net::Ipv4Addr;
+use std::net::Ipv6Addr;
 use std::net::SocketAddr;
 use std::rc::Rc;
+use std::time::Duration;
 use tokio::net::TcpListener;
 use tokio::net::TcpStream;
 use tokio::net::UdpSocket;
+use trust_dns_proto::error::ProtoErrorKind;
+use trust_dns_proto::op::ResponseCode;
 use trust_dns_proto::rr::record_data::RData;
 use trust_dns_proto::rr::record_type::RecordType;
 use trust_dns_resolver::config::NameServerConfigGroup;
 use trust_dns_resolver::config::ResolverConfig;
 use trust_dns_resolver::config::ResolverOpts;
+use trust_dns_resolver::error::ResolveError;
+use trust_dns_resolver::error::ResolveErrorKind;
 use trust_dns_resolver::system_conf;
 use trust_dns_resolver::AsyncResolver;
 
@@ -50,7 +65,22 @@ pub fn init(rt: &mut deno_core::JsRuntime) {
   super::reg_json_sync(rt, "op_listen", op_listen);
   super::reg_json_async(rt, "op_datagram_receive", op_datagram_receive);
   super::reg_json_async(rt, "op_datagram_send", op_datagram_send);
+  super::reg_json_async(rt, "op_net_join_multi_v4", op_net_join_multi_v4);
+  super::reg_json_async(rt, "op_net_leave_multi_v4", op_net_leave_multi_v4);
+  super::reg_json_async(rt, "op_net_join_multi_v6", op_net_join_multi_v6);
+  super::reg_json_async(rt, "op_net_leave_multi_v6", op_net_leave_multi_v6);
+  super::reg_json_async(
+    rt,
+    "op_net_set_multi_loopback",
+    op_net_set_multi_loopback,
+  );
+  super::reg_json_async(rt, "op_net_set_multi_ttl", op_net_set_multi_ttl);
+  super::reg_json_async(rt, "op_net_set_broadcast", op_net_set_broadcast);
   super::reg_json_async(rt, "op_dns_resolve", op_dns_resolve);
+  super::reg_json_sync(rt, "op_dns_cancel_handle", op_dns_cancel_handle);
+  super::reg_json_async(rt, "op_net_set_nodelay", op_net_set_nodelay);
+  super::reg_json_async(rt, "op_net_get_nodelay", op_net_get_nodelay);
+  super::reg_json_async(rt, "op_net_set_keepalive", op_net_set_keepalive);
 }
 
 #[derive(Deserialize)]
@@ -248,6 +278,183 @@ async fn op_datagram_send(
   }
 }
 
+fn get_udp_socket_resource(
+  state: &Rc<RefCell<OpState>>,
+  rid: i32,
+) -> Result<Rc<UdpSocketResource>, AnyError> {
+  state
+    .borrow()
+    .resource_table
+    .get::<UdpSocketResource>(rid as u32)
+    .ok_or_else(|| bad_resource("Socket has been closed"))
+}
+
+/// Parses a multicast group address and checks that joining it is allowed.
+/// Groups are treated like any other host for the purpose of `--allow-net`.
+fn check_multicast_group<A>(
+  state: &Rc<RefCell<OpState>>,
+  address: &str,
+) -> Result<A, AnyError>
+where
+  A: std::str::FromStr + Into<std::net::IpAddr> + Copy,
+{
+  let addr = address
+    .parse::<A>()
+    .map_err(|_| type_error(format!("Invalid IP address: {}", address)))?;
+  if !addr.into().is_multicast() {
+    return Err(type_error(format!(
+      "{} is not a multicast group address",
+      address
+    )));
+  }
+  state
+    .borrow()
+    .borrow::<Permissions>()
+    .check_net(&(address, None))?;
+  Ok(addr)
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct MulticastV4Args {
+  rid: i32,
+  address: String,
+  multi_interface: String,
+}
+
+async fn op_net_join_multi_v4(
+  state: Rc<RefCell<OpState>>,
+  args: Value,
+  _zero_copy: BufVec,
+) -> Result<Value, AnyError> {
+  let args: MulticastV4Args = serde_json::from_value(args)?;
+  let addr = check_multicast_group::<Ipv4Addr>(&state, &args.address)?;
+  let interface = args.multi_interface.parse::<Ipv4Addr>().map_err(|_| {
+    type_error(format!(
+      "Invalid interface address: {}",
+      args.multi_interface
+    ))
+  })?;
+  let resource = get_udp_socket_resource(&state, args.rid)?;
+  let socket = RcRef::map(&resource, |r| &r.socket).borrow().await;
+  socket.join_multicast_v4(addr, interface)?;
+  Ok(json!({}))
+}
+
+async fn op_net_leave_multi_v4(
+  state: Rc<RefCell<OpState>>,
+  args: Value,
+  _zero_copy: BufVec,
+) -> Result<Value, AnyError> {
+  let args: MulticastV4Args = serde_json::from_value(args)?;
+  let addr = check_multicast_group::<Ipv4Addr>(&state, &args.address)?;
+  let interface = args.multi_interface.parse::<Ipv4Addr>().map_err(|_| {
+    type_error(format!(
+      "Invalid interface address: {}",
+      args.multi_interface
+    ))
+  })?;
+  let resource = get_udp_socket_resource(&state, args.rid)?;
+  let socket = RcRef::map(&resource, |r| &r.socket).borrow().await;
+  socket.leave_multicast_v4(addr, interface)?;
+  Ok(json!({}))
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct MulticastV6Args {
+  rid: i32,
+  address: String,
+  /// Interface index; `0` lets the system pick one.
+  multi_interface: u32,
+}
+
+async fn op_net_join_multi_v6(
+  state: Rc<RefCell<OpState>>,
+  args: Value,
+  _zero_copy: BufVec,
+) -> Result<Value, AnyError> {
+  let args: MulticastV6Args = serde_json::from_value(args)?;
+  let addr = check_multicast_group::<Ipv6Addr>(&state, &args.address)?;
+  let resource = get_udp_socket_resource(&state, args.rid)?;
+  let socket = RcRef::map(&resource, |r| &r.socket).borrow().await;
+  socket.join_multicast_v6(&addr, args.multi_interface)?;
+  Ok(json!({}))
+}
+
+async fn op_net_leave_multi_v6(
+  state: Rc<RefCell<OpState>>,
+  args: Value,
+  _zero_copy: BufVec,
+) -> Result<Value, AnyError> {
+  let args: MulticastV6Args = serde_json::from_value(args)?;
+  let addr = check_multicast_group::<Ipv6Addr>(&state, &args.address)?;
+  let resource = get_udp_socket_resource(&state, args.rid)?;
+  let socket = RcRef::map(&resource, |r| &r.socket).borrow().await;
+  socket.leave_multicast_v6(&addr, args.multi_interface)?;
+  Ok(json!({}))
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct MulticastLoopbackArgs {
+  rid: i32,
+  is_v4: bool,
+  loopback: bool,
+}
+
+async fn op_net_set_multi_loopback(
+  state: Rc<RefCell<OpState>>,
+  args: Value,
+  _zero_copy: BufVec,
+) -> Result<Value, AnyError> {
+  let args: MulticastLoopbackArgs = serde_json::from_value(args)?;
+  let resource = get_udp_socket_resource(&state, args.rid)?;
+  let socket = RcRef::map(&resource, |r| &r.socket).borrow().await;
+  if args.is_v4 {
+    socket.set_multicast_loop_v4(args.loopback)?;
+  } else {
+    socket.set_multicast_loop_v6(args.loopback)?;
+  }
+  Ok(json!({}))
+}
+
+#[derive(Deserialize)]
+struct MulticastTtlArgs {
+  rid: i32,
+  ttl: u32,
+}
+
+async fn op_net_set_multi_ttl(
+  state: Rc<RefCell<OpState>>,
+  args: Value,
+  _zero_copy: BufVec,
+) -> Result<Value, AnyError> {
+  let args: MulticastTtlArgs = serde_json::from_value(args)?;
+  let resource = get_udp_socket_resource(&state, args.rid)?;
+  let socket = RcRef::map(&resource, |r| &r.socket).borrow().await;
+  socket.set_multicast_ttl_v4(args.ttl)?;
+  Ok(json!({}))
+}
+
+#[derive(Deserialize)]
+struct BroadcastArgs {
+  rid: i32,
+  broadcast: bool,
+}
+
+async fn op_net_set_broadcast(
+  state: Rc<RefCell<OpState>>,
+  args: Value,
+  _zero_copy: BufVec,
+) -> Result<Value, AnyError> {
+  let args: BroadcastArgs = serde_json::from_value(args)?;
+  let resource = get_udp_socket_resource(&state, args.rid)?;
+  let socket = RcRef::map(&resource, |r| &r.socket).borrow().await;
+  socket.set_broadcast(args.broadcast)?;
+  Ok(json!({}))
+}
+
 #[derive(Deserialize)]
 struct ConnectArgs {
   transport: String,
@@ -333,6 +540,120 @@ async fn op_connect(
   }
 }
 
+/// Runs `f` on a `socket2::Socket` that borrows the file descriptor of
+/// `stream`, for options that tokio doesn't expose.
+fn with_socket2<F, R>(stream: &TcpStream, f: F) -> std::io::Result<R>
+where
+  F: FnOnce(&Socket) -> std::io::Result<R>,
+{
+  // The socket must not be dropped, because that would close the descriptor
+  // that is still owned by `stream`.
+  #[cfg(unix)]
+  let socket = {
+    use std::os::unix::io::AsRawFd;
+    use std::os::unix::io::FromRawFd;
+    ManuallyDrop::new(unsafe { Socket::from_raw_fd(stream.as_raw_fd()) })
+  };
+  #[cfg(windows)]
+  let socket = {
+    use std::os::windows::io::AsRawSocket;
+    use std::os::windows::io::FromRawSocket;
+    ManuallyDrop::new(unsafe {
+      Socket::from_raw_socket(stream.as_raw_socket())
+    })
+  };
+  f(&socket)
+}
+
+fn get_tcp_stream_resource(
+  state: &Rc<RefCell<OpState>>,
+  rid: i32,
+) -> Result<Rc<TcpStreamResource>, AnyError> {
+  let resource = state
+    .borrow()
+    .resource_table
+    .get_any(rid as u32)
+    .ok_or_else(bad_resource_id)?;
+  resource
+    .downcast_rc::<TcpStreamResource>()
+    .cloned()
+    .ok_or_else(|| {
+      // TLS streams are split with `tokio::io::split()`, which doesn't give
+      // access to the inner `TcpStream` again.
+      not_supported()
+    })
+}
+
+#[derive(Deserialize)]
+struct SetNoDelayArgs {
+  rid: i32,
+  nodelay: bool,
+}
+
+async fn op_net_set_nodelay(
+  state: Rc<RefCell<OpState>>,
+  args: Value,
+  _zero_copy: BufVec,
+) -> Result<Value, AnyError> {
+  let args: SetNoDelayArgs = serde_json::from_value(args)?;
+  let resource = get_tcp_stream_resource(&state, args.rid)?;
+  resource
+    .with_stream(|stream| stream.set_nodelay(args.nodelay))
+    .await?;
+  Ok(json!({}))
+}
+
+#[derive(Deserialize)]
+struct RidArgs {
+  rid: i32,
+}
+
+/// Only used by tests, to check that `op_net_set_nodelay` took effect.
+async fn op_net_get_nodelay(
+  state: Rc<RefCell<OpState>>,
+  args: Value,
+  _zero_copy: BufVec,
+) -> Result<Value, AnyError> {
+  let args: RidArgs = serde_json::from_value(args)?;
+  let resource = get_tcp_stream_resource(&state, args.rid)?;
+  let nodelay = resource.with_stream(|stream| stream.nodelay()).await?;
+  Ok(json!(nodelay))
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SetKeepAliveArgs {
+  rid: i32,
+  keepalive: bool,
+  /// Idle time before the first probe is sent. Defaults to two hours, which
+  /// is what most systems use.
+  interval_secs: Option<u64>,
+}
+
+async fn op_net_set_keepalive(
+  state: Rc<RefCell<OpState>>,
+  args: Value,
+  _zero_copy: BufVec,
+) -> Result<Value, AnyError> {
+  let args: SetKeepAliveArgs = serde_json::from_value(args)?;
+  let keepalive = if args.keepalive {
+    let secs = args.interval_secs.unwrap_or(2 * 60 * 60);
+    if secs == 0 {
+      return Err(type_error("Keepalive interval must be positive"));
+    }
+    Some(Duration::from_secs(secs))
+  } else {
+    None
+  };
+  let resource = get_tcp_stream_resource(&state, args.rid)?;
+  resource
+    .with_stream(|stream| {
+      with_socket2(stream, |socket| socket.set_keepalive(keepalive))
+    })
+    .await?;
+  Ok(json!({}))
+}
+
 struct TcpListenerResource {
   listener: AsyncRefCell<TcpListener>,
   cancel: CancelHandle,
@@ -367,6 +688,7 @@ impl Resource for UdpSocketResource {
 struct IpListenArgs {
   hostname: String,
   port: u16,
+  backlog: Option<u32>,
 }
 
 #[derive(Deserialize)]
@@ -384,13 +706,41 @@ struct ListenArgs {
   transport_args: ArgsEnum,
 }
 
+/// The accept backlog used when none is given, same as the standard library's.
+const DEFAULT_BACKLOG: u32 = 128;
+
+/// Binds a TCP listener with the given accept backlog, which `std::net`
+/// doesn't allow to configure.
+pub(crate) fn bind_tcp_listener(
+  addr: SocketAddr,
+  backlog: Option<u32>,
+) -> Result<TcpListener, AnyError> {
+  let domain = if addr.is_ipv4() {
+    Domain::ipv4()
+  } else {
+    Domain::ipv6()
+  };
+  let socket = Socket::new(domain, Type::stream(), Some(Protocol::tcp()))?;
+  // Matches what `std::net::TcpListener::bind` does on these platforms.
+  #[cfg(not(windows))]
+  socket.set_reuse_address(true)?;
+  socket.bind(&addr.into())?;
+  let backlog = backlog.unwrap_or(DEFAULT_BACKLOG);
+  if backlog == 0 || backlog > i32::MAX as u32 {
+    return Err(type_error("Invalid backlog"));
+  }
+  socket.listen(backlog as i32)?;
+  let std_listener = socket.into_tcp_listener();
+  std_listener.set_nonblocking(true)?;
+  Ok(TcpListener::from_std(std_listener)?)
+}
+
 fn listen_tcp(
   state: &mut OpState,
   addr: SocketAddr,
+  backlog: Option<u32>,
 ) -> Result<(u32, SocketAddr), AnyError> {
-  let std_listener = std::net::TcpListener::bind(&addr)?;
-  std_listener.set_nonblocking(true)?;
-  let listener = TcpListener::from_std(std_listener)?;
+  let listener = bind_tcp_listener(addr, backlog)?;
   let local_addr = listener.local_addr()?;
   let listener_resource = TcpListenerResource {
     listener: AsyncRefCell::new(listener),
@@ -439,7 +789,7 @@ fn op_listen(
         .next()
         .ok_or_else(|| generic_error("No resolved address found"))?;
       let (rid, local_addr) = if transport == "tcp" {
-        listen_tcp(state, addr)?
+        listen_tcp(state, addr, args.backlog)?
       } else {
         listen_udp(state, addr)?
       };
@@ -533,6 +883,7 @@ async fn op_dns_resolve(
     query: String,
     record_type: RecordType,
     options: Option<ResolveDnsOption>,
+    cancel_rid: Option<u32>,
   }
 
   #[derive(Deserialize)]
@@ -553,6 +904,7 @@ async fn op_dns_resolve(
     query,
     record_type,
     options,
+    cancel_rid,
   } = serde_json::from_value(args)?;
 
   let (config, opts) = if let Some(name_server) =
@@ -584,12 +936,30 @@ async fn op_dns_resolve(
     }
   }
 
+  let cancel = match cancel_rid {
+    Some(rid) => Some(
+      state
+        .borrow()
+        .resource_table
+        .get::<DnsCancelResource>(rid)
+        .ok_or_else(bad_resource_id)?,
+    ),
+    None => None,
+  };
+
   let resolver = AsyncResolver::tokio(config, opts)?;
 
-  let results: Vec<DnsReturnRecord> = resolver
-    .lookup(query, record_type, Default::default())
-    .await
-    .map_err(|e| generic_error(format!("{}", e)))?
+  let lookup = resolver.lookup(query, record_type, Default::default());
+  let lookup = match cancel {
+    Some(cancel) => lookup
+      .or_cancel(RcRef::map(&cancel, |r| &r.0))
+      .await
+      .map_err(|_| custom_error("Interrupted", "DNS lookup was aborted"))?,
+    None => lookup.await,
+  }
+  .map_err(dns_error)?;
+
+  let results: Vec<DnsReturnRecord> = lookup
     .iter()
     .filter_map(rdata_to_return_record(record_type))
     .collect();
@@ -597,6 +967,50 @@ async fn op_dns_resolve(
   Ok(json!(results))
 }
 
+/// Maps resolver errors to the error classes JavaScript checks for, so that a
+/// missing record can be told apart from an unreachable name server.
+fn dns_error(err: ResolveError) -> AnyError {
+  match err.kind() {
+    ResolveErrorKind::NoRecordsFound { response_code, .. }
+      if *response_code != ResponseCode::ServFail =>
+    {
+      custom_error("NotFound", err.to_string())
+    }
+    ResolveErrorKind::Timeout => custom_error("TimedOut", err.to_string()),
+    ResolveErrorKind::Proto(proto_err)
+      if matches!(proto_err.kind(), ProtoErrorKind::Timeout) =>
+    {
+      custom_error("TimedOut", err.to_string())
+    }
+    _ => generic_error(err.to_string()),
+  }
+}
+
+/// Lets JavaScript abort a pending `op_dns_resolve` by closing this resource.
+struct DnsCancelResource(CancelHandle);
+
+impl Resource for DnsCancelResource {
+  fn name(&self) -> Cow<str> {
+    "dnsCancelHandle".into()
+  }
+
+  fn close(self: Rc<Self>) {
+    self.0.cancel()
+  }
+}
+
+#[allow(clippy::unnecessary_wraps)]
+fn op_dns_cancel_handle(
+  state: &mut OpState,
+  _args: Value,
+  _zero_copy: &mut [ZeroCopyBuf],
+) -> Result<Value, AnyError> {
+  let rid = state
+    .resource_table
+    .add(DnsCancelResource(CancelHandle::default()));
+  Ok(json!(rid))
+}
+
 fn rdata_to_return_record(
   ty: RecordType,
 ) -> impl Fn(&RData) -> Option<DnsReturnRecord> {
@@ -123,6 +123,10 @@ impl<'a> plugin_api::Interface for PluginInterface<'a> {
         Op::AsyncUnref(fut) => {
           Op::AsyncUnref(PluginOpAsyncFuture::new(&plugin_lib, fut))
         }
+        Op::AsyncPriority(priority, fut) => Op::AsyncPriority(
+          priority,
+          PluginOpAsyncFuture::new(&plugin_lib, fut),
+        ),
         _ => unreachable!(),
       }
     };
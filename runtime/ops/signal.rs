@@ -31,10 +31,19 @@ use std::borrow::Cow;
 #[cfg(unix)]
 use tokio::signal::unix::{signal, Signal, SignalKind};
 
+/// Signal handlers are run before timer callbacks and the callbacks of ops
+/// with the default priority that completed in the same turn of the event loop.
+const SIGNAL_OP_PRIORITY: u8 = 32;
+
 pub fn init(rt: &mut deno_core::JsRuntime) {
   super::reg_json_sync(rt, "op_signal_bind", op_signal_bind);
   super::reg_json_sync(rt, "op_signal_unbind", op_signal_unbind);
-  super::reg_json_async(rt, "op_signal_poll", op_signal_poll);
+  super::reg_json_async_priority(
+    rt,
+    "op_signal_poll",
+    SIGNAL_OP_PRIORITY,
+    op_signal_poll,
+  );
 }
 
 #[cfg(unix)]
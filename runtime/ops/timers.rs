@@ -71,6 +71,10 @@ impl GlobalTimer {
   }
 }
 
+/// Timer callbacks are run before the callbacks of ops with the default
+/// priority that completed in the same turn of the event loop.
+const TIMER_OP_PRIORITY: u8 = 64;
+
 pub fn init(rt: &mut deno_core::JsRuntime) {
   {
     let op_state = rt.op_state();
@@ -80,7 +84,12 @@ pub fn init(rt: &mut deno_core::JsRuntime) {
   }
   super::reg_json_sync(rt, "op_global_timer_stop", op_global_timer_stop);
   super::reg_json_sync(rt, "op_global_timer_start", op_global_timer_start);
-  super::reg_json_async(rt, "op_global_timer", op_global_timer);
+  super::reg_json_async_priority(
+    rt,
+    "op_global_timer",
+    TIMER_OP_PRIORITY,
+    op_global_timer,
+  );
   rt.register_op("op_now", metrics_op(minimal_op(op_now)));
   super::reg_json_sync(rt, "op_sleep_sync", op_sleep_sync);
 }
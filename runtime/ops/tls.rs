@@ -3,6 +3,7 @@
 use super::io::TcpStreamResource;
 use super::io::TlsClientStreamResource;
 use super::io::TlsServerStreamResource;
+use super::net::bind_tcp_listener;
 use crate::permissions::Permissions;
 use crate::resolve_addr::resolve_addr;
 use crate::resolve_addr::resolve_addr_sync;
@@ -91,6 +92,11 @@ struct ConnectTLSArgs {
 struct StartTLSArgs {
   rid: u32,
   cert_file: Option<String>,
+  /// PEM encoded CA certificates to trust in addition to the default roots.
+  #[serde(default)]
+  ca_certs: Vec<String>,
+  #[serde(default)]
+  alpn_protocols: Vec<String>,
   hostname: String,
 }
 
@@ -116,18 +122,6 @@ async fn op_start_tls(
     }
   }
 
-  let resource_rc = state
-    .borrow_mut()
-    .resource_table
-    .take::<TcpStreamResource>(rid)
-    .ok_or_else(bad_resource_id)?;
-  let resource = Rc::try_unwrap(resource_rc)
-    .expect("Only a single use of this resource should happen");
-  let (read_half, write_half) = resource.into_inner();
-  let tcp_stream = read_half.reunite(write_half)?;
-
-  let local_addr = tcp_stream.local_addr()?;
-  let remote_addr = tcp_stream.peer_addr()?;
   let mut config = ClientConfig::new();
   config.set_persistence(CLIENT_SESSION_MEMORY_CACHE.clone());
   config
@@ -136,20 +130,70 @@ async fn op_start_tls(
   if let Some(path) = args.cert_file {
     let key_file = File::open(path)?;
     let reader = &mut BufReader::new(key_file);
-    config.root_store.add_pem_file(reader).unwrap();
+    config
+      .root_store
+      .add_pem_file(reader)
+      .map_err(|_| invalid_data("Unable to decode certificate file"))?;
   }
-
-  let tls_connector = TlsConnector::from(Arc::new(config));
+  for ca_cert in &args.ca_certs {
+    let reader = &mut BufReader::new(ca_cert.as_bytes());
+    match config.root_store.add_pem_file(reader) {
+      Ok((added, _)) if added > 0 => {}
+      _ => return Err(invalid_data("Unable to decode CA certificate")),
+    }
+  }
+  config.set_protocols(
+    &args
+      .alpn_protocols
+      .into_iter()
+      .map(String::into_bytes)
+      .collect::<Vec<_>>(),
+  );
   let dnsname = DNSNameRef::try_from_ascii_str(&domain)
     .map_err(|_| generic_error("Invalid DNS lookup"))?;
-  let tls_stream = tls_connector.connect(dnsname, tcp_stream).await?;
 
-  let rid = {
-    let mut state_ = state.borrow_mut();
-    state_
-      .resource_table
-      .add(TlsClientStreamResource::from(tls_stream))
+  let resource_rc = state
+    .borrow_mut()
+    .resource_table
+    .take::<TcpStreamResource>(rid)
+    .ok_or_else(bad_resource_id)?;
+  // Pending reads or writes still hold references to the resource. The
+  // stream can't be taken apart under them, so leave it in place; callers
+  // have to wait for (or cancel) those ops before upgrading.
+  let resource = match Rc::try_unwrap(resource_rc) {
+    Ok(resource) => resource,
+    Err(resource_rc) => {
+      state
+        .borrow_mut()
+        .resource_table
+        .replace_rc(rid, resource_rc);
+      return Err(custom_error(
+        "Busy",
+        "Cannot start TLS while the connection has pending reads or writes",
+      ));
+    }
   };
+  let (read_half, write_half) = resource.into_inner();
+  let tcp_stream = read_half.reunite(write_half)?;
+
+  let local_addr = tcp_stream.local_addr()?;
+  let remote_addr = tcp_stream.peer_addr()?;
+
+  let tls_connector = TlsConnector::from(Arc::new(config));
+  let tls_stream =
+    tls_connector
+      .connect(dnsname, tcp_stream)
+      .await
+      .map_err(|err| {
+        invalid_data(format!("TLS handshake with {} failed: {}", domain, err))
+      })?;
+
+  // The upgraded stream replaces the plaintext one under the same rid, so
+  // that existing references to the connection keep working.
+  state
+    .borrow_mut()
+    .resource_table
+    .replace(rid, TlsClientStreamResource::from(tls_stream));
   Ok(json!({
       "rid": rid,
       "localAddr": {
@@ -165,6 +209,10 @@ async fn op_start_tls(
   }))
 }
 
+fn invalid_data(message: impl Into<Cow<'static, str>>) -> AnyError {
+  custom_error("InvalidData", message)
+}
+
 async fn op_connect_tls(
   state: Rc<RefCell<OpState>>,
   args: Value,
@@ -304,6 +352,7 @@ struct ListenTlsArgs {
   port: u16,
   cert_file: String,
   key_file: String,
+  backlog: Option<u32>,
 }
 
 fn op_listen_tls(
@@ -330,9 +379,7 @@ fn op_listen_tls(
   let addr = resolve_addr_sync(&args.hostname, args.port)?
     .next()
     .ok_or_else(|| generic_error("No resolved address found"))?;
-  let std_listener = std::net::TcpListener::bind(&addr)?;
-  std_listener.set_nonblocking(true)?;
-  let listener = TcpListener::from_std(std_listener)?;
+  let listener = bind_tcp_listener(addr, args.backlog)?;
   let local_addr = listener.local_addr()?;
   let tls_listener_resource = TlsListenerResource {
     listener: AsyncRefCell::new(listener),
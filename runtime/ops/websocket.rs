@@ -1,5 +1,6 @@
 // Copyright 2018-2021 the Deno authors. All rights reserved. MIT license.
 use crate::permissions::Permissions;
+use deno_websocket::op_ws_cancel_handle;
 use deno_websocket::op_ws_check_permission;
 use deno_websocket::op_ws_close;
 use deno_websocket::op_ws_create;
@@ -26,6 +27,7 @@ pub fn init(
     "op_ws_check_permission",
     op_ws_check_permission::<Permissions>,
   );
+  super::reg_json_sync(rt, "op_ws_cancel_handle", op_ws_cancel_handle);
   super::reg_json_async(rt, "op_ws_create", op_ws_create::<Permissions>);
   super::reg_json_async(rt, "op_ws_send", op_ws_send);
   super::reg_json_async(rt, "op_ws_close", op_ws_close);
@@ -17,7 +17,7 @@ use deno_core::futures::task::AtomicWaker;
 use deno_core::serde_json;
 use deno_core::serde_json::json;
 use deno_core::url::Url;
-use deno_core::v8;
+use deno_core::CancellationToken;
 use deno_core::GetErrorClassFn;
 use deno_core::JsErrorCreateFn;
 use deno_core::JsRuntime;
@@ -26,8 +26,6 @@ use deno_core::ModuleSpecifier;
 use deno_core::RuntimeOptions;
 use std::env;
 use std::rc::Rc;
-use std::sync::atomic::AtomicBool;
-use std::sync::atomic::Ordering;
 use std::sync::Arc;
 use std::task::Context;
 use std::task::Poll;
@@ -58,8 +56,7 @@ pub struct WebWorkerHandle {
   pub sender: mpsc::Sender<Box<[u8]>>,
   pub receiver: Arc<AsyncMutex<mpsc::Receiver<WorkerEvent>>>,
   terminate_tx: mpsc::Sender<()>,
-  terminated: Arc<AtomicBool>,
-  isolate_handle: v8::IsolateHandle,
+  cancellation_token: CancellationToken,
 }
 
 impl WebWorkerHandle {
@@ -79,12 +76,9 @@ impl WebWorkerHandle {
 
   pub fn terminate(&self) {
     // This function can be called multiple times by whomever holds
-    // the handle. However only a single "termination" should occur so
-    // we need a guard here.
-    let already_terminated = self.terminated.swap(true, Ordering::SeqCst);
-
-    if !already_terminated {
-      self.isolate_handle.terminate_execution();
+    // the handle. However only a single "termination" should occur, which
+    // the cancellation token guards.
+    if self.cancellation_token.cancel() {
       let mut sender = self.terminate_tx.clone();
       // This call should be infallible hence the `expect`.
       // This might change in the future.
@@ -94,7 +88,7 @@ impl WebWorkerHandle {
 }
 
 fn create_channels(
-  isolate_handle: v8::IsolateHandle,
+  cancellation_token: CancellationToken,
   terminate_tx: mpsc::Sender<()>,
 ) -> (WorkerChannelsInternal, WebWorkerHandle) {
   let (in_tx, in_rx) = mpsc::channel::<Box<[u8]>>(1);
@@ -106,9 +100,8 @@ fn create_channels(
   let external_channels = WebWorkerHandle {
     sender: in_tx,
     receiver: Arc::new(AsyncMutex::new(out_rx)),
-    terminated: Arc::new(AtomicBool::new(false)),
     terminate_tx,
-    isolate_handle,
+    cancellation_token,
   };
   (internal_channels, external_channels)
 }
@@ -183,9 +176,9 @@ impl WebWorker {
     };
 
     let (terminate_tx, terminate_rx) = mpsc::channel::<()>(1);
-    let isolate_handle = js_runtime.v8_isolate().thread_safe_handle();
+    let cancellation_token = js_runtime.cancellation_token();
     let (internal_channels, handle) =
-      create_channels(isolate_handle, terminate_tx);
+      create_channels(cancellation_token, terminate_tx);
 
     let mut worker = Self {
       id: worker_id,
@@ -231,11 +224,28 @@ impl WebWorker {
       );
       ops::reg_json_sync(js_runtime, "op_close", deno_core::op_close);
       ops::reg_json_sync(js_runtime, "op_resources", deno_core::op_resources);
+      ops::reg_json_sync(
+        js_runtime,
+        "op_abort_signal_new",
+        deno_core::op_abort_signal_new,
+      );
+      ops::reg_json_sync(js_runtime, "op_abort", deno_core::op_abort);
       ops::reg_json_sync(
         js_runtime,
         "op_domain_to_ascii",
         deno_web::op_domain_to_ascii,
       );
+      ops::reg_json_sync(
+        js_runtime,
+        "op_urlpattern_parse",
+        deno_web::op_urlpattern_parse,
+      );
+      ops::reg_json_sync(
+        js_runtime,
+        "op_urlpattern_process_match_input",
+        deno_web::op_urlpattern_process_match_input,
+      );
+      js_runtime.register_performance_ops();
       ops::io::init(js_runtime);
       ops::websocket::init(
         js_runtime,
@@ -327,7 +337,7 @@ impl WebWorker {
   }
 
   pub fn has_been_terminated(&self) -> bool {
-    self.handle.terminated.load(Ordering::SeqCst)
+    self.handle.cancellation_token.is_cancelled()
   }
 
   pub fn poll_event_loop(
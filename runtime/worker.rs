@@ -126,11 +126,28 @@ impl MainWorker {
       ops::crypto::init(js_runtime, options.seed);
       ops::reg_json_sync(js_runtime, "op_close", deno_core::op_close);
       ops::reg_json_sync(js_runtime, "op_resources", deno_core::op_resources);
+      ops::reg_json_sync(
+        js_runtime,
+        "op_abort_signal_new",
+        deno_core::op_abort_signal_new,
+      );
+      ops::reg_json_sync(js_runtime, "op_abort", deno_core::op_abort);
       ops::reg_json_sync(
         js_runtime,
         "op_domain_to_ascii",
         deno_web::op_domain_to_ascii,
       );
+      ops::reg_json_sync(
+        js_runtime,
+        "op_urlpattern_parse",
+        deno_web::op_urlpattern_parse,
+      );
+      ops::reg_json_sync(
+        js_runtime,
+        "op_urlpattern_process_match_input",
+        deno_web::op_urlpattern_process_match_input,
+      );
+      js_runtime.register_performance_ops();
       ops::fs_events::init(js_runtime);
       ops::fs::init(js_runtime);
       ops::io::init(js_runtime);
@@ -346,4 +363,29 @@ mod tests {
     let result = worker.execute_module(&module_specifier).await;
     assert!(result.is_ok());
   }
+
+  #[tokio::test]
+  async fn performance_entries() {
+    let mut worker = create_test_worker();
+    worker
+      .execute(
+        r#"
+        performance.mark("start", { startTime: 10, detail: { step: 1 } });
+        performance.measure("work", { start: "start", end: 25 });
+        "#,
+      )
+      .unwrap();
+    // The marks and measures of the `performance` global are buffered where
+    // `JsRuntime::get_performance_entries()` reads them.
+    let entries = worker.js_runtime.get_performance_entries();
+    assert_eq!(entries.len(), 2);
+    assert_eq!(entries[0].name, "start");
+    assert_eq!(entries[0].entry_type, "mark");
+    assert_eq!(
+      entries[0].detail,
+      deno_core::serde_json::json!({ "step": 1 })
+    );
+    assert_eq!(entries[1].name, "work");
+    assert_eq!((entries[1].start_time, entries[1].duration), (10.0, 15.0));
+  }
 }
@@ -50,6 +50,8 @@ use tokio_tungstenite::accept_async;
 
 const PORT: u16 = 4545;
 const TEST_AUTH_TOKEN: &str = "abcdef123456789";
+/// The base64 encoding of `testuser:testpassword`.
+const TEST_BASIC_AUTH: &str = "dGVzdHVzZXI6dGVzdHBhc3N3b3Jk";
 const REDIRECT_PORT: u16 = 4546;
 const ANOTHER_REDIRECT_PORT: u16 = 4547;
 const DOUBLE_REDIRECTS_PORT: u16 = 4548;
@@ -72,6 +74,11 @@ lazy_static! {
   ).unwrap();
 
   static ref GUARD: Mutex<HttpServerCount> = Mutex::new(HttpServerCount::default());
+
+  /// The number of requests the main server got for each host and path, see
+  /// `/request_count/`.
+  static ref REQUEST_COUNTS: Mutex<HashMap<String, usize>> =
+    Mutex::new(HashMap::new());
 }
 
 pub fn root_path() -> PathBuf {
@@ -209,7 +216,9 @@ async fn auth_redirect(req: Request<Body>) -> hyper::Result<Response<Body>> {
     .get("authorization")
     .map(|v| v.to_str().unwrap())
   {
-    if auth.to_lowercase() == format!("bearer {}", TEST_AUTH_TOKEN) {
+    if auth.to_lowercase() == format!("bearer {}", TEST_AUTH_TOKEN)
+      || auth == format!("Basic {}", TEST_BASIC_AUTH)
+    {
       let p = req.uri().path();
       assert_eq!(&p[0..1], "/");
       let url = format!("http://localhost:{}{}", PORT, p);
@@ -218,7 +227,7 @@ async fn auth_redirect(req: Request<Body>) -> hyper::Result<Response<Body>> {
   }
 
   let mut resp = Response::new(Body::empty());
-  *resp.status_mut() = StatusCode::NOT_FOUND;
+  *resp.status_mut() = StatusCode::UNAUTHORIZED;
   Ok(resp)
 }
 
@@ -358,7 +367,27 @@ async fn absolute_redirect(
   return Ok(file_resp);
 }
 
+/// Counts the requests of the main server by host and path, e.g.
+/// "localhost:4545/cli/tests/002_hello.ts".
+fn count_request(req: &Request<Body>) {
+  let host = req
+    .headers()
+    .get("host")
+    .and_then(|host| host.to_str().ok())
+    .unwrap_or_default();
+  let key = format!("{}{}", host, req.uri().path());
+  *REQUEST_COUNTS.lock().unwrap().entry(key).or_insert(0) += 1;
+}
+
 async fn main_server(req: Request<Body>) -> hyper::Result<Response<Body>> {
+  if let Some(key) = req.uri().path().strip_prefix("/request_count/") {
+    // Ex: `/request_count/127.0.0.1:4545/cli/tests/002_hello.ts` returns
+    // how many times `http://127.0.0.1:4545/cli/tests/002_hello.ts` was
+    // requested.
+    let count = REQUEST_COUNTS.lock().unwrap().get(key).cloned();
+    return Ok(Response::new(Body::from(count.unwrap_or(0).to_string())));
+  }
+  count_request(&req);
   return match (req.method(), req.uri().path()) {
     (&hyper::Method::POST, "/echo_server") => {
       let (parts, body) = req.into_parts();
@@ -444,6 +473,22 @@ async fn main_server(req: Request<Body>) -> hyper::Result<Response<Body>> {
       );
       Ok(res)
     }
+    (_, "/no_authorization.js") => {
+      // Auth tokens must not be sent to other hosts than their own, e.g.
+      // after a redirect from `AUTH_REDIRECT_PORT`.
+      if req.headers().contains_key("authorization") {
+        let mut res = Response::new(Body::empty());
+        *res.status_mut() = StatusCode::BAD_REQUEST;
+        return Ok(res);
+      }
+      let mut res =
+        Response::new(Body::from("console.log('no authorization');"));
+      res.headers_mut().insert(
+        "Content-type",
+        HeaderValue::from_static("application/javascript"),
+      );
+      Ok(res)
+    }
     (_, "/bad_redirect") => {
       let mut res = Response::new(Body::empty());
       *res.status_mut() = StatusCode::FOUND;